@@ -0,0 +1,126 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Scheduled backups of the wallet, copied to each configured backup target
+//! so they leave the host without anyone having to take them
+
+use crate::call_queue::{CallPriority, CallQueue};
+use crate::keychain::Keychain;
+use crate::libwallet::api_impl::owner;
+use crate::libwallet::{BackupTarget, NodeClient, WalletBackup, WalletInst, WalletLCProvider};
+use crate::util::secp::key::SecretKey;
+use crate::util::Mutex;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Thread backing up the wallet at a regular interval and storing each
+/// backup on every target. The thread exits when the scheduler is dropped
+pub struct BackupScheduler {
+	// dropping the sender stops the thread
+	_stop: Mutex<mpsc::Sender<()>>,
+	last_backup: Arc<Mutex<Option<WalletBackup>>>,
+}
+
+impl BackupScheduler {
+	/// Back up the wallet every `interval`, the first time after one
+	/// interval. Every `full_every`th backup is full and the others hold the
+	/// changes since the previous one. If a call queue is given, backups wait
+	/// their turn behind interactive calls
+	pub fn start<L, C, K>(
+		wallet_inst: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
+		keychain_mask: Option<SecretKey>,
+		targets: Vec<Arc<dyn BackupTarget>>,
+		interval: Duration,
+		full_every: u32,
+		call_queue: Option<Arc<CallQueue>>,
+	) -> Self
+	where
+		L: WalletLCProvider<'static, C, K> + 'static,
+		C: NodeClient + 'static,
+		K: Keychain + 'static,
+	{
+		let (stop_tx, stop_rx) = mpsc::channel::<()>();
+		let last_backup = Arc::new(Mutex::new(None));
+		let thread_last_backup = last_backup.clone();
+		let _ = thread::Builder::new()
+			.name("wallet_backups".to_string())
+			.spawn(move || {
+				// backups taken since the last full one, none yet
+				let mut since_full = None;
+				loop {
+					match stop_rx.recv_timeout(interval) {
+						Err(RecvTimeoutError::Timeout) => {}
+						_ => break,
+					}
+					let incremental = match since_full {
+						Some(n) => n < full_every.max(1),
+						None => false,
+					};
+					let res = {
+						let _turn = call_queue
+							.as_ref()
+							.map(|q| q.wait_turn(CallPriority::Background));
+						let mut w_lock = wallet_inst.lock();
+						w_lock
+							.lc_provider()
+							.and_then(|lc| lc.wallet_inst())
+							.and_then(|w| {
+								owner::backup(&mut **w, keychain_mask.as_ref(), incremental)
+							})
+					};
+					let backup = match res {
+						Ok(b) => b,
+						Err(e) => {
+							warn!("Scheduled backup failed: {}", e);
+							continue;
+						}
+					};
+					let mut stored = true;
+					for t in targets.iter() {
+						if let Err(e) = t.store(&backup) {
+							warn!("{}", e);
+							stored = false;
+						}
+					}
+					// a target missing one backup can't rebuild from those
+					// following it, so the next one is full
+					since_full = match (stored, incremental, since_full) {
+						(false, _, _) => None,
+						(true, true, Some(n)) => Some(n + 1),
+						(true, _, _) => Some(1),
+					};
+					if stored {
+						debug!(
+							"Stored {} backup {} on {} target(s)",
+							if incremental { "incremental" } else { "full" },
+							backup.id,
+							targets.len()
+						);
+					}
+					*thread_last_backup.lock() = Some(backup);
+				}
+			});
+		BackupScheduler {
+			_stop: Mutex::new(stop_tx),
+			last_backup,
+		}
+	}
+
+	/// The most recent backup taken, whether or not all targets stored it
+	pub fn last_backup(&self) -> Option<WalletBackup> {
+		self.last_backup.lock().clone()
+	}
+}
@@ -0,0 +1,194 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Queue ordering calls on the wallet instance, so interactive calls waiting
+//! for it go ahead of long-running ones rather than being stuck behind them.
+//! Calls already running aren't interrupted
+
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex};
+
+/// Owner API methods that may run for a long time, queued behind
+/// interactive calls
+const LONG_RUNNING_METHODS: &[&str] = &["restore", "check_repair", "remediate"];
+
+/// Owner API methods that don't use the wallet, so don't need to queue. They
+/// must answer while a background operation holds the wallet
+const UNQUEUED_METHODS: &[&str] = &[
+	"start_operation",
+	"operation_status",
+	"cancel_operation",
+	"list_operations",
+];
+
+/// Priority of a call waiting for the wallet
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CallPriority {
+	/// A call a user is waiting on, such as retrieving the balance
+	Interactive,
+	/// A long-running operation, such as scanning the chain
+	Background,
+}
+
+impl CallPriority {
+	/// Priority of a call to the Owner API method with the given name
+	pub fn of_method(method: &str) -> CallPriority {
+		if LONG_RUNNING_METHODS.contains(&method) {
+			CallPriority::Background
+		} else {
+			CallPriority::Interactive
+		}
+	}
+
+	/// Priority of a JSON-RPC request, or of the lowest priority call in a
+	/// batch of requests. `None` if no call in the request needs to queue
+	pub fn of_request(request: &serde_json::Value) -> Option<CallPriority> {
+		let method = |r: &serde_json::Value| {
+			r.get("method")
+				.and_then(|m| m.as_str())
+				.unwrap_or("")
+				.to_owned()
+		};
+		let methods: Vec<String> = match request.as_array() {
+			Some(batch) => batch.iter().map(method).collect(),
+			None => vec![method(request)],
+		};
+		let queued: Vec<CallPriority> = methods
+			.iter()
+			.filter(|m| !UNQUEUED_METHODS.contains(&m.as_str()))
+			.map(|m| CallPriority::of_method(m))
+			.collect();
+		if queued.contains(&CallPriority::Background) {
+			Some(CallPriority::Background)
+		} else {
+			queued.first().cloned()
+		}
+	}
+}
+
+#[derive(Default)]
+struct QueueState {
+	running: bool,
+	next_ticket: u64,
+	interactive: VecDeque<u64>,
+	background: VecDeque<u64>,
+}
+
+/// Queue letting one call at a time use the wallet, interactive calls first
+/// and otherwise in order of arrival
+#[derive(Default)]
+pub struct CallQueue {
+	state: Mutex<QueueState>,
+	turn: Condvar,
+}
+
+/// A call's turn to use the wallet, which passes to the next call when
+/// dropped
+pub struct CallTurn<'a> {
+	queue: &'a CallQueue,
+}
+
+impl CallQueue {
+	/// Create an empty queue
+	pub fn new() -> CallQueue {
+		CallQueue::default()
+	}
+
+	/// Wait until it's the turn of a call with the given priority
+	pub fn wait_turn(&self, priority: CallPriority) -> CallTurn<'_> {
+		let mut state = self.state.lock().unwrap();
+		let ticket = state.next_ticket;
+		state.next_ticket += 1;
+		match priority {
+			CallPriority::Interactive => state.interactive.push_back(ticket),
+			CallPriority::Background => state.background.push_back(ticket),
+		}
+		loop {
+			let next = state
+				.interactive
+				.front()
+				.or_else(|| state.background.front());
+			if !state.running && next == Some(&ticket) {
+				break;
+			}
+			state = self.turn.wait(state).unwrap();
+		}
+		match priority {
+			CallPriority::Interactive => state.interactive.pop_front(),
+			CallPriority::Background => state.background.pop_front(),
+		};
+		state.running = true;
+		CallTurn { queue: self }
+	}
+
+	/// Number of calls waiting, as (interactive, background)
+	pub fn depth(&self) -> (usize, usize) {
+		let state = self.state.lock().unwrap();
+		(state.interactive.len(), state.background.len())
+	}
+}
+
+impl<'a> Drop for CallTurn<'a> {
+	fn drop(&mut self) {
+		if let Ok(mut state) = self.queue.state.lock() {
+			state.running = false;
+		}
+		self.queue.turn.notify_all();
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use std::sync::Arc;
+	use std::thread;
+	use std::time::Duration;
+
+	#[test]
+	fn interactive_calls_go_first() {
+		let queue = Arc::new(CallQueue::new());
+		let order = Arc::new(Mutex::new(vec![]));
+		let turn = queue.wait_turn(CallPriority::Background);
+
+		let mut handles = vec![];
+		for (name, priority) in &[
+			("scan", CallPriority::Background),
+			("balance", CallPriority::Interactive),
+		] {
+			let (q, o) = (queue.clone(), order.clone());
+			handles.push(thread::spawn(move || {
+				let _turn = q.wait_turn(*priority);
+				o.lock().unwrap().push(*name);
+			}));
+			// make sure the calls are queued in this order
+			while queue.depth().0 + queue.depth().1 < handles.len() {
+				thread::sleep(Duration::from_millis(1));
+			}
+		}
+		assert_eq!(queue.depth(), (1, 1));
+		drop(turn);
+		for h in handles {
+			h.join().unwrap();
+		}
+		assert_eq!(*order.lock().unwrap(), vec!["balance", "scan"]);
+		assert_eq!(
+			CallPriority::of_request(&serde_json::json!({"method": "check_repair"})),
+			Some(CallPriority::Background)
+		);
+		assert_eq!(
+			CallPriority::of_request(&serde_json::json!({"method": "cancel_operation"})),
+			None
+		);
+	}
+}
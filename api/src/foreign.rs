@@ -14,15 +14,19 @@
 
 //! Foreign API External Definition
 
+use crate::config::WebhookEventType;
+use crate::impls::{WebhookDispatcher, WebhookPayload};
 use crate::keychain::Keychain;
 use crate::libwallet::api_impl::foreign;
 use crate::libwallet::{
-	BlockFees, CbData, Error, NodeClient, NodeVersionInfo, Slate, VersionInfo, WalletInst,
+	BlockFees, CbData, Error, ErrorKind, NodeClient, NodeVersionInfo, PushNotifier,
+	PushRegistration, ReceiveLimits, Slate, TxLogEntryType, VersionInfo, WalletInst,
 	WalletLCProvider,
 };
 use crate::util::secp::key::SecretKey;
 use crate::util::Mutex;
 use std::sync::Arc;
+use std::thread;
 
 /// ForeignAPI Middleware Check callback
 pub type ForeignCheckMiddleware =
@@ -70,6 +74,14 @@ where
 	middleware: Option<ForeignCheckMiddleware>,
 	/// Stored keychain mask (in case the stored wallet seed is tokenized)
 	keychain_mask: Option<SecretKey>,
+	/// Webhooks to notify of received transactions
+	webhooks: Option<WebhookDispatcher>,
+	/// Notifier waking registered devices when a slate is received
+	push_notifier: Option<Arc<dyn PushNotifier>>,
+	/// Amount above which received slates are queued for review
+	review_threshold: Option<u64>,
+	/// Limits on the slates received, if any
+	receive_limits: Option<ReceiveLimits>,
 }
 
 impl<'a, L, C, K> Foreign<'a, L, C, K>
@@ -168,9 +180,57 @@ where
 			doctest_mode: false,
 			middleware,
 			keychain_mask,
+			webhooks: None,
+			push_notifier: None,
+			review_threshold: None,
+			receive_limits: None,
 		}
 	}
 
+	/// Set the webhooks to be notified when a slate is received via this API.
+	///
+	/// # Arguments
+	/// * `webhooks` - A [`WebhookDispatcher`](../grin_wallet_impls/struct.WebhookDispatcher.html)
+	/// holding the configured webhooks, or `None` to disable notifications.
+	pub fn set_webhooks(&mut self, webhooks: Option<WebhookDispatcher>) {
+		self.webhooks = webhooks;
+	}
+
+	/// Set the notifier used to wake devices registered via
+	/// [`register_push_token`](struct.Owner.html#method.register_push_token)
+	/// when a slate is received via this API.
+	///
+	/// # Arguments
+	/// * `push_notifier` - A [`PushNotifier`](../grin_wallet_libwallet/types/trait.PushNotifier.html)
+	/// implementation, or `None` to disable push notifications.
+	pub fn set_push_notifier(&mut self, push_notifier: Option<Arc<dyn PushNotifier>>) {
+		self.push_notifier = push_notifier;
+	}
+
+	/// Set the amount above which slates received via
+	/// [`receive_tx`](struct.Foreign.html#method.receive_tx) aren't signed, but queued for the
+	/// wallet's owner to approve or reject via the Owner API's
+	/// [`approve_receive`](struct.Owner.html#method.approve_receive) and
+	/// [`reject_receive`](struct.Owner.html#method.reject_receive).
+	///
+	/// # Arguments
+	/// * `review_threshold` - The amount, in nanogrins, or `None` to sign all slates.
+	pub fn set_review_threshold(&mut self, review_threshold: Option<u64>) {
+		self.review_threshold = review_threshold;
+	}
+
+	/// Set limits on the slates received via [`receive_tx`](struct.Foreign.html#method.receive_tx),
+	/// refused with a `ReceiveLimit` error before they're processed or queued for review, and
+	/// whether invoices are finalized via
+	/// [`finalize_invoice_tx`](struct.Foreign.html#method.finalize_invoice_tx).
+	///
+	/// # Arguments
+	/// * `receive_limits` - The [`ReceiveLimits`](../grin_wallet_libwallet/struct.ReceiveLimits.html),
+	/// or `None` to receive any slate.
+	pub fn set_receive_limits(&mut self, receive_limits: Option<ReceiveLimits>) {
+		self.receive_limits = receive_limits;
+	}
+
 	/// Return the version capabilities of the running ForeignApi Node
 	/// # Arguments
 	/// None
@@ -356,6 +416,14 @@ where
 	/// # Remarks
 	///
 	/// * This method will store a partially completed transaction in the wallet's transaction log.
+	/// * If [receive limits](struct.Foreign.html#method.set_receive_limits) are set, slates outside
+	/// them are refused with a `ReceiveLimit` error.
+	/// * If a [review threshold](struct.Foreign.html#method.set_review_threshold) is set and the
+	/// slate's amount is above it, the slate is queued for review rather than signed, and a
+	/// `TransactionAwaitingReview` error is returned.
+	/// * The wallet is only locked while checking the transaction hasn't been received already and
+	/// while saving the output, so the node queries and the building of the output's range proof
+	/// for several transactions received at once can be done in parallel.
 	///
 	/// # Example
 	/// Set up as in [new](struct.Foreign.html#method.new) method above.
@@ -381,23 +449,95 @@ where
 		dest_acct_name: Option<&str>,
 		message: Option<String>,
 	) -> Result<Slate, Error> {
-		let mut w_lock = self.wallet_inst.lock();
-		let w = w_lock.lc_provider()?.wallet_inst()?;
+		let mut client = {
+			let mut w_lock = self.wallet_inst.lock();
+			let w = w_lock.lc_provider()?.wallet_inst()?;
+			w.w2n_client().clone()
+		};
 		if let Some(m) = self.middleware.as_ref() {
 			m(
 				ForeignCheckMiddlewareFn::ReceiveTx,
-				w.w2n_client().get_version_info(),
+				client.get_version_info(),
 				Some(slate),
 			)?;
 		}
-		foreign::receive_tx(
-			&mut **w,
-			(&self.keychain_mask).as_ref(),
-			slate,
-			dest_acct_name,
-			message,
-			self.doctest_mode,
-		)
+		foreign::check_ttl(&client, slate)?;
+		let mask = (&self.keychain_mask).as_ref();
+		// the slate is counted against the limits while the wallet is held to
+		// queue it or reserve its output, so concurrent receives can't both
+		// take the last one of the hour
+		if self
+			.review_threshold
+			.map(|t| slate.amount > t)
+			.unwrap_or(false)
+		{
+			let mut w_lock = self.wallet_inst.lock();
+			let w = w_lock.lc_provider()?.wallet_inst()?;
+			let count = match self.receive_limits.as_ref() {
+				Some(l) => Some(foreign::check_receive_limits(&**w, l, slate)?),
+				None => None,
+			};
+			foreign::queue_receive(
+				&mut **w,
+				mask,
+				slate,
+				dest_acct_name,
+				message,
+				self.doctest_mode,
+			)?;
+			if let Some(c) = count {
+				foreign::save_receive_count(&mut **w, mask, &c)?;
+			}
+			return Err(ErrorKind::TransactionAwaitingReview(slate.id.to_string()).into());
+		}
+		let (reservation, keychain) = {
+			let mut w_lock = self.wallet_inst.lock();
+			let w = w_lock.lc_provider()?.wallet_inst()?;
+			let count = match self.receive_limits.as_ref() {
+				Some(l) => Some(foreign::check_receive_limits(&**w, l, slate)?),
+				None => None,
+			};
+			let reservation =
+				foreign::reserve_receive(&mut **w, mask, slate, dest_acct_name, self.doctest_mode)?;
+			if let Some(c) = count {
+				foreign::save_receive_count(&mut **w, mask, &c)?;
+			}
+			(reservation, w.keychain(mask)?)
+		};
+		let res =
+			foreign::build_receive(&keychain, slate, &reservation, message, self.doctest_mode)?;
+
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		foreign::commit_receive(&mut **w, mask, &res, &reservation, self.doctest_mode)?;
+		if let Some(h) = self.webhooks.as_ref() {
+			if h.wants(WebhookEventType::TxReceived) {
+				let tx = w.tx_log_iter()?.find(|t| {
+					t.tx_slate_id == Some(slate.id) && t.tx_type == TxLogEntryType::TxReceived
+				});
+				h.dispatch(
+					WebhookPayload::new(WebhookEventType::TxReceived, Some(slate.id), tx)
+						.with_account(w.acct_path_iter()?),
+				);
+			}
+		}
+		if let Some(n) = self.push_notifier.as_ref() {
+			let registrations: Vec<PushRegistration> = w.push_registration_iter()?.collect();
+			if !registrations.is_empty() {
+				let notifier = n.clone();
+				let slate_id = slate.id;
+				let _ = thread::Builder::new()
+					.name("push_notify".to_string())
+					.spawn(move || {
+						for r in registrations.iter() {
+							if let Err(e) = notifier.notify_slate_received(r, &slate_id) {
+								warn!("Failed to notify {} device: {}", r.platform, e);
+							}
+						}
+					});
+			}
+		}
+		Ok(res)
 	}
 
 	/// Finalizes an invoice transaction initiated by this wallet's Owner api.
@@ -457,6 +597,9 @@ where
 				Some(slate),
 			)?;
 		}
+		if let Some(l) = self.receive_limits.as_ref() {
+			l.check_invoice()?;
+		}
 		foreign::finalize_invoice_tx(&mut **w, (&self.keychain_mask).as_ref(), slate)
 	}
 }
@@ -192,11 +192,17 @@ pub trait ForeignRpc {
 	# ,false, 1 ,false, false);
 	```
 	*/
-	fn verify_slate_messages(&self, slate: &Slate) -> Result<(), ErrorKind>;
+	fn verify_slate_messages(&self, slate: serde_json::Value) -> Result<(), ErrorKind>;
 
 	/**
 		Networked version of [Foreign::receive_tx](struct.Foreign.html#method.receive_tx).
 
+	The slate is checked against the schema of the version it declares before it's
+	deserialized. A slate which doesn't match is rejected with a `SlateValidation` error
+	giving the slate version and the path of the missing or invalid field, e.g.
+	`{"Err": {"SlateValidation": {"version": 2, "field": "tx.body.kernels[0].excess",
+	"reason": "is missing"}}}`.
+
 	# Json rpc example
 
 	```
@@ -347,7 +353,7 @@ pub trait ForeignRpc {
 	*/
 	fn receive_tx(
 		&self,
-		slate: VersionedSlate,
+		slate: serde_json::Value,
 		dest_acct_name: Option<String>,
 		message: Option<String>,
 	) -> Result<VersionedSlate, ErrorKind>;
@@ -356,6 +362,9 @@ pub trait ForeignRpc {
 
 	Networked version of [Foreign::finalize_invoice_tx](struct.Foreign.html#method.finalize_invoice_tx).
 
+	As with [receive_tx](trait.ForeignRpc.html#tymethod.receive_tx), an invalid slate
+	is rejected with a `SlateValidation` error naming the field at fault.
+
 	# Json rpc example
 
 	```
@@ -513,7 +522,7 @@ pub trait ForeignRpc {
 	# ,false, 5, false, true);
 	```
 	*/
-	fn finalize_invoice_tx(&self, slate: &Slate) -> Result<Slate, ErrorKind>;
+	fn finalize_invoice_tx(&self, slate: serde_json::Value) -> Result<Slate, ErrorKind>;
 }
 
 impl<'a, L, C, K> ForeignRpc for Foreign<'a, L, C, K>
@@ -530,16 +539,20 @@ where
 		Foreign::build_coinbase(self, block_fees).map_err(|e| e.kind())
 	}
 
-	fn verify_slate_messages(&self, slate: &Slate) -> Result<(), ErrorKind> {
-		Foreign::verify_slate_messages(self, slate).map_err(|e| e.kind())
+	fn verify_slate_messages(&self, slate: serde_json::Value) -> Result<(), ErrorKind> {
+		let slate: Slate = VersionedSlate::from_json(slate)
+			.map_err(|e| e.kind())?
+			.into();
+		Foreign::verify_slate_messages(self, &slate).map_err(|e| e.kind())
 	}
 
 	fn receive_tx(
 		&self,
-		slate: VersionedSlate,
+		slate: serde_json::Value,
 		dest_acct_name: Option<String>,
 		message: Option<String>,
 	) -> Result<VersionedSlate, ErrorKind> {
+		let slate = VersionedSlate::from_json(slate).map_err(|e| e.kind())?;
 		let version = slate.version();
 		let slate: Slate = slate.into();
 		let slate = Foreign::receive_tx(
@@ -553,8 +566,11 @@ where
 		Ok(VersionedSlate::into_version(slate, version))
 	}
 
-	fn finalize_invoice_tx(&self, slate: &Slate) -> Result<Slate, ErrorKind> {
-		Foreign::finalize_invoice_tx(self, slate).map_err(|e| e.kind())
+	fn finalize_invoice_tx(&self, slate: serde_json::Value) -> Result<Slate, ErrorKind> {
+		let slate: Slate = VersionedSlate::from_json(slate)
+			.map_err(|e| e.kind())?
+			.into();
+		Foreign::finalize_invoice_tx(self, &slate).map_err(|e| e.kind())
 	}
 }
 
@@ -783,7 +799,7 @@ macro_rules! doctest_helper_json_rpc_foreign_assert_response {
 			$blocks_to_mine,
 			$init_tx,
 			$init_invoice_tx,
-			)
+		)
 		.unwrap()
 		.unwrap();
 
@@ -792,7 +808,7 @@ macro_rules! doctest_helper_json_rpc_foreign_assert_response {
 				"(left != right) \nleft: {}\nright: {}",
 				serde_json::to_string_pretty(&response).unwrap(),
 				serde_json::to_string_pretty(&expected_response).unwrap()
-				);
-			}
+			);
+		}
 	};
 }
@@ -25,6 +25,7 @@
 use grin_wallet_util::grin_core as core;
 use grin_wallet_util::grin_keychain as keychain;
 use grin_wallet_util::grin_util as util;
+extern crate grin_wallet_config as config;
 extern crate grin_wallet_impls as impls;
 extern crate grin_wallet_libwallet as libwallet;
 
@@ -36,18 +37,32 @@ extern crate serde_json;
 #[macro_use]
 extern crate log;
 
+mod backup_scheduler;
+mod call_queue;
 mod foreign;
 mod foreign_rpc;
 
+mod node_monitor;
+mod operations;
 mod owner;
 mod owner_rpc;
 mod owner_rpc_s;
+mod recovery;
+mod shutdown;
+mod updater;
 
+pub use crate::backup_scheduler::BackupScheduler;
+pub use crate::call_queue::{CallPriority, CallQueue, CallTurn};
 pub use crate::foreign::{Foreign, ForeignCheckMiddleware, ForeignCheckMiddlewareFn};
 pub use crate::foreign_rpc::ForeignRpc;
+pub use crate::node_monitor::NodeMonitor;
+pub use crate::operations::{OperationEvent, OperationRunner};
 pub use crate::owner::Owner;
 pub use crate::owner_rpc::OwnerRpc;
 pub use crate::owner_rpc_s::OwnerRpcS;
+pub use crate::recovery::{build_recovery_tx, RecoveryPlan, RecoveryPlanner};
+pub use crate::shutdown::ShutdownHandle;
+pub use crate::updater::WalletUpdater;
 
 pub use crate::foreign_rpc::foreign_rpc as foreign_rpc_client;
 pub use crate::foreign_rpc::run_doctest_foreign;
@@ -0,0 +1,113 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Background monitoring of the wallet's connectivity to its node
+
+use crate::libwallet::{NodeClient, NodeConnectivity};
+use crate::util::{Mutex, RwLock};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Check the node's connectivity, given the result of the previous check.
+/// If the node has just become reachable again, the client is asked to
+/// reconnect, dropping anything cached from before the outage
+pub fn check_node<C>(client: &mut C, previous: &NodeConnectivity) -> NodeConnectivity
+where
+	C: NodeClient,
+{
+	match client.get_chain_height() {
+		Ok(height) => {
+			if previous.last_error.is_some() {
+				warn!("Node {} is reachable again", client.node_url());
+				client.reconnect();
+			}
+			let version = client.get_version_info();
+			NodeConnectivity {
+				node_url: client.node_url().to_owned(),
+				reachable: true,
+				chain_height: Some(height),
+				node_version: version.as_ref().map(|v| v.node_version.clone()),
+				block_header_version: version.as_ref().map(|v| v.block_header_version),
+				last_error: None,
+			}
+		}
+		Err(e) => {
+			if previous.reachable {
+				warn!("Node {} is unreachable: {}", client.node_url(), e);
+			}
+			NodeConnectivity {
+				node_url: client.node_url().to_owned(),
+				reachable: false,
+				chain_height: None,
+				node_version: previous.node_version.clone(),
+				block_header_version: previous.block_header_version,
+				last_error: Some(format!("{}", e)),
+			}
+		}
+	}
+}
+
+/// Checks the node's connectivity at a regular interval on a background
+/// thread, which stops when the monitor is dropped
+pub struct NodeMonitor {
+	status: Arc<RwLock<NodeConnectivity>>,
+	// dropping the sender wakes and stops the monitor thread
+	_stop: Mutex<mpsc::Sender<()>>,
+}
+
+impl NodeMonitor {
+	/// Start monitoring the node used by the client, checking it straight
+	/// away and then every `interval`. If given, `on_check` is called with
+	/// the result of each check
+	pub fn start<C>(
+		client: C,
+		interval: Duration,
+		on_check: Option<Box<dyn Fn(&NodeConnectivity) + Send>>,
+	) -> NodeMonitor
+	where
+		C: NodeClient + 'static,
+	{
+		let status = Arc::new(RwLock::new(NodeConnectivity::default()));
+		let (stop_tx, stop_rx) = mpsc::channel::<()>();
+		let thread_status = status.clone();
+		let _ = thread::Builder::new()
+			.name("node_monitor".to_string())
+			.spawn(move || {
+				let mut client = client;
+				loop {
+					let previous = thread_status.read().clone();
+					let current = check_node(&mut client, &previous);
+					if let Some(f) = on_check.as_ref() {
+						f(&current);
+					}
+					*thread_status.write() = current;
+					match stop_rx.recv_timeout(interval) {
+						Err(RecvTimeoutError::Timeout) => continue,
+						_ => break,
+					}
+				}
+			});
+		NodeMonitor {
+			status,
+			_stop: Mutex::new(stop_tx),
+		}
+	}
+
+	/// The result of the most recent check
+	pub fn status(&self) -> NodeConnectivity {
+		self.status.read().clone()
+	}
+}
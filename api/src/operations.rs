@@ -0,0 +1,414 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Background running of long operations such as restore, so callers get an
+//! id to follow or cancel the operation with instead of waiting on it.
+//! Operations are recorded in the wallet database as they progress, so those
+//! interrupted by a restart are resumed and finished ones can be listed
+
+use crate::call_queue::{CallPriority, CallQueue};
+use crate::keychain::Keychain;
+use crate::libwallet::api_impl::owner;
+use crate::libwallet::{
+	CancelToken, Error, LongOperation, NodeClient, OperationState, OperationStatus, ScanProgress,
+	WalletInst, WalletLCProvider,
+};
+use crate::util::secp::key::SecretKey;
+use crate::util::Mutex;
+use chrono::Utc;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::thread;
+
+/// Number of finished operations kept for their status to be retrieved
+const MAX_FINISHED: usize = 100;
+
+/// What the runner needs of the wallet, whatever its types
+trait OperationWallet: Send + Sync {
+	/// Run the operation to completion
+	fn run(
+		&self,
+		status: &OperationStatus,
+		keychain_mask: Option<&SecretKey>,
+		cancel: &CancelToken,
+	) -> Result<(), Error>;
+	/// Recorded operations
+	fn load(&self) -> Result<Vec<OperationStatus>, Error>;
+	/// Record the operation's status, forgetting the given operations
+	fn save(
+		&self,
+		status: &OperationStatus,
+		forget: &[u32],
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<(), Error>;
+}
+
+struct WalletOperations<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	wallet_inst: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
+}
+
+impl<L, C, K> OperationWallet for WalletOperations<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	fn run(
+		&self,
+		status: &OperationStatus,
+		keychain_mask: Option<&SecretKey>,
+		cancel: &CancelToken,
+	) -> Result<(), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		match status.operation {
+			// a restore only starts on an empty wallet, so a resumed one, which
+			// may have been interrupted part way through, is finished by a full
+			// check instead, restoring whatever is missing
+			LongOperation::Restore if status.resumed > 0 => {
+				owner::check_repair(&mut **w, keychain_mask, false, true, Some(cancel))
+			}
+			LongOperation::Restore => owner::restore(&mut **w, keychain_mask, Some(cancel)),
+			LongOperation::CheckRepair {
+				delete_unconfirmed,
+				full,
+			} => owner::check_repair(
+				&mut **w,
+				keychain_mask,
+				delete_unconfirmed,
+				full,
+				Some(cancel),
+			),
+		}
+	}
+
+	fn load(&self) -> Result<Vec<OperationStatus>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		Ok(w.operation_iter()?.collect())
+	}
+
+	fn save(
+		&self,
+		status: &OperationStatus,
+		forget: &[u32],
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<(), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		let mut batch = w.batch(keychain_mask)?;
+		batch.save_operation(status)?;
+		for id in forget {
+			batch.delete_operation(*id)?;
+		}
+		batch.commit()?;
+		Ok(())
+	}
+}
+
+/// Something that happened to an operation being followed with
+/// [`follow`](struct.OperationRunner.html#method.follow)
+#[derive(Debug, Clone)]
+pub enum OperationEvent {
+	/// The operation's state changed, or it was first followed
+	Status(OperationStatus),
+	/// The operation scanned further through the UTXO set
+	Progress(ScanProgress),
+}
+
+/// Called with each event of a followed operation, returning whether to keep
+/// following it
+type EventCallback = Arc<dyn Fn(OperationEvent) -> bool + Send + Sync>;
+
+struct Operation {
+	status: OperationStatus,
+	token: CancelToken,
+	// only callers with the mask the operation was started with can see it
+	keychain_mask: Option<SecretKey>,
+	followers: Vec<EventCallback>,
+}
+
+impl Operation {
+	fn status(&self) -> OperationStatus {
+		let ScanProgress {
+			scanned,
+			scan_total,
+			..
+		} = self.token.progress();
+		let percent_complete = match self.status.state {
+			OperationState::Completed => 100,
+			_ if scan_total == 0 => 0,
+			_ => (scanned * 100 / scan_total).min(99) as u8,
+		};
+		OperationStatus {
+			scanned,
+			scan_total,
+			percent_complete,
+			..self.status.clone()
+		}
+	}
+}
+
+/// Runs long operations on a wallet, each on its own thread
+pub struct OperationRunner {
+	wallet: Arc<dyn OperationWallet>,
+	call_queue: Option<Arc<CallQueue>>,
+	operations: Arc<Mutex<BTreeMap<u32, Operation>>>,
+}
+
+impl OperationRunner {
+	/// Create a runner for the given wallet. If a call queue is given,
+	/// operations wait their turn behind interactive calls
+	pub fn new<L, C, K>(
+		wallet_inst: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
+		call_queue: Option<Arc<CallQueue>>,
+	) -> Self
+	where
+		L: WalletLCProvider<'static, C, K> + 'static,
+		C: NodeClient + 'static,
+		K: Keychain + 'static,
+	{
+		OperationRunner {
+			wallet: Arc::new(WalletOperations { wallet_inst }),
+			call_queue,
+			operations: Arc::new(Mutex::new(BTreeMap::new())),
+		}
+	}
+
+	/// Load the operations recorded in the wallet database, restarting those
+	/// that hadn't finished. Loaded operations are visible to callers with the
+	/// given keychain mask. Returns the number of operations restarted
+	pub fn resume(&self, keychain_mask: Option<SecretKey>) -> Result<usize, Error> {
+		let mut resumed = 0;
+		for mut status in self.wallet.load()? {
+			if status.state.is_finished() {
+				self.operations.lock().insert(
+					status.id,
+					Operation {
+						token: CancelToken::new(),
+						status,
+						keychain_mask: keychain_mask.clone(),
+						followers: vec![],
+					},
+				);
+				continue;
+			}
+			warn!(
+				"Resuming operation {} ({:?}) interrupted by a restart",
+				status.id, status.operation
+			);
+			status.state = OperationState::Queued;
+			status.resumed += 1;
+			let id = status.id;
+			self.operations.lock().insert(
+				id,
+				Operation {
+					token: CancelToken::new(),
+					status,
+					keychain_mask: keychain_mask.clone(),
+					followers: vec![],
+				},
+			);
+			self.spawn(id);
+			resumed += 1;
+		}
+		Ok(resumed)
+	}
+
+	/// Start the given operation, returning its id
+	pub fn start(&self, operation: LongOperation, keychain_mask: Option<SecretKey>) -> u32 {
+		let id = {
+			let mut ops = self.operations.lock();
+			let id = ops.keys().next_back().map_or(1, |i| i + 1);
+			ops.insert(
+				id,
+				Operation {
+					status: OperationStatus {
+						id,
+						operation,
+						state: OperationState::Queued,
+						scanned: 0,
+						scan_total: 0,
+						percent_complete: 0,
+						created: Utc::now(),
+						finished: None,
+						resumed: 0,
+					},
+					token: CancelToken::new(),
+					keychain_mask,
+					followers: vec![],
+				},
+			);
+			id
+		};
+		self.spawn(id);
+		id
+	}
+
+	/// Run the known operation with the given id on its own thread
+	fn spawn(&self, id: u32) {
+		let (status, token, keychain_mask) = {
+			let ops = self.operations.lock();
+			let op = &ops[&id];
+			(
+				op.status.clone(),
+				op.token.clone(),
+				op.keychain_mask.clone(),
+			)
+		};
+		let wallet = self.wallet.clone();
+		let call_queue = self.call_queue.clone();
+		let operations = self.operations.clone();
+		let set_state = move |state: OperationState| {
+			let (status, forget, mask) = {
+				let mut ops = operations.lock();
+				let (status, mask) = match ops.get_mut(&id) {
+					Some(op) => {
+						if state.is_finished() {
+							op.status.finished = Some(Utc::now());
+						}
+						op.status.state = state;
+						let status = op.status();
+						op.followers
+							.retain(|f| f(OperationEvent::Status(status.clone())));
+						(status, op.keychain_mask.clone())
+					}
+					None => return,
+				};
+				// forget the oldest finished operations
+				let finished: Vec<u32> = ops
+					.iter()
+					.filter(|(_, op)| op.status.state.is_finished())
+					.map(|(id, _)| *id)
+					.collect();
+				let forget: Vec<u32> = finished
+					.iter()
+					.take(finished.len().saturating_sub(MAX_FINISHED))
+					.cloned()
+					.collect();
+				for id in forget.iter() {
+					ops.remove(id);
+				}
+				(status, forget, mask)
+			};
+			// recorded outside the lock on operations, as the wallet may be
+			// busy for a while
+			if let Err(e) = wallet.save(&status, &forget, mask.as_ref()) {
+				warn!("Unable to record operation {}: {}", id, e);
+			}
+		};
+		let res = thread::Builder::new()
+			.name(format!("wallet_operation_{}", id))
+			.spawn({
+				let set_state = set_state.clone();
+				let wallet = self.wallet.clone();
+				move || {
+					// recorded before waiting, so it's resumed if the wallet
+					// restarts in the meantime
+					set_state(OperationState::Queued);
+					let _turn = call_queue
+						.as_ref()
+						.map(|q| q.wait_turn(CallPriority::Background));
+					if token.is_cancelled() {
+						set_state(OperationState::Cancelled);
+						return;
+					}
+					set_state(OperationState::Running);
+					let state = match wallet.run(&status, keychain_mask.as_ref(), &token) {
+						Ok(()) => OperationState::Completed,
+						Err(_) if token.is_cancelled() => OperationState::Cancelled,
+						Err(e) => {
+							warn!("Operation {} ({:?}) failed: {}", id, status.operation, e);
+							OperationState::Failed(e.to_string())
+						}
+					};
+					set_state(state);
+				}
+			});
+		if let Err(e) = res {
+			set_state(OperationState::Failed(format!("Starting operation: {}", e)));
+		}
+	}
+
+	/// Status of the operation with the given id, if known and started with
+	/// the given keychain mask
+	pub fn status(&self, id: u32, keychain_mask: Option<&SecretKey>) -> Option<OperationStatus> {
+		let ops = self.operations.lock();
+		ops.get(&id)
+			.filter(|op| op.keychain_mask.as_ref() == keychain_mask)
+			.map(|op| op.status())
+	}
+
+	/// Statuses of all known operations started with the given keychain
+	/// mask, running and finished, in order of id
+	pub fn list(&self, keychain_mask: Option<&SecretKey>) -> Vec<OperationStatus> {
+		self.operations
+			.lock()
+			.values()
+			.filter(|op| op.keychain_mask.as_ref() == keychain_mask)
+			.map(|op| op.status())
+			.collect()
+	}
+
+	/// Follow the operation with the given id, if known and started with the
+	/// given keychain mask, calling `f` with its status straight away, and
+	/// then with each change of its state and each step of its scan until it
+	/// finishes or `f` returns false. Returns whether the operation is known
+	pub fn follow<F>(&self, id: u32, keychain_mask: Option<&SecretKey>, f: F) -> bool
+	where
+		F: Fn(OperationEvent) -> bool + Send + Sync + 'static,
+	{
+		let mut ops = self.operations.lock();
+		let op = match ops.get_mut(&id) {
+			Some(op) if op.keychain_mask.as_ref() == keychain_mask => op,
+			_ => return false,
+		};
+		let status = op.status();
+		if !f(OperationEvent::Status(status.clone())) || status.state.is_finished() {
+			return true;
+		}
+		let f: EventCallback = Arc::new(f);
+		op.followers.push(f.clone());
+		op.token
+			.on_progress(move |p| f(OperationEvent::Progress(p.clone())));
+		true
+	}
+
+	/// Ask the operation with the given id to stop. Returns whether the
+	/// operation is known and was started with the given keychain mask
+	pub fn cancel(&self, id: u32, keychain_mask: Option<&SecretKey>) -> bool {
+		match self.operations.lock().get(&id) {
+			Some(op) if op.keychain_mask.as_ref() == keychain_mask => {
+				op.token.cancel();
+				true
+			}
+			_ => false,
+		}
+	}
+
+	/// Ask every queued or running operation to stop, whatever keychain mask
+	/// it was started with
+	pub fn cancel_all(&self) {
+		for op in self.operations.lock().values() {
+			if !op.status.state.is_finished() {
+				op.token.cancel();
+			}
+		}
+	}
+}
@@ -17,18 +17,38 @@
 use chrono::prelude::*;
 use uuid::Uuid;
 
-use crate::core::core::Transaction;
-use crate::impls::create_sender;
+use crate::config::WebhookEventType;
+use crate::core::core::{transaction, Transaction};
+use crate::impls::{
+	create_sender, transport_for, EmailNotifier, SlateSender, WebhookDeadLetter, WebhookDispatcher,
+	WebhookPayload,
+};
+use crate::impls::{AuditLog, AuditLogExport};
 use crate::keychain::{Identifier, Keychain};
 use crate::libwallet::api_impl::owner;
+use crate::libwallet::ReservedKeys;
+use crate::libwallet::{
+	build_info, set_privacy_mode, statistics, BuildInfo, OutputDerivation, WalletStatistics,
+};
 use crate::libwallet::{
-	AcctPathMapping, Error, ErrorKind, InitTxArgs, IssueInvoiceTxArgs, NodeClient,
-	NodeHeightResult, OutputCommitMapping, Slate, TxLogEntry, WalletInfo, WalletInst,
-	WalletLCProvider,
+	AcctPathMapping, ChainAuditReport, ConsolidateArgs, Error, ErrorKind, FeatureFlags, InitTxArgs,
+	InitTxRecipient, IssueInvoiceTxArgs, LongOperation, NodeClient, NodeConnectivity,
+	NodeHeightResult, NodeStatus, OperationStatus, OutputCommitMapping, PendingReceive,
+	PriceOracle, PushRegistration, RemediationAction, RetrieveOutputsQueryArgs,
+	RetrieveTxQueryArgs, SettlementReport, SignedTx, Slate, StatusMessage, TotpEnrollment,
+	TxAnomaly, TxBatchResult, TxConflict, TxExportEntry, TxExportFormat, TxLogEntry,
+	TxLogEntryType, TxReplayReport, TxValidationResult, UnsignedTx, WalletBackend, WalletBackup,
+	WalletInfo, WalletInst, WalletLCProvider, WalletRecords,
 };
-use crate::util::secp::key::SecretKey;
-use crate::util::Mutex;
+use crate::node_monitor::check_node;
+use crate::util::secp::key::{PublicKey, SecretKey};
+use crate::util::{Mutex, ZeroingString};
+use crate::{NodeMonitor, OperationRunner, ShutdownHandle, WalletUpdater};
+use std::collections::{HashMap, HashSet};
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Main interface into all wallet API functions.
 /// Wallet APIs are split into two seperate blocks of functionality
@@ -54,6 +74,86 @@ where
 	pub wallet_inst: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
 	/// Flag to normalize some output during testing. Can mostly be ignored.
 	pub doctest_mode: bool,
+	/// Webhooks to notify of transaction lifecycle events
+	webhooks: Option<WebhookDispatcher>,
+	/// Emails to send on significant events
+	email: Option<EmailNotifier>,
+	/// Handle used to stop the listener serving this API, if any
+	shutdown: Option<ShutdownHandle>,
+	/// Background monitor of the node's connectivity, if running
+	node_monitor: Option<Arc<NodeMonitor>>,
+	/// Background updater refreshing the wallet, if available
+	updater: Option<Arc<WalletUpdater>>,
+	/// Runner of background operations, if available
+	operations: Option<Arc<OperationRunner>>,
+	/// Key transaction memos are sealed to in exports, if any
+	memo_escrow_key: Option<PublicKey>,
+	/// Log of the calls made through the listener serving this API, if kept
+	audit_log: Option<Arc<AuditLog>>,
+	/// TOTP code given for spends, if codes are asked for
+	totp: Option<Option<String>>,
+	/// Whether the TOTP code has been accepted, so spends made within a call, such as
+	/// the finalize and post of a send, don't use it again
+	totp_accepted: AtomicBool,
+	/// Account calls act on in place of the wallet's active one, if scoped to one
+	account: Option<String>,
+}
+
+/// Lock on the wallet, with its active account switched to the one the calls
+/// are scoped to, if any, and switched back when the lock is released
+struct WalletLock<'g, 'a, L, C, K>
+where
+	L: WalletLCProvider<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	guard: Box<dyn DerefMut<Target = Box<dyn WalletInst<'a, L, C, K>>> + 'g>,
+	previous: Option<Identifier>,
+}
+
+impl<'g, 'a, L, C, K> Deref for WalletLock<'g, 'a, L, C, K>
+where
+	L: WalletLCProvider<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	type Target = Box<dyn WalletInst<'a, L, C, K>>;
+
+	fn deref(&self) -> &Self::Target {
+		&**self.guard
+	}
+}
+
+impl<'g, 'a, L, C, K> DerefMut for WalletLock<'g, 'a, L, C, K>
+where
+	L: WalletLCProvider<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut **self.guard
+	}
+}
+
+impl<'g, 'a, L, C, K> Drop for WalletLock<'g, 'a, L, C, K>
+where
+	L: WalletLCProvider<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	fn drop(&mut self) {
+		let previous = match self.previous.take() {
+			Some(p) => p,
+			None => return,
+		};
+		// put back whichever account was active before, including after a
+		// set_active_account call made within the scope
+		if let Ok(lc) = self.guard.lc_provider() {
+			if let Ok(w) = lc.wallet_inst() {
+				w.set_parent_key_id(previous);
+			}
+		}
+	}
 }
 
 impl<'a, L, C, K> Owner<'a, L, C, K>
@@ -141,6 +241,236 @@ where
 		Owner {
 			wallet_inst,
 			doctest_mode: false,
+			webhooks: None,
+			email: None,
+			shutdown: None,
+			node_monitor: None,
+			updater: None,
+			operations: None,
+			memo_escrow_key: None,
+			audit_log: None,
+			totp: None,
+			totp_accepted: AtomicBool::new(false),
+			account: None,
+		}
+	}
+
+	/// Set the webhooks to be notified when transactions are confirmed (as
+	/// discovered when refreshing from the node) or cancelled via this API.
+	///
+	/// # Arguments
+	/// * `webhooks` - A [`WebhookDispatcher`](../grin_wallet_impls/struct.WebhookDispatcher.html)
+	/// holding the configured webhooks, or `None` to disable notifications.
+	pub fn set_webhooks(&mut self, webhooks: Option<WebhookDispatcher>) {
+		self.webhooks = webhooks;
+	}
+
+	/// Set the notifier emailing when more than its threshold is sent, once
+	/// the outputs of a send are locked via this API.
+	///
+	/// # Arguments
+	/// * `email` - An [`EmailNotifier`](../grin_wallet_impls/struct.EmailNotifier.html)
+	/// sending the configured emails, or `None` to disable them.
+	pub fn set_email_notifier(&mut self, email: Option<EmailNotifier>) {
+		self.email = email;
+	}
+
+	/// Set the handle used by [`stop_listener`](struct.Owner.html#method.stop_listener)
+	/// to stop the listener serving this API.
+	///
+	/// # Arguments
+	/// * `shutdown` - The [`ShutdownHandle`](struct.ShutdownHandle.html) the listener
+	/// is waiting on, or `None` if the API isn't being served by a listener.
+	pub fn set_shutdown_handle(&mut self, shutdown: Option<ShutdownHandle>) {
+		self.shutdown = shutdown;
+	}
+
+	/// Set the monitor [`get_node_status`](struct.Owner.html#method.get_node_status)
+	/// reports the result of, rather than checking the node itself.
+	///
+	/// # Arguments
+	/// * `node_monitor` - A running [`NodeMonitor`](struct.NodeMonitor.html), or `None`
+	/// to check the node on each call.
+	pub fn set_node_monitor(&mut self, node_monitor: Option<Arc<NodeMonitor>>) {
+		self.node_monitor = node_monitor;
+	}
+
+	/// Set the updater controlled by [`start_updater`](struct.Owner.html#method.start_updater)
+	/// and [`stop_updater`](struct.Owner.html#method.stop_updater). The owner listener shares
+	/// one updater between all the API instances it creates.
+	///
+	/// # Arguments
+	/// * `updater` - A [`WalletUpdater`](struct.WalletUpdater.html) for this API's wallet,
+	/// or `None` if background updates aren't available.
+	pub fn set_updater(&mut self, updater: Option<Arc<WalletUpdater>>) {
+		self.updater = updater;
+	}
+
+	/// Set the runner of operations started by
+	/// [`start_operation`](struct.Owner.html#method.start_operation). The owner listener
+	/// shares one runner between all the API instances it creates.
+	///
+	/// # Arguments
+	/// * `operations` - An [`OperationRunner`](struct.OperationRunner.html) for this API's
+	/// wallet, or `None` if background operations aren't available.
+	pub fn set_operation_runner(&mut self, operations: Option<Arc<OperationRunner>>) {
+		self.operations = operations;
+	}
+
+	/// Set the escrow key [`export_txs`](struct.Owner.html#method.export_txs) seals each
+	/// transaction's participant messages to, so the holder of the matching secret key can
+	/// read them from a JSON export with [`open_memo`](../grin_wallet_libwallet/fn.open_memo.html),
+	/// without any access to the wallet.
+	///
+	/// # Arguments
+	/// * `memo_escrow_key` - The escrow public key, or `None` to leave memos out of exports.
+	pub fn set_memo_escrow_key(&mut self, memo_escrow_key: Option<PublicKey>) {
+		self.memo_escrow_key = memo_escrow_key;
+	}
+
+	/// Set the audit log [`export_audit_log`](struct.Owner.html#method.export_audit_log)
+	/// exports. Calls are recorded in it by the listener serving this API, not by the API itself.
+	///
+	/// # Arguments
+	/// * `audit_log` - The [`AuditLog`](../grin_wallet_impls/struct.AuditLog.html) kept by the
+	/// listener, or `None` if calls aren't being recorded.
+	pub fn set_audit_log(&mut self, audit_log: Option<Arc<AuditLog>>) {
+		self.audit_log = audit_log;
+	}
+
+	/// Ask for a TOTP code on spends, if the wallet has a confirmed TOTP secret (see
+	/// [`enroll_totp`](struct.Owner.html#method.enroll_totp)). Calls to
+	/// [`init_send_tx`](struct.Owner.html#method.init_send_tx),
+	/// [`process_invoice_tx`](struct.Owner.html#method.process_invoice_tx),
+	/// [`finalize_tx`](struct.Owner.html#method.finalize_tx),
+	/// [`post_tx`](struct.Owner.html#method.post_tx),
+	/// [`post_tx_checked`](struct.Owner.html#method.post_tx_checked) and
+	/// [`broadcast_signed`](struct.Owner.html#method.broadcast_signed) then fail without a
+	/// valid code. Set by the listeners for each call, from its `totp_code` named
+	/// parameter or, for positional V2 calls, its `X-Grin-Totp-Code` header.
+	///
+	/// # Arguments
+	/// * `code` - The code given with the call, if any.
+	pub fn require_totp(&mut self, code: Option<String>) {
+		self.totp = Some(code);
+		self.totp_accepted.store(false, Ordering::Relaxed);
+	}
+
+	/// Act on the given account in place of the wallet's active one. The account is
+	/// only switched to while a call holds the wallet lock, so the Foreign API, the
+	/// background updater and other callers sharing the wallet never see it active.
+	/// Set by the listeners for each call made with a bearer token scoped to an
+	/// account.
+	///
+	/// # Arguments
+	/// * `account` - Label of the account calls act on, or `None` for the active one.
+	pub fn scope_to_account(&mut self, account: Option<String>) {
+		self.account = account;
+	}
+
+	/// Lock the wallet, switched to the account calls are scoped to, if any, until
+	/// the lock is released
+	fn lock_wallet(&self) -> Result<WalletLock<'_, 'a, L, C, K>, Error> {
+		let mut guard: Box<dyn DerefMut<Target = Box<dyn WalletInst<'a, L, C, K>>> + '_> =
+			Box::new(self.wallet_inst.lock());
+		let previous = match self.account.as_ref() {
+			Some(account) => {
+				let w = guard.lc_provider()?.wallet_inst()?;
+				let previous = w.parent_key_id();
+				w.set_parent_key_id_by_name(account)?;
+				Some(previous)
+			}
+			None => None,
+		};
+		Ok(WalletLock { guard, previous })
+	}
+
+	/// Check the TOTP code given for a spend, if codes are asked for
+	fn check_totp(
+		&self,
+		w: &mut (dyn WalletBackend<'a, C, K> + 'a),
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<(), Error> {
+		let code = match self.totp.as_ref() {
+			Some(c) if !self.totp_accepted.load(Ordering::Relaxed) => c,
+			_ => return Ok(()),
+		};
+		owner::check_totp(w, keychain_mask, code.as_ref().map(|c| c.as_str()))?;
+		self.totp_accepted.store(true, Ordering::Relaxed);
+		Ok(())
+	}
+
+	/// While the background updater is running, calls asking to refresh from the
+	/// node skip the inline refresh and report the updater's last result instead.
+	/// Returns whether to refresh inline, and the updater's result if it stands in
+	fn updater_refresh(&self, refresh_from_node: bool) -> (bool, Option<bool>) {
+		match self.updater.as_ref() {
+			Some(u) if refresh_from_node && u.is_running() => (false, Some(u.refreshed())),
+			_ => (refresh_from_node, None),
+		}
+	}
+
+	/// Snapshot of unconfirmed transactions, taken before a refresh if any
+	/// webhook is interested in confirmations or height updates
+	fn unconfirmed_tx_ids<T: ?Sized>(
+		&self,
+		w: &T,
+		refresh_from_node: bool,
+	) -> Option<HashSet<(Identifier, u32)>>
+	where
+		T: WalletBackend<'a, C, K>,
+	{
+		match self.webhooks.as_ref() {
+			Some(h)
+				if refresh_from_node
+					&& (h.wants(WebhookEventType::TxConfirmed)
+						|| h.wants(WebhookEventType::HeightUpdated)) =>
+			{
+				Some(
+					w.tx_log_iter()
+						.ok()?
+						.filter(|t| !t.confirmed)
+						.map(|t| (t.parent_key_id, t.id))
+						.collect(),
+				)
+			}
+			_ => None,
+		}
+	}
+
+	/// Notify webhooks of transactions in the snapshot that have since confirmed,
+	/// and of the height the wallet was refreshed to
+	fn notify_confirmed<T: ?Sized>(
+		&self,
+		w: &mut T,
+		unconfirmed: Option<HashSet<(Identifier, u32)>>,
+	) where
+		T: WalletBackend<'a, C, K>,
+	{
+		if let (Some(h), Some(u)) = (self.webhooks.as_ref(), unconfirmed) {
+			if h.wants(WebhookEventType::TxConfirmed) {
+				let confirmed = match w.tx_log_iter() {
+					Ok(txs) => txs
+						.filter(|t| t.confirmed && u.contains(&(t.parent_key_id.clone(), t.id)))
+						.collect(),
+					Err(e) => {
+						warn!("Failed to read the wallet's transactions: {}", e);
+						vec![]
+					}
+				};
+				for t in confirmed {
+					h.dispatch(
+						WebhookPayload::new(WebhookEventType::TxConfirmed, None, Some(t))
+							.with_account(w.acct_path_iter().into_iter().flatten()),
+					);
+				}
+			}
+			if h.wants(WebhookEventType::HeightUpdated) {
+				match w.last_confirmed_height() {
+					Ok(height) => h.notify_height(height),
+					Err(e) => warn!("Failed to read the wallet's height: {}", e),
+				}
+			}
 		}
 	}
 
@@ -179,7 +509,7 @@ where
 		&self,
 		keychain_mask: Option<&SecretKey>,
 	) -> Result<Vec<AcctPathMapping>, Error> {
-		let mut w_lock = self.wallet_inst.lock();
+		let mut w_lock = self.lock_wallet()?;
 		let w = w_lock.lc_provider()?.wallet_inst()?;
 		// Test keychain mask, to keep API consistent
 		let _ = w.keychain(keychain_mask)?;
@@ -231,7 +561,7 @@ where
 		keychain_mask: Option<&SecretKey>,
 		label: &str,
 	) -> Result<Identifier, Error> {
-		let mut w_lock = self.wallet_inst.lock();
+		let mut w_lock = self.lock_wallet()?;
 		let w = w_lock.lc_provider()?.wallet_inst()?;
 		owner::create_account_path(&mut **w, keychain_mask, label)
 	}
@@ -279,39 +609,26 @@ where
 		keychain_mask: Option<&SecretKey>,
 		label: &str,
 	) -> Result<(), Error> {
-		let mut w_lock = self.wallet_inst.lock();
+		let mut w_lock = self.lock_wallet()?;
 		let w = w_lock.lc_provider()?.wallet_inst()?;
 		// Test keychain mask, to keep API consistent
 		let _ = w.keychain(keychain_mask)?;
 		owner::set_active_account(&mut **w, label)
 	}
 
-	/// Returns a list of outputs from the active account in the wallet.
+	/// Exports an account, for moving it to another wallet instance with the same seed. The
+	/// export holds the account's label, derivation index, outputs, transaction log and stored
+	/// transactions, encrypted with a key derived from the wallet seed so that only a wallet
+	/// with the same seed can import it.
 	///
 	/// # Arguments
 	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
 	/// being used.
-	/// * `include_spent` - If `true`, outputs that have been marked as 'spent'
-	/// in the wallet will be returned. If `false`, spent outputs will omitted
-	/// from the results.
-	/// * `refresh_from_node` - If true, the wallet will attempt to contact
-	/// a node (via the [`NodeClient`](../grin_wallet_libwallet/types/trait.NodeClient.html)
-	/// provided during wallet instantiation). If `false`, the results will
-	/// contain output information that may be out-of-date (from the last time
-	/// the wallet's output set was refreshed against the node).
-	/// * `tx_id` - If `Some(i)`, only return the outputs associated with
-	/// the transaction log entry of id `i`.
+	/// * `label` - The label of the account to export.
 	///
 	/// # Returns
-	/// * `(bool, Vec<OutputCommitMapping>)` - A tuple:
-	/// * The first `bool` element indicates whether the data was successfully
-	/// refreshed from the node (note this may be false even if the `refresh_from_node`
-	/// argument was set to `true`.
-	/// * The second element contains a vector of
-	/// [OutputCommitMapping](../grin_wallet_libwallet/types/struct.OutputCommitMapping.html)
-	/// of which each element is a mapping between the wallet's internal
-	/// [OutputData](../grin_wallet_libwallet/types/struct.Output.html)
-	/// and the Output commitment as identified in the chain's UTXO set
+	/// * Ok(String) containing the encrypted export, hex encoded, if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
 	///
 	/// # Example
 	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
@@ -319,58 +636,43 @@ where
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
 	/// let api_owner = Owner::new(wallet.clone());
-	/// let show_spent = false;
-	/// let update_from_node = true;
-	/// let tx_id = None;
 	///
-	/// let result = api_owner.retrieve_outputs(None, show_spent, update_from_node, tx_id);
+	/// let result = api_owner.export_account(None, "default");
 	///
-	/// if let Ok((was_updated, output_mappings)) = result {
+	/// if let Ok(export) = result {
+	///		// import into another wallet with the same seed
 	///		//...
 	/// }
 	/// ```
 
-	pub fn retrieve_outputs(
+	pub fn export_account(
 		&self,
 		keychain_mask: Option<&SecretKey>,
-		include_spent: bool,
-		refresh_from_node: bool,
-		tx_id: Option<u32>,
-	) -> Result<(bool, Vec<OutputCommitMapping>), Error> {
-		let mut w_lock = self.wallet_inst.lock();
+		label: &str,
+	) -> Result<String, Error> {
+		let mut w_lock = self.lock_wallet()?;
 		let w = w_lock.lc_provider()?.wallet_inst()?;
-		owner::retrieve_outputs(
-			&mut **w,
-			keychain_mask,
-			include_spent,
-			refresh_from_node,
-			tx_id,
-		)
+		owner::export_account(&mut **w, keychain_mask, label)
 	}
 
-	/// Returns a list of [Transaction Log Entries](../grin_wallet_libwallet/types/struct.TxLogEntry.html)
-	/// from the active account in the wallet.
+	/// Imports an account exported by [`export_account`](struct.Owner.html#method.export_account)
+	/// from a wallet with the same seed. The account is created if the wallet doesn't know it,
+	/// or keeps its existing label if it does. Outputs and transactions the wallet already has
+	/// are skipped, and imported transactions are given new ids, so importing the same export
+	/// again changes nothing. The derivation index is only ever moved forward, so keys aren't
+	/// reused.
 	///
 	/// # Arguments
 	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
 	/// being used.
-	/// * `refresh_from_node` - If true, the wallet will attempt to contact
-	/// a node (via the [`NodeClient`](../grin_wallet_libwallet/types/trait.NodeClient.html)
-	/// provided during wallet instantiation). If `false`, the results will
-	/// contain transaction information that may be out-of-date (from the last time
-	/// the wallet's output set was refreshed against the node).
-	/// * `tx_id` - If `Some(i)`, only return the transactions associated with
-	/// the transaction log entry of id `i`.
-	/// * `tx_slate_id` - If `Some(uuid)`, only return transactions associated with
-	/// the given [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html) uuid.
+	/// * `export` - The export, as returned by `export_account`.
 	///
 	/// # Returns
-	/// * `(bool, Vec<TxLogEntry)` - A tuple:
-	/// * The first `bool` element indicates whether the data was successfully
-	/// refreshed from the node (note this may be false even if the `refresh_from_node`
-	/// argument was set to `true`.
-	/// * The second element contains the set of retrieved
-	/// [TxLogEntries](../grin_wallet_libwallet/types/struct.TxLogEntry.html)
+	/// * Ok([`AcctPathMapping`](../grin_wallet_libwallet/types/struct.AcctPathMapping.html))
+	/// of the imported account if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if the export can't
+	/// be decrypted, e.g. because it's from a wallet with another seed, or if its label is used
+	/// by another account.
 	///
 	/// # Example
 	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
@@ -378,141 +680,925 @@ where
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
 	/// let api_owner = Owner::new(wallet.clone());
-	/// let update_from_node = true;
-	/// let tx_id = None;
-	/// let tx_slate_id = None;
 	///
-	/// // Return all TxLogEntries
-	/// let result = api_owner.retrieve_txs(None, update_from_node, tx_id, tx_slate_id);
+	/// let export = api_owner.export_account(None, "default").unwrap();
+	/// let result = api_owner.import_account(None, &export);
 	///
-	/// if let Ok((was_updated, tx_log_entries)) = result {
-	///		//...
+	/// if let Ok(account) = result {
+	///		println!("Imported account '{}'", account.label);
 	/// }
 	/// ```
 
-	pub fn retrieve_txs(
+	pub fn import_account(
 		&self,
 		keychain_mask: Option<&SecretKey>,
-		refresh_from_node: bool,
-		tx_id: Option<u32>,
-		tx_slate_id: Option<Uuid>,
-	) -> Result<(bool, Vec<TxLogEntry>), Error> {
-		let mut w_lock = self.wallet_inst.lock();
+		export: &str,
+	) -> Result<AcctPathMapping, Error> {
+		let mut w_lock = self.lock_wallet()?;
 		let w = w_lock.lc_provider()?.wallet_inst()?;
-		let mut res = owner::retrieve_txs(
-			&mut **w,
-			keychain_mask,
-			refresh_from_node,
-			tx_id,
-			tx_slate_id,
-		)?;
-		if self.doctest_mode {
-			res.1 = res
-				.1
-				.into_iter()
-				.map(|mut t| {
-					t.confirmation_ts = Some(Utc.ymd(2019, 1, 15).and_hms(16, 1, 26));
-					t.creation_ts = Utc.ymd(2019, 1, 15).and_hms(16, 1, 26);
-					t
-				})
-				.collect();
-		}
-		Ok(res)
+		owner::import_account(&mut **w, keychain_mask, export)
 	}
 
-	/// Returns summary information from the active account in the wallet.
+	/// Backs up the wallet's accounts, outputs, transaction log, stored transactions and
+	/// settings. A full backup holds all of them, while an incremental backup only holds what
+	/// has been added, changed or removed since the last backup taken, full or incremental, so
+	/// frequent backups of a large wallet stay small. Backups are encrypted with a key derived
+	/// from the wallet seed, and are returned to be stored by the caller.
 	///
 	/// # Arguments
 	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
 	/// being used.
-	/// * `refresh_from_node` - If true, the wallet will attempt to contact
-	/// a node (via the [`NodeClient`](../grin_wallet_libwallet/types/trait.NodeClient.html)
-	/// provided during wallet instantiation). If `false`, the results will
-	/// contain transaction information that may be out-of-date (from the last time
-	/// the wallet's output set was refreshed against the node).
-	/// * `minimum_confirmations` - The minimum number of confirmations an output
-	/// should have before it's included in the 'amount_currently_spendable' total
+	/// * `incremental` - Whether to only back up the changes since the last backup. The backup
+	/// is full if the wallet hasn't been backed up yet.
 	///
 	/// # Returns
-	/// * (`bool`, [`WalletInfo`](../grin_wallet_libwallet/types/struct.WalletInfo.html)) - A tuple:
-	/// * The first `bool` element indicates whether the data was successfully
-	/// refreshed from the node (note this may be false even if the `refresh_from_node`
-	/// argument was set to `true`.
-	/// * The second element contains the Summary [`WalletInfo`](../grin_wallet_libwallet/types/struct.WalletInfo.html)
+	/// * Ok([`WalletBackup`](../grin_wallet_libwallet/struct.WalletBackup.html)) if successful,
+	/// whose `parent` is the id of the backup it holds the changes since
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
 	///
 	/// # Example
 	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
 	/// ```
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
-	/// let mut api_owner = Owner::new(wallet.clone());
-	/// let update_from_node = true;
-	/// let minimum_confirmations=10;
+	/// let api_owner = Owner::new(wallet.clone());
 	///
-	/// // Return summary info for active account
-	/// let result = api_owner.retrieve_summary_info(None, update_from_node, minimum_confirmations);
+	/// let full = api_owner.backup(None, false).unwrap();
+	/// let result = api_owner.backup(None, true);
 	///
-	/// if let Ok((was_updated, summary_info)) = result {
+	/// if let Ok(backup) = result {
+	///		assert_eq!(backup.parent, Some(full.id));
+	///		// store backup.data along with the full backup
 	///		//...
 	/// }
 	/// ```
 
-	pub fn retrieve_summary_info(
+	pub fn backup(
 		&self,
 		keychain_mask: Option<&SecretKey>,
-		refresh_from_node: bool,
-		minimum_confirmations: u64,
-	) -> Result<(bool, WalletInfo), Error> {
-		let mut w_lock = self.wallet_inst.lock();
+		incremental: bool,
+	) -> Result<WalletBackup, Error> {
+		let mut w_lock = self.lock_wallet()?;
 		let w = w_lock.lc_provider()?.wallet_inst()?;
-		owner::retrieve_summary_info(
-			&mut **w,
-			keychain_mask,
-			refresh_from_node,
-			minimum_confirmations,
-		)
+		owner::backup(&mut **w, keychain_mask, incremental)
 	}
 
-	/// Initiates a new transaction as the sender, creating a new
-	/// [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html) object containing
-	/// the sender's inputs, change outputs, and public signature data. This slate can
-	/// then be sent to the recipient to continue the transaction via the
-	/// [Foreign API's `receive_tx`](struct.Foreign.html#method.receive_tx) method.
-	///
-	/// When a transaction is created, the wallet must also lock inputs (and create unconfirmed
-	/// outputs) corresponding to the transaction created in the slate, so that the wallet doesn't
-	/// attempt to re-spend outputs that are already included in a transaction before the transaction
-	/// is confirmed. This method also returns a function that will perform that locking, and it is
-	/// up to the caller to decide the best time to call the lock function
-	/// (via the [`tx_lock_outputs`](struct.Owner.html#method.tx_lock_outputs) method).
-	/// If the exchange method is intended to be synchronous (such as via a direct http call,)
-	/// then the lock call can wait until the response is confirmed. If it is asynchronous, (such
-	/// as via file transfer,) the lock call should happen immediately (before the file is sent
-	/// to the recipient).
-	///
-	/// If the `send_args` [`InitTxSendArgs`](../grin_wallet_libwallet/types/struct.InitTxSendArgs.html),
-	/// of the [`args`](../grin_wallet_libwallet/types/struct.InitTxArgs.html), field is Some, this
-	/// function will attempt to perform a synchronous send to the recipient specified in the `dest`
-	/// field according to the `method` field, and will also finalize and post the transaction if
-	/// the `finalize` field is set.
+	/// Rebuilds the wallet's records as they were when a backup was taken, from the full backup
+	/// taken before it and the incremental backups taken in between, given in any order. Only
+	/// backups of a wallet with the same seed can be read.
 	///
 	/// # Arguments
 	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
 	/// being used.
-	/// * `args` - [`InitTxArgs`](../grin_wallet_libwallet/types/struct.InitTxArgs.html),
-	/// transaction initialization arguments. See struct documentation for further detail.
+	/// * `backups` - The `data` of the backups, as returned by
+	/// [`backup`](struct.Owner.html#method.backup). Backups not needed are ignored.
+	/// * `until` - Id of the backup to rebuild the records as of, or `None` for the latest of
+	/// the given backups.
 	///
 	/// # Returns
-	/// * a result containing:
-	/// * The transaction [Slate](../grin_wallet_libwallet/slate/struct.Slate.html),
-	/// which can be forwarded to the recieving party by any means. Once the caller is relatively
-	/// certain that the transaction has been sent to the recipient, the associated wallet
-	/// transaction outputs should be locked via a call to
-	/// [`tx_lock_outputs`](struct.Owner.html#method.tx_lock_outputs). This must be called before calling
-	/// [`finalize_tx`](struct.Owner.html#method.finalize_tx).
-	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	/// * Ok([`WalletRecords`](../grin_wallet_libwallet/type.WalletRecords.html)) if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if a backup can't be
+	/// decrypted or a backup the records depend on is missing.
 	///
-	/// # Remarks
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	///
+	/// let full = api_owner.backup(None, false).unwrap();
+	/// let result = api_owner.reconstruct_backup(None, &[full.data], Some(full.id));
+	///
+	/// if let Ok(records) = result {
+	///		println!("{} records", records.len());
+	/// }
+	/// ```
+
+	pub fn reconstruct_backup(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		backups: &[String],
+		until: Option<Uuid>,
+	) -> Result<WalletRecords, Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::reconstruct_backup(&mut **w, keychain_mask, backups, until)
+	}
+
+	/// Restores a wallet from its encrypted seed file and backups into the wallet's data
+	/// directory, which must not hold a wallet yet. The archive is decrypted and its records
+	/// rebuilt before anything is written, and once installed the restored outputs are checked
+	/// against the chain as with [`check_repair`](struct.Owner.html#method.check_repair). The
+	/// wallet isn't opened.
+	///
+	/// # Arguments
+	/// * `archive` - A JSON [`BackupArchive`](../grin_wallet_libwallet/struct.BackupArchive.html)
+	/// holding the contents of the wallet's seed file and the `data` of its backups.
+	/// * `password` - The password the seed file is encrypted with.
+	///
+	/// # Returns
+	/// * `Ok(())` if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if the archive can't
+	/// be read or decrypted, a wallet already exists, or the integrity check fails. Nothing is
+	/// left in the data directory on failure.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	/// use libwallet::BackupArchive;
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	///
+	/// // the seed file and backups, as copied off the host
+	/// let archive = BackupArchive {
+	///		seed: std::fs::read_to_string("/backups/wallet.seed").unwrap_or_default(),
+	///		backups: vec![],
+	/// };
+	/// let archive = serde_json::to_string(&archive).unwrap();
+	/// let result = api_owner.restore_from_backup(&archive, ZeroingString::from("password"));
+	///
+	/// if let Ok(()) = result {
+	///		// open the restored wallet
+	///		//...
+	/// }
+	/// ```
+
+	pub fn restore_from_backup(&self, archive: &str, password: ZeroingString) -> Result<(), Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let lc = w_lock.lc_provider()?;
+		lc.restore_from_backup(archive, password)
+	}
+
+	/// Changes the password the wallet's seed file is encrypted with. The seed file is the only
+	/// wallet data encrypted with the password, and is replaced in a single step once
+	/// re-encrypted, so a failure leaves the old password in place. If the open wallet is
+	/// masked, its keychain is masked again with a new mask, so every mask issued before the
+	/// change is rejected from then on; background updates started with the old mask stop at
+	/// their next refresh. Listeners issuing bearer tokens revoke all of them once the password
+	/// is changed, so every client has to log in again.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `old` - The password the seed file is currently encrypted with.
+	/// * `new` - The password to encrypt it with from now on.
+	///
+	/// # Returns
+	/// * `Ok(Some(mask))` with the new keychain mask if the wallet is masked, `Ok(None)` if not
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if the wallet isn't
+	/// open, the old password is wrong or the new seed file can't be written.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.change_password(
+	/// 	None,
+	/// 	ZeroingString::from("password"),
+	/// 	ZeroingString::from("a better password"),
+	/// );
+	///
+	/// if let Ok(new_mask) = result {
+	///		// later calls pass `new_mask.as_ref()`, and the next time the wallet is
+	///		// opened it takes the new password
+	///		//...
+	/// }
+	/// ```
+
+	pub fn change_password(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		old: ZeroingString,
+		new: ZeroingString,
+	) -> Result<Option<SecretKey>, Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let lc = w_lock.lc_provider()?;
+		// only for callers holding the open wallet's mask
+		let k = lc.wallet_inst()?.keychain(keychain_mask)?;
+		lc.change_password(old, new)?;
+		lc.wallet_inst()?
+			.set_keychain(Box::new(k), keychain_mask.is_some(), self.doctest_mode)
+	}
+
+	/// Returns a list of outputs from the active account in the wallet.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `include_spent` - If `true`, outputs that have been marked as 'spent'
+	/// in the wallet will be returned. If `false`, spent outputs will omitted
+	/// from the results.
+	/// * `refresh_from_node` - If true, the wallet will attempt to contact
+	/// a node (via the [`NodeClient`](../grin_wallet_libwallet/types/trait.NodeClient.html)
+	/// provided during wallet instantiation). If `false`, the results will
+	/// contain output information that may be out-of-date (from the last time
+	/// the wallet's output set was refreshed against the node).
+	/// * `tx_id` - If `Some(i)`, only return the outputs associated with
+	/// the transaction log entry of id `i`.
+	///
+	/// # Returns
+	/// * `(bool, Vec<OutputCommitMapping>)` - A tuple:
+	/// * The first `bool` element indicates whether the data was successfully
+	/// refreshed from the node (note this may be false even if the `refresh_from_node`
+	/// argument was set to `true`.
+	/// * The second element contains a vector of
+	/// [OutputCommitMapping](../grin_wallet_libwallet/types/struct.OutputCommitMapping.html)
+	/// of which each element is a mapping between the wallet's internal
+	/// [OutputData](../grin_wallet_libwallet/types/struct.Output.html)
+	/// and the Output commitment as identified in the chain's UTXO set
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let show_spent = false;
+	/// let update_from_node = true;
+	/// let tx_id = None;
+	///
+	/// let result = api_owner.retrieve_outputs(None, show_spent, update_from_node, tx_id);
+	///
+	/// if let Ok((was_updated, output_mappings)) = result {
+	///		//...
+	/// }
+	/// ```
+
+	pub fn retrieve_outputs(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		include_spent: bool,
+		refresh_from_node: bool,
+		tx_id: Option<u32>,
+	) -> Result<(bool, Vec<OutputCommitMapping>), Error> {
+		let (refresh_from_node, refreshed) = self.updater_refresh(refresh_from_node);
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		let unconfirmed = self.unconfirmed_tx_ids(&**w, refresh_from_node);
+		let mut res = owner::retrieve_outputs(
+			&mut **w,
+			keychain_mask,
+			include_spent,
+			refresh_from_node,
+			tx_id,
+		)?;
+		self.notify_confirmed(&mut **w, unconfirmed);
+		if let Some(r) = refreshed {
+			res.0 = r;
+		}
+		Ok(res)
+	}
+
+	/// Returns a list of outputs from the wallet matching the given query. Unlike
+	/// [`retrieve_outputs`](struct.Owner.html#method.retrieve_outputs), filters are applied while
+	/// the outputs are read from the wallet's store, so only matching outputs are held in memory.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `refresh_from_node` - If true, the wallet will attempt to contact
+	/// a node (via the [`NodeClient`](../grin_wallet_libwallet/types/trait.NodeClient.html)
+	/// provided during wallet instantiation). If `false`, the results will
+	/// contain output information that may be out-of-date (from the last time
+	/// the wallet's output set was refreshed against the node).
+	/// * `query` - [`RetrieveOutputsQueryArgs`](../grin_wallet_libwallet/types/struct.RetrieveOutputsQueryArgs.html),
+	/// containing the status filters, block height range and account to query. If no account is
+	/// given, the active account is used. The outputs of the account queried are the ones
+	/// refreshed, rather than those of the active account.
+	///
+	/// # Returns
+	/// * `(bool, Vec<OutputCommitMapping>)` - A tuple:
+	/// * The first `bool` element indicates whether the data was successfully
+	/// refreshed from the node (note this may be false even if the `refresh_from_node`
+	/// argument was set to `true`.
+	/// * The second element contains a vector of
+	/// [OutputCommitMapping](../grin_wallet_libwallet/types/struct.OutputCommitMapping.html)
+	/// of which each element is a mapping between the wallet's internal
+	/// [OutputData](../grin_wallet_libwallet/types/struct.Output.html)
+	/// and the Output commitment as identified in the chain's UTXO set
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let update_from_node = true;
+	///
+	/// // Return unspent outputs created at or after block 100
+	/// let query = RetrieveOutputsQueryArgs {
+	/// 	statuses: Some(vec![OutputStatus::Unspent]),
+	/// 	min_height: Some(100),
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.query_outputs(None, update_from_node, query);
+	///
+	/// if let Ok((was_updated, output_mappings)) = result {
+	///		//...
+	/// }
+	/// ```
+
+	pub fn query_outputs(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		refresh_from_node: bool,
+		query: RetrieveOutputsQueryArgs,
+	) -> Result<(bool, Vec<OutputCommitMapping>), Error> {
+		// the updater only refreshes the active account
+		let (refresh_from_node, refreshed) = match query.account {
+			Some(_) => (refresh_from_node, None),
+			None => self.updater_refresh(refresh_from_node),
+		};
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		let unconfirmed = self.unconfirmed_tx_ids(&**w, refresh_from_node);
+		let mut res = owner::query_outputs(&mut **w, keychain_mask, refresh_from_node, &query)?;
+		self.notify_confirmed(&mut **w, unconfirmed);
+		if let Some(r) = refreshed {
+			res.0 = r;
+		}
+		Ok(res)
+	}
+
+	/// Returns how the key of one of the wallet's outputs is derived from the wallet's seed,
+	/// for checking the derivation against other tooling or a hardware wallet. The output may
+	/// belong to any account, and may be spent.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `commit` - The output's commitment, hex encoded.
+	///
+	/// # Returns
+	/// * Ok([`OutputDerivation`](../grin_wallet_libwallet/api_impl/types/struct.OutputDerivation.html))
+	/// holding the output's account, key identifier, full derivation path, key index and switch
+	/// commitment type, if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// such as the wallet having no output with the commitment.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let commit = "087df32304c5d4ae8b2af0bc31e700019d722910ef87dd4eec3197b80b207e3045";
+	///
+	/// let result = api_owner.get_output_derivation(None, commit);
+	///
+	/// if let Ok(derivation) = result {
+	///		// e.g. m/0/0/1
+	///		println!("{}", derivation.path);
+	/// }
+	/// ```
+
+	pub fn get_output_derivation(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		commit: &str,
+	) -> Result<OutputDerivation, Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::get_output_derivation(&mut **w, keychain_mask, commit)
+	}
+
+	/// Reserves the next keys of the active account for use outside the wallet, such as by a
+	/// mining pool or payment processor building its own outputs. The wallet never derives
+	/// reserved keys for its own outputs, so commitments can safely be generated from them
+	/// ahead of time. A wallet restored from its seed only skips reserved keys whose outputs
+	/// it finds on chain.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `count` - Number of keys to reserve, between 1 and 1000.
+	///
+	/// # Returns
+	/// * Ok([`ReservedKeys`](../grin_wallet_libwallet/api_impl/types/struct.ReservedKeys.html))
+	/// holding the account, the index of the first key reserved and the identifiers of the keys,
+	/// if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	///
+	/// let result = api_owner.reserve_derivation_indices(None, 10);
+	///
+	/// if let Ok(reserved) = result {
+	///		// keys the wallet won't use, from reserved.first_index
+	///		println!("{:?}", reserved.key_ids);
+	/// }
+	/// ```
+
+	pub fn reserve_derivation_indices(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		count: u32,
+	) -> Result<ReservedKeys, Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::reserve_derivation_indices(&mut **w, keychain_mask, count)
+	}
+
+	/// Returns a list of [Transaction Log Entries](../grin_wallet_libwallet/types/struct.TxLogEntry.html)
+	/// from the active account in the wallet, or the page of them matching a query. Entries are
+	/// filtered by the query as they are read from the wallet's store, so wallets with a large
+	/// number of transactions should page through them with one.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `refresh_from_node` - If true, the wallet will attempt to contact
+	/// a node (via the [`NodeClient`](../grin_wallet_libwallet/types/trait.NodeClient.html)
+	/// provided during wallet instantiation). If `false`, the results will
+	/// contain transaction information that may be out-of-date (from the last time
+	/// the wallet's output set was refreshed against the node).
+	/// * `tx_id` - If `Some(i)`, only return the transactions associated with
+	/// the transaction log entry of id `i`.
+	/// * `tx_slate_id` - If `Some(uuid)`, only return transactions associated with
+	/// the given [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html) uuid.
+	/// * `query` - If `Some`, a [`RetrieveTxQueryArgs`](../grin_wallet_libwallet/types/struct.RetrieveTxQueryArgs.html)
+	/// containing the offset, limit, status filters, creation date range, sort order and account
+	/// to apply. If it names an account, that account is refreshed and its entries returned.
+	///
+	/// # Returns
+	/// * `(bool, Vec<TxLogEntry)` - A tuple:
+	/// * The first `bool` element indicates whether the data was successfully
+	/// refreshed from the node (note this may be false even if the `refresh_from_node`
+	/// argument was set to `true`.
+	/// * The second element contains the set of retrieved
+	/// [TxLogEntries](../grin_wallet_libwallet/types/struct.TxLogEntry.html)
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let update_from_node = true;
+	/// let tx_id = None;
+	/// let tx_slate_id = None;
+	///
+	/// // Return all TxLogEntries
+	/// let result = api_owner.retrieve_txs(None, update_from_node, tx_id, tx_slate_id, None);
+	///
+	/// if let Ok((was_updated, tx_log_entries)) = result {
+	///		//...
+	/// }
+	///
+	/// // Return the 10 most recent unconfirmed transactions
+	/// let query = RetrieveTxQueryArgs {
+	/// 	limit: Some(10),
+	/// 	statuses: Some(vec![TxQueryStatus::Unconfirmed]),
+	/// 	sort_order: Some(TxSortOrder::Desc),
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.retrieve_txs(None, update_from_node, None, None, Some(query));
+	///
+	/// if let Ok((was_updated, tx_log_entries)) = result {
+	///		//...
+	/// }
+	/// ```
+
+	pub fn retrieve_txs(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		refresh_from_node: bool,
+		tx_id: Option<u32>,
+		tx_slate_id: Option<Uuid>,
+		query: Option<RetrieveTxQueryArgs>,
+	) -> Result<(bool, Vec<TxLogEntry>), Error> {
+		// the updater only refreshes the active account
+		let (refresh_from_node, refreshed) = match query.as_ref().and_then(|q| q.account.as_ref()) {
+			Some(_) => (refresh_from_node, None),
+			None => self.updater_refresh(refresh_from_node),
+		};
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		let unconfirmed = self.unconfirmed_tx_ids(&**w, refresh_from_node);
+		let mut res = owner::retrieve_txs(
+			&mut **w,
+			keychain_mask,
+			refresh_from_node,
+			tx_id,
+			tx_slate_id,
+			query.as_ref(),
+		)?;
+		self.notify_confirmed(&mut **w, unconfirmed);
+		if let Some(r) = refreshed {
+			res.0 = r;
+		}
+		if self.doctest_mode {
+			res.1 = res
+				.1
+				.into_iter()
+				.map(|mut t| {
+					t.confirmation_ts = Some(Utc.ymd(2019, 1, 15).and_hms(16, 1, 26));
+					t.creation_ts = Utc.ymd(2019, 1, 15).and_hms(16, 1, 26);
+					t
+				})
+				.collect();
+		}
+		Ok(res)
+	}
+
+	/// Produces a [settlement report](../grin_wallet_libwallet/struct.SettlementReport.html)
+	/// closing an accounting period for the active account: its balance at the start and end
+	/// of the period, and the receipts, disbursements and fees in between, each valued in
+	/// fiat at the time of the transaction. Only confirmed, uncancelled transactions count.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `refresh_from_node` - If true, the wallet will attempt to contact
+	/// a node (via the [`NodeClient`](../grin_wallet_libwallet/types/trait.NodeClient.html)
+	/// provided during wallet instantiation) so confirmations are up to date.
+	/// * `start` - Start of the period (inclusive).
+	/// * `end` - End of the period (exclusive).
+	/// * `currency` - Fiat currency to value transactions in, as understood by the oracle.
+	/// * `oracle` - The [`PriceOracle`](../grin_wallet_libwallet/trait.PriceOracle.html)
+	/// giving the price of grin at each transaction's time.
+	///
+	/// # Returns
+	/// * Ok([`SettlementReport`](../grin_wallet_libwallet/struct.SettlementReport.html)) if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	/// use chrono::prelude::*;
+	/// use grin_wallet_libwallet::FixedPriceOracle;
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let start = Utc.ymd(2019, 1, 1).and_hms(0, 0, 0);
+	/// let end = Utc.ymd(2019, 2, 1).and_hms(0, 0, 0);
+	/// let oracle = FixedPriceOracle::new(2.5);
+	///
+	/// let result = api_owner.settlement_report(None, true, start, end, "usd", &oracle);
+	///
+	/// if let Ok(report) = result {
+	///		println!("{}", report.to_csv());
+	///		//...
+	/// }
+	/// ```
+
+	pub fn settlement_report(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		refresh_from_node: bool,
+		start: DateTime<Utc>,
+		end: DateTime<Utc>,
+		currency: &str,
+		oracle: &dyn PriceOracle,
+	) -> Result<SettlementReport, Error> {
+		if start >= end {
+			return Err(ErrorKind::GenericError(
+				"Settlement period must end after it starts".to_owned(),
+			))?;
+		}
+		let (_, txs) = self.retrieve_txs(keychain_mask, refresh_from_node, None, None, None)?;
+		libwallet::settlement_report(&txs, start, end, currency, oracle)
+	}
+
+	/// Exports the active account's transactions matching a filter, for accounting and tax
+	/// records. Each transaction is given with its amounts, fee, timestamps, slate id and
+	/// the excess of its kernel, by which it can be found on chain, when the wallet stored
+	/// the transaction. If a [memo escrow key](struct.Owner.html#method.set_memo_escrow_key)
+	/// is set, JSON exports also give each transaction's participant messages sealed to it.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `refresh_from_node` - If true, the wallet will attempt to contact
+	/// a node (via the [`NodeClient`](../grin_wallet_libwallet/types/trait.NodeClient.html)
+	/// provided during wallet instantiation) so confirmations are up to date.
+	/// * `format` - The [`TxExportFormat`](../grin_wallet_libwallet/enum.TxExportFormat.html)
+	/// to render the transactions in.
+	/// * `filter` - [`RetrieveTxQueryArgs`](../grin_wallet_libwallet/types/struct.RetrieveTxQueryArgs.html)
+	/// selecting the transactions to export, as for
+	/// [`retrieve_txs`](struct.Owner.html#method.retrieve_txs).
+	///
+	/// # Returns
+	/// * Ok(String) containing the exported transactions if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	/// use grin_wallet_libwallet::TxExportFormat;
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.export_txs(None, true, TxExportFormat::Csv, Default::default());
+	///
+	/// if let Ok(csv) = result {
+	///		println!("{}", csv);
+	///		//...
+	/// }
+	/// ```
+
+	pub fn export_txs(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		refresh_from_node: bool,
+		format: TxExportFormat,
+		filter: RetrieveTxQueryArgs,
+	) -> Result<String, Error> {
+		let (_, txs) =
+			self.retrieve_txs(keychain_mask, refresh_from_node, None, None, Some(filter))?;
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		let mut entries = vec![];
+		for t in txs.iter() {
+			// a missing transaction file shouldn't stop the rest being exported
+			let tx = owner::get_stored_tx(&**w, t).unwrap_or(None);
+			let mut entry = TxExportEntry::new(t, tx.as_ref());
+			if let (Some(key), TxExportFormat::Json) = (self.memo_escrow_key.as_ref(), format) {
+				entry.escrow_memo(t, key)?;
+			}
+			entries.push(entry);
+		}
+		libwallet::export_txs(&entries, format)
+	}
+
+	/// Lists transactions in the active account needing the user's attention, each with a
+	/// suggested way to resolve it, as a
+	/// [`TxAnomaly`](../grin_wallet_libwallet/struct.TxAnomaly.html). These are:
+	/// * Sent transactions still unconfirmed after `stuck_after_mins` minutes, to be reposted
+	/// if they were finalized or cancelled if not.
+	/// * Received transactions the sender hasn't finalized and posted within `stuck_after_mins`
+	/// minutes.
+	/// * Cancelled transactions whose outputs are still locked or unconfirmed.
+	/// * Confirmed transactions whose outputs have since left the chain, most likely through
+	/// a reorg.
+	/// * Transactions having spent an output that's since reappeared on chain, most likely
+	/// through a replay of the transaction creating it. Only looked for when refreshing from
+	/// the node.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `refresh_from_node` - If true, the wallet will attempt to contact
+	/// a node (via the [`NodeClient`](../grin_wallet_libwallet/types/trait.NodeClient.html)
+	/// provided during wallet instantiation) before looking for anomalies.
+	/// * `stuck_after_mins` - How long a transaction may stay unconfirmed before it's listed.
+	///
+	/// # Returns
+	/// * `(bool, Vec<TxAnomaly>)` - A tuple:
+	/// * The first `bool` element indicates whether the data was successfully
+	/// refreshed from the node (note this may be false even if the `refresh_from_node`
+	/// argument was set to `true`.
+	/// * The second element contains the anomalies found
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.list_anomalies(None, true, 1440);
+	///
+	/// if let Ok((_, anomalies)) = result {
+	///		for a in anomalies {
+	///			println!("Tx {}: {} ({:?})", a.tx_id, a.description, a.suggested_action);
+	///		}
+	/// }
+	/// ```
+
+	pub fn list_anomalies(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		refresh_from_node: bool,
+		stuck_after_mins: u64,
+	) -> Result<(bool, Vec<TxAnomaly>), Error> {
+		let (refresh_from_node, refreshed) = self.updater_refresh(refresh_from_node);
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		let unconfirmed = self.unconfirmed_tx_ids(&**w, refresh_from_node);
+		let mut res =
+			owner::list_anomalies(&mut **w, keychain_mask, refresh_from_node, stuck_after_mins)?;
+		self.notify_confirmed(&mut **w, unconfirmed);
+		if let Some(r) = refreshed {
+			res.0 = r;
+		}
+		Ok(res)
+	}
+
+	/// Carries out the fix suggested by [`list_anomalies`](struct.Owner.html#method.list_anomalies)
+	/// for a transaction. The transaction is checked again first, and the fix is only applied
+	/// if the transaction is still anomalous and `action` is the fix suggested for it, so
+	/// passing the action serves as the caller's approval of it. Each fix attempted is
+	/// logged to the `audit` log target along with its outcome.
+	///
+	/// * [`Repost`](../grin_wallet_libwallet/enum.RemediationAction.html#variant.Repost)
+	/// posts the transaction's stored copy to the node again.
+	/// * [`Cancel`](../grin_wallet_libwallet/enum.RemediationAction.html#variant.Cancel)
+	/// cancels the transaction, unlocking its inputs, as
+	/// [`cancel_tx`](struct.Owner.html#method.cancel_tx) does.
+	/// * [`CheckRepair`](../grin_wallet_libwallet/enum.RemediationAction.html#variant.CheckRepair)
+	/// rescans the wallet's outputs against the chain, as
+	/// [`check_repair`](struct.Owner.html#method.check_repair) does.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `tx_id` - The id of the anomalous transaction, as given in its
+	/// [`TxAnomaly`](../grin_wallet_libwallet/struct.TxAnomaly.html).
+	/// * `action` - The approved fix, which must be the one suggested.
+	///
+	/// # Returns
+	/// * Ok(()) if the fix was applied successfully
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if the transaction
+	/// is no longer anomalous, a different fix was suggested, or the fix failed.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.list_anomalies(None, true, 1440);
+	///
+	/// if let Ok((_, anomalies)) = result {
+	///		for a in anomalies {
+	///			// after asking the user to approve the suggested fix
+	///			let _ = api_owner.remediate(None, a.tx_id, a.suggested_action);
+	///		}
+	/// }
+	/// ```
+
+	pub fn remediate(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		tx_id: u32,
+		action: RemediationAction,
+	) -> Result<(), Error> {
+		let (anomaly, tx) = {
+			let mut w_lock = self.lock_wallet()?;
+			let w = w_lock.lc_provider()?.wallet_inst()?;
+			let (_, anomalies) = owner::list_anomalies(&mut **w, keychain_mask, false, 0)?;
+			let anomaly = anomalies.into_iter().find(|a| a.tx_id == tx_id);
+			let parent_key_id = w.parent_key_id();
+			let tx = w
+				.tx_log_iter()?
+				.find(|t| t.id == tx_id && t.parent_key_id == parent_key_id);
+			(anomaly, tx)
+		};
+		let anomaly = match anomaly {
+			Some(a) => a,
+			None => {
+				return Err(ErrorKind::GenericError(format!(
+					"Transaction {} has no anomaly to remediate",
+					tx_id
+				)))?;
+			}
+		};
+		if anomaly.suggested_action != action {
+			return Err(ErrorKind::GenericError(format!(
+				"{:?} is not the suggested remediation for transaction {}, {:?} is",
+				action, tx_id, anomaly.suggested_action
+			)))?;
+		}
+		info!(
+			target: "audit",
+			"Remediating {:?} transaction {} with {:?}", anomaly.kind, tx_id, action
+		);
+		let res = match action {
+			RemediationAction::Repost => match tx {
+				Some(t) => match self.get_stored_tx(keychain_mask, &t)? {
+					Some(stored) => self.post_tx(keychain_mask, &stored, t.fluff),
+					None => Err(ErrorKind::GenericError(format!(
+						"Transaction {} has no stored transaction to repost",
+						tx_id
+					)))?,
+				},
+				None => Err(ErrorKind::TransactionDoesntExist(tx_id.to_string()))?,
+			},
+			RemediationAction::Cancel => self.cancel_tx(keychain_mask, Some(tx_id), None),
+			RemediationAction::CheckRepair => self.check_repair(keychain_mask, false, true),
+		};
+		match res.as_ref() {
+			Ok(_) => info!(target: "audit", "Remediation of transaction {} succeeded", tx_id),
+			Err(e) => warn!(
+				target: "audit",
+				"Remediation of transaction {} failed: {}", tx_id, e
+			),
+		}
+		res
+	}
+
+	/// Returns summary information from the active account in the wallet.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `refresh_from_node` - If true, the wallet will attempt to contact
+	/// a node (via the [`NodeClient`](../grin_wallet_libwallet/types/trait.NodeClient.html)
+	/// provided during wallet instantiation). If `false`, the results will
+	/// contain transaction information that may be out-of-date (from the last time
+	/// the wallet's output set was refreshed against the node).
+	/// * `minimum_confirmations` - The minimum number of confirmations an output
+	/// should have before it's included in the 'amount_currently_spendable' total
+	///
+	/// # Returns
+	/// * (`bool`, [`WalletInfo`](../grin_wallet_libwallet/types/struct.WalletInfo.html)) - A tuple:
+	/// * The first `bool` element indicates whether the data was successfully
+	/// refreshed from the node (note this may be false even if the `refresh_from_node`
+	/// argument was set to `true`.
+	/// * The second element contains the Summary [`WalletInfo`](../grin_wallet_libwallet/types/struct.WalletInfo.html)
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let update_from_node = true;
+	/// let minimum_confirmations=10;
+	///
+	/// // Return summary info for active account
+	/// let result = api_owner.retrieve_summary_info(None, update_from_node, minimum_confirmations);
+	///
+	/// if let Ok((was_updated, summary_info)) = result {
+	///		//...
+	/// }
+	/// ```
+
+	pub fn retrieve_summary_info(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		refresh_from_node: bool,
+		minimum_confirmations: u64,
+	) -> Result<(bool, WalletInfo), Error> {
+		let (refresh_from_node, refreshed) = self.updater_refresh(refresh_from_node);
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		let unconfirmed = self.unconfirmed_tx_ids(&**w, refresh_from_node);
+		let mut res = owner::retrieve_summary_info(
+			&mut **w,
+			keychain_mask,
+			refresh_from_node,
+			minimum_confirmations,
+		)?;
+		self.notify_confirmed(&mut **w, unconfirmed);
+		if let Some(r) = refreshed {
+			res.0 = r;
+		}
+		Ok(res)
+	}
+
+	/// Initiates a new transaction as the sender, creating a new
+	/// [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html) object containing
+	/// the sender's inputs, change outputs, and public signature data. This slate can
+	/// then be sent to the recipient to continue the transaction via the
+	/// [Foreign API's `receive_tx`](struct.Foreign.html#method.receive_tx) method.
+	///
+	/// When a transaction is created, the wallet must also lock inputs (and create unconfirmed
+	/// outputs) corresponding to the transaction created in the slate, so that the wallet doesn't
+	/// attempt to re-spend outputs that are already included in a transaction before the transaction
+	/// is confirmed. This method also returns a function that will perform that locking, and it is
+	/// up to the caller to decide the best time to call the lock function
+	/// (via the [`tx_lock_outputs`](struct.Owner.html#method.tx_lock_outputs) method).
+	/// If the exchange method is intended to be synchronous (such as via a direct http call,)
+	/// then the lock call can wait until the response is confirmed. If it is asynchronous, (such
+	/// as via file transfer,) the lock call should happen immediately (before the file is sent
+	/// to the recipient).
+	///
+	/// If the `send_args` [`InitTxSendArgs`](../grin_wallet_libwallet/types/struct.InitTxSendArgs.html),
+	/// of the [`args`](../grin_wallet_libwallet/types/struct.InitTxArgs.html), field is Some, this
+	/// function will attempt to perform a synchronous send to the recipient specified in the `dest`
+	/// field according to the `method` field, and will also finalize and post the transaction if
+	/// the `finalize` field is set.
+	///
+	/// Several recipients are paid with [`init_send_tx_multi`](struct.Owner.html#method.init_send_tx_multi)
+	/// instead, and args with `additional_recipients` populated are refused here.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `args` - [`InitTxArgs`](../grin_wallet_libwallet/types/struct.InitTxArgs.html),
+	/// transaction initialization arguments. See struct documentation for further detail.
+	///
+	/// # Returns
+	/// * a result containing:
+	/// * The transaction [Slate](../grin_wallet_libwallet/slate/struct.Slate.html),
+	/// which can be forwarded to the recieving party by any means. Once the caller is relatively
+	/// certain that the transaction has been sent to the recipient, the associated wallet
+	/// transaction outputs should be locked via a call to
+	/// [`tx_lock_outputs`](struct.Owner.html#method.tx_lock_outputs). This must be called before calling
+	/// [`finalize_tx`](struct.Owner.html#method.finalize_tx).
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Remarks
 	///
 	/// * This method requires an active connection to a node, and will fail with error if a node
 	/// cannot be contacted to refresh output statuses.
@@ -520,93 +1606,2444 @@ where
 	/// which will be updated on the corresponding call to [`finalize_tx`](struct.Owner.html#method.finalize_tx).
 	///
 	/// # Example
-	/// Set up as in [new](struct.Owner.html#method.new) method above.
+	/// Set up as in [new](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// // Attempt to create a transaction using the 'default' account
+	/// let args = InitTxArgs {
+	/// 	src_acct_name: None,
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 2,
+	/// 	max_outputs: 500,
+	/// 	num_change_outputs: 1,
+	/// 	selection_strategy_is_use_all: true,
+	/// 	message: Some("Have some Grins. Love, Yeastplume".to_owned()),
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.init_send_tx(
+	/// 	None,
+	/// 	args,
+	/// );
+	///
+	/// if let Ok(slate) = result {
+	/// 	// Send slate somehow
+	/// 	// ...
+	/// 	// Lock our outputs if we're happy the slate was (or is being) sent
+	/// 	api_owner.tx_lock_outputs(None, &slate, 0);
+	/// }
+	/// ```
+
+	pub fn init_send_tx(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		args: InitTxArgs,
+	) -> Result<Slate, Error> {
+		let send_args = args.send_args.clone();
+		if args.estimate_only != Some(true) {
+			let mut w_lock = self.lock_wallet()?;
+			let w = w_lock.lc_provider()?.wallet_inst()?;
+			self.check_totp(&mut **w, keychain_mask)?;
+		}
+		if let Some(r) = args.additional_recipients.as_ref() {
+			if !r.is_empty() {
+				return Err(ErrorKind::GenericError(
+					"additional recipients are paid with init_send_tx_multi".to_owned(),
+				)
+				.into());
+			}
+		}
+		let mut slate = {
+			let mut w_lock = self.lock_wallet()?;
+			let w = w_lock.lc_provider()?.wallet_inst()?;
+			owner::init_send_tx(&mut **w, keychain_mask, args, self.doctest_mode)?
+		};
+		// Helper functionality. If send arguments exist, attempt to send
+		match send_args {
+			Some(sa) => {
+				//TODO: in case of keybase, the response might take 60s and leave the service hanging
+				if transport_for(&sa.method, &sa.dest).is_none() {
+					error!("unsupported payment method: {}", sa.method);
+					return Err(
+						ErrorKind::ClientCallback("unsupported payment method".to_owned()).into(),
+					);
+				}
+				let comm_adapter = create_sender(&sa.method, &sa.dest)
+					.map_err(|e| ErrorKind::GenericError(format!("{}", e)))?;
+				slate = comm_adapter.send_tx(&slate)?;
+				self.tx_lock_outputs(keychain_mask, &slate, 0)?;
+				let slate = match sa.finalize {
+					true => self.finalize_tx(keychain_mask, &slate, None)?,
+					false => slate,
+				};
+
+				if sa.post_tx {
+					// otherwise follow the preference stored with the transaction
+					let fluff = match sa.fluff {
+						true => Some(true),
+						false => None,
+					};
+					self.post_tx(keychain_mask, &slate.tx, fluff)?;
+				}
+				Ok(slate)
+			}
+			None => Ok(slate),
+		}
+	}
+
+	/// Sends to several recipients, the one given by `amount` and `send_args.dest` of the
+	/// [`InitTxArgs`](../grin_wallet_libwallet/types/struct.InitTxArgs.html) and those in its
+	/// `additional_recipients` field, all with the `method` of its `send_args`.
+	///
+	/// Each recipient completes a receive round of its own, in order, its transaction built as
+	/// by [`init_send_tx`](struct.Owner.html#method.init_send_tx). Outputs are locked after each
+	/// round so subsequent rounds select from the remaining outputs. Once every round has been
+	/// finalized, the resulting transactions are aggregated and, if `send_args.post_tx` is set,
+	/// posted as a single transaction.
+	///
+	/// The recipients, their destinations and the funds for all of them, with the fee of each
+	/// round, are checked before the first round. If a round fails anyway, or the transaction
+	/// can't be posted, the transactions of the rounds already completed are cancelled, unlocking
+	/// their inputs, so nothing is sent to any recipient.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `args` - [`InitTxArgs`](../grin_wallet_libwallet/types/struct.InitTxArgs.html),
+	/// transaction initialization arguments, whose `send_args` must be set with `finalize`.
+	///
+	/// # Returns
+	/// * Ok(Vec<[`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html)>) of the finalized
+	/// slate of each recipient, in order
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	///
+	/// let args = InitTxArgs {
+	/// 	src_acct_name: None,
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 2,
+	/// 	max_outputs: 500,
+	/// 	num_change_outputs: 1,
+	/// 	selection_strategy_is_use_all: false,
+	/// 	send_args: Some(InitTxSendArgs {
+	/// 		method: "http".to_owned(),
+	/// 		dest: "http://localhost:3415".to_owned(),
+	/// 		finalize: true,
+	/// 		post_tx: true,
+	/// 		fluff: false,
+	/// 	}),
+	/// 	additional_recipients: Some(vec![InitTxRecipient {
+	/// 		amount: 1_000_000_000,
+	/// 		dest: "http://localhost:3416".to_owned(),
+	/// 	}]),
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.init_send_tx_multi(None, args);
+	///
+	/// if let Ok(slates) = result {
+	///		println!("Paid {} recipients", slates.len());
+	/// }
+	/// ```
+
+	pub fn init_send_tx_multi(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		args: InitTxArgs,
+	) -> Result<Vec<Slate>, Error> {
+		{
+			let mut w_lock = self.lock_wallet()?;
+			let w = w_lock.lc_provider()?.wallet_inst()?;
+			self.check_totp(&mut **w, keychain_mask)?;
+		}
+		let sa = match args.send_args.clone() {
+			Some(sa) => sa,
+			None => {
+				return Err(ErrorKind::GenericError(
+					"additional recipients require send arguments".to_owned(),
+				)
+				.into());
+			}
+		};
+		if transport_for(&sa.method, &sa.dest).is_none() {
+			error!("unsupported payment method: {}", sa.method);
+			return Err(ErrorKind::ClientCallback("unsupported payment method".to_owned()).into());
+		}
+		if !sa.finalize {
+			return Err(ErrorKind::GenericError(
+				"additional recipients require the transaction to be finalized".to_owned(),
+			)
+			.into());
+		}
+		let mut recipients = vec![InitTxRecipient {
+			amount: args.amount,
+			dest: sa.dest.clone(),
+		}];
+		recipients.extend(args.additional_recipients.clone().unwrap_or_default());
+
+		// check all that can be before any recipient is sent a slate
+		let mut total: u64 = 0;
+		let mut rounds = vec![];
+		for r in recipients {
+			if r.amount == 0 {
+				return Err(
+					ErrorKind::GenericError(format!("nothing to send to {}", r.dest)).into(),
+				);
+			}
+			total = total.checked_add(r.amount).ok_or_else(|| {
+				ErrorKind::GenericError("total amount sent is too large".to_owned())
+			})?;
+			let comm_adapter = create_sender(&sa.method, &r.dest)
+				.map_err(|e| ErrorKind::GenericError(format!("{}", e)))?;
+			rounds.push((r, comm_adapter));
+		}
+		{
+			let mut w_lock = self.lock_wallet()?;
+			let w = w_lock.lc_provider()?.wallet_inst()?;
+			for (r, _) in rounds.iter() {
+				w.spending_policy().check_destination(Some(&r.dest))?;
+			}
+			let amounts: Vec<u64> = rounds.iter().map(|(r, _)| r.amount).collect();
+			owner::estimate_send_rounds(&mut **w, keychain_mask, &args, &amounts)?;
+		}
+
+		let mut locked = vec![];
+		let res = self
+			.send_tx_rounds(keychain_mask, &args, rounds, &mut locked)
+			.and_then(|slates| {
+				if sa.post_tx {
+					let txs = slates.iter().map(|s| s.tx.clone()).collect();
+					let tx = transaction::aggregate(txs)?;
+					let fluff = match sa.fluff {
+						true => Some(true),
+						false => None,
+					};
+					self.post_tx(keychain_mask, &tx, fluff)?;
+				}
+				Ok(slates)
+			});
+		match res {
+			Ok(slates) => Ok(slates),
+			Err(e) => {
+				for id in locked {
+					if let Err(c) = self.cancel_tx(keychain_mask, None, Some(id)) {
+						error!("Unable to cancel multi-recipient send round {}: {}", id, c);
+					}
+				}
+				Err(e)
+			}
+		}
+	}
+
+	/// Completes the rounds of a multi-recipient send in order, adding the slate id of each
+	/// round to `locked` once its outputs are locked
+	fn send_tx_rounds(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		args: &InitTxArgs,
+		rounds: Vec<(InitTxRecipient, Box<dyn SlateSender>)>,
+		locked: &mut Vec<Uuid>,
+	) -> Result<Vec<Slate>, Error> {
+		let mut slates = vec![];
+		for (r, comm_adapter) in rounds {
+			let round_args = InitTxArgs {
+				amount: r.amount,
+				send_args: None,
+				additional_recipients: None,
+				..args.clone()
+			};
+			let slate = {
+				let mut w_lock = self.lock_wallet()?;
+				let w = w_lock.lc_provider()?.wallet_inst()?;
+				owner::init_send_tx(&mut **w, keychain_mask, round_args, self.doctest_mode)?
+			};
+			let slate = comm_adapter.send_tx(&slate)?;
+			self.tx_lock_outputs(keychain_mask, &slate, 0)?;
+			locked.push(slate.id);
+			let slate = self.finalize_tx(keychain_mask, &slate, None)?;
+			debug!(
+				"Multi-recipient send: round {} complete for {}",
+				slates.len() + 1,
+				r.dest
+			);
+			slates.push(slate);
+		}
+		Ok(slates)
+	}
+
+	/// Combines many small outputs into a few larger ones, by spending them to the wallet itself.
+	/// The smallest spendable outputs of the account are combined first, into `num_outputs`
+	/// outputs of about equal value per transaction. Each transaction combines as many outputs as
+	/// fit within `max_weight`, and outputs left over are combined in further transactions, up to
+	/// `max_txs` of them. As the wallet is the only party, each transaction is finalized straight
+	/// away, and its outputs locked as for a send. Transactions that wouldn't reduce the number of
+	/// outputs aren't created.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `args` - [`ConsolidateArgs`](../grin_wallet_libwallet/types/struct.ConsolidateArgs.html),
+	/// consolidation arguments. See struct documentation for further detail.
+	///
+	/// # Returns
+	/// * Ok(Vec<[`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html)>) of the finalized
+	/// transactions, posted if `post_tx` was set, which is empty if there was nothing to
+	/// consolidate
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	/// Transactions created before posting failed are left locked, to be reposted or cancelled.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	///
+	/// let args = ConsolidateArgs {
+	/// 	num_outputs: 2,
+	/// 	max_txs: 5,
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.consolidate(None, args);
+	///
+	/// if let Ok(slates) = result {
+	///		println!("Consolidated in {} transactions", slates.len());
+	/// }
+	/// ```
+
+	pub fn consolidate(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		args: ConsolidateArgs,
+	) -> Result<Vec<Slate>, Error> {
+		let post_tx = args.post_tx;
+		let fluff = args.fluff;
+		let slates = {
+			let mut w_lock = self.lock_wallet()?;
+			let w = w_lock.lc_provider()?.wallet_inst()?;
+			owner::consolidate(&mut **w, keychain_mask, args, self.doctest_mode)?
+		};
+		if post_tx {
+			for slate in slates.iter() {
+				self.post_tx(keychain_mask, &slate.tx, Some(fluff))?;
+			}
+		}
+		Ok(slates)
+	}
+
+	/// Issues a new invoice transaction slate, essentially a `request for payment`.
+	/// The slate created by this function will contain the amount, an output for the amount,
+	/// as well as round 1 of singature creation complete. The slate should then be send
+	/// to the payer, who should add their inputs and signature data and return the slate
+	/// via the [Foreign API's `finalize_invoice_tx`](struct.Foreign.html#method.finalize_invoice_tx) method.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `args` - [`IssueInvoiceTxArgs`](../grin_wallet_libwallet/types/struct.IssueInvoiceTxArgs.html),
+	/// invoice transaction initialization arguments. See struct documentation for further detail.
+	///
+	/// # Returns
+	/// * ``Ok([`slate`](../grin_wallet_libwallet/slate/struct.Slate.html))` if successful,
+	/// containing the updated slate.
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	///
+	/// let args = IssueInvoiceTxArgs {
+	/// 	amount: 60_000_000_000,
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.issue_invoice_tx(None, args);
+	///
+	/// if let Ok(slate) = result {
+	///		// if okay, send to the payer to add their inputs
+	///		// . . .
+	/// }
+	/// ```
+	pub fn issue_invoice_tx(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		args: IssueInvoiceTxArgs,
+	) -> Result<Slate, Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::issue_invoice_tx(&mut **w, keychain_mask, args, self.doctest_mode)
+	}
+
+	/// Processes an invoice tranaction created by another party, essentially
+	/// a `request for payment`. The incoming slate should contain a requested
+	/// amount, an output created by the invoicer convering the amount, and
+	/// part 1 of signature creation completed. This function will add inputs
+	/// equalling the amount + fees, as well as perform round 1 and 2 of signature
+	/// creation.
+	///
+	/// Callers should note that no prompting of the user will be done by this function
+	/// it is up to the caller to present the request for payment to the user
+	/// and verify that payment should go ahead.
+	///
+	/// This function also stores the final transaction in the user's wallet files for retrieval
+	/// via the [`get_stored_tx`](struct.Owner.html#method.get_stored_tx) function.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `slate` - The transaction [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html). The
+	/// payer should have filled in round 1 and 2.
+	/// * `args` - [`InitTxArgs`](../grin_wallet_libwallet/types/struct.InitTxArgs.html),
+	/// transaction initialization arguments. See struct documentation for further detail.
+	///
+	/// # Returns
+	/// * ``Ok([`slate`](../grin_wallet_libwallet/slate/struct.Slate.html))` if successful,
+	/// containing the updated slate.
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	///
+	/// // . . .
+	/// // The slate has been recieved from the invoicer, somehow
+	/// # let slate = Slate::blank(2);
+	/// let args = InitTxArgs {
+	///		src_acct_name: None,
+	///		amount: slate.amount,
+	///		minimum_confirmations: 2,
+	///		max_outputs: 500,
+	///		num_change_outputs: 1,
+	///		selection_strategy_is_use_all: true,
+	///		..Default::default()
+	///	};
+	///
+	/// let result = api_owner.process_invoice_tx(None, &slate, args);
+	///
+	/// if let Ok(slate) = result {
+	///	// If result okay, send back to the invoicer
+	///	// . . .
+	///	}
+	/// ```
+
+	pub fn process_invoice_tx(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		slate: &Slate,
+		args: InitTxArgs,
+	) -> Result<Slate, Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		self.check_totp(&mut **w, keychain_mask)?;
+		owner::process_invoice_tx(&mut **w, keychain_mask, slate, args, self.doctest_mode)
+	}
+
+	/// Locks the outputs associated with the inputs to the transaction in the given
+	/// [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html),
+	/// making them unavailable for use in further transactions. This function is called
+	/// by the sender, (or more generally, all parties who have put inputs into the transaction,)
+	/// and must be called before the corresponding call to [`finalize_tx`](struct.Owner.html#method.finalize_tx)
+	/// that completes the transaction.
+	///
+	/// Outputs will generally remain locked until they are removed from the chain,
+	/// at which point they will become `Spent`. It is commonplace for transactions not to complete
+	/// for various reasons over which a particular wallet has no control. For this reason,
+	/// [`cancel_tx`](struct.Owner.html#method.cancel_tx) can be used to manually unlock outputs
+	/// and return them to the `Unspent` state.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `slate` - The transaction [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html). All
+	/// * `participant_id` - The participant id, generally 0 for the party putting in funds, 1 for the
+	/// party receiving.
+	/// elements in the `input` vector of the `tx` field that are found in the wallet's currently
+	/// active account will be set to status `Locked`
+	///
+	/// # Returns
+	/// * Ok(()) if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let args = InitTxArgs {
+	/// 	src_acct_name: None,
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 10,
+	/// 	max_outputs: 500,
+	/// 	num_change_outputs: 1,
+	/// 	selection_strategy_is_use_all: true,
+	/// 	message: Some("Remember to lock this when we're happy this is sent".to_owned()),
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.init_send_tx(
+	/// 	None,
+	/// 	args,
+	/// );
+	///
+	/// if let Ok(slate) = result {
+	///		// Send slate somehow
+	///		// ...
+	///		// Lock our outputs if we're happy the slate was (or is being) sent
+	///		api_owner.tx_lock_outputs(None, &slate, 0);
+	/// }
+	/// ```
+
+	pub fn tx_lock_outputs(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		slate: &Slate,
+		participant_id: usize,
+	) -> Result<(), Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::tx_lock_outputs(&mut **w, keychain_mask, slate, participant_id)?;
+		if let Some(e) = self.email.as_ref() {
+			e.tx_sent(slate.amount, Some(slate.id));
+		}
+		Ok(())
+	}
+
+	/// Finalizes a transaction, after all parties
+	/// have filled in both rounds of Slate generation. This step adds
+	/// all participants partial signatures to create the final signature,
+	/// resulting in a final transaction that is ready to post to a node.
+	///
+	/// Note that this function DOES NOT POST the transaction to a node
+	/// for validation. This is done in separately via the
+	/// [`post_tx`](struct.Owner.html#method.post_tx) function.
+	///
+	/// This function also stores the final transaction in the user's wallet files for retrieval
+	/// via the [`get_stored_tx`](struct.Owner.html#method.get_stored_tx) function.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `slate` - The transaction [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html). All
+	/// participants must have filled in both rounds, and the sender should have locked their
+	/// outputs (via the [`tx_lock_outputs`](struct.Owner.html#method.tx_lock_outputs) function).
+	/// * `fluff` - If set, replaces the Dandelion preference stored with the transaction, which
+	/// [`post_tx`](struct.Owner.html#method.post_tx) follows unless told otherwise. If `None`, the
+	/// preference given by the `fluff` field of
+	/// [`InitTxArgs`](../grin_wallet_libwallet/types/struct.InitTxArgs.html) is kept.
+	///
+	/// # Returns
+	/// * ``Ok([`slate`](../grin_wallet_libwallet/slate/struct.Slate.html))` if successful,
+	/// containing the new finalized slate.
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let args = InitTxArgs {
+	/// 	src_acct_name: None,
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 10,
+	/// 	max_outputs: 500,
+	/// 	num_change_outputs: 1,
+	/// 	selection_strategy_is_use_all: true,
+	/// 	message: Some("Finalize this tx now".to_owned()),
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.init_send_tx(
+	/// 	None,
+	/// 	args,
+	/// );
+	///
+	/// if let Ok(slate) = result {
+	///		// Send slate somehow
+	///		// ...
+	///		// Lock our outputs if we're happy the slate was (or is being) sent
+	///		let res = api_owner.tx_lock_outputs(None, &slate, 0);
+	///		//
+	///		// Retrieve slate back from recipient
+	///		//
+	///		let res = api_owner.finalize_tx(None, &slate, None);
+	/// }
+	/// ```
+
+	pub fn finalize_tx(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		slate: &Slate,
+		fluff: Option<bool>,
+	) -> Result<Slate, Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		self.check_totp(&mut **w, keychain_mask)?;
+		owner::finalize_tx(&mut **w, keychain_mask, &slate, fluff)
+	}
+
+	/// Prepares a send for a wallet whose seed is kept offline. Called on a watch-only copy of
+	/// the wallet, opened without its seed, which selects the inputs as
+	/// [`init_send_tx`](struct.Owner.html#method.init_send_tx) would, locks them and sets
+	/// aside keys for the change. The result is handed to the wallet holding the seed, which
+	/// signs it with [`sign_offline`](struct.Owner.html#method.sign_offline) without
+	/// contacting a node.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `args` - [`InitTxArgs`](../grin_wallet_libwallet/types/struct.InitTxArgs.html),
+	/// transaction initialization arguments. Estimation, late locking and sending are not
+	/// supported.
+	///
+	/// # Returns
+	/// * Ok([`UnsignedTx`](../grin_wallet_libwallet/types/struct.UnsignedTx.html)) if successful,
+	/// holding the slate, inputs and change to build
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let args = InitTxArgs {
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 10,
+	/// 	num_change_outputs: 1,
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.prepare_send(None, args);
+	///
+	/// if let Ok(unsigned) = result {
+	///		// Save to a file and carry to the offline wallet
+	/// }
+	/// ```
+
+	pub fn prepare_send(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		args: InitTxArgs,
+	) -> Result<UnsignedTx, Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::prepare_send(&mut **w, keychain_mask, args, self.doctest_mode)
+	}
+
+	/// Signs a send prepared by [`prepare_send`](struct.Owner.html#method.prepare_send) on a
+	/// watch-only copy of the wallet. Called on the wallet holding the seed, which needn't be
+	/// connected to a node. The inputs and change are built and round 1 of signing completed,
+	/// and the transaction's context kept for when the recipient returns the slate to
+	/// [`finalize_offline`](struct.Owner.html#method.finalize_offline). The inputs must be
+	/// outputs of this wallet's seed. The wallet's own outputs are left as they are, the
+	/// watch-only copy keeping track of them.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `unsigned` - The [`UnsignedTx`](../grin_wallet_libwallet/types/struct.UnsignedTx.html)
+	/// returned by `prepare_send`.
+	///
+	/// # Returns
+	/// * Ok([`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html)) if successful, to send
+	/// to the recipient
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let args = InitTxArgs {
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 10,
+	/// 	num_change_outputs: 1,
+	/// 	..Default::default()
+	/// };
+	/// // on the watch-only wallet
+	/// let result = api_owner.prepare_send(None, args);
+	///
+	/// if let Ok(unsigned) = result {
+	///		// on the wallet holding the seed
+	///		let slate = api_owner.sign_offline(None, &unsigned);
+	/// }
+	/// ```
+
+	pub fn sign_offline(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		unsigned: &UnsignedTx,
+	) -> Result<Slate, Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::sign_offline(&mut **w, keychain_mask, unsigned, self.doctest_mode)
+	}
+
+	/// Finalizes a send signed offline by [`sign_offline`](struct.Owner.html#method.sign_offline),
+	/// once the recipient has returned the slate. Called on the wallet holding the seed. The
+	/// result is handed back to the watch-only copy of the wallet, which records the change and
+	/// posts the transaction with [`broadcast_signed`](struct.Owner.html#method.broadcast_signed).
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `slate` - The [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html) returned by
+	/// the recipient.
+	///
+	/// # Returns
+	/// * Ok([`SignedTx`](../grin_wallet_libwallet/types/struct.SignedTx.html)) if successful,
+	/// holding the finalized slate and the commitments of its change
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let args = InitTxArgs {
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 10,
+	/// 	num_change_outputs: 1,
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.prepare_send(None, args);
+	///
+	/// if let Ok(unsigned) = result {
+	///		if let Ok(slate) = api_owner.sign_offline(None, &unsigned) {
+	///			// Send slate somehow, and retrieve it back from the recipient
+	///			let signed = api_owner.finalize_offline(None, &slate);
+	///		}
+	/// }
+	/// ```
+
+	pub fn finalize_offline(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		slate: &Slate,
+	) -> Result<SignedTx, Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::finalize_offline(&mut **w, keychain_mask, slate)
+	}
+
+	/// Records the change of a send finalized offline by
+	/// [`finalize_offline`](struct.Owner.html#method.finalize_offline) and posts the
+	/// transaction. Called on the watch-only copy of the wallet that prepared the send, which
+	/// saves the change outputs as unconfirmed against the prepared transaction and stores the
+	/// finalized transaction. A transaction already recorded is only posted again.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `signed` - The [`SignedTx`](../grin_wallet_libwallet/types/struct.SignedTx.html)
+	/// returned by `finalize_offline`.
+	/// * `fluff` - Whether the node should skip the Dandelion stem phase when posting.
+	///
+	/// # Returns
+	/// * `Ok(())` if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// such as `TransactionDoesntExist` if no send was prepared for the slate.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let args = InitTxArgs {
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 10,
+	/// 	num_change_outputs: 1,
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.prepare_send(None, args);
+	///
+	/// if let Ok(unsigned) = result {
+	///		// carried to the wallet holding the seed and back
+	///		if let Ok(slate) = api_owner.sign_offline(None, &unsigned) {
+	///			if let Ok(signed) = api_owner.finalize_offline(None, &slate) {
+	///				let res = api_owner.broadcast_signed(None, &signed, false);
+	///			}
+	///		}
+	/// }
+	/// ```
+
+	pub fn broadcast_signed(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		signed: &SignedTx,
+		fluff: bool,
+	) -> Result<(), Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		self.check_totp(&mut **w, keychain_mask)?;
+		owner::broadcast_signed(&mut **w, keychain_mask, signed, fluff)
+	}
+
+	/// Posts a completed transaction to the listening node for validation and inclusion in a block
+	/// for mining.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `tx` - A completed [`Transaction`](../grin_core/core/transaction/struct.Transaction.html),
+	/// typically the `tx` field in the transaction [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html).
+	/// * `fluff` - Instruct the node whether to use the Dandelion protocol when posting the
+	/// transaction. If `true`, the node should skip the Dandelion phase and broadcast the
+	/// transaction to all peers immediately. If `false`, the node will follow dandelion logic and
+	/// initiate the stem phase. If `None`, the preference stored with the wallet transaction
+	/// sharing a kernel with `tx` is followed, stemming if there is none.
+	///
+	/// # Returns
+	/// * `Ok(())` if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let args = InitTxArgs {
+	/// 	src_acct_name: None,
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 10,
+	/// 	max_outputs: 500,
+	/// 	num_change_outputs: 1,
+	/// 	selection_strategy_is_use_all: true,
+	/// 	message: Some("Post this tx".to_owned()),
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.init_send_tx(
+	/// 	None,
+	/// 	args,
+	/// );
+	///
+	/// if let Ok(slate) = result {
+	///		// Send slate somehow
+	///		// ...
+	///		// Lock our outputs if we're happy the slate was (or is being) sent
+	///		let res = api_owner.tx_lock_outputs(None, &slate, 0);
+	///		//
+	///		// Retrieve slate back from recipient
+	///		//
+	///		let res = api_owner.finalize_tx(None, &slate, None);
+	///		let res = api_owner.post_tx(None, &slate.tx, Some(true));
+	/// }
+	/// ```
+
+	pub fn post_tx(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		tx: &Transaction,
+		fluff: Option<bool>,
+	) -> Result<(), Error> {
+		let (client, fluff) = {
+			let mut w_lock = self.lock_wallet()?;
+			let w = w_lock.lc_provider()?.wallet_inst()?;
+			self.check_totp(&mut **w, keychain_mask)?;
+			// Test keychain mask, to keep API consistent
+			let _ = w.keychain(keychain_mask)?;
+			let fluff = match fluff {
+				Some(f) => f,
+				None => owner::stored_fluff(&mut **w, tx)?.unwrap_or(false),
+			};
+			(w.w2n_client().clone(), fluff)
+		};
+		owner::post_tx(&client, tx, fluff)
+	}
+
+	/// Posts a completed transaction to the listening node, as
+	/// [`post_tx`](struct.Owner.html#method.post_tx) does, after checking it for conflicting
+	/// spends (see [`check_tx_conflicts`](struct.Owner.html#method.check_tx_conflicts)). The
+	/// transaction isn't posted if any of its inputs are already spent on chain, or, unless
+	/// `replace` is set, if any are also spent by another unconfirmed transaction of this wallet.
+	///
+	/// When replacing, those transactions are marked as cancelled, but their inputs shared with
+	/// `tx` stay locked and their change outputs are kept, so the wallet stays consistent
+	/// whichever of the transactions ends up confirmed.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `tx` - A completed [`Transaction`](../grin_core/core/transaction/struct.Transaction.html),
+	/// typically the `tx` field in the transaction [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html).
+	/// * `fluff` - Whether to bypass the Dandelion stem phase, as for
+	/// [`post_tx`](struct.Owner.html#method.post_tx).
+	/// * `replace` - Whether to post `tx` in place of other wallet transactions it conflicts with.
+	///
+	/// # Returns
+	/// * `Ok(())` if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// including a conflict.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let args = InitTxArgs {
+	/// 	src_acct_name: None,
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 10,
+	/// 	max_outputs: 500,
+	/// 	num_change_outputs: 1,
+	/// 	selection_strategy_is_use_all: true,
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.init_send_tx(
+	/// 	None,
+	/// 	args,
+	/// );
+	///
+	/// if let Ok(slate) = result {
+	///		// Send slate somehow
+	///		// ...
+	///		let res = api_owner.tx_lock_outputs(None, &slate, 0);
+	///		let res = api_owner.finalize_tx(None, &slate, None);
+	///		let res = api_owner.post_tx_checked(None, &slate.tx, Some(true), false);
+	/// }
+	/// ```
+
+	pub fn post_tx_checked(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		tx: &Transaction,
+		fluff: Option<bool>,
+		replace: bool,
+	) -> Result<(), Error> {
+		let (client, fluff) = {
+			let mut w_lock = self.lock_wallet()?;
+			let w = w_lock.lc_provider()?.wallet_inst()?;
+			self.check_totp(&mut **w, keychain_mask)?;
+			// Test keychain mask, to keep API consistent
+			let _ = w.keychain(keychain_mask)?;
+			let fluff = match fluff {
+				Some(f) => f,
+				None => owner::stored_fluff(&mut **w, tx)?.unwrap_or(false),
+			};
+			owner::check_post_tx(&mut **w, keychain_mask, tx, replace)?;
+			(w.w2n_client().clone(), fluff)
+		};
+		owner::post_tx(&client, tx, fluff)
+	}
+
+	/// Checks a completed transaction for conflicting spends before it is posted. Each input is
+	/// checked against the node's UTXO set, and against any other sent transactions created by
+	/// this wallet that are not yet confirmed (and so may already be in the node's mempool).
+	///
+	/// It is up to the caller to decide what to do with any conflicts reported, i.e. whether to
+	/// abort, or to post this transaction in place of the conflicting wallet transactions via
+	/// [`post_tx_checked`](struct.Owner.html#method.post_tx_checked).
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `tx` - A completed [`Transaction`](../grin_core/core/transaction/struct.Transaction.html),
+	/// typically the `tx` field in the transaction [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html).
+	///
+	/// # Returns
+	/// * Ok with a (possibly empty) vector of [`TxConflict`](../grin_wallet_libwallet/types/enum.TxConflict.html)
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let args = InitTxArgs {
+	/// 	src_acct_name: None,
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 10,
+	/// 	max_outputs: 500,
+	/// 	num_change_outputs: 1,
+	/// 	selection_strategy_is_use_all: true,
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.init_send_tx(
+	/// 	None,
+	/// 	args,
+	/// );
+	///
+	/// if let Ok(slate) = result {
+	///		// Send slate somehow
+	///		// ...
+	///		let res = api_owner.tx_lock_outputs(None, &slate, 0);
+	///		let res = api_owner.finalize_tx(None, &slate, None);
+	///		if let Ok(conflicts) = api_owner.check_tx_conflicts(None, &slate.tx) {
+	///			if conflicts.is_empty() {
+	///				let res = api_owner.post_tx(None, &slate.tx, Some(true));
+	///			}
+	///		}
+	/// }
+	/// ```
+
+	pub fn check_tx_conflicts(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		tx: &Transaction,
+	) -> Result<Vec<TxConflict>, Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		owner::check_tx_conflicts(&mut **w, tx)
+	}
+
+	/// Cancels a transaction. This entails:
+	/// * Setting the transaction status to either `TxSentCancelled` or `TxReceivedCancelled`
+	/// * Deleting all change outputs or recipient outputs associated with the transaction
+	/// * Setting the status of all assocatied inputs from `Locked` to `Spent` so they can be
+	/// used in new transactions.
+	///
+	/// Transactions can be cancelled by transaction log id or slate id (call with either set to
+	/// Some, not both)
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `tx_id` - If present, cancel by the [`TxLogEntry`](../grin_wallet_libwallet/types/struct.TxLogEntry.html) id
+	/// for the transaction.
+	///
+	/// * `tx_slate_id` - If present, cancel by the Slate id.
+	///
+	/// # Returns
+	/// * `Ok(())` if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let args = InitTxArgs {
+	/// 	src_acct_name: None,
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 10,
+	/// 	max_outputs: 500,
+	/// 	num_change_outputs: 1,
+	/// 	selection_strategy_is_use_all: true,
+	/// 	message: Some("Cancel this tx".to_owned()),
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.init_send_tx(
+	/// 	None,
+	/// 	args,
+	/// );
+	///
+	/// if let Ok(slate) = result {
+	///		// Send slate somehow
+	///		// ...
+	///		// Lock our outputs if we're happy the slate was (or is being) sent
+	///		let res = api_owner.tx_lock_outputs(None, &slate, 0);
+	///		//
+	///		// We didn't get the slate back, or something else went wrong
+	///		//
+	///		let res = api_owner.cancel_tx(None, None, Some(slate.id.clone()));
+	/// }
+	/// ```
+
+	pub fn cancel_tx(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		tx_id: Option<u32>,
+		tx_slate_id: Option<Uuid>,
+	) -> Result<(), Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::cancel_tx(&mut **w, keychain_mask, tx_id, tx_slate_id)?;
+		self.notify_cancelled(&**w, tx_id, tx_slate_id);
+		Ok(())
+	}
+
+	/// Notify webhooks of a transaction in the active account being cancelled
+	fn notify_cancelled<T: ?Sized>(&self, w: &T, tx_id: Option<u32>, tx_slate_id: Option<Uuid>)
+	where
+		T: WalletBackend<'a, C, K>,
+	{
+		if let Some(h) = self.webhooks.as_ref() {
+			if h.wants(WebhookEventType::TxCancelled) {
+				let parent_key_id = w.parent_key_id();
+				let tx = w.tx_log_iter().into_iter().flatten().find(|t| {
+					t.parent_key_id == parent_key_id
+						&& (Some(t.id) == tx_id
+							|| (tx_slate_id.is_some() && t.tx_slate_id == tx_slate_id))
+				});
+				h.dispatch(
+					WebhookPayload::new(WebhookEventType::TxCancelled, tx_slate_id, tx)
+						.with_account(w.acct_path_iter().into_iter().flatten()),
+				);
+			}
+		}
+	}
+
+	/// Cancels many transactions in one call, such as when cleaning up after an incident. Each
+	/// transaction is cancelled as by [`cancel_tx`](struct.Owner.html#method.cancel_tx), on its
+	/// own: one that can't be cancelled is reported in the results, and doesn't stop the others
+	/// being cancelled.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `tx_ids` - If present, cancel the transactions with these
+	/// [`TxLogEntry`](../grin_wallet_libwallet/types/struct.TxLogEntry.html) ids.
+	/// * `filter` - If present, cancel the transactions in the active account matching this
+	/// [`RetrieveTxQueryArgs`](../grin_wallet_libwallet/types/struct.RetrieveTxQueryArgs.html)
+	/// query, as found after updating from the node. Exactly one of `tx_ids` and `filter` must
+	/// be given.
+	///
+	/// # Returns
+	/// * Ok with a [`TxBatchResult`](../grin_wallet_libwallet/struct.TxBatchResult.html) for each
+	/// transaction, in order, giving the error it failed with, if any
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if the batch couldn't
+	/// be run at all, such as when the node can't be reached.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	///
+	/// // Cancel every unconfirmed transaction
+	/// let filter = RetrieveTxQueryArgs {
+	/// 	statuses: Some(vec![TxQueryStatus::Unconfirmed]),
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.cancel_txs(None, None, Some(filter));
+	///
+	/// if let Ok(results) = result {
+	///		for r in results.iter().filter(|r| r.error.is_some()) {
+	///			// Look into why the transaction couldn't be cancelled
+	///		}
+	/// }
+	/// ```
+
+	pub fn cancel_txs(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		tx_ids: Option<Vec<u32>>,
+		filter: Option<RetrieveTxQueryArgs>,
+	) -> Result<Vec<TxBatchResult>, Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		let results = owner::cancel_txs(&mut **w, keychain_mask, tx_ids, filter.as_ref())?;
+		for r in results.iter().filter(|r| r.error.is_none()) {
+			self.notify_cancelled(&**w, Some(r.tx_id), None);
+		}
+		Ok(results)
+	}
+
+	/// Reposts many stored transactions in one call, such as after the node lost its mempool.
+	/// Each transaction is posted on its own, as its stored
+	/// [`Transaction`](../grin_core/core/transaction/struct.Transaction.html) would be by
+	/// [`post_tx`](struct.Owner.html#method.post_tx): one that can't be posted is reported in
+	/// the results, and doesn't stop the others being posted.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `tx_ids` - The [`TxLogEntry`](../grin_wallet_libwallet/types/struct.TxLogEntry.html) ids
+	/// of the transactions in the active account to repost.
+	/// * `fluff` - If `Some(true)` or `Some(false)`, whether to bypass Dandelion relay for all of
+	/// them. If `None`, each follows the preference stored with it.
+	///
+	/// # Returns
+	/// * Ok with a [`TxBatchResult`](../grin_wallet_libwallet/struct.TxBatchResult.html) for each
+	/// transaction, in order, giving the error it failed with, if any. Transactions that are
+	/// confirmed, or have no stored transaction, fail
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.post_txs(None, vec![5, 6, 7], None);
+	///
+	/// if let Ok(results) = result {
+	///		for r in results.iter().filter(|r| r.error.is_some()) {
+	///			// Look into why the transaction couldn't be posted
+	///		}
+	/// }
+	/// ```
+
+	pub fn post_txs(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		tx_ids: Vec<u32>,
+		fluff: Option<bool>,
+	) -> Result<Vec<TxBatchResult>, Error> {
+		let txs: HashMap<u32, TxLogEntry> = {
+			let mut w_lock = self.lock_wallet()?;
+			let w = w_lock.lc_provider()?.wallet_inst()?;
+			// Test keychain mask, to keep API consistent
+			let _ = w.keychain(keychain_mask)?;
+			let parent_key_id = w.parent_key_id();
+			w.tx_log_iter()?
+				.filter(|t| t.parent_key_id == parent_key_id)
+				.map(|t| (t.id, t))
+				.collect()
+		};
+		let results = tx_ids
+			.into_iter()
+			.map(|id| {
+				let res = match txs.get(&id) {
+					Some(t) if t.confirmed => Err(ErrorKind::TransactionAlreadyConfirmed.into()),
+					Some(t) => match self.get_stored_tx(keychain_mask, t) {
+						Ok(Some(stored)) => self.post_tx(keychain_mask, &stored, fluff.or(t.fluff)),
+						Ok(None) => Err(ErrorKind::GenericError(format!(
+							"Transaction {} has no stored transaction to repost",
+							id
+						))
+						.into()),
+						Err(e) => Err(e),
+					},
+					None => Err(ErrorKind::TransactionDoesntExist(id.to_string()).into()),
+				};
+				TxBatchResult::new(id, res)
+			})
+			.collect();
+		Ok(results)
+	}
+
+	/// Sets the label, or memo, of a transaction in the active account, to note what the
+	/// transaction was for. The label is kept in the wallet only, and returned as part of the
+	/// [`TxLogEntry`](../grin_wallet_libwallet/types/struct.TxLogEntry.html) by
+	/// [`retrieve_txs`](struct.Owner.html#method.retrieve_txs).
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `tx_id` - The [`TxLogEntry`](../grin_wallet_libwallet/types/struct.TxLogEntry.html) id
+	/// of the transaction.
+	/// * `label` - The label, of up to 256 characters, replacing any existing one. `None` or an
+	/// empty label clears it.
+	///
+	/// # Returns
+	/// * `Ok(())` if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is
+	/// encountered, including if there's no such transaction in the active account.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.set_tx_label(None, 1, Some("Rent for March".to_owned()));
+	///
+	/// if let Ok(_) = result {
+	///		// Label will be shown alongside the transaction
+	/// }
+	/// ```
+
+	pub fn set_tx_label(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		tx_id: u32,
+		label: Option<String>,
+	) -> Result<(), Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::set_tx_label(&mut **w, keychain_mask, tx_id, label)
+	}
+
+	/// Retrieves the stored transaction associated with a TxLogEntry. Can be used even after the
+	/// transaction has completed.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `tx_log_entry` - A [`TxLogEntry`](../grin_wallet_libwallet/types/struct.TxLogEntry.html)
+	///
+	/// # Returns
+	/// * Ok with the stored  [`Transaction`](../grin_core/core/transaction/struct.Transaction.html)
+	/// if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let update_from_node = true;
+	/// let tx_id = None;
+	/// let tx_slate_id = None;
+	///
+	/// // Return all TxLogEntries
+	/// let result = api_owner.retrieve_txs(None, update_from_node, tx_id, tx_slate_id, None);
+	///
+	/// if let Ok((was_updated, tx_log_entries)) = result {
+	///		let stored_tx = api_owner.get_stored_tx(None, &tx_log_entries[0]).unwrap();
+	///		//...
+	/// }
+	/// ```
+
+	// TODO: Should be accepting an id, not an entire entry struct
+	pub fn get_stored_tx(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		tx_log_entry: &TxLogEntry,
+	) -> Result<Option<Transaction>, Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		owner::get_stored_tx(&**w, tx_log_entry)
+	}
+
+	/// Replays a transaction in the active account against the running wallet, to investigate
+	/// transactions that worked, or failed, with another version. The wallet stores the slate as
+	/// it stands after each step of a transaction it takes part in. Each stored slate is
+	/// checked to deserialize and serialize back to what was stored, to carry valid signatures,
+	/// and to follow from the step before it, and the finalized slate is finalized again, which
+	/// must give the recorded kernel and transaction. The replay works on copies, with a
+	/// throwaway keychain, and doesn't change the wallet.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `tx_id` - If present, replay by the [`TxLogEntry`](../grin_wallet_libwallet/types/struct.TxLogEntry.html) id
+	/// for the transaction.
+	/// * `tx_slate_id` - If present, replay by the Slate id.
+	///
+	/// # Returns
+	/// * Ok with a [`TxReplayReport`](../grin_wallet_libwallet/struct.TxReplayReport.html) giving
+	/// what wasn't reproduced at each step, and the wallet version which took it
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is
+	/// encountered, including if the transaction has no slate.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.replay_tx(None, Some(1), None);
+	///
+	/// if let Ok(report) = result {
+	///		for step in report.steps {
+	///			println!("{:?}: {:?}", step.step, step.failures);
+	///		}
+	/// }
+	/// ```
+
+	pub fn replay_tx(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		tx_id: Option<u32>,
+		tx_slate_id: Option<Uuid>,
+	) -> Result<TxReplayReport, Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::replay_tx(&mut **w, keychain_mask, tx_id, tx_slate_id)
+	}
+
+	/// Verifies all messages in the slate match their public keys.
+	///
+	/// The optional messages themselves are part of the `participant_data` field within the slate.
+	/// Messages are signed with the same key used to sign for the paricipant's inputs, and can thus be
+	/// verified with the public key found in the `public_blind_excess` field. This function is a
+	/// simple helper to returns whether all signatures in the participant data match their public
+	/// keys.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `slate` - The transaction [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html).
+	///
+	/// # Returns
+	/// * `Ok(())` if successful and the signatures validate
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let args = InitTxArgs {
+	/// 	src_acct_name: None,
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 10,
+	/// 	max_outputs: 500,
+	/// 	num_change_outputs: 1,
+	/// 	selection_strategy_is_use_all: true,
+	/// 	message: Some("Just verify messages".to_owned()),
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.init_send_tx(
+	/// 	None,
+	/// 	args,
+	/// );
+	///
+	/// if let Ok(slate) = result {
+	///		// Send slate somehow
+	///		// ...
+	///		// Lock our outputs if we're happy the slate was (or is being) sent
+	///		let res = api_owner.tx_lock_outputs(None, &slate, 0);
+	///		//
+	///		// Retrieve slate back from recipient
+	///		//
+	///		let res = api_owner.verify_slate_messages(None, &slate);
+	/// }
+	/// ```
+	pub fn verify_slate_messages(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		slate: &Slate,
+	) -> Result<(), Error> {
+		{
+			let mut w_lock = self.lock_wallet()?;
+			let w = w_lock.lc_provider()?.wallet_inst()?;
+			// Test keychain mask, to keep API consistent
+			let _ = w.keychain(keychain_mask)?;
+		}
+		owner::verify_slate_messages(slate)
+	}
+
+	/// Runs full local consensus validation (weight, fee, kernel signatures, rangeproofs and
+	/// kernel sums) on a finalized transaction, and checks its inputs are unspent outputs known
+	/// to the listening node. Intended to be called before
+	/// [`post_tx`](struct.Owner.html#method.post_tx), so problems with a transaction are
+	/// reported with a specific reason rather than as an opaque error from the node.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `tx` - A completed [`Transaction`](../grin_core/core/transaction/struct.Transaction.html),
+	/// typically the `tx` field in the transaction [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html).
+	///
+	/// # Returns
+	/// * Ok with a [`TxValidationResult`](../grin_wallet_libwallet/types/struct.TxValidationResult.html)
+	/// listing any [`TxRejectionReason`](../grin_wallet_libwallet/types/enum.TxRejectionReason.html)s
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let args = InitTxArgs {
+	/// 	src_acct_name: None,
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 10,
+	/// 	max_outputs: 500,
+	/// 	num_change_outputs: 1,
+	/// 	selection_strategy_is_use_all: true,
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.init_send_tx(
+	/// 	None,
+	/// 	args,
+	/// );
+	///
+	/// if let Ok(slate) = result {
+	///		// Send slate somehow
+	///		// ...
+	///		// Lock our outputs if we're happy the slate was (or is being) sent
+	///		let res = api_owner.tx_lock_outputs(None, &slate, 0);
+	///		//
+	///		// Retrieve slate back from recipient
+	///		//
+	///		let res = api_owner.finalize_tx(None, &slate, None);
+	///		if let Ok(slate) = res {
+	///			let res = api_owner.validate_tx(None, &slate.tx);
+	///		}
+	/// }
+	/// ```
+	pub fn validate_tx(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		tx: &Transaction,
+	) -> Result<TxValidationResult, Error> {
+		let client = {
+			let mut w_lock = self.lock_wallet()?;
+			let w = w_lock.lc_provider()?.wallet_inst()?;
+			// Test keychain mask, to keep API consistent
+			let _ = w.keychain(keychain_mask)?;
+			w.w2n_client().clone()
+		};
+		owner::validate_tx(&client, tx)
+	}
+
+	/// Scans the entire UTXO set from the node, creating outputs for each scanned
+	/// output that matches the wallet's master seed. This function is intended to be called as part
+	/// of a recovery process (either from BIP32 phrase or backup seed files,) and will error if the
+	/// wallet is non-empty, i.e. contains any outputs at all.
+	///
+	/// This operation scans the entire chain, and is expected to be time intensive. It is imperative
+	/// that no other processes should be trying to use the wallet at the same time this function is
+	/// running.
+	///
+	/// A single [TxLogEntry](../grin_wallet_libwallet/types/struct.TxLogEntry.html) is created for
+	/// all non-coinbase outputs discovered and restored during this process. A separate entry
+	/// is created for each coinbase output.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * `Ok(())` if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.restore(None);
+	///
+	/// if let Ok(_) = result {
+	///		// Wallet outputs should be consistent with what's on chain
+	///		// ...
+	/// }
+	/// ```
+	pub fn restore(&self, keychain_mask: Option<&SecretKey>) -> Result<(), Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		let res = owner::restore(&mut **w, keychain_mask, None);
+		res
+	}
+
+	/// Scans the entire UTXO set from the node, identify which outputs belong to the given wallet
+	/// update the wallet state to be consistent with what's currently in the UTXO set.
+	///
+	/// This function can be used to repair wallet state, particularly by restoring outputs that may
+	/// be missing if the wallet owner has cancelled transactions locally that were then successfully
+	/// posted to the chain.
+	///
+	/// This operation scans the entire chain, and is expected to be time intensive. It is imperative
+	/// that no other processes should be trying to use the wallet at the same time this function is
+	/// running.
+	///
+	/// When an output is found that doesn't exist in the wallet, a corresponding
+	/// [TxLogEntry](../grin_wallet_libwallet/types/struct.TxLogEntry.html) is created.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `delete_unconfirmed` - if `false`, the check_repair process will be non-destructive, and
+	/// mostly limited to restoring missing outputs. It will leave unconfirmed transaction logs entries
+	/// and unconfirmed outputs intact. If `true`, the process will unlock all locked outputs,
+	/// restore all missing outputs, and mark any outputs that have been marked 'Spent' but are still
+	/// in the UTXO set as 'Unspent' (as can happen during a fork). It will also attempt to cancel any
+	/// transaction log entries associated with any locked outputs or outputs incorrectly marked 'Spent'.
+	/// Note this completely removes all outstanding transactions, so users should be very aware what
+	/// will happen if this flag is set. Note that if transactions/outputs are removed that later
+	/// confirm on the chain, another call to this function will restore them.
+	/// * `full` - if `true`, the whole UTXO set is scanned. Otherwise only the outputs added to
+	/// the UTXO set since the last restore or check are scanned, which is much faster but won't
+	/// find problems with older outputs. The whole set is scanned regardless if the wallet has
+	/// never been scanned, or the chain has become shorter since it last was.
+	///
+	/// # Returns
+	/// * `Ok(())` if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.check_repair(
+	/// 	None,
+	/// 	false,
+	/// 	false,
+	/// );
+	///
+	/// if let Ok(_) = result {
+	///		// Wallet outputs should be consistent with what's on chain
+	///		// ...
+	/// }
+	/// ```
+
+	pub fn check_repair(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		delete_unconfirmed: bool,
+		full: bool,
+	) -> Result<(), Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::check_repair(&mut **w, keychain_mask, delete_unconfirmed, full, None)
+	}
+
+	/// Compares the wallet's outputs and transaction log, across all accounts, against the
+	/// node's UTXO set and reports where they disagree, without changing anything in the wallet.
+	/// Unlike [`check_repair`](struct.Owner.html#method.check_repair), which fixes what it finds
+	/// as it goes, this lets the user see what a repair would touch before deciding on it.
+	///
+	/// Outputs are reported as:
+	/// * Unknown, when they belong to the wallet's keys and are in the UTXO set but the wallet
+	/// has no record of them.
+	/// * Missing, when the wallet holds them as unspent but they aren't in the UTXO set.
+	/// * Spent but unmarked, when they're locked by one of the wallet's transactions and have
+	/// left the UTXO set.
+	/// * Marked spent, when the wallet holds them as spent but they're still in the UTXO set.
+	/// * Confirmed but unmarked, when the wallet holds them as unconfirmed but they're in the
+	/// UTXO set, along with the transactions that created them.
+	///
+	/// The whole UTXO set is scanned, so this takes as long as a full check.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok with a [`ChainAuditReport`](../grin_wallet_libwallet/struct.ChainAuditReport.html)
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.audit_chain(None);
+	///
+	/// if let Ok(report) = result {
+	///		if !report.is_clean() {
+	///			println!("{} outputs missing from the chain", report.missing_outputs.len());
+	///		}
+	/// }
+	/// ```
+
+	pub fn audit_chain(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<ChainAuditReport, Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::audit_chain(&mut **w, keychain_mask)
+	}
+
+	/// Starts a long-running operation, such as a [`restore`](struct.Owner.html#method.restore)
+	/// or [`check_repair`](struct.Owner.html#method.check_repair), in the background and
+	/// returns straight away. The operation's progress can be followed with
+	/// [`operation_status`](struct.Owner.html#method.operation_status), and it can be stopped
+	/// with [`cancel_operation`](struct.Owner.html#method.cancel_operation).
+	///
+	/// The operation waits for any other operation using the wallet to finish first. The
+	/// keychain mask is checked when the operation runs, so an invalid mask makes the
+	/// operation fail rather than this call.
+	///
+	/// Operations are recorded in the wallet database, so an owner listener restarted while
+	/// an operation is queued or running resumes it. A resumed restore finishes with a full
+	/// [`check_repair`](struct.Owner.html#method.check_repair), as the wallet is no longer
+	/// empty.
+	///
+	/// Rather than polling, clients of the owner listener can follow an operation's progress as
+	/// server-sent events streamed from `/v3/operations/<id>/events`.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `operation` - The [`LongOperation`](../grin_wallet_libwallet/enum.LongOperation.html)
+	/// to run.
+	///
+	/// # Returns
+	/// * Ok with the id of the operation if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is
+	/// encountered, or no [`OperationRunner`](struct.OperationRunner.html) is set.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	/// use grin_wallet_api::OperationRunner;
+	/// use grin_wallet_libwallet::LongOperation;
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// api_owner.set_operation_runner(Some(Arc::new(OperationRunner::new(wallet.clone(), None))));
+	/// let result = api_owner.start_operation(None, LongOperation::Restore);
+	///
+	/// if let Ok(id) = result {
+	///		// Restore is running in the background
+	///		let status = api_owner.operation_status(None, id);
+	/// }
+	/// ```
+
+	pub fn start_operation(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		operation: LongOperation,
+	) -> Result<u32, Error> {
+		match self.operations.as_ref() {
+			Some(o) => Ok(o.start(operation, keychain_mask.cloned())),
+			None => Err(ErrorKind::GenericError(
+				"Background operations are not available for this wallet".to_owned(),
+			))?,
+		}
+	}
+
+	/// Retrieves the state and progress of an operation started with
+	/// [`start_operation`](struct.Owner.html#method.start_operation). Doesn't wait for
+	/// the wallet, so it can be called while the operation runs.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - The wallet secret mask the operation was started with, if any.
+	/// * `id` - The id of the operation, as returned when it was started.
+	///
+	/// # Returns
+	/// * Ok with the [`OperationStatus`](../grin_wallet_libwallet/struct.OperationStatus.html)
+	/// if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if no operation
+	/// with the given id was started with the given mask.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.operation_status(None, 1);
+	///
+	/// if let Ok(status) = result {
+	///		println!("{:?}: {}% done", status.state, status.percent_complete);
+	/// }
+	/// ```
+
+	pub fn operation_status(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		id: u32,
+	) -> Result<OperationStatus, Error> {
+		self.operations
+			.as_ref()
+			.and_then(|o| o.status(id, keychain_mask))
+			.ok_or_else(|| ErrorKind::UnknownOperation(id).into())
+	}
+
+	/// Cancels an operation started with
+	/// [`start_operation`](struct.Owner.html#method.start_operation). The operation stops at
+	/// its next chance to, undoing any changes it has made: a restore removes the outputs it
+	/// has restored, and a check stops before repairing anything. A check that has finished
+	/// scanning the chain completes its repairs. Doesn't wait for the operation to stop;
+	/// its state becomes `Cancelled` once it has.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - The wallet secret mask the operation was started with, if any.
+	/// * `id` - The id of the operation, as returned when it was started.
+	///
+	/// # Returns
+	/// * Ok(()) if successful, including if the operation had already finished
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if no operation
+	/// with the given id was started with the given mask.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.cancel_operation(None, 1);
+	///
+	/// if let Ok(_) = result {
+	///		// Operation will stop
+	/// }
+	/// ```
+
+	pub fn cancel_operation(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		id: u32,
+	) -> Result<(), Error> {
+		match self.operations.as_ref() {
+			Some(o) if o.cancel(id, keychain_mask) => Ok(()),
+			_ => Err(ErrorKind::UnknownOperation(id))?,
+		}
+	}
+
+	/// Lists the operations started with
+	/// [`start_operation`](struct.Owner.html#method.start_operation), both those still
+	/// queued or running and the most recent finished ones, including any recorded before
+	/// the listener last restarted. Doesn't wait for the wallet.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - The wallet secret mask the operations were started with, if any.
+	///
+	/// # Returns
+	/// * Ok with a vector of [`OperationStatus`](../grin_wallet_libwallet/struct.OperationStatus.html)
+	/// in order of id, empty if no [`OperationRunner`](struct.OperationRunner.html) is set
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is
+	/// encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.list_operations(None);
+	///
+	/// if let Ok(operations) = result {
+	///		for status in operations {
+	///			println!("{}: {:?} {:?}", status.id, status.operation, status.state);
+	///		}
+	/// }
+	/// ```
+
+	pub fn list_operations(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<Vec<OperationStatus>, Error> {
+		Ok(self
+			.operations
+			.as_ref()
+			.map(|o| o.list(keychain_mask))
+			.unwrap_or_default())
+	}
+
+	/// Lists the webhook events that couldn't be delivered, after retrying each as many times
+	/// as its webhook allows. The owner listener keeps them in `webhook_dead_letters.json` in
+	/// the wallet's directory, so they survive restarts.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok with a vector of [`WebhookDeadLetter`](../grin_wallet_impls/struct.WebhookDeadLetter.html),
+	/// oldest first, empty if no webhooks are set
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.get_webhook_dead_letters(None);
+	///
+	/// if let Ok(letters) = result {
+	///		for l in letters {
+	///			println!("{:?} event to {} failed: {}", l.payload.event, l.url, l.error);
+	///		}
+	/// }
+	/// ```
+
+	pub fn get_webhook_dead_letters(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<Vec<WebhookDeadLetter>, Error> {
+		{
+			let mut w_lock = self.lock_wallet()?;
+			let w = w_lock.lc_provider()?.wallet_inst()?;
+			let _ = w.keychain(keychain_mask)?; // Test keychain mask, to keep API consistent
+		}
+		match self.webhooks.as_ref() {
+			Some(h) => h.dead_letters(),
+			None => Ok(vec![]),
+		}
+	}
+
+	/// Exports the audit log of the calls made to the Owner API through the listener, each
+	/// recording the method called, a summary of its arguments including amounts and
+	/// destinations, who called it, when, and its result. Entries are hash-chained, and the
+	/// chain is checked as the log is exported, so any entry altered or removed since it was
+	/// recorded is reported. The owner listener keeps the log in `owner_api_audit.json` in the
+	/// wallet's directory when `owner_api_audit_log` is set.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok([`AuditLogExport`](../grin_wallet_impls/struct.AuditLogExport.html)) holding the
+	/// entries, oldest first, and the position of the first that doesn't chain on from those
+	/// before it, if any
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// including the audit log not being kept.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.export_audit_log(None);
+	///
+	/// if let Ok(export) = result {
+	///		if let Some(seq) = export.first_invalid {
+	///			println!("Audit log tampered with from entry {}", seq);
+	///		}
+	/// }
+	/// ```
+
+	pub fn export_audit_log(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<AuditLogExport, Error> {
+		{
+			let mut w_lock = self.lock_wallet()?;
+			let w = w_lock.lc_provider()?.wallet_inst()?;
+			let _ = w.keychain(keychain_mask)?; // Test keychain mask, to keep API consistent
+		}
+		match self.audit_log.as_ref() {
+			Some(l) => l.export(),
+			None => {
+				Err(ErrorKind::GenericError("The audit log isn't being kept".to_owned()).into())
+			}
+		}
+	}
+
+	/// Retrieves the wallet's feature flags, which turn features on or off for this
+	/// wallet instance.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok([`FeatureFlags`](../grin_wallet_libwallet/struct.FeatureFlags.html)) if successful,
+	/// the defaults if none have been set
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.get_feature_flags(None);
+	///
+	/// if let Ok(flags) = result {
+	///		assert!(flags.invoice_flow);
+	/// }
+	/// ```
+
+	pub fn get_feature_flags(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<FeatureFlags, Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		let _ = w.keychain(keychain_mask)?; // Test keychain mask, to keep API consistent
+		owner::get_feature_flags(&mut **w)
+	}
+
+	/// Replaces the wallet's feature flags, which are persisted in the wallet's database and
+	/// take effect straight away. Calls to a disabled feature fail with
+	/// [`ErrorKind::FeatureDisabled`](../grin_wallet_libwallet/enum.ErrorKind.html#variant.FeatureDisabled).
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `flags` - The new [`FeatureFlags`](../grin_wallet_libwallet/struct.FeatureFlags.html).
+	///
+	/// # Returns
+	/// * Ok(()) if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let flags = FeatureFlags {
+	///		invoice_flow: false,
+	///		..FeatureFlags::default()
+	/// };
+	/// let result = api_owner.set_feature_flags(None, flags);
+	///
+	/// if let Ok(_) = result {
+	///		// Invoices can no longer be issued or paid
+	/// }
+	/// ```
+
+	pub fn set_feature_flags(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		flags: FeatureFlags,
+	) -> Result<(), Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::set_feature_flags(&mut **w, keychain_mask, flags)
+	}
+
+	/// Verifies the user has recorded the recovery phrase the wallet generated when it was
+	/// created, by checking the words they give at the given positions. Once at least 3 words
+	/// have all been given right, the phrase is recorded as verified. Sending from the wallet
+	/// waits for this if the `seed_backup_check` feature flag is set (see
+	/// [`set_feature_flags`](struct.Owner.html#method.set_feature_flags)).
+	///
+	/// The phrase itself isn't kept in the wallet database, only a check value for each word
+	/// keyed by the wallet's root key.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `words` - Words of the recovery phrase, each with its position in the phrase, counting
+	/// from 1.
+	///
+	/// # Returns
+	/// * Ok(true) if all the words were right, Ok(false) otherwise
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is
+	/// encountered, including if fewer than 3 words are given or the wallet was recovered from
+	/// a phrase rather than generating one.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let words = vec![
+	/// 	(3, "fame".to_owned()),
+	/// 	(7, "lamp".to_owned()),
+	/// 	(18, "rocket".to_owned()),
+	/// ];
+	/// let result = api_owner.verify_seed_backup(None, words);
+	///
+	/// if let Ok(false) = result {
+	///		// ask the user to check their copy of the phrase
+	/// }
+	/// ```
+
+	pub fn verify_seed_backup(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		words: Vec<(u32, String)>,
+	) -> Result<bool, Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::verify_seed_backup(&mut **w, keychain_mask, &words)
+	}
+
+	/// Enrolls a new TOTP secret (RFC 6238), to be entered in an authenticator app. Once a
+	/// code from it is given to [`confirm_totp`](struct.Owner.html#method.confirm_totp),
+	/// spends made through the Owner API listeners need a valid code from the app, given as
+	/// the `totp_code` named parameter of the call, or in its `X-Grin-Totp-Code` header for
+	/// V2 calls with positional parameters, so a leaked API secret alone can't move funds.
+	/// A secret being enrolled but not yet confirmed is replaced.
+	///
+	/// The secret is kept in the wallet database, and isn't included in backups.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok([`TotpEnrollment`](../grin_wallet_libwallet/struct.TotpEnrollment.html)) holding
+	/// the secret and an `otpauth://` URI for it
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is
+	/// encountered, including if a confirmed secret is already enrolled.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.enroll_totp(None);
+	///
+	/// if let Ok(enrollment) = result {
+	///		// show enrollment.uri as a QR code, then confirm a code from the app
+	/// }
+	/// ```
+
+	pub fn enroll_totp(&self, keychain_mask: Option<&SecretKey>) -> Result<TotpEnrollment, Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::enroll_totp(&mut **w, keychain_mask, self.doctest_mode)
+	}
+
+	/// Confirms the TOTP secret enrolled with [`enroll_totp`](struct.Owner.html#method.enroll_totp)
+	/// with a code from the authenticator app, after which spends made through the Owner API
+	/// listeners need a valid code.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `code` - The code the app currently shows.
+	///
+	/// # Returns
+	/// * Ok(()) if the code was valid
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is
+	/// encountered, including if the code is invalid or no secret is being enrolled.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.confirm_totp(None, "123456");
+	///
+	/// if let Err(_) = result {
+	///		// ask for the code again
+	/// }
+	/// ```
+
+	pub fn confirm_totp(&self, keychain_mask: Option<&SecretKey>, code: &str) -> Result<(), Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::confirm_totp(&mut **w, keychain_mask, code)
+	}
+
+	/// Removes the wallet's TOTP secret, so codes are no longer asked for. A valid code is
+	/// needed if the secret was confirmed.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `code` - The code the authenticator app currently shows.
+	///
+	/// # Returns
+	/// * Ok(()) if the secret was removed
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is
+	/// encountered, including if the code is invalid or no secret is enrolled.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.disable_totp(None, "123456");
+	///
+	/// if let Ok(_) = result {
+	///		// spends no longer need a code
+	/// }
+	/// ```
+
+	pub fn disable_totp(&self, keychain_mask: Option<&SecretKey>, code: &str) -> Result<(), Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::disable_totp(&mut **w, keychain_mask, code)
+	}
+
+	/// Retrieves the last known height known by the wallet. This is determined as follows:
+	/// * If the wallet can successfully contact its configured node, the reported node
+	/// height is returned, and the `updated_from_node` field in the response is `true`
+	/// * If the wallet cannot contact the node, this function returns the maximum height
+	/// of all outputs contained within the wallet, and the `updated_from_node` fields
+	/// in the response is set to false.
+	///
+	/// Clients should generally ensure the `updated_from_node` field is returned as
+	/// `true` before assuming the height for any operation.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok with a  [`NodeHeightResult`](../grin_wallet_libwallet/types/struct.NodeHeightResult.html)
+	/// if successful. If the height result was obtained from the configured node,
+	/// `updated_from_node` will be set to `true`
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.node_height(None);
+	///
+	/// if let Ok(node_height_result) = result {
+	///		if node_height_result.updated_from_node {
+	///			//we can assume node_height_result.height is relatively safe to use
+	///
+	///		}
+	///		//...
+	/// }
+	/// ```
+
+	pub fn node_height(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<NodeHeightResult, Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		owner::node_height(&mut **w, keychain_mask)
+	}
+
+	/// Reports which node the wallet is currently using, along with the health of
+	/// each node it's configured to fail over between. Each node is contacted to
+	/// check its health, and if the node in use can't be reached the wallet switches
+	/// to the first node that can.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok with a [`NodeStatus`](../grin_wallet_libwallet/struct.NodeStatus.html)
+	/// if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.node_status(None);
+	///
+	/// if let Ok(status) = result {
+	///		println!("Using node {}", status.active_node);
+	/// }
+	/// ```
+
+	pub fn node_status(&self, keychain_mask: Option<&SecretKey>) -> Result<NodeStatus, Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		Ok(w.w2n_client().node_status())
+	}
+
+	/// Reports the wallet's connectivity to its node: whether the node can be reached,
+	/// the chain height and block header version it reports, and the error returned
+	/// by the last failed attempt to contact it. If a background
+	/// [`NodeMonitor`](struct.NodeMonitor.html) is set (as it is when the API is served
+	/// by the owner listener) the result of its most recent check is returned,
+	/// otherwise the node is checked now.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok with a [`NodeConnectivity`](../grin_wallet_libwallet/struct.NodeConnectivity.html)
+	/// if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.get_node_status(None);
+	///
+	/// if let Ok(status) = result {
+	///		if !status.reachable {
+	///			println!("Node unreachable: {:?}", status.last_error);
+	///		}
+	/// }
+	/// ```
+
+	pub fn get_node_status(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<NodeConnectivity, Error> {
+		let mut client = {
+			let mut w_lock = self.lock_wallet()?;
+			let w = w_lock.lc_provider()?.wallet_inst()?;
+			// Test keychain mask, to keep API consistent
+			let _ = w.keychain(keychain_mask)?;
+			w.w2n_client().clone()
+		};
+		if let Some(m) = self.node_monitor.as_ref() {
+			return Ok(m.status());
+		}
+		// checked without holding the wallet lock, as the node may be slow to answer
+		Ok(check_node(&mut client, &NodeConnectivity::default()))
+	}
+
+	/// Starts the background updater, which refreshes the active account's outputs and
+	/// transactions against the node straight away and then every `frequency`. While it
+	/// runs, calls asking to refresh from the node (such as
+	/// [`retrieve_txs`](struct.Owner.html#method.retrieve_txs)) return the wallet's state as
+	/// of the updater's last refresh rather than refreshing inline. Calling this while the
+	/// updater is running changes its frequency. For as long as the node throttles the
+	/// wallet's requests, the time between refreshes is doubled, up to 32 times `frequency`.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used. The updater keeps it for as long as it runs.
+	/// * `frequency` - Time between refreshes.
+	///
+	/// # Returns
+	/// * Ok(()) if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is
+	/// encountered, or no [`WalletUpdater`](struct.WalletUpdater.html) is set.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	/// use grin_wallet_api::WalletUpdater;
+	/// use std::time::Duration;
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// api_owner.set_updater(Some(Arc::new(WalletUpdater::new(wallet.clone(), None))));
+	/// let result = api_owner.start_updater(None, Duration::from_secs(30));
+	///
+	/// if let Ok(_) = result {
+	///		// Wallet is being refreshed in the background
+	///		let _ = api_owner.stop_updater(None);
+	/// }
+	/// ```
+
+	pub fn start_updater(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		frequency: Duration,
+	) -> Result<(), Error> {
+		{
+			let mut w_lock = self.lock_wallet()?;
+			let w = w_lock.lc_provider()?.wallet_inst()?;
+			// Test keychain mask, to keep API consistent
+			let _ = w.keychain(keychain_mask)?;
+		}
+		match self.updater.as_ref() {
+			Some(u) => {
+				u.start(frequency, keychain_mask.cloned());
+				Ok(())
+			}
+			None => Err(ErrorKind::GenericError(
+				"Background updates are not available for this wallet".to_owned(),
+			))?,
+		}
+	}
+
+	/// Stops the background updater started by
+	/// [`start_updater`](struct.Owner.html#method.start_updater). Does nothing if the
+	/// updater isn't running.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok(()) if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.stop_updater(None);
+	///
+	/// if let Ok(_) = result {
+	///		// Wallet is no longer refreshed in the background
+	/// }
+	/// ```
+
+	pub fn stop_updater(&self, keychain_mask: Option<&SecretKey>) -> Result<(), Error> {
+		{
+			let mut w_lock = self.lock_wallet()?;
+			let w = w_lock.lc_provider()?.wallet_inst()?;
+			// Test keychain mask, to keep API consistent
+			let _ = w.keychain(keychain_mask)?;
+		}
+		if let Some(u) = self.updater.as_ref() {
+			u.stop();
+		}
+		Ok(())
+	}
+
+	/// Retrieves progress and warning messages from the background updater, removing
+	/// them so that each message is only returned once.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `count` - The maximum number of messages to return, the most recent being kept.
+	///
+	/// # Returns
+	/// * Ok with up to `count` of the most recent
+	/// [`StatusMessage`](../grin_wallet_libwallet/enum.StatusMessage.html)s, oldest first
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
 	/// ```
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
-	/// let mut api_owner = Owner::new(wallet.clone());
-	/// // Attempt to create a transaction using the 'default' account
-	/// let args = InitTxArgs {
-	/// 	src_acct_name: None,
-	/// 	amount: 2_000_000_000,
-	/// 	minimum_confirmations: 2,
-	/// 	max_outputs: 500,
-	/// 	num_change_outputs: 1,
-	/// 	selection_strategy_is_use_all: true,
-	/// 	message: Some("Have some Grins. Love, Yeastplume".to_owned()),
-	/// 	..Default::default()
-	/// };
-	/// let result = api_owner.init_send_tx(
-	/// 	None,
-	/// 	args,
-	/// );
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.get_updater_messages(None, 10);
 	///
-	/// if let Ok(slate) = result {
-	/// 	// Send slate somehow
-	/// 	// ...
-	/// 	// Lock our outputs if we're happy the slate was (or is being) sent
-	/// 	api_owner.tx_lock_outputs(None, &slate, 0);
+	/// if let Ok(messages) = result {
+	///		for m in messages {
+	///			println!("{:?}", m);
+	///		}
 	/// }
 	/// ```
 
-	pub fn init_send_tx(
+	pub fn get_updater_messages(
 		&self,
 		keychain_mask: Option<&SecretKey>,
-		args: InitTxArgs,
-	) -> Result<Slate, Error> {
-		let send_args = args.send_args.clone();
-		let mut slate = {
-			let mut w_lock = self.wallet_inst.lock();
+		count: usize,
+	) -> Result<Vec<StatusMessage>, Error> {
+		{
+			let mut w_lock = self.lock_wallet()?;
 			let w = w_lock.lc_provider()?.wallet_inst()?;
-			owner::init_send_tx(&mut **w, keychain_mask, args, self.doctest_mode)?
-		};
-		// Helper functionality. If send arguments exist, attempt to send
-		match send_args {
-			Some(sa) => {
-				//TODO: in case of keybase, the response might take 60s and leave the service hanging
-				match sa.method.as_ref() {
-					"http" | "keybase" => {}
-					_ => {
-						error!("unsupported payment method: {}", sa.method);
-						return Err(ErrorKind::ClientCallback(
-							"unsupported payment method".to_owned(),
-						)
-						.into());
-					}
-				};
-				let comm_adapter = create_sender(&sa.method, &sa.dest)
-					.map_err(|e| ErrorKind::GenericError(format!("{}", e)))?;
-				slate = comm_adapter.send_tx(&slate)?;
-				self.tx_lock_outputs(keychain_mask, &slate, 0)?;
-				let slate = match sa.finalize {
-					true => self.finalize_tx(keychain_mask, &slate)?,
-					false => slate,
-				};
-
-				if sa.post_tx {
-					self.post_tx(keychain_mask, &slate.tx, sa.fluff)?;
-				}
-				Ok(slate)
-			}
-			None => Ok(slate),
+			// Test keychain mask, to keep API consistent
+			let _ = w.keychain(keychain_mask)?;
 		}
+		Ok(match self.updater.as_ref() {
+			Some(u) => u.messages(count),
+			None => vec![],
+		})
 	}
 
-	/// Issues a new invoice transaction slate, essentially a `request for payment`.
-	/// The slate created by this function will contain the amount, an output for the amount,
-	/// as well as round 1 of singature creation complete. The slate should then be send
-	/// to the payer, who should add their inputs and signature data and return the slate
-	/// via the [Foreign API's `finalize_invoice_tx`](struct.Foreign.html#method.finalize_invoice_tx) method.
+	/// Suspends the open wallet, flushing and closing its database and releasing
+	/// any file locks it holds, while keeping the wallet's keychain in memory.
+	/// Intended for applications embedding the wallet on platforms (such as iOS
+	/// and Android) where the OS may freeze or kill a backgrounded process at any
+	/// time. Calls requiring the wallet's database will fail until
+	/// [`resume`](struct.Owner.html#method.resume) is called.
 	///
-	/// # Arguments
-	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
-	/// being used.
-	/// * `args` - [`IssueInvoiceTxArgs`](../grin_wallet_libwallet/types/struct.IssueInvoiceTxArgs.html),
-	/// invoice transaction initialization arguments. See struct documentation for further detail.
+	/// The background updater is stopped and queued or running operations are
+	/// cancelled first, so nothing is left using the database as it's closed.
+	/// The updater isn't restarted on resuming.
 	///
 	/// # Returns
-	/// * ``Ok([`slate`](../grin_wallet_libwallet/slate/struct.Slate.html))` if successful,
-	/// containing the updated slate.
+	/// * `Ok(())` if successful, or if no wallet is open
 	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
 	///
 	/// # Example
@@ -614,54 +4051,73 @@ where
 	/// ```
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
-	/// let mut api_owner = Owner::new(wallet.clone());
-	///
-	/// let args = IssueInvoiceTxArgs {
-	/// 	amount: 60_000_000_000,
-	/// 	..Default::default()
-	/// };
-	/// let result = api_owner.issue_invoice_tx(None, args);
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.suspend();
 	///
-	/// if let Ok(slate) = result {
-	///		// if okay, send to the payer to add their inputs
-	///		// . . .
+	/// if let Ok(_) = result {
+	///		// app may now be backgrounded
+	///		let result = api_owner.resume();
 	/// }
 	/// ```
-	pub fn issue_invoice_tx(
-		&self,
-		keychain_mask: Option<&SecretKey>,
-		args: IssueInvoiceTxArgs,
-	) -> Result<Slate, Error> {
-		let mut w_lock = self.wallet_inst.lock();
-		let w = w_lock.lc_provider()?.wallet_inst()?;
-		owner::issue_invoice_tx(&mut **w, keychain_mask, args, self.doctest_mode)
+
+	pub fn suspend(&self) -> Result<(), Error> {
+		if let Some(u) = self.updater.as_ref() {
+			u.stop();
+		}
+		if let Some(o) = self.operations.as_ref() {
+			o.cancel_all();
+		}
+		let mut w_lock = self.lock_wallet()?;
+		w_lock.lc_provider()?.suspend_wallet()
 	}
 
-	/// Processes an invoice tranaction created by another party, essentially
-	/// a `request for payment`. The incoming slate should contain a requested
-	/// amount, an output created by the invoicer convering the amount, and
-	/// part 1 of signature creation completed. This function will add inputs
-	/// equalling the amount + fees, as well as perform round 1 and 2 of signature
-	/// creation.
+	/// Resumes a wallet suspended with [`suspend`](struct.Owner.html#method.suspend),
+	/// reopening its database and revalidating the connection to the configured
+	/// node, which may have been dropped while the process was frozen.
 	///
-	/// Callers should note that no prompting of the user will be done by this function
-	/// it is up to the caller to present the request for payment to the user
-	/// and verify that payment should go ahead.
+	/// # Returns
+	/// * `Ok(true)` if the wallet was resumed and the node could be contacted
+	/// * `Ok(false)` if the wallet was resumed but the node could not be contacted
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
 	///
-	/// This function also stores the final transaction in the user's wallet files for retrieval
-	/// via the [`get_stored_tx`](struct.Owner.html#method.get_stored_tx) function.
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let _ = api_owner.suspend();
+	/// let result = api_owner.resume();
+	///
+	/// if let Ok(node_reachable) = result {
+	///		if !node_reachable {
+	///			// warn user that balances may be stale
+	///		}
+	/// }
+	/// ```
+
+	pub fn resume(&self) -> Result<bool, Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let lc = w_lock.lc_provider()?;
+		lc.resume_wallet()?;
+		let w = lc.wallet_inst()?;
+		Ok(w.w2n_client().get_chain_height().is_ok())
+	}
+
+	/// Registers a device's push notification token, so the device can be woken
+	/// when a slate is received for this wallet by a listener with a push notifier
+	/// configured (see [`Foreign::set_push_notifier`](struct.Foreign.html#method.set_push_notifier)).
+	/// Registering an already registered token replaces its registration.
 	///
 	/// # Arguments
+	///
 	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
 	/// being used.
-	/// * `slate` - The transaction [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html). The
-	/// payer should have filled in round 1 and 2.
-	/// * `args` - [`InitTxArgs`](../grin_wallet_libwallet/types/struct.InitTxArgs.html),
-	/// transaction initialization arguments. See struct documentation for further detail.
+	/// * `token` - The token issued to the device by its push service.
+	/// * `platform` - The push service that issued the token, e.g. `apns` or `fcm`.
 	///
 	/// # Returns
-	/// * ``Ok([`slate`](../grin_wallet_libwallet/slate/struct.Slate.html))` if successful,
-	/// containing the updated slate.
+	/// * `Ok(())` if successful
 	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
 	///
 	/// # Example
@@ -669,64 +4125,35 @@ where
 	/// ```
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
-	/// let mut api_owner = Owner::new(wallet.clone());
-	///
-	/// // . . .
-	/// // The slate has been recieved from the invoicer, somehow
-	/// # let slate = Slate::blank(2);
-	/// let args = InitTxArgs {
-	///		src_acct_name: None,
-	///		amount: slate.amount,
-	///		minimum_confirmations: 2,
-	///		max_outputs: 500,
-	///		num_change_outputs: 1,
-	///		selection_strategy_is_use_all: true,
-	///		..Default::default()
-	///	};
-	///
-	/// let result = api_owner.process_invoice_tx(None, &slate, args);
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.register_push_token(None, "device_token", "fcm");
 	///
-	/// if let Ok(slate) = result {
-	///	// If result okay, send back to the invoicer
-	///	// . . .
-	///	}
+	/// if let Ok(_) = result {
+	///		// device will now be notified of received slates
+	/// }
 	/// ```
 
-	pub fn process_invoice_tx(
+	pub fn register_push_token(
 		&self,
 		keychain_mask: Option<&SecretKey>,
-		slate: &Slate,
-		args: InitTxArgs,
-	) -> Result<Slate, Error> {
-		let mut w_lock = self.wallet_inst.lock();
+		token: &str,
+		platform: &str,
+	) -> Result<(), Error> {
+		let mut w_lock = self.lock_wallet()?;
 		let w = w_lock.lc_provider()?.wallet_inst()?;
-		owner::process_invoice_tx(&mut **w, keychain_mask, slate, args, self.doctest_mode)
+		owner::register_push_token(&mut **w, keychain_mask, token, platform)
 	}
 
-	/// Locks the outputs associated with the inputs to the transaction in the given
-	/// [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html),
-	/// making them unavailable for use in further transactions. This function is called
-	/// by the sender, (or more generally, all parties who have put inputs into the transaction,)
-	/// and must be called before the corresponding call to [`finalize_tx`](struct.Owner.html#method.finalize_tx)
-	/// that completes the transaction.
-	///
-	/// Outputs will generally remain locked until they are removed from the chain,
-	/// at which point they will become `Spent`. It is commonplace for transactions not to complete
-	/// for various reasons over which a particular wallet has no control. For this reason,
-	/// [`cancel_tx`](struct.Owner.html#method.cancel_tx) can be used to manually unlock outputs
-	/// and return them to the `Unspent` state.
+	/// Removes a device's push notification token registration, if present.
 	///
 	/// # Arguments
+	///
 	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
 	/// being used.
-	/// * `slate` - The transaction [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html). All
-	/// * `participant_id` - The participant id, generally 0 for the party putting in funds, 1 for the
-	/// party receiving.
-	/// elements in the `input` vector of the `tx` field that are found in the wallet's currently
-	/// active account will be set to status `Locked`
+	/// * `token` - The token to unregister.
 	///
 	/// # Returns
-	/// * Ok(()) if successful
+	/// * `Ok(())` if successful
 	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
 	///
 	/// # Example
@@ -734,63 +4161,30 @@ where
 	/// ```
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
-	/// let mut api_owner = Owner::new(wallet.clone());
-	/// let args = InitTxArgs {
-	/// 	src_acct_name: None,
-	/// 	amount: 2_000_000_000,
-	/// 	minimum_confirmations: 10,
-	/// 	max_outputs: 500,
-	/// 	num_change_outputs: 1,
-	/// 	selection_strategy_is_use_all: true,
-	/// 	message: Some("Remember to lock this when we're happy this is sent".to_owned()),
-	/// 	..Default::default()
-	/// };
-	/// let result = api_owner.init_send_tx(
-	/// 	None,
-	/// 	args,
-	/// );
-	///
-	/// if let Ok(slate) = result {
-	///		// Send slate somehow
-	///		// ...
-	///		// Lock our outputs if we're happy the slate was (or is being) sent
-	///		api_owner.tx_lock_outputs(None, &slate, 0);
-	/// }
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.unregister_push_token(None, "device_token");
 	/// ```
 
-	pub fn tx_lock_outputs(
+	pub fn unregister_push_token(
 		&self,
 		keychain_mask: Option<&SecretKey>,
-		slate: &Slate,
-		participant_id: usize,
+		token: &str,
 	) -> Result<(), Error> {
-		let mut w_lock = self.wallet_inst.lock();
+		let mut w_lock = self.lock_wallet()?;
 		let w = w_lock.lc_provider()?.wallet_inst()?;
-		owner::tx_lock_outputs(&mut **w, keychain_mask, slate, participant_id)
+		owner::unregister_push_token(&mut **w, keychain_mask, token)
 	}
 
-	/// Finalizes a transaction, after all parties
-	/// have filled in both rounds of Slate generation. This step adds
-	/// all participants partial signatures to create the final signature,
-	/// resulting in a final transaction that is ready to post to a node.
-	///
-	/// Note that this function DOES NOT POST the transaction to a node
-	/// for validation. This is done in separately via the
-	/// [`post_tx`](struct.Owner.html#method.post_tx) function.
-	///
-	/// This function also stores the final transaction in the user's wallet files for retrieval
-	/// via the [`get_stored_tx`](struct.Owner.html#method.get_stored_tx) function.
+	/// Returns the devices registered for push notifications.
 	///
 	/// # Arguments
+	///
 	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
 	/// being used.
-	/// * `slate` - The transaction [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html). All
-	/// participants must have filled in both rounds, and the sender should have locked their
-	/// outputs (via the [`tx_lock_outputs`](struct.Owner.html#method.tx_lock_outputs) function).
 	///
 	/// # Returns
-	/// * ``Ok([`slate`](../grin_wallet_libwallet/slate/struct.Slate.html))` if successful,
-	/// containing the new finalized slate.
+	/// * Ok with a Vec of [`PushRegistration`](../grin_wallet_libwallet/types/struct.PushRegistration.html)
+	/// if successful
 	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
 	///
 	/// # Example
@@ -798,59 +4192,39 @@ where
 	/// ```
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
-	/// let mut api_owner = Owner::new(wallet.clone());
-	/// let args = InitTxArgs {
-	/// 	src_acct_name: None,
-	/// 	amount: 2_000_000_000,
-	/// 	minimum_confirmations: 10,
-	/// 	max_outputs: 500,
-	/// 	num_change_outputs: 1,
-	/// 	selection_strategy_is_use_all: true,
-	/// 	message: Some("Finalize this tx now".to_owned()),
-	/// 	..Default::default()
-	/// };
-	/// let result = api_owner.init_send_tx(
-	/// 	None,
-	/// 	args,
-	/// );
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.retrieve_push_registrations(None);
 	///
-	/// if let Ok(slate) = result {
-	///		// Send slate somehow
-	///		// ...
-	///		// Lock our outputs if we're happy the slate was (or is being) sent
-	///		let res = api_owner.tx_lock_outputs(None, &slate, 0);
-	///		//
-	///		// Retrieve slate back from recipient
-	///		//
-	///		let res = api_owner.finalize_tx(None, &slate);
+	/// if let Ok(registrations) = result {
+	///		//...
 	/// }
 	/// ```
 
-	pub fn finalize_tx(
+	pub fn retrieve_push_registrations(
 		&self,
 		keychain_mask: Option<&SecretKey>,
-		slate: &Slate,
-	) -> Result<Slate, Error> {
-		let mut w_lock = self.wallet_inst.lock();
+	) -> Result<Vec<PushRegistration>, Error> {
+		let mut w_lock = self.lock_wallet()?;
 		let w = w_lock.lc_provider()?.wallet_inst()?;
-		owner::finalize_tx(&mut **w, keychain_mask, &slate)
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		owner::retrieve_push_registrations(&mut **w)
 	}
 
-	/// Posts a completed transaction to the listening node for validation and inclusion in a block
-	/// for mining.
+	/// Returns the received transactions waiting for review, oldest first. Slates received via
+	/// the Foreign API for more than the configured `receive_review_threshold` aren't signed
+	/// but queued, until approved with
+	/// [`approve_receive`](struct.Owner.html#method.approve_receive) or rejected with
+	/// [`reject_receive`](struct.Owner.html#method.reject_receive).
 	///
 	/// # Arguments
+	///
 	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
 	/// being used.
-	/// * `tx` - A completed [`Transaction`](../grin_core/core/transaction/struct.Transaction.html),
-	/// typically the `tx` field in the transaction [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html).
-	/// * `fluff` - Instruct the node whether to use the Dandelion protocol when posting the
-	/// transaction. If `true`, the node should skip the Dandelion phase and broadcast the
-	/// transaction to all peers immediately. If `false`, the node will follow dandelion logic and
-	/// initiate the stem phase.
 	///
 	/// # Returns
-	/// * `Ok(())` if successful
+	/// * Ok with a Vec of [`PendingReceive`](../grin_wallet_libwallet/types/struct.PendingReceive.html)
+	/// if successful
 	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
 	///
 	/// # Example
@@ -858,71 +4232,39 @@ where
 	/// ```
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
-	/// let mut api_owner = Owner::new(wallet.clone());
-	/// let args = InitTxArgs {
-	/// 	src_acct_name: None,
-	/// 	amount: 2_000_000_000,
-	/// 	minimum_confirmations: 10,
-	/// 	max_outputs: 500,
-	/// 	num_change_outputs: 1,
-	/// 	selection_strategy_is_use_all: true,
-	/// 	message: Some("Post this tx".to_owned()),
-	/// 	..Default::default()
-	/// };
-	/// let result = api_owner.init_send_tx(
-	/// 	None,
-	/// 	args,
-	/// );
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.retrieve_pending_receives(None);
 	///
-	/// if let Ok(slate) = result {
-	///		// Send slate somehow
-	///		// ...
-	///		// Lock our outputs if we're happy the slate was (or is being) sent
-	///		let res = api_owner.tx_lock_outputs(None, &slate, 0);
-	///		//
-	///		// Retrieve slate back from recipient
-	///		//
-	///		let res = api_owner.finalize_tx(None, &slate);
-	///		let res = api_owner.post_tx(None, &slate.tx, true);
+	/// if let Ok(pending) = result {
+	///		//...
 	/// }
 	/// ```
 
-	pub fn post_tx(
+	pub fn retrieve_pending_receives(
 		&self,
 		keychain_mask: Option<&SecretKey>,
-		tx: &Transaction,
-		fluff: bool,
-	) -> Result<(), Error> {
-		let client = {
-			let mut w_lock = self.wallet_inst.lock();
-			let w = w_lock.lc_provider()?.wallet_inst()?;
-			// Test keychain mask, to keep API consistent
-			let _ = w.keychain(keychain_mask)?;
-			w.w2n_client().clone()
-		};
-		owner::post_tx(&client, tx, fluff)
+	) -> Result<Vec<PendingReceive>, Error> {
+		let mut w_lock = self.lock_wallet()?;
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		owner::retrieve_pending_receives(&mut **w)
 	}
 
-	/// Cancels a transaction. This entails:
-	/// * Setting the transaction status to either `TxSentCancelled` or `TxReceivedCancelled`
-	/// * Deleting all change outputs or recipient outputs associated with the transaction
-	/// * Setting the status of all assocatied inputs from `Locked` to `Spent` so they can be
-	/// used in new transactions.
-	///
-	/// Transactions can be cancelled by transaction log id or slate id (call with either set to
-	/// Some, not both)
+	/// Receives a transaction waiting for review, as
+	/// [`Foreign::receive_tx`](struct.Foreign.html#method.receive_tx) would have, and removes it
+	/// from the queue. The returned slate is to be sent back to the sender by other means, such
+	/// as a file, to be finalized. A transaction that can't be received, such as one past its TTL,
+	/// stays queued until rejected.
 	///
 	/// # Arguments
 	///
 	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
 	/// being used.
-	/// * `tx_id` - If present, cancel by the [`TxLogEntry`](../grin_wallet_libwallet/types/struct.TxLogEntry.html) id
-	/// for the transaction.
-	///
-	/// * `tx_slate_id` - If present, cancel by the Slate id.
+	/// * `tx_slate_id` - The id of the queued slate.
 	///
 	/// # Returns
-	/// * `Ok(())` if successful
+	/// * Ok with the signed [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html) if successful
 	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
 	///
 	/// # Example
@@ -930,58 +4272,53 @@ where
 	/// ```
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
-	/// let mut api_owner = Owner::new(wallet.clone());
-	/// let args = InitTxArgs {
-	/// 	src_acct_name: None,
-	/// 	amount: 2_000_000_000,
-	/// 	minimum_confirmations: 10,
-	/// 	max_outputs: 500,
-	/// 	num_change_outputs: 1,
-	/// 	selection_strategy_is_use_all: true,
-	/// 	message: Some("Cancel this tx".to_owned()),
-	/// 	..Default::default()
-	/// };
-	/// let result = api_owner.init_send_tx(
-	/// 	None,
-	/// 	args,
-	/// );
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.retrieve_pending_receives(None);
 	///
-	/// if let Ok(slate) = result {
-	///		// Send slate somehow
-	///		// ...
-	///		// Lock our outputs if we're happy the slate was (or is being) sent
-	///		let res = api_owner.tx_lock_outputs(None, &slate, 0);
-	///		//
-	///		// We didn't get the slate back, or something else went wrong
-	///		//
-	///		let res = api_owner.cancel_tx(None, None, Some(slate.id.clone()));
+	/// if let Ok(pending) = result {
+	///		for p in pending {
+	///			let slate = api_owner.approve_receive(None, p.slate.id);
+	///			// . . .
+	///			// Send the slate back to the sender somehow
+	///		}
 	/// }
 	/// ```
 
-	pub fn cancel_tx(
+	pub fn approve_receive(
 		&self,
 		keychain_mask: Option<&SecretKey>,
-		tx_id: Option<u32>,
-		tx_slate_id: Option<Uuid>,
-	) -> Result<(), Error> {
-		let mut w_lock = self.wallet_inst.lock();
+		tx_slate_id: Uuid,
+	) -> Result<Slate, Error> {
+		let mut w_lock = self.lock_wallet()?;
 		let w = w_lock.lc_provider()?.wallet_inst()?;
-		owner::cancel_tx(&mut **w, keychain_mask, tx_id, tx_slate_id)
+		let slate =
+			owner::approve_receive(&mut **w, keychain_mask, tx_slate_id, self.doctest_mode)?;
+		if let Some(h) = self.webhooks.as_ref() {
+			if h.wants(WebhookEventType::TxReceived) {
+				let tx = w.tx_log_iter()?.find(|t| {
+					t.tx_slate_id == Some(slate.id) && t.tx_type == TxLogEntryType::TxReceived
+				});
+				h.dispatch(
+					WebhookPayload::new(WebhookEventType::TxReceived, Some(slate.id), tx)
+						.with_account(w.acct_path_iter()?),
+				);
+			}
+		}
+		Ok(slate)
 	}
 
-	/// Retrieves the stored transaction associated with a TxLogEntry. Can be used even after the
-	/// transaction has completed.
+	/// Removes a transaction waiting for review from the queue without receiving it.
 	///
 	/// # Arguments
 	///
 	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
 	/// being used.
-	/// * `tx_log_entry` - A [`TxLogEntry`](../grin_wallet_libwallet/types/struct.TxLogEntry.html)
+	/// * `tx_slate_id` - The id of the queued slate.
 	///
 	/// # Returns
-	/// * Ok with the stored  [`Transaction`](../grin_core/core/transaction/struct.Transaction.html)
-	/// if successful
-	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	/// * `Ok(())` if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is
+	/// encountered, including if no slate with the id is queued.
 	///
 	/// # Example
 	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
@@ -989,49 +4326,38 @@ where
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
 	/// let api_owner = Owner::new(wallet.clone());
-	/// let update_from_node = true;
-	/// let tx_id = None;
-	/// let tx_slate_id = None;
-	///
-	/// // Return all TxLogEntries
-	/// let result = api_owner.retrieve_txs(None, update_from_node, tx_id, tx_slate_id);
+	/// let result = api_owner.retrieve_pending_receives(None);
 	///
-	/// if let Ok((was_updated, tx_log_entries)) = result {
-	///		let stored_tx = api_owner.get_stored_tx(None, &tx_log_entries[0]).unwrap();
-	///		//...
+	/// if let Ok(pending) = result {
+	///		for p in pending {
+	///			let _ = api_owner.reject_receive(None, p.slate.id);
+	///		}
 	/// }
 	/// ```
 
-	// TODO: Should be accepting an id, not an entire entry struct
-	pub fn get_stored_tx(
+	pub fn reject_receive(
 		&self,
 		keychain_mask: Option<&SecretKey>,
-		tx_log_entry: &TxLogEntry,
-	) -> Result<Option<Transaction>, Error> {
-		let mut w_lock = self.wallet_inst.lock();
+		tx_slate_id: Uuid,
+	) -> Result<(), Error> {
+		let mut w_lock = self.lock_wallet()?;
 		let w = w_lock.lc_provider()?.wallet_inst()?;
-		// Test keychain mask, to keep API consistent
-		let _ = w.keychain(keychain_mask)?;
-		owner::get_stored_tx(&**w, tx_log_entry)
+		owner::reject_receive(&mut **w, keychain_mask, tx_slate_id)
 	}
 
-	/// Verifies all messages in the slate match their public keys.
-	///
-	/// The optional messages themselves are part of the `participant_data` field within the slate.
-	/// Messages are signed with the same key used to sign for the paricipant's inputs, and can thus be
-	/// verified with the public key found in the `public_blind_excess` field. This function is a
-	/// simple helper to returns whether all signatures in the participant data match their public
-	/// keys.
+	/// Stops the listener serving this API. The listener finishes handling
+	/// in-flight requests (including this one), closes the wallet and returns
+	/// control to whatever started it.
 	///
 	/// # Arguments
 	///
 	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
 	/// being used.
-	/// * `slate` - The transaction [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html).
 	///
 	/// # Returns
-	/// * `Ok(())` if successful and the signatures validate
-	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	/// * `Ok(())` if the listener was asked to stop
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// including if the API isn't being served by a listener.
 	///
 	/// # Example
 	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
@@ -1039,156 +4365,114 @@ where
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
 	/// let mut api_owner = Owner::new(wallet.clone());
-	/// let args = InitTxArgs {
-	/// 	src_acct_name: None,
-	/// 	amount: 2_000_000_000,
-	/// 	minimum_confirmations: 10,
-	/// 	max_outputs: 500,
-	/// 	num_change_outputs: 1,
-	/// 	selection_strategy_is_use_all: true,
-	/// 	message: Some("Just verify messages".to_owned()),
-	/// 	..Default::default()
-	/// };
-	/// let result = api_owner.init_send_tx(
-	/// 	None,
-	/// 	args,
-	/// );
+	/// let shutdown = api::ShutdownHandle::new();
+	/// api_owner.set_shutdown_handle(Some(shutdown.clone()));
 	///
-	/// if let Ok(slate) = result {
-	///		// Send slate somehow
-	///		// ...
-	///		// Lock our outputs if we're happy the slate was (or is being) sent
-	///		let res = api_owner.tx_lock_outputs(None, &slate, 0);
-	///		//
-	///		// Retrieve slate back from recipient
-	///		//
-	///		let res = api_owner.verify_slate_messages(None, &slate);
-	/// }
+	/// let result = api_owner.stop_listener(None);
+	/// assert!(shutdown.is_shutdown());
 	/// ```
-	pub fn verify_slate_messages(
-		&self,
-		keychain_mask: Option<&SecretKey>,
-		slate: &Slate,
-	) -> Result<(), Error> {
+
+	pub fn stop_listener(&self, keychain_mask: Option<&SecretKey>) -> Result<(), Error> {
 		{
-			let mut w_lock = self.wallet_inst.lock();
+			let mut w_lock = self.lock_wallet()?;
 			let w = w_lock.lc_provider()?.wallet_inst()?;
 			// Test keychain mask, to keep API consistent
 			let _ = w.keychain(keychain_mask)?;
 		}
-		owner::verify_slate_messages(slate)
+		match self.shutdown.as_ref() {
+			Some(s) => {
+				s.shutdown();
+				Ok(())
+			}
+			None => Err(ErrorKind::GenericError(
+				"Owner API is not being served by a listener".to_owned(),
+			))?,
+		}
 	}
 
-	/// Scans the entire UTXO set from the node, creating outputs for each scanned
-	/// output that matches the wallet's master seed. This function is intended to be called as part
-	/// of a recovery process (either from BIP32 phrase or backup seed files,) and will error if the
-	/// wallet is non-empty, i.e. contains any outputs at all.
-	///
-	/// This operation scans the entire chain, and is expected to be time intensive. It is imperative
-	/// that no other processes should be trying to use the wallet at the same time this function is
-	/// running.
-	///
-	/// A single [TxLogEntry](../grin_wallet_libwallet/types/struct.TxLogEntry.html) is created for
-	/// all non-coinbase outputs discovered and restored during this process. A separate entry
-	/// is created for each coinbase output.
+	/// Turns privacy mode on or off for the whole process. In privacy mode,
+	/// amounts are redacted from logs and debug output down to the power of
+	/// ten range they fall in, and commitments to hashes that can't be looked
+	/// up on chain, so logs can be shared for support without disclosing
+	/// balances. Privacy mode is set at startup from the `log_privacy`
+	/// configuration value.
 	///
 	/// # Arguments
 	///
 	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
 	/// being used.
+	/// * `enabled` - Whether privacy mode should be on.
 	///
 	/// # Returns
 	/// * `Ok(())` if successful
 	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
-
+	///
 	/// # Example
 	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
 	/// ```
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
-	/// let mut api_owner = Owner::new(wallet.clone());
-	/// let result = api_owner.restore(None);
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.set_privacy_mode(None, true);
 	///
 	/// if let Ok(_) = result {
-	///		// Wallet outputs should be consistent with what's on chain
-	///		// ...
+	///		// logs can now be shared
 	/// }
 	/// ```
-	pub fn restore(&self, keychain_mask: Option<&SecretKey>) -> Result<(), Error> {
-		let mut w_lock = self.wallet_inst.lock();
+
+	pub fn set_privacy_mode(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		enabled: bool,
+	) -> Result<(), Error> {
+		let mut w_lock = self.lock_wallet()?;
 		let w = w_lock.lc_provider()?.wallet_inst()?;
-		let res = owner::restore(&mut **w, keychain_mask);
-		res
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		set_privacy_mode(enabled);
+		Ok(())
 	}
 
-	/// Scans the entire UTXO set from the node, identify which outputs belong to the given wallet
-	/// update the wallet state to be consistent with what's currently in the UTXO set.
-	///
-	/// This function can be used to repair wallet state, particularly by restoring outputs that may
-	/// be missing if the wallet owner has cancelled transactions locally that were then successfully
-	/// posted to the chain.
-	///
-	/// This operation scans the entire chain, and is expected to be time intensive. It is imperative
-	/// that no other processes should be trying to use the wallet at the same time this function is
-	/// running.
-	///
-	/// When an output is found that doesn't exist in the wallet, a corresponding
-	/// [TxLogEntry](../grin_wallet_libwallet/types/struct.TxLogEntry.html) is created.
+	/// Returns the version and build details of the running wallet, for support to tell which
+	/// wallet a user runs. Along with the versions recorded at each step of a transaction, in
+	/// its [`TxLogEntry`](../grin_wallet_libwallet/types/struct.TxLogEntry.html) and in JSON
+	/// exports, this helps diagnose problems between wallets of different versions.
 	///
 	/// # Arguments
 	///
 	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
 	/// being used.
-	/// * `delete_unconfirmed` - if `false`, the check_repair process will be non-destructive, and
-	/// mostly limited to restoring missing outputs. It will leave unconfirmed transaction logs entries
-	/// and unconfirmed outputs intact. If `true`, the process will unlock all locked outputs,
-	/// restore all missing outputs, and mark any outputs that have been marked 'Spent' but are still
-	/// in the UTXO set as 'Unspent' (as can happen during a fork). It will also attempt to cancel any
-	/// transaction log entries associated with any locked outputs or outputs incorrectly marked 'Spent'.
-	/// Note this completely removes all outstanding transactions, so users should be very aware what
-	/// will happen if this flag is set. Note that if transactions/outputs are removed that later
-	/// confirm on the chain, another call to this function will restore them.
 	///
 	/// # Returns
-	/// * `Ok(())` if successful
+	/// * Ok with a [`BuildInfo`](../grin_wallet_libwallet/struct.BuildInfo.html)
 	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
-
+	///
 	/// # Example
 	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
 	/// ```
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
-	/// let mut api_owner = Owner::new(wallet.clone());
-	/// let result = api_owner.check_repair(
-	/// 	None,
-	/// 	false,
-	/// );
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.get_build_info(None);
 	///
-	/// if let Ok(_) = result {
-	///		// Wallet outputs should be consistent with what's on chain
-	///		// ...
+	/// if let Ok(info) = result {
+	///		println!("Grin Wallet {} ({:?})", info.version, info.git_hash);
 	/// }
 	/// ```
 
-	pub fn check_repair(
-		&self,
-		keychain_mask: Option<&SecretKey>,
-		delete_unconfirmed: bool,
-	) -> Result<(), Error> {
-		let mut w_lock = self.wallet_inst.lock();
+	pub fn get_build_info(&self, keychain_mask: Option<&SecretKey>) -> Result<BuildInfo, Error> {
+		let mut w_lock = self.lock_wallet()?;
 		let w = w_lock.lc_provider()?.wallet_inst()?;
-		owner::check_repair(&mut **w, keychain_mask, delete_unconfirmed)
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		Ok(build_info())
 	}
 
-	/// Retrieves the last known height known by the wallet. This is determined as follows:
-	/// * If the wallet can successfully contact its configured node, the reported node
-	/// height is returned, and the `updated_from_node` field in the response is `true`
-	/// * If the wallet cannot contact the node, this function returns the maximum height
-	/// of all outputs contained within the wallet, and the `updated_from_node` fields
-	/// in the response is set to false.
-	///
-	/// Clients should generally ensure the `updated_from_node` field is returned as
-	/// `true` before assuming the height for any operation.
+	/// Returns counters of what the wallet did since the process started: transactions sent and
+	/// received, the amounts and fees involved, errors returned through the API listeners by kind
+	/// and the average time transactions took to confirm. Operators of many wallets can scrape
+	/// and aggregate these without parsing each wallet's transaction log. The counters cover
+	/// every wallet opened in the process, and start again from zero when it restarts.
 	///
 	/// # Arguments
 	///
@@ -1196,9 +4480,7 @@ where
 	/// being used.
 	///
 	/// # Returns
-	/// * Ok with a  [`NodeHeightResult`](../grin_wallet_libwallet/types/struct.NodeHeightResult.html)
-	/// if successful. If the height result was obtained from the configured node,
-	/// `updated_from_node` will be set to `true`
+	/// * Ok with the [`WalletStatistics`](../grin_wallet_libwallet/struct.WalletStatistics.html)
 	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
 	///
 	/// # Example
@@ -1207,26 +4489,26 @@ where
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
 	/// let api_owner = Owner::new(wallet.clone());
-	/// let result = api_owner.node_height(None);
-	///
-	/// if let Ok(node_height_result) = result {
-	///		if node_height_result.updated_from_node {
-	///			//we can assume node_height_result.height is relatively safe to use
+	/// let result = api_owner.get_statistics(None);
 	///
-	///		}
-	///		//...
+	/// if let Ok(stats) = result {
+	///		println!("Sent {} and received {} txs", stats.txs_sent, stats.txs_received);
 	/// }
 	/// ```
 
-	pub fn node_height(
+	pub fn get_statistics(
 		&self,
 		keychain_mask: Option<&SecretKey>,
-	) -> Result<NodeHeightResult, Error> {
-		let mut w_lock = self.wallet_inst.lock();
+	) -> Result<WalletStatistics, Error> {
+		let mut w_lock = self.lock_wallet()?;
 		let w = w_lock.lc_provider()?.wallet_inst()?;
 		// Test keychain mask, to keep API consistent
 		let _ = w.keychain(keychain_mask)?;
-		owner::node_height(&mut **w, keychain_mask)
+		let mut stats = statistics();
+		if self.doctest_mode {
+			stats.started = Utc.ymd(2019, 1, 15).and_hms(16, 1, 26);
+		}
+		Ok(stats)
 	}
 }
 
@@ -1250,7 +4532,11 @@ macro_rules! doctest_helper_setup_doc_env {
 		use api::{Foreign, Owner};
 		use config::WalletConfig;
 		use impls::{DefaultLCProvider, DefaultWalletImpl, HTTPNodeClient};
-		use libwallet::{BlockFees, InitTxArgs, IssueInvoiceTxArgs, Slate, WalletInst};
+		use libwallet::{
+			BlockFees, ConsolidateArgs, FeatureFlags, InitTxArgs, InitTxRecipient, InitTxSendArgs,
+			IssueInvoiceTxArgs, OutputStatus, RetrieveOutputsQueryArgs, RetrieveTxQueryArgs, Slate,
+			TxQueryStatus, TxSortOrder, WalletInst,
+		};
 
 		let dir = tempdir().map_err(|e| format!("{:#?}", e)).unwrap();
 		let dir = dir
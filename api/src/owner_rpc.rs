@@ -16,16 +16,24 @@
 use uuid::Uuid;
 
 use crate::core::core::Transaction;
+use crate::impls::AuditLogExport;
+use crate::impls::WebhookDeadLetter;
 use crate::keychain::{Identifier, Keychain};
+use crate::libwallet::OutputDerivation;
+use crate::libwallet::ReservedKeys;
 use crate::libwallet::{
-	AcctPathMapping, ErrorKind, InitTxArgs, IssueInvoiceTxArgs, NodeClient, NodeHeightResult,
-	OutputCommitMapping, Slate, SlateVersion, TxLogEntry, VersionedSlate, WalletInfo,
-	WalletLCProvider,
+	AcctPathMapping, BuildInfo, ChainAuditReport, ConsolidateArgs, ErrorKind, FeatureFlags,
+	InitTxArgs, IssueInvoiceTxArgs, LongOperation, NodeClient, NodeConnectivity, NodeHeightResult,
+	NodeStatus, OperationStatus, OutputCommitMapping, PendingReceive, RemediationAction,
+	RetrieveOutputsQueryArgs, RetrieveTxQueryArgs, SignedTx, Slate, SlateVersion, StatusMessage,
+	TotpEnrollment, TxAnomaly, TxBatchResult, TxExportFormat, TxLogEntry, TxReplayReport,
+	UnsignedTx, VersionedSlate, WalletInfo, WalletLCProvider, WalletStatistics,
 };
 use crate::util::Mutex;
-use crate::{Owner, OwnerRpcS};
+use crate::{OperationRunner, Owner, OwnerRpcS, WalletUpdater};
 use easy_jsonrpc;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Public definition used to generate Owner jsonrpc api.
 /// * When running `grin-wallet owner_api` with defaults, the V2 api is available at
@@ -210,7 +218,7 @@ pub trait OwnerRpc {
 		{
 			"jsonrpc": "2.0",
 			"method": "retrieve_txs",
-			"params": [true, null, null],
+			"params": [true, null, null, null],
 			"id": 1
 		}
 		# "#
@@ -226,15 +234,22 @@ pub trait OwnerRpc {
 			{
 			  "amount_credited": "60000000000",
 			  "amount_debited": "0",
+			  "confirmation_height": null,
 			  "confirmation_ts": "2019-01-15T16:01:26Z",
 			  "confirmed": true,
+			  "creation_height": null,
 			  "creation_ts": "2019-01-15T16:01:26Z",
+			  "expired": false,
 			  "fee": null,
+			  "fluff": null,
 			  "id": 0,
+			  "kernel_excess": null,
+			  "label": null,
 			  "messages": null,
 			  "num_inputs": 0,
 			  "num_outputs": 1,
 			  "parent_key_id": "0200000000000000000000000000000000",
+			  "step_versions": [],
 			  "stored_tx": null,
 			  "tx_slate_id": null,
 			  "tx_type": "ConfirmedCoinbase"
@@ -242,15 +257,82 @@ pub trait OwnerRpc {
 			{
 			  "amount_credited": "60000000000",
 			  "amount_debited": "0",
+			  "confirmation_height": null,
 			  "confirmation_ts": "2019-01-15T16:01:26Z",
 			  "confirmed": true,
+			  "creation_height": null,
 			  "creation_ts": "2019-01-15T16:01:26Z",
+			  "expired": false,
 			  "fee": null,
+			  "fluff": null,
 			  "id": 1,
+			  "kernel_excess": null,
+			  "label": null,
 			  "messages": null,
 			  "num_inputs": 0,
 			  "num_outputs": 1,
 			  "parent_key_id": "0200000000000000000000000000000000",
+			  "step_versions": [],
+			  "stored_tx": null,
+			  "tx_slate_id": null,
+			  "tx_type": "ConfirmedCoinbase"
+			}
+		  ]
+		]
+	  }
+	}
+	# "#
+	# , false, 2, false, false, false);
+	```
+
+	Paging through the most recent transactions matching a query:
+
+	```
+		# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+		# r#"
+		{
+			"jsonrpc": "2.0",
+			"method": "retrieve_txs",
+			"params": [true, null, null, {
+				"offset": null,
+				"limit": 1,
+				"statuses": ["Coinbase"],
+				"min_creation_ts": null,
+				"max_creation_ts": null,
+				"sort_order": "Desc",
+				"account": null
+			}],
+			"id": 1
+		}
+		# "#
+		# ,
+		# r#"
+		{
+		"id": 1,
+		"jsonrpc": "2.0",
+	  "result": {
+		"Ok": [
+		  true,
+		  [
+			{
+			  "amount_credited": "60000000000",
+			  "amount_debited": "0",
+			  "confirmation_height": null,
+			  "confirmation_ts": "2019-01-15T16:01:26Z",
+			  "confirmed": true,
+			  "creation_height": null,
+			  "creation_ts": "2019-01-15T16:01:26Z",
+			  "expired": false,
+			  "fee": null,
+			  "fluff": null,
+			  "id": 1,
+			  "kernel_excess": null,
+			  "label": null,
+			  "messages": null,
+			  "num_inputs": 0,
+			  "num_outputs": 1,
+			  "parent_key_id": "0200000000000000000000000000000000",
+			  "step_versions": [],
 			  "stored_tx": null,
 			  "tx_slate_id": null,
 			  "tx_type": "ConfirmedCoinbase"
@@ -269,8 +351,188 @@ pub trait OwnerRpc {
 		refresh_from_node: bool,
 		tx_id: Option<u32>,
 		tx_slate_id: Option<Uuid>,
+		query: Option<RetrieveTxQueryArgs>,
 	) -> Result<(bool, Vec<TxLogEntry>), ErrorKind>;
 
+	/**
+	Networked version of [Owner::export_txs](struct.Owner.html#method.export_txs).
+
+	# Json rpc example
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "export_txs",
+		"params": [true, "Csv", {
+			"offset": null,
+			"limit": 1,
+			"statuses": ["Coinbase"],
+			"min_creation_ts": null,
+			"max_creation_ts": null,
+			"sort_order": "Desc"
+		}],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": "id,tx_slate_id,type,creation_ts,confirmation_ts,confirmed,amount_credited,amount_debited,fee,kernel_excess,stored_tx,label\n1,,ConfirmedCoinbase,2019-01-15T16:01:26+00:00,2019-01-15T16:01:26+00:00,true,60.000000000,0.000000000,,,,\n"
+		}
+	}
+	# "#
+	# , false, 2, false, false, false);
+	```
+	*/
+
+	fn export_txs(
+		&self,
+		refresh_from_node: bool,
+		format: TxExportFormat,
+		filter: RetrieveTxQueryArgs,
+	) -> Result<String, ErrorKind>;
+
+	/**
+	Networked version of [Owner::query_outputs](struct.Owner.html#method.query_outputs).
+
+	# Json rpc example
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "query_outputs",
+		"params": [true, {
+			"statuses": ["Unspent"],
+			"min_height": 2,
+			"max_height": null,
+			"account": null
+		}],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": [
+				true,
+				[
+					{
+						"commit": "087df32304c5d4ae8b2af0bc31e700019d722910ef87dd4eec3197b80b207e3045",
+						"output": {
+							"commit": "087df32304c5d4ae8b2af0bc31e700019d722910ef87dd4eec3197b80b207e3045",
+							"height": "2",
+							"is_coinbase": true,
+							"key_id": "0300000000000000000000000100000000",
+							"lock_height": "5",
+							"mmr_index": null,
+							"n_child": 1,
+							"root_key_id": "0200000000000000000000000000000000",
+							"status": "Unspent",
+							"tx_log_entry": 1,
+							"value": "60000000000"
+						}
+					}
+				]
+			]
+		}
+	}
+	# "#
+	# , false, 2, false, false, false);
+	```
+	*/
+
+	fn query_outputs(
+		&self,
+		refresh_from_node: bool,
+		query: RetrieveOutputsQueryArgs,
+	) -> Result<(bool, Vec<OutputCommitMapping>), ErrorKind>;
+
+	/**
+	Networked version of [Owner::get_output_derivation](struct.Owner.html#method.get_output_derivation).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_output_derivation",
+		"params": ["087df32304c5d4ae8b2af0bc31e700019d722910ef87dd4eec3197b80b207e3045"],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"account": "default",
+				"commit": "087df32304c5d4ae8b2af0bc31e700019d722910ef87dd4eec3197b80b207e3045",
+				"key_id": "0300000000000000000000000100000000",
+				"key_index": 1,
+				"parent_key_id": "0200000000000000000000000000000000",
+				"path": "m/0/0/1",
+				"switch_commitment_type": "Regular"
+			}
+		}
+	}
+	# "#
+	# , false, 2, false, false, false);
+	```
+	*/
+
+	fn get_output_derivation(&self, commit: String) -> Result<OutputDerivation, ErrorKind>;
+
+	/**
+	Networked version of [Owner::reserve_derivation_indices](struct.Owner.html#method.reserve_derivation_indices).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "reserve_derivation_indices",
+		"params": [3],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"account": "default",
+				"count": 3,
+				"first_index": 2,
+				"key_ids": [
+					"0300000000000000000000000200000000",
+					"0300000000000000000000000300000000",
+					"0300000000000000000000000400000000"
+				],
+				"parent_key_id": "0200000000000000000000000000000000"
+			}
+		}
+	}
+	# "#
+	# , false, 2, false, false, false);
+	```
+	*/
+
+	fn reserve_derivation_indices(&self, count: u32) -> Result<ReservedKeys, ErrorKind>;
+
 	/**
 	Networked version of [Owner::retrieve_summary_info](struct.Owner.html#method.retrieve_summary_info).
 
@@ -406,6 +668,92 @@ pub trait OwnerRpc {
 
 	fn init_send_tx(&self, args: InitTxArgs) -> Result<VersionedSlate, ErrorKind>;
 
+	/**
+	Networked version of [Owner::init_send_tx_multi](struct.Owner.html#method.init_send_tx_multi).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "init_send_tx_multi",
+		"params": {
+			"args": {
+				"src_acct_name": null,
+				"amount": "2000000000",
+				"minimum_confirmations": 2,
+				"max_outputs": 500,
+				"num_change_outputs": 1,
+				"selection_strategy_is_use_all": false,
+				"send_args": null,
+				"additional_recipients": [
+					{
+						"amount": "1000000000",
+						"dest": "http://localhost:3416"
+					}
+				]
+			}
+		},
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Err": {
+				"GenericError": "additional recipients require send arguments"
+			}
+		}
+	}
+	# "#
+	# , false, 4, false, false, false);
+	```
+	*/
+
+	fn init_send_tx_multi(&self, args: InitTxArgs) -> Result<Vec<VersionedSlate>, ErrorKind>;
+
+	/**
+	Networked version of [Owner::consolidate](struct.Owner.html#method.consolidate).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "consolidate",
+		"params": {
+			"args": {
+				"src_acct_name": null,
+				"minimum_confirmations": 10,
+				"num_outputs": 2,
+				"max_weight": 4000,
+				"max_txs": 5,
+				"post_tx": true,
+				"fluff": false
+			}
+		},
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": []
+		}
+	}
+	# "#
+	# , false, 0, false, false, false);
+	```
+	*/
+
+	fn consolidate(&self, args: ConsolidateArgs) -> Result<Vec<VersionedSlate>, ErrorKind>;
+
 	/**
 		Networked version of [Owner::issue_invoice_tx](struct.Owner.html#method.issue_invoice_tx).
 
@@ -799,7 +1147,8 @@ pub trait OwnerRpc {
 					"message_sig": null
 				}
 			]
-		}
+		},
+		null
 		]
 	}
 	# "#
@@ -882,28 +1231,317 @@ pub trait OwnerRpc {
 	# , false, 5, true, true, false);
 	```
 	 */
-	fn finalize_tx(&self, slate: VersionedSlate) -> Result<VersionedSlate, ErrorKind>;
+	fn finalize_tx(
+		&self,
+		slate: VersionedSlate,
+		fluff: Option<bool>,
+	) -> Result<VersionedSlate, ErrorKind>;
 
 	/**
-	Networked version of [Owner::post_tx](struct.Owner.html#method.post_tx).
+	Networked version of [Owner::prepare_send](struct.Owner.html#method.prepare_send).
 
 	```
 	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
 	# r#"
 	{
 		"jsonrpc": "2.0",
+		"method": "prepare_send",
+		"params": {
+			"args": {
+				"src_acct_name": null,
+				"amount": "6000000000",
+				"minimum_confirmations": 2,
+				"max_outputs": 500,
+				"num_change_outputs": 0,
+				"selection_strategy_is_use_all": false,
+				"message": null,
+				"target_slate_version": null,
+				"send_args": null
+			}
+		},
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
 		"id": 1,
-		"method": "post_tx",
-		"params": [
-		{
-			"offset": "d202964900000000d302964900000000d402964900000000d502964900000000",
-			"body": {
-				"inputs": [
-					{
-						"features": "Coinbase",
-						"commit": "087df32304c5d4ae8b2af0bc31e700019d722910ef87dd4eec3197b80b207e3045"
-					},
-					{
+		"jsonrpc": "2.0",
+		"result": {
+			"Err": {
+				"GenericError": "At least one change output is needed"
+			}
+		}
+	}
+	# "#
+	# , false, 1, false, false, false);
+	```
+	*/
+
+	fn prepare_send(&self, args: InitTxArgs) -> Result<UnsignedTx, ErrorKind>;
+
+	/**
+	Networked version of [Owner::sign_offline](struct.Owner.html#method.sign_offline).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "sign_offline",
+		"params": {
+			"unsigned": {
+				"slate": {
+					"version_info": {
+						"version": 2,
+						"orig_version": 2,
+						"block_header_version": 1
+					},
+					"num_participants": 2,
+					"id": "0436430c-2b02-624c-2032-570501212b00",
+					"tx": {
+						"offset": "0000000000000000000000000000000000000000000000000000000000000000",
+						"body": {
+							"inputs": [],
+							"outputs": [],
+							"kernels": []
+						}
+					},
+					"amount": "60000000000",
+					"fee": "7000000",
+					"height": "5",
+					"lock_height": "0",
+					"ttl_cutoff_height": null,
+					"participant_data": []
+				},
+				"parent_key_id": "0200000000000000000000000000000000",
+				"inputs": [],
+				"change": [],
+				"message": null
+			}
+		},
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Err": {
+				"GenericError": "Transaction has no inputs to sign"
+			}
+		}
+	}
+	# "#
+	# , false, 1, false, false, false);
+	```
+	*/
+
+	fn sign_offline(&self, unsigned: UnsignedTx) -> Result<VersionedSlate, ErrorKind>;
+
+	/**
+	Networked version of [Owner::finalize_offline](struct.Owner.html#method.finalize_offline).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "finalize_offline",
+		"params": {
+			"slate": {
+				"version_info": {
+					"version": 2,
+					"orig_version": 2,
+					"block_header_version": 1
+				},
+				"num_participants": 2,
+				"id": "0436430c-2b02-624c-2032-570501212b00",
+				"tx": {
+					"offset": "0000000000000000000000000000000000000000000000000000000000000000",
+					"body": {
+						"inputs": [],
+						"outputs": [],
+						"kernels": []
+					}
+				},
+				"amount": "60000000000",
+				"fee": "7000000",
+				"height": "5",
+				"lock_height": "0",
+				"ttl_cutoff_height": null,
+				"participant_data": []
+			}
+		},
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Err": {
+				"GenericError": "Slate hasn't been signed by the recipient"
+			}
+		}
+	}
+	# "#
+	# , false, 1, false, false, false);
+	```
+	*/
+
+	fn finalize_offline(&self, slate: VersionedSlate) -> Result<SignedTx, ErrorKind>;
+
+	/**
+	Networked version of [Owner::broadcast_signed](struct.Owner.html#method.broadcast_signed).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "broadcast_signed",
+		"params": {
+			"signed": {
+				"slate": {
+					"version_info": {
+						"version": 2,
+						"orig_version": 2,
+						"block_header_version": 1
+					},
+					"num_participants": 2,
+					"id": "0436430c-2b02-624c-2032-570501212b00",
+					"tx": {
+						"offset": "0000000000000000000000000000000000000000000000000000000000000000",
+						"body": {
+							"inputs": [],
+							"outputs": [],
+							"kernels": []
+						}
+					},
+					"amount": "60000000000",
+					"fee": "7000000",
+					"height": "5",
+					"lock_height": "0",
+					"ttl_cutoff_height": null,
+					"participant_data": []
+				},
+				"change": []
+			},
+			"fluff": false
+		},
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Err": {
+				"TransactionDoesntExist": "0436430c-2b02-624c-2032-570501212b00"
+			}
+		}
+	}
+	# "#
+	# , false, 1, false, false, false);
+	```
+	*/
+
+	fn broadcast_signed(&self, signed: SignedTx, fluff: bool) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::post_tx](struct.Owner.html#method.post_tx).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"id": 1,
+		"method": "post_tx",
+		"params": [
+		{
+			"offset": "d202964900000000d302964900000000d402964900000000d502964900000000",
+			"body": {
+				"inputs": [
+					{
+						"features": "Coinbase",
+						"commit": "087df32304c5d4ae8b2af0bc31e700019d722910ef87dd4eec3197b80b207e3045"
+					},
+					{
+						"features": "Coinbase",
+						"commit": "08e1da9e6dc4d6e808a718b2f110a991dd775d65ce5ae408a4e1f002a4961aa9e7"
+					}
+				],
+				"outputs": [
+					{
+						"features": "Plain",
+						"commit": "099b48cfb1f80a2347dc89818449e68e76a3c6817a532a8e9ef2b4a5ccf4363850",
+						"proof": "7ebcd2ed9bf5fb29854033ba3d0e720613bdf7dfacc586d2f6084c1cde0a2b72e955d4ce625916701dc7c347132f40d0f102a34e801d745ee54b49b765d08aae0bb801c60403e57cafade3b4b174e795b633ab9e402b5b1b6e1243fd10bbcf9368a75cb6a6c375c7bdf02da9e03b7f210df45d942e6fba2729cd512a372e6ed91a1b5c9c22831febea843e3f85adcf198f39ac9f7b73b70c60bfb474aa69878ea8d1d32fef30166b59caacaec3fd024de29a90f1587e08d2c36b3d5c560cabf658e212e0a40a4129b3e5c35557058def5551f4eb395759597ba808b3c34eac3bfb9716e4480d7931c5789c538463ec75be0eb807c894047fda6cbcd22682d3c6d3823cb330f090a2099e3510a3706b57d46c95224394d7f1c0a20d99cc314b8f1d9d02668e2e435f62e1194de0be6a1f50f72ed777ed51c8819f527a94918d1aa8df6461e98ed4c2b18210de50fbcf8c3df210bfe326d41f1dc0ad748cb0320ae28401c85ab4f7dcb99d88a052e95dc85b76d22b36cabd60e06ab84bb7e4ddfdab9c9730c8a986583237ed1ecbb323ee8e79b8cadca4b438b7c09531670b471dda6a2eb3e747916c88ce7d9d8e1b7f61660eeb9e5a13c60e4dfe89d1177d81d6f6570fda85158e646a15f1e8b9e977494dc19a339aab2e0e478670d80092d6ba37646e60714ef64eb4a3d37fe15f8f38b59114af34b235489eed3f69b7781c5fe496eb43ffe245c14bd740f745844a38cf0d904347aaa2b64f51add18822dac009d8b63fa3e4c9b1fa72187f9a4acba1ab315daa1b04c9a41f3be846ac420b37990e6c947a16cc9d5c0671b292bf77d7d8b8974d2ad3afae95ba7772c37432840f53a007f31e0195f3abdf100c4477723cc6c6d5da14894a73dfac342833731036487488fdade7b9d556c06f26173b6b67598d3769447ce2828d71dd45ac5af436c6b0"
+					},
+					{
+						"features": "Plain",
+						"commit": "0812276cc788e6870612296d926cba9f0e7b9810670710b5a6e6f1ba006d395774",
+						"proof": "dcff6175390c602bfa92c2ffd1a9b2d84dcc9ea941f6f317bdd0f875244ef23e696fd17c71df79760ce5ce1a96aab1d15dd057358dc835e972febeb86d50ccec0dad7cfe0246d742eb753cf7b88c045d15bc7123f8cf7155647ccf663fca92a83c9a65d0ed756ea7ebffd2cac90c380a102ed9caaa355d175ed0bf58d3ac2f5e909d6c447dfc6b605e04925c2b17c33ebd1908c965a5541ea5d2ed45a0958e6402f89d7a56df1992e036d836e74017e73ccad5cb3a82b8e139e309792a31b15f3ffd72ed033253428c156c2b9799458a25c1da65b719780a22de7fe7f437ae2fccd22cf7ea357ab5aa66a5ef7d71fb0dc64aa0b5761f68278062bb39bb296c787e4cabc5e2a2933a416ce1c9a9696160386449c437e9120f7bb26e5b0e74d1f2e7d5bcd7aafb2a92b87d1548f1f911fb06af7bd6cc13cee29f7c9cb79021aed18186272af0e9d189ec107c81a8a3aeb4782b0d950e4881aa51b776bb6844b25bce97035b48a9bdb2aea3608687bcdd479d4fa998b5a839ff88558e4a29dff0ed13b55900abb5d439b70793d902ae9ad34587b18c919f6b875c91d14deeb1c373f5e76570d59a6549758f655f1128a54f162dfe8868e1587028e26ad91e528c5ae7ee9335fa58fb59022b5de29d80f0764a9917390d46db899acc6a5b416e25ecc9dccb7153646addcc81cadb5f0078febc7e05d7735aba494f39ef05697bbcc9b47b2ccc79595d75fc13c80678b5e237edce58d731f34c05b1ddcaa649acf2d865bbbc3ceda10508bcdd29d0496744644bf1c3516f6687dfeef5649c7dff90627d642739a59d91a8d1d0c4dc55d74a949e1074427664b467992c9e0f7d3af9d6ea79513e8946ddc0d356bac49878e64e6a95b0a30214214faf2ce317fa622ff3266b32a816e10a18e6d789a5da1f23e67b4f970a68a7bcd9e18825ee274b0483896a40"
+					}
+				],
+				"kernels": [
+					{
+						"features": "Plain",
+						"fee": "7000000",
+						"lock_height": "0",
+						"excess": "09bac6083b05a32a9d9b37710c70dd0a1ef9329fde0848558976b6f1b81d80ceed",
+						"excess_sig": "66074d25a751c4743342c90ad8ead9454daa00d9b9aed29bca321036d16c4b4da0e9c180a26b88565afcd269a7ac98f896c8db3dcbd48ab69443e8eac3beb3a4"
+					}
+				]
+			}
+		},
+		false
+		]
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# , false, 5, true, true, true);
+	```
+	 */
+
+	fn post_tx(&self, tx: &Transaction, fluff: Option<bool>) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::post_tx_checked](struct.Owner.html#method.post_tx_checked).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"id": 1,
+		"method": "post_tx_checked",
+		"params": [
+		{
+			"offset": "d202964900000000d302964900000000d402964900000000d502964900000000",
+			"body": {
+				"inputs": [
+					{
+						"features": "Coinbase",
+						"commit": "087df32304c5d4ae8b2af0bc31e700019d722910ef87dd4eec3197b80b207e3045"
+					},
+					{
 						"features": "Coinbase",
 						"commit": "08e1da9e6dc4d6e808a718b2f110a991dd775d65ce5ae408a4e1f002a4961aa9e7"
 					}
@@ -931,6 +1569,7 @@ pub trait OwnerRpc {
 				]
 			}
 		},
+		false,
 		false
 		]
 	}
@@ -949,7 +1588,12 @@ pub trait OwnerRpc {
 	```
 	 */
 
-	fn post_tx(&self, tx: &Transaction, fluff: bool) -> Result<(), ErrorKind>;
+	fn post_tx_checked(
+		&self,
+		tx: &Transaction,
+		fluff: Option<bool>,
+		replace: bool,
+	) -> Result<(), ErrorKind>;
 
 	/**
 	Networked version of [Owner::cancel_tx](struct.Owner.html#method.cancel_tx).
@@ -981,41 +1625,243 @@ pub trait OwnerRpc {
 	fn cancel_tx(&self, tx_id: Option<u32>, tx_slate_id: Option<Uuid>) -> Result<(), ErrorKind>;
 
 	/**
-	Networked version of [Owner::get_stored_tx](struct.Owner.html#method.get_stored_tx).
+	Networked version of [Owner::cancel_txs](struct.Owner.html#method.cancel_txs).
+
 
 	```
 	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
 	# r#"
 	{
 		"jsonrpc": "2.0",
-		"method": "get_stored_tx",
+		"method": "cancel_txs",
+		"params": [[5, 42], null],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
 		"id": 1,
-		"params": [
-			{
-				"amount_credited": "59993000000",
-				"amount_debited": "120000000000",
-				"confirmation_ts": "2019-01-15T16:01:26Z",
-				"confirmed": false,
-				"creation_ts": "2019-01-15T16:01:26Z",
-				"fee": "7000000",
-				"id": 5,
-				"messages": {
-					"messages": [
-						{
-							"id": "0",
-							"message": null,
-							"message_sig": null,
-							"public_key": "033ac2158fa0077f087de60c19d8e431753baa5b63b6e1477f05a2a6e7190d4592"
-						},
-						{
-							"id": "1",
-							"message": null,
-							"message_sig": null,
-							"public_key": "024f9bc78c984c78d6e916d3a00746aa30fa1172124c8dbc0cbddcb7b486719bc7"
-						}
-					]
-				},
-				"num_inputs": 2,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": [
+				{
+					"error": null,
+					"tx_id": 5
+				},
+				{
+					"error": "Transaction 42 doesn't exist",
+					"tx_id": 42
+				}
+			]
+		}
+	}
+	# "#
+	# , false, 5, true, true, false);
+	```
+	 */
+	fn cancel_txs(
+		&self,
+		tx_ids: Option<Vec<u32>>,
+		filter: Option<RetrieveTxQueryArgs>,
+	) -> Result<Vec<TxBatchResult>, ErrorKind>;
+
+	/**
+	Networked version of [Owner::post_txs](struct.Owner.html#method.post_txs).
+
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "post_txs",
+		"params": [[5, 42], null],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": [
+				{
+					"error": null,
+					"tx_id": 5
+				},
+				{
+					"error": "Transaction 42 doesn't exist",
+					"tx_id": 42
+				}
+			]
+		}
+	}
+	# "#
+	# , false, 5, true, true, true);
+	```
+	 */
+	fn post_txs(
+		&self,
+		tx_ids: Vec<u32>,
+		fluff: Option<bool>,
+	) -> Result<Vec<TxBatchResult>, ErrorKind>;
+
+	/**
+	Networked version of [Owner::retrieve_pending_receives](struct.Owner.html#method.retrieve_pending_receives).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "retrieve_pending_receives",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": []
+		}
+	}
+	# "#
+	# , false, 1, false, false, false);
+	```
+	 */
+	fn retrieve_pending_receives(&self) -> Result<Vec<PendingReceive>, ErrorKind>;
+
+	/**
+	Networked version of [Owner::approve_receive](struct.Owner.html#method.approve_receive).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "approve_receive",
+		"params": ["0436430c-2b02-624c-2032-570501212b00"],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Err": {
+				"TransactionDoesntExist": "0436430c-2b02-624c-2032-570501212b00"
+			}
+		}
+	}
+	# "#
+	# , false, 1, false, false, false);
+	```
+	 */
+	fn approve_receive(&self, tx_slate_id: Uuid) -> Result<VersionedSlate, ErrorKind>;
+
+	/**
+	Networked version of [Owner::reject_receive](struct.Owner.html#method.reject_receive).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "reject_receive",
+		"params": ["0436430c-2b02-624c-2032-570501212b00"],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Err": {
+				"TransactionDoesntExist": "0436430c-2b02-624c-2032-570501212b00"
+			}
+		}
+	}
+	# "#
+	# , false, 1, false, false, false);
+	```
+	 */
+	fn reject_receive(&self, tx_slate_id: Uuid) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::set_tx_label](struct.Owner.html#method.set_tx_label).
+
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "set_tx_label",
+		"params": [0, "Mining reward"],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# , false, 1, false, false, false);
+	```
+	 */
+	fn set_tx_label(&self, tx_id: u32, label: Option<String>) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::get_stored_tx](struct.Owner.html#method.get_stored_tx).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_stored_tx",
+		"id": 1,
+		"params": [
+			{
+				"amount_credited": "59993000000",
+				"amount_debited": "120000000000",
+				"confirmation_ts": "2019-01-15T16:01:26Z",
+				"confirmed": false,
+				"creation_ts": "2019-01-15T16:01:26Z",
+				"fee": "7000000",
+				"id": 5,
+				"label": null,
+				"messages": {
+					"messages": [
+						{
+							"id": "0",
+							"message": null,
+							"message_sig": null,
+							"public_key": "033ac2158fa0077f087de60c19d8e431753baa5b63b6e1477f05a2a6e7190d4592"
+						},
+						{
+							"id": "1",
+							"message": null,
+							"message_sig": null,
+							"public_key": "024f9bc78c984c78d6e916d3a00746aa30fa1172124c8dbc0cbddcb7b486719bc7"
+						}
+					]
+				},
+				"num_inputs": 2,
 				"num_outputs": 1,
 				"parent_key_id": "0200000000000000000000000000000000",
 				"stored_tx": "0436430c-2b02-624c-2032-570501212b00.grintx",
@@ -1028,133 +1874,948 @@ pub trait OwnerRpc {
 	# ,
 	# r#"
 	{
-		"jsonrpc": "2.0",
+		"jsonrpc": "2.0",
+		"id": 1,
+		"result": {
+			"Ok": {
+				"body": {
+					"inputs": [
+						{
+							"commit": "087df32304c5d4ae8b2af0bc31e700019d722910ef87dd4eec3197b80b207e3045",
+							"features": "Coinbase"
+						},
+						{
+							"commit": "08e1da9e6dc4d6e808a718b2f110a991dd775d65ce5ae408a4e1f002a4961aa9e7",
+							"features": "Coinbase"
+						}
+					],
+					"kernels": [
+						{
+							"excess": "000000000000000000000000000000000000000000000000000000000000000000",
+							"excess_sig": "00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000",
+							"features": "Plain",
+							"fee": "7000000",
+							"lock_height": "0"
+						}
+					],
+					"outputs": [
+						{
+							"commit": "099b48cfb1f80a2347dc89818449e68e76a3c6817a532a8e9ef2b4a5ccf4363850",
+							"features": "Plain",
+							"proof": "29701ceae262cac77b79b868c883a292e61e6de8192b868edcd1300b0973d91396b156ace6bd673402a303de10ddd8a5e6b7f17ba6557a574a672bd04cc273ab04ed8e2ca80bac483345c0ec843f521814ce1301ec9adc38956a12b4d948acce71295a4f52bcdeb8a1c9f2d6b2da5d731262a5e9c0276ef904df9ef8d48001420cd59f75a2f1ae5c7a1c7c6b9f140e7613e52ef9e249f29f9340b7efb80699e460164324616f98fd4cde3db52497c919e95222fffeacb7e65deca7e368a80ce713c19de7da5369726228ee336f5bd494538c12ccbffeb1b9bfd5fc8906d1c64245b516f103fa96d9c56975837652c1e0fa5803d7ccf1147d8f927e36da717f7ad79471dbe192f5f50f87a79fc3fe030dba569b634b92d2cf307993cce545633af263897cd7e6ebf4dcafb176d07358bdc38d03e45a49dfa9c8c6517cd68d167ffbf6c3b4de0e2dd21909cbad4c467b84e5700be473a39ac59c669d7c155c4bcab9b8026eea3431c779cd277e4922d2b9742e1f6678cbe869ec3b5b7ef4132ddb6cdd06cf27dbeb28be72b949fa897610e48e3a0d789fd2eea75abc97b3dc7e00e5c8b3d24e40c6f24112adb72352b89a2bef0599345338e9e76202a3c46efa6370952b2aca41aadbae0ea32531acafcdab6dd066d769ebf50cf4f3c0a59d2d5fa79600a207b9417c623f76ad05e8cccfcd4038f9448bc40f127ca7c0d372e46074e334fe49f5a956ec0056f4da601e6af80eb1a6c4951054869e665b296d8c14f344ca2dc5fdd5df4a3652536365a1615ad9b422165c77bf8fe65a835c8e0c41e070014eb66ef8c525204e990b3a3d663c1e42221b496895c37a2f0c1bf05e91235409c3fe3d89a9a79d6c78609ab18a463311911f71fa37bb73b15fcd38143d1404fd2ce81004dc7ff89cf1115dcc0c35ce1c1bf9941586fb959770f2618ccb7118a7"
+						},
+						{
+							"commit": "0812276cc788e6870612296d926cba9f0e7b9810670710b5a6e6f1ba006d395774",
+							"features": "Plain",
+							"proof": "284b9f9199411c6bbf7227eae15cc9fa7ed30534af3ecff85b0d016da329cae1f1edf79f01426434cb90afcc2f0a1fb296e5c51a91b5e57a1f0230fda4f8c5950e7986fa379b99d64b6039a86cc7e35e040ba192b781043959851268ca9874a918805ea958c84f7fee8d3ab4262f032f5a3f840ebcdd273b29be810114e6e86a959d8e4c080572e3ef249edd6ad68503ec3bc4486548520ea2775a41aea67aac99945fce9e7a7769d71f893ad7f0d0108692f6b6852312cae6f9857063055bda59dce521927c70040b8026a41b6517cae0a1c947ca244984a5c0adf7c6483b0939346c48f61cac37d501f46a1c5878b67cee0d0723f4eeadc9f5d71ed5e9f33b4294b58d3ebeefeaa13f20357599be549ce18e6e2eb1d50e1235ccc40ec9184c68a623741a7238ce69aa3a1d25156b3b7eb38fdd6fbe5473979feee3317df279c60d48a289826aa4c76dbce24d526890d4e6e2f83e80f674a1248fc1dc037d982009012ae1133f5e158ae6cdadb18c8d53e4a8ae5595c758782c67aa0c20f146d52085cf45a35794cec45702830f8952a69744718fbe6fe0d3da66e348dd3473a0aced7080fbf5494c3e7e141916f3b135b33277f998fcd9acfbca8709814866e9838a5dcda4c29422cf157293e6fc2ccc2d25423517843bd8e21c61ced72312c0b48814c312021b0d31598d2389b0b329baa1169922a4c34173dd5f540545be5066a0f291f1a870e1aaff94c19f0a855254882a17984baeda08e8ead53d1563e9ee4bc36742789cef4f3ab158054d7ddfe2a2b37b5a8a30be84ff7df9a7d758a9b76751a3622057721a3ec5b873582d0c91cd1febbc153662739854ea15c9903023c193c6b5768b55484108f897cdc47d13a9088b32558e57f12f5807668649c99a17b51905127340e8b49c42775f1b3cab072dfad33d33cc1e4ba5964d728ed0a905"
+						}
+					]
+				},
+				"offset": "d202964900000000d302964900000000d402964900000000d502964900000000"
+			}
+		}
+	}
+	# "#
+	# , false, 5, true, true, false);
+	```
+	 */
+	fn get_stored_tx(&self, tx: &TxLogEntry) -> Result<Option<Transaction>, ErrorKind>;
+
+	/**
+	Networked version of [Owner::replay_tx](struct.Owner.html#method.replay_tx).
+
+	There's no transaction to replay in this example.
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "replay_tx",
+		"params": [1, null],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Err": {
+				"TransactionDoesntExist": "1"
+			}
+		}
+	}
+	# "#
+	# , false, 0, false, false, false);
+	```
+	 */
+	fn replay_tx(
+		&self,
+		tx_id: Option<u32>,
+		tx_slate_id: Option<Uuid>,
+	) -> Result<TxReplayReport, ErrorKind>;
+
+	/**
+	Networked version of [Owner::verify_slate_messages](struct.Owner.html#method.verify_slate_messages).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "verify_slate_messages",
+		"id": 1,
+		"params": [ {
+				"amount": "6000000000",
+				"fee": "8000000",
+				"height": "4",
+				"id": "0436430c-2b02-624c-2032-570501212b00",
+				"lock_height": "4",
+				"num_participants": 2,
+				"participant_data": [
+				{
+					"id": "0",
+					"message": "my message",
+					"message_sig": "8f07ddd5e9f5179cff19486034181ed76505baaad53e5d994064127b56c5841b1d4c1358be398f801eb90d933774b5218fa7e769b11c4c640402253353656f75",
+					"part_sig": null,
+					"public_blind_excess": "034b4df2f0558b73ea72a1ca5c4ab20217c66bbe0829056fca7abe76888e9349ee",
+					"public_nonce": "031b84c5567b126440995d3ed5aaba0565d71e1834604819ff9c17f5e9d5dd078f"
+				}
+				],
+				"tx": {
+					"body": {
+						"inputs": [
+						{
+							"commit": "08e1da9e6dc4d6e808a718b2f110a991dd775d65ce5ae408a4e1f002a4961aa9e7",
+							"features": "Coinbase"
+						}
+						],
+						"kernels": [
+						{
+							"excess": "000000000000000000000000000000000000000000000000000000000000000000",
+							"excess_sig": "00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000",
+							"features": "HeightLocked",
+							"fee": "8000000",
+							"lock_height": "4"
+						}
+						],
+						"outputs": [
+						{
+							"commit": "094be57c91787fc2033d5d97fae099f1a6ddb37ea48370f1a138f09524c767fdd3",
+							"features": "Plain",
+							"proof": "2a42e9e902b70ce44e1fccb14de87ee0a97100bddf12c6bead1b9c5f4eb60300f29c13094fa12ffeee238fb4532b18f6b61cf51b23c1c7e1ad2e41560dc27edc0a2b9e647a0b3e4e806fced5b65e61d0f1f5197d3e2285c632d359e27b6b9206b2caffea4f67e0c7a2812e7a22c134b98cf89bd43d9f28b8bec25cce037a0ac5b1ae8f667e54e1250813a5263004486b4465ad4e641ab2b535736ea26535a11013564f08f483b7dab1c2bcc3ee38eadf2f7850eff7e3459a4bbabf9f0cf6c50d0c0a4120565cd4a2ce3e354c11721cd695760a24c70e0d5a0dfc3c5dcd51dfad6de2c237a682f36dc0b271f21bb3655e5333016aaa42c2efa1446e5f3c0a79ec417c4d30f77556951cb0f05dbfafb82d9f95951a9ea241fda2a6388f73ace036b98acce079f0e4feebccc96290a86dcc89118a901210b245f2d114cf94396e4dbb461e82aa26a0581389707957968c7cdc466213bb1cd417db207ef40c05842ab67a01a9b96eb1430ebc26e795bb491258d326d5174ad549401059e41782121e506744af8af9d8e493644a87d613600888541cbbe538c625883f3eb4aa3102c5cfcc25de8e97af8927619ce6a731b3b8462d51d993066b935b0648d2344ad72e4fd70f347fbd81041042e5ea31cc7b2e3156a920b80ecba487b950ca32ca95fae85b759c936246ecf441a9fdd95e8fee932d6782cdec686064018c857efc47fb4b2a122600d5fdd79af2486f44df7e629184e1c573bc0a9b3feb40b190ef2861a1ab45e2ac2201b9cd42e495deea247269820ed32389a2810ad6c0f9a296d2a2d9c54089fed50b7f5ecfcd33ab9954360e1d7f5598c32128cfcf2a1d8bf14616818da8a5343bfa88f0eedf392e9d4ab1ace1b60324129cd4852c2e27813a9cf71a6ae6229a4fcecc1a756b3e664c5f50af333082616815a3bec8fc0b75b8e4e767d719"
+						}
+						]
+					},
+					"offset": "d202964900000000d302964900000000d402964900000000d502964900000000"
+				},
+				"version_info": {
+					"orig_version": 2,
+					"version": 2,
+					"block_header_version": 2
+				}
+			}
+		]
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"id": 1,
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# ,false, 5 ,true, false, false);
+	```
+	*/
+	fn verify_slate_messages(&self, slate: VersionedSlate) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::restore](struct.Owner.html#method.restore).
+
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "restore",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# , false, 1, false, false, false);
+	```
+	 */
+	fn restore(&self) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::check_repair](struct.Owner.html#method.check_repair).
+
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "check_repair",
+		"params": [false, false],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# , false, 1, false, false, false);
+	```
+	 */
+	fn check_repair(&self, delete_unconfirmed: bool, full: bool) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::audit_chain](struct.Owner.html#method.audit_chain).
+
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "audit_chain",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"confirmed_unmarked_outputs": [],
+				"height": "1",
+				"missing_outputs": [],
+				"spent_unmarked_outputs": [],
+				"unconfirmed_txs_on_chain": [],
+				"unknown_outputs": [],
+				"unspent_marked_spent_outputs": []
+			}
+		}
+	}
+	# "#
+	# , false, 1, false, false, false);
+	```
+	 */
+	fn audit_chain(&self) -> Result<ChainAuditReport, ErrorKind>;
+
+	/**
+	Networked version of [Owner::node_height](struct.Owner.html#method.node_height).
+
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "node_height",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"height": "5",
+				"updated_from_node": true
+			}
+		}
+	}
+	# "#
+	# , false, 5, false, false, false);
+	```
+	 */
+	fn node_height(&self) -> Result<NodeHeightResult, ErrorKind>;
+
+	/**
+	Networked version of [Owner::node_status](struct.Owner.html#method.node_status).
+
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "node_status",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"active_node": "node",
+				"nodes": [
+					{
+						"url": "node",
+						"healthy": null,
+						"last_error": null,
+						"last_checked": null
+					}
+				]
+			}
+		}
+	}
+	# "#
+	# , false, 0, false, false, false);
+	```
+	 */
+	fn node_status(&self) -> Result<NodeStatus, ErrorKind>;
+
+	/**
+	Networked version of [Owner::get_node_status](struct.Owner.html#method.get_node_status).
+
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_node_status",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"node_url": "node",
+				"reachable": true,
+				"chain_height": 5,
+				"node_version": null,
+				"block_header_version": null,
+				"last_error": null
+			}
+		}
+	}
+	# "#
+	# , false, 5, false, false, false);
+	```
+	 */
+	fn get_node_status(&self) -> Result<NodeConnectivity, ErrorKind>;
+
+	/**
+	Networked version of [Owner::list_anomalies](struct.Owner.html#method.list_anomalies).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "list_anomalies",
+		"params": [false, 0],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": [
+				false,
+				[
+					{
+						"description": "Transaction was never finalized, and its inputs remain locked",
+						"kind": "StuckUnconfirmed",
+						"suggested_action": "Cancel",
+						"tx_id": 5,
+						"tx_slate_id": "0436430c-2b02-624c-2032-570501212b00"
+					}
+				]
+			]
+		}
+	}
+	# "#
+	# , false, 5, true, true, false);
+	```
+	 */
+	fn list_anomalies(
+		&self,
+		refresh_from_node: bool,
+		stuck_after_mins: u64,
+	) -> Result<(bool, Vec<TxAnomaly>), ErrorKind>;
+
+	/**
+	Networked version of [Owner::remediate](struct.Owner.html#method.remediate).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "remediate",
+		"params": [5, "Cancel"],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# , false, 5, true, true, false);
+	```
+	 */
+	fn remediate(&self, tx_id: u32, action: RemediationAction) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::get_feature_flags](struct.Owner.html#method.get_feature_flags).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_feature_flags",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"experimental_swaps": false,
+				"invoice_flow": true,
+				"payment_proofs_required": false,
+				"seed_backup_check": false
+			}
+		}
+	}
+	# "#
+	# , false, 0, false, false, false);
+	```
+	 */
+	fn get_feature_flags(&self) -> Result<FeatureFlags, ErrorKind>;
+
+	/**
+	Networked version of [Owner::set_feature_flags](struct.Owner.html#method.set_feature_flags).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "set_feature_flags",
+		"params": [
+			{
+				"experimental_swaps": false,
+				"invoice_flow": false,
+				"payment_proofs_required": false
+			}
+		],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# , false, 0, false, false, false);
+	```
+	 */
+	fn set_feature_flags(&self, flags: FeatureFlags) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::verify_seed_backup](struct.Owner.html#method.verify_seed_backup).
+
+	The wallet in this example was recovered from a phrase, so has none to verify.
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "verify_seed_backup",
+		"params": [
+			[[3, "fame"], [7, "lamp"], [18, "rocket"]]
+		],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Err": {
+				"GenericError": "The wallet has no generated recovery phrase to verify"
+			}
+		}
+	}
+	# "#
+	# , false, 0, false, false, false);
+	```
+	 */
+	fn verify_seed_backup(&self, words: Vec<(u32, String)>) -> Result<bool, ErrorKind>;
+
+	/**
+	Networked version of [Owner::stop_listener](struct.Owner.html#method.stop_listener).
+
+	Returns an error if the API isn't being served by a listener, as in this example.
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "stop_listener",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Err": {
+				"GenericError": "Owner API is not being served by a listener"
+			}
+		}
+	}
+	# "#
+	# , false, 1, false, false, false);
+	```
+	 */
+	fn stop_listener(&self) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::set_privacy_mode](struct.Owner.html#method.set_privacy_mode).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "set_privacy_mode",
+		"params": [true],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# , false, 0, false, false, false);
+	```
+	 */
+	fn set_privacy_mode(&self, enabled: bool) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::get_build_info](struct.Owner.html#method.get_build_info).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_build_info",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"features": [],
+				"git_hash": null,
+				"supported_slate_versions": ["V2"],
+				"version": "2.1.0-beta.1"
+			}
+		}
+	}
+	# "#
+	# , false, 0, false, false, false);
+	```
+	 */
+	fn get_build_info(&self) -> Result<BuildInfo, ErrorKind>;
+
+	/**
+	Networked version of [Owner::get_statistics](struct.Owner.html#method.get_statistics).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_statistics",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"amount_received": "0",
+				"amount_sent": "0",
+				"avg_confirmation_secs": null,
+				"errors": {},
+				"fees_paid": "0",
+				"started": "2019-01-15T16:01:26Z",
+				"txs_confirmed": "0",
+				"txs_received": "0",
+				"txs_sent": "0"
+			}
+		}
+	}
+	# "#
+	# , false, 0, false, false, false);
+	```
+	 */
+	fn get_statistics(&self) -> Result<WalletStatistics, ErrorKind>;
+
+	/**
+	Networked version of [Owner::enroll_totp](struct.Owner.html#method.enroll_totp).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "enroll_totp",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"secret": "A4DQOBYHA4DQOBYHA4DQOBYHA4DQOBYH",
+				"uri": "otpauth://totp/Grin:wallet?secret=A4DQOBYHA4DQOBYHA4DQOBYHA4DQOBYH&issuer=Grin&digits=6&period=30"
+			}
+		}
+	}
+	# "#
+	# , false, 0, false, false, false);
+	```
+	 */
+	fn enroll_totp(&self) -> Result<TotpEnrollment, ErrorKind>;
+
+	/**
+	Networked version of [Owner::confirm_totp](struct.Owner.html#method.confirm_totp).
+
+	No secret is being enrolled in this example, so there's none to confirm.
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "confirm_totp",
+		"params": ["123456"],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Err": {
+				"SecondFactor": "no TOTP secret is being enrolled"
+			}
+		}
+	}
+	# "#
+	# , false, 0, false, false, false);
+	```
+	 */
+	fn confirm_totp(&self, code: String) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::disable_totp](struct.Owner.html#method.disable_totp).
+
+	No secret is enrolled in this example, so there's none to remove.
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "disable_totp",
+		"params": ["123456"],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Err": {
+				"SecondFactor": "no TOTP secret is enrolled"
+			}
+		}
+	}
+	# "#
+	# , false, 0, false, false, false);
+	```
+	 */
+	fn disable_totp(&self, code: String) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::start_updater](struct.Owner.html#method.start_updater).
+
+	The frequency is given in milliseconds.
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "start_updater",
+		"params": [30000],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# , false, 0, false, false, false);
+	```
+	 */
+	fn start_updater(&self, frequency: u32) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::stop_updater](struct.Owner.html#method.stop_updater).
+
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "stop_updater",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# , false, 0, false, false, false);
+	```
+	 */
+	fn stop_updater(&self) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::get_updater_messages](struct.Owner.html#method.get_updater_messages).
+
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "get_updater_messages",
+		"params": [10],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": []
+		}
+	}
+	# "#
+	# , false, 0, false, false, false);
+	```
+	 */
+	fn get_updater_messages(&self, count: u32) -> Result<Vec<StatusMessage>, ErrorKind>;
+
+	/**
+	Networked version of [Owner::start_operation](struct.Owner.html#method.start_operation).
+
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "start_operation",
+		"params": ["Restore"],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
 		"id": 1,
+		"jsonrpc": "2.0",
 		"result": {
-			"Ok": {
-				"body": {
-					"inputs": [
-						{
-							"commit": "087df32304c5d4ae8b2af0bc31e700019d722910ef87dd4eec3197b80b207e3045",
-							"features": "Coinbase"
-						},
-						{
-							"commit": "08e1da9e6dc4d6e808a718b2f110a991dd775d65ce5ae408a4e1f002a4961aa9e7",
-							"features": "Coinbase"
-						}
-					],
-					"kernels": [
-						{
-							"excess": "000000000000000000000000000000000000000000000000000000000000000000",
-							"excess_sig": "00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000",
-							"features": "Plain",
-							"fee": "7000000",
-							"lock_height": "0"
-						}
-					],
-					"outputs": [
-						{
-							"commit": "099b48cfb1f80a2347dc89818449e68e76a3c6817a532a8e9ef2b4a5ccf4363850",
-							"features": "Plain",
-							"proof": "29701ceae262cac77b79b868c883a292e61e6de8192b868edcd1300b0973d91396b156ace6bd673402a303de10ddd8a5e6b7f17ba6557a574a672bd04cc273ab04ed8e2ca80bac483345c0ec843f521814ce1301ec9adc38956a12b4d948acce71295a4f52bcdeb8a1c9f2d6b2da5d731262a5e9c0276ef904df9ef8d48001420cd59f75a2f1ae5c7a1c7c6b9f140e7613e52ef9e249f29f9340b7efb80699e460164324616f98fd4cde3db52497c919e95222fffeacb7e65deca7e368a80ce713c19de7da5369726228ee336f5bd494538c12ccbffeb1b9bfd5fc8906d1c64245b516f103fa96d9c56975837652c1e0fa5803d7ccf1147d8f927e36da717f7ad79471dbe192f5f50f87a79fc3fe030dba569b634b92d2cf307993cce545633af263897cd7e6ebf4dcafb176d07358bdc38d03e45a49dfa9c8c6517cd68d167ffbf6c3b4de0e2dd21909cbad4c467b84e5700be473a39ac59c669d7c155c4bcab9b8026eea3431c779cd277e4922d2b9742e1f6678cbe869ec3b5b7ef4132ddb6cdd06cf27dbeb28be72b949fa897610e48e3a0d789fd2eea75abc97b3dc7e00e5c8b3d24e40c6f24112adb72352b89a2bef0599345338e9e76202a3c46efa6370952b2aca41aadbae0ea32531acafcdab6dd066d769ebf50cf4f3c0a59d2d5fa79600a207b9417c623f76ad05e8cccfcd4038f9448bc40f127ca7c0d372e46074e334fe49f5a956ec0056f4da601e6af80eb1a6c4951054869e665b296d8c14f344ca2dc5fdd5df4a3652536365a1615ad9b422165c77bf8fe65a835c8e0c41e070014eb66ef8c525204e990b3a3d663c1e42221b496895c37a2f0c1bf05e91235409c3fe3d89a9a79d6c78609ab18a463311911f71fa37bb73b15fcd38143d1404fd2ce81004dc7ff89cf1115dcc0c35ce1c1bf9941586fb959770f2618ccb7118a7"
-						},
-						{
-							"commit": "0812276cc788e6870612296d926cba9f0e7b9810670710b5a6e6f1ba006d395774",
-							"features": "Plain",
-							"proof": "284b9f9199411c6bbf7227eae15cc9fa7ed30534af3ecff85b0d016da329cae1f1edf79f01426434cb90afcc2f0a1fb296e5c51a91b5e57a1f0230fda4f8c5950e7986fa379b99d64b6039a86cc7e35e040ba192b781043959851268ca9874a918805ea958c84f7fee8d3ab4262f032f5a3f840ebcdd273b29be810114e6e86a959d8e4c080572e3ef249edd6ad68503ec3bc4486548520ea2775a41aea67aac99945fce9e7a7769d71f893ad7f0d0108692f6b6852312cae6f9857063055bda59dce521927c70040b8026a41b6517cae0a1c947ca244984a5c0adf7c6483b0939346c48f61cac37d501f46a1c5878b67cee0d0723f4eeadc9f5d71ed5e9f33b4294b58d3ebeefeaa13f20357599be549ce18e6e2eb1d50e1235ccc40ec9184c68a623741a7238ce69aa3a1d25156b3b7eb38fdd6fbe5473979feee3317df279c60d48a289826aa4c76dbce24d526890d4e6e2f83e80f674a1248fc1dc037d982009012ae1133f5e158ae6cdadb18c8d53e4a8ae5595c758782c67aa0c20f146d52085cf45a35794cec45702830f8952a69744718fbe6fe0d3da66e348dd3473a0aced7080fbf5494c3e7e141916f3b135b33277f998fcd9acfbca8709814866e9838a5dcda4c29422cf157293e6fc2ccc2d25423517843bd8e21c61ced72312c0b48814c312021b0d31598d2389b0b329baa1169922a4c34173dd5f540545be5066a0f291f1a870e1aaff94c19f0a855254882a17984baeda08e8ead53d1563e9ee4bc36742789cef4f3ab158054d7ddfe2a2b37b5a8a30be84ff7df9a7d758a9b76751a3622057721a3ec5b873582d0c91cd1febbc153662739854ea15c9903023c193c6b5768b55484108f897cdc47d13a9088b32558e57f12f5807668649c99a17b51905127340e8b49c42775f1b3cab072dfad33d33cc1e4ba5964d728ed0a905"
-						}
-					]
-				},
-				"offset": "d202964900000000d302964900000000d402964900000000d502964900000000"
-			}
+			"Ok": 1
 		}
 	}
 	# "#
-	# , false, 5, true, true, false);
+	# , false, 1, false, false, false);
 	```
 	 */
-	fn get_stored_tx(&self, tx: &TxLogEntry) -> Result<Option<Transaction>, ErrorKind>;
+	fn start_operation(&self, operation: LongOperation) -> Result<u32, ErrorKind>;
 
 	/**
-	Networked version of [Owner::verify_slate_messages](struct.Owner.html#method.verify_slate_messages).
+	Networked version of [Owner::operation_status](struct.Owner.html#method.operation_status).
+
 
 	```
 	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
 	# r#"
 	{
 		"jsonrpc": "2.0",
-		"method": "verify_slate_messages",
+		"method": "operation_status",
+		"params": [2],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
 		"id": 1,
-		"params": [ {
-				"amount": "6000000000",
-				"fee": "8000000",
-				"height": "4",
-				"id": "0436430c-2b02-624c-2032-570501212b00",
-				"lock_height": "4",
-				"num_participants": 2,
-				"participant_data": [
-				{
-					"id": "0",
-					"message": "my message",
-					"message_sig": "8f07ddd5e9f5179cff19486034181ed76505baaad53e5d994064127b56c5841b1d4c1358be398f801eb90d933774b5218fa7e769b11c4c640402253353656f75",
-					"part_sig": null,
-					"public_blind_excess": "034b4df2f0558b73ea72a1ca5c4ab20217c66bbe0829056fca7abe76888e9349ee",
-					"public_nonce": "031b84c5567b126440995d3ed5aaba0565d71e1834604819ff9c17f5e9d5dd078f"
-				}
-				],
-				"tx": {
-					"body": {
-						"inputs": [
-						{
-							"commit": "08e1da9e6dc4d6e808a718b2f110a991dd775d65ce5ae408a4e1f002a4961aa9e7",
-							"features": "Coinbase"
-						}
-						],
-						"kernels": [
-						{
-							"excess": "000000000000000000000000000000000000000000000000000000000000000000",
-							"excess_sig": "00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000",
-							"features": "HeightLocked",
-							"fee": "8000000",
-							"lock_height": "4"
-						}
-						],
-						"outputs": [
-						{
-							"commit": "094be57c91787fc2033d5d97fae099f1a6ddb37ea48370f1a138f09524c767fdd3",
-							"features": "Plain",
-							"proof": "2a42e9e902b70ce44e1fccb14de87ee0a97100bddf12c6bead1b9c5f4eb60300f29c13094fa12ffeee238fb4532b18f6b61cf51b23c1c7e1ad2e41560dc27edc0a2b9e647a0b3e4e806fced5b65e61d0f1f5197d3e2285c632d359e27b6b9206b2caffea4f67e0c7a2812e7a22c134b98cf89bd43d9f28b8bec25cce037a0ac5b1ae8f667e54e1250813a5263004486b4465ad4e641ab2b535736ea26535a11013564f08f483b7dab1c2bcc3ee38eadf2f7850eff7e3459a4bbabf9f0cf6c50d0c0a4120565cd4a2ce3e354c11721cd695760a24c70e0d5a0dfc3c5dcd51dfad6de2c237a682f36dc0b271f21bb3655e5333016aaa42c2efa1446e5f3c0a79ec417c4d30f77556951cb0f05dbfafb82d9f95951a9ea241fda2a6388f73ace036b98acce079f0e4feebccc96290a86dcc89118a901210b245f2d114cf94396e4dbb461e82aa26a0581389707957968c7cdc466213bb1cd417db207ef40c05842ab67a01a9b96eb1430ebc26e795bb491258d326d5174ad549401059e41782121e506744af8af9d8e493644a87d613600888541cbbe538c625883f3eb4aa3102c5cfcc25de8e97af8927619ce6a731b3b8462d51d993066b935b0648d2344ad72e4fd70f347fbd81041042e5ea31cc7b2e3156a920b80ecba487b950ca32ca95fae85b759c936246ecf441a9fdd95e8fee932d6782cdec686064018c857efc47fb4b2a122600d5fdd79af2486f44df7e629184e1c573bc0a9b3feb40b190ef2861a1ab45e2ac2201b9cd42e495deea247269820ed32389a2810ad6c0f9a296d2a2d9c54089fed50b7f5ecfcd33ab9954360e1d7f5598c32128cfcf2a1d8bf14616818da8a5343bfa88f0eedf392e9d4ab1ace1b60324129cd4852c2e27813a9cf71a6ae6229a4fcecc1a756b3e664c5f50af333082616815a3bec8fc0b75b8e4e767d719"
-						}
-						]
-					},
-					"offset": "d202964900000000d302964900000000d402964900000000d502964900000000"
-				},
-				"version_info": {
-					"orig_version": 2,
-					"version": 2,
-					"block_header_version": 2
-				}
+		"jsonrpc": "2.0",
+		"result": {
+			"Err": {
+				"UnknownOperation": 2
 			}
-		]
+		}
 	}
 	# "#
-	# ,
+	# , false, 0, false, false, false);
+	```
+	 */
+	fn operation_status(&self, id: u32) -> Result<OperationStatus, ErrorKind>;
+
+	/**
+	Networked version of [Owner::cancel_operation](struct.Owner.html#method.cancel_operation).
+
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
 	# r#"
 	{
 		"jsonrpc": "2.0",
+		"method": "cancel_operation",
+		"params": [2],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
 		"id": 1,
+		"jsonrpc": "2.0",
 		"result": {
-			"Ok": null
+			"Err": {
+				"UnknownOperation": 2
+			}
 		}
 	}
 	# "#
-	# ,false, 5 ,true, false, false);
+	# , false, 0, false, false, false);
 	```
-	*/
-	fn verify_slate_messages(&self, slate: VersionedSlate) -> Result<(), ErrorKind>;
+	 */
+	fn cancel_operation(&self, id: u32) -> Result<(), ErrorKind>;
 
 	/**
-	Networked version of [Owner::restore](struct.Owner.html#method.restore).
+	Networked version of [Owner::list_operations](struct.Owner.html#method.list_operations).
 
 
 	```
@@ -1162,7 +2823,7 @@ pub trait OwnerRpc {
 	# r#"
 	{
 		"jsonrpc": "2.0",
-		"method": "restore",
+		"method": "list_operations",
 		"params": [],
 		"id": 1
 	}
@@ -1173,17 +2834,17 @@ pub trait OwnerRpc {
 		"id": 1,
 		"jsonrpc": "2.0",
 		"result": {
-			"Ok": null
+			"Ok": []
 		}
 	}
 	# "#
-	# , false, 1, false, false, false);
+	# , false, 0, false, false, false);
 	```
 	 */
-	fn restore(&self) -> Result<(), ErrorKind>;
+	fn list_operations(&self) -> Result<Vec<OperationStatus>, ErrorKind>;
 
 	/**
-	Networked version of [Owner::check_repair](struct.Owner.html#method.check_repair).
+	Networked version of [Owner::get_webhook_dead_letters](struct.Owner.html#method.get_webhook_dead_letters).
 
 
 	```
@@ -1191,8 +2852,8 @@ pub trait OwnerRpc {
 	# r#"
 	{
 		"jsonrpc": "2.0",
-		"method": "check_repair",
-		"params": [false],
+		"method": "get_webhook_dead_letters",
+		"params": [],
 		"id": 1
 	}
 	# "#
@@ -1202,25 +2863,24 @@ pub trait OwnerRpc {
 		"id": 1,
 		"jsonrpc": "2.0",
 		"result": {
-			"Ok": null
+			"Ok": []
 		}
 	}
 	# "#
-	# , false, 1, false, false, false);
+	# , false, 0, false, false, false);
 	```
 	 */
-	fn check_repair(&self, delete_unconfirmed: bool) -> Result<(), ErrorKind>;
+	fn get_webhook_dead_letters(&self) -> Result<Vec<WebhookDeadLetter>, ErrorKind>;
 
 	/**
-	Networked version of [Owner::node_height](struct.Owner.html#method.node_height).
-
+	Networked version of [Owner::export_audit_log](struct.Owner.html#method.export_audit_log).
 
 	```
 	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
 	# r#"
 	{
 		"jsonrpc": "2.0",
-		"method": "node_height",
+		"method": "export_audit_log",
 		"params": [],
 		"id": 1
 	}
@@ -1231,17 +2891,16 @@ pub trait OwnerRpc {
 		"id": 1,
 		"jsonrpc": "2.0",
 		"result": {
-			"Ok": {
-				"height": "5",
-				"updated_from_node": true
+			"Err": {
+				"GenericError": "The audit log isn't being kept"
 			}
 		}
 	}
 	# "#
-	# , false, 5, false, false, false);
+	# , false, 0, false, false, false);
 	```
 	 */
-	fn node_height(&self) -> Result<NodeHeightResult, ErrorKind>;
+	fn export_audit_log(&self) -> Result<AuditLogExport, ErrorKind>;
 }
 
 impl<'a, L, C, K> OwnerRpc for Owner<'a, L, C, K>
@@ -1277,8 +2936,35 @@ where
 		refresh_from_node: bool,
 		tx_id: Option<u32>,
 		tx_slate_id: Option<Uuid>,
+		query: Option<RetrieveTxQueryArgs>,
 	) -> Result<(bool, Vec<TxLogEntry>), ErrorKind> {
-		Owner::retrieve_txs(self, None, refresh_from_node, tx_id, tx_slate_id).map_err(|e| e.kind())
+		Owner::retrieve_txs(self, None, refresh_from_node, tx_id, tx_slate_id, query)
+			.map_err(|e| e.kind())
+	}
+
+	fn export_txs(
+		&self,
+		refresh_from_node: bool,
+		format: TxExportFormat,
+		filter: RetrieveTxQueryArgs,
+	) -> Result<String, ErrorKind> {
+		Owner::export_txs(self, None, refresh_from_node, format, filter).map_err(|e| e.kind())
+	}
+
+	fn query_outputs(
+		&self,
+		refresh_from_node: bool,
+		query: RetrieveOutputsQueryArgs,
+	) -> Result<(bool, Vec<OutputCommitMapping>), ErrorKind> {
+		Owner::query_outputs(self, None, refresh_from_node, query).map_err(|e| e.kind())
+	}
+
+	fn get_output_derivation(&self, commit: String) -> Result<OutputDerivation, ErrorKind> {
+		Owner::get_output_derivation(self, None, &commit).map_err(|e| e.kind())
+	}
+
+	fn reserve_derivation_indices(&self, count: u32) -> Result<ReservedKeys, ErrorKind> {
+		Owner::reserve_derivation_indices(self, None, count).map_err(|e| e.kind())
 	}
 
 	fn retrieve_summary_info(
@@ -1296,6 +2982,22 @@ where
 		Ok(VersionedSlate::into_version(slate, version))
 	}
 
+	fn init_send_tx_multi(&self, args: InitTxArgs) -> Result<Vec<VersionedSlate>, ErrorKind> {
+		let slates = Owner::init_send_tx_multi(self, None, args).map_err(|e| e.kind())?;
+		Ok(slates
+			.into_iter()
+			.map(|s| VersionedSlate::into_version(s, SlateVersion::V2))
+			.collect())
+	}
+
+	fn consolidate(&self, args: ConsolidateArgs) -> Result<Vec<VersionedSlate>, ErrorKind> {
+		let slates = Owner::consolidate(self, None, args).map_err(|e| e.kind())?;
+		Ok(slates
+			.into_iter()
+			.map(|s| VersionedSlate::into_version(s, SlateVersion::V2))
+			.collect())
+	}
+
 	fn issue_invoice_tx(&self, args: IssueInvoiceTxArgs) -> Result<VersionedSlate, ErrorKind> {
 		let slate = Owner::issue_invoice_tx(self, None, args).map_err(|e| e.kind())?;
 		let version = SlateVersion::V2;
@@ -1314,13 +3016,34 @@ where
 		Ok(VersionedSlate::into_version(out_slate, version))
 	}
 
-	fn finalize_tx(&self, slate: VersionedSlate) -> Result<VersionedSlate, ErrorKind> {
+	fn finalize_tx(
+		&self,
+		slate: VersionedSlate,
+		fluff: Option<bool>,
+	) -> Result<VersionedSlate, ErrorKind> {
 		let in_slate = Slate::from(slate);
-		let out_slate = Owner::finalize_tx(self, None, &in_slate).map_err(|e| e.kind())?;
+		let out_slate = Owner::finalize_tx(self, None, &in_slate, fluff).map_err(|e| e.kind())?;
 		let version = SlateVersion::V2;
 		Ok(VersionedSlate::into_version(out_slate, version))
 	}
 
+	fn prepare_send(&self, args: InitTxArgs) -> Result<UnsignedTx, ErrorKind> {
+		Owner::prepare_send(self, None, args).map_err(|e| e.kind())
+	}
+
+	fn sign_offline(&self, unsigned: UnsignedTx) -> Result<VersionedSlate, ErrorKind> {
+		let slate = Owner::sign_offline(self, None, &unsigned).map_err(|e| e.kind())?;
+		Ok(VersionedSlate::into_version(slate, SlateVersion::V2))
+	}
+
+	fn finalize_offline(&self, slate: VersionedSlate) -> Result<SignedTx, ErrorKind> {
+		Owner::finalize_offline(self, None, &Slate::from(slate)).map_err(|e| e.kind())
+	}
+
+	fn broadcast_signed(&self, signed: SignedTx, fluff: bool) -> Result<(), ErrorKind> {
+		Owner::broadcast_signed(self, None, &signed, fluff).map_err(|e| e.kind())
+	}
+
 	fn tx_lock_outputs(
 		&self,
 		slate: VersionedSlate,
@@ -1334,14 +3057,64 @@ where
 		Owner::cancel_tx(self, None, tx_id, tx_slate_id).map_err(|e| e.kind())
 	}
 
+	fn cancel_txs(
+		&self,
+		tx_ids: Option<Vec<u32>>,
+		filter: Option<RetrieveTxQueryArgs>,
+	) -> Result<Vec<TxBatchResult>, ErrorKind> {
+		Owner::cancel_txs(self, None, tx_ids, filter).map_err(|e| e.kind())
+	}
+
+	fn post_txs(
+		&self,
+		tx_ids: Vec<u32>,
+		fluff: Option<bool>,
+	) -> Result<Vec<TxBatchResult>, ErrorKind> {
+		Owner::post_txs(self, None, tx_ids, fluff).map_err(|e| e.kind())
+	}
+
+	fn retrieve_pending_receives(&self) -> Result<Vec<PendingReceive>, ErrorKind> {
+		Owner::retrieve_pending_receives(self, None).map_err(|e| e.kind())
+	}
+
+	fn approve_receive(&self, tx_slate_id: Uuid) -> Result<VersionedSlate, ErrorKind> {
+		let slate = Owner::approve_receive(self, None, tx_slate_id).map_err(|e| e.kind())?;
+		Ok(VersionedSlate::into_version(slate, SlateVersion::V2))
+	}
+
+	fn reject_receive(&self, tx_slate_id: Uuid) -> Result<(), ErrorKind> {
+		Owner::reject_receive(self, None, tx_slate_id).map_err(|e| e.kind())
+	}
+
+	fn set_tx_label(&self, tx_id: u32, label: Option<String>) -> Result<(), ErrorKind> {
+		Owner::set_tx_label(self, None, tx_id, label).map_err(|e| e.kind())
+	}
+
 	fn get_stored_tx(&self, tx: &TxLogEntry) -> Result<Option<Transaction>, ErrorKind> {
 		Owner::get_stored_tx(self, None, tx).map_err(|e| e.kind())
 	}
 
-	fn post_tx(&self, tx: &Transaction, fluff: bool) -> Result<(), ErrorKind> {
+	fn replay_tx(
+		&self,
+		tx_id: Option<u32>,
+		tx_slate_id: Option<Uuid>,
+	) -> Result<TxReplayReport, ErrorKind> {
+		Owner::replay_tx(self, None, tx_id, tx_slate_id).map_err(|e| e.kind())
+	}
+
+	fn post_tx(&self, tx: &Transaction, fluff: Option<bool>) -> Result<(), ErrorKind> {
 		Owner::post_tx(self, None, tx, fluff).map_err(|e| e.kind())
 	}
 
+	fn post_tx_checked(
+		&self,
+		tx: &Transaction,
+		fluff: Option<bool>,
+		replace: bool,
+	) -> Result<(), ErrorKind> {
+		Owner::post_tx_checked(self, None, tx, fluff, replace).map_err(|e| e.kind())
+	}
+
 	fn verify_slate_messages(&self, slate: VersionedSlate) -> Result<(), ErrorKind> {
 		let in_slate = Slate::from(slate);
 		Owner::verify_slate_messages(self, None, &in_slate).map_err(|e| e.kind())
@@ -1351,13 +3124,114 @@ where
 		Owner::restore(self, None).map_err(|e| e.kind())
 	}
 
-	fn check_repair(&self, delete_unconfirmed: bool) -> Result<(), ErrorKind> {
-		Owner::check_repair(self, None, delete_unconfirmed).map_err(|e| e.kind())
+	fn check_repair(&self, delete_unconfirmed: bool, full: bool) -> Result<(), ErrorKind> {
+		Owner::check_repair(self, None, delete_unconfirmed, full).map_err(|e| e.kind())
+	}
+
+	fn audit_chain(&self) -> Result<ChainAuditReport, ErrorKind> {
+		Owner::audit_chain(self, None).map_err(|e| e.kind())
+	}
+
+	fn get_node_status(&self) -> Result<NodeConnectivity, ErrorKind> {
+		Owner::get_node_status(self, None).map_err(|e| e.kind())
+	}
+
+	fn node_status(&self) -> Result<NodeStatus, ErrorKind> {
+		Owner::node_status(self, None).map_err(|e| e.kind())
 	}
 
 	fn node_height(&self) -> Result<NodeHeightResult, ErrorKind> {
 		Owner::node_height(self, None).map_err(|e| e.kind())
 	}
+
+	fn list_anomalies(
+		&self,
+		refresh_from_node: bool,
+		stuck_after_mins: u64,
+	) -> Result<(bool, Vec<TxAnomaly>), ErrorKind> {
+		Owner::list_anomalies(self, None, refresh_from_node, stuck_after_mins).map_err(|e| e.kind())
+	}
+
+	fn remediate(&self, tx_id: u32, action: RemediationAction) -> Result<(), ErrorKind> {
+		Owner::remediate(self, None, tx_id, action).map_err(|e| e.kind())
+	}
+
+	fn get_feature_flags(&self) -> Result<FeatureFlags, ErrorKind> {
+		Owner::get_feature_flags(self, None).map_err(|e| e.kind())
+	}
+
+	fn set_feature_flags(&self, flags: FeatureFlags) -> Result<(), ErrorKind> {
+		Owner::set_feature_flags(self, None, flags).map_err(|e| e.kind())
+	}
+
+	fn verify_seed_backup(&self, words: Vec<(u32, String)>) -> Result<bool, ErrorKind> {
+		Owner::verify_seed_backup(self, None, words).map_err(|e| e.kind())
+	}
+
+	fn stop_listener(&self) -> Result<(), ErrorKind> {
+		Owner::stop_listener(self, None).map_err(|e| e.kind())
+	}
+
+	fn set_privacy_mode(&self, enabled: bool) -> Result<(), ErrorKind> {
+		Owner::set_privacy_mode(self, None, enabled).map_err(|e| e.kind())
+	}
+
+	fn get_build_info(&self) -> Result<BuildInfo, ErrorKind> {
+		Owner::get_build_info(self, None).map_err(|e| e.kind())
+	}
+
+	fn get_statistics(&self) -> Result<WalletStatistics, ErrorKind> {
+		Owner::get_statistics(self, None).map_err(|e| e.kind())
+	}
+
+	fn enroll_totp(&self) -> Result<TotpEnrollment, ErrorKind> {
+		Owner::enroll_totp(self, None).map_err(|e| e.kind())
+	}
+
+	fn confirm_totp(&self, code: String) -> Result<(), ErrorKind> {
+		Owner::confirm_totp(self, None, &code).map_err(|e| e.kind())
+	}
+
+	fn disable_totp(&self, code: String) -> Result<(), ErrorKind> {
+		Owner::disable_totp(self, None, &code).map_err(|e| e.kind())
+	}
+
+	fn start_updater(&self, frequency: u32) -> Result<(), ErrorKind> {
+		Owner::start_updater(self, None, Duration::from_millis(frequency as u64))
+			.map_err(|e| e.kind())
+	}
+
+	fn stop_updater(&self) -> Result<(), ErrorKind> {
+		Owner::stop_updater(self, None).map_err(|e| e.kind())
+	}
+
+	fn get_updater_messages(&self, count: u32) -> Result<Vec<StatusMessage>, ErrorKind> {
+		Owner::get_updater_messages(self, None, count as usize).map_err(|e| e.kind())
+	}
+
+	fn start_operation(&self, operation: LongOperation) -> Result<u32, ErrorKind> {
+		Owner::start_operation(self, None, operation).map_err(|e| e.kind())
+	}
+
+	fn operation_status(&self, id: u32) -> Result<OperationStatus, ErrorKind> {
+		Owner::operation_status(self, None, id).map_err(|e| e.kind())
+	}
+
+	fn cancel_operation(&self, id: u32) -> Result<(), ErrorKind> {
+		Owner::cancel_operation(self, None, id).map_err(|e| e.kind())
+	}
+
+	fn list_operations(&self) -> Result<Vec<OperationStatus>, ErrorKind> {
+		Owner::list_operations(self, None).map_err(|e| e.kind())
+	}
+
+	fn get_webhook_dead_letters(&self) -> Result<Vec<WebhookDeadLetter>, ErrorKind> {
+		Owner::get_webhook_dead_letters(self, None).map_err(|e| e.kind())
+	}
+
+	fn export_audit_log(&self) -> Result<AuditLogExport, ErrorKind> {
+		Owner::export_audit_log(self, None).map_err(|e| e.kind())
+	}
 }
 
 /// helper to set up a real environment to run integrated doctests
@@ -1528,7 +3402,8 @@ pub fn run_doctest_owner(
 		println!("RECEIPIENT SLATE");
 		println!("{}", serde_json::to_string_pretty(&slate).unwrap());
 		if finalize_tx {
-			slate = api_impl::owner::finalize_tx(&mut **w, (&mask2).as_ref(), &slate).unwrap();
+			slate =
+				api_impl::owner::finalize_tx(&mut **w, (&mask2).as_ref(), &slate, None).unwrap();
 			error!("FINALIZED TX SLATE");
 			println!("{}", serde_json::to_string_pretty(&slate).unwrap());
 		}
@@ -1545,8 +3420,10 @@ pub fn run_doctest_owner(
 		);
 	}
 
-	let mut api_owner = Owner::new(wallet1);
+	let mut api_owner = Owner::new(wallet1.clone());
 	api_owner.doctest_mode = true;
+	api_owner.set_updater(Some(Arc::new(WalletUpdater::new(wallet1.clone(), None))));
+	api_owner.set_operation_runner(Some(Arc::new(OperationRunner::new(wallet1, None))));
 	if use_token {
 		let owner_api = &api_owner as &dyn OwnerRpcS;
 		Ok(owner_api.handle_request(request).as_option())
@@ -1586,7 +3463,7 @@ macro_rules! doctest_helper_json_rpc_owner_assert_response {
 			$perform_tx,
 			$lock_tx,
 			$finalize_tx,
-			)
+		)
 		.unwrap()
 		.unwrap();
 
@@ -1595,7 +3472,7 @@ macro_rules! doctest_helper_json_rpc_owner_assert_response {
 				"(left != right) \nleft: {}\nright: {}",
 				serde_json::to_string_pretty(&response).unwrap(),
 				serde_json::to_string_pretty(&expected_response).unwrap()
-				);
-			}
+			);
+		}
 	};
 }
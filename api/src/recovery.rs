@@ -0,0 +1,177 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Height-locked transactions sweeping the wallet to a recovery destination,
+//! kept exported for heirs or the holder of a backup key to post should the
+//! wallet go unused for long. The transaction is rebuilt whenever the outputs
+//! it sweeps change, so using the wallet keeps pushing back the height from
+//! which it can be posted
+
+use crate::call_queue::{CallPriority, CallQueue};
+use crate::impls::{create_sender, PathToSlate, SlateGetter, SlatePutter};
+use crate::keychain::Keychain;
+use crate::libwallet::api_impl::owner;
+use crate::libwallet::{
+	Error, NodeClient, RecoveryTxArgs, Slate, WalletBackend, WalletInst, WalletLCProvider,
+};
+use crate::util::secp::key::SecretKey;
+use crate::util::Mutex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Where the recovery transaction sends the wallet's funds and where it's
+/// exported to
+#[derive(Clone, Debug)]
+pub struct RecoveryPlan {
+	/// Outputs to sweep and the blocks before the transaction can be posted
+	pub args: RecoveryTxArgs,
+	/// Method to reach the recovery destination with, as for a send
+	pub method: String,
+	/// Listener of the wallet receiving the funds
+	pub dest: String,
+	/// File the finalized transaction is written to, as a slate that
+	/// `grin-wallet post` accepts
+	pub export_path: PathBuf,
+}
+
+/// Build a recovery transaction with the destination's wallet and finalize
+/// it, without recording or posting it
+pub fn build_recovery_tx<L, C, K>(
+	wallet_inst: &Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
+	keychain_mask: Option<&SecretKey>,
+	plan: &RecoveryPlan,
+) -> Result<Slate, Error>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	let slate = {
+		let mut w_lock = wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::init_recovery_tx(&mut **w, keychain_mask, plan.args.clone(), false)?
+	};
+	let sent = create_sender(&plan.method, &plan.dest).and_then(|s| s.send_tx(&slate));
+	let mut w_lock = wallet_inst.lock();
+	let w = w_lock.lc_provider()?.wallet_inst()?;
+	match sent {
+		Ok(s) => owner::finalize_recovery_tx(&mut **w, keychain_mask, &s),
+		Err(e) => {
+			// nothing is locked, so there's only the context to drop
+			let mut batch = w.batch(keychain_mask)?;
+			batch.delete_private_context(slate.id.as_bytes(), 0)?;
+			batch.commit()?;
+			Err(e)
+		}
+	}
+}
+
+/// Thread keeping the exported recovery transaction current. The thread exits
+/// when the planner is dropped
+pub struct RecoveryPlanner {
+	// dropping the sender stops the thread
+	_stop: Mutex<mpsc::Sender<()>>,
+	last_built: Arc<Mutex<Option<Slate>>>,
+}
+
+impl RecoveryPlanner {
+	/// Check straight away and then every `interval` that the exported
+	/// transaction still sweeps the wallet's spendable outputs, rebuilding and
+	/// exporting it again if not. If a call queue is given, checks wait their
+	/// turn behind interactive calls
+	pub fn start<L, C, K>(
+		wallet_inst: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
+		keychain_mask: Option<SecretKey>,
+		plan: RecoveryPlan,
+		interval: Duration,
+		call_queue: Option<Arc<CallQueue>>,
+	) -> Self
+	where
+		L: WalletLCProvider<'static, C, K> + 'static,
+		C: NodeClient + 'static,
+		K: Keychain + 'static,
+	{
+		let (stop_tx, stop_rx) = mpsc::channel::<()>();
+		let last_built = Arc::new(Mutex::new(None));
+		let thread_last_built = last_built.clone();
+		let _ = thread::Builder::new()
+			.name("wallet_recovery".to_string())
+			.spawn(move || loop {
+				let res = {
+					let _turn = call_queue
+						.as_ref()
+						.map(|q| q.wait_turn(CallPriority::Background));
+					let exported = PathToSlate(plan.export_path.clone()).get_tx().ok();
+					let mut w_lock = wallet_inst.lock();
+					w_lock
+						.lc_provider()
+						.and_then(|lc| lc.wallet_inst())
+						.and_then(|w| match exported {
+							Some(s) => owner::recovery_tx_current(
+								&mut **w,
+								keychain_mask.as_ref(),
+								&plan.args,
+								&s,
+							),
+							None => Ok(false),
+						})
+				};
+				let res = match res {
+					Ok(true) => Ok(None),
+					Ok(false) => build_recovery_tx(&wallet_inst, keychain_mask.as_ref(), &plan)
+						.and_then(|s| export(&plan.export_path, &s).map(|_| Some(s))),
+					Err(e) => Err(e),
+				};
+				match res {
+					Ok(Some(s)) => {
+						info!(
+							"Exported recovery transaction {} to {}, valid from height {}",
+							s.id,
+							plan.export_path.display(),
+							s.lock_height
+						);
+						*thread_last_built.lock() = Some(s);
+					}
+					Ok(None) => {}
+					// e.g. the destination's listener is offline, tried again next time
+					Err(e) => warn!("Unable to update the recovery transaction: {}", e),
+				}
+				match stop_rx.recv_timeout(interval) {
+					Err(RecvTimeoutError::Timeout) => {}
+					_ => break,
+				}
+			});
+		RecoveryPlanner {
+			_stop: Mutex::new(stop_tx),
+			last_built,
+		}
+	}
+
+	/// The most recent recovery transaction built, if any since starting
+	pub fn last_built(&self) -> Option<Slate> {
+		self.last_built.lock().clone()
+	}
+}
+
+/// Replace the exported transaction, so there's never a partly written one
+fn export(path: &Path, slate: &Slate) -> Result<(), Error> {
+	let tmp = path.with_extension("tmp");
+	PathToSlate(tmp.clone()).put_tx(slate)?;
+	fs::rename(&tmp, path)?;
+	Ok(())
+}
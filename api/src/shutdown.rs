@@ -0,0 +1,53 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Signal used to ask running listeners to shut down
+
+use std::sync::{Arc, Condvar, Mutex};
+
+/// Handle shared between a listener and whatever may ask it to stop (a signal
+/// handler, an Owner API call or an embedding application). Clones share
+/// the same state, so shutting down one shuts down all of them
+#[derive(Clone, Default)]
+pub struct ShutdownHandle {
+	state: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl ShutdownHandle {
+	/// Create a new handle
+	pub fn new() -> ShutdownHandle {
+		ShutdownHandle::default()
+	}
+
+	/// Ask listeners holding this handle to shut down
+	pub fn shutdown(&self) {
+		let (lock, cvar) = &*self.state;
+		*lock.lock().unwrap() = true;
+		cvar.notify_all();
+	}
+
+	/// Whether shutdown has been requested
+	pub fn is_shutdown(&self) -> bool {
+		*self.state.0.lock().unwrap()
+	}
+
+	/// Block until shutdown is requested
+	pub fn wait(&self) {
+		let (lock, cvar) = &*self.state;
+		let mut stopped = lock.lock().unwrap();
+		while !*stopped {
+			stopped = cvar.wait(stopped).unwrap();
+		}
+	}
+}
@@ -0,0 +1,242 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Background refresh of the wallet's outputs and transactions against the
+//! node, so clients polling the Owner API don't each refresh inline. After
+//! each successful refresh the updater can also cancel transactions left
+//! unconfirmed for too long, and spend received outputs back to the wallet.
+//! The updater refreshes less often for as long as the node throttles it
+
+use crate::call_queue::{CallPriority, CallQueue};
+use crate::keychain::Keychain;
+use crate::libwallet::api_impl::owner;
+use crate::libwallet::{
+	resource_limits, ErrorKind, NodeClient, StatusMessage, WalletInst, WalletLCProvider,
+};
+use crate::util::secp::key::SecretKey;
+use crate::util::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Maximum number of status messages kept for clients to retrieve
+const MAX_MESSAGES: usize = 1000;
+/// Times the interval between refreshes is doubled at most while the node is
+/// throttling the wallet's requests
+const MAX_THROTTLED_DOUBLINGS: u32 = 5;
+
+enum UpdaterCommand {
+	Start(Duration, Option<SecretKey>),
+	Stop,
+}
+
+/// Thread refreshing the wallet at a regular interval while started. The
+/// thread is idle until started and exits when the updater is dropped
+pub struct WalletUpdater {
+	commands: Mutex<mpsc::Sender<UpdaterCommand>>,
+	running: Arc<AtomicBool>,
+	// whether the most recent refresh succeeded
+	refreshed: Arc<AtomicBool>,
+	// blocks after which unconfirmed transactions are cancelled, 0 if never
+	auto_cancel: Arc<AtomicU64>,
+	// whether confirmed received outputs are spent back to the wallet
+	self_spend: Arc<AtomicBool>,
+	messages: Arc<Mutex<Vec<StatusMessage>>>,
+}
+
+impl WalletUpdater {
+	/// Create the updater thread for the given wallet. If a call queue is
+	/// given, refreshes wait their turn behind interactive calls
+	pub fn new<L, C, K>(
+		wallet_inst: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
+		call_queue: Option<Arc<CallQueue>>,
+	) -> Self
+	where
+		L: WalletLCProvider<'static, C, K> + 'static,
+		C: NodeClient + 'static,
+		K: Keychain + 'static,
+	{
+		let (cmd_tx, cmd_rx) = mpsc::channel::<UpdaterCommand>();
+		let (status_tx, status_rx) = mpsc::channel::<StatusMessage>();
+		let running = Arc::new(AtomicBool::new(false));
+		let refreshed = Arc::new(AtomicBool::new(false));
+		let auto_cancel = Arc::new(AtomicU64::new(0));
+		let self_spend = Arc::new(AtomicBool::new(false));
+		let messages = Arc::new(Mutex::new(vec![]));
+
+		let thread_running = running.clone();
+		let thread_refreshed = refreshed.clone();
+		let thread_auto_cancel = auto_cancel.clone();
+		let thread_self_spend = self_spend.clone();
+		let thread_messages = messages.clone();
+		let _ = thread::Builder::new()
+			.name("wallet_updater".to_string())
+			.spawn(move || {
+				let status_tx = Some(status_tx);
+				let mut schedule: Option<(Duration, Option<SecretKey>)> = None;
+				// refreshes in a row the node has throttled
+				let mut throttled = 0;
+				loop {
+					let cmd = match &schedule {
+						Some((interval, _)) => cmd_rx.recv_timeout(*interval * 2u32.pow(throttled)),
+						None => cmd_rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+					};
+					match cmd {
+						Ok(UpdaterCommand::Start(interval, mask)) => {
+							schedule = Some((interval, mask));
+							throttled = 0;
+							thread_running.store(true, Ordering::Relaxed);
+						}
+						Ok(UpdaterCommand::Stop) => {
+							schedule = None;
+							thread_running.store(false, Ordering::Relaxed);
+							continue;
+						}
+						Err(RecvTimeoutError::Timeout) => {}
+						Err(RecvTimeoutError::Disconnected) => break,
+					}
+					let mask = schedule.as_ref().and_then(|s| s.1.clone());
+					let res = {
+						let _turn = call_queue
+							.as_ref()
+							.map(|q| q.wait_turn(CallPriority::Background));
+						let mut w_lock = wallet_inst.lock();
+						match w_lock.lc_provider().and_then(|lc| lc.wallet_inst()) {
+							Ok(w) => {
+								owner::update_wallet_state(&mut **w, mask.as_ref(), &status_tx)
+									.and_then(|ok| {
+										let blocks = thread_auto_cancel.load(Ordering::Relaxed);
+										if ok && blocks > 0 {
+											owner::cancel_stale_txs(
+												&mut **w,
+												mask.as_ref(),
+												blocks,
+												&status_tx,
+											)?;
+										}
+										if ok && thread_self_spend.load(Ordering::Relaxed) {
+											// retried after the next refresh if it fails
+											if let Err(e) = owner::self_spend_received(
+												&mut **w,
+												mask.as_ref(),
+												&status_tx,
+											) {
+												warn!("Unable to spend received outputs: {}", e);
+											}
+										}
+										Ok(ok)
+									})
+							}
+							Err(e) => Err(e),
+						}
+					};
+					match res {
+						Ok(ok) => {
+							throttled = 0;
+							thread_refreshed.store(ok, Ordering::Relaxed);
+						}
+						Err(e) => {
+							thread_refreshed.store(false, Ordering::Relaxed);
+							if let ErrorKind::NodeThrottled(_) = e.kind() {
+								throttled = (throttled + 1).min(MAX_THROTTLED_DOUBLINGS);
+								warn!(
+									"Node is throttling the wallet updater, refreshing every {}s",
+									schedule.as_ref().map_or(0, |s| s.0.as_secs())
+										* 2u64.pow(throttled)
+								);
+							} else {
+								// e.g. the wallet was closed or the mask is no longer valid
+								warn!("Stopping wallet updater: {}", e);
+								if let Some(s) = &status_tx {
+									let _ = s.send(StatusMessage::UpdateWarning(format!(
+										"Updater stopped: {}",
+										e
+									)));
+								}
+								thread_running.store(false, Ordering::Relaxed);
+								schedule = None;
+							}
+						}
+					}
+					let mut q = thread_messages.lock();
+					q.extend(status_rx.try_iter());
+					let excess = q.len().saturating_sub(MAX_MESSAGES);
+					q.drain(..excess);
+				}
+			});
+		WalletUpdater {
+			commands: Mutex::new(cmd_tx),
+			running,
+			refreshed,
+			auto_cancel,
+			self_spend,
+			messages,
+		}
+	}
+
+	/// Start refreshing the wallet straight away and then every `frequency`,
+	/// replacing any earlier schedule. The frequency is raised to the
+	/// process's minimum updater interval, if any
+	pub fn start(&self, frequency: Duration, keychain_mask: Option<SecretKey>) {
+		let frequency = match resource_limits().min_updater_interval_secs {
+			Some(secs) => frequency.max(Duration::from_secs(secs)),
+			None => frequency,
+		};
+		self.running.store(true, Ordering::Relaxed);
+		let _ = self
+			.commands
+			.lock()
+			.send(UpdaterCommand::Start(frequency, keychain_mask));
+	}
+
+	/// Stop refreshing the wallet
+	pub fn stop(&self) {
+		self.running.store(false, Ordering::Relaxed);
+		let _ = self.commands.lock().send(UpdaterCommand::Stop);
+	}
+
+	/// Cancel transactions, and unlock their inputs, once they've gone the
+	/// given number of blocks without being confirmed, checked after each
+	/// successful refresh. `None` leaves them be
+	pub fn set_auto_cancel(&self, blocks: Option<u64>) {
+		self.auto_cancel
+			.store(blocks.unwrap_or(0), Ordering::Relaxed);
+	}
+
+	/// Spend the active account's confirmed received outputs back to the
+	/// wallet after each successful refresh, or leave them be
+	pub fn set_self_spend_on_receive(&self, enabled: bool) {
+		self.self_spend.store(enabled, Ordering::Relaxed);
+	}
+
+	/// Whether the updater is refreshing the wallet
+	pub fn is_running(&self) -> bool {
+		self.running.load(Ordering::Relaxed)
+	}
+
+	/// Whether the most recent refresh succeeded
+	pub fn refreshed(&self) -> bool {
+		self.refreshed.load(Ordering::Relaxed)
+	}
+
+	/// Remove and return up to `count` of the most recent status messages,
+	/// oldest first
+	pub fn messages(&self, count: usize) -> Vec<StatusMessage> {
+		let mut q = self.messages.lock();
+		let index = q.len().saturating_sub(count);
+		q.split_off(index)
+	}
+}
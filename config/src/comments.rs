@@ -50,10 +50,42 @@ fn comments() -> HashMap<String, String> {
 		.to_string(),
 	);
 
+	retval.insert(
+		"foreign_api_max_body_size".to_string(),
+		"
+#maximum size in bytes of requests accepted by the foreign api listener
+"
+		.to_string(),
+	);
+	retval.insert(
+		"foreign_api_rate_limit".to_string(),
+		"
+#maximum number of requests per minute the foreign api listener accepts from
+#each client, identified by the address it connects from
+"
+		.to_string(),
+	);
+	retval.insert(
+		"foreign_api_trusted_proxies".to_string(),
+		"
+#ip addresses of reverse proxies in front of the foreign api listener. clients
+#connecting through them are identified by the X-Real-IP or X-Forwarded-For
+#header they set, which is ignored on requests from anywhere else
+"
+		.to_string(),
+	);
 	retval.insert(
 		"owner_api_listen_port".to_string(),
 		"
 #port for wallet owner api
+"
+		.to_string(),
+	);
+	retval.insert(
+		"owner_api_listen_socket".to_string(),
+		"
+#path of a unix socket the owner api listens on instead of the port above,
+#only accessible to the wallet's user. tls isn't used on the socket
 "
 		.to_string(),
 	);
@@ -69,7 +101,69 @@ fn comments() -> HashMap<String, String> {
 	retval.insert(
 		"check_node_api_http_addr".to_string(),
 		"
-#where the wallet should find a running node
+#where the wallet should find a running node. may also be a list of nodes,
+#e.g. [\"http://127.0.0.1:3413\", \"http://10.0.0.2:3413\"], which are tried
+#in order, the wallet failing over to the next whenever one can't be reached
+"
+		.to_string(),
+	);
+	retval.insert(
+		"archive_node_api_http_addr".to_string(),
+		"
+#an archival node to fall back on for historical queries made during
+#restore and check, should the node above be unable to answer them
+"
+		.to_string(),
+	);
+	retval.insert(
+		"node_discovery".to_string(),
+		"
+#whether to look for a node on the default ports on this machine when the
+#node above can't be reached at startup, asking before switching to it
+"
+		.to_string(),
+	);
+	retval.insert(
+		"public_node_directory_url".to_string(),
+		"
+#url of a directory of community-run public nodes, listed and health-checked
+#by the public_nodes command, which can also pin the best one above
+"
+		.to_string(),
+	);
+	retval.insert(
+		"public_node_directory_key".to_string(),
+		"
+#public key (hex) the directory must be signed with. the directory isn't used
+#without it
+"
+		.to_string(),
+	);
+	retval.insert(
+		"owner_api_token_ttl".to_string(),
+		"
+#if set, the owner api secret is only accepted at /v3/auth/login, which issues
+#bearer tokens expiring after this many seconds without use. tokens can be
+#revoked at /v3/auth/revoke. logging in with {\"account\": \"<label>\"} issues a
+#token restricted to that account
+"
+		.to_string(),
+	);
+	retval.insert(
+		"owner_api_audit_log".to_string(),
+		"
+#if true, each owner api call is recorded with its arguments, caller and result
+#in owner_api_audit.json in the wallet's directory. entries are hash-chained,
+#so changes to the log are detected when it's exported with export_audit_log
+"
+		.to_string(),
+	);
+	retval.insert(
+		"owner_api_trusted_proxies".to_string(),
+		"
+#ip addresses of reverse proxies in front of the owner api listener. calls
+#made through them are audited as coming from the client address given by the
+#X-Real-IP or X-Forwarded-For header, which is ignored from anywhere else
 "
 		.to_string(),
 	);
@@ -77,6 +171,16 @@ fn comments() -> HashMap<String, String> {
 		"node_api_secret_path".to_string(),
 		"
 #location of the node api secret for basic auth on the Grin API
+"
+		.to_string(),
+	);
+	retval.insert(
+		"owner_api_tls_client_ca_file".to_string(),
+		"
+#file of PEM encoded CA certificates. if set, along with tls_certificate_file
+#and tls_certificate_key, owner api clients must present a certificate signed
+#by one of these CAs. remove api_secret_path to use certificates instead of
+#basic auth, rather than as well as it
 "
 		.to_string(),
 	);
@@ -86,6 +190,139 @@ fn comments() -> HashMap<String, String> {
 #include the foreign API endpoints on the same port as the owner
 #API. Useful for networking environments like AWS ECS that make
 #it difficult to access multiple ports on a single service.
+"
+		.to_string(),
+	);
+	retval.insert(
+		"owner_api_read_only".to_string(),
+		"
+#only serve Owner API methods retrieving information, such as for
+#monitoring dashboards. Calls which change the wallet or spend, such
+#as init_send_tx, finalize_tx or cancel_tx, are refused, and the
+#foreign API isn't included. Can also be set with owner_api --read-only
+"
+		.to_string(),
+	);
+	retval.insert(
+		"owner_api_graphql".to_string(),
+		"
+#serve read-only GraphQL queries over accounts, transactions and outputs
+#at /v3/graphql, behind the same authentication as the owner API
+"
+		.to_string(),
+	);
+	retval.insert(
+		"node_monitor_interval".to_string(),
+		"
+#how often, in seconds, the owner api listener checks it can reach the node,
+#as reported by the get_node_status method. 0 disables the background check
+"
+		.to_string(),
+	);
+	retval.insert(
+		"auto_cancel_after_blocks".to_string(),
+		"
+#number of blocks after which the owner api listener cancels transactions
+#that still aren't confirmed, e.g. never finalized by the other party,
+#unlocking their inputs. Unset or 0 never cancels them
+"
+		.to_string(),
+	);
+	retval.insert(
+		"self_spend_on_receive".to_string(),
+		"
+#whether the owner api listener's updater spends received outputs back to
+#the wallet once confirmed, at the cost of a fee each time, so funds aren't
+#left in outputs a sender could recreate by replaying their transaction
+"
+		.to_string(),
+	);
+	retval.insert(
+		"receive_review_threshold".to_string(),
+		"
+#amount, in nanogrins, above which slates received through the foreign api
+#aren't signed but queued for review. the sender is told the transaction is
+#awaiting review; approving it with the owner api's approve_receive method
+#returns the signed slate, to be sent back to the sender as a file
+"
+		.to_string(),
+	);
+	retval.insert(
+		"memo_escrow_key".to_string(),
+		"
+#public key (hex) to seal the messages of each transaction to in json history
+#exports, so support staff holding its secret key can read order references
+#without access to the wallet. see the escrow_key and open_memos commands
+"
+		.to_string(),
+	);
+	retval.insert(
+		"num_change_outputs".to_string(),
+		"
+#number of outputs the change of each send or invoice payment is split
+#between. several smaller change outputs make later spends harder to link
+#and leave part of the balance unlocked while a send is pending
+"
+		.to_string(),
+	);
+	retval.insert(
+		"api_response_envelope".to_string(),
+		"
+#add a \"meta\" member to each owner and foreign API response, giving the
+#API version, server time and warnings for methods slated for removal
+"
+		.to_string(),
+	);
+	retval.insert(
+		"api_response_pretty".to_string(),
+		"
+#pretty-print owner and foreign API responses. compact responses are much
+#smaller; a request can ask for either by adding ?pretty=true or ?pretty=false
+#to the URL
+"
+		.to_string(),
+	);
+	retval.insert(
+		"api_max_response_bytes".to_string(),
+		"
+#largest owner or foreign API response to send, in bytes. a larger response
+#is replaced by an error with a \"truncated\" member hinting at how to page
+#through the data
+"
+		.to_string(),
+	);
+	retval.insert(
+		"log_privacy".to_string(),
+		"
+#redact amounts (to the range they fall in) and commitments (to hashes) from
+#logs and debug output, so logs can be shared without disclosing balances
+"
+		.to_string(),
+	);
+	retval.insert(
+		"metrics_enabled".to_string(),
+		"
+#collect request counts and latencies per API method, and serve them along
+#with balance and node connectivity gauges at /metrics on the owner API
+"
+		.to_string(),
+	);
+	retval.insert(
+		"metrics_listen_port".to_string(),
+		"
+#if metrics are enabled, also serve them on this port (without authentication).
+#the balance and node gauges are refreshed at most every 15 seconds, whatever
+#the rate of scrapes
+"
+		.to_string(),
+	);
+	retval.insert(
+		"owner_api_grpc_listen_port".to_string(),
+		"
+#if set, also serve the owner api over grpc on this port, with the same
+#api secret, along with the foreign api if owner_api_include_foreign is set.
+#the service definitions are in controller/proto/wallet.proto. only available
+#in wallets built with the grpc feature
 "
 		.to_string(),
 	);
@@ -102,6 +339,17 @@ fn comments() -> HashMap<String, String> {
 #If true, don't store calculated commits in the database
 #better privacy, but at a performance cost of having to
 #re-calculate commits every time they're used
+"
+		.to_string(),
+	);
+	retval.insert(
+		"derivation_scheme".to_string(),
+		"
+#scheme the wallet's keys are derived from the seed with: grin (the default),
+#or bip39 to use the BIP39 seed of the recovery phrase as other wallets do.
+#schemes registered by an application may also lay out the paths of accounts
+#and their outputs as another wallet does.
+#a wallet must always be opened with the scheme it was created with
 "
 		.to_string(),
 	);
@@ -109,6 +357,238 @@ fn comments() -> HashMap<String, String> {
 		"dark_background_color_scheme".to_string(),
 		"
 #Whether to use the black background color scheme for command line
+"
+		.to_string(),
+	);
+	retval.insert(
+		"push_gateway_url".to_string(),
+		"
+#gateway to POST push notifications to when a slate is received, waking
+#mobile devices registered with register_push_token to complete the receive
+"
+		.to_string(),
+	);
+	retval.insert(
+		"zmq_publish_address".to_string(),
+		"
+#address of a zeromq PUB socket to publish the same events as webhooks on,
+#for subscribers set up as they would be for bitcoind's zmqpub options, e.g.
+#tcp://127.0.0.1:28332. each message has three frames: the event name as
+#topic, the JSON payload and a 4 byte little-endian sequence number
+"
+		.to_string(),
+	);
+	retval.insert(
+		"price_oracle_url".to_string(),
+		"
+#price service used to value transactions in fiat for the settlement command,
+#queried as <url>?currency=<currency>&time=<unix timestamp> and answering
+#with {\"currency\": \"usd\", \"price\": 0.5}
+"
+		.to_string(),
+	);
+	retval.insert(
+		"resource_profile".to_string(),
+		"
+#limits on threads, batch sizes and concurrent node requests, one of
+#Low (for mobile and embedded devices), Standard or Server
+"
+		.to_string(),
+	);
+	retval.insert(
+		"[wallet.spend_confirmations]".to_string(),
+		"
+#minimum confirmations before outputs are spent or counted as spendable, by
+#where they came from: coinbase, received from others, or change from the
+#wallet's own sends. those set are used instead of the number of
+#confirmations given when sending, e.g. more for coinbase and received
+#outputs, which a reorg or a double spend by the sender can undo, and fewer
+#for change
+"
+		.to_string(),
+	);
+	retval.insert(
+		"[wallet.spending_policy]".to_string(),
+		"
+#limits on what the wallet sends, checked when a send is started and again
+#when it's finalized: max_amount_per_tx and max_daily_total (over the last
+#24 hours) in nanogrin, and allowed_destinations, the only addresses slates
+#may be sent to. sends breaking a limit are refused and logged
+"
+		.to_string(),
+	);
+	retval.insert(
+		"[wallet.receive_limits]".to_string(),
+		"
+#limits on the slates received through the foreign api, so a public listener
+#can't be spammed with dust: min_amount and max_amount in nanogrin,
+#max_per_hour, and finalize_invoices, set to false to refuse paid invoices
+#returned for finalizing. slates outside the limits are refused
+"
+		.to_string(),
+	);
+	retval.insert(
+		"[wallet.owner_api_cors]".to_string(),
+		"
+#origins, methods and headers allowed to make cross-origin requests to the
+#owner api. any origin is allowed if not set
+"
+		.to_string(),
+	);
+	retval.insert(
+		"[wallet.foreign_api_cors]".to_string(),
+		"
+#origins, methods and headers allowed to make cross-origin requests to the
+#foreign api. any origin is allowed if not set
+"
+		.to_string(),
+	);
+	retval.insert(
+		"[wallet.foreign_api_tarpit]".to_string(),
+		"
+#if set, clients over foreign_api_rate_limit are held rather than turned away,
+#their response dripped out a byte every drip_interval_secs for hold_secs, to
+#keep persistent scanners occupied. at most max_connections_per_client and
+#max_connections in all are held, clients over them are turned away
+"
+		.to_string(),
+	);
+	retval.insert(
+		"[wallet.proxy]".to_string(),
+		"
+#socks5 proxy the node client and http slate sender connect through, e.g.
+#socks5_address = \"127.0.0.1:9050\" for a local tor daemon. host names are
+#resolved by the proxy, so .onion addresses can be used. only http addresses
+#can be reached through the proxy. username and password are only needed if
+#the proxy asks for them
+"
+		.to_string(),
+	);
+	retval.insert(
+		"[wallet.zmq_publish_filter]".to_string(),
+		"
+#events published on the zeromq socket, all events if not set. events lists
+#the events to publish, accounts the labels of the accounts to publish
+#transactions of and min_amount the fewest nanogrins a published transaction
+#moves, e.g.
+#events = [\"TxReceived\", \"TxConfirmed\"]
+#accounts = [\"default\"]
+#min_amount = 1000000000
+"
+		.to_string(),
+	);
+	retval.insert(
+		"[[wallet.webhooks]]".to_string(),
+		"
+#endpoint to POST JSON notifications to on transaction lifecycle events
+#available events are TxReceived, TxConfirmed, TxCancelled and HeightUpdated,
+#all events are sent if none are given. if a secret is given, each payload
+#is signed with it, giving its HMAC-SHA256 in an X-Grin-Signature header as
+#sha256=<hex>. failed deliveries are retried with backoff up to max_attempts
+#times (5 by default), after which the event is kept in webhook_dead_letters.json
+#in the wallet's directory, to be listed with the owner api's
+#get_webhook_dead_letters. transaction events can also be limited to the
+#accounts with the labels in accounts, and to transactions moving at least
+#min_amount nanogrins
+"
+		.to_string(),
+	);
+	retval.insert(
+		"[wallet.email_notifications]".to_string(),
+		"
+#emails sent through an smtp server on significant events, for operators
+#without a monitoring stack. smtp_url is smtps://host:465, or smtp://host:587
+#to use STARTTLS; unencrypted connections are refused unless require_tls is
+#false. emails are sent with the curl command, for:
+#- sends of more than min_send_amount nanogrins
+#- auth_failures failed owner api logins within auth_failure_window seconds
+#- the node being unreachable for node_unreachable_mins minutes, as found by
+#the node monitor (see node_monitor_interval)
+#events without a threshold are never emailed. templates can replace the
+#default subject and body of an event's emails, e.g.
+#[[wallet.email_notifications.templates]]
+#event = \"LargeSend\"
+#subject = \"Sent {amount} grin\"
+#body = \"Sent {amount} grin in transaction {tx_slate_id} at {time}\"
+#placeholders are {time} and, by event, LargeSend: {amount}, {tx_slate_id};
+#AuthFailures: {failures}, {window}; NodeUnreachable: {node_url}, {minutes},
+#{error}
+"
+		.to_string(),
+	);
+	retval.insert(
+		"[wallet.email_slates]".to_string(),
+		"
+#mailbox to exchange slates by email with, using `send -m email -d <address>`
+#and `listen -m email`. slates are sent as attachments through smtp_url from
+#address, and looked for in the imap_url folder, e.g. imaps://host:993/INBOX,
+#every poll_interval seconds. a sender waits reply_timeout seconds for the
+#signed slate. unencrypted connections are refused unless require_tls is
+#false. mail is sent and read with the curl command
+"
+		.to_string(),
+	);
+	retval.insert(
+		"backup_interval".to_string(),
+		"
+#how often, in seconds, the listeners back up the wallet to the backup
+#targets. Backups are encrypted with a key derived from the wallet's seed
+"
+		.to_string(),
+	);
+	retval.insert(
+		"backup_full_every".to_string(),
+		"
+#take a full backup every this many backups, the others only holding the
+#changes since the previous backup
+"
+		.to_string(),
+	);
+	retval.insert(
+		"[[wallet.backup_targets]]".to_string(),
+		"
+#where to copy backups to, by type: S3 (endpoint, region, bucket, prefix,
+#access_key, secret_key), WebDav (url, username, password) or Sftp (host,
+#port, user, path, identity_file), which uses the sftp command
+"
+		.to_string(),
+	);
+	retval.insert(
+		"[[wallet.hosted_wallets]]".to_string(),
+		"
+#other wallets the listeners serve alongside this one, each with an id and
+#the data_dir holding its wallet_data directory. Foreign api calls to a
+#hosted wallet are posted to /v2/foreign/<id>, so senders use that url as
+#their destination. Owner api calls give its id as their wallet_id
+#parameter, as do logins for bearer tokens valid for it, and its password
+#is asked for when the listener starts. A spending_policy table, as for
+#[wallet.spending_policy], replaces this wallet's policy for the hosted
+#wallet, which otherwise shares it
+"
+		.to_string(),
+	);
+	retval.insert(
+		"[[wallet.forwarded_wallets]]".to_string(),
+		"
+#wallets served by other listeners, each with an id and the upstream url of
+#its foreign listener, https unless it's on the same host, and optionally the
+#api_secret_path of the upstream's api secret. Foreign api calls posted to
+#/v2/foreign/<id> are passed on to the upstream's /v2/foreign as they came,
+#slate untouched, so one public endpoint can front many internal wallets
+"
+		.to_string(),
+	);
+	retval.insert(
+		"[wallet.recovery]".to_string(),
+		"
+#a transaction sweeping the wallet to a recovery destination, such as an
+#heir's wallet, which can't be posted before lock_blocks blocks (about a
+#year if not set). The destination's listener at dest, reached by method
+#(http if not set), signs for its output, and the finalized transaction is
+#written to export_file (recovery.tx in the data directory if not set) for
+#posting with grin-wallet post. It's rebuilt, checking every check_interval
+#seconds, whenever outputs with minimum_confirmations are spent or received,
+#so it only becomes valid if the wallet goes unused for that long
 "
 		.to_string(),
 	);
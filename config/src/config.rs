@@ -30,6 +30,7 @@ use crate::core::global;
 use crate::types::WalletConfig;
 use crate::types::{ConfigError, GlobalWalletConfig, GlobalWalletConfigMembers};
 use crate::util::LoggingConfig;
+use crate::validation;
 
 /// Wallet configuration file name
 pub const WALLET_CONFIG_FILE_NAME: &'static str = "grin-wallet.toml";
@@ -211,6 +212,18 @@ impl GlobalWalletConfig {
 		let decoded: Result<GlobalWalletConfigMembers, toml::de::Error> = toml::from_str(&contents);
 		match decoded {
 			Ok(gc) => {
+				let issues = validation::validate(&contents, &gc);
+				if !issues.is_empty() {
+					return Err(ConfigError::ValidationError(
+						self.config_file_path
+							.as_ref()
+							.unwrap()
+							.to_str()
+							.unwrap()
+							.to_owned(),
+						issues,
+					));
+				}
 				self.members = Some(gc);
 				return Ok(self);
 			}
@@ -29,6 +29,13 @@ use grin_wallet_util::grin_util as util;
 mod comments;
 pub mod config;
 pub mod types;
+mod validation;
 
 pub use crate::config::{initial_setup_wallet, GRIN_WALLET_DIR, WALLET_CONFIG_FILE_NAME};
-pub use crate::types::{ConfigError, GlobalWalletConfig, WalletConfig};
+pub use crate::types::{
+	BackupTargetConfig, ConfigError, CorsConfig, EmailEventType, EmailNotificationConfig,
+	EmailSlateConfig, EmailTemplate, EventFilter, ForwardedWalletConfig, GlobalWalletConfig,
+	HostedWalletConfig, ProxyConfig, ReceiveLimitsConfig, RecoveryConfig, ResourceProfile,
+	SpendConfirmationsConfig, SpendingPolicyConfig, TarpitConfig, WalletConfig, WebhookConfig,
+	WebhookEventType,
+};
@@ -20,6 +20,25 @@ use std::path::PathBuf;
 
 use crate::core::global::ChainTypes;
 use crate::util::LoggingConfig;
+use serde::{Deserialize, Deserializer};
+
+/// Accept node addresses given either as a single string or as a list,
+/// joining a list with commas
+fn node_addrs_from_string_or_list<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+	D: Deserializer<'de>,
+{
+	#[derive(Deserialize)]
+	#[serde(untagged)]
+	enum StringOrList {
+		String(String),
+		List(Vec<String>),
+	}
+	Ok(match StringOrList::deserialize(deserializer)? {
+		StringOrList::String(s) => s,
+		StringOrList::List(l) => l.join(","),
+	})
+}
 
 /// Command-line wallet configuration
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -31,31 +50,168 @@ pub struct WalletConfig {
 	pub api_listen_interface: String,
 	/// The port this wallet will run on
 	pub api_listen_port: u16,
+	/// Maximum size in bytes of a request body accepted by the foreign listener
+	pub foreign_api_max_body_size: Option<u64>,
+	/// Maximum number of requests per minute the foreign listener accepts
+	/// from each client, unlimited if not set
+	pub foreign_api_rate_limit: Option<u32>,
+	/// Addresses of reverse proxies in front of the foreign listener, whose
+	/// requests are identified by the client address in the headers they set
+	pub foreign_api_trusted_proxies: Option<Vec<String>>,
 	/// The port this wallet's owner API will run on
 	pub owner_api_listen_port: Option<u16>,
+	/// If set, the Owner API listens on a unix socket at this path, only
+	/// accessible to the wallet's user, rather than on owner_api_listen_port
+	pub owner_api_listen_socket: Option<String>,
 	/// Location of the secret for basic auth on the Owner API
 	pub api_secret_path: Option<String>,
+	/// If set, the Owner API secret is only used to log in, issuing bearer
+	/// tokens that expire after this many seconds unused
+	pub owner_api_token_ttl: Option<u64>,
+	/// Whether to record each Owner API call, who made it and its result in a
+	/// hash-chained audit log in the wallet's directory
+	pub owner_api_audit_log: Option<bool>,
+	/// Addresses of reverse proxies in front of the Owner API listener, whose
+	/// requests are audited with the client address in the headers they set
+	pub owner_api_trusted_proxies: Option<Vec<String>>,
 	/// Location of the node api secret for basic auth on the Grin API
 	pub node_api_secret_path: Option<String>,
 	/// The api address of a running server node against which transaction inputs
-	/// will be checked during send
+	/// will be checked during send. May be given as a list of addresses, in order
+	/// of preference, which the wallet fails over between. Held comma-separated
+	#[serde(deserialize_with = "node_addrs_from_string_or_list")]
 	pub check_node_api_http_addr: String,
+	/// The api address of an archival node, used as a fall-back for historical
+	/// queries (such as those made during restore and check) the primary node
+	/// is unable to answer
+	pub archive_node_api_http_addr: Option<String>,
+	/// Whether to look for a node on this machine when the node above can't
+	/// be reached at startup, offering to use it instead
+	pub node_discovery: Option<bool>,
+	/// URL of a signed directory of public nodes, for wallets without a node
+	/// of their own
+	pub public_node_directory_url: Option<String>,
+	/// Public key (hex) the public node directory must be signed with
+	pub public_node_directory_key: Option<String>,
 	/// Whether to include foreign API endpoints on the Owner API
 	pub owner_api_include_foreign: Option<bool>,
+	/// Whether the Owner API listener only serves methods retrieving
+	/// information, refusing any which change the wallet or spend
+	pub owner_api_read_only: Option<bool>,
+	/// Whether the Owner API listener serves read-only GraphQL queries at
+	/// /v3/graphql
+	pub owner_api_graphql: Option<bool>,
+	/// Interval in seconds at which the Owner API listener checks the node's
+	/// connectivity in the background. 0 disables the check
+	pub node_monitor_interval: Option<u64>,
+	/// Number of blocks after which the Owner API listener's updater cancels
+	/// transactions that still aren't confirmed, unlocking their inputs.
+	/// Unset or 0 leaves them be
+	pub auto_cancel_after_blocks: Option<u64>,
+	/// Whether the Owner API listener's updater spends confirmed received
+	/// outputs back to the wallet, anchoring them against replays
+	pub self_spend_on_receive: Option<bool>,
+	/// Amount, in nanogrins, above which slates received through the Foreign
+	/// API are queued for the owner to approve or reject rather than signed
+	pub receive_review_threshold: Option<u64>,
+	/// Public key (hex) transaction memos are sealed to in JSON history
+	/// exports, so they can be read by its holder without the wallet
+	pub memo_escrow_key: Option<String>,
+	/// Number of change outputs the change of a send is split between, when
+	/// not given on the command line
+	pub num_change_outputs: Option<u32>,
+	/// Whether to add a `meta` member to each API response, giving the API
+	/// version, server time and deprecation warnings for the method called
+	pub api_response_envelope: Option<bool>,
+	/// Whether to pretty-print API responses, unless a request asks otherwise
+	/// with its `pretty` query parameter
+	pub api_response_pretty: Option<bool>,
+	/// Largest API response to send, in bytes. Larger responses are replaced by
+	/// an error hinting at how to page through the data
+	pub api_max_response_bytes: Option<u64>,
+	/// Whether to redact amounts and commitments from logs and debug output,
+	/// so logs can be shared without disclosing balances. Can be toggled at
+	/// runtime through the Owner API
+	pub log_privacy: Option<bool>,
+	/// Whether to collect request metrics and serve them at /metrics on the Owner API
+	pub metrics_enabled: Option<bool>,
+	/// If set, metrics are also served on this port, without authentication
+	pub metrics_listen_port: Option<u16>,
+	/// If set, the Owner API, and the Foreign API if included in it, are
+	/// also served over gRPC on this port. Needs the grpc feature
+	pub owner_api_grpc_listen_port: Option<u16>,
 	/// The directory in which wallet files are stored
 	pub data_file_dir: String,
 	/// If Some(true), don't cache commits alongside output data
 	/// speed improvement, but your commits are in the database
 	pub no_commit_cache: Option<bool>,
+	/// Name of the scheme the keychain, and the paths of accounts and their
+	/// outputs, are derived from the seed with, `grin` if not set. Wallets
+	/// must be opened with the scheme they were created with
+	pub derivation_scheme: Option<String>,
 	/// TLS certificate file
 	pub tls_certificate_file: Option<String>,
 	/// TLS certificate private key file
 	pub tls_certificate_key: Option<String>,
+	/// File of CA certificates trusted to sign Owner API client certificates.
+	/// If set, Owner API clients must authenticate with a certificate
+	pub owner_api_tls_client_ca_file: Option<String>,
 	/// Whether to use the black background color scheme for command line
 	/// if enabled, wallet command output color will be suitable for black background terminal
 	pub dark_background_color_scheme: Option<bool>,
 	/// The exploding lifetime (minutes) for keybase notification on coins received
 	pub keybase_notify_ttl: Option<u16>,
+	/// Gateway to which push notifications for registered devices are posted
+	pub push_gateway_url: Option<String>,
+	/// Address of a ZeroMQ PUB socket publishing the events webhooks are
+	/// notified of, such as `tcp://127.0.0.1:28332`
+	pub zmq_publish_address: Option<String>,
+	/// Service queried for historical grin prices in settlement reports
+	pub price_oracle_url: Option<String>,
+	/// Interval in seconds at which the listeners back up the wallet to the
+	/// backup targets
+	pub backup_interval: Option<u64>,
+	/// Take a full backup every this many backups, the others only holding
+	/// what changed since the previous one
+	pub backup_full_every: Option<u32>,
+	/// Caps on threads, batch sizes and concurrency, for constrained devices
+	pub resource_profile: Option<ResourceProfile>,
+	/// Minimum confirmations before coinbase, received and change outputs are
+	/// spent, in place of those asked for when sending
+	pub spend_confirmations: Option<SpendConfirmationsConfig>,
+	/// Limits on the amounts sent and the destinations sent to
+	pub spending_policy: Option<SpendingPolicyConfig>,
+	/// Limits on the slates received through the Foreign API
+	pub receive_limits: Option<ReceiveLimitsConfig>,
+	/// CORS policy for the Owner API, allowing any origin if not set
+	pub owner_api_cors: Option<CorsConfig>,
+	/// CORS policy for the Foreign API, allowing any origin if not set
+	pub foreign_api_cors: Option<CorsConfig>,
+	/// Tarpit holding clients over the foreign listener's rate limit, which
+	/// are turned away straight away if not set
+	pub foreign_api_tarpit: Option<TarpitConfig>,
+	/// SOCKS5 proxy, such as Tor's, to reach nodes and send slates through
+	pub proxy: Option<ProxyConfig>,
+	/// Events published on the ZeroMQ socket, all events if not set
+	pub zmq_publish_filter: Option<EventFilter>,
+	/// Endpoints to notify of transaction lifecycle events
+	pub webhooks: Option<Vec<WebhookConfig>>,
+	/// Emails sent through an SMTP server on significant events
+	pub email_notifications: Option<EmailNotificationConfig>,
+	/// Mailbox slates are exchanged through by email
+	pub email_slates: Option<EmailSlateConfig>,
+	/// Places backups are copied to, off the host
+	pub backup_targets: Option<Vec<BackupTargetConfig>>,
+	/// Other wallets served by the listeners alongside this one, addressed by
+	/// the `/v2/foreign/<wallet_id>` path of Foreign API calls and the
+	/// `wallet_id` parameter of Owner API calls
+	pub hosted_wallets: Option<Vec<HostedWalletConfig>>,
+	/// Wallets served by other listeners, to which Foreign API calls posted
+	/// to `/v2/foreign/<wallet_id>` are passed on
+	pub forwarded_wallets: Option<Vec<ForwardedWalletConfig>>,
+	/// Height-locked transaction sweeping the wallet to a recovery
+	/// destination, kept exported should the wallet go unused for long
+	pub recovery: Option<RecoveryConfig>,
 }
 
 impl Default for WalletConfig {
@@ -64,17 +220,66 @@ impl Default for WalletConfig {
 			chain_type: Some(ChainTypes::Mainnet),
 			api_listen_interface: "127.0.0.1".to_string(),
 			api_listen_port: 3415,
+			foreign_api_max_body_size: Some(WalletConfig::default_foreign_api_max_body_size()),
+			foreign_api_rate_limit: None,
+			foreign_api_trusted_proxies: None,
 			owner_api_listen_port: Some(WalletConfig::default_owner_api_listen_port()),
+			owner_api_listen_socket: None,
 			api_secret_path: Some(".api_secret".to_string()),
+			owner_api_token_ttl: None,
+			owner_api_audit_log: None,
+			owner_api_trusted_proxies: None,
 			node_api_secret_path: Some(".api_secret".to_string()),
 			check_node_api_http_addr: "http://127.0.0.1:3413".to_string(),
+			archive_node_api_http_addr: None,
+			node_discovery: Some(false),
+			public_node_directory_url: None,
+			public_node_directory_key: None,
 			owner_api_include_foreign: Some(false),
+			owner_api_read_only: None,
+			owner_api_graphql: None,
+			node_monitor_interval: Some(60),
+			auto_cancel_after_blocks: None,
+			self_spend_on_receive: Some(false),
+			receive_review_threshold: None,
+			memo_escrow_key: None,
+			num_change_outputs: Some(1),
+			api_response_envelope: Some(false),
+			api_response_pretty: Some(true),
+			api_max_response_bytes: None,
+			log_privacy: Some(false),
+			metrics_enabled: Some(false),
+			metrics_listen_port: None,
+			owner_api_grpc_listen_port: None,
 			data_file_dir: ".".to_string(),
 			no_commit_cache: Some(false),
+			derivation_scheme: None,
 			tls_certificate_file: None,
 			tls_certificate_key: None,
+			owner_api_tls_client_ca_file: None,
 			dark_background_color_scheme: Some(true),
 			keybase_notify_ttl: Some(1440),
+			push_gateway_url: None,
+			zmq_publish_address: None,
+			price_oracle_url: None,
+			backup_interval: Some(3600),
+			backup_full_every: Some(24),
+			resource_profile: Some(ResourceProfile::Standard),
+			spend_confirmations: None,
+			spending_policy: None,
+			receive_limits: None,
+			owner_api_cors: None,
+			foreign_api_cors: None,
+			foreign_api_tarpit: None,
+			proxy: None,
+			zmq_publish_filter: None,
+			webhooks: None,
+			email_notifications: None,
+			email_slates: None,
+			backup_targets: None,
+			hosted_wallets: None,
+			forwarded_wallets: None,
+			recovery: None,
 		}
 	}
 }
@@ -90,6 +295,17 @@ impl WalletConfig {
 		3420
 	}
 
+	/// Default maximum foreign API request body size
+	pub fn default_foreign_api_max_body_size() -> u64 {
+		1_048_576
+	}
+
+	/// Use value from config file, defaulting to sensible value if missing.
+	pub fn foreign_api_max_body_size(&self) -> u64 {
+		self.foreign_api_max_body_size
+			.unwrap_or(WalletConfig::default_foreign_api_max_body_size())
+	}
+
 	/// Use value from config file, defaulting to sensible value if missing.
 	pub fn owner_api_listen_port(&self) -> u16 {
 		self.owner_api_listen_port
@@ -100,7 +316,442 @@ impl WalletConfig {
 	pub fn owner_api_listen_addr(&self) -> String {
 		format!("127.0.0.1:{}", self.owner_api_listen_port())
 	}
+
+	/// Owner API gRPC listen address, if the API is to be served over gRPC
+	pub fn owner_api_grpc_listen_addr(&self) -> Option<String> {
+		self.owner_api_grpc_listen_port
+			.map(|p| format!("127.0.0.1:{}", p))
+	}
+
+	/// Metrics listen address, if metrics are to be served on a separate port
+	pub fn metrics_listen_addr(&self) -> Option<String> {
+		match self.metrics_enabled {
+			Some(true) => self.metrics_listen_port.map(|p| format!("127.0.0.1:{}", p)),
+			_ => None,
+		}
+	}
+}
+/// Resource usage profiles, trading speed for memory and thread usage
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ResourceProfile {
+	/// For mobile and embedded devices
+	Low,
+	/// For desktop wallets
+	Standard,
+	/// For wallets running on dedicated servers
+	Server,
+}
+
+/// Minimum confirmations before outputs are spent, by where they came from.
+/// Sources not set need the number of confirmations asked for
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct SpendConfirmationsConfig {
+	/// Confirmations for coinbase outputs, once mature
+	#[serde(default)]
+	pub coinbase: Option<u64>,
+	/// Confirmations for outputs received from others
+	#[serde(default)]
+	pub received: Option<u64>,
+	/// Confirmations for change outputs
+	#[serde(default)]
+	pub change: Option<u64>,
+}
+
+/// Limits on what the wallet sends, enforced when sends are started and
+/// finalized. Limits not set don't apply
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct SpendingPolicyConfig {
+	/// Largest amount sent in one transaction, in nanogrin
+	#[serde(default)]
+	pub max_amount_per_tx: Option<u64>,
+	/// Largest amount sent over the last 24 hours, in nanogrin
+	#[serde(default)]
+	pub max_daily_total: Option<u64>,
+	/// Destinations slates may be sent to, refusing sends to any other or
+	/// returning the slate to the caller
+	#[serde(default)]
+	pub allowed_destinations: Option<Vec<String>>,
+}
+
+/// Limits on the slates received through the Foreign API. Limits not set
+/// don't apply
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ReceiveLimitsConfig {
+	/// Smallest amount received, in nanogrin
+	#[serde(default)]
+	pub min_amount: Option<u64>,
+	/// Largest amount received, in nanogrin
+	#[serde(default)]
+	pub max_amount: Option<u64>,
+	/// Most slates received in an hour
+	#[serde(default)]
+	pub max_per_hour: Option<u32>,
+	/// Whether invoices issued by the wallet are finalized, true if not set
+	#[serde(default)]
+	pub finalize_invoices: Option<bool>,
+}
+
+/// A SOCKS5 proxy outbound connections are made through
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProxyConfig {
+	/// Address of the proxy, such as `127.0.0.1:9050` for Tor
+	pub socks5_address: String,
+	/// Username to authenticate to the proxy with, if it needs one
+	#[serde(default)]
+	pub username: Option<String>,
+	/// Password to authenticate to the proxy with
+	#[serde(default)]
+	pub password: Option<String>,
+}
+
+/// Transaction lifecycle events a webhook can be notified of
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum WebhookEventType {
+	/// A slate was received via the Foreign API
+	TxReceived,
+	/// A transaction was confirmed on chain
+	TxConfirmed,
+	/// A transaction was cancelled
+	TxCancelled,
+	/// The height the wallet was last refreshed to went up
+	HeightUpdated,
+}
+
+/// A webhook endpoint, along with the events it should be notified of
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WebhookConfig {
+	/// URL to which event payloads are POSTed
+	pub url: String,
+	/// Events to notify the endpoint of, all events if empty
+	#[serde(default)]
+	pub events: Vec<WebhookEventType>,
+	/// Secret the payloads sent are signed with, in an `X-Grin-Signature`
+	/// header giving their HMAC-SHA256 as `sha256=<hex>`
+	#[serde(default)]
+	pub secret: Option<String>,
+	/// Number of attempts to deliver each event before giving up and keeping it
+	/// as a dead letter, 5 if unset
+	#[serde(default)]
+	pub max_attempts: Option<u32>,
+	/// Labels of the accounts whose transactions the endpoint is notified
+	/// of, all accounts if empty
+	#[serde(default)]
+	pub accounts: Vec<String>,
+	/// Smallest amount moved by a transaction the endpoint is notified of
+	#[serde(default)]
+	pub min_amount: Option<u64>,
+}
+
+impl WebhookConfig {
+	/// The events the endpoint is to be notified of
+	pub fn filter(&self) -> EventFilter {
+		EventFilter {
+			events: self.events.clone(),
+			accounts: self.accounts.clone(),
+			min_amount: self.min_amount,
+		}
+	}
 }
+
+/// Filter on the events sent to a consumer. The account and amount filters
+/// only apply to events about a transaction
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct EventFilter {
+	/// Events to send, all events if empty
+	#[serde(default)]
+	pub events: Vec<WebhookEventType>,
+	/// Labels of the accounts whose transactions are sent, all accounts if
+	/// empty
+	#[serde(default)]
+	pub accounts: Vec<String>,
+	/// Smallest amount moved by a transaction that's sent
+	#[serde(default)]
+	pub min_amount: Option<u64>,
+}
+
+/// Significant events an email can be sent for
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum EmailEventType {
+	/// More than the configured amount was sent
+	LargeSend,
+	/// Many Owner API requests failed authentication in a short time
+	AuthFailures,
+	/// The node has been unreachable for longer than configured
+	NodeUnreachable,
+}
+
+/// Subject and body of the emails sent for an event, in which `{name}`
+/// placeholders are replaced with the event's details
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EmailTemplate {
+	/// Event the template is used for
+	pub event: EmailEventType,
+	/// Subject line
+	pub subject: String,
+	/// Plain text body
+	pub body: String,
+}
+
+/// An SMTP server to send emails through, and the events worth sending them
+/// for. No emails are sent for an event without a threshold
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EmailNotificationConfig {
+	/// URL of the server, as smtps://host:465, or smtp://host:587 to
+	/// upgrade the connection with STARTTLS
+	pub smtp_url: String,
+	/// User to authenticate as, if any
+	#[serde(default)]
+	pub username: Option<String>,
+	/// Password of the user
+	#[serde(default)]
+	pub password: Option<String>,
+	/// Whether to refuse sending over an unencrypted connection, true if unset
+	#[serde(default)]
+	pub require_tls: Option<bool>,
+	/// Sender address
+	pub from: String,
+	/// Recipient addresses
+	pub to: Vec<String>,
+	/// Send an email for sends of more than this amount, in nanogrins
+	#[serde(default)]
+	pub min_send_amount: Option<u64>,
+	/// Send an email when this many Owner API requests fail authentication
+	/// within `auth_failure_window` seconds
+	#[serde(default)]
+	pub auth_failures: Option<u32>,
+	/// Window in which authentication failures are counted, 300 seconds if unset
+	#[serde(default)]
+	pub auth_failure_window: Option<u64>,
+	/// Send an email once the node has been unreachable for this many minutes
+	#[serde(default)]
+	pub node_unreachable_mins: Option<u64>,
+	/// Templates replacing the default ones
+	#[serde(default)]
+	pub templates: Vec<EmailTemplate>,
+}
+
+/// Mailbox slates are sent from through SMTP, and received at through IMAP
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EmailSlateConfig {
+	/// URL of the SMTP server, as smtps://host:465, or smtp://host:587 to
+	/// upgrade the connection with STARTTLS
+	pub smtp_url: String,
+	/// URL of the IMAP folder slates arrive in, as imaps://host:993/INBOX
+	pub imap_url: String,
+	/// User to authenticate to both servers as, if any
+	#[serde(default)]
+	pub username: Option<String>,
+	/// Password of the user
+	#[serde(default)]
+	pub password: Option<String>,
+	/// Whether to refuse unencrypted connections, true if unset
+	#[serde(default)]
+	pub require_tls: Option<bool>,
+	/// Address slates are sent from, and replied to
+	pub address: String,
+	/// How often to check the folder for slates, in seconds, 30 if unset
+	#[serde(default)]
+	pub poll_interval: Option<u64>,
+	/// How long to wait for the reply to a sent slate, in seconds, 600 if unset
+	#[serde(default)]
+	pub reply_timeout: Option<u64>,
+}
+
+/// A wallet served by the listeners alongside the wallet they were started
+/// for, with its own data directory and seed
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HostedWalletConfig {
+	/// Id of the wallet, in the path of its Foreign API calls and as the
+	/// `wallet_id` parameter of its Owner API calls
+	pub id: String,
+	/// Top level directory of the wallet, holding its wallet_data directory
+	pub data_dir: String,
+	/// Limits on what the wallet sends, in place of the spending policy of
+	/// the wallet serving it
+	#[serde(default)]
+	pub spending_policy: Option<SpendingPolicyConfig>,
+}
+
+/// A wallet served by another listener, which the Foreign API passes calls
+/// for it on to, so one public endpoint can front many internal wallets
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ForwardedWalletConfig {
+	/// Id of the wallet, in the path of its Foreign API calls
+	pub id: String,
+	/// Base URL of the wallet's foreign listener, e.g. https://10.0.0.5:3415.
+	/// Plain http is only allowed to a listener on the same host
+	pub upstream: String,
+	/// Location of the API secret of the wallet's foreign listener, if it
+	/// requires one
+	#[serde(default)]
+	pub api_secret_path: Option<String>,
+}
+
+/// A transaction sweeping the wallet to a recovery destination, such as an
+/// heir's wallet, pre-signed and exported so it can be posted once the chain
+/// reaches its lock height. It's rebuilt whenever the wallet's spendable
+/// outputs change, so the lock height is only reached if the wallet isn't
+/// used for that long
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecoveryConfig {
+	/// Listener of the wallet receiving the funds, e.g. http://10.0.0.5:3415
+	pub dest: String,
+	/// Method the destination is reached with, http if not set
+	#[serde(default)]
+	pub method: Option<String>,
+	/// Blocks from the time it's built before the transaction can be posted,
+	/// about a year if not set
+	#[serde(default)]
+	pub lock_blocks: Option<u64>,
+	/// Confirmations an output needs to be swept, 10 if not set
+	#[serde(default)]
+	pub minimum_confirmations: Option<u64>,
+	/// File the transaction is exported to, recovery.tx in the data directory
+	/// if not set
+	#[serde(default)]
+	pub export_file: Option<String>,
+	/// Seconds between checks that the transaction still sweeps the wallet,
+	/// 3600 if not set
+	#[serde(default)]
+	pub check_interval: Option<u64>,
+}
+
+/// Somewhere off the host to copy backups to. Backups are encrypted with a
+/// key derived from the wallet's seed before being sent
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type")]
+pub enum BackupTargetConfig {
+	/// An S3-compatible object store, addressed path-style
+	S3 {
+		/// Endpoint of the store, e.g. https://s3.eu-west-1.amazonaws.com
+		endpoint: String,
+		/// Region the bucket is in, used to sign requests
+		region: String,
+		/// Bucket to store backups in
+		bucket: String,
+		/// Prefix of the stored objects' keys
+		#[serde(default)]
+		prefix: String,
+		/// Access key id
+		access_key: String,
+		/// Secret access key
+		secret_key: String,
+	},
+	/// A directory on a WebDAV server
+	WebDav {
+		/// URL of the directory
+		url: String,
+		/// User to authenticate as, if any
+		username: Option<String>,
+		/// Password of the user
+		password: Option<String>,
+	},
+	/// A directory on an SFTP server, copied to with the system's `sftp`
+	/// client, which must be able to log in without a password
+	Sftp {
+		/// Host to connect to
+		host: String,
+		/// Port to connect to, 22 if not set
+		port: Option<u16>,
+		/// User to log in as
+		user: String,
+		/// Directory on the server
+		path: String,
+		/// Private key to log in with, if not the client's default
+		identity_file: Option<String>,
+	},
+}
+
+/// Cross-origin resource sharing policy for an API listener
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CorsConfig {
+	/// Origins allowed to make requests, or "*" for any origin
+	#[serde(default = "CorsConfig::default_allowed_origins")]
+	pub allowed_origins: Vec<String>,
+	/// Methods allowed in cross-origin requests
+	#[serde(default = "CorsConfig::default_allowed_methods")]
+	pub allowed_methods: Vec<String>,
+	/// Headers allowed in cross-origin requests
+	#[serde(default = "CorsConfig::default_allowed_headers")]
+	pub allowed_headers: Vec<String>,
+}
+
+impl Default for CorsConfig {
+	fn default() -> CorsConfig {
+		CorsConfig {
+			allowed_origins: CorsConfig::default_allowed_origins(),
+			allowed_methods: CorsConfig::default_allowed_methods(),
+			allowed_headers: CorsConfig::default_allowed_headers(),
+		}
+	}
+}
+
+impl CorsConfig {
+	fn default_allowed_origins() -> Vec<String> {
+		vec!["*".to_owned()]
+	}
+
+	fn default_allowed_methods() -> Vec<String> {
+		vec!["GET".to_owned(), "POST".to_owned(), "OPTIONS".to_owned()]
+	}
+
+	fn default_allowed_headers() -> Vec<String> {
+		vec![
+			"Content-Type".to_owned(),
+			"Authorization".to_owned(),
+			"X-Grin-Token".to_owned(),
+		]
+	}
+}
+
+/// Tarpit for clients over a listener's rate limit, answering them with a
+/// response dripped out slowly rather than at once. Connections held are
+/// capped per client and in all, clients over the caps being turned away
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TarpitConfig {
+	/// Seconds between each byte of the response
+	#[serde(default = "TarpitConfig::default_drip_interval_secs")]
+	pub drip_interval_secs: u64,
+	/// Seconds a connection is held before the response is completed
+	#[serde(default = "TarpitConfig::default_hold_secs")]
+	pub hold_secs: u64,
+	/// Most connections held for any one client
+	#[serde(default = "TarpitConfig::default_max_connections_per_client")]
+	pub max_connections_per_client: u32,
+	/// Most connections held for all clients
+	#[serde(default = "TarpitConfig::default_max_connections")]
+	pub max_connections: u32,
+}
+
+impl Default for TarpitConfig {
+	fn default() -> TarpitConfig {
+		TarpitConfig {
+			drip_interval_secs: TarpitConfig::default_drip_interval_secs(),
+			hold_secs: TarpitConfig::default_hold_secs(),
+			max_connections_per_client: TarpitConfig::default_max_connections_per_client(),
+			max_connections: TarpitConfig::default_max_connections(),
+		}
+	}
+}
+
+impl TarpitConfig {
+	fn default_drip_interval_secs() -> u64 {
+		10
+	}
+
+	fn default_hold_secs() -> u64 {
+		600
+	}
+
+	fn default_max_connections_per_client() -> u32 {
+		4
+	}
+
+	fn default_max_connections() -> u32 {
+		256
+	}
+}
+
 /// Error type wrapping config errors.
 #[derive(Debug)]
 pub enum ConfigError {
@@ -115,6 +766,9 @@ pub enum ConfigError {
 
 	/// Error serializing config values
 	SerializationError(String),
+
+	/// Config file parsed, but has invalid or conflicting values
+	ValidationError(String, Vec<String>),
 }
 
 impl fmt::Display for ConfigError {
@@ -134,6 +788,13 @@ impl fmt::Display for ConfigError {
 			ConfigError::SerializationError(ref message) => {
 				write!(f, "Error serializing configuration: {}", message)
 			}
+			ConfigError::ValidationError(ref file_name, ref issues) => {
+				write!(f, "Invalid configuration file at {}:", file_name)?;
+				for i in issues {
+					write!(f, "\n  {}", i)?;
+				}
+				Ok(())
+			}
 		}
 	}
 }
@@ -0,0 +1,507 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Validation of a parsed configuration file, so mistakes are reported
+//! together at startup rather than one at a time at first use
+
+use std::collections::HashSet;
+use std::net::IpAddr;
+
+use crate::types::{BackupTargetConfig, GlobalWalletConfigMembers, WalletConfig};
+use crate::util::LoggingConfig;
+use toml;
+
+/// Keys accepted in the `[wallet]` section
+const WALLET_KEYS: &[&str] = &[
+	"chain_type",
+	"api_listen_interface",
+	"api_listen_port",
+	"foreign_api_max_body_size",
+	"foreign_api_rate_limit",
+	"foreign_api_trusted_proxies",
+	"owner_api_listen_port",
+	"owner_api_listen_socket",
+	"api_secret_path",
+	"owner_api_token_ttl",
+	"owner_api_audit_log",
+	"owner_api_trusted_proxies",
+	"node_api_secret_path",
+	"check_node_api_http_addr",
+	"archive_node_api_http_addr",
+	"node_discovery",
+	"public_node_directory_url",
+	"public_node_directory_key",
+	"owner_api_include_foreign",
+	"owner_api_read_only",
+	"owner_api_graphql",
+	"node_monitor_interval",
+	"auto_cancel_after_blocks",
+	"self_spend_on_receive",
+	"receive_review_threshold",
+	"memo_escrow_key",
+	"num_change_outputs",
+	"api_response_envelope",
+	"api_response_pretty",
+	"api_max_response_bytes",
+	"log_privacy",
+	"metrics_enabled",
+	"metrics_listen_port",
+	"owner_api_grpc_listen_port",
+	"data_file_dir",
+	"no_commit_cache",
+	"derivation_scheme",
+	"tls_certificate_file",
+	"tls_certificate_key",
+	"owner_api_tls_client_ca_file",
+	"dark_background_color_scheme",
+	"keybase_notify_ttl",
+	"push_gateway_url",
+	"zmq_publish_address",
+	"price_oracle_url",
+	"backup_interval",
+	"backup_full_every",
+	"resource_profile",
+	"spend_confirmations",
+	"spending_policy",
+	"receive_limits",
+	"owner_api_cors",
+	"foreign_api_cors",
+	"foreign_api_tarpit",
+	"proxy",
+	"zmq_publish_filter",
+	"webhooks",
+	"email_notifications",
+	"email_slates",
+	"backup_targets",
+	"hosted_wallets",
+	"forwarded_wallets",
+	"recovery",
+];
+
+/// Keys accepted in the `[logging]` section, besides any set by default
+const LOGGING_KEYS: &[&str] = &[
+	"log_to_stdout",
+	"stdout_log_level",
+	"log_to_file",
+	"file_log_level",
+	"log_file_path",
+	"log_file_append",
+	"log_max_size",
+	"log_max_files",
+	"tui_running",
+];
+
+/// A problem found in the configuration file
+struct Issue {
+	section: String,
+	key: Option<String>,
+	message: String,
+}
+
+impl Issue {
+	fn new(section: &str, key: Option<&str>, message: String) -> Issue {
+		Issue {
+			section: section.to_owned(),
+			key: key.map(|k| k.to_owned()),
+			message,
+		}
+	}
+}
+
+/// Line (1-based) on which `key` is set within `section`, or on which the
+/// section starts if no key is given
+fn find_line(contents: &str, section: &str, key: Option<&str>) -> Option<usize> {
+	let header = format!("[{}]", section);
+	let mut in_section = false;
+	for (i, line) in contents.lines().enumerate() {
+		let line = line.trim();
+		if line.starts_with('[') {
+			in_section = line == header || line.starts_with(&format!("[[{}.", section));
+			if line == header && key.is_none() {
+				return Some(i + 1);
+			}
+			continue;
+		}
+		if let (true, Some(k)) = (in_section, key) {
+			let name = line.split('=').next().unwrap_or("").trim();
+			if name == k || name.trim_matches('"') == k {
+				return Some(i + 1);
+			}
+		}
+	}
+	None
+}
+
+/// Whether the address looks like an http(s) URL with a host
+fn is_http_url(addr: &str) -> bool {
+	let rest = if addr.starts_with("http://") {
+		&addr[7..]
+	} else if addr.starts_with("https://") {
+		&addr[8..]
+	} else {
+		return false;
+	};
+	let host = rest.split('/').next().unwrap_or("");
+	!host.is_empty() && !host.contains(char::is_whitespace)
+}
+
+/// Whether calls can be forwarded to the upstream at the URL: https, or http
+/// only to a host on the same machine
+fn is_secure_upstream(addr: &str) -> bool {
+	if !is_http_url(addr) {
+		return false;
+	}
+	if addr.starts_with("https://") {
+		return true;
+	}
+	let host = addr[7..].split('/').next().unwrap_or("");
+	let host = match host.starts_with('[') {
+		true => host[1..].split(']').next().unwrap_or(""),
+		false => host.split(':').next().unwrap_or(""),
+	};
+	host == "localhost" || host.parse::<IpAddr>().map_or(false, |ip| ip.is_loopback())
+}
+
+fn check_url(issues: &mut Vec<Issue>, key: &str, addr: &str) {
+	if !is_http_url(addr) {
+		issues.push(Issue::new(
+			"wallet",
+			Some(key),
+			format!("'{}' is not a valid http or https URL", addr),
+		));
+	}
+}
+
+fn check_unknown_keys(issues: &mut Vec<Issue>, value: &toml::Value) {
+	let mut logging_keys: Vec<String> = LOGGING_KEYS.iter().map(|k| k.to_string()).collect();
+	if let Ok(toml::Value::Table(t)) = toml::Value::try_from(LoggingConfig::default()) {
+		logging_keys.extend(t.keys().cloned());
+	}
+	let table = match value.as_table() {
+		Some(t) => t,
+		None => return,
+	};
+	for (section, contents) in table {
+		let known: Vec<&str> = match section.as_str() {
+			"wallet" => WALLET_KEYS.to_vec(),
+			"logging" => logging_keys.iter().map(|k| k.as_str()).collect(),
+			_ => {
+				issues.push(Issue::new(
+					section,
+					None,
+					format!("unknown section [{}]", section),
+				));
+				continue;
+			}
+		};
+		if let Some(t) = contents.as_table() {
+			for key in t.keys() {
+				if !known.contains(&key.as_str()) {
+					issues.push(Issue::new(
+						section,
+						Some(key),
+						format!("unknown key '{}'", key),
+					));
+				}
+			}
+		}
+	}
+}
+
+fn check_wallet(issues: &mut Vec<Issue>, config: &WalletConfig) {
+	if config.api_listen_interface.parse::<IpAddr>().is_err() {
+		issues.push(Issue::new(
+			"wallet",
+			Some("api_listen_interface"),
+			format!(
+				"'{}' is not a valid IP address",
+				config.api_listen_interface
+			),
+		));
+	}
+	for addr in config.check_node_api_http_addr.split(',') {
+		check_url(issues, "check_node_api_http_addr", addr.trim());
+	}
+	let urls = [
+		(
+			"archive_node_api_http_addr",
+			&config.archive_node_api_http_addr,
+		),
+		("push_gateway_url", &config.push_gateway_url),
+		("price_oracle_url", &config.price_oracle_url),
+		(
+			"public_node_directory_url",
+			&config.public_node_directory_url,
+		),
+	];
+	for (key, url) in urls.iter() {
+		if let Some(u) = url {
+			check_url(issues, key, u);
+		}
+	}
+	for webhook in config.webhooks.iter().flatten() {
+		check_url(issues, "webhooks", &webhook.url);
+	}
+	for target in config.backup_targets.iter().flatten() {
+		match target {
+			BackupTargetConfig::S3 { endpoint, .. } => {
+				check_url(issues, "backup_targets", endpoint)
+			}
+			BackupTargetConfig::WebDav { url, .. } => check_url(issues, "backup_targets", url),
+			BackupTargetConfig::Sftp { .. } => {}
+		}
+	}
+	if config.proxy.is_some() && config.check_node_api_http_addr.contains("https://") {
+		issues.push(Issue::new(
+			"wallet",
+			Some("check_node_api_http_addr"),
+			"https nodes can't be reached through the proxy".to_owned(),
+		));
+	}
+	if let Some(e) = config.email_notifications.as_ref() {
+		if !e.smtp_url.starts_with("smtp://") && !e.smtp_url.starts_with("smtps://") {
+			issues.push(Issue::new(
+				"wallet",
+				Some("email_notifications"),
+				format!("'{}' is not an smtp or smtps URL", e.smtp_url),
+			));
+		}
+		if e.to.is_empty() {
+			issues.push(Issue::new(
+				"wallet",
+				Some("email_notifications"),
+				"no recipients are given".to_owned(),
+			));
+		}
+	}
+	if let Some(e) = config.email_slates.as_ref() {
+		if !e.smtp_url.starts_with("smtp://") && !e.smtp_url.starts_with("smtps://") {
+			issues.push(Issue::new(
+				"wallet",
+				Some("email_slates"),
+				format!("'{}' is not an smtp or smtps URL", e.smtp_url),
+			));
+		}
+		if !e.imap_url.starts_with("imap://") && !e.imap_url.starts_with("imaps://") {
+			issues.push(Issue::new(
+				"wallet",
+				Some("email_slates"),
+				format!("'{}' is not an imap or imaps URL", e.imap_url),
+			));
+		}
+	}
+	if config.backup_interval == Some(0) {
+		issues.push(Issue::new(
+			"wallet",
+			Some("backup_interval"),
+			"backup_interval must be at least 1 second".to_owned(),
+		));
+	}
+	if config.backup_full_every == Some(0) {
+		issues.push(Issue::new(
+			"wallet",
+			Some("backup_full_every"),
+			"backup_full_every must be at least 1".to_owned(),
+		));
+	}
+	if config.api_max_response_bytes == Some(0) {
+		issues.push(Issue::new(
+			"wallet",
+			Some("api_max_response_bytes"),
+			"api_max_response_bytes must be at least 1".to_owned(),
+		));
+	}
+	let trusted_proxies = [
+		(
+			"foreign_api_trusted_proxies",
+			&config.foreign_api_trusted_proxies,
+		),
+		(
+			"owner_api_trusted_proxies",
+			&config.owner_api_trusted_proxies,
+		),
+	];
+	for (key, proxies) in trusted_proxies.iter() {
+		for proxy in proxies.iter().flatten() {
+			if proxy.parse::<IpAddr>().is_err() {
+				issues.push(Issue::new(
+					"wallet",
+					Some(*key),
+					format!("'{}' is not an IP address", proxy),
+				));
+			}
+		}
+	}
+	if let Some(t) = config.foreign_api_tarpit.as_ref() {
+		if t.drip_interval_secs == 0 {
+			issues.push(Issue::new(
+				"wallet",
+				Some("foreign_api_tarpit"),
+				"drip_interval_secs must be at least 1 second".to_owned(),
+			));
+		}
+	}
+	let mut hosted_ids = HashSet::new();
+	for hosted in config.hosted_wallets.iter().flatten() {
+		if hosted.id.is_empty() || !hosted_ids.insert(hosted.id.as_str()) {
+			issues.push(Issue::new(
+				"wallet",
+				Some("hosted_wallets"),
+				format!("'{}' is not a unique wallet id", hosted.id),
+			));
+		}
+	}
+	for forwarded in config.forwarded_wallets.iter().flatten() {
+		if forwarded.id.is_empty() || !hosted_ids.insert(forwarded.id.as_str()) {
+			issues.push(Issue::new(
+				"wallet",
+				Some("forwarded_wallets"),
+				format!("'{}' is not a unique wallet id", forwarded.id),
+			));
+		}
+		if !is_secure_upstream(&forwarded.upstream) {
+			issues.push(Issue::new(
+				"wallet",
+				Some("forwarded_wallets"),
+				format!(
+					"upstream of wallet '{}' must be an https url, or an http url on the same host",
+					forwarded.id
+				),
+			));
+		}
+	}
+
+	if let Some(recovery) = config.recovery.as_ref() {
+		let method = recovery.method.as_ref().map_or("http", |m| m.as_str());
+		if method == "http"
+			&& !recovery.dest.starts_with("http://")
+			&& !recovery.dest.starts_with("https://")
+		{
+			issues.push(Issue::new(
+				"wallet",
+				Some("recovery"),
+				"dest must be an http or https url".to_owned(),
+			));
+		}
+		if recovery.lock_blocks == Some(0) {
+			issues.push(Issue::new(
+				"wallet",
+				Some("recovery"),
+				"lock_blocks must be at least 1".to_owned(),
+			));
+		}
+	}
+
+	let owner_port = config.owner_api_listen_port();
+	if owner_port == config.api_listen_port {
+		issues.push(Issue::new(
+			"wallet",
+			Some("owner_api_listen_port"),
+			format!(
+				"the Owner API can't listen on port {}, which the Foreign API listens on",
+				owner_port
+			),
+		));
+	}
+	if let Some(p) = config.metrics_listen_port {
+		if config.metrics_enabled != Some(true) {
+			issues.push(Issue::new(
+				"wallet",
+				Some("metrics_listen_port"),
+				"metrics_listen_port is set but metrics_enabled isn't".to_owned(),
+			));
+		}
+		if p == owner_port || p == config.api_listen_port {
+			issues.push(Issue::new(
+				"wallet",
+				Some("metrics_listen_port"),
+				format!("port {} is already used by the Owner or Foreign API", p),
+			));
+		}
+	}
+	if let Some(p) = config.owner_api_grpc_listen_port {
+		if p == owner_port || p == config.api_listen_port || Some(p) == config.metrics_listen_port {
+			issues.push(Issue::new(
+				"wallet",
+				Some("owner_api_grpc_listen_port"),
+				format!("port {} is already used by another listener", p),
+			));
+		}
+	}
+	match (&config.tls_certificate_file, &config.tls_certificate_key) {
+		(Some(_), None) => issues.push(Issue::new(
+			"wallet",
+			Some("tls_certificate_file"),
+			"tls_certificate_file is set without tls_certificate_key".to_owned(),
+		)),
+		(None, Some(_)) => issues.push(Issue::new(
+			"wallet",
+			Some("tls_certificate_key"),
+			"tls_certificate_key is set without tls_certificate_file".to_owned(),
+		)),
+		_ => {}
+	}
+	if config.owner_api_tls_client_ca_file.is_some() && config.tls_certificate_file.is_none() {
+		issues.push(Issue::new(
+			"wallet",
+			Some("owner_api_tls_client_ca_file"),
+			"client certificates can only be required when TLS is configured with \
+			 tls_certificate_file and tls_certificate_key"
+				.to_owned(),
+		));
+	}
+	if config.owner_api_listen_socket.is_some() && config.owner_api_tls_client_ca_file.is_some() {
+		issues.push(Issue::new(
+			"wallet",
+			Some("owner_api_listen_socket"),
+			"client certificates (owner_api_tls_client_ca_file) can't be required on the \
+			 Owner API's unix socket, which doesn't use TLS"
+				.to_owned(),
+		));
+	}
+	if config.owner_api_include_foreign == Some(true)
+		&& config.owner_api_tls_client_ca_file.is_some()
+	{
+		issues.push(Issue::new(
+			"wallet",
+			Some("owner_api_include_foreign"),
+			"the Foreign API can't be included on an Owner API requiring client certificates \
+			 (owner_api_tls_client_ca_file), as senders won't have one"
+				.to_owned(),
+		));
+	}
+}
+
+/// Validate a parsed configuration file, returning a description of each
+/// problem found, prefixed with the line it's on where known
+pub fn validate(contents: &str, config: &GlobalWalletConfigMembers) -> Vec<String> {
+	let mut issues = vec![];
+	if let Ok(value) = contents.parse::<toml::Value>() {
+		check_unknown_keys(&mut issues, &value);
+	}
+	check_wallet(&mut issues, &config.wallet);
+	issues
+		.into_iter()
+		.map(|i| {
+			let name = match &i.key {
+				Some(k) => format!("{}.{}", i.section, k),
+				None => i.section.clone(),
+			};
+			match find_line(contents, &i.section, i.key.as_ref().map(|k| k.as_str())) {
+				Some(l) => format!("line {}: {}: {}", l, name, i.message),
+				None => format!("{}: {}", name, i.message),
+			}
+		})
+		.collect()
+}
@@ -0,0 +1,27 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generates the gRPC services and messages from proto/wallet.proto, when
+//! built with the grpc feature
+
+fn main() {
+	#[cfg(feature = "grpc")]
+	{
+		println!("cargo:rerun-if-changed=proto/wallet.proto");
+		prost_build::Config::new()
+			.service_generator(Box::new(grpcio_compiler::prost_codegen::Generator))
+			.compile_protos(&["proto/wallet.proto"], &["proto"])
+			.expect("Unable to generate gRPC code from proto/wallet.proto");
+	}
+}
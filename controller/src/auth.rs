@@ -0,0 +1,648 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bearer token authentication for the Owner API. Clients log in once with
+//! the static API secret and use the issued, expiring token thereafter.
+//! Tokens are only valid for the wallet they were issued for, either the
+//! listener's own or one of its hosted wallets, and can be scoped to a single
+//! account, for sub-systems that should only see and spend from their own
+//! account
+
+use crate::api::{self, HandlerObj, ResponseFuture};
+use crate::impls::EmailNotifier;
+use crate::middleware::client_address;
+use crate::util::{to_hex, Mutex};
+use futures::future::ok;
+use futures::{Future, Stream};
+use hyper::header::{HeaderValue, AUTHORIZATION, WWW_AUTHENTICATE};
+use hyper::{Body, Request, Response, StatusCode};
+use rand::{thread_rng, Rng};
+use ring::constant_time::verify_slices_are_equal;
+use ring::digest;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Route at which tokens are issued, authenticated with the API secret
+pub const LOGIN_ROUTE: &str = "/v3/auth/login";
+/// Route at which the token presented is revoked
+pub const REVOKE_ROUTE: &str = "/v3/auth/revoke";
+
+/// Owner API methods a token scoped to an account may call. Everything else
+/// acts on the wallet as a whole
+const SCOPED_METHODS: &[&str] = &[
+	"accounts",
+	"set_active_account",
+	"retrieve_outputs",
+	"query_outputs",
+	"retrieve_txs",
+	"export_txs",
+	"retrieve_summary_info",
+	"list_anomalies",
+	"init_send_tx",
+	"init_send_tx_multi",
+	"consolidate",
+	"issue_invoice_tx",
+	"process_invoice_tx",
+	"tx_lock_outputs",
+	"finalize_tx",
+	"post_tx",
+	"prepare_send",
+	"broadcast_signed",
+	"cancel_tx",
+	"set_tx_label",
+	"verify_slate_messages",
+	"node_height",
+	"node_status",
+	"get_node_status",
+];
+
+/// Members of method arguments naming the account to act on
+const ACCOUNT_MEMBERS: &[&str] = &["src_acct_name", "dest_acct_name", "account"];
+
+/// Optional request body on the login route
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LoginRequest {
+	/// Account label to restrict the issued token to
+	#[serde(default)]
+	pub account: Option<String>,
+	/// Id of the hosted wallet the token is for, or `None` for the listener's
+	/// own wallet
+	#[serde(default)]
+	pub wallet_id: Option<String>,
+}
+
+/// Response body returned on a successful login
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TokenResponse {
+	/// Token to present as `Authorization: Bearer <token>`
+	pub token: String,
+	/// Number of seconds the token remains valid if unused. Each use of the
+	/// token extends its life by this amount
+	pub expires_in: u64,
+	/// Account label the token is restricted to, if any
+	#[serde(default)]
+	pub account: Option<String>,
+	/// Id of the hosted wallet the token is for, if not the listener's own
+	#[serde(default)]
+	pub wallet_id: Option<String>,
+}
+
+/// What a token gives access to. Added to the extensions of each request
+/// authenticated with a token
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TokenScope {
+	/// Account label the token is restricted to, or `None` for the whole
+	/// wallet
+	pub account: Option<String>,
+	/// Id of the hosted wallet the token is for, or `None` for the listener's
+	/// own wallet
+	pub wallet_id: Option<String>,
+}
+
+impl TokenScope {
+	/// Check the token is for the wallet with the given id, `None` being the
+	/// listener's own, returning why not otherwise
+	pub fn check_wallet(&self, wallet_id: Option<&str>) -> Result<(), String> {
+		if self.wallet_id.as_ref().map(|w| w.as_str()) == wallet_id {
+			return Ok(());
+		}
+		match &self.wallet_id {
+			Some(w) => Err(format!("Token is only valid for wallet {}", w)),
+			None => Err("Token is only valid for the listener's own wallet".to_owned()),
+		}
+	}
+
+	/// Check a JSON-RPC request is allowed within this scope, returning why
+	/// not otherwise. Unscoped tokens may call anything
+	pub fn check_request(&self, req: &Value) -> Result<(), String> {
+		let account = match &self.account {
+			Some(a) => a,
+			None => return Ok(()),
+		};
+		let method = req.get("method").and_then(|m| m.as_str()).unwrap_or("");
+		if !SCOPED_METHODS.contains(&method) {
+			return Err(format!(
+				"Method {} is not available to a token scoped to account {}",
+				method, account
+			));
+		}
+		// positional (V2) or named (V3) parameters
+		let (params, label): (Vec<&Value>, _) = match req.get("params") {
+			Some(Value::Array(a)) => (a.iter().collect(), a.get(0)),
+			Some(Value::Object(o)) => (o.values().collect(), o.get("label")),
+			_ => (vec![], None),
+		};
+		let mut named: Vec<&str> = params
+			.iter()
+			.filter_map(|p| p.as_object())
+			.flat_map(|o| {
+				ACCOUNT_MEMBERS
+					.iter()
+					.filter_map(move |m| o.get(*m).and_then(|v| v.as_str()))
+			})
+			.collect();
+		if method == "set_active_account" {
+			named.extend(label.and_then(|l| l.as_str()));
+		}
+		match named.into_iter().find(|n| n != account) {
+			Some(n) => Err(format!(
+				"Account {} is not available to a token scoped to account {}",
+				n, account
+			)),
+			None => Ok(()),
+		}
+	}
+
+	/// Remove anything outside this scope from the response to the given
+	/// request. Only the list of accounts needs filtering, other responses
+	/// are already limited to the active account
+	pub fn filter_response(&self, req: &Value, resp: &mut Value) {
+		let account = match &self.account {
+			Some(a) => a,
+			None => return,
+		};
+		if req.get("method").and_then(|m| m.as_str()) != Some("accounts") {
+			return;
+		}
+		if let Some(Value::Array(accts)) = resp.pointer_mut("/result/Ok") {
+			accts.retain(|a| a.get("label").and_then(|l| l.as_str()) == Some(account));
+		}
+	}
+}
+
+struct IssuedToken {
+	expiry: Instant,
+	scope: TokenScope,
+}
+
+/// Issued tokens, their scopes and expiry times
+pub struct AuthTokens {
+	ttl: Duration,
+	tokens: Mutex<HashMap<String, IssuedToken>>,
+}
+
+impl AuthTokens {
+	/// Create an empty token store, issuing tokens valid for `ttl_secs`
+	/// seconds after their last use
+	pub fn new(ttl_secs: u64) -> AuthTokens {
+		AuthTokens {
+			ttl: Duration::from_secs(ttl_secs),
+			tokens: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Issue a new token for the given hosted wallet, or the listener's own,
+	/// restricted to the given account if any
+	pub fn issue(&self, wallet_id: Option<String>, account: Option<String>) -> TokenResponse {
+		let bytes: [u8; 32] = thread_rng().gen();
+		let token = to_hex(bytes.to_vec());
+		let now = Instant::now();
+		let mut tokens = self.tokens.lock();
+		tokens.retain(|_, t| t.expiry > now);
+		tokens.insert(
+			token.clone(),
+			IssuedToken {
+				expiry: now + self.ttl,
+				scope: TokenScope {
+					account: account.clone(),
+					wallet_id: wallet_id.clone(),
+				},
+			},
+		);
+		TokenResponse {
+			token,
+			expires_in: self.ttl.as_secs(),
+			account,
+			wallet_id,
+		}
+	}
+
+	/// Check the token is valid, extending its expiry and returning its scope
+	/// if so
+	pub fn validate(&self, token: &str) -> Option<TokenScope> {
+		let now = Instant::now();
+		let mut tokens = self.tokens.lock();
+		match tokens.get_mut(token) {
+			Some(t) if t.expiry > now => {
+				t.expiry = now + self.ttl;
+				Some(t.scope.clone())
+			}
+			Some(_) => {
+				tokens.remove(token);
+				None
+			}
+			None => None,
+		}
+	}
+
+	/// Revoke the token, returning whether it had been issued
+	pub fn revoke(&self, token: &str) -> bool {
+		self.tokens.lock().remove(token).is_some()
+	}
+
+	/// Revoke every token issued, returning how many there were
+	pub fn revoke_all(&self) -> usize {
+		let mut tokens = self.tokens.lock();
+		let count = tokens.len();
+		tokens.clear();
+		count
+	}
+
+	/// Revoke every token issued for the given hosted wallet, or the
+	/// listener's own, returning how many there were
+	pub fn revoke_wallet(&self, wallet_id: Option<&str>) -> usize {
+		let mut tokens = self.tokens.lock();
+		let count = tokens.len();
+		tokens.retain(|_, t| t.scope.wallet_id.as_ref().map(|w| w.as_str()) != wallet_id);
+		count - tokens.len()
+	}
+}
+
+fn bearer_token(req: &Request<Body>) -> Option<String> {
+	req.headers()
+		.get(AUTHORIZATION)
+		.and_then(|h| h.to_str().ok())
+		.and_then(|h| {
+			if h.starts_with("Bearer ") {
+				Some(h["Bearer ".len()..].trim().to_owned())
+			} else {
+				None
+			}
+		})
+}
+
+/// Who made a request, for the audit log: a fingerprint of the bearer token,
+/// the hosted wallet it's for and the account it's scoped to, if any, or
+/// whether the API secret was given, followed by the client's
+/// [address](../middleware/fn.client_address.html), as given by the
+/// `trusted_proxies` if it connected through one
+pub fn caller_identity(req: &Request<Body>, trusted_proxies: &[IpAddr]) -> String {
+	let mut caller = match bearer_token(req) {
+		Some(t) => {
+			let hash = digest::digest(&digest::SHA256, t.as_bytes());
+			format!("token {}", to_hex(hash.as_ref()[..8].to_vec()))
+		}
+		None if req.headers().contains_key(AUTHORIZATION) => "api secret".to_owned(),
+		None => "unauthenticated".to_owned(),
+	};
+	if let Some(scope) = req.extensions().get::<TokenScope>() {
+		if let Some(w) = scope.wallet_id.as_ref() {
+			caller = format!("{} (wallet {})", caller, w);
+		}
+		if let Some(a) = scope.account.as_ref() {
+			caller = format!("{} (account {})", caller, a);
+		}
+	}
+	match client_address(req, trusted_proxies) {
+		Some(c) => format!("{} from {}", caller, c),
+		None => caller,
+	}
+}
+
+fn response(status: StatusCode, body: String) -> ResponseFuture {
+	Box::new(ok(Response::builder()
+		.status(status)
+		.body(body.into())
+		.unwrap()))
+}
+
+fn unauthorized(realm: &'static HeaderValue) -> ResponseFuture {
+	Box::new(ok(Response::builder()
+		.status(StatusCode::UNAUTHORIZED)
+		.header(WWW_AUTHENTICATE, realm)
+		.body(Body::empty())
+		.unwrap()))
+}
+
+/// Middleware accepting either a valid bearer token or, on the login route
+/// only, the static basic auth credentials
+pub struct TokenAuthMiddleware {
+	api_basic_auth: String,
+	basic_realm: &'static HeaderValue,
+	tokens: Arc<AuthTokens>,
+}
+
+impl TokenAuthMiddleware {
+	/// Create new middleware for the given basic auth header value and tokens
+	pub fn new(
+		api_basic_auth: String,
+		basic_realm: &'static HeaderValue,
+		tokens: Arc<AuthTokens>,
+	) -> TokenAuthMiddleware {
+		TokenAuthMiddleware {
+			api_basic_auth,
+			basic_realm,
+			tokens,
+		}
+	}
+
+	fn basic_auth_ok(&self, req: &Request<Body>) -> bool {
+		match req.headers().get(AUTHORIZATION) {
+			Some(h) => {
+				verify_slices_are_equal(h.as_bytes(), self.api_basic_auth.as_bytes()).is_ok()
+			}
+			None => false,
+		}
+	}
+}
+
+impl api::Handler for TokenAuthMiddleware {
+	fn call(
+		&self,
+		mut req: Request<Body>,
+		mut handlers: Box<dyn Iterator<Item = HandlerObj>>,
+	) -> ResponseFuture {
+		let next_handler = match handlers.next() {
+			Some(h) => h,
+			None => {
+				return response(
+					StatusCode::INTERNAL_SERVER_ERROR,
+					"no handler found".to_owned(),
+				)
+			}
+		};
+		if req.method().as_str() == "OPTIONS" {
+			return next_handler.call(req, handlers);
+		}
+		let authorized = if req.uri().path() == LOGIN_ROUTE {
+			self.basic_auth_ok(&req)
+		} else {
+			match bearer_token(&req).and_then(|t| self.tokens.validate(&t)) {
+				Some(scope) => {
+					req.extensions_mut().insert(scope);
+					true
+				}
+				None => false,
+			}
+		};
+		if authorized {
+			next_handler.call(req, handlers)
+		} else {
+			unauthorized(self.basic_realm)
+		}
+	}
+}
+
+/// Middleware telling the email notifier of each request that fails
+/// authentication, so bursts of failures can be emailed. It must come before
+/// the authentication middleware
+pub struct AuthFailureMiddleware {
+	email: EmailNotifier,
+}
+
+impl AuthFailureMiddleware {
+	/// Create new middleware for the given notifier
+	pub fn new(email: EmailNotifier) -> AuthFailureMiddleware {
+		AuthFailureMiddleware { email }
+	}
+}
+
+impl api::Handler for AuthFailureMiddleware {
+	fn call(
+		&self,
+		req: Request<Body>,
+		mut handlers: Box<dyn Iterator<Item = HandlerObj>>,
+	) -> ResponseFuture {
+		let next_handler = match handlers.next() {
+			Some(h) => h,
+			None => {
+				return response(
+					StatusCode::INTERNAL_SERVER_ERROR,
+					"no handler found".to_owned(),
+				)
+			}
+		};
+		let email = self.email.clone();
+		Box::new(next_handler.call(req, handlers).map(move |resp| {
+			if resp.status() == StatusCode::UNAUTHORIZED {
+				email.auth_failed();
+			}
+			resp
+		}))
+	}
+}
+
+/// Issue a token for the login request, unless it's for a wallet not served
+fn login(tokens: &AuthTokens, wallet_ids: &[String], req: LoginRequest) -> (StatusCode, String) {
+	match req.wallet_id {
+		Some(ref w) if !wallet_ids.contains(w) => {
+			(StatusCode::BAD_REQUEST, format!("Unknown wallet {}", w))
+		}
+		_ => {
+			let token = tokens.issue(req.wallet_id, req.account);
+			(StatusCode::OK, serde_json::to_string(&token).unwrap())
+		}
+	}
+}
+
+/// Handler issuing tokens on the login route and revoking them on the
+/// revoke route
+pub struct TokenAuthHandler {
+	tokens: Arc<AuthTokens>,
+	wallet_ids: Vec<String>,
+}
+
+impl TokenAuthHandler {
+	/// Create a new handler for the given tokens
+	pub fn new(tokens: Arc<AuthTokens>) -> TokenAuthHandler {
+		TokenAuthHandler {
+			tokens,
+			wallet_ids: vec![],
+		}
+	}
+
+	/// Also issue tokens for the hosted wallets with the given ids
+	pub fn with_wallet_ids(mut self, wallet_ids: Vec<String>) -> TokenAuthHandler {
+		self.wallet_ids = wallet_ids;
+		self
+	}
+}
+
+impl api::Handler for TokenAuthHandler {
+	fn post(&self, req: Request<Body>) -> ResponseFuture {
+		match req.uri().path() {
+			LOGIN_ROUTE => {
+				let tokens = self.tokens.clone();
+				let wallet_ids = self.wallet_ids.clone();
+				Box::new(req.into_body().concat2().then(
+					move |body| -> Result<Response<Body>, hyper::Error> {
+						// an empty body logs in for the whole wallet
+						let (status, body) = match body {
+							Ok(ref b) if b.is_empty() => {
+								let token = tokens.issue(None, None);
+								(StatusCode::OK, serde_json::to_string(&token).unwrap())
+							}
+							Ok(b) => match serde_json::from_slice::<LoginRequest>(&b) {
+								Ok(l) => login(&tokens, &wallet_ids, l),
+								Err(e) => (
+									StatusCode::BAD_REQUEST,
+									format!("Invalid login request: {}", e),
+								),
+							},
+							Err(_) => {
+								(StatusCode::BAD_REQUEST, "Failed to read request".to_owned())
+							}
+						};
+						Ok(Response::builder()
+							.status(status)
+							.body(body.into())
+							.unwrap())
+					},
+				))
+			}
+			REVOKE_ROUTE => match bearer_token(&req) {
+				Some(t) if self.tokens.revoke(&t) => response(StatusCode::OK, "{}".to_owned()),
+				_ => response(StatusCode::BAD_REQUEST, "Unknown token".to_owned()),
+			},
+			_ => response(StatusCode::NOT_FOUND, String::new()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::server::PeerAddr;
+	use serde_json::json;
+
+	#[test]
+	fn scoped_tokens_limited_to_their_account() {
+		let tokens = AuthTokens::new(60);
+		let whole = tokens.issue(None, None);
+		let scoped = tokens.issue(None, Some("savings".to_owned()));
+		assert_eq!(tokens.validate(&whole.token), Some(TokenScope::default()));
+		let scope = tokens.validate(&scoped.token).unwrap();
+		assert_eq!(scope.account, Some("savings".to_owned()));
+
+		let req = |method: &str, params: Value| json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+		assert!(scope
+			.check_request(&req("retrieve_txs", json!([true, null, null, null])))
+			.is_ok());
+		assert!(scope.check_request(&req("restore", json!([]))).is_err());
+		assert!(scope
+			.check_request(&req("create_account_path", json!(["x"])))
+			.is_err());
+		assert!(scope
+			.check_request(&req(
+				"set_active_account",
+				json!({"token": "t", "label": "savings"})
+			))
+			.is_ok());
+		assert!(scope
+			.check_request(&req("set_active_account", json!(["default"])))
+			.is_err());
+		let send = |acct: &str| {
+			req(
+				"init_send_tx",
+				json!([{"amount": 1, "src_acct_name": acct}]),
+			)
+		};
+		assert!(scope.check_request(&send("savings")).is_ok());
+		assert!(scope.check_request(&send("default")).is_err());
+		assert!(TokenScope::default()
+			.check_request(&send("default"))
+			.is_ok());
+
+		let mut resp = json!({"jsonrpc": "2.0", "id": 1, "result": {"Ok": [
+			{"label": "default", "path": "0200000000000000000000000000000000"},
+			{"label": "savings", "path": "0200000001000000000000000000000000"},
+		]}});
+		scope.filter_response(&req("accounts", json!([])), &mut resp);
+		assert_eq!(resp["result"]["Ok"].as_array().unwrap().len(), 1);
+		assert_eq!(resp["result"]["Ok"][0]["label"], json!("savings"));
+	}
+
+	#[test]
+	fn identifies_callers() {
+		let proxies: Vec<IpAddr> = vec!["10.0.0.9".parse().unwrap()];
+		let req = |auth: Option<&str>, peer: Option<&str>, client: Option<&str>| {
+			let mut builder = Request::builder();
+			if let Some(a) = auth {
+				builder.header(AUTHORIZATION, a);
+			}
+			if let Some(c) = client {
+				builder.header("x-forwarded-for", c);
+			}
+			let mut req = builder.body(Body::empty()).unwrap();
+			if let Some(p) = peer {
+				req.extensions_mut()
+					.insert(PeerAddr(format!("{}:3420", p).parse().unwrap()));
+			}
+			req
+		};
+		assert_eq!(
+			caller_identity(&req(None, None, None), &proxies),
+			"unauthenticated"
+		);
+		assert_eq!(
+			caller_identity(&req(Some("Basic Z3JpbjpzZWNyZXQ="), None, None), &proxies),
+			"api secret"
+		);
+		let mut scoped = req(
+			Some("Bearer abcd"),
+			Some("10.0.0.9"),
+			Some("10.0.0.1, 10.0.0.2"),
+		);
+		scoped.extensions_mut().insert(TokenScope {
+			account: Some("savings".to_owned()),
+			wallet_id: Some("shop".to_owned()),
+		});
+		let caller = caller_identity(&scoped, &proxies);
+		assert!(caller.starts_with("token "));
+		assert!(!caller.contains("abcd"));
+		assert!(caller.ends_with(" (wallet shop) (account savings) from 10.0.0.2"));
+
+		// clients can't claim an address unless through a trusted proxy
+		let spoofed = req(None, Some("192.168.1.5"), Some("10.0.0.1"));
+		assert_eq!(
+			caller_identity(&spoofed, &proxies),
+			"unauthenticated from 192.168.1.5"
+		);
+	}
+
+	#[test]
+	fn revokes_all_tokens() {
+		let tokens = AuthTokens::new(60);
+		let whole = tokens.issue(None, None).token;
+		let scoped = tokens.issue(None, Some("savings".to_owned())).token;
+		assert!(tokens.validate(&whole).is_some());
+		assert_eq!(tokens.revoke_all(), 2);
+		assert!(tokens.validate(&whole).is_none());
+		assert!(tokens.validate(&scoped).is_none());
+		assert_eq!(tokens.revoke_all(), 0);
+	}
+
+	#[test]
+	fn tokens_limited_to_their_wallet() {
+		let tokens = AuthTokens::new(60);
+		let own = tokens.issue(None, None).token;
+		let hosted = tokens.issue(Some("shop".to_owned()), None).token;
+		let own_scope = tokens.validate(&own).unwrap();
+		let hosted_scope = tokens.validate(&hosted).unwrap();
+		assert!(own_scope.check_wallet(None).is_ok());
+		assert!(own_scope.check_wallet(Some("shop")).is_err());
+		assert!(hosted_scope.check_wallet(Some("shop")).is_ok());
+		assert!(hosted_scope.check_wallet(Some("other")).is_err());
+		assert!(hosted_scope.check_wallet(None).is_err());
+
+		// a hosted wallet's password change only revokes its own tokens
+		assert_eq!(tokens.revoke_wallet(Some("shop")), 1);
+		assert!(tokens.validate(&hosted).is_none());
+		assert!(tokens.validate(&own).is_some());
+	}
+}
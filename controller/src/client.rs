@@ -0,0 +1,493 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Client for the Owner and Foreign JSON-RPC APIs of a running wallet, over
+//! HTTP(S) or an SSH tunnel to the unix socket the wallet listens on. Calls
+//! authenticate with the API secret, or log in with it for a bearer token
+//! where the listener issues them, logging in again once the token expires
+//! or is revoked. Calls the listener refused for being busy are retried after
+//! a growing delay
+
+use crate::api;
+use crate::auth::{TokenResponse, LOGIN_ROUTE};
+use crate::core::core::Transaction;
+use crate::error::{Error, ErrorKind};
+#[cfg(unix)]
+use crate::impls::{SshRemote, SshTunnel};
+use crate::libwallet::{
+	self, AcctPathMapping, InitTxArgs, NodeHeightResult, OutputCommitMapping, RetrieveTxQueryArgs,
+	Slate, SlateVersion, TxLogEntry, VersionInfo, VersionedSlate, WalletInfo,
+};
+use crate::util::Mutex;
+use hyper::header::{HeaderValue, AUTHORIZATION};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Route of the V3 Owner API
+pub const OWNER_V3_ROUTE: &str = "/v3/owner";
+/// Route of the V2 Foreign API
+pub const FOREIGN_V2_ROUTE: &str = "/v2/foreign";
+
+/// Attempts made at a call by default, including the first
+const DEFAULT_ATTEMPTS: u32 = 3;
+/// Delay before the first retry, doubled for each one after
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Where calls are sent
+enum Endpoint {
+	/// Listener at a URL such as `https://wallet.example.com:3420`
+	Http(String),
+	/// Listener on a unix socket on another host
+	#[cfg(unix)]
+	Ssh(SshTunnel),
+}
+
+/// Status code the listener answered a failed request with, if it answered at
+/// all. Unfortunately have to parse string due to error structure
+fn response_status(e: &api::Error) -> Option<u16> {
+	let msg = format!("{}", e);
+	let prefix = "Wrong response code: ";
+	let start = msg.find(prefix)? + prefix.len();
+	msg[start..]
+		.split(|c: char| !c.is_ascii_digit())
+		.next()?
+		.parse()
+		.ok()
+}
+
+/// Whether the listener, or a proxy in front of it, turned the call away
+/// without making it
+fn is_busy(e: &api::Error) -> bool {
+	match response_status(e) {
+		Some(429) | Some(502) | Some(503) => true,
+		_ => false,
+	}
+}
+
+/// The error for a request the listener didn't answer, or refused
+fn request_error(url: &str, e: api::Error) -> Error {
+	ErrorKind::GenericError(format!("Request to {} failed: {}", url, e)).into()
+}
+
+/// The error for a call the wallet answered with an error of its own
+fn call_error(method: &str, err: &Value) -> Error {
+	match serde_json::from_value::<libwallet::ErrorKind>(err.clone()) {
+		Ok(kind) => {
+			let msg = format!("{} failed: {}", method, kind);
+			ErrorKind::LibWallet(kind, msg).into()
+		}
+		Err(_) => ErrorKind::GenericError(format!("{} failed: {}", method, err)).into(),
+	}
+}
+
+/// Client of a wallet's listener, making JSON-RPC calls to any of its APIs
+pub struct WalletClient {
+	endpoint: Endpoint,
+	api_secret: Option<String>,
+	/// Whether to log in for a bearer token, and the account to scope it to
+	login: Option<Option<String>>,
+	/// Hosted wallet to log in for, if not the listener's own
+	login_wallet_id: Option<String>,
+	bearer_token: Mutex<Option<String>>,
+	attempts: u32,
+	next_id: AtomicUsize,
+}
+
+impl WalletClient {
+	fn new(endpoint: Endpoint, api_secret: Option<String>) -> WalletClient {
+		WalletClient {
+			endpoint,
+			api_secret,
+			login: None,
+			login_wallet_id: None,
+			bearer_token: Mutex::new(None),
+			attempts: DEFAULT_ATTEMPTS,
+			next_id: AtomicUsize::new(1),
+		}
+	}
+
+	/// Client of the listener at `url`, such as `http://127.0.0.1:3420`,
+	/// authenticating with the API secret if given
+	pub fn http(url: &str, api_secret: Option<String>) -> WalletClient {
+		let url = url.trim_end_matches('/').to_owned();
+		WalletClient::new(Endpoint::Http(url), api_secret)
+	}
+
+	/// Client of the listener on a unix socket on another host, given as
+	/// `ssh://[user@]host[:port]/path/to/socket`, through an SSH tunnel kept
+	/// open for as long as the client
+	#[cfg(unix)]
+	pub fn ssh(remote: &str, api_secret: Option<String>) -> Result<WalletClient, Error> {
+		let tunnel = SshTunnel::open(&SshRemote::parse(remote)?)?;
+		Ok(WalletClient::new(Endpoint::Ssh(tunnel), api_secret))
+	}
+
+	/// Client of the listener on a unix socket on another host
+	#[cfg(not(unix))]
+	pub fn ssh(_remote: &str, _api_secret: Option<String>) -> Result<WalletClient, Error> {
+		Err(ErrorKind::GenericError(
+			"Unix sockets aren't supported on this platform".to_string(),
+		))?
+	}
+
+	/// Log in with the API secret for a bearer token, scoped to the given
+	/// account if any, for listeners only accepting the secret at their login
+	/// route. Only used over HTTP
+	pub fn set_login(&mut self, account: Option<String>) {
+		self.login = Some(account);
+		*self.bearer_token.lock() = None;
+	}
+
+	/// Log in for bearer tokens valid for the hosted wallet with the given
+	/// id, rather than the listener's own wallet
+	pub fn set_login_wallet_id(&mut self, wallet_id: Option<String>) {
+		self.login_wallet_id = wallet_id;
+		*self.bearer_token.lock() = None;
+	}
+
+	/// Set the number of attempts made at a call the listener is too busy to
+	/// take, including the first
+	pub fn set_attempts(&mut self, attempts: u32) {
+		self.attempts = attempts.max(1);
+	}
+
+	/// Make the call to the API at `path`, returning the JSON-RPC response
+	pub fn call(&self, path: &str, method: &str, params: Value) -> Result<Value, Error> {
+		let req = json!({
+			"jsonrpc": "2.0",
+			"method": method,
+			"params": params,
+			"id": self.next_id.fetch_add(1, Ordering::Relaxed),
+		});
+		let res = match &self.endpoint {
+			Endpoint::Http(base) => self.post_with_retry(base, path, &req)?,
+			#[cfg(unix)]
+			Endpoint::Ssh(tunnel) => tunnel.call(path, self.api_secret.clone(), &req)?,
+		};
+		if let Some(e) = res.get("error") {
+			return Err(call_error(method, e));
+		}
+		Ok(res)
+	}
+
+	/// Make the call and return its result, for APIs answering with an `Ok`
+	/// or `Err` member
+	pub fn call_result<T: DeserializeOwned>(
+		&self,
+		path: &str,
+		method: &str,
+		params: Value,
+	) -> Result<T, Error> {
+		let res = self.call(path, method, params)?;
+		let result = res.get("result").unwrap_or(&Value::Null);
+		if let Some(e) = result.get("Err") {
+			return Err(call_error(method, e));
+		}
+		let ok = result.get("Ok").cloned().unwrap_or(Value::Null);
+		serde_json::from_value(ok).map_err(|e| {
+			let msg = format!("Unexpected result of {}: {}", method, e);
+			ErrorKind::GenericError(msg).into()
+		})
+	}
+
+	fn post_with_retry(&self, base: &str, path: &str, req: &Value) -> Result<Value, Error> {
+		let url = format!("{}{}", base, path);
+		let mut attempt = 0;
+		loop {
+			match self.post(base, &url, req) {
+				Err(ref e) if is_busy(e) && attempt + 1 < self.attempts => {
+					let delay = RETRY_DELAY * 2u32.pow(attempt);
+					debug!("{} is busy, retrying in {}ms", url, delay.as_millis());
+					thread::sleep(delay);
+					attempt += 1;
+				}
+				// an expired or revoked token, logged in again once
+				Err(ref e) if response_status(e) == Some(401) && self.login.is_some() => {
+					*self.bearer_token.lock() = None;
+					return self
+						.post(base, &url, req)
+						.map_err(|e| request_error(&url, e));
+				}
+				res => return res.map_err(|e| request_error(&url, e)),
+			}
+		}
+	}
+
+	fn post(&self, base: &str, url: &str, req: &Value) -> Result<Value, api::Error> {
+		let request = match self.login {
+			None => api::client::create_post_request(url, self.api_secret.clone(), req)?,
+			Some(_) => {
+				let token = self.bearer_token(base)?;
+				let mut request = api::client::create_post_request(url, None, req)?;
+				if let Ok(h) = HeaderValue::from_str(&format!("Bearer {}", token)) {
+					request.headers_mut().insert(AUTHORIZATION, h);
+				}
+				request
+			}
+		};
+		let res = api::client::send_request(request)?;
+		serde_json::from_str(&res).map_err(|e| {
+			api::ErrorKind::ResponseError(format!("Invalid response from {}: {}", url, e)).into()
+		})
+	}
+
+	/// The bearer token to present, logging in at the listener at `base` for
+	/// one if needed
+	fn bearer_token(&self, base: &str) -> Result<String, api::Error> {
+		let mut token = self.bearer_token.lock();
+		if let Some(t) = token.as_ref() {
+			return Ok(t.clone());
+		}
+		let login_url = format!("{}{}", base, LOGIN_ROUTE);
+		let account = self.login.clone().unwrap_or(None);
+		let request = api::client::create_post_request(
+			&login_url,
+			self.api_secret.clone(),
+			&json!({ "account": account, "wallet_id": self.login_wallet_id }),
+		)?;
+		let res = api::client::send_request(request)?;
+		let login: TokenResponse = serde_json::from_str(&res)
+			.map_err(|e| api::ErrorKind::ResponseError(format!("Invalid login response: {}", e)))?;
+		*token = Some(login.token.clone());
+		Ok(login.token)
+	}
+}
+
+/// Typed client of the V3 Owner API
+pub struct OwnerClient {
+	client: WalletClient,
+	token: Value,
+	wallet_id: Option<String>,
+	totp_code: Option<String>,
+}
+
+impl OwnerClient {
+	/// Make calls through the given client. The wallet's keychain mask is
+	/// only needed if the listener opened the wallet with one
+	pub fn new(client: WalletClient) -> OwnerClient {
+		OwnerClient {
+			client,
+			token: Value::Null,
+			wallet_id: None,
+			totp_code: None,
+		}
+	}
+
+	/// Set the hex keychain mask passed as the `token` of each call
+	pub fn set_keychain_mask(&mut self, mask: Option<String>) {
+		self.token = mask.map(Value::String).unwrap_or(Value::Null);
+	}
+
+	/// Direct calls to a wallet hosted by the listener, rather than its own,
+	/// logging in for tokens valid for it if logging in at all
+	pub fn set_wallet_id(&mut self, wallet_id: Option<String>) {
+		self.client.set_login_wallet_id(wallet_id.clone());
+		self.wallet_id = wallet_id;
+	}
+
+	/// Set the TOTP code given with each call, for spends from a wallet
+	/// asking for one
+	pub fn set_totp_code(&mut self, code: Option<String>) {
+		self.totp_code = code;
+	}
+
+	/// The underlying client, for methods without a typed wrapper
+	pub fn client(&self) -> &WalletClient {
+		&self.client
+	}
+
+	/// Call the method with the given named parameters, adding the token,
+	/// wallet id and TOTP code
+	pub fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T, Error> {
+		let mut params = match params {
+			Value::Object(m) => m,
+			_ => serde_json::Map::new(),
+		};
+		params.insert("token".to_owned(), self.token.clone());
+		if let Some(id) = self.wallet_id.as_ref() {
+			params.insert("wallet_id".to_owned(), Value::String(id.clone()));
+		}
+		if let Some(code) = self.totp_code.as_ref() {
+			params.insert("totp_code".to_owned(), Value::String(code.clone()));
+		}
+		self.client
+			.call_result(OWNER_V3_ROUTE, method, Value::Object(params))
+	}
+
+	/// See `Owner::accounts`
+	pub fn accounts(&self) -> Result<Vec<AcctPathMapping>, Error> {
+		self.call("accounts", json!({}))
+	}
+
+	/// See `Owner::retrieve_summary_info`
+	pub fn retrieve_summary_info(
+		&self,
+		refresh_from_node: bool,
+		minimum_confirmations: u64,
+	) -> Result<(bool, WalletInfo), Error> {
+		self.call(
+			"retrieve_summary_info",
+			json!({
+				"refresh_from_node": refresh_from_node,
+				"minimum_confirmations": minimum_confirmations,
+			}),
+		)
+	}
+
+	/// See `Owner::retrieve_outputs`
+	pub fn retrieve_outputs(
+		&self,
+		include_spent: bool,
+		refresh_from_node: bool,
+		tx_id: Option<u32>,
+	) -> Result<(bool, Vec<OutputCommitMapping>), Error> {
+		self.call(
+			"retrieve_outputs",
+			json!({
+				"include_spent": include_spent,
+				"refresh_from_node": refresh_from_node,
+				"tx_id": tx_id,
+			}),
+		)
+	}
+
+	/// See `Owner::retrieve_txs`
+	pub fn retrieve_txs(
+		&self,
+		refresh_from_node: bool,
+		tx_id: Option<u32>,
+		tx_slate_id: Option<Uuid>,
+		query: Option<RetrieveTxQueryArgs>,
+	) -> Result<(bool, Vec<TxLogEntry>), Error> {
+		self.call(
+			"retrieve_txs",
+			json!({
+				"refresh_from_node": refresh_from_node,
+				"tx_id": tx_id,
+				"tx_slate_id": tx_slate_id,
+				"query": query,
+			}),
+		)
+	}
+
+	/// See `Owner::init_send_tx`
+	pub fn init_send_tx(&self, args: InitTxArgs) -> Result<Slate, Error> {
+		let slate: VersionedSlate = self.call("init_send_tx", json!({ "args": args }))?;
+		Ok(slate.into())
+	}
+
+	/// See `Owner::tx_lock_outputs`
+	pub fn tx_lock_outputs(&self, slate: &Slate, participant_id: usize) -> Result<(), Error> {
+		self.call(
+			"tx_lock_outputs",
+			json!({
+				"slate": VersionedSlate::into_version(slate.clone(), SlateVersion::V2),
+				"participant_id": participant_id,
+			}),
+		)
+	}
+
+	/// See `Owner::finalize_tx`
+	pub fn finalize_tx(&self, slate: &Slate) -> Result<Slate, Error> {
+		let slate: VersionedSlate = self.call(
+			"finalize_tx",
+			json!({ "slate": VersionedSlate::into_version(slate.clone(), SlateVersion::V2) }),
+		)?;
+		Ok(slate.into())
+	}
+
+	/// See `Owner::post_tx`
+	pub fn post_tx(&self, tx: &Transaction, fluff: bool) -> Result<(), Error> {
+		self.call("post_tx", json!({ "tx": tx, "fluff": fluff }))
+	}
+
+	/// See `Owner::cancel_tx`
+	pub fn cancel_tx(&self, tx_id: Option<u32>, tx_slate_id: Option<Uuid>) -> Result<(), Error> {
+		self.call(
+			"cancel_tx",
+			json!({ "tx_id": tx_id, "tx_slate_id": tx_slate_id }),
+		)
+	}
+
+	/// See `Owner::node_height`
+	pub fn node_height(&self) -> Result<NodeHeightResult, Error> {
+		self.call("node_height", json!({}))
+	}
+
+	/// See `Owner::change_password`.
+	/// Later calls pass the wallet's new keychain mask, and a client logging
+	/// in for tokens logs in again on its next call
+	pub fn change_password(&mut self, old: &str, new: &str) -> Result<(), Error> {
+		let mask: Option<String> =
+			self.call("change_password", json!({ "old": old, "new": new }))?;
+		self.set_keychain_mask(mask);
+		Ok(())
+	}
+}
+
+/// Typed client of the V2 Foreign API
+pub struct ForeignClient {
+	client: WalletClient,
+}
+
+impl ForeignClient {
+	/// Make calls through the given client
+	pub fn new(client: WalletClient) -> ForeignClient {
+		ForeignClient { client }
+	}
+
+	/// See `Foreign::check_version`
+	pub fn check_version(&self) -> Result<VersionInfo, Error> {
+		self.client
+			.call_result(FOREIGN_V2_ROUTE, "check_version", json!([]))
+	}
+
+	/// See `Foreign::receive_tx`
+	pub fn receive_tx(
+		&self,
+		slate: &Slate,
+		dest_acct_name: Option<String>,
+		message: Option<String>,
+	) -> Result<Slate, Error> {
+		let slate = VersionedSlate::into_version(slate.clone(), SlateVersion::V2);
+		let slate: VersionedSlate = self.client.call_result(
+			FOREIGN_V2_ROUTE,
+			"receive_tx",
+			json!([slate, dest_acct_name, message]),
+		)?;
+		Ok(slate.into())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn wallet_errors() {
+		let err = call_error("accounts", &json!({ "GenericError": "no wallet" }));
+		match err.kind() {
+			ErrorKind::LibWallet(libwallet::ErrorKind::GenericError(m), _) => {
+				assert_eq!(m, "no wallet")
+			}
+			k => panic!("unexpected error {:?}", k),
+		}
+		let err = call_error("accounts", &json!({ "code": -32601 }));
+		assert!(format!("{}", err).contains("accounts failed"));
+	}
+}
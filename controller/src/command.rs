@@ -15,24 +15,52 @@
 //! Grin wallet command-line function implementations
 
 use crate::api::TLSConfig;
-use crate::config::{WalletConfig, WALLET_CONFIG_FILE_NAME};
-use crate::core::{core, global};
+use crate::apiwallet::{BackupScheduler, RecoveryPlan, RecoveryPlanner, ShutdownHandle};
+use crate::client::{WalletClient, OWNER_V3_ROUTE};
+use crate::config::{GlobalWalletConfig, WalletConfig, WALLET_CONFIG_FILE_NAME};
+use crate::controller::{ForeignApiConfig, ForeignListenerConfig, OwnerListenerConfig};
+use crate::core::global;
 use crate::error::{Error, ErrorKind};
-use crate::impls::{create_sender, KeybaseAllChannels, SlateGetter as _, SlateReceiver as _};
-use crate::impls::{PathToSlate, SlatePutter};
+use crate::hosted::{ForwardedWallet, HostedWallet};
+use crate::impls::AuditLog;
+use crate::impls::{
+	create_backup_target, EmailNotifier, GatewayPushNotifier, HttpPriceOracle, NodeDirectory,
+	PathToSlate, QrSlateReader, SlatePutter, WebhookDispatcher, ZmqPublisher,
+};
+use crate::impls::{create_sender, transport_for, SlateGetter as _};
 use crate::keychain;
-use crate::libwallet::{InitTxArgs, IssueInvoiceTxArgs, NodeClient, WalletInst, WalletLCProvider};
+use crate::libwallet::{
+	self, InitTxArgs, InitTxRecipient, InitTxSendArgs, IssueInvoiceTxArgs, NodeClient,
+	PushNotifier, ReceiveLimits, RecoveryTxArgs, RetrieveTxQueryArgs, SignedTx, TxExportEntry,
+	TxExportFormat, UnsignedTx, WalletInst, WalletLCProvider,
+};
+use crate::metrics::WalletMetrics;
+use crate::middleware::RequestLimitsMiddleware;
+use crate::response::ResponseFormat;
+use crate::util::file::get_first_line;
 use crate::util::secp::key::SecretKey;
-use crate::util::{Mutex, ZeroingString};
+use crate::util::{self, Mutex, ZeroingString};
 use crate::{controller, display};
+use chrono::prelude::{DateTime, Utc};
 use serde_json as json;
-use std::fs::File;
-use std::io::Write;
+use std::fs::{self, File};
+use std::io::{self, BufRead, Write};
+use std::net::IpAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 use uuid::Uuid;
 
+/// File in the wallet's directory keeping webhook events that couldn't be
+/// delivered
+const WEBHOOK_DEAD_LETTERS_FILE: &str = "webhook_dead_letters.json";
+/// File in the wallet's directory keeping the Owner API audit log
+const AUDIT_LOG_FILE: &str = "owner_api_audit.json";
+/// File in the wallet's directory the recovery transaction is exported to,
+/// unless configured otherwise
+const RECOVERY_TX_FILE: &str = "recovery.tx";
+
 fn show_recovery_phrase(phrase: ZeroingString) {
 	println!("Your recovery phrase is:");
 	println!();
@@ -41,6 +69,27 @@ fn show_recovery_phrase(phrase: ZeroingString) {
 	println!("Please back-up these words in a non-digital format.");
 }
 
+fn show_seed_passphrase_warning() {
+	println!();
+	println!("This wallet's keys are derived using your BIP39 passphrase, which isn't stored.");
+	println!("It must be given with --seed_passphrase whenever the wallet is used or recovered.");
+}
+
+fn show_seed_shares(shares: Vec<ZeroingString>, threshold: u8) {
+	println!(
+		"Your seed has been split into {} shares, any {} of which recover it:",
+		shares.len(),
+		threshold
+	);
+	println!();
+	for (i, s) in shares.iter().enumerate() {
+		println!("Share {}:", i + 1);
+		println!("{}", &**s);
+		println!();
+	}
+	println!("Please back-up each share in a non-digital format, keeping them in separate places.");
+}
+
 /// Arguments common to all wallet commands
 #[derive(Clone)]
 pub struct GlobalArgs {
@@ -49,6 +98,8 @@ pub struct GlobalArgs {
 	pub show_spent: bool,
 	pub chain_type: global::ChainTypes,
 	pub password: Option<ZeroingString>,
+	/// BIP39 passphrase mixed into the derivation of the keychain
+	pub seed_passphrase: Option<ZeroingString>,
 	pub tls_conf: Option<TLSConfig>,
 }
 
@@ -84,17 +135,91 @@ where
 
 	let m = p.get_mnemonic(None, args.password)?;
 	show_recovery_phrase(m);
+	if g_args.seed_passphrase.is_some() {
+		show_seed_passphrase_warning();
+	}
+	Ok(())
+}
+
+/// Change the wallet settings in the configuration file in the given
+/// directory, returning the file's path. The file is read back afterwards
+/// to validate it, and restored if the wallet couldn't start with it
+fn update_config_file<F>(dir: &str, update: F) -> Result<String, Error>
+where
+	F: FnOnce(&mut WalletConfig),
+{
+	let mut config_path = PathBuf::from(dir);
+	config_path.push(WALLET_CONFIG_FILE_NAME);
+	let config_path = config_path.to_str().unwrap();
+	let mut global_config = GlobalWalletConfig::new(config_path)
+		.map_err(|e| ErrorKind::ArgumentError(format!("{}", e)))?;
+	let original =
+		fs::read_to_string(config_path).map_err(|e| ErrorKind::ArgumentError(format!("{}", e)))?;
+	update(&mut global_config.members.as_mut().unwrap().wallet);
+	global_config
+		.write_to_file(config_path)
+		.map_err(|e| ErrorKind::ArgumentError(format!("{}", e)))?;
+	if let Err(e) = GlobalWalletConfig::new(config_path) {
+		let _ = fs::write(config_path, original);
+		return Err(ErrorKind::ArgumentError(format!("{}", e)).into());
+	}
+	Ok(config_path.to_owned())
+}
+
+/// Arguments for setup command
+pub struct SetupArgs {
+	/// BIP39 recovery phrase length
+	pub list_length: usize,
+	pub password: ZeroingString,
+	/// Configuration with the node, listener and TLS settings chosen
+	pub config: WalletConfig,
+}
+
+/// Create the configuration file with the settings chosen during setup,
+/// check it's valid, then create the wallet
+pub fn setup<'a, L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+	g_args: &GlobalArgs,
+	args: SetupArgs,
+) -> Result<(), Error>
+where
+	L: WalletLCProvider<'a, C, K>,
+	C: NodeClient + 'a,
+	K: keychain::Keychain + 'a,
+{
+	let mut w_lock = wallet.lock();
+	let p = w_lock.lc_provider()?;
+	p.create_config(&g_args.chain_type, WALLET_CONFIG_FILE_NAME)?;
+
+	let config_path = update_config_file(&args.config.data_file_dir, |c| {
+		c.check_node_api_http_addr = args.config.check_node_api_http_addr.clone();
+		c.api_listen_interface = args.config.api_listen_interface.clone();
+		c.api_listen_port = args.config.api_listen_port;
+		c.tls_certificate_file = args.config.tls_certificate_file.clone();
+		c.tls_certificate_key = args.config.tls_certificate_key.clone();
+	})?;
+	println!("Configuration written to {}", config_path);
+
+	p.create_wallet(None, None, args.list_length, args.password.clone())?;
+	let m = p.get_mnemonic(None, args.password)?;
+	show_recovery_phrase(m);
+	if g_args.seed_passphrase.is_some() {
+		show_seed_passphrase_warning();
+	}
 	Ok(())
 }
 
 /// Argument for recover
 pub struct RecoverArgs {
 	pub recovery_phrase: Option<ZeroingString>,
+	/// SLIP-39 shares to recover from instead of a recovery phrase
+	pub seed_shares: Option<Vec<ZeroingString>>,
 	pub passphrase: ZeroingString,
 }
 
 pub fn recover<'a, L, C, K>(
 	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+	g_args: &GlobalArgs,
 	args: RecoverArgs,
 ) -> Result<(), Error>
 where
@@ -104,13 +229,216 @@ where
 {
 	let mut w_lock = wallet.lock();
 	let p = w_lock.lc_provider()?;
-	match args.recovery_phrase {
-		None => {
+	match (args.recovery_phrase, args.seed_shares) {
+		(_, Some(shares)) => p.recover_from_seed_shares(shares, args.passphrase)?,
+		(Some(phrase), None) => p.recover_from_mnemonic(phrase, args.passphrase)?,
+		(None, None) => {
 			let m = p.get_mnemonic(None, args.passphrase)?;
 			show_recovery_phrase(m);
+			return Ok(());
+		}
+	}
+	if g_args.seed_passphrase.is_some() {
+		show_seed_passphrase_warning();
+	}
+	Ok(())
+}
+
+/// Arguments for split_seed command
+pub struct SplitSeedArgs {
+	pub password: ZeroingString,
+	/// Number of shares needed to recover the seed
+	pub threshold: u8,
+	/// Number of shares to create
+	pub count: u8,
+}
+
+pub fn split_seed<'a, L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+	args: SplitSeedArgs,
+) -> Result<(), Error>
+where
+	L: WalletLCProvider<'a, C, K>,
+	C: NodeClient + 'a,
+	K: keychain::Keychain + 'a,
+{
+	let mut w_lock = wallet.lock();
+	let p = w_lock.lc_provider()?;
+	let shares = p.get_seed_shares(None, args.password, args.threshold, args.count)?;
+	show_seed_shares(shares, args.threshold);
+	Ok(())
+}
+
+/// Arguments for change_password command
+pub struct ChangePasswordArgs {
+	/// Password the seed file is encrypted with
+	pub old: ZeroingString,
+	/// Password to encrypt it with instead
+	pub new: ZeroingString,
+}
+
+/// Re-encrypt the wallet's seed file with a new password
+pub fn change_password<'a, L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+	args: ChangePasswordArgs,
+) -> Result<(), Error>
+where
+	L: WalletLCProvider<'a, C, K>,
+	C: NodeClient + 'a,
+	K: keychain::Keychain + 'a,
+{
+	let mut w_lock = wallet.lock();
+	let p = w_lock.lc_provider()?;
+	p.change_password(args.old, args.new)?;
+	warn!("Wallet password changed");
+	Ok(())
+}
+
+/// Arguments for public_nodes command
+pub struct PublicNodesArgs {
+	/// Whether to use the best public node from now on
+	pub select: bool,
+	/// Node to use from now on, overriding the directory's scores
+	pub pin: Option<String>,
+}
+
+/// List the nodes in the configured public node directory with their health,
+/// optionally switching the wallet to the best of them or to a given node
+pub fn public_nodes(
+	config: &WalletConfig,
+	node_api_secret: Option<String>,
+	args: PublicNodesArgs,
+) -> Result<(), Error> {
+	let node = match args.pin {
+		Some(url) => url,
+		None => {
+			let (url, key) = match (
+				&config.public_node_directory_url,
+				&config.public_node_directory_key,
+			) {
+				(Some(u), Some(k)) => (u, k),
+				_ => {
+					let msg =
+						"public_node_directory_url and public_node_directory_key must be set \
+					           to use the public node directory";
+					return Err(ErrorKind::ArgumentError(msg.to_owned()).into());
+				}
+			};
+			let directory = NodeDirectory::fetch(url, key)?;
+			let health = directory.check_health(node_api_secret);
+			display::public_nodes(&health, &config.check_node_api_http_addr);
+			if !args.select {
+				return Ok(());
+			}
+			match health.into_iter().find(|h| h.score > 0) {
+				Some(h) => h.node.url,
+				None => {
+					let msg = "None of the public nodes answered and is up to date";
+					return Err(ErrorKind::GenericError(msg.to_owned()).into());
+				}
+			}
+		}
+	};
+	let config_path = update_config_file(&config.data_file_dir, |c| {
+		c.check_node_api_http_addr = node.clone();
+	})?;
+	println!(
+		"Node set to {} in {}. It's used until changed there or by this command",
+		node, config_path
+	);
+	Ok(())
+}
+
+/// Arguments for escrow_key command
+pub struct EscrowKeyArgs {
+	/// File to write the secret key to
+	pub dest: String,
+}
+
+/// Generate a key pair for escrowing transaction memos, writing the secret key
+/// to a file and showing the public key to configure wallets with
+pub fn escrow_key(args: EscrowKeyArgs) -> Result<(), Error> {
+	if PathBuf::from(&args.dest).exists() {
+		let msg = format!("{} already exists, not overwriting it", args.dest);
+		return Err(ErrorKind::ArgumentError(msg).into());
+	}
+	let (secret_key, public_key) = libwallet::new_escrow_key()?;
+	fs::write(&args.dest, util::to_hex(secret_key.0.to_vec())).map_err(|e| {
+		ErrorKind::GenericError(format!("Writing the escrow key to {}: {}", args.dest, e))
+	})?;
+	println!("Escrow secret key written to {}", args.dest);
+	println!("Set memo_escrow_key to this public key to seal memos to it:");
+	println!();
+	println!("{}", public_key);
+	Ok(())
+}
+
+/// Arguments for open_memos command
+pub struct OpenMemosArgs {
+	/// File holding the escrow secret key
+	pub key_file: String,
+	/// JSON transaction history export
+	pub input: String,
+}
+
+/// Show the messages of the transactions in a JSON history export whose
+/// memos were sealed to the given escrow key
+pub fn open_memos(args: OpenMemosArgs) -> Result<(), Error> {
+	let read = |path: &str| {
+		fs::read_to_string(path)
+			.map_err(|e| ErrorKind::ArgumentError(format!("Reading {}: {}", path, e)))
+	};
+	let secret_key = util::from_hex(read(&args.key_file)?.trim().to_owned())
+		.ok()
+		.and_then(|k| SecretKey::from_slice(&util::static_secp_instance().lock(), &k).ok())
+		.ok_or_else(|| {
+			ErrorKind::ArgumentError(format!("{} doesn't hold an escrow key", args.key_file))
+		})?;
+	let entries: Vec<TxExportEntry> = json::from_str(&read(&args.input)?).map_err(|e| {
+		ErrorKind::ArgumentError(format!("{} isn't a JSON history export: {}", args.input, e))
+	})?;
+	let mut memos = vec![];
+	for e in entries {
+		if let Some(sealed) = e.escrowed_memo.clone() {
+			let messages = libwallet::open_memo(&secret_key, &sealed)?;
+			memos.push((e, messages));
 		}
-		Some(phrase) => p.recover_from_mnemonic(phrase, args.passphrase)?,
 	}
+	display::escrowed_memos(&memos);
+	Ok(())
+}
+
+/// Arguments for client command
+pub struct ClientArgs {
+	/// Remote wallet's Owner API socket, as `ssh://[user@]host[:port]/path`
+	pub remote: String,
+	/// Owner API secret of the remote wallet
+	pub api_secret: Option<String>,
+	/// Owner API method to call
+	pub method: String,
+	/// Named parameters of the call, as a JSON object
+	pub params: Option<String>,
+}
+
+/// Call a method of the Owner API of a wallet on another host, through an
+/// SSH connection to the unix socket it listens on, and show the result
+pub fn client(args: ClientArgs) -> Result<(), Error> {
+	let mut params = match args.params.as_ref() {
+		None => json::Map::new(),
+		Some(p) => match json::from_str(p) {
+			Ok(json::Value::Object(m)) => m,
+			_ => {
+				let msg = format!("Parameters must be a JSON object: {}", p);
+				return Err(ErrorKind::ArgumentError(msg).into());
+			}
+		},
+	};
+	// the remote daemon keeps its wallet open, so calls don't need a token
+	params.entry("token").or_insert_with(|| json::Value::Null);
+	let client = WalletClient::ssh(&args.remote, args.api_secret)?;
+	let res = client.call(OWNER_V3_ROUTE, &args.method, json::Value::Object(params))?;
+	let result = res.get("result").cloned().unwrap_or(json::Value::Null);
+	println!("{}", json::to_string_pretty(&result).unwrap_or_default());
 	Ok(())
 }
 
@@ -119,37 +447,313 @@ pub struct ListenArgs {
 	pub method: String,
 }
 
+/// Set up metrics collection if configured, starting a separate metrics
+/// listener if metrics are to be served on their own port
+fn start_metrics<L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
+	config: &WalletConfig,
+) -> Option<Arc<WalletMetrics>>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: keychain::Keychain + 'static,
+{
+	if !config.metrics_enabled.unwrap_or(false) {
+		return None;
+	}
+	let metrics = Arc::new(WalletMetrics::new());
+	if let Some(addr) = config.metrics_listen_addr() {
+		let m = metrics.clone();
+		let _ = thread::Builder::new()
+			.name("metrics_listener".to_string())
+			.spawn(move || {
+				if let Err(e) = controller::metrics_listener(wallet, m, &addr) {
+					error!("Error starting metrics listener: {}", e);
+				}
+			});
+	}
+	Some(metrics)
+}
+
+/// Dispatcher for the configured webhooks and ZeroMQ publisher, if any,
+/// keeping the events it can't deliver in the wallet's directory
+fn webhooks(config: &WalletConfig) -> Result<Option<WebhookDispatcher>, Error> {
+	if config.webhooks.is_none() && config.zmq_publish_address.is_none() {
+		return Ok(None);
+	}
+	let path = PathBuf::from(&config.data_file_dir).join(WEBHOOK_DEAD_LETTERS_FILE);
+	let mut dispatcher =
+		WebhookDispatcher::new(config.webhooks.clone().unwrap_or_default()).with_dead_letters(path);
+	if let Some(address) = config.zmq_publish_address.as_ref() {
+		let filter = config.zmq_publish_filter.clone().unwrap_or_default();
+		dispatcher = dispatcher.with_publisher(ZmqPublisher::bind(address)?, filter);
+	}
+	Ok(Some(dispatcher))
+}
+
+/// Audit log of Owner API calls, in the wallet's directory, if it's to be kept
+fn audit_log(config: &WalletConfig) -> Result<Option<Arc<AuditLog>>, Error> {
+	if config.owner_api_audit_log != Some(true) {
+		return Ok(None);
+	}
+	let path = PathBuf::from(&config.data_file_dir).join(AUDIT_LOG_FILE);
+	Ok(Some(Arc::new(AuditLog::open(path)?)))
+}
+
+/// Notifier emailing significant events as configured, if at all
+fn email_notifier(config: &WalletConfig) -> Option<EmailNotifier> {
+	config.email_notifications.clone().map(EmailNotifier::new)
+}
+
+/// How the listeners render their responses, as configured
+fn response_format(config: &WalletConfig) -> ResponseFormat {
+	ResponseFormat {
+		pretty: config.api_response_pretty.unwrap_or(true),
+		max_bytes: config.api_max_response_bytes,
+	}
+}
+
+/// Push notifier posting to the configured gateway, if any
+fn push_notifier(config: &WalletConfig) -> Option<Arc<dyn PushNotifier>> {
+	config
+		.push_gateway_url
+		.clone()
+		.map(|url| Arc::new(GatewayPushNotifier::new(url)) as Arc<dyn PushNotifier>)
+}
+
+/// Start backing up the wallet to the configured backup targets, if any.
+/// Backups stop when the returned scheduler is dropped
+fn start_backups<L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
+	keychain_mask: Option<SecretKey>,
+	config: &WalletConfig,
+) -> Option<BackupScheduler>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: keychain::Keychain + 'static,
+{
+	let targets: Vec<_> = config
+		.backup_targets
+		.iter()
+		.flatten()
+		.map(create_backup_target)
+		.collect();
+	if targets.is_empty() {
+		return None;
+	}
+	Some(BackupScheduler::start(
+		wallet,
+		keychain_mask,
+		targets,
+		Duration::from_secs(config.backup_interval.unwrap_or(3600)),
+		config.backup_full_every.unwrap_or(24),
+		None,
+	))
+}
+
+/// Start keeping the configured recovery transaction current, if any. Checks
+/// stop when the returned planner is dropped
+fn start_recovery<L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
+	keychain_mask: Option<SecretKey>,
+	config: &WalletConfig,
+) -> Option<RecoveryPlanner>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: keychain::Keychain + 'static,
+{
+	let recovery = config.recovery.as_ref()?;
+	let defaults = RecoveryTxArgs::default();
+	let plan = RecoveryPlan {
+		args: RecoveryTxArgs {
+			src_acct_name: None,
+			lock_blocks: recovery.lock_blocks.unwrap_or(defaults.lock_blocks),
+			minimum_confirmations: recovery
+				.minimum_confirmations
+				.unwrap_or(defaults.minimum_confirmations),
+		},
+		method: recovery.method.clone().unwrap_or_else(|| "http".to_owned()),
+		dest: recovery.dest.clone(),
+		export_path: match recovery.export_file.as_ref() {
+			Some(f) => PathBuf::from(f),
+			None => PathBuf::from(&config.data_file_dir).join(RECOVERY_TX_FILE),
+		},
+	};
+	Some(RecoveryPlanner::start(
+		wallet,
+		keychain_mask,
+		plan,
+		Duration::from_secs(recovery.check_interval.unwrap_or(3600)),
+		None,
+	))
+}
+
+/// Start serving the Owner API over gRPC, if so configured. The server stops
+/// when the returned handle is dropped
+#[cfg(feature = "grpc")]
+fn start_grpc<L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
+	keychain_mask: Option<SecretKey>,
+	config: &WalletConfig,
+	g_args: &GlobalArgs,
+	webhooks: Option<WebhookDispatcher>,
+) -> Result<Option<grpcio::Server>, Error>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: keychain::Keychain + 'static,
+{
+	let addr = match config.owner_api_grpc_listen_addr() {
+		Some(a) => a,
+		None => return Ok(None),
+	};
+	// the gRPC API has no read-only mode
+	if config.owner_api_read_only == Some(true) {
+		warn!("Not serving the Owner API over gRPC, as the Owner API is read-only");
+		return Ok(None);
+	}
+	let server = crate::grpc::grpc_listener(
+		wallet,
+		keychain_mask,
+		&addr,
+		g_args.node_api_secret.clone(),
+		config.owner_api_include_foreign.unwrap_or(false),
+		webhooks,
+		config.receive_review_threshold,
+		receive_limits(config),
+	)?;
+	Ok(Some(server))
+}
+
+#[cfg(not(feature = "grpc"))]
+fn start_grpc<L, C, K>(
+	_wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
+	_keychain_mask: Option<SecretKey>,
+	config: &WalletConfig,
+	_g_args: &GlobalArgs,
+	_webhooks: Option<WebhookDispatcher>,
+) -> Result<Option<()>, Error>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: keychain::Keychain + 'static,
+{
+	if config.owner_api_grpc_listen_port.is_some() {
+		warn!(
+			"owner_api_grpc_listen_port is set, but this wallet was built without the grpc feature"
+		);
+	}
+	Ok(None)
+}
+
+/// Addresses of the configured reverse proxies
+fn trusted_proxies(proxies: &Option<Vec<String>>) -> Vec<IpAddr> {
+	proxies
+		.iter()
+		.flatten()
+		.filter_map(|p| p.parse().ok())
+		.collect()
+}
+
+/// Limits on requests to the foreign listener, and the tarpit for clients
+/// over them if configured
+fn request_limits(config: &WalletConfig) -> RequestLimitsMiddleware {
+	let limits = RequestLimitsMiddleware::new(
+		config.foreign_api_max_body_size(),
+		config.foreign_api_rate_limit,
+	)
+	.with_trusted_proxies(trusted_proxies(&config.foreign_api_trusted_proxies));
+	match config.foreign_api_tarpit.clone() {
+		Some(t) => limits.with_tarpit(t),
+		None => limits,
+	}
+}
+
+/// Limits on the slates received through the Foreign API, if configured
+fn receive_limits(config: &WalletConfig) -> Option<ReceiveLimits> {
+	config.receive_limits.as_ref().map(|l| ReceiveLimits {
+		min_amount: l.min_amount,
+		max_amount: l.max_amount,
+		max_per_hour: l.max_per_hour,
+		finalize_invoices: l.finalize_invoices.unwrap_or(true),
+	})
+}
+
+/// Wallets whose Foreign API calls are passed on to their own listeners
+fn forwarded_wallets(config: &WalletConfig) -> Vec<ForwardedWallet> {
+	config
+		.forwarded_wallets
+		.iter()
+		.flatten()
+		.map(|f| ForwardedWallet {
+			id: f.id.clone(),
+			upstream: f.upstream.clone(),
+			api_secret: get_first_line(f.api_secret_path.clone()),
+		})
+		.collect()
+}
+
 pub fn listen<'a, L, C, K>(
 	wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
 	keychain_mask: Option<SecretKey>,
+	hosted_wallets: Vec<HostedWallet<L, C, K>>,
 	config: &WalletConfig,
 	args: &ListenArgs,
 	g_args: &GlobalArgs,
+	shutdown: Option<ShutdownHandle>,
 ) -> Result<(), Error>
 where
 	L: WalletLCProvider<'static, C, K> + 'static,
 	C: NodeClient + 'static,
 	K: keychain::Keychain + 'static,
 {
+	let _backups = start_backups(wallet.clone(), keychain_mask.clone(), config);
+	let _recovery = start_recovery(wallet.clone(), keychain_mask.clone(), config);
 	let res = match args.method.as_str() {
 		"http" => controller::foreign_listener(
 			wallet.clone(),
 			keychain_mask,
+			hosted_wallets,
+			forwarded_wallets(config),
 			&config.api_listen_addr(),
-			g_args.tls_conf.clone(),
-		),
-		"keybase" => KeybaseAllChannels::new()?.listen(
-			config.clone(),
-			g_args.password.clone().unwrap(),
-			&g_args.account,
-			g_args.node_api_secret.clone(),
+			ForeignListenerConfig {
+				tls_config: g_args.tls_conf.clone(),
+				cors: config.foreign_api_cors.clone(),
+				limits: Some(request_limits(config)),
+				api: ForeignApiConfig {
+					webhooks: webhooks(config)?,
+					push_notifier: push_notifier(config),
+					review_threshold: config.receive_review_threshold,
+					receive_limits: receive_limits(config),
+					metrics: start_metrics(wallet.clone(), config),
+					envelope: config.api_response_envelope.unwrap_or(false),
+					format: response_format(config),
+				},
+			},
+			shutdown,
 		),
 		method => {
-			return Err(ErrorKind::ArgumentError(format!(
-				"No listener for method \"{}\".",
-				method
-			))
-			.into());
+			let receiver = match transport_for(method, "") {
+				Some(t) => t.receiver()?,
+				None => None,
+			};
+			match receiver {
+				Some(r) => r.listen(
+					config.clone(),
+					g_args.password.clone().unwrap(),
+					&g_args.account,
+					g_args.node_api_secret.clone(),
+				),
+				None => {
+					return Err(ErrorKind::ArgumentError(format!(
+						"No listener for method \"{}\".",
+						method
+					))
+					.into());
+				}
+			}
 		}
 	};
 
@@ -162,21 +766,64 @@ where
 pub fn owner_api<L, C, K>(
 	wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
 	keychain_mask: Option<SecretKey>,
+	hosted_wallets: Vec<HostedWallet<L, C, K>>,
 	config: &WalletConfig,
 	g_args: &GlobalArgs,
+	shutdown: Option<ShutdownHandle>,
 ) -> Result<(), Error>
 where
 	L: WalletLCProvider<'static, C, K> + Send + Sync + 'static,
 	C: NodeClient + 'static,
 	K: keychain::Keychain + 'static,
 {
+	let metrics = start_metrics(wallet.clone(), config);
+	let memo_escrow_key = match config.memo_escrow_key.as_ref() {
+		Some(k) => Some(libwallet::parse_escrow_key(k)?),
+		None => None,
+	};
+	let _backups = start_backups(wallet.clone(), keychain_mask.clone(), config);
+	let _recovery = start_recovery(wallet.clone(), keychain_mask.clone(), config);
+	let webhooks = webhooks(config)?;
+	let _grpc = start_grpc(
+		wallet.clone(),
+		keychain_mask.clone(),
+		config,
+		g_args,
+		webhooks.clone(),
+	)?;
+	let listener_config = OwnerListenerConfig {
+		socket_path: config.owner_api_listen_socket.clone(),
+		api_secret: g_args.node_api_secret.clone(),
+		token_ttl: config.owner_api_token_ttl,
+		cors: config.owner_api_cors.clone(),
+		tls_config: g_args.tls_conf.clone(),
+		tls_client_ca_file: config.owner_api_tls_client_ca_file.clone(),
+		include_foreign: config.owner_api_include_foreign.unwrap_or(false),
+		read_only: config.owner_api_read_only.unwrap_or(false),
+		graphql: config.owner_api_graphql.unwrap_or(false),
+		webhooks,
+		email: email_notifier(config),
+		push_notifier: push_notifier(config),
+		review_threshold: config.receive_review_threshold,
+		receive_limits: receive_limits(config),
+		metrics,
+		node_monitor_interval: config.node_monitor_interval,
+		auto_cancel_after_blocks: config.auto_cancel_after_blocks,
+		self_spend_on_receive: config.self_spend_on_receive.unwrap_or(false),
+		memo_escrow_key,
+		audit_log: audit_log(config)?,
+		envelope: config.api_response_envelope.unwrap_or(false),
+		format: response_format(config),
+		trusted_proxies: trusted_proxies(&config.owner_api_trusted_proxies),
+	};
 	let res = controller::owner_listener(
 		wallet,
 		keychain_mask,
+		hosted_wallets,
+		forwarded_wallets(config),
 		config.owner_api_listen_addr().as_str(),
-		g_args.node_api_secret.clone(),
-		g_args.tls_conf.clone(),
-		config.owner_api_include_foreign.clone(),
+		listener_config,
+		shutdown,
 	);
 	if let Err(e) = res {
 		return Err(ErrorKind::LibWallet(e.kind(), e.cause_string()).into());
@@ -241,6 +888,11 @@ pub struct SendArgs {
 	pub fluff: bool,
 	pub max_outputs: usize,
 	pub target_slate_version: Option<u16>,
+	pub additional_recipients: Vec<InitTxRecipient>,
+	pub replace: bool,
+	pub ttl_blocks: Option<u64>,
+	pub late_lock: bool,
+	pub qr: bool,
 }
 
 pub fn send<'a, L, C, K>(
@@ -254,6 +906,11 @@ where
 	C: NodeClient + 'a,
 	K: keychain::Keychain + 'a,
 {
+	// without the flag, the transaction is stemmed unless it's told otherwise
+	let fluff = match args.fluff {
+		true => Some(true),
+		false => None,
+	};
 	controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
 		if args.estimate_selection_strategies {
 			let strategies = vec!["smallest", "all"]
@@ -274,6 +931,39 @@ where
 				})
 				.collect();
 			display::estimate(args.amount, strategies, dark_scheme);
+		} else if !args.additional_recipients.is_empty() {
+			let init_args = InitTxArgs {
+				src_acct_name: None,
+				amount: args.amount,
+				minimum_confirmations: args.minimum_confirmations,
+				max_outputs: args.max_outputs as u32,
+				num_change_outputs: args.change_outputs as u32,
+				selection_strategy_is_use_all: args.selection_strategy == "all",
+				randomize_selection: Some(args.selection_strategy == "random"),
+				message: args.message.clone(),
+				target_slate_version: args.target_slate_version,
+				send_args: Some(InitTxSendArgs {
+					method: args.method.clone(),
+					dest: args.dest.clone(),
+					finalize: true,
+					post_tx: true,
+					fluff: args.fluff,
+				}),
+				additional_recipients: Some(args.additional_recipients.clone()),
+				ttl_blocks: args.ttl_blocks,
+				late_lock: Some(args.late_lock),
+				fluff,
+				..Default::default()
+			};
+			match api.init_send_tx_multi(m, init_args) {
+				Ok(slates) => {
+					info!("Tx sent ok to {} recipients", slates.len());
+				}
+				Err(e) => {
+					error!("Tx sent fail: {}", e);
+					return Err(e);
+				}
+			}
 		} else {
 			let init_args = InitTxArgs {
 				src_acct_name: None,
@@ -282,9 +972,13 @@ where
 				max_outputs: args.max_outputs as u32,
 				num_change_outputs: args.change_outputs as u32,
 				selection_strategy_is_use_all: args.selection_strategy == "all",
+				randomize_selection: Some(args.selection_strategy == "random"),
 				message: args.message.clone(),
 				target_slate_version: args.target_slate_version,
 				send_args: None,
+				ttl_blocks: args.ttl_blocks,
+				late_lock: Some(args.late_lock),
+				fluff,
 				..Default::default()
 			};
 			let result = api.init_send_tx(m, init_args);
@@ -292,7 +986,7 @@ where
 				Ok(s) => {
 					info!(
 						"Tx created: {} grin to {} (strategy '{}')",
-						core::amount_to_hr_string(args.amount, false),
+						libwallet::log_amount(args.amount),
 						args.dest,
 						args.selection_strategy,
 					);
@@ -308,6 +1002,9 @@ where
 				"file" => {
 					PathToSlate((&args.dest).into()).put_tx(&slate)?;
 					api.tx_lock_outputs(m, &slate, 0)?;
+					if args.qr {
+						display::slate_qr(&slate)?;
+					}
 					return Ok(());
 				}
 				"self" => {
@@ -332,8 +1029,8 @@ where
 				error!("Error validating participant messages: {}", e);
 				e
 			})?;
-			slate = api.finalize_tx(m, &slate)?;
-			let result = api.post_tx(m, &slate.tx, args.fluff);
+			slate = api.finalize_tx(m, &slate, None)?;
+			let result = api.post_tx_checked(m, &slate.tx, fluff, args.replace);
 			match result {
 				Ok(_) => {
 					info!("Tx sent ok",);
@@ -352,8 +1049,12 @@ where
 
 /// Receive command argument
 pub struct ReceiveArgs {
-	pub input: String,
+	/// The sender's transaction file, or None to read the text scanned from
+	/// the sender's QR codes from stdin
+	pub input: Option<String>,
 	pub message: Option<String>,
+	/// Whether to display the response as QR codes
+	pub qr: bool,
 }
 
 pub fn receive<'a, L, C, K>(
@@ -367,7 +1068,10 @@ where
 	C: NodeClient + 'a,
 	K: keychain::Keychain + 'a,
 {
-	let mut slate = PathToSlate((&args.input).into()).get_tx()?;
+	let mut slate = match args.input.as_ref() {
+		Some(i) => PathToSlate(i.into()).get_tx()?,
+		None => read_qr_slate()?,
+	};
 	let km = match keychain_mask.as_ref() {
 		None => None,
 		Some(&m) => Some(m.to_owned()),
@@ -380,18 +1084,38 @@ where
 		slate = api.receive_tx(&slate, Some(&g_args.account), args.message.clone())?;
 		Ok(())
 	})?;
-	PathToSlate(format!("{}.response", args.input).into()).put_tx(&slate)?;
-	info!(
-		"Response file {}.response generated, and can be sent back to the transaction originator.",
-		args.input
-	);
+	if let Some(input) = args.input.as_ref() {
+		PathToSlate(format!("{}.response", input).into()).put_tx(&slate)?;
+		info!(
+			"Response file {}.response generated, and can be sent back to the transaction originator.",
+			input
+		);
+	}
+	if args.qr {
+		display::slate_qr(&slate)?;
+		info!("Response QR codes displayed, to be scanned by the transaction originator.");
+	}
 	Ok(())
 }
 
+/// Reads a slate from the text of its QR codes, pasted into stdin one per line
+fn read_qr_slate() -> Result<libwallet::Slate, Error> {
+	println!("Paste the text of each of the sender's QR codes, one per line:");
+	let mut reader = QrSlateReader::new();
+	for line in io::stdin().lock().lines() {
+		let line = line.map_err(|_| ErrorKind::IO)?;
+		if reader.add_line(&line)? == Some(0) {
+			return Ok(reader.slate()?);
+		}
+	}
+	Ok(reader.slate()?)
+}
+
 /// Finalize command args
 pub struct FinalizeArgs {
 	pub input: String,
 	pub fluff: bool,
+	pub replace: bool,
 }
 
 pub fn finalize<'a, L, C, K>(
@@ -405,6 +1129,11 @@ where
 	K: keychain::Keychain + 'a,
 {
 	let mut slate = PathToSlate((&args.input).into()).get_tx()?;
+	// without the flag, the preference stored with the transaction is followed
+	let fluff = match args.fluff {
+		true => Some(true),
+		false => None,
+	};
 
 	// Rather than duplicating the entire command, we'll just
 	// try to determine what kind of finalization this is
@@ -443,13 +1172,13 @@ where
 				error!("Error validating participant messages: {}", e);
 				return Err(e);
 			}
-			slate = api.finalize_tx(m, &mut slate)?;
+			slate = api.finalize_tx(m, &mut slate, fluff)?;
 			Ok(())
 		})?;
 	}
 
 	controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
-		let result = api.post_tx(m, &slate.tx, args.fluff);
+		let result = api.post_tx_checked(m, &slate.tx, fluff, args.replace);
 		match result {
 			Ok(_) => {
 				info!("Transaction sent successfully, check the wallet again for confirmation.");
@@ -465,12 +1194,157 @@ where
 	Ok(())
 }
 
+/// Arguments for the prepare_send command
+pub struct PrepareSendArgs {
+	pub amount: u64,
+	pub message: Option<String>,
+	pub minimum_confirmations: u64,
+	pub selection_strategy: String,
+	pub change_outputs: usize,
+	pub max_outputs: usize,
+	pub target_slate_version: Option<u16>,
+	pub ttl_blocks: Option<u64>,
+	/// File the unsigned transaction is written to
+	pub dest: String,
+}
+
+/// Prepare a send on a watch-only wallet, writing the unsigned transaction
+/// for the wallet holding the seed to sign offline
+pub fn prepare_send<'a, L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+	keychain_mask: Option<&SecretKey>,
+	args: PrepareSendArgs,
+) -> Result<(), Error>
+where
+	L: WalletLCProvider<'a, C, K>,
+	C: NodeClient + 'a,
+	K: keychain::Keychain + 'a,
+{
+	controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
+		let init_args = InitTxArgs {
+			src_acct_name: None,
+			amount: args.amount,
+			minimum_confirmations: args.minimum_confirmations,
+			max_outputs: args.max_outputs as u32,
+			num_change_outputs: args.change_outputs as u32,
+			selection_strategy_is_use_all: args.selection_strategy == "all",
+			randomize_selection: Some(args.selection_strategy == "random"),
+			message: args.message.clone(),
+			target_slate_version: args.target_slate_version,
+			ttl_blocks: args.ttl_blocks,
+			..Default::default()
+		};
+		let unsigned = api.prepare_send(m, init_args)?;
+		let out = json::to_string_pretty(&unsigned).unwrap();
+		write_output(&out, Some(&args.dest), "Unsigned transaction")
+	})?;
+	Ok(())
+}
+
+/// Arguments for the sign_offline command
+pub struct SignOfflineArgs {
+	/// Unsigned transaction, or the slate returned by the recipient
+	pub input: String,
+	pub dest: Option<String>,
+}
+
+/// Sign a transaction prepared on a watch-only wallet with the wallet holding
+/// the seed, without a node. An unsigned transaction is signed into a slate
+/// for the recipient, and the slate the recipient returns is finalized for
+/// the watch-only wallet to broadcast
+pub fn sign_offline<'a, L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+	keychain_mask: Option<&SecretKey>,
+	args: SignOfflineArgs,
+) -> Result<(), Error>
+where
+	L: WalletLCProvider<'a, C, K>,
+	C: NodeClient + 'a,
+	K: keychain::Keychain + 'a,
+{
+	let data = fs::read_to_string(&args.input)
+		.map_err(|e| ErrorKind::ArgumentError(format!("Reading {}: {}", args.input, e)))?;
+	let value: json::Value = json::from_str(&data).map_err(|e| {
+		ErrorKind::ArgumentError(format!("{} isn't a transaction file: {}", args.input, e))
+	})?;
+	if value.get("inputs").is_some() && value.get("change").is_some() {
+		let unsigned: UnsignedTx = json::from_value(value).map_err(|e| {
+			ErrorKind::ArgumentError(format!(
+				"{} isn't an unsigned transaction: {}",
+				args.input, e
+			))
+		})?;
+		let dest = args.dest.unwrap_or_else(|| format!("{}.slate", args.input));
+		controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
+			let slate = api.sign_offline(m, &unsigned)?;
+			PathToSlate((&dest).into()).put_tx(&slate)?;
+			info!("Slate for the recipient written to {}", dest);
+			Ok(())
+		})?;
+	} else {
+		let slate = PathToSlate((&args.input).into()).get_tx()?;
+		let dest = args
+			.dest
+			.unwrap_or_else(|| format!("{}.signed", args.input));
+		controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
+			if let Err(e) = api.verify_slate_messages(m, &slate) {
+				error!("Error validating participant messages: {}", e);
+				return Err(e);
+			}
+			let signed = api.finalize_offline(m, &slate)?;
+			let out = json::to_string_pretty(&signed).unwrap();
+			write_output(&out, Some(&dest), "Signed transaction")
+		})?;
+	}
+	Ok(())
+}
+
+/// Arguments for the broadcast_signed command
+pub struct BroadcastSignedArgs {
+	pub input: String,
+	pub fluff: bool,
+}
+
+/// Record the change of a transaction signed offline in the watch-only wallet
+/// that prepared it, and post it
+pub fn broadcast_signed<'a, L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+	keychain_mask: Option<&SecretKey>,
+	args: BroadcastSignedArgs,
+) -> Result<(), Error>
+where
+	L: WalletLCProvider<'a, C, K>,
+	C: NodeClient + 'a,
+	K: keychain::Keychain + 'a,
+{
+	let data = fs::read_to_string(&args.input)
+		.map_err(|e| ErrorKind::ArgumentError(format!("Reading {}: {}", args.input, e)))?;
+	let signed: SignedTx = json::from_str(&data).map_err(|e| {
+		ErrorKind::ArgumentError(format!("{} isn't a signed transaction: {}", args.input, e))
+	})?;
+	controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
+		match api.broadcast_signed(m, &signed, args.fluff) {
+			Ok(_) => {
+				info!("Transaction sent successfully, check the wallet again for confirmation.");
+				Ok(())
+			}
+			Err(e) => {
+				error!("Tx not sent: {}", e);
+				Err(e)
+			}
+		}
+	})?;
+	Ok(())
+}
+
 /// Issue Invoice Args
 pub struct IssueInvoiceArgs {
 	/// output file
 	pub dest: String,
 	/// issue invoice tx args
 	pub issue_args: IssueInvoiceTxArgs,
+	/// whether to also display the slate as QR codes
+	pub qr: bool,
 }
 
 pub fn issue_invoice_tx<'a, L, C, K>(
@@ -488,6 +1362,9 @@ where
 		let mut tx_file = File::create(args.dest.clone())?;
 		tx_file.write_all(json::to_string(&slate).unwrap().as_bytes())?;
 		tx_file.sync_all()?;
+		if args.qr {
+			display::slate_qr(&slate)?;
+		}
 		Ok(())
 	})?;
 	Ok(())
@@ -501,6 +1378,7 @@ pub struct ProcessInvoiceArgs {
 	pub method: String,
 	pub dest: String,
 	pub max_outputs: usize,
+	pub change_outputs: usize,
 	pub input: String,
 	pub estimate_selection_strategies: bool,
 }
@@ -528,7 +1406,7 @@ where
 						amount: slate.amount,
 						minimum_confirmations: args.minimum_confirmations,
 						max_outputs: args.max_outputs as u32,
-						num_change_outputs: 1u32,
+						num_change_outputs: args.change_outputs as u32,
 						selection_strategy_is_use_all: strategy == "all",
 						estimate_only: Some(true),
 						..Default::default()
@@ -544,8 +1422,9 @@ where
 				amount: 0,
 				minimum_confirmations: args.minimum_confirmations,
 				max_outputs: args.max_outputs as u32,
-				num_change_outputs: 1u32,
+				num_change_outputs: args.change_outputs as u32,
 				selection_strategy_is_use_all: args.selection_strategy == "all",
+				randomize_selection: Some(args.selection_strategy == "random"),
 				message: args.message.clone(),
 				send_args: None,
 				..Default::default()
@@ -559,7 +1438,7 @@ where
 				Ok(s) => {
 					info!(
 						"Invoice processed: {} grin to {} (strategy '{}')",
-						core::amount_to_hr_string(slate.amount, false),
+						libwallet::log_amount(slate.amount),
 						args.dest,
 						args.selection_strategy,
 					);
@@ -665,7 +1544,7 @@ where
 {
 	controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
 		let res = api.node_height(m)?;
-		let (validated, txs) = api.retrieve_txs(m, true, args.id, args.tx_slate_id)?;
+		let (validated, txs) = api.retrieve_txs(m, true, args.id, args.tx_slate_id, None)?;
 		let include_status = !args.id.is_some() && !args.tx_slate_id.is_some();
 		display::txs(
 			&g_args.account,
@@ -705,11 +1584,97 @@ where
 	Ok(())
 }
 
+/// Settlement report
+pub struct SettlementArgs {
+	pub start: DateTime<Utc>,
+	pub end: DateTime<Utc>,
+	pub currency: String,
+	pub format: String,
+	pub dest: Option<String>,
+}
+
+pub fn settlement<'a, L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+	keychain_mask: Option<&SecretKey>,
+	args: SettlementArgs,
+	price_oracle_url: Option<String>,
+) -> Result<(), Error>
+where
+	L: WalletLCProvider<'a, C, K>,
+	C: NodeClient + 'a,
+	K: keychain::Keychain + 'a,
+{
+	let oracle = match price_oracle_url {
+		Some(url) => HttpPriceOracle::new(url),
+		None => {
+			let msg = "No price_oracle_url is configured to value transactions with".to_owned();
+			return Err(ErrorKind::ArgumentError(msg).into());
+		}
+	};
+	controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
+		let report =
+			api.settlement_report(m, true, args.start, args.end, &args.currency, &oracle)?;
+		let out = match args.format.as_str() {
+			"csv" => report.to_csv(),
+			_ => json::to_string_pretty(&report).unwrap(),
+		};
+		write_output(&out, args.dest.as_ref(), "Settlement report")
+	})?;
+	Ok(())
+}
+
+/// Write a command's output to the given file, or print it if none is given
+fn write_output(out: &str, dest: Option<&String>, what: &str) -> Result<(), libwallet::Error> {
+	match dest {
+		Some(f) => {
+			let mut file = File::create(f)?;
+			file.write_all(out.as_bytes())?;
+			file.sync_all()?;
+			info!("{} written to {}", what, f);
+		}
+		None => println!("{}", out),
+	}
+	Ok(())
+}
+
+/// Transaction history export
+pub struct ExportHistoryArgs {
+	pub format: TxExportFormat,
+	/// Transactions to export
+	pub filter: RetrieveTxQueryArgs,
+	pub dest: Option<String>,
+	/// Public key (hex) to seal transaction memos to, if any
+	pub memo_escrow_key: Option<String>,
+}
+
+pub fn export_history<'a, L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+	keychain_mask: Option<&SecretKey>,
+	args: ExportHistoryArgs,
+) -> Result<(), Error>
+where
+	L: WalletLCProvider<'a, C, K>,
+	C: NodeClient + 'a,
+	K: keychain::Keychain + 'a,
+{
+	let memo_escrow_key = match args.memo_escrow_key.as_ref() {
+		Some(k) => Some(libwallet::parse_escrow_key(k)?),
+		None => None,
+	};
+	controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
+		api.set_memo_escrow_key(memo_escrow_key.clone());
+		let out = api.export_txs(m, true, args.format, args.filter.clone())?;
+		write_output(&out, args.dest.as_ref(), "Transaction history")
+	})?;
+	Ok(())
+}
+
 /// Repost
 pub struct RepostArgs {
 	pub id: u32,
 	pub dump_file: Option<String>,
 	pub fluff: bool,
+	pub replace: bool,
 }
 
 pub fn repost<'a, L, C, K>(
@@ -723,7 +1688,7 @@ where
 	K: keychain::Keychain + 'a,
 {
 	controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
-		let (_, txs) = api.retrieve_txs(m, true, Some(args.id), None)?;
+		let (_, txs) = api.retrieve_txs(m, true, Some(args.id), None, None)?;
 		let stored_tx = api.get_stored_tx(m, &txs[0])?;
 		if stored_tx.is_none() {
 			error!(
@@ -741,7 +1706,12 @@ where
 					);
 					return Ok(());
 				}
-				api.post_tx(m, &stored_tx.unwrap(), args.fluff)?;
+				// without the flag, follow the preference stored with the transaction
+				let fluff = match args.fluff {
+					true => Some(true),
+					false => txs[0].fluff,
+				};
+				api.post_tx_checked(m, &stored_tx.unwrap(), fluff, args.replace)?;
 				info!("Reposted transaction at {}", args.id);
 				return Ok(());
 			}
@@ -790,6 +1760,84 @@ where
 	Ok(())
 }
 
+/// Consolidate
+pub struct ConsolidateArgs {
+	pub minimum_confirmations: u64,
+	pub num_outputs: u32,
+	pub max_weight: u64,
+	pub max_txs: u32,
+	pub fluff: bool,
+}
+
+pub fn consolidate<'a, L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+	keychain_mask: Option<&SecretKey>,
+	args: ConsolidateArgs,
+) -> Result<(), Error>
+where
+	L: WalletLCProvider<'a, C, K>,
+	C: NodeClient + 'a,
+	K: keychain::Keychain + 'a,
+{
+	controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
+		let args = libwallet::ConsolidateArgs {
+			minimum_confirmations: args.minimum_confirmations,
+			num_outputs: args.num_outputs,
+			max_weight: args.max_weight,
+			max_txs: args.max_txs,
+			fluff: args.fluff,
+			..Default::default()
+		};
+		let slates = match api.consolidate(m, args) {
+			Ok(s) => s,
+			Err(e) => {
+				error!("Consolidation failed: {}", e);
+				return Err(e);
+			}
+		};
+		if slates.is_empty() {
+			info!("Nothing to consolidate");
+		}
+		for slate in slates {
+			info!(
+				"Transaction {} combining {} outputs into {} posted",
+				slate.id,
+				slate.tx.inputs().len(),
+				slate.tx.outputs().len()
+			);
+		}
+		Ok(())
+	})?;
+	Ok(())
+}
+
+/// Label
+pub struct LabelArgs {
+	pub tx_id: u32,
+	pub label: Option<String>,
+}
+
+pub fn label<'a, L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+	keychain_mask: Option<&SecretKey>,
+	args: LabelArgs,
+) -> Result<(), Error>
+where
+	L: WalletLCProvider<'a, C, K>,
+	C: NodeClient + 'a,
+	K: keychain::Keychain + 'a,
+{
+	controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
+		api.set_tx_label(m, args.tx_id, args.label.clone())?;
+		match args.label {
+			Some(_) => info!("Transaction {} labelled", args.tx_id),
+			None => info!("Label of transaction {} cleared", args.tx_id),
+		}
+		Ok(())
+	})?;
+	Ok(())
+}
+
 pub fn restore<'a, L, C, K>(
 	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
 	keychain_mask: Option<&SecretKey>,
@@ -819,6 +1867,7 @@ where
 /// wallet check
 pub struct CheckArgs {
 	pub delete_unconfirmed: bool,
+	pub full: bool,
 }
 
 pub fn check_repair<'a, L, C, K>(
@@ -834,7 +1883,7 @@ where
 	controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
 		warn!("Starting wallet check...",);
 		warn!("Updating all wallet outputs, please wait ...",);
-		let result = api.check_repair(m, args.delete_unconfirmed);
+		let result = api.check_repair(m, args.delete_unconfirmed, args.full);
 		match result {
 			Ok(_) => {
 				warn!("Wallet check complete",);
@@ -14,13 +14,32 @@
 
 //! Controller for wallet.. instantiates and handles listeners (or single-run
 //! invocations) as needed.
-use crate::api::{self, ApiServer, BasicAuthMiddleware, ResponseFuture, Router, TLSConfig};
+use crate::api::{
+	self, ApiServer, BasicAuthMiddleware, HandlerObj, ResponseFuture, Router, TLSConfig,
+};
+use crate::auth::{
+	caller_identity, AuthFailureMiddleware, AuthTokens, TokenAuthHandler, TokenAuthMiddleware,
+	TokenScope, LOGIN_ROUTE, REVOKE_ROUTE,
+};
+use crate::config::CorsConfig;
+use crate::envelope::add_envelope;
+use crate::events::{OperationEventsHandler, OPERATION_EVENTS_ROUTE};
+use crate::graphql::GraphQLHandler;
+use crate::hosted::{ForwardedWallet, HostedWallet, WalletRoutingHandler};
+use crate::impls::{AuditLog, EmailNotifier, WebhookDispatcher};
 use crate::keychain::Keychain;
 use crate::libwallet::{
-	Error, ErrorKind, NodeClient, NodeVersionInfo, Slate, WalletInst, WalletLCProvider,
-	CURRENT_SLATE_VERSION, GRIN_BLOCK_HEADER_VERSION,
+	record_error, Error, ErrorKind, NodeClient, NodeConnectivity, NodeVersionInfo, PushNotifier,
+	ReceiveLimits, Slate, SlateVersion, WalletInst, WalletLCProvider, GRIN_BLOCK_HEADER_VERSION,
 };
-use crate::util::secp::key::SecretKey;
+use crate::metrics::{MetricsHandler, WalletMetrics};
+use crate::middleware::{CorsMiddleware, RequestLimitsMiddleware};
+use crate::response::ResponseFormat;
+use crate::server::start_tcp_server;
+use crate::tls::{self, start_client_auth_server, ClientAuthTLSConfig};
+#[cfg(unix)]
+use crate::uds::start_unix_socket_server;
+use crate::util::secp::key::{PublicKey, SecretKey};
 use crate::util::{to_base64, Mutex};
 use failure::ResultExt;
 use futures::future::{err, ok};
@@ -29,19 +48,64 @@ use hyper::header::HeaderValue;
 use hyper::{Body, Request, Response, StatusCode};
 use serde::{Deserialize, Serialize};
 use serde_json;
-use std::net::SocketAddr;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
-use crate::apiwallet::{Foreign, ForeignCheckMiddlewareFn, ForeignRpc, Owner, OwnerRpc, OwnerRpcS};
+use crate::apiwallet::{
+	CallPriority, CallQueue, Foreign, ForeignCheckMiddlewareFn, ForeignRpc, NodeMonitor,
+	OperationRunner, Owner, OwnerRpc, OwnerRpcS, ShutdownHandle, WalletUpdater,
+};
 use easy_jsonrpc;
 use easy_jsonrpc::{Handler, MaybeReply};
 
+/// Named parameter giving the TOTP code for a spend, if the wallet asks for
+/// one
+pub const TOTP_CODE_PARAM: &str = "totp_code";
+
+/// Header giving the TOTP code for a spend, for calls with positional
+/// parameters, such as those of the V2 API, which can't name one
+pub const TOTP_CODE_HEADER: &str = "X-Grin-Totp-Code";
+
+/// Owner API methods served by a read-only listener. Refreshing from the node
+/// while retrieving is allowed, as it only brings the wallet's records in line
+/// with the chain
+const READ_ONLY_METHODS: &[&str] = &[
+	"accounts",
+	"retrieve_outputs",
+	"query_outputs",
+	"get_output_derivation",
+	"retrieve_txs",
+	"export_txs",
+	"retrieve_summary_info",
+	"retrieve_pending_receives",
+	"retrieve_push_registrations",
+	"get_stored_tx",
+	"replay_tx",
+	"verify_slate_messages",
+	"audit_chain",
+	"list_anomalies",
+	"node_height",
+	"node_status",
+	"get_node_status",
+	"get_feature_flags",
+	"get_build_info",
+	"get_statistics",
+	"get_updater_messages",
+	"operation_status",
+	"list_operations",
+	"get_webhook_dead_letters",
+	"export_audit_log",
+];
+
 lazy_static! {
 	pub static ref GRIN_OWNER_BASIC_REALM: HeaderValue =
 		HeaderValue::from_str("Basic realm=GrinOwnerAPI").unwrap();
 }
 
-fn check_middleware(
+pub(crate) fn check_middleware(
 	name: ForeignCheckMiddlewareFn,
 	node_version_info: Option<NodeVersionInfo>,
 	slate: Option<&Slate>,
@@ -55,7 +119,9 @@ fn check_middleware(
 				bhv = n.block_header_version;
 			}
 			if let Some(s) = slate {
-				if s.version_info.version < CURRENT_SLATE_VERSION
+				// older slates this wallet reads were upgraded without loss when
+				// deserialized
+				if SlateVersion::from_number(s.version_info.version).is_none()
 					|| (bhv == 1 && s.version_info.block_header_version != 1)
 					|| (bhv > 1 && s.version_info.block_header_version < GRIN_BLOCK_HEADER_VERSION)
 				{
@@ -109,17 +175,181 @@ where
 	Ok(())
 }
 
+/// Block until the listener's API server exits or, if a shutdown handle is given, until
+/// shutdown is requested, in which case the server is stopped and the wallets closed
+fn wait_for_shutdown<L, C, K>(
+	wallets: Vec<Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>>,
+	stop: Box<dyn FnOnce()>,
+	api_thread: JoinHandle<()>,
+	shutdown: Option<ShutdownHandle>,
+	name: &str,
+) -> Result<(), Error>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	if let Some(s) = shutdown {
+		s.wait();
+		warn!("Stopping HTTP {} listener.", name);
+		stop();
+	}
+	api_thread
+		.join()
+		.map_err(|e| ErrorKind::GenericError(format!("API thread panicked :{:?}", e)))?;
+	for wallet in wallets {
+		let mut w_lock = wallet.lock();
+		w_lock.lc_provider()?.close_wallet(None)?;
+	}
+	warn!("HTTP {} listener stopped.", name);
+	Ok(())
+}
+
+/// How the Foreign API handles calls, whether served by its own listener or
+/// included in the owner listener
+#[derive(Clone, Default)]
+pub struct ForeignApiConfig {
+	/// Webhooks to notify of transaction lifecycle events
+	pub webhooks: Option<WebhookDispatcher>,
+	/// Notifier waking registered devices when a slate is received
+	pub push_notifier: Option<Arc<dyn PushNotifier>>,
+	/// Amount above which received slates are queued for the owner to approve
+	/// or reject rather than signed straight away
+	pub review_threshold: Option<u64>,
+	/// Limits outside which received slates are refused, if any
+	pub receive_limits: Option<ReceiveLimits>,
+	/// Request metrics, if being collected
+	pub metrics: Option<Arc<WalletMetrics>>,
+	/// Whether each response carries a `meta` member, as for the owner listener
+	pub envelope: bool,
+	/// How responses are rendered, as for the owner listener
+	pub format: ResponseFormat,
+}
+
+/// Options of the foreign listener
+#[derive(Default)]
+pub struct ForeignListenerConfig {
+	/// TLS certificate and key to serve the listener with, if any
+	pub tls_config: Option<TLSConfig>,
+	/// Cross-origin policy, any origin being allowed if not given
+	pub cors: Option<CorsConfig>,
+	/// Limits on the size and rate of requests, if any
+	pub limits: Option<RequestLimitsMiddleware>,
+	/// How the Foreign API handles calls
+	pub api: ForeignApiConfig,
+}
+
+/// Options of the owner listener
+#[derive(Default)]
+pub struct OwnerListenerConfig {
+	/// Unix socket to serve the listener on over plain HTTP, readable and
+	/// writable only by the wallet's user, rather than its address
+	pub socket_path: Option<String>,
+	/// Secret clients authenticate with, if any
+	pub api_secret: Option<String>,
+	/// If set along with `api_secret`, the secret is only accepted by the
+	/// login route, which issues bearer tokens expiring after this many
+	/// seconds of disuse
+	pub token_ttl: Option<u64>,
+	/// Cross-origin policy, any origin being allowed if not given
+	pub cors: Option<CorsConfig>,
+	/// TLS certificate and key to serve the listener with, if any
+	pub tls_config: Option<TLSConfig>,
+	/// If given along with `tls_config`, clients must present a certificate
+	/// signed by one of the CAs in this file
+	pub tls_client_ca_file: Option<String>,
+	/// Whether the Foreign API is served by the listener too
+	pub include_foreign: bool,
+	/// Whether only methods retrieving information are served
+	pub read_only: bool,
+	/// Whether read-only GraphQL queries are served at /v3/graphql
+	pub graphql: bool,
+	/// Webhooks to notify of transaction lifecycle events
+	pub webhooks: Option<WebhookDispatcher>,
+	/// Emails to send on significant events, if any
+	pub email: Option<EmailNotifier>,
+	/// Notifier waking registered devices, for the included Foreign API
+	pub push_notifier: Option<Arc<dyn PushNotifier>>,
+	/// Review threshold of the included Foreign API
+	pub review_threshold: Option<u64>,
+	/// Receive limits of the included Foreign API
+	pub receive_limits: Option<ReceiveLimits>,
+	/// Request metrics, if being collected
+	pub metrics: Option<Arc<WalletMetrics>>,
+	/// Seconds between background checks of the node's connectivity, for the
+	/// `get_node_status` method, if checked at all
+	pub node_monitor_interval: Option<u64>,
+	/// Blocks after which the updater cancels transactions still unconfirmed,
+	/// marking them as expired and unlocking their inputs
+	pub auto_cancel_after_blocks: Option<u64>,
+	/// Whether the updater spends confirmed received outputs back to the
+	/// wallet, so received funds sit in outputs created by the wallet's own
+	/// transaction
+	pub self_spend_on_receive: bool,
+	/// Key each transaction's messages are sealed to in JSON transaction
+	/// exports, if any
+	pub memo_escrow_key: Option<PublicKey>,
+	/// Log each call to the Owner API is recorded in, along with who made it
+	/// and its result, if kept
+	pub audit_log: Option<Arc<AuditLog>>,
+	/// Whether each response carries a `meta` member giving the API version,
+	/// server time and any deprecation warnings for the method called
+	pub envelope: bool,
+	/// How responses are rendered: pretty-printed or compact unless the
+	/// request's `pretty` query parameter asks otherwise, and replaced by an
+	/// error if over the maximum size
+	pub format: ResponseFormat,
+	/// Addresses of reverse proxies in front of the listener, whose requests
+	/// are recorded in the audit log with the client address they give
+	pub trusted_proxies: Vec<IpAddr>,
+}
+
+impl OwnerListenerConfig {
+	/// How the included Foreign API handles calls
+	fn foreign_api(&self) -> ForeignApiConfig {
+		ForeignApiConfig {
+			webhooks: self.webhooks.clone(),
+			push_notifier: self.push_notifier.clone(),
+			review_threshold: self.review_threshold,
+			receive_limits: self.receive_limits.clone(),
+			metrics: self.metrics.clone(),
+			envelope: self.envelope,
+			format: self.format,
+		}
+	}
+}
+
 /// Listener version, providing same API but listening for requests on a
 /// port and wrapping the calls
 /// Note keychain mask is only provided here in case the foreign listener is also being used
 /// in the same wallet instance
+/// Bearer tokens are issued for the wallet, or one of the `hosted_wallets` if the login
+/// gives its id as `wallet_id`, and are only accepted for calls on that wallet. Those
+/// issued for a wallet are revoked once its password is changed through the V3
+/// `change_password` method
+/// If a `shutdown` handle is given, the listener stops and returns when shutdown is
+/// requested, either through the handle or the Owner API's `stop_listener` method
+/// The wallet can be refreshed against the node in the background by calling the
+/// `start_updater` method, rather than by each call asking for a refresh
+/// Restores and checks can be run in the background, to be followed and cancelled through
+/// the API, by calling the `start_operation` method. Those a previous listener left
+/// unfinished are resumed. Their progress is also streamed as server-sent events from
+/// /v3/operations/<id>/events
+/// If enabled, read-only GraphQL queries over accounts, transactions and outputs are
+/// served at /v3/graphql, behind the same authentication as the Owner API
+/// Each of the `hosted_wallets` is served alongside the wallet, with its own updater,
+/// background operations and node monitor, to V3 calls and GraphQL queries giving its id
+/// as their `wallet_id` parameter, and to Foreign API calls posted to
+/// `/v2/foreign/<wallet_id>`. Webhook events of a hosted wallet carry its id. Calls
+/// to the included Foreign API for one of the `forwarded_wallets` are passed on to it
 pub fn owner_listener<L, C, K>(
 	wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
 	keychain_mask: Option<SecretKey>,
+	hosted_wallets: Vec<HostedWallet<L, C, K>>,
+	forwarded_wallets: Vec<ForwardedWallet>,
 	addr: &str,
-	api_secret: Option<String>,
-	tls_config: Option<TLSConfig>,
-	owner_api_include_foreign: Option<bool>,
+	config: OwnerListenerConfig,
+	shutdown: Option<ShutdownHandle>,
 ) -> Result<(), Error>
 where
 	L: WalletLCProvider<'static, C, K> + 'static,
@@ -127,82 +357,440 @@ where
 	K: Keychain + 'static,
 {
 	let mut router = Router::new();
-	if api_secret.is_some() {
-		let api_basic_auth =
-			"Basic ".to_string() + &to_base64(&("grin:".to_string() + &api_secret.unwrap()));
-		let basic_auth_middleware = Arc::new(BasicAuthMiddleware::new(
-			api_basic_auth,
-			&GRIN_OWNER_BASIC_REALM,
-		));
-		router.add_middleware(basic_auth_middleware);
+	router.add_middleware(Arc::new(CorsMiddleware::new(
+		config.cors.clone().unwrap_or_default(),
+	)));
+	let mut auth_tokens = None;
+	if let Some(api_secret) = config.api_secret.as_ref() {
+		if let Some(e) = config.email.as_ref() {
+			router.add_middleware(Arc::new(AuthFailureMiddleware::new(e.clone())));
+		}
+		let api_basic_auth = "Basic ".to_string() + &to_base64(&("grin:".to_string() + api_secret));
+		match config.token_ttl {
+			Some(ttl) => {
+				let tokens = Arc::new(AuthTokens::new(ttl));
+				router.add_middleware(Arc::new(TokenAuthMiddleware::new(
+					api_basic_auth,
+					&GRIN_OWNER_BASIC_REALM,
+					tokens.clone(),
+				)));
+				auth_tokens = Some(tokens.clone());
+				let hosted_ids = hosted_wallets.iter().map(|h| h.id.clone()).collect();
+				let auth_handler =
+					Arc::new(TokenAuthHandler::new(tokens).with_wallet_ids(hosted_ids));
+				router
+					.add_route(LOGIN_ROUTE, auth_handler.clone())
+					.map_err(|_| {
+						ErrorKind::GenericError("Router failed to add route".to_string())
+					})?;
+				router.add_route(REVOKE_ROUTE, auth_handler).map_err(|_| {
+					ErrorKind::GenericError("Router failed to add route".to_string())
+				})?;
+			}
+			None => {
+				let basic_auth_middleware = Arc::new(BasicAuthMiddleware::new(
+					api_basic_auth,
+					&GRIN_OWNER_BASIC_REALM,
+				));
+				router.add_middleware(basic_auth_middleware);
+			}
+		}
+	}
+
+	let node_monitor = start_node_monitor(&wallet, &config)?;
+
+	// orders calls on the wallet, shared by both API versions and the updater
+	let call_queue = Arc::new(CallQueue::new());
+	if let Some(m) = config.metrics.as_ref() {
+		m.watch_call_queue(call_queue.clone());
+	}
+
+	// idle until started through the API
+	let updater = WalletUpdater::new(wallet.clone(), Some(call_queue.clone()));
+	updater.set_auto_cancel(config.auto_cancel_after_blocks);
+	updater.set_self_spend_on_receive(config.self_spend_on_receive);
+	let updater = Some(Arc::new(updater));
+	let operations = OperationRunner::new(wallet.clone(), Some(call_queue.clone()));
+	match operations.resume(keychain_mask.clone()) {
+		Ok(0) => {}
+		Ok(n) => warn!("Resumed {} background operation(s)", n),
+		Err(e) => error!("Unable to resume background operations: {}", e),
 	}
+	let operations = Some(Arc::new(operations));
 
-	let api_handler_v2 = OwnerAPIHandlerV2::new(wallet.clone());
+	let handler_config = OwnerHandlerConfig {
+		webhooks: config.webhooks.clone(),
+		email: config.email.clone(),
+		metrics: config.metrics.clone(),
+		node_monitor,
+		updater,
+		operations: operations.clone(),
+		memo_escrow_key: config.memo_escrow_key.clone(),
+		audit_log: config.audit_log.clone(),
+		call_queue,
+		envelope: config.envelope,
+		format: config.format,
+		read_only: config.read_only,
+		shutdown: shutdown.clone(),
+		auth_tokens,
+		wallet_id: None,
+		trusted_proxies: config.trusted_proxies.clone(),
+	};
+	let api_handler_v2 = OwnerAPIHandlerV2::new(wallet.clone(), handler_config.clone());
+	let api_handler_v3 = OwnerAPIHandlerV3::new(wallet.clone(), handler_config.clone());
 
-	let api_handler_v3 = OwnerAPIHandlerV3::new(wallet.clone());
+	// hosted wallets don't wait on each other's calls
+	let mut hosted_handlers_v3: HashMap<String, HandlerObj> = HashMap::new();
+	let mut hosted_graphql_handlers: HashMap<String, HandlerObj> = HashMap::new();
+	for h in hosted_wallets.iter() {
+		let call_queue = Arc::new(CallQueue::new());
+		let updater = WalletUpdater::new(h.wallet.clone(), Some(call_queue.clone()));
+		updater.set_auto_cancel(config.auto_cancel_after_blocks);
+		updater.set_self_spend_on_receive(config.self_spend_on_receive);
+		let operations = OperationRunner::new(h.wallet.clone(), Some(call_queue.clone()));
+		match operations.resume(h.keychain_mask.clone()) {
+			Ok(0) => {}
+			Ok(n) => warn!("Resumed {} background operation(s) of wallet {}", n, h.id),
+			Err(e) => error!(
+				"Unable to resume background operations of wallet {}: {}",
+				h.id, e
+			),
+		}
+		let hosted_config = OwnerHandlerConfig {
+			webhooks: config
+				.webhooks
+				.clone()
+				.map(|w| w.with_wallet_id(h.id.clone())),
+			node_monitor: start_node_monitor(&h.wallet, &config)?,
+			updater: Some(Arc::new(updater)),
+			operations: Some(Arc::new(operations)),
+			call_queue,
+			wallet_id: Some(h.id.clone()),
+			..handler_config.clone()
+		};
+		let graphql_handler = GraphQLHandler::new(h.wallet.clone(), hosted_config.clone());
+		hosted_graphql_handlers.insert(h.id.clone(), Arc::new(graphql_handler));
+		let handler = OwnerAPIHandlerV3::new(h.wallet.clone(), hosted_config);
+		hosted_handlers_v3.insert(h.id.clone(), Arc::new(handler));
+	}
+	let api_handler_v3: HandlerObj = match hosted_handlers_v3.is_empty() {
+		true => Arc::new(api_handler_v3),
+		false => Arc::new(WalletRoutingHandler::new(
+			Arc::new(api_handler_v3),
+			hosted_handlers_v3,
+		)),
+	};
 
 	router
 		.add_route("/v2/owner", Arc::new(api_handler_v2))
 		.map_err(|_| ErrorKind::GenericError("Router failed to add route".to_string()))?;
 
 	router
-		.add_route("/v3/owner", Arc::new(api_handler_v3))
+		.add_route("/v3/owner", api_handler_v3)
 		.map_err(|_| ErrorKind::GenericError("Router failed to add route".to_string()))?;
 
-	// If so configured, add the foreign API to the same port
-	if owner_api_include_foreign.unwrap_or(false) {
-		warn!("Starting HTTP Foreign API on Owner server at {}.", addr);
-		let foreign_api_handler_v2 = ForeignAPIHandlerV2::new(wallet, keychain_mask);
+	if config.graphql {
+		let graphql_handler = Arc::new(GraphQLHandler::new(wallet.clone(), handler_config));
+		let graphql_handler: HandlerObj = match hosted_graphql_handlers.is_empty() {
+			true => graphql_handler,
+			false => Arc::new(WalletRoutingHandler::new(
+				graphql_handler,
+				hosted_graphql_handlers,
+			)),
+		};
 		router
-			.add_route("/v2/foreign", Arc::new(foreign_api_handler_v2))
+			.add_route("/v3/graphql", graphql_handler)
 			.map_err(|_| ErrorKind::GenericError("Router failed to add route".to_string()))?;
 	}
 
-	let mut apis = ApiServer::new();
+	if let Some(o) = operations.as_ref() {
+		router
+			.add_route(
+				OPERATION_EVENTS_ROUTE,
+				Arc::new(OperationEventsHandler::new(o.clone())),
+			)
+			.map_err(|_| ErrorKind::GenericError("Router failed to add route".to_string()))?;
+	}
+
+	// Metrics are served alongside the owner API, behind the same authentication
+	if let Some(m) = config.metrics.as_ref() {
+		router
+			.add_route(
+				"/metrics",
+				Arc::new(MetricsHandler::new(wallet.clone(), m.clone())),
+			)
+			.map_err(|_| ErrorKind::GenericError("Router failed to add route".to_string()))?;
+	}
+
+	let addr = config
+		.socket_path
+		.clone()
+		.unwrap_or_else(|| addr.to_owned());
+
+	// If so configured, add the foreign API to the same port, unless only
+	// serving information
+	if config.read_only && config.include_foreign {
+		warn!("Not including the Foreign API on a read-only Owner API server");
+	} else if config.include_foreign {
+		warn!("Starting HTTP Foreign API on Owner server at {}.", addr);
+		let foreign_api_handler_v2 = foreign_handler(
+			wallet.clone(),
+			keychain_mask,
+			&hosted_wallets,
+			&forwarded_wallets,
+			&config.foreign_api(),
+		)?;
+		add_foreign_routes(
+			&mut router,
+			foreign_api_handler_v2,
+			!hosted_wallets.is_empty() || !forwarded_wallets.is_empty(),
+		)?;
+	}
+
 	warn!("Starting HTTP Owner API server at {}.", addr);
-	let socket_addr: SocketAddr = addr.parse().expect("unable to parse socket address");
-	let api_thread =
-		apis.start(socket_addr, router, tls_config)
-			.context(ErrorKind::GenericError(
-				"API thread failed to start".to_string(),
-			))?;
+	if config.read_only {
+		warn!("Owner API server is read-only, only serving methods retrieving information");
+	}
+	if config.socket_path.is_some() && config.tls_config.is_some() {
+		warn!("TLS isn't used by Owner API clients connecting through the unix socket");
+	}
+	let (api_thread, stop): (JoinHandle<()>, Box<dyn FnOnce()>) =
+		match (config.tls_config, config.tls_client_ca_file) {
+			_ if config.socket_path.is_some() => start_socket_server(&addr, router)?,
+			(Some(tls), Some(ca)) => {
+				warn!(
+					"Owner API clients must present a certificate signed by a CA in {}",
+					ca
+				);
+				let conf = ClientAuthTLSConfig::new(tls, ca);
+				let socket_addr: SocketAddr = addr.parse().expect("unable to parse socket address");
+				let (api_thread, stop_tx) = start_client_auth_server(socket_addr, router, &conf)?;
+				let stop: Box<dyn FnOnce()> = Box::new(move || {
+					let _ = stop_tx.send(());
+				});
+				(api_thread, stop)
+			}
+			(None, Some(_)) => {
+				return Err(ErrorKind::GenericError(
+					"Client certificate verification requires a TLS certificate and key"
+						.to_string(),
+				))?;
+			}
+			(tls_config, None) => {
+				let socket_addr: SocketAddr = addr.parse().expect("unable to parse socket address");
+				let mut apis = ApiServer::new();
+				let api_thread = apis.start(socket_addr, router, tls_config).context(
+					ErrorKind::GenericError("API thread failed to start".to_string()),
+				)?;
+				let stop: Box<dyn FnOnce()> = Box::new(move || {
+					apis.stop();
+				});
+				(api_thread, stop)
+			}
+		};
 	warn!("HTTP Owner listener started.");
-	api_thread
-		.join()
-		.map_err(|e| ErrorKind::GenericError(format!("API thread panicked :{:?}", e)).into())
+	let mut wallets = vec![wallet];
+	wallets.extend(hosted_wallets.into_iter().map(|h| h.wallet));
+	wait_for_shutdown(wallets, stop, api_thread, shutdown, "Owner")
+}
+
+/// Monitor of the connectivity of the wallet's node, if so configured
+fn start_node_monitor<L, C, K>(
+	wallet: &Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+	config: &OwnerListenerConfig,
+) -> Result<Option<Arc<NodeMonitor>>, Error>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	match config.node_monitor_interval {
+		Some(secs) if secs > 0 => {
+			let client = wallet.lock().lc_provider()?.node_client();
+			let on_check = config.email.clone().map(|e| {
+				Box::new(move |s: &NodeConnectivity| e.node_checked(s))
+					as Box<dyn Fn(&NodeConnectivity) + Send>
+			});
+			Ok(Some(Arc::new(NodeMonitor::start(
+				client,
+				Duration::from_secs(secs),
+				on_check,
+			))))
+		}
+		_ => Ok(None),
+	}
+}
+
+/// Foreign API handler for the wallet, routing calls posted to
+/// `/v2/foreign/<wallet_id>` to the hosted wallets, or on to the forwarded
+/// wallets, if any
+fn foreign_handler<L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+	keychain_mask: Option<SecretKey>,
+	hosted_wallets: &[HostedWallet<L, C, K>],
+	forwarded_wallets: &[ForwardedWallet],
+	config: &ForeignApiConfig,
+) -> Result<HandlerObj, Error>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	let handler = |wallet, keychain_mask, config| -> HandlerObj {
+		Arc::new(ForeignAPIHandlerV2::new(wallet, keychain_mask, config))
+	};
+	if hosted_wallets.is_empty() && forwarded_wallets.is_empty() {
+		return Ok(handler(wallet, keychain_mask, config.clone()));
+	}
+	let hosted = hosted_wallets
+		.iter()
+		.map(|h| {
+			// events of a hosted wallet say which wallet they're from
+			let hosted_config = ForeignApiConfig {
+				webhooks: config
+					.webhooks
+					.clone()
+					.map(|w| w.with_wallet_id(h.id.clone())),
+				..config.clone()
+			};
+			let foreign = handler(h.wallet.clone(), h.keychain_mask.clone(), hosted_config);
+			(h.id.clone(), foreign)
+		})
+		.collect();
+	Ok(Arc::new(
+		WalletRoutingHandler::new(handler(wallet, keychain_mask, config.clone()), hosted)
+			.with_path_routing("/v2/foreign")
+			.with_forwarded(forwarded_wallets)?,
+	))
+}
+
+/// Serve the Foreign API `handler` at `/v2/foreign`, and at
+/// `/v2/foreign/<wallet_id>` too if it routes calls to other wallets
+fn add_foreign_routes(router: &mut Router, handler: HandlerObj, routed: bool) -> Result<(), Error> {
+	let mut routes = vec!["/v2/foreign"];
+	if routed {
+		routes.push("/v2/foreign/*");
+	}
+	for route in routes {
+		router
+			.add_route(route, handler.clone())
+			.map_err(|_| ErrorKind::GenericError("Router failed to add route".to_string()))?;
+	}
+	Ok(())
+}
+
+/// Serve the router on the unix socket at `path`
+#[cfg(unix)]
+fn start_socket_server(
+	path: &str,
+	router: Router,
+) -> Result<(JoinHandle<()>, Box<dyn FnOnce()>), Error> {
+	let (api_thread, stop_tx) = start_unix_socket_server(path, router)?;
+	let stop: Box<dyn FnOnce()> = Box::new(move || {
+		let _ = stop_tx.send(());
+	});
+	Ok((api_thread, stop))
+}
+
+#[cfg(not(unix))]
+fn start_socket_server(
+	_path: &str,
+	_router: Router,
+) -> Result<(JoinHandle<()>, Box<dyn FnOnce()>), Error> {
+	Err(ErrorKind::GenericError(
+		"Unix sockets aren't supported on this platform".to_string(),
+	))?
 }
 
 /// Listener version, providing same API but listening for requests on a
 /// port and wrapping the calls
+/// If a `shutdown` handle is given, the listener stops and returns when shutdown is requested
+/// Calls posted to `/v2/foreign/<wallet_id>` are passed to that one of the `hosted_wallets`,
+/// or on to the listener of that one of the `forwarded_wallets`
 pub fn foreign_listener<L, C, K>(
 	wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
 	keychain_mask: Option<SecretKey>,
+	hosted_wallets: Vec<HostedWallet<L, C, K>>,
+	forwarded_wallets: Vec<ForwardedWallet>,
 	addr: &str,
-	tls_config: Option<TLSConfig>,
+	config: ForeignListenerConfig,
+	shutdown: Option<ShutdownHandle>,
 ) -> Result<(), Error>
 where
 	L: WalletLCProvider<'static, C, K> + 'static,
 	C: NodeClient + 'static,
 	K: Keychain + 'static,
 {
-	let api_handler_v2 = ForeignAPIHandlerV2::new(wallet, keychain_mask);
+	let api_handler_v2 = foreign_handler(
+		wallet.clone(),
+		keychain_mask,
+		&hosted_wallets,
+		&forwarded_wallets,
+		&config.api,
+	)?;
 
 	let mut router = Router::new();
+	router.add_middleware(Arc::new(CorsMiddleware::new(
+		config.cors.unwrap_or_default(),
+	)));
+	if let Some(l) = config.limits {
+		router.add_middleware(Arc::new(l));
+	}
 
-	router
-		.add_route("/v2/foreign", Arc::new(api_handler_v2))
-		.map_err(|_| ErrorKind::GenericError("Router failed to add route".to_string()))?;
+	add_foreign_routes(
+		&mut router,
+		api_handler_v2,
+		!hosted_wallets.is_empty() || !forwarded_wallets.is_empty(),
+	)?;
 
-	let mut apis = ApiServer::new();
 	warn!("Starting HTTP Foreign listener API server at {}.", addr);
 	let socket_addr: SocketAddr = addr.parse().expect("unable to parse socket address");
-	let api_thread =
-		apis.start(socket_addr, router, tls_config)
-			.context(ErrorKind::GenericError(
-				"API thread failed to start".to_string(),
-			))?;
+	// served by the wallet's own server, so rate limits can tell clients apart
+	let tls_config = match config.tls_config {
+		Some(t) => Some(tls::server_config(&t, None)?),
+		None => None,
+	};
+	let (api_thread, stop_tx) = start_tcp_server(socket_addr, router, tls_config)?;
 	warn!("HTTP Foreign listener started.");
+	let mut wallets = vec![wallet];
+	wallets.extend(hosted_wallets.into_iter().map(|h| h.wallet));
+	wait_for_shutdown(
+		wallets,
+		Box::new(move || {
+			let _ = stop_tx.send(());
+		}),
+		api_thread,
+		shutdown,
+		"Foreign",
+	)
+}
+
+/// Listener serving only metrics, for when they should be scraped from a
+/// separate port to the wallet's APIs
+pub fn metrics_listener<L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+	metrics: Arc<WalletMetrics>,
+	addr: &str,
+) -> Result<(), Error>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	let mut router = Router::new();
+	router
+		.add_route("/metrics", Arc::new(MetricsHandler::new(wallet, metrics)))
+		.map_err(|_| ErrorKind::GenericError("Router failed to add route".to_string()))?;
 
+	let mut apis = ApiServer::new();
+	warn!("Starting HTTP metrics server at {}.", addr);
+	let socket_addr: SocketAddr = addr.parse().expect("unable to parse socket address");
+	let api_thread = apis
+		.start(socket_addr, router, None)
+		.context(ErrorKind::GenericError(
+			"API thread failed to start".to_string(),
+		))?;
+	warn!("HTTP metrics listener started.");
 	api_thread
 		.join()
 		.map_err(|e| ErrorKind::GenericError(format!("API thread panicked :{:?}", e)).into())
@@ -210,6 +798,80 @@ where
 
 type WalletResponseFuture = Box<dyn Future<Item = Response<Body>, Error = Error> + Send>;
 
+/// What the owner API handlers give the Owner API for each call, and how they
+/// serve calls
+#[derive(Clone)]
+pub struct OwnerHandlerConfig {
+	/// Webhooks to notify of transaction lifecycle events
+	pub webhooks: Option<WebhookDispatcher>,
+	/// Emails to send on significant events, if any
+	pub email: Option<EmailNotifier>,
+	/// Request metrics, if being collected
+	pub metrics: Option<Arc<WalletMetrics>>,
+	/// Background monitor of the node's connectivity, if running
+	pub node_monitor: Option<Arc<NodeMonitor>>,
+	/// Background updater controlled by the start_updater and stop_updater methods
+	pub updater: Option<Arc<WalletUpdater>>,
+	/// Runner of operations started by the start_operation method
+	pub operations: Option<Arc<OperationRunner>>,
+	/// Key transaction memos are sealed to in exports, if any
+	pub memo_escrow_key: Option<PublicKey>,
+	/// Log each call is recorded in, if kept
+	pub audit_log: Option<Arc<AuditLog>>,
+	/// Queue ordering calls on the wallet by priority
+	pub call_queue: Arc<CallQueue>,
+	/// Whether to add the response envelope to each response
+	pub envelope: bool,
+	/// How responses are rendered
+	pub format: ResponseFormat,
+	/// Whether only methods retrieving information are served
+	pub read_only: bool,
+	/// Handle used by the stop_listener method to stop the listener
+	pub shutdown: Option<ShutdownHandle>,
+	/// Bearer tokens issued by the listener, those for the wallet revoked by
+	/// the V3 handler when its password is changed
+	pub auth_tokens: Option<Arc<AuthTokens>>,
+	/// Id of the hosted wallet served, or `None` for the listener's own.
+	/// Bearer tokens issued for any other wallet are refused
+	pub wallet_id: Option<String>,
+	/// Reverse proxies trusted to give the address of the client a call is
+	/// recorded in the audit log as coming from
+	pub trusted_proxies: Vec<IpAddr>,
+}
+
+impl OwnerHandlerConfig {
+	/// Owner API for a call on the wallet
+	pub(crate) fn owner_api<L, C, K>(
+		&self,
+		wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+	) -> Owner<'static, L, C, K>
+	where
+		L: WalletLCProvider<'static, C, K>,
+		C: NodeClient + 'static,
+		K: Keychain + 'static,
+	{
+		let mut api = Owner::new(wallet);
+		api.set_webhooks(self.webhooks.clone());
+		api.set_email_notifier(self.email.clone());
+		api.set_shutdown_handle(self.shutdown.clone());
+		api.set_node_monitor(self.node_monitor.clone());
+		api.set_updater(self.updater.clone());
+		api.set_operation_runner(self.operations.clone());
+		api.set_memo_escrow_key(self.memo_escrow_key.clone());
+		api.set_audit_log(self.audit_log.clone());
+		api
+	}
+
+	/// Check the bearer token the request was authenticated with, if any, is
+	/// for the wallet served
+	pub(crate) fn check_token_wallet(&self, req: &Request<Body>) -> Result<(), String> {
+		match req.extensions().get::<TokenScope>() {
+			Some(s) => s.check_wallet(self.wallet_id.as_ref().map(|w| w.as_str())),
+			None => Ok(()),
+		}
+	}
+}
+
 /// V2 API Handler/Wrapper for owner functions
 pub struct OwnerAPIHandlerV2<L, C, K>
 where
@@ -219,6 +881,8 @@ where
 {
 	/// Wallet instance
 	pub wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+	/// What each call's Owner API is given, and how calls are served
+	pub config: OwnerHandlerConfig,
 }
 
 impl<L, C, K> OwnerAPIHandlerV2<L, C, K>
@@ -230,19 +894,65 @@ where
 	/// Create a new owner API handler for GET methods
 	pub fn new(
 		wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+		config: OwnerHandlerConfig,
 	) -> OwnerAPIHandlerV2<L, C, K> {
-		OwnerAPIHandlerV2 { wallet }
+		OwnerAPIHandlerV2 { wallet, config }
 	}
 
 	fn call_api(
 		&self,
 		req: Request<Body>,
-		api: Owner<'static, L, C, K>,
+		mut api: Owner<'static, L, C, K>,
 	) -> Box<dyn Future<Item = serde_json::Value, Error = Error> + Send> {
-		Box::new(parse_body(req).and_then(move |val: serde_json::Value| {
+		let metrics = self.config.metrics.clone();
+		let call_queue = self.config.call_queue.clone();
+		let envelope = self.config.envelope;
+		let audit_log = self.config.audit_log.clone();
+		let read_only = self.config.read_only;
+		let caller = caller_identity(&req, &self.config.trusted_proxies);
+		let totp_code = header_totp_code(&req);
+		let token_wallet = self.config.check_token_wallet(&req);
+		let scope = req
+			.extensions()
+			.get::<TokenScope>()
+			.cloned()
+			.unwrap_or_default();
+		Box::new(parse_body(req).and_then(move |mut val: serde_json::Value| {
+			api.require_totp(take_totp_code(&mut val).or(totp_code));
+			api.scope_to_account(scope.account.clone());
+			let audit = |result: String| {
+				if let Some(l) = audit_log.as_ref() {
+					audit_call(l, "owner_v2", &val, &caller, result);
+				}
+			};
+			if let Err(msg) = check_read_only(read_only, &val) {
+				audit(format!("refused: {}", msg));
+				return ok(scope_error_response(&val, msg));
+			}
+			if let Err(msg) = token_wallet.and_then(|_| scope.check_request(&val)) {
+				audit(format!("refused: {}", msg));
+				return ok(scope_error_response(&val, msg));
+			}
 			let owner_api = &api as &dyn OwnerRpc;
-			match owner_api.handle_request(val) {
-				MaybeReply::Reply(r) => ok(r),
+			let start = Instant::now();
+			let reply = {
+				let _turn = CallPriority::of_request(&val).map(|p| call_queue.wait_turn(p));
+				owner_api.handle_request(val.clone())
+			};
+			audit(call_result(&reply));
+			record_reply_error(&reply);
+			if let Some(m) = metrics {
+				m.record_request("owner_v2", &val, start, &reply);
+			}
+			match reply {
+				MaybeReply::Reply(mut r) => {
+					scope.filter_response(&val, &mut r);
+					if envelope {
+						ok(add_envelope("owner", 2, &val, r))
+					} else {
+						ok(r)
+					}
+				}
 				MaybeReply::DontReply => {
 					// Since it's http, we need to return something. We return [] because jsonrpc
 					// clients will parse it as an empty batch response.
@@ -253,10 +963,11 @@ where
 	}
 
 	fn handle_post_request(&self, req: Request<Body>) -> WalletResponseFuture {
-		let api = Owner::new(self.wallet.clone());
+		let api = self.config.owner_api(self.wallet.clone());
+		let format = self.config.format.for_request(&req);
 		Box::new(
 			self.call_api(req, api)
-				.and_then(|resp| ok(json_response_pretty(&resp))),
+				.and_then(move |resp| ok(json_response(&resp, &format))),
 		)
 	}
 }
@@ -268,11 +979,15 @@ where
 	K: Keychain + 'static,
 {
 	fn post(&self, req: Request<Body>) -> ResponseFuture {
+		let metrics = self.config.metrics.clone();
 		Box::new(
 			self.handle_post_request(req)
 				.and_then(|r| ok(r))
-				.or_else(|e| {
+				.or_else(move |e| {
 					error!("Request Error: {:?}", e);
+					if let Some(m) = metrics {
+						m.record_listener_error("owner_v2");
+					}
 					ok(create_error_response(e))
 				}),
 		)
@@ -293,6 +1008,8 @@ where
 {
 	/// Wallet instance
 	pub wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+	/// What each call's Owner API is given, and how calls are served
+	pub config: OwnerHandlerConfig,
 }
 
 impl<L, C, K> OwnerAPIHandlerV3<L, C, K>
@@ -304,19 +1021,77 @@ where
 	/// Create a new owner API handler for GET methods
 	pub fn new(
 		wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+		config: OwnerHandlerConfig,
 	) -> OwnerAPIHandlerV3<L, C, K> {
-		OwnerAPIHandlerV3 { wallet }
+		OwnerAPIHandlerV3 { wallet, config }
 	}
 
 	fn call_api(
 		&self,
 		req: Request<Body>,
-		api: Owner<'static, L, C, K>,
+		mut api: Owner<'static, L, C, K>,
 	) -> Box<dyn Future<Item = serde_json::Value, Error = Error> + Send> {
-		Box::new(parse_body(req).and_then(move |val: serde_json::Value| {
+		let metrics = self.config.metrics.clone();
+		let call_queue = self.config.call_queue.clone();
+		let envelope = self.config.envelope;
+		let audit_log = self.config.audit_log.clone();
+		let read_only = self.config.read_only;
+		let auth_tokens = self.config.auth_tokens.clone();
+		let wallet_id = self.config.wallet_id.clone();
+		let caller = caller_identity(&req, &self.config.trusted_proxies);
+		let totp_code = header_totp_code(&req);
+		let token_wallet = self.config.check_token_wallet(&req);
+		let scope = req
+			.extensions()
+			.get::<TokenScope>()
+			.cloned()
+			.unwrap_or_default();
+		Box::new(parse_body(req).and_then(move |mut val: serde_json::Value| {
+			api.require_totp(take_totp_code(&mut val).or(totp_code));
+			api.scope_to_account(scope.account.clone());
+			let audit = |result: String| {
+				if let Some(l) = audit_log.as_ref() {
+					audit_call(l, "owner_v3", &val, &caller, result);
+				}
+			};
+			if let Err(msg) = check_read_only(read_only, &val) {
+				audit(format!("refused: {}", msg));
+				return ok(scope_error_response(&val, msg));
+			}
+			if let Err(msg) = token_wallet.and_then(|_| scope.check_request(&val)) {
+				audit(format!("refused: {}", msg));
+				return ok(scope_error_response(&val, msg));
+			}
 			let owner_api_s = &api as &dyn OwnerRpcS;
-			match owner_api_s.handle_request(val) {
-				MaybeReply::Reply(r) => ok(r),
+			let start = Instant::now();
+			let reply = {
+				let _turn = CallPriority::of_request(&val).map(|p| call_queue.wait_turn(p));
+				owner_api_s.handle_request(val.clone())
+			};
+			audit(call_result(&reply));
+			record_reply_error(&reply);
+			if let Some(m) = metrics {
+				m.record_request("owner_v3", &val, start, &reply);
+			}
+			if let Some(t) = auth_tokens {
+				if val.get("method").and_then(|m| m.as_str()) == Some("change_password")
+					&& call_result(&reply) == "ok"
+				{
+					warn!(
+						"Wallet password changed, revoked {} token(s)",
+						t.revoke_wallet(wallet_id.as_ref().map(|w| w.as_str()))
+					);
+				}
+			}
+			match reply {
+				MaybeReply::Reply(mut r) => {
+					scope.filter_response(&val, &mut r);
+					if envelope {
+						ok(add_envelope("owner", 3, &val, r))
+					} else {
+						ok(r)
+					}
+				}
 				MaybeReply::DontReply => {
 					// Since it's http, we need to return something. We return [] because jsonrpc
 					// clients will parse it as an empty batch response.
@@ -327,10 +1102,11 @@ where
 	}
 
 	fn handle_post_request(&self, req: Request<Body>) -> WalletResponseFuture {
-		let api = Owner::new(self.wallet.clone());
+		let api = self.config.owner_api(self.wallet.clone());
+		let format = self.config.format.for_request(&req);
 		Box::new(
 			self.call_api(req, api)
-				.and_then(|resp| ok(json_response_pretty(&resp))),
+				.and_then(move |resp| ok(json_response(&resp, &format))),
 		)
 	}
 }
@@ -342,11 +1118,15 @@ where
 	K: Keychain + 'static,
 {
 	fn post(&self, req: Request<Body>) -> ResponseFuture {
+		let metrics = self.config.metrics.clone();
 		Box::new(
 			self.handle_post_request(req)
 				.and_then(|r| ok(r))
-				.or_else(|e| {
+				.or_else(move |e| {
 					error!("Request Error: {:?}", e);
+					if let Some(m) = metrics {
+						m.record_listener_error("owner_v3");
+					}
 					ok(create_error_response(e))
 				}),
 		)
@@ -367,6 +1147,8 @@ where
 	pub wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
 	/// Keychain mask
 	pub keychain_mask: Option<SecretKey>,
+	/// How calls are handled
+	pub config: ForeignApiConfig,
 }
 
 impl<L, C, K> ForeignAPIHandlerV2<L, C, K>
@@ -379,10 +1161,12 @@ where
 	pub fn new(
 		wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
 		keychain_mask: Option<SecretKey>,
+		config: ForeignApiConfig,
 	) -> ForeignAPIHandlerV2<L, C, K> {
 		ForeignAPIHandlerV2 {
 			wallet,
 			keychain_mask,
+			config,
 		}
 	}
 
@@ -391,9 +1175,18 @@ where
 		req: Request<Body>,
 		api: Foreign<'static, L, C, K>,
 	) -> Box<dyn Future<Item = serde_json::Value, Error = Error> + Send> {
+		let metrics = self.config.metrics.clone();
+		let envelope = self.config.envelope;
 		Box::new(parse_body(req).and_then(move |val: serde_json::Value| {
 			let foreign_api = &api as &dyn ForeignRpc;
-			match foreign_api.handle_request(val) {
+			let start = Instant::now();
+			let reply = foreign_api.handle_request(val.clone());
+			record_reply_error(&reply);
+			if let Some(m) = metrics {
+				m.record_request("foreign_v2", &val, start, &reply);
+			}
+			match reply {
+				MaybeReply::Reply(r) if envelope => ok(add_envelope("foreign", 2, &val, r)),
 				MaybeReply::Reply(r) => ok(r),
 				MaybeReply::DontReply => {
 					// Since it's http, we need to return something. We return [] because jsonrpc
@@ -405,14 +1198,19 @@ where
 	}
 
 	fn handle_post_request(&self, req: Request<Body>) -> WalletResponseFuture {
-		let api = Foreign::new(
+		let mut api = Foreign::new(
 			self.wallet.clone(),
 			self.keychain_mask.clone(),
 			Some(check_middleware),
 		);
+		api.set_webhooks(self.config.webhooks.clone());
+		api.set_push_notifier(self.config.push_notifier.clone());
+		api.set_review_threshold(self.config.review_threshold);
+		api.set_receive_limits(self.config.receive_limits.clone());
+		let format = self.config.format.for_request(&req);
 		Box::new(
 			self.call_api(req, api)
-				.and_then(|resp| ok(json_response_pretty(&resp))),
+				.and_then(move |resp| ok(json_response(&resp, &format))),
 		)
 	}
 }
@@ -424,11 +1222,15 @@ where
 	K: Keychain + 'static,
 {
 	fn post(&self, req: Request<Body>) -> ResponseFuture {
+		let metrics = self.config.metrics.clone();
 		Box::new(
 			self.handle_post_request(req)
 				.and_then(|r| ok(r))
-				.or_else(|e| {
+				.or_else(move |e| {
 					error!("Request Error: {:?}", e);
+					if let Some(m) = metrics {
+						m.record_listener_error("foreign_v2");
+					}
 					ok(create_error_response(e))
 				}),
 		)
@@ -451,25 +1253,103 @@ where
 	}
 }
 
-// pretty-printed version of above
-fn json_response_pretty<T>(s: &T) -> Response<Body>
-where
-	T: Serialize,
-{
-	match serde_json::to_string_pretty(s) {
+// version of above rendering a JSON-RPC response in the listener's format
+pub(crate) fn json_response(s: &serde_json::Value, format: &ResponseFormat) -> Response<Body> {
+	match format.render(s) {
 		Ok(json) => response(StatusCode::OK, json),
 		Err(_) => response(StatusCode::INTERNAL_SERVER_ERROR, ""),
 	}
 }
 
+/// Record an Owner API call in the audit log
+fn audit_call(log: &AuditLog, api: &str, req: &serde_json::Value, caller: &str, result: String) {
+	let method = req.get("method").and_then(|m| m.as_str()).unwrap_or("");
+	let params = req
+		.get("params")
+		.cloned()
+		.unwrap_or(serde_json::Value::Null);
+	if let Err(e) = log.record(api, method, &params, caller, &result) {
+		error!("Unable to record {} call in the audit log: {}", method, e);
+	}
+}
+
+/// Result of a call for the audit log: `ok`, or the error returned
+fn call_result(reply: &MaybeReply) -> String {
+	let r = match reply {
+		MaybeReply::Reply(r) => r,
+		MaybeReply::DontReply => return "ok".to_owned(),
+	};
+	match (r.pointer("/result/Err"), r.get("error")) {
+		(Some(e), _) | (None, Some(e)) => format!("error: {}", e),
+		(None, None) => "ok".to_owned(),
+	}
+}
+
+/// Count the kind of error a call returned, if any, in the wallet's statistics
+fn record_reply_error(reply: &MaybeReply) {
+	let r = match reply {
+		MaybeReply::Reply(r) => r,
+		MaybeReply::DontReply => return,
+	};
+	let kind = match (r.pointer("/result/Err"), r.get("error")) {
+		(Some(serde_json::Value::String(k)), _) => k.clone(),
+		(Some(serde_json::Value::Object(e)), _) => match e.keys().next() {
+			Some(k) => k.clone(),
+			None => return,
+		},
+		(Some(e), _) => e.to_string(),
+		(None, Some(_)) => "JsonRpcError".to_owned(),
+		(None, None) => return,
+	};
+	record_error(&kind);
+}
+
+/// Check a request may be served by a read-only listener, returning why not
+/// otherwise
+fn check_read_only(read_only: bool, req: &serde_json::Value) -> Result<(), String> {
+	let method = req.get("method").and_then(|m| m.as_str()).unwrap_or("");
+	match read_only && !READ_ONLY_METHODS.contains(&method) {
+		true => Err(format!(
+			"Method {} is not available on a read-only listener",
+			method
+		)),
+		false => Ok(()),
+	}
+}
+
+/// Take the TOTP code given for a spend out of a call's named parameters, so
+/// it's neither passed to the method nor recorded in the audit log
+fn take_totp_code(req: &mut serde_json::Value) -> Option<String> {
+	req.get_mut("params")
+		.and_then(|p| p.as_object_mut())
+		.and_then(|p| p.remove(TOTP_CODE_PARAM))
+		.and_then(|c| c.as_str().map(|c| c.to_owned()))
+}
+
+/// The TOTP code given for a spend in the request's header, if any
+fn header_totp_code(req: &Request<Body>) -> Option<String> {
+	req.headers()
+		.get(TOTP_CODE_HEADER)
+		.and_then(|c| c.to_str().ok())
+		.map(|c| c.to_owned())
+}
+
+/// JSON-RPC response to a request refused for being outside a token's scope, on
+/// a read-only listener, or for a wallet the listener doesn't serve, in the same form as an error
+/// returned by the method itself
+pub(crate) fn scope_error_response(req: &serde_json::Value, msg: String) -> serde_json::Value {
+	serde_json::json!({
+		"id": req.get("id"),
+		"jsonrpc": "2.0",
+		"result": {
+			"Err": ErrorKind::GenericError(msg),
+		},
+	})
+}
+
 fn create_error_response(e: Error) -> Response<Body> {
 	Response::builder()
 		.status(StatusCode::INTERNAL_SERVER_ERROR)
-		.header("access-control-allow-origin", "*")
-		.header(
-			"access-control-allow-headers",
-			"Content-Type, Authorization",
-		)
 		.body(format!("{}", e).into())
 		.unwrap()
 }
@@ -477,11 +1357,6 @@ fn create_error_response(e: Error) -> Response<Body> {
 fn create_ok_response(json: &str) -> Response<Body> {
 	Response::builder()
 		.status(StatusCode::OK)
-		.header("access-control-allow-origin", "*")
-		.header(
-			"access-control-allow-headers",
-			"Content-Type, Authorization",
-		)
 		.header(hyper::header::CONTENT_TYPE, "application/json")
 		.body(json.to_string().into())
 		.unwrap()
@@ -494,13 +1369,7 @@ fn create_ok_response(json: &str) -> Response<Body> {
 fn response<T: Into<Body>>(status: StatusCode, text: T) -> Response<Body> {
 	let mut builder = &mut Response::builder();
 
-	builder = builder
-		.status(status)
-		.header("access-control-allow-origin", "*")
-		.header(
-			"access-control-allow-headers",
-			"Content-Type, Authorization",
-		);
+	builder = builder.status(status);
 
 	if status == StatusCode::OK {
 		builder = builder.header(hyper::header::CONTENT_TYPE, "application/json");
@@ -509,7 +1378,7 @@ fn response<T: Into<Body>>(status: StatusCode, text: T) -> Response<Body> {
 	builder.body(text.into()).unwrap()
 }
 
-fn parse_body<T>(req: Request<Body>) -> Box<dyn Future<Item = T, Error = Error> + Send>
+pub(crate) fn parse_body<T>(req: Request<Body>) -> Box<dyn Future<Item = T, Error = Error> + Send>
 where
 	for<'de> T: Deserialize<'de> + Send + 'static,
 {
@@ -14,8 +14,10 @@
 
 use crate::core::core::{self, amount_to_hr_string};
 use crate::core::global;
+use crate::impls::{render_qr, slate_to_qr_parts, PublicNodeHealth};
 use crate::libwallet::{
-	AcctPathMapping, Error, OutputCommitMapping, OutputStatus, TxLogEntry, WalletInfo,
+	AcctPathMapping, Error, OutputCommitMapping, OutputStatus, ParticipantMessages, Slate,
+	TxExportEntry, TxLogEntry, WalletInfo,
 };
 use crate::util;
 use prettytable;
@@ -161,6 +163,7 @@ pub fn txs(
 		bMG->"Fee",
 		bMG->"Net \nDifference",
 		bMG->"Tx \nData",
+		bMG->"Label",
 	]);
 
 	for t in txs {
@@ -169,7 +172,10 @@ pub fn txs(
 			Some(m) => format!("{}", m),
 			None => "None".to_owned(),
 		};
-		let entry_type = format!("{}", t.tx_type);
+		let entry_type = match t.expired {
+			true => format!("{} (Expired)", t.tx_type),
+			false => format!("{}", t.tx_type),
+		};
 		let creation_ts = format!("{}", t.creation_ts.format("%Y-%m-%d %H:%M:%S"));
 		let confirmation_ts = match t.confirmation_ts {
 			Some(m) => format!("{}", m.format("%Y-%m-%d %H:%M:%S")),
@@ -196,6 +202,7 @@ pub fn txs(
 			Some(_) => "Yes".to_owned(),
 			None => "None".to_owned(),
 		};
+		let label = t.label.clone().unwrap_or_default();
 		if dark_background_color_scheme {
 			table.add_row(row![
 				bFC->id,
@@ -211,6 +218,7 @@ pub fn txs(
 				bFR->fee,
 				bFY->net_diff,
 				bFb->tx_data,
+				bFD->label,
 			]);
 		} else {
 			if t.confirmed {
@@ -228,6 +236,7 @@ pub fn txs(
 					bFD->fee,
 					bFG->net_diff,
 					bFB->tx_data,
+					bFD->label,
 				]);
 			} else {
 				table.add_row(row![
@@ -244,6 +253,7 @@ pub fn txs(
 					bFD->fee,
 					bFG->net_diff,
 					bFB->tx_data,
+					bFD->label,
 				]);
 			}
 		}
@@ -413,6 +423,71 @@ pub fn accounts(acct_mappings: Vec<AcctPathMapping>) {
 	println!();
 }
 
+/// Display the health of public nodes, best first, marking the node in use
+pub fn public_nodes(health: &[PublicNodeHealth], current: &str) {
+	println!("\n____ Public Nodes ____\n",);
+	let mut table = table!();
+
+	table.set_titles(row![
+		mMG->"",
+		bMG->"URL",
+		bMG->"Name",
+		bMG->"Height",
+		bMG->"Latency (ms)",
+		bMG->"Score",
+	]);
+	for h in health {
+		let in_use = if h.node.url == current { "*" } else { "" };
+		let height = match h.chain_height {
+			Some(height) => height.to_string(),
+			None => "unreachable".to_owned(),
+		};
+		table.add_row(row![
+			bFG->in_use,
+			bFC->h.node.url,
+			bFD->h.node.name.clone().unwrap_or_default(),
+			bFB->height,
+			bFD->h.latency_ms,
+			bFY->h.score,
+		]);
+	}
+	table.set_format(*prettytable::format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+	table.printstd();
+	println!();
+}
+
+/// Display the messages opened from the escrowed memos of exported transactions
+pub fn escrowed_memos(memos: &[(TxExportEntry, ParticipantMessages)]) {
+	println!("\n____ Transaction Memos ____\n",);
+	let mut table = table!();
+
+	table.set_titles(row![
+		bMG->"Id",
+		bMG->"Shared Transaction Id",
+		bMG->"Creation Time",
+		bMG->"Participant Id",
+		bMG->"Message",
+	]);
+	for (tx, msgs) in memos {
+		let slate_id = match tx.tx_slate_id {
+			Some(id) => id.to_string(),
+			None => "None".to_owned(),
+		};
+		for m in msgs.messages.iter() {
+			table.add_row(row![
+				bFC->tx.id,
+				bFC->slate_id,
+				bFB->tx.creation_ts.format("%Y-%m-%d %H:%M:%S"),
+				bFD->m.id,
+				bFY->m.message.clone().unwrap_or_else(|| "None".to_owned()),
+			]);
+		}
+	}
+	table.set_format(*prettytable::format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+	table.printstd();
+	println!();
+}
+
 /// Display transaction log messages
 pub fn tx_messages(tx: &TxLogEntry, dark_background_color_scheme: bool) -> Result<(), Error> {
 	let title = format!("Transaction Messages - Transaction '{}'", tx.id,);
@@ -490,3 +565,18 @@ pub fn tx_messages(tx: &TxLogEntry, dark_background_color_scheme: bool) -> Resul
 
 	Ok(())
 }
+
+/// Display a slate as QR codes, more than one for a large slate, to be scanned
+/// by a mobile wallet
+pub fn slate_qr(slate: &Slate) -> Result<(), Error> {
+	let parts = slate_to_qr_parts(slate)?;
+	for (i, part) in parts.iter().enumerate() {
+		println!();
+		if parts.len() > 1 {
+			println!("QR code {} of {}:", i + 1, parts.len());
+			println!();
+		}
+		println!("{}", render_qr(part)?);
+	}
+	Ok(())
+}
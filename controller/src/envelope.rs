@@ -0,0 +1,124 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Optional response envelope, adding a `meta` member to each JSON-RPC
+//! response with the API version, server time and any deprecation warnings
+//! for the method called. JSON-RPC clients ignore members they don't expect,
+//! so enabling the envelope doesn't break existing clients
+
+use chrono::prelude::{DateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A method, or a whole API, slated for removal
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Deprecation {
+	/// Method the deprecation applies to
+	pub method: String,
+	/// What to use instead
+	pub message: String,
+	/// Wallet version in which the method is expected to be removed
+	pub removal_version: String,
+}
+
+/// Metadata added to each response when the envelope is enabled
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ResponseMeta {
+	/// Name of the API answering, e.g. `owner`
+	pub api: String,
+	/// Version of the API answering
+	pub api_version: u16,
+	/// Version of the wallet serving the API
+	pub wallet_version: String,
+	/// Time the response was produced
+	pub server_time: DateTime<Utc>,
+	/// Deprecation warnings for the method called
+	pub deprecations: Vec<Deprecation>,
+}
+
+/// Known deprecations, as (api, api version, method or `*` for every method,
+/// message, removal version)
+const DEPRECATIONS: &[(&str, u16, &str, &str, &str)] = &[(
+	"owner",
+	2,
+	"*",
+	"The V2 Owner API is deprecated in favour of the secure V3 Owner API at /v3/owner",
+	"4.0.0",
+)];
+
+/// Deprecation warnings for a method of the given API
+pub fn deprecations(api: &str, api_version: u16, method: &str) -> Vec<Deprecation> {
+	DEPRECATIONS
+		.iter()
+		.filter(|d| d.0 == api && d.1 == api_version && (d.2 == "*" || d.2 == method))
+		.map(|d| Deprecation {
+			method: method.to_owned(),
+			message: d.3.to_owned(),
+			removal_version: d.4.to_owned(),
+		})
+		.collect()
+}
+
+/// Add the envelope's `meta` member to a response, or to each response of a
+/// batch, using the request to find the method each response answers
+pub fn add_envelope(api: &str, api_version: u16, request: &Value, response: Value) -> Value {
+	let server_time = Utc::now();
+	let meta_for = |id: &Value| {
+		let method = match request {
+			Value::Array(reqs) => reqs.iter().find(|r| &r["id"] == id),
+			r => Some(r),
+		}
+		.and_then(|r| r["method"].as_str())
+		.unwrap_or("");
+		ResponseMeta {
+			api: api.to_owned(),
+			api_version,
+			wallet_version: env!("CARGO_PKG_VERSION").to_owned(),
+			server_time,
+			deprecations: deprecations(api, api_version, method),
+		}
+	};
+	let wrap = |mut resp: Value| {
+		let meta = serde_json::to_value(meta_for(&resp["id"])).unwrap_or(Value::Null);
+		if let Value::Object(ref mut o) = resp {
+			o.insert("meta".to_owned(), meta);
+		}
+		resp
+	};
+	match response {
+		Value::Array(resps) => Value::Array(resps.into_iter().map(wrap).collect()),
+		resp => wrap(resp),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use serde_json::json;
+
+	#[test]
+	fn envelope_adds_meta_with_deprecations() {
+		let req = json!({"jsonrpc": "2.0", "id": 1, "method": "retrieve_summary_info"});
+		let resp = json!({"jsonrpc": "2.0", "id": 1, "result": {"Ok": null}});
+		let wrapped = add_envelope("owner", 2, &req, resp.clone());
+		assert_eq!(wrapped["meta"]["api_version"], json!(2));
+		assert_eq!(
+			wrapped["meta"]["deprecations"][0]["method"],
+			json!("retrieve_summary_info")
+		);
+		let wrapped = add_envelope("owner", 3, &req, resp);
+		assert_eq!(wrapped["meta"]["deprecations"], json!([]));
+		assert_eq!(wrapped["result"], json!({"Ok": null}));
+	}
+}
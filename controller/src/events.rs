@@ -0,0 +1,184 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Server-sent events following a background operation, for clients that
+//! can't poll `operation_status`
+//!
+//! `GET /v3/operations/<id>/events` streams a `status` event holding the
+//! operation's status, then a `progress` event each time its scan of the UTXO
+//! set moves on and a `status` event each time its state changes. The stream
+//! ends once the operation finishes. Operations started through the V3 API
+//! are only followed when the session's token is given in the `X-Grin-Token`
+//! header, kept out of the URL so it isn't logged by proxies or kept in
+//! browser histories.
+
+use crate::api::{self, ResponseFuture};
+use crate::apiwallet::{OperationEvent, OperationRunner};
+use crate::auth::TokenScope;
+use crate::util::secp::key::SecretKey;
+use crate::util::{self, Mutex};
+use futures::future::ok;
+use futures::sync::mpsc;
+use futures::Stream;
+use hyper::{Body, Request, Response, StatusCode};
+use std::io;
+use std::sync::Arc;
+
+/// Route events are served on
+pub const OPERATION_EVENTS_ROUTE: &str = "/v3/operations/*/events";
+
+/// Header the session's token is given in
+pub const TOKEN_HEADER: &str = "X-Grin-Token";
+
+/// Handler streaming the events of background operations
+pub struct OperationEventsHandler {
+	operations: Arc<OperationRunner>,
+}
+
+impl OperationEventsHandler {
+	/// Create a handler following the given runner's operations
+	pub fn new(operations: Arc<OperationRunner>) -> OperationEventsHandler {
+		OperationEventsHandler { operations }
+	}
+}
+
+impl api::Handler for OperationEventsHandler {
+	fn get(&self, req: Request<Body>) -> ResponseFuture {
+		let scope = req.extensions().get::<TokenScope>();
+		if scope.map_or(false, |s| s.account.is_some()) {
+			return text_response(
+				StatusCode::FORBIDDEN,
+				"Operations are not available to a token scoped to an account",
+			);
+		}
+		// only the listener's own wallet's operations are streamed
+		if let Some(Err(e)) = scope.map(|s| s.check_wallet(None)) {
+			return text_response(StatusCode::FORBIDDEN, &e);
+		}
+		let id = match operation_id(req.uri().path()) {
+			Some(id) => id,
+			None => return text_response(StatusCode::BAD_REQUEST, "Invalid operation id"),
+		};
+		let token = req
+			.headers()
+			.get(TOKEN_HEADER)
+			.map(|h| h.to_str().unwrap_or_default());
+		let keychain_mask = match keychain_mask(token) {
+			Ok(m) => m,
+			Err(e) => return text_response(StatusCode::BAD_REQUEST, &e),
+		};
+
+		let (tx, rx) = mpsc::unbounded();
+		// dropped once the operation finishes, ending the stream
+		let tx = Mutex::new(Some(tx));
+		let followed = self
+			.operations
+			.follow(id, keychain_mask.as_ref(), move |event| {
+				let mut tx = tx.lock();
+				let finished = match &event {
+					OperationEvent::Status(s) => s.state.is_finished(),
+					OperationEvent::Progress(_) => false,
+				};
+				let sent = tx
+					.as_ref()
+					.map_or(false, |t| t.unbounded_send(sse_event(&event)).is_ok());
+				if finished {
+					*tx = None;
+				}
+				sent && !finished
+			});
+		if !followed {
+			return text_response(StatusCode::NOT_FOUND, "Unknown operation");
+		}
+
+		let events = rx.map_err(|_| io::Error::new(io::ErrorKind::Other, "event stream failed"));
+		Box::new(ok(Response::builder()
+			.status(StatusCode::OK)
+			.header(hyper::header::CONTENT_TYPE, "text/event-stream")
+			.header(hyper::header::CACHE_CONTROL, "no-cache")
+			.body(Body::wrap_stream(events))
+			.unwrap()))
+	}
+}
+
+/// The id in a path of the form `/v3/operations/<id>/events`
+fn operation_id(path: &str) -> Option<u32> {
+	path.trim_end_matches('/')
+		.rsplit('/')
+		.nth(1)
+		.and_then(|id| id.parse().ok())
+}
+
+/// The keychain mask given as the hex token, if any
+fn keychain_mask(token: Option<&str>) -> Result<Option<SecretKey>, String> {
+	let token = match token {
+		Some(t) => t,
+		None => return Ok(None),
+	};
+	let secp = util::static_secp_instance();
+	let secp = secp.lock();
+	util::from_hex(token.to_owned())
+		.ok()
+		.and_then(|b| SecretKey::from_slice(&secp, &b).ok())
+		.map(Some)
+		.ok_or_else(|| "Invalid token".to_owned())
+}
+
+/// An event in the server-sent events format
+fn sse_event(event: &OperationEvent) -> String {
+	let (name, data) = match event {
+		OperationEvent::Status(s) => ("status", serde_json::to_string(s)),
+		OperationEvent::Progress(p) => ("progress", serde_json::to_string(p)),
+	};
+	format!("event: {}\ndata: {}\n\n", name, data.unwrap_or_default())
+}
+
+fn text_response(status: StatusCode, text: &str) -> ResponseFuture {
+	Box::new(ok(Response::builder()
+		.status(status)
+		.body(text.to_owned().into())
+		.unwrap()))
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::libwallet::ScanProgress;
+
+	#[test]
+	fn parses_requests() {
+		assert_eq!(operation_id("/v3/operations/12/events"), Some(12));
+		assert_eq!(operation_id("/v3/operations/12/events/"), Some(12));
+		assert_eq!(operation_id("/v3/operations/x/events"), None);
+
+		assert_eq!(keychain_mask(None), Ok(None));
+		let token = "d202964900000000d302964900000000d402964900000000d502964900000000";
+		let mask = keychain_mask(Some(token)).unwrap();
+		assert_eq!(util::to_hex(mask.unwrap().0.to_vec()), token);
+		assert!(keychain_mask(Some("zz")).is_err());
+		assert!(keychain_mask(Some("")).is_err());
+
+		let progress = ScanProgress {
+			scanned: 10,
+			scan_total: 20,
+			height: 5,
+			outputs_found: 1,
+		};
+		assert_eq!(
+			sse_event(&OperationEvent::Progress(progress)),
+			"event: progress\ndata: {\"scanned\":10,\"scan_total\":20,\"height\":5,\
+			 \"outputs_found\":1}\n\n"
+		);
+	}
+}
@@ -0,0 +1,737 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Read-only GraphQL queries over the wallet's accounts, transactions and
+//! outputs, served at /v3/graphql alongside the Owner API. Queries read what
+//! the wallet has stored, without refreshing from the node, through the same
+//! retrieval as the Owner API's `retrieve_txs` and `query_outputs`. Like V3 calls,
+//! a query gives the wallet's session token as its `token` member, and the
+//! hosted wallet it's for, if any, as its `wallet_id` member.
+//!
+//! Amounts are given as strings of nanogrins, as they may not fit in
+//! GraphQL's 32 bit integers.
+
+use crate::api::{self, ResponseFuture};
+use crate::apiwallet::{CallPriority, Owner};
+use crate::auth::{caller_identity, TokenScope};
+use crate::controller::{parse_body, OwnerHandlerConfig};
+use crate::core::libtx::secp_ser;
+use crate::keychain::{Identifier, Keychain};
+use crate::libwallet::{
+	AcctPathMapping, Error, NodeClient, OutputCommitMapping, OutputStatus,
+	RetrieveOutputsQueryArgs, RetrieveTxQueryArgs, TxLogEntry, TxLogEntryType, TxQueryStatus,
+	TxSortOrder, WalletInst, WalletLCProvider,
+};
+use crate::util::secp::key::SecretKey;
+use crate::util::{self, Mutex};
+use chrono::{DateTime, Utc};
+use futures::future::{ok, Future};
+use hyper::{Body, Request, Response, StatusCode};
+use juniper::http::GraphQLRequest;
+use juniper::{EmptyMutation, FieldError, FieldResult, RootNode};
+use serde_derive::Deserialize;
+use std::sync::Arc;
+
+/// Wallet retrieval the resolvers are layered on
+trait WalletQueries {
+	fn accounts(&self) -> Result<Vec<AcctPathMapping>, Error>;
+	fn txs(&self, query: RetrieveTxQueryArgs) -> Result<Vec<TxLogEntry>, Error>;
+	fn outputs(&self, query: RetrieveOutputsQueryArgs) -> Result<Vec<OutputCommitMapping>, Error>;
+}
+
+struct OwnerQueries<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	owner: Owner<'static, L, C, K>,
+	keychain_mask: Option<SecretKey>,
+}
+
+impl<L, C, K> WalletQueries for OwnerQueries<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	fn accounts(&self) -> Result<Vec<AcctPathMapping>, Error> {
+		self.owner.accounts(self.keychain_mask.as_ref())
+	}
+
+	fn txs(&self, query: RetrieveTxQueryArgs) -> Result<Vec<TxLogEntry>, Error> {
+		let mask = self.keychain_mask.as_ref();
+		Ok(self
+			.owner
+			.retrieve_txs(mask, false, None, None, Some(query))?
+			.1)
+	}
+
+	fn outputs(&self, query: RetrieveOutputsQueryArgs) -> Result<Vec<OutputCommitMapping>, Error> {
+		let mask = self.keychain_mask.as_ref();
+		Ok(self.owner.query_outputs(mask, false, query)?.1)
+	}
+}
+
+/// Context of a GraphQL query
+pub struct Context {
+	wallet: Box<dyn WalletQueries>,
+	scope: TokenScope,
+}
+
+impl juniper::Context for Context {}
+
+impl Context {
+	/// The account to query, checked against the scope of the request's
+	/// token. A scoped token queries its own account when none is given,
+	/// otherwise the active account is queried
+	fn account(&self, account: Option<String>) -> FieldResult<Option<String>> {
+		match (&self.scope.account, account) {
+			(Some(s), Some(a)) if *s != a => Err(FieldError::from(format!(
+				"Account {} is not available to a token scoped to account {}",
+				a, s
+			))),
+			(Some(s), _) => Ok(Some(s.clone())),
+			(None, a) => Ok(a),
+		}
+	}
+
+	fn accounts(&self) -> FieldResult<Vec<Account>> {
+		Ok(self
+			.wallet
+			.accounts()?
+			.into_iter()
+			.filter(|a| match &self.scope.account {
+				Some(s) => *s == a.label,
+				None => true,
+			})
+			.map(|a| Account {
+				label: a.label,
+				path: a.path,
+			})
+			.collect())
+	}
+
+	/// Label of the account with the given path
+	fn account_label(&self, path: &Identifier) -> FieldResult<Option<String>> {
+		Ok(self
+			.accounts()?
+			.into_iter()
+			.find(|a| a.path == *path)
+			.map(|a| a.label))
+	}
+
+	fn txs(&self, account: Option<String>, filter: Option<TxFilter>) -> FieldResult<Vec<Tx>> {
+		let mut query = filter.map(|f| f.into_query()).unwrap_or_default();
+		query.account = self.account(account)?;
+		Ok(self.wallet.txs(query)?.into_iter().map(Tx).collect())
+	}
+
+	fn outputs(
+		&self,
+		account: Option<String>,
+		filter: Option<OutputFilter>,
+	) -> FieldResult<Vec<Output>> {
+		let mut query = filter.map(|f| f.into_query()).unwrap_or_default();
+		query.account = self.account(account)?;
+		Ok(self
+			.wallet
+			.outputs(query)?
+			.into_iter()
+			.map(Output)
+			.collect())
+	}
+}
+
+/// Schema served at /v3/graphql
+pub type Schema = RootNode<'static, Query, EmptyMutation<Context>>;
+
+/// Create the schema
+pub fn schema() -> Schema {
+	Schema::new(Query, EmptyMutation::new())
+}
+
+/// Root of all queries
+pub struct Query;
+
+#[juniper::object(Context = Context)]
+impl Query {
+	/// The wallet's accounts
+	fn accounts(context: &Context) -> FieldResult<Vec<Account>> {
+		context.accounts()
+	}
+
+	/// The account with the given label, if any
+	fn account(context: &Context, label: String) -> FieldResult<Option<Account>> {
+		context.account(Some(label.clone()))?;
+		Ok(context.accounts()?.into_iter().find(|a| a.label == label))
+	}
+
+	/// Transactions of the given account, or of the active one
+	fn txs(
+		context: &Context,
+		account: Option<String>,
+		filter: Option<TxFilter>,
+	) -> FieldResult<Vec<Tx>> {
+		context.txs(account, filter)
+	}
+
+	/// Outputs of the given account, or of the active one
+	fn outputs(
+		context: &Context,
+		account: Option<String>,
+		filter: Option<OutputFilter>,
+	) -> FieldResult<Vec<Output>> {
+		context.outputs(account, filter)
+	}
+}
+
+/// An account
+pub struct Account {
+	label: String,
+	path: Identifier,
+}
+
+#[juniper::object(Context = Context)]
+impl Account {
+	/// Human readable label
+	fn label(&self) -> &str {
+		&self.label
+	}
+
+	/// Derivation path, hex encoded
+	fn path(&self) -> String {
+		self.path.to_hex()
+	}
+
+	/// The account's transactions
+	fn txs(&self, context: &Context, filter: Option<TxFilter>) -> FieldResult<Vec<Tx>> {
+		context.txs(Some(self.label.clone()), filter)
+	}
+
+	/// The account's outputs
+	fn outputs(&self, context: &Context, filter: Option<OutputFilter>) -> FieldResult<Vec<Output>> {
+		context.outputs(Some(self.label.clone()), filter)
+	}
+}
+
+/// Transaction status, as filtered on by the Owner API's `retrieve_txs`
+#[derive(juniper::GraphQLEnum, Clone, Copy)]
+pub enum TxStatus {
+	/// Sent or received transactions that are not yet confirmed
+	Unconfirmed,
+	/// Confirmed transactions, including coinbase
+	Confirmed,
+	/// Sent or received transactions that were cancelled
+	Cancelled,
+	/// Sent transactions, including cancelled ones
+	Sent,
+	/// Received transactions, including cancelled ones
+	Received,
+	/// Confirmed coinbase transactions
+	Coinbase,
+}
+
+/// Filter on transactions
+#[derive(juniper::GraphQLInputObject)]
+pub struct TxFilter {
+	/// Number of matching transactions to skip
+	offset: Option<i32>,
+	/// Maximum number of transactions to return
+	limit: Option<i32>,
+	/// Only return transactions with any of these statuses
+	statuses: Option<Vec<TxStatus>>,
+	/// Only return transactions created at or after this time
+	min_creation_ts: Option<DateTime<Utc>>,
+	/// Only return transactions created at or before this time
+	max_creation_ts: Option<DateTime<Utc>>,
+	/// Return the newest transactions first
+	newest_first: Option<bool>,
+}
+
+impl TxFilter {
+	fn into_query(self) -> RetrieveTxQueryArgs {
+		RetrieveTxQueryArgs {
+			offset: self.offset.map(|o| o.max(0) as u32),
+			limit: self.limit.map(|l| l.max(0) as u32),
+			statuses: self.statuses.map(|s| {
+				s.into_iter()
+					.map(|s| match s {
+						TxStatus::Unconfirmed => TxQueryStatus::Unconfirmed,
+						TxStatus::Confirmed => TxQueryStatus::Confirmed,
+						TxStatus::Cancelled => TxQueryStatus::Cancelled,
+						TxStatus::Sent => TxQueryStatus::Sent,
+						TxStatus::Received => TxQueryStatus::Received,
+						TxStatus::Coinbase => TxQueryStatus::Coinbase,
+					})
+					.collect()
+			}),
+			min_creation_ts: self.min_creation_ts,
+			max_creation_ts: self.max_creation_ts,
+			sort_order: match self.newest_first {
+				Some(true) => Some(TxSortOrder::Desc),
+				_ => None,
+			},
+			account: None,
+		}
+	}
+}
+
+/// Type of a transaction log entry
+#[derive(juniper::GraphQLEnum)]
+pub enum TxType {
+	/// A coinbase transaction becomes confirmed
+	ConfirmedCoinbase,
+	/// Outputs created when a transaction is received
+	TxReceived,
+	/// Inputs locked and change outputs created when a transaction is sent
+	TxSent,
+	/// Received transaction that was cancelled
+	TxReceivedCancelled,
+	/// Sent transaction that was cancelled
+	TxSentCancelled,
+}
+
+/// A transaction log entry
+pub struct Tx(TxLogEntry);
+
+#[juniper::object(Context = Context)]
+impl Tx {
+	/// Local id
+	fn id(&self) -> i32 {
+		self.0.id as i32
+	}
+
+	/// Id shared with the other parties to the transaction
+	fn slate_id(&self) -> Option<String> {
+		self.0.tx_slate_id.map(|id| id.to_string())
+	}
+
+	fn tx_type(&self) -> TxType {
+		match self.0.tx_type {
+			TxLogEntryType::ConfirmedCoinbase => TxType::ConfirmedCoinbase,
+			TxLogEntryType::TxReceived => TxType::TxReceived,
+			TxLogEntryType::TxSent => TxType::TxSent,
+			TxLogEntryType::TxReceivedCancelled => TxType::TxReceivedCancelled,
+			TxLogEntryType::TxSentCancelled => TxType::TxSentCancelled,
+		}
+	}
+
+	fn creation_ts(&self) -> DateTime<Utc> {
+		self.0.creation_ts
+	}
+
+	fn confirmation_ts(&self) -> Option<DateTime<Utc>> {
+		self.0.confirmation_ts
+	}
+
+	fn confirmed(&self) -> bool {
+		self.0.confirmed
+	}
+
+	/// Chain height when the transaction was created, if known
+	fn creation_height(&self) -> Option<i32> {
+		self.0.creation_height.map(|h| h as i32)
+	}
+
+	fn num_inputs(&self) -> i32 {
+		self.0.num_inputs as i32
+	}
+
+	fn num_outputs(&self) -> i32 {
+		self.0.num_outputs as i32
+	}
+
+	/// Amount credited, in nanogrins
+	fn amount_credited(&self) -> String {
+		self.0.amount_credited.to_string()
+	}
+
+	/// Amount debited, in nanogrins
+	fn amount_debited(&self) -> String {
+		self.0.amount_debited.to_string()
+	}
+
+	/// Fee, in nanogrins
+	fn fee(&self) -> Option<String> {
+		self.0.fee.map(|f| f.to_string())
+	}
+
+	fn label(&self) -> Option<&str> {
+		self.0.label.as_ref().map(|l| l.as_str())
+	}
+
+	/// Whether the transaction passed its TTL without confirming
+	fn expired(&self) -> bool {
+		self.0.expired
+	}
+
+	/// The account the transaction belongs to
+	fn account(&self, context: &Context) -> FieldResult<Option<Account>> {
+		let path = self.0.parent_key_id.clone();
+		Ok(context
+			.account_label(&path)?
+			.map(|label| Account { label, path }))
+	}
+
+	/// Outputs created or spent by the transaction
+	fn outputs(&self, context: &Context) -> FieldResult<Vec<Output>> {
+		let account = context.account_label(&self.0.parent_key_id)?;
+		Ok(context
+			.outputs(account, None)?
+			.into_iter()
+			.filter(|o| (o.0).output.tx_log_entry == Some(self.0.id))
+			.collect())
+	}
+}
+
+/// Status of an output
+#[derive(juniper::GraphQLEnum, Clone, Copy)]
+#[graphql(name = "OutputStatus")]
+pub enum OutputState {
+	/// Unconfirmed
+	Unconfirmed,
+	/// Unspent
+	Unspent,
+	/// Locked
+	Locked,
+	/// Spent
+	Spent,
+}
+
+impl From<OutputState> for OutputStatus {
+	fn from(s: OutputState) -> OutputStatus {
+		match s {
+			OutputState::Unconfirmed => OutputStatus::Unconfirmed,
+			OutputState::Unspent => OutputStatus::Unspent,
+			OutputState::Locked => OutputStatus::Locked,
+			OutputState::Spent => OutputStatus::Spent,
+		}
+	}
+}
+
+impl From<&OutputStatus> for OutputState {
+	fn from(s: &OutputStatus) -> OutputState {
+		match s {
+			OutputStatus::Unconfirmed => OutputState::Unconfirmed,
+			OutputStatus::Unspent => OutputState::Unspent,
+			OutputStatus::Locked => OutputState::Locked,
+			OutputStatus::Spent => OutputState::Spent,
+		}
+	}
+}
+
+/// Filter on outputs
+#[derive(juniper::GraphQLInputObject)]
+pub struct OutputFilter {
+	/// Only return outputs with any of these statuses
+	statuses: Option<Vec<OutputState>>,
+	/// Only return outputs with a block height at or above this height
+	min_height: Option<i32>,
+	/// Only return outputs with a block height at or below this height
+	max_height: Option<i32>,
+}
+
+impl OutputFilter {
+	fn into_query(self) -> RetrieveOutputsQueryArgs {
+		RetrieveOutputsQueryArgs {
+			statuses: self
+				.statuses
+				.map(|s| s.into_iter().map(OutputStatus::from).collect()),
+			min_height: self.min_height.map(|h| h.max(0) as u64),
+			max_height: self.max_height.map(|h| h.max(0) as u64),
+			account: None,
+		}
+	}
+}
+
+/// An output
+pub struct Output(OutputCommitMapping);
+
+#[juniper::object(Context = Context)]
+impl Output {
+	/// Commitment, hex encoded
+	fn commit(&self) -> String {
+		util::to_hex(self.0.commit.as_ref().to_vec())
+	}
+
+	/// Key id, hex encoded
+	fn key_id(&self) -> String {
+		self.0.output.key_id.to_hex()
+	}
+
+	fn n_child(&self) -> i32 {
+		self.0.output.n_child as i32
+	}
+
+	/// Value, in nanogrins
+	fn value(&self) -> String {
+		self.0.output.value.to_string()
+	}
+
+	fn status(&self) -> OutputState {
+		OutputState::from(&self.0.output.status)
+	}
+
+	/// Height of the block the output was created in
+	fn height(&self) -> i32 {
+		self.0.output.height as i32
+	}
+
+	/// Height from which the output can be spent
+	fn lock_height(&self) -> i32 {
+		self.0.output.lock_height as i32
+	}
+
+	fn is_coinbase(&self) -> bool {
+		self.0.output.is_coinbase
+	}
+
+	/// Position in the output MMR, once confirmed
+	fn mmr_index(&self) -> Option<String> {
+		self.0.output.mmr_index.map(|i| i.to_string())
+	}
+
+	/// The transaction that created the output
+	fn tx(&self, context: &Context) -> FieldResult<Option<Tx>> {
+		let id = match self.0.output.tx_log_entry {
+			Some(id) => id,
+			None => return Ok(None),
+		};
+		let account = context.account_label(&self.0.output.root_key_id)?;
+		Ok(context
+			.txs(account, None)?
+			.into_iter()
+			.find(|t| t.0.id == id))
+	}
+}
+
+/// A GraphQL request, along with the token of the wallet's session (see the
+/// V3 Owner API's `open_wallet`), given as `token` as in V3 calls
+#[derive(Deserialize)]
+struct WalletGraphQLRequest {
+	#[serde(flatten)]
+	request: GraphQLRequest,
+	#[serde(default, with = "secp_ser::option_seckey_serde")]
+	token: Option<SecretKey>,
+}
+
+/// Serves GraphQL queries at /v3/graphql
+pub struct GraphQLHandler<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	/// Wallet instance
+	pub wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+	/// What each query's Owner API is given, and how queries are served
+	pub config: OwnerHandlerConfig,
+}
+
+impl<L, C, K> GraphQLHandler<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	/// Create a new GraphQL handler
+	pub fn new(
+		wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+		config: OwnerHandlerConfig,
+	) -> GraphQLHandler<L, C, K> {
+		GraphQLHandler { wallet, config }
+	}
+}
+
+impl<L, C, K> api::Handler for GraphQLHandler<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	fn post(&self, req: Request<Body>) -> ResponseFuture {
+		if let Err(e) = self.config.check_token_wallet(&req) {
+			return Box::new(ok(Response::builder()
+				.status(StatusCode::FORBIDDEN)
+				.body(e.into())
+				.unwrap()));
+		}
+		let owner = self.config.owner_api(self.wallet.clone());
+		let call_queue = self.config.call_queue.clone();
+		let audit_log = self.config.audit_log.clone();
+		let caller = caller_identity(&req, &self.config.trusted_proxies);
+		let scope = req
+			.extensions()
+			.get::<TokenScope>()
+			.cloned()
+			.unwrap_or_default();
+		Box::new(
+			parse_body(req)
+				.and_then(move |request: WalletGraphQLRequest| {
+					let context = Context {
+						wallet: Box::new(OwnerQueries {
+							owner,
+							keychain_mask: request.token,
+						}),
+						scope,
+					};
+					let response = {
+						let _turn = call_queue.wait_turn(CallPriority::Interactive);
+						request.request.execute(&schema(), &context)
+					};
+					let status = match response.is_ok() {
+						true => StatusCode::OK,
+						false => StatusCode::BAD_REQUEST,
+					};
+					let body = serde_json::to_value(&response).unwrap_or_default();
+					if let Some(l) = audit_log.as_ref() {
+						let params = serde_json::to_value(&request.request).unwrap_or_default();
+						let result = match body.get("errors") {
+							Some(e) => format!("error: {}", e),
+							None => "ok".to_owned(),
+						};
+						if let Err(e) = l.record("graphql", "query", &params, &caller, &result) {
+							error!("Unable to record GraphQL query in the audit log: {}", e);
+						}
+					}
+					ok(Response::builder()
+						.status(status)
+						.header(hyper::header::CONTENT_TYPE, "application/json")
+						.body(body.to_string().into())
+						.unwrap())
+				})
+				.or_else(|e| {
+					ok(Response::builder()
+						.status(StatusCode::BAD_REQUEST)
+						.body(format!("{}", e).into())
+						.unwrap())
+				}),
+		)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::libwallet::OutputData;
+	use crate::util::secp::pedersen::Commitment;
+	use juniper::Variables;
+
+	/// A wallet with an account holding one received transaction and its output
+	struct TestWallet;
+
+	fn path(n: u32) -> Identifier {
+		crate::keychain::ExtKeychain::derive_key_id(2, n, 0, 0, 0)
+	}
+
+	impl WalletQueries for TestWallet {
+		fn accounts(&self) -> Result<Vec<AcctPathMapping>, Error> {
+			Ok(vec![
+				AcctPathMapping {
+					label: "default".to_owned(),
+					path: path(0),
+				},
+				AcctPathMapping {
+					label: "savings".to_owned(),
+					path: path(1),
+				},
+			])
+		}
+
+		fn txs(&self, query: RetrieveTxQueryArgs) -> Result<Vec<TxLogEntry>, Error> {
+			let mut tx = TxLogEntry::new(path(1), TxLogEntryType::TxReceived, 3);
+			tx.amount_credited = 60_000_000_000;
+			Ok(match query.account.as_ref().map(|a| a.as_str()) {
+				Some("savings") => vec![tx],
+				_ => vec![],
+			})
+		}
+
+		fn outputs(
+			&self,
+			query: RetrieveOutputsQueryArgs,
+		) -> Result<Vec<OutputCommitMapping>, Error> {
+			let output = OutputData {
+				root_key_id: path(1),
+				key_id: path(1),
+				n_child: 0,
+				commit: None,
+				mmr_index: None,
+				value: 60_000_000_000,
+				status: OutputStatus::Unspent,
+				height: 10,
+				lock_height: 0,
+				is_coinbase: false,
+				tx_log_entry: Some(3),
+			};
+			Ok(match query.account.as_ref().map(|a| a.as_str()) {
+				Some("savings") => vec![OutputCommitMapping {
+					output,
+					commit: Commitment::from_vec(vec![9; 33]),
+				}],
+				_ => vec![],
+			})
+		}
+	}
+
+	fn run(query: &str, account: Option<&str>) -> serde_json::Value {
+		let context = Context {
+			wallet: Box::new(TestWallet),
+			scope: TokenScope {
+				account: account.map(|a| a.to_owned()),
+				..Default::default()
+			},
+		};
+		let res = juniper::execute(query, None, &schema(), &Variables::new(), &context);
+		match res {
+			Ok((value, errors)) => {
+				let mut json = serde_json::to_value(&value).unwrap();
+				if !errors.is_empty() {
+					json["errors"] = serde_json::json!(errors.len());
+				}
+				json
+			}
+			Err(e) => panic!("query failed: {:?}", e),
+		}
+	}
+
+	#[test]
+	fn nested_queries() {
+		let res = run(
+			"{ accounts { label txs { id amountCredited outputs { value status tx { id } } } } }",
+			None,
+		);
+		assert_eq!(res["accounts"][0]["txs"], serde_json::json!([]));
+		let tx = &res["accounts"][1]["txs"][0];
+		assert_eq!(tx["id"], 3);
+		assert_eq!(tx["amountCredited"], "60000000000");
+		assert_eq!(tx["outputs"][0]["status"], "UNSPENT");
+		assert_eq!(tx["outputs"][0]["tx"]["id"], 3);
+
+		let res = run(
+			"{ outputs(account: \"savings\", filter: { statuses: [UNSPENT] }) \
+			 { tx { account { label } } } }",
+			None,
+		);
+		assert_eq!(res["outputs"][0]["tx"]["account"]["label"], "savings");
+
+		// a scoped token only sees its own account
+		let res = run("{ accounts { label } txs { id } }", Some("savings"));
+		assert_eq!(res["accounts"], serde_json::json!([{ "label": "savings" }]));
+		assert_eq!(res["txs"][0]["id"], 3);
+		let res = run("{ txs(account: \"default\") { id } }", Some("savings"));
+		assert_eq!(res["errors"], 1);
+	}
+}
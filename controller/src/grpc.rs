@@ -0,0 +1,712 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! gRPC server for the Owner and Foreign APIs, with the services and messages
+//! defined in proto/wallet.proto. Each method calls the API method of the same
+//! name, as the V2 JSON-RPC API does.
+
+use crate::apiwallet::{Foreign, Owner};
+use crate::controller::check_middleware;
+use crate::impls::WebhookDispatcher;
+use crate::keychain::Keychain;
+use crate::libwallet::{
+	AcctPathMapping, Error, ErrorKind, InitTxArgs, IssueInvoiceTxArgs, NodeClient,
+	OutputCommitMapping, OutputStatus, ReceiveLimits, Slate, SlateVersion, TxLogEntry,
+	TxLogEntryType, VersionedSlate, WalletInfo, WalletInst, WalletLCProvider,
+};
+use crate::util::secp::key::SecretKey;
+use crate::util::{self, to_base64, Mutex};
+use futures::{stream, Future, Sink};
+use grpcio::{
+	Environment, RpcContext, RpcStatus, RpcStatusCode, Server, ServerBuilder, ServerStreamingSink,
+	UnarySink, WriteFlags,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Code generated from proto/wallet.proto
+#[allow(missing_docs)]
+pub mod proto {
+	include!(concat!(env!("OUT_DIR"), "/grinwallet.rs"));
+}
+
+/// Starts serving the Owner API over gRPC, along with the Foreign API if
+/// `include_foreign` is set. The server runs until the returned handle is
+/// dropped. If `api_secret` is given, Owner calls must carry the same basic
+/// auth header as JSON-RPC requests, in their `authorization` metadata.
+/// Spends on a wallet with TOTP enabled must carry a code in their
+/// `totp-code` metadata.
+pub fn grpc_listener<L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+	keychain_mask: Option<SecretKey>,
+	addr: &str,
+	api_secret: Option<String>,
+	include_foreign: bool,
+	webhooks: Option<WebhookDispatcher>,
+	review_threshold: Option<u64>,
+	receive_limits: Option<ReceiveLimits>,
+) -> Result<Server, Error>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	let invalid_addr = || ErrorKind::GenericError(format!("Invalid gRPC address {}", addr));
+	let mut split = addr.rsplitn(2, ':');
+	let port = split
+		.next()
+		.and_then(|p| p.parse::<u16>().ok())
+		.ok_or_else(invalid_addr)?;
+	let host = split.next().ok_or_else(invalid_addr)?;
+
+	let auth = api_secret.map(|s| "Basic ".to_string() + &to_base64(&("grin:".to_string() + &s)));
+	let owner = GrpcOwner {
+		wallet: wallet.clone(),
+		auth,
+		webhooks: webhooks.clone(),
+	};
+	let mut builder = ServerBuilder::new(Arc::new(Environment::new(1)))
+		.register_service(proto::create_owner(owner));
+	if include_foreign {
+		warn!(
+			"Starting gRPC Foreign API on Owner gRPC server at {}.",
+			addr
+		);
+		let foreign = GrpcForeign {
+			wallet,
+			keychain_mask,
+			webhooks,
+			review_threshold,
+			receive_limits,
+		};
+		builder = builder.register_service(proto::create_foreign(foreign));
+	}
+	let mut server = builder.bind(host, port).build().map_err(|e| {
+		ErrorKind::GenericError(format!("Unable to start gRPC server at {}: {}", addr, e))
+	})?;
+	server.start();
+	warn!("gRPC Owner API listening on {}", addr);
+	Ok(server)
+}
+
+/// gRPC Owner service
+struct GrpcOwner<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+	/// Expected authorization header, if any
+	auth: Option<String>,
+	webhooks: Option<WebhookDispatcher>,
+}
+
+impl<L, C, K> Clone for GrpcOwner<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	fn clone(&self) -> Self {
+		GrpcOwner {
+			wallet: self.wallet.clone(),
+			auth: self.auth.clone(),
+			webhooks: self.webhooks.clone(),
+		}
+	}
+}
+
+impl<L, C, K> GrpcOwner<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	/// Checks the call is authorized, then calls the Owner API
+	fn call<T, F>(&self, ctx: &RpcContext, f: F) -> Result<T, RpcStatus>
+	where
+		F: FnOnce(&Owner<'static, L, C, K>) -> Result<T, Error>,
+	{
+		if let Some(auth) = self.auth.as_ref() {
+			let authorized = ctx
+				.request_headers()
+				.iter()
+				.any(|(k, v)| k.eq_ignore_ascii_case("authorization") && v == auth.as_bytes());
+			if !authorized {
+				return Err(RpcStatus::new(
+					RpcStatusCode::UNAUTHENTICATED,
+					Some("Invalid or missing authorization".to_owned()),
+				));
+			}
+		}
+		let mut api = Owner::new(self.wallet.clone());
+		api.set_webhooks(self.webhooks.clone());
+		api.require_totp(totp_code(ctx));
+		f(&api).map_err(status)
+	}
+}
+
+impl<L, C, K> proto::Owner for GrpcOwner<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	fn accounts(
+		&mut self,
+		ctx: RpcContext,
+		_req: proto::Empty,
+		sink: UnarySink<proto::AccountsResponse>,
+	) {
+		let res = self.call(&ctx, |api| {
+			Ok(proto::AccountsResponse {
+				accounts: api.accounts(None)?.iter().map(acct_path_mapping).collect(),
+			})
+		});
+		reply(&ctx, sink, res);
+	}
+
+	fn create_account_path(
+		&mut self,
+		ctx: RpcContext,
+		req: proto::CreateAccountPathRequest,
+		sink: UnarySink<proto::CreateAccountPathResponse>,
+	) {
+		let res = self.call(&ctx, |api| {
+			Ok(proto::CreateAccountPathResponse {
+				path: api.create_account_path(None, &req.label)?.to_hex(),
+			})
+		});
+		reply(&ctx, sink, res);
+	}
+
+	fn set_active_account(
+		&mut self,
+		ctx: RpcContext,
+		req: proto::SetActiveAccountRequest,
+		sink: UnarySink<proto::Empty>,
+	) {
+		let res = self.call(&ctx, |api| {
+			api.set_active_account(None, &req.label)?;
+			Ok(proto::Empty {})
+		});
+		reply(&ctx, sink, res);
+	}
+
+	fn retrieve_outputs(
+		&mut self,
+		ctx: RpcContext,
+		req: proto::RetrieveOutputsRequest,
+		sink: UnarySink<proto::RetrieveOutputsResponse>,
+	) {
+		let res = self.call(&ctx, |api| {
+			let (refreshed, outputs) =
+				api.retrieve_outputs(None, req.include_spent, req.refresh_from_node, req.tx_id)?;
+			Ok(proto::RetrieveOutputsResponse {
+				refreshed,
+				outputs: outputs.iter().map(output_commit_mapping).collect(),
+			})
+		});
+		reply(&ctx, sink, res);
+	}
+
+	fn retrieve_txs(
+		&mut self,
+		ctx: RpcContext,
+		req: proto::RetrieveTxsRequest,
+		sink: UnarySink<proto::RetrieveTxsResponse>,
+	) {
+		let res = self
+			.call(&ctx, |api| retrieve_txs(api, &req))
+			.map(|(refreshed, txs)| proto::RetrieveTxsResponse { refreshed, txs });
+		reply(&ctx, sink, res);
+	}
+
+	fn stream_txs(
+		&mut self,
+		ctx: RpcContext,
+		req: proto::RetrieveTxsRequest,
+		sink: ServerStreamingSink<proto::TxLogEntry>,
+	) {
+		match self.call(&ctx, |api| retrieve_txs(api, &req)) {
+			Ok((_, txs)) => {
+				let txs = stream::iter_ok::<_, grpcio::Error>(
+					txs.into_iter().map(|t| (t, WriteFlags::default())),
+				);
+				ctx.spawn(
+					sink.send_all(txs)
+						.map(|_| ())
+						.map_err(|e| error!("Unable to stream gRPC reply: {}", e)),
+				);
+			}
+			Err(s) => ctx.spawn(
+				sink.fail(s)
+					.map_err(|e| error!("Unable to reply to gRPC call: {}", e)),
+			),
+		}
+	}
+
+	fn retrieve_summary_info(
+		&mut self,
+		ctx: RpcContext,
+		req: proto::RetrieveSummaryInfoRequest,
+		sink: UnarySink<proto::RetrieveSummaryInfoResponse>,
+	) {
+		let res = self.call(&ctx, |api| {
+			let (refreshed, info) =
+				api.retrieve_summary_info(None, req.refresh_from_node, req.minimum_confirmations)?;
+			Ok(proto::RetrieveSummaryInfoResponse {
+				refreshed,
+				info: Some(wallet_info(&info)),
+			})
+		});
+		reply(&ctx, sink, res);
+	}
+
+	fn init_send_tx(
+		&mut self,
+		ctx: RpcContext,
+		req: proto::InitTxArgs,
+		sink: UnarySink<proto::Slate>,
+	) {
+		let res = self.call(&ctx, |api| api.init_send_tx(None, init_tx_args(req)));
+		reply(&ctx, sink, res.and_then(to_slate));
+	}
+
+	fn issue_invoice_tx(
+		&mut self,
+		ctx: RpcContext,
+		req: proto::IssueInvoiceTxArgs,
+		sink: UnarySink<proto::Slate>,
+	) {
+		let args = IssueInvoiceTxArgs {
+			dest_acct_name: non_empty(req.dest_acct_name),
+			amount: req.amount,
+			message: non_empty(req.message),
+			..Default::default()
+		};
+		let res = self.call(&ctx, |api| api.issue_invoice_tx(None, args));
+		reply(&ctx, sink, res.and_then(to_slate));
+	}
+
+	fn process_invoice_tx(
+		&mut self,
+		ctx: RpcContext,
+		req: proto::ProcessInvoiceTxRequest,
+		sink: UnarySink<proto::Slate>,
+	) {
+		let res = from_slate(req.slate).and_then(|slate| {
+			let args = init_tx_args(req.args.unwrap_or_default());
+			self.call(&ctx, |api| api.process_invoice_tx(None, &slate, args))
+		});
+		reply(&ctx, sink, res.and_then(to_slate));
+	}
+
+	fn tx_lock_outputs(
+		&mut self,
+		ctx: RpcContext,
+		req: proto::TxLockOutputsRequest,
+		sink: UnarySink<proto::Empty>,
+	) {
+		let res = from_slate(req.slate).and_then(|slate| {
+			self.call(&ctx, |api| {
+				api.tx_lock_outputs(None, &slate, req.participant_id as usize)?;
+				Ok(proto::Empty {})
+			})
+		});
+		reply(&ctx, sink, res);
+	}
+
+	fn finalize_tx(
+		&mut self,
+		ctx: RpcContext,
+		req: proto::FinalizeTxRequest,
+		sink: UnarySink<proto::Slate>,
+	) {
+		let res = from_slate(req.slate)
+			.and_then(|slate| self.call(&ctx, |api| api.finalize_tx(None, &slate, req.fluff)));
+		reply(&ctx, sink, res.and_then(to_slate));
+	}
+
+	fn post_tx(
+		&mut self,
+		ctx: RpcContext,
+		req: proto::PostTxRequest,
+		sink: UnarySink<proto::Empty>,
+	) {
+		let res = from_slate(req.slate).and_then(|slate| {
+			self.call(&ctx, |api| {
+				api.post_tx(None, &slate.tx, req.fluff)?;
+				Ok(proto::Empty {})
+			})
+		});
+		reply(&ctx, sink, res);
+	}
+
+	fn cancel_tx(
+		&mut self,
+		ctx: RpcContext,
+		req: proto::CancelTxRequest,
+		sink: UnarySink<proto::Empty>,
+	) {
+		let res = slate_id(&req.tx_slate_id).and_then(|tx_slate_id| {
+			self.call(&ctx, |api| {
+				api.cancel_tx(None, req.tx_id, tx_slate_id)?;
+				Ok(proto::Empty {})
+			})
+		});
+		reply(&ctx, sink, res);
+	}
+
+	fn node_height(
+		&mut self,
+		ctx: RpcContext,
+		_req: proto::Empty,
+		sink: UnarySink<proto::NodeHeightResponse>,
+	) {
+		let res = self.call(&ctx, |api| {
+			let h = api.node_height(None)?;
+			Ok(proto::NodeHeightResponse {
+				height: h.height,
+				updated_from_node: h.updated_from_node,
+			})
+		});
+		reply(&ctx, sink, res);
+	}
+}
+
+/// gRPC Foreign service
+struct GrpcForeign<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+	keychain_mask: Option<SecretKey>,
+	webhooks: Option<WebhookDispatcher>,
+	review_threshold: Option<u64>,
+	receive_limits: Option<ReceiveLimits>,
+}
+
+impl<L, C, K> Clone for GrpcForeign<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	fn clone(&self) -> Self {
+		GrpcForeign {
+			wallet: self.wallet.clone(),
+			keychain_mask: self.keychain_mask.clone(),
+			webhooks: self.webhooks.clone(),
+			review_threshold: self.review_threshold,
+			receive_limits: self.receive_limits.clone(),
+		}
+	}
+}
+
+impl<L, C, K> GrpcForeign<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	fn call<T, F>(&self, f: F) -> Result<T, RpcStatus>
+	where
+		F: FnOnce(&Foreign<'static, L, C, K>) -> Result<T, Error>,
+	{
+		let mut api = Foreign::new(
+			self.wallet.clone(),
+			self.keychain_mask.clone(),
+			Some(check_middleware),
+		);
+		api.set_webhooks(self.webhooks.clone());
+		api.set_review_threshold(self.review_threshold);
+		api.set_receive_limits(self.receive_limits.clone());
+		f(&api).map_err(status)
+	}
+}
+
+impl<L, C, K> proto::Foreign for GrpcForeign<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	fn check_version(
+		&mut self,
+		ctx: RpcContext,
+		_req: proto::Empty,
+		sink: UnarySink<proto::VersionInfo>,
+	) {
+		let res = self.call(|api| {
+			let v = api.check_version()?;
+			Ok(proto::VersionInfo {
+				foreign_api_version: v.foreign_api_version as u32,
+				supported_slate_versions: v
+					.supported_slate_versions
+					.iter()
+					.map(|s| format!("{:?}", s))
+					.collect(),
+			})
+		});
+		reply(&ctx, sink, res);
+	}
+
+	fn verify_slate_messages(
+		&mut self,
+		ctx: RpcContext,
+		req: proto::Slate,
+		sink: UnarySink<proto::Empty>,
+	) {
+		let res = from_slate(Some(req)).and_then(|slate| {
+			self.call(|api| {
+				api.verify_slate_messages(&slate)?;
+				Ok(proto::Empty {})
+			})
+		});
+		reply(&ctx, sink, res);
+	}
+
+	fn receive_tx(
+		&mut self,
+		ctx: RpcContext,
+		req: proto::ReceiveTxRequest,
+		sink: UnarySink<proto::Slate>,
+	) {
+		let dest_acct_name = non_empty(req.dest_acct_name);
+		let message = non_empty(req.message);
+		let res = from_slate(req.slate).and_then(|slate| {
+			self.call(|api| {
+				api.receive_tx(&slate, dest_acct_name.as_ref().map(String::as_str), message)
+			})
+		});
+		reply(&ctx, sink, res.and_then(to_slate));
+	}
+
+	fn finalize_invoice_tx(
+		&mut self,
+		ctx: RpcContext,
+		req: proto::Slate,
+		sink: UnarySink<proto::Slate>,
+	) {
+		let res = from_slate(Some(req))
+			.and_then(|slate| self.call(|api| api.finalize_invoice_tx(&slate)));
+		reply(&ctx, sink, res.and_then(to_slate));
+	}
+}
+
+/// Completes a unary call with its result
+fn reply<T>(ctx: &RpcContext, sink: UnarySink<T>, res: Result<T, RpcStatus>) {
+	let f = match res {
+		Ok(r) => sink.success(r),
+		Err(s) => sink.fail(s),
+	};
+	ctx.spawn(f.map_err(|e| error!("Unable to reply to gRPC call: {}", e)));
+}
+
+/// The TOTP code in the call's metadata, if any
+fn totp_code(ctx: &RpcContext) -> Option<String> {
+	ctx.request_headers()
+		.iter()
+		.find(|(k, _)| k.eq_ignore_ascii_case("totp-code"))
+		.and_then(|(_, v)| String::from_utf8(v.to_vec()).ok())
+}
+
+/// Status of a call the wallet failed
+fn status(e: Error) -> RpcStatus {
+	let code = match e.kind() {
+		ErrorKind::TransactionDoesntExist(_) | ErrorKind::UnknownAccountLabel(_) => {
+			RpcStatusCode::NOT_FOUND
+		}
+		ErrorKind::NotEnoughFunds { .. } => RpcStatusCode::FAILED_PRECONDITION,
+		_ => RpcStatusCode::INTERNAL,
+	};
+	RpcStatus::new(code, Some(e.to_string()))
+}
+
+fn invalid_argument(msg: String) -> RpcStatus {
+	RpcStatus::new(RpcStatusCode::INVALID_ARGUMENT, Some(msg))
+}
+
+fn non_empty(s: String) -> Option<String> {
+	match s.is_empty() {
+		true => None,
+		false => Some(s),
+	}
+}
+
+fn slate_id(s: &str) -> Result<Option<Uuid>, RpcStatus> {
+	match s.is_empty() {
+		true => Ok(None),
+		false => Uuid::parse_str(s)
+			.map(Some)
+			.map_err(|_| invalid_argument(format!("Invalid slate id {}", s))),
+	}
+}
+
+fn from_slate(slate: Option<proto::Slate>) -> Result<Slate, RpcStatus> {
+	let json = slate
+		.ok_or_else(|| invalid_argument("Missing slate".to_owned()))?
+		.json;
+	let value = serde_json::from_str(&json)
+		.map_err(|e| invalid_argument(format!("Invalid slate JSON: {}", e)))?;
+	VersionedSlate::from_json(value)
+		.map(|s| s.into())
+		.map_err(|e| invalid_argument(e.to_string()))
+}
+
+fn to_slate(slate: Slate) -> Result<proto::Slate, RpcStatus> {
+	let slate = VersionedSlate::into_version(slate, SlateVersion::V2);
+	serde_json::to_string(&slate)
+		.map(|json| proto::Slate { json })
+		.map_err(|_| status(ErrorKind::SlateSer.into()))
+}
+
+fn retrieve_txs<L, C, K>(
+	api: &Owner<'static, L, C, K>,
+	req: &proto::RetrieveTxsRequest,
+) -> Result<(bool, Vec<proto::TxLogEntry>), Error>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	let tx_slate_id = match req.tx_slate_id.is_empty() {
+		true => None,
+		false => Some(Uuid::parse_str(&req.tx_slate_id).map_err(|_| {
+			ErrorKind::GenericError(format!("Invalid slate id {}", req.tx_slate_id))
+		})?),
+	};
+	let (refreshed, txs) =
+		api.retrieve_txs(None, req.refresh_from_node, req.tx_id, tx_slate_id, None)?;
+	Ok((refreshed, txs.iter().map(tx_log_entry).collect()))
+}
+
+fn init_tx_args(args: proto::InitTxArgs) -> InitTxArgs {
+	InitTxArgs {
+		src_acct_name: non_empty(args.src_acct_name),
+		amount: args.amount,
+		minimum_confirmations: args.minimum_confirmations,
+		max_outputs: args.max_outputs,
+		num_change_outputs: args.num_change_outputs,
+		selection_strategy_is_use_all: args.selection_strategy_is_use_all,
+		message: non_empty(args.message),
+		ttl_blocks: args.ttl_blocks,
+		late_lock: Some(args.late_lock),
+		estimate_only: Some(args.estimate_only),
+		..Default::default()
+	}
+}
+
+fn acct_path_mapping(m: &AcctPathMapping) -> proto::AcctPathMapping {
+	proto::AcctPathMapping {
+		label: m.label.clone(),
+		path: m.path.to_hex(),
+	}
+}
+
+fn output_commit_mapping(m: &OutputCommitMapping) -> proto::OutputCommitMapping {
+	let o = &m.output;
+	let status = match o.status {
+		OutputStatus::Unconfirmed => proto::OutputStatus::Unconfirmed,
+		OutputStatus::Unspent => proto::OutputStatus::Unspent,
+		OutputStatus::Locked => proto::OutputStatus::Locked,
+		OutputStatus::Spent => proto::OutputStatus::Spent,
+	};
+	proto::OutputCommitMapping {
+		output: Some(proto::OutputData {
+			root_key_id: o.root_key_id.to_hex(),
+			key_id: o.key_id.to_hex(),
+			n_child: o.n_child,
+			mmr_index: o.mmr_index,
+			value: o.value,
+			status: status as i32,
+			height: o.height,
+			lock_height: o.lock_height,
+			is_coinbase: o.is_coinbase,
+			tx_log_entry: o.tx_log_entry,
+		}),
+		commit: util::to_hex(m.commit.as_ref().to_vec()),
+	}
+}
+
+fn tx_log_entry(t: &TxLogEntry) -> proto::TxLogEntry {
+	let tx_type = match t.tx_type {
+		TxLogEntryType::ConfirmedCoinbase => proto::TxLogEntryType::ConfirmedCoinbase,
+		TxLogEntryType::TxReceived => proto::TxLogEntryType::TxReceived,
+		TxLogEntryType::TxSent => proto::TxLogEntryType::TxSent,
+		TxLogEntryType::TxReceivedCancelled => proto::TxLogEntryType::TxReceivedCancelled,
+		TxLogEntryType::TxSentCancelled => proto::TxLogEntryType::TxSentCancelled,
+	};
+	proto::TxLogEntry {
+		parent_key_id: t.parent_key_id.to_hex(),
+		id: t.id,
+		tx_slate_id: t.tx_slate_id.map(|id| id.to_string()).unwrap_or_default(),
+		tx_type: tx_type as i32,
+		creation_ts: t.creation_ts.to_rfc3339(),
+		confirmation_ts: t
+			.confirmation_ts
+			.map(|ts| ts.to_rfc3339())
+			.unwrap_or_default(),
+		confirmed: t.confirmed,
+		num_inputs: t.num_inputs as u64,
+		num_outputs: t.num_outputs as u64,
+		amount_credited: t.amount_credited,
+		amount_debited: t.amount_debited,
+		fee: t.fee,
+		stored_tx: t.stored_tx.clone().unwrap_or_default(),
+		label: t.label.clone().unwrap_or_default(),
+		creation_height: t.creation_height,
+		expired: t.expired,
+		confirmation_height: t.confirmation_height,
+	}
+}
+
+fn wallet_info(i: &WalletInfo) -> proto::WalletInfo {
+	proto::WalletInfo {
+		last_confirmed_height: i.last_confirmed_height,
+		minimum_confirmations: i.minimum_confirmations,
+		total: i.total,
+		amount_awaiting_finalization: i.amount_awaiting_finalization,
+		amount_awaiting_confirmation: i.amount_awaiting_confirmation,
+		amount_immature: i.amount_immature,
+		amount_currently_spendable: i.amount_currently_spendable,
+		amount_locked: i.amount_locked,
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn slate_round_trip() {
+		let mut slate = Slate::blank(2);
+		slate.amount = 1_000_000_000;
+		let parsed = from_slate(Some(to_slate(slate.clone()).unwrap())).unwrap();
+		assert_eq!(parsed.id, slate.id);
+		assert_eq!(parsed.amount, slate.amount);
+
+		assert!(from_slate(None).is_err());
+		assert!(from_slate(Some(proto::Slate {
+			json: "{\"id\": 1}".to_owned()
+		}))
+		.is_err());
+		assert_eq!(slate_id("").unwrap(), None);
+		assert!(slate_id("not an id").is_err());
+	}
+}
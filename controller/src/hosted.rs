@@ -0,0 +1,361 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Several wallets served by one listener. Besides the wallet the listener
+//! was started for, each hosted wallet has its own data directory, seed and
+//! keychain mask. Foreign API calls are routed to it by the path they're
+//! posted to, `/v2/foreign/<wallet_id>`, so other wallets can send to it with
+//! their usual positional calls. Owner API calls are routed by a `wallet_id`
+//! member of their named parameters, or of the request itself for GraphQL
+//! queries. Calls without one go to the listener's own wallet.
+//! Foreign API calls posted to `/v2/foreign/<wallet_id>` of a forwarded
+//! wallet, run by another listener, are passed on to that listener's
+//! `/v2/foreign` as they came, so one public endpoint can front many internal
+//! wallets. Calls are only forwarded over TLS, or over plain HTTP to a
+//! listener on the same host, authenticating with the upstream's API secret if
+//! one is given
+
+use crate::api::{self, HandlerObj, ResponseFuture};
+use crate::controller::{json_response, scope_error_response};
+use crate::keychain::Keychain;
+use crate::libwallet::{Error, ErrorKind, NodeClient, WalletInst, WalletLCProvider};
+use crate::response::ResponseFormat;
+use crate::util::secp::key::SecretKey;
+use crate::util::Mutex;
+use futures::future::ok;
+use futures::{Future, Stream};
+use hyper::{Body, Request};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use url::{Host, Url};
+
+/// Named parameter giving the wallet a call is for
+pub const WALLET_ID_PARAM: &str = "wallet_id";
+
+/// A wallet served by a listener alongside its own
+pub struct HostedWallet<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	/// Id of the wallet, in the path of its Foreign API calls and as the
+	/// `wallet_id` parameter of its Owner API calls
+	pub id: String,
+	/// Wallet instance, already opened
+	pub wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+	/// Keychain mask the wallet was opened with
+	pub keychain_mask: Option<SecretKey>,
+}
+
+/// A wallet served by another listener, whose Foreign API calls are passed on
+/// to it
+#[derive(Clone, Debug, PartialEq)]
+pub struct ForwardedWallet {
+	/// Id of the wallet, in the path of its Foreign API calls
+	pub id: String,
+	/// Base URL of the listener serving the wallet, such as
+	/// `https://10.0.0.5:3415`
+	pub upstream: String,
+	/// API secret of the listener serving the wallet, if it has one
+	pub api_secret: Option<String>,
+}
+
+/// Whether calls can be forwarded to the upstream at `url`: over TLS, or over
+/// plain HTTP only to a listener on the same host
+pub fn is_secure_upstream(url: &str) -> bool {
+	let url = match Url::parse(url) {
+		Ok(u) => u,
+		Err(_) => return false,
+	};
+	match (url.scheme(), url.host()) {
+		("https", Some(_)) => true,
+		("http", Some(Host::Domain(d))) => d == "localhost",
+		("http", Some(Host::Ipv4(ip))) => ip.is_loopback(),
+		("http", Some(Host::Ipv6(ip))) => ip.is_loopback(),
+		_ => false,
+	}
+}
+
+/// Foreign API of a forwarded wallet's listener
+#[derive(Clone, Debug, PartialEq)]
+struct Upstream {
+	url: String,
+	api_secret: Option<String>,
+}
+
+/// Handler passing each JSON-RPC call to the handler of the wallet it's for,
+/// with the `wallet_id` parameter taken out
+pub struct WalletRoutingHandler {
+	default: HandlerObj,
+	wallets: Arc<HashMap<String, HandlerObj>>,
+	upstreams: Arc<HashMap<String, Upstream>>,
+	path_prefix: Option<&'static str>,
+}
+
+impl WalletRoutingHandler {
+	/// Route calls without a `wallet_id` to the `default` handler, and the
+	/// others to the handler of the wallet with their `wallet_id`
+	pub fn new(default: HandlerObj, wallets: HashMap<String, HandlerObj>) -> WalletRoutingHandler {
+		WalletRoutingHandler {
+			default,
+			wallets: Arc::new(wallets),
+			upstreams: Arc::new(HashMap::new()),
+			path_prefix: None,
+		}
+	}
+
+	/// Route calls by the path segment after `prefix`, as in
+	/// `/v2/foreign/<wallet_id>`, rather than by a `wallet_id` parameter, and
+	/// calls to `prefix` itself to the `default` handler
+	pub fn with_path_routing(self, prefix: &'static str) -> WalletRoutingHandler {
+		WalletRoutingHandler {
+			path_prefix: Some(prefix),
+			..self
+		}
+	}
+
+	/// Pass Foreign API calls for the `forwarded` wallets on to their
+	/// upstream listeners. Fails if one of them isn't
+	/// [secure](fn.is_secure_upstream.html)
+	pub fn with_forwarded(
+		self,
+		forwarded: &[ForwardedWallet],
+	) -> Result<WalletRoutingHandler, Error> {
+		let mut upstreams = HashMap::new();
+		for f in forwarded.iter() {
+			if !is_secure_upstream(&f.upstream) {
+				return Err(ErrorKind::GenericError(format!(
+					"Upstream {} of wallet '{}' must use https, or http on the same host",
+					f.upstream, f.id
+				)))?;
+			}
+			let upstream = Upstream {
+				url: format!("{}/v2/foreign", f.upstream.trim_end_matches('/')),
+				api_secret: f.api_secret.clone(),
+			};
+			upstreams.insert(f.id.clone(), upstream);
+		}
+		Ok(WalletRoutingHandler {
+			upstreams: Arc::new(upstreams),
+			..self
+		})
+	}
+}
+
+/// Where a call goes
+enum Route {
+	/// To the handler of a wallet served by the listener
+	Local(HandlerObj),
+	/// To the Foreign API of a forwarded wallet's listener
+	Upstream(Upstream),
+}
+
+/// Where a call for wallet `id` goes, or an error if it's an unknown wallet
+fn route(
+	default: &HandlerObj,
+	wallets: &HashMap<String, HandlerObj>,
+	upstreams: &HashMap<String, Upstream>,
+	id: Option<String>,
+) -> Result<Route, String> {
+	let id = match id {
+		None => return Ok(Route::Local(default.clone())),
+		Some(id) => id,
+	};
+	if let Some(h) = wallets.get(&id) {
+		return Ok(Route::Local(h.clone()));
+	}
+	upstreams
+		.get(&id)
+		.map(|u| Route::Upstream(u.clone()))
+		.ok_or_else(|| format!("Unknown wallet '{}'", id))
+}
+
+/// Pass the call on to the upstream Foreign API, resolving to its response
+fn forward(upstream: Upstream, req: &Value) -> impl Future<Item = Value, Error = String> {
+	debug!("Forwarding {} call to {}", req["method"], upstream.url);
+	let url = upstream.url;
+	api::client::post_async::<Value, Value>(&url, req, upstream.api_secret).map_err(move |e| {
+		warn!("Upstream wallet at {} failed: {}", url, e);
+		format!("Upstream wallet is unavailable: {}", e)
+	})
+}
+
+/// Take the wallet a call is for out of its named parameters, or out of the
+/// request itself for a GraphQL query, which has no parameters
+fn take_wallet_id(req: &mut Value) -> Result<Option<String>, String> {
+	let members = match req.get("query").is_some() {
+		true => req.as_object_mut(),
+		false => req.get_mut("params").and_then(|p| p.as_object_mut()),
+	};
+	let id = match members.and_then(|p| p.remove(WALLET_ID_PARAM)) {
+		Some(id) => id,
+		None => return Ok(None),
+	};
+	match id {
+		Value::String(s) => Ok(Some(s)),
+		_ => Err(format!("{} must be a string", WALLET_ID_PARAM)),
+	}
+}
+
+/// The wallet a call posted to `path` is for: the path segment after
+/// `prefix`, if there is one
+fn path_wallet_id(prefix: &str, path: &str) -> Option<String> {
+	if !path.starts_with(prefix) {
+		return None;
+	}
+	let id = path[prefix.len()..].trim_matches('/');
+	match id.is_empty() {
+		true => None,
+		false => Some(id.to_owned()),
+	}
+}
+
+impl api::Handler for WalletRoutingHandler {
+	fn post(&self, req: Request<Body>) -> ResponseFuture {
+		let default = self.default.clone();
+		let wallets = self.wallets.clone();
+		let upstreams = self.upstreams.clone();
+		let path_prefix = self.path_prefix;
+		let (parts, body) = req.into_parts();
+		Box::new(body.concat2().and_then(move |body| -> ResponseFuture {
+			let mut val: Value = match serde_json::from_slice(&body) {
+				Ok(v) => v,
+				// left to the wallet's own handler to answer
+				Err(_) => return default.post(Request::from_parts(parts, body.into())),
+			};
+			let id = match path_prefix {
+				Some(prefix) => Ok(path_wallet_id(prefix, parts.uri.path())),
+				None => take_wallet_id(&mut val),
+			};
+			let res = match id.and_then(|id| route(&default, &wallets, &upstreams, id)) {
+				Ok(Route::Local(handler)) => {
+					let body = serde_json::to_vec(&val).unwrap_or_default();
+					return handler.post(Request::from_parts(parts, body.into()));
+				}
+				Ok(Route::Upstream(upstream)) => {
+					return Box::new(forward(upstream, &val).then(move |res| {
+						let res = res.unwrap_or_else(|msg| scope_error_response(&val, msg));
+						Ok::<_, hyper::Error>(json_response(&res, &ResponseFormat::default()))
+					}));
+				}
+				Err(msg) => scope_error_response(&val, msg),
+			};
+			Box::new(ok(json_response(&res, &ResponseFormat::default())))
+		}))
+	}
+
+	fn options(&self, req: Request<Body>) -> ResponseFuture {
+		self.default.options(req)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use serde_json::json;
+
+	#[test]
+	fn takes_wallet_id() {
+		let mut req = json!({
+			"jsonrpc": "2.0",
+			"method": "accounts",
+			"params": {"token": null, "wallet_id": "shop"},
+			"id": 1,
+		});
+		assert_eq!(take_wallet_id(&mut req), Ok(Some("shop".to_owned())));
+		assert_eq!(req["params"], json!({"token": null}));
+		assert_eq!(take_wallet_id(&mut req), Ok(None));
+
+		let mut req = json!({"method": "accounts", "params": [null]});
+		assert_eq!(take_wallet_id(&mut req), Ok(None));
+		let mut req = json!({"method": "accounts", "params": {"wallet_id": 1}});
+		assert!(take_wallet_id(&mut req).is_err());
+
+		let mut req = json!({"query": "{ accounts { label } }", "wallet_id": "shop"});
+		assert_eq!(take_wallet_id(&mut req), Ok(Some("shop".to_owned())));
+		assert_eq!(req, json!({"query": "{ accounts { label } }"}));
+	}
+
+	#[test]
+	fn takes_path_wallet_id() {
+		let prefix = "/v2/foreign";
+		assert_eq!(
+			path_wallet_id(prefix, "/v2/foreign/shop"),
+			Some("shop".to_owned())
+		);
+		assert_eq!(
+			path_wallet_id(prefix, "/v2/foreign/shop/"),
+			Some("shop".to_owned())
+		);
+		assert_eq!(path_wallet_id(prefix, "/v2/foreign"), None);
+		assert_eq!(path_wallet_id(prefix, "/v2/foreign/"), None);
+		assert_eq!(path_wallet_id(prefix, "/v2/owner"), None);
+	}
+
+	struct NoHandler;
+	impl api::Handler for NoHandler {}
+
+	#[test]
+	fn routes_to_upstream() {
+		let handler = WalletRoutingHandler::new(Arc::new(NoHandler), HashMap::new())
+			.with_forwarded(&[ForwardedWallet {
+				id: "shop".to_owned(),
+				upstream: "https://10.0.0.5:3415/".to_owned(),
+				api_secret: Some("secret".to_owned()),
+			}])
+			.unwrap();
+		let route_id = |id: &str| {
+			let id = match id.is_empty() {
+				true => None,
+				false => Some(id.to_owned()),
+			};
+			route(&handler.default, &handler.wallets, &handler.upstreams, id)
+		};
+		match route_id("shop") {
+			Ok(Route::Upstream(u)) => {
+				assert_eq!(u.url, "https://10.0.0.5:3415/v2/foreign");
+				assert_eq!(u.api_secret, Some("secret".to_owned()));
+			}
+			_ => panic!("call wasn't forwarded"),
+		}
+		assert!(route_id("other").is_err());
+		assert!(match route_id("") {
+			Ok(Route::Local(_)) => true,
+			_ => false,
+		});
+	}
+
+	#[test]
+	fn forwards_securely() {
+		assert!(is_secure_upstream("https://10.0.0.5:3415"));
+		assert!(is_secure_upstream("http://127.0.0.1:3415"));
+		assert!(is_secure_upstream("http://localhost:3415"));
+		assert!(is_secure_upstream("http://[::1]:3415"));
+		assert!(!is_secure_upstream("http://10.0.0.5:3415"));
+		assert!(!is_secure_upstream("10.0.0.5:3415"));
+
+		let forwarded = ForwardedWallet {
+			id: "shop".to_owned(),
+			upstream: "http://10.0.0.5:3415".to_owned(),
+			api_secret: None,
+		};
+		assert!(
+			WalletRoutingHandler::new(Arc::new(NoHandler), HashMap::new())
+				.with_forwarded(&[forwarded])
+				.is_err()
+		);
+	}
+}
@@ -31,9 +31,24 @@ use grin_wallet_util::grin_core as core;
 use grin_wallet_util::grin_keychain as keychain;
 use grin_wallet_util::grin_util as util;
 
+pub mod auth;
+pub mod client;
 pub mod command;
 pub mod controller;
 pub mod display;
+pub mod envelope;
 mod error;
+pub mod events;
+pub mod graphql;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod hosted;
+pub mod metrics;
+pub mod middleware;
+pub mod response;
+pub mod server;
+pub mod tls;
+#[cfg(unix)]
+pub mod uds;
 
 pub use crate::error::{Error, ErrorKind};
@@ -0,0 +1,414 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Request and wallet state metrics, exposed in the Prometheus text format
+
+use crate::api::{self, ResponseFuture};
+use crate::apiwallet::CallQueue;
+use crate::keychain::Keychain;
+use crate::libwallet::api_impl::owner;
+use crate::libwallet::{Error, NodeClient, WalletInfo, WalletInst, WalletLCProvider};
+use crate::util::Mutex;
+use easy_jsonrpc::MaybeReply;
+use futures::future::ok;
+use hyper::{Body, Request, Response, StatusCode};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// RPC methods requests are recorded against, so labels can't be chosen by
+/// whoever sends a request. Requests for any other method are recorded as
+/// "unknown"
+const RPC_METHODS: &[&str] = &[
+	"accounts",
+	"create_account_path",
+	"set_active_account",
+	"retrieve_outputs",
+	"retrieve_txs",
+	"export_txs",
+	"query_outputs",
+	"get_output_derivation",
+	"reserve_derivation_indices",
+	"retrieve_summary_info",
+	"init_send_tx",
+	"init_send_tx_multi",
+	"consolidate",
+	"issue_invoice_tx",
+	"process_invoice_tx",
+	"tx_lock_outputs",
+	"finalize_tx",
+	"prepare_send",
+	"sign_offline",
+	"finalize_offline",
+	"broadcast_signed",
+	"post_tx",
+	"post_tx_checked",
+	"cancel_tx",
+	"cancel_txs",
+	"post_txs",
+	"retrieve_pending_receives",
+	"approve_receive",
+	"reject_receive",
+	"set_tx_label",
+	"get_stored_tx",
+	"replay_tx",
+	"verify_slate_messages",
+	"restore",
+	"check_repair",
+	"audit_chain",
+	"node_height",
+	"node_status",
+	"get_node_status",
+	"register_push_token",
+	"unregister_push_token",
+	"retrieve_push_registrations",
+	"list_anomalies",
+	"remediate",
+	"get_feature_flags",
+	"set_feature_flags",
+	"verify_seed_backup",
+	"stop_listener",
+	"set_privacy_mode",
+	"get_build_info",
+	"get_statistics",
+	"enroll_totp",
+	"confirm_totp",
+	"disable_totp",
+	"restore_from_backup",
+	"change_password",
+	"start_updater",
+	"stop_updater",
+	"get_updater_messages",
+	"start_operation",
+	"operation_status",
+	"cancel_operation",
+	"list_operations",
+	"get_webhook_dead_letters",
+	"export_audit_log",
+	"check_version",
+	"build_coinbase",
+	"receive_tx",
+	"finalize_invoice_tx",
+];
+
+/// How long the wallet's balance and the node's height are served from the
+/// cache, so scrapes don't each take the wallet lock and query the node
+const WALLET_STATE_TTL: Duration = Duration::from_secs(15);
+
+/// Wallet state last reported, and when it was fetched
+struct WalletState {
+	fetched: Instant,
+	info: Option<WalletInfo>,
+	node_height: Option<u64>,
+}
+
+/// Per RPC method request statistics
+#[derive(Clone, Default)]
+struct MethodStats {
+	requests: u64,
+	errors: u64,
+	latency_secs: f64,
+}
+
+/// Metrics collected by the wallet's API listeners
+#[derive(Default)]
+pub struct WalletMetrics {
+	/// Stats keyed by (api, method)
+	methods: Mutex<BTreeMap<(String, String), MethodStats>>,
+	/// Failed requests that never reached an RPC method, keyed by api
+	listener_errors: Mutex<BTreeMap<String, u64>>,
+	/// Owner API call queue, whose depth is reported
+	call_queue: Mutex<Option<Arc<CallQueue>>>,
+	/// Wallet state last reported
+	wallet_state: Mutex<Option<WalletState>>,
+}
+
+impl WalletMetrics {
+	/// Create an empty set of metrics
+	pub fn new() -> WalletMetrics {
+		WalletMetrics::default()
+	}
+
+	/// Record the outcome of a JSON-RPC request handled by the given api
+	pub fn record_request(
+		&self,
+		api: &str,
+		request: &serde_json::Value,
+		start: Instant,
+		reply: &MaybeReply,
+	) {
+		let method = request
+			.get("method")
+			.and_then(|m| m.as_str())
+			.and_then(|m| RPC_METHODS.iter().find(|known| **known == m).cloned())
+			.unwrap_or("unknown");
+		let is_error = match reply {
+			MaybeReply::Reply(r) => {
+				r.get("error").is_some() || r.get("result").and_then(|res| res.get("Err")).is_some()
+			}
+			MaybeReply::DontReply => false,
+		};
+		let elapsed = start.elapsed();
+		let mut methods = self.methods.lock();
+		let stats = methods
+			.entry((api.to_owned(), method.to_owned()))
+			.or_insert_with(MethodStats::default);
+		stats.requests += 1;
+		if is_error {
+			stats.errors += 1;
+		}
+		stats.latency_secs +=
+			elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) / 1_000_000_000.0;
+	}
+
+	/// Record a request the given api's listener failed to handle
+	pub fn record_listener_error(&self, api: &str) {
+		*self
+			.listener_errors
+			.lock()
+			.entry(api.to_owned())
+			.or_insert(0) += 1;
+	}
+
+	/// Report the depth of the given Owner API call queue
+	pub fn watch_call_queue(&self, queue: Arc<CallQueue>) {
+		*self.call_queue.lock() = Some(queue);
+	}
+
+	/// Render all metrics, along with gauges describing the state of the
+	/// given wallet, in the Prometheus text exposition format
+	pub fn render<L, C, K>(
+		&self,
+		wallet: &Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+	) -> String
+	where
+		L: WalletLCProvider<'static, C, K> + 'static,
+		C: NodeClient + 'static,
+		K: Keychain + 'static,
+	{
+		let mut out = String::new();
+		{
+			let methods = self.methods.lock();
+			let _ = writeln!(
+				out,
+				"# HELP grin_wallet_rpc_requests_total Requests handled per RPC method\n\
+				 # TYPE grin_wallet_rpc_requests_total counter"
+			);
+			for ((api, method), s) in methods.iter() {
+				let _ = writeln!(
+					out,
+					"grin_wallet_rpc_requests_total{{api=\"{}\",method=\"{}\"}} {}",
+					api, method, s.requests
+				);
+			}
+			let _ = writeln!(
+				out,
+				"# HELP grin_wallet_rpc_errors_total Requests per RPC method returning an error\n\
+				 # TYPE grin_wallet_rpc_errors_total counter"
+			);
+			for ((api, method), s) in methods.iter() {
+				let _ = writeln!(
+					out,
+					"grin_wallet_rpc_errors_total{{api=\"{}\",method=\"{}\"}} {}",
+					api, method, s.errors
+				);
+			}
+			let _ = writeln!(
+				out,
+				"# HELP grin_wallet_rpc_request_duration_seconds Time spent handling requests per RPC method\n\
+				 # TYPE grin_wallet_rpc_request_duration_seconds summary"
+			);
+			for ((api, method), s) in methods.iter() {
+				let _ = writeln!(
+					out,
+					"grin_wallet_rpc_request_duration_seconds_sum{{api=\"{}\",method=\"{}\"}} {}\n\
+					 grin_wallet_rpc_request_duration_seconds_count{{api=\"{}\",method=\"{}\"}} {}",
+					api, method, s.latency_secs, api, method, s.requests
+				);
+			}
+		}
+		let _ = writeln!(
+			out,
+			"# HELP grin_wallet_listener_errors_total Requests the listener failed to handle\n\
+			 # TYPE grin_wallet_listener_errors_total counter"
+		);
+		for (api, count) in self.listener_errors.lock().iter() {
+			let _ = writeln!(
+				out,
+				"grin_wallet_listener_errors_total{{api=\"{}\"}} {}",
+				api, count
+			);
+		}
+		if let Some(q) = self.call_queue.lock().as_ref() {
+			let (interactive, background) = q.depth();
+			let _ = writeln!(
+				out,
+				"# HELP grin_wallet_owner_queue_depth Owner API calls waiting for the wallet\n\
+				 # TYPE grin_wallet_owner_queue_depth gauge\n\
+				 grin_wallet_owner_queue_depth{{priority=\"interactive\"}} {}\n\
+				 grin_wallet_owner_queue_depth{{priority=\"background\"}} {}",
+				interactive, background
+			);
+		}
+
+		let (info, node_height) = self.cached_wallet_state(wallet);
+		let _ = writeln!(
+			out,
+			"# HELP grin_wallet_open Whether the wallet is open\n\
+			 # TYPE grin_wallet_open gauge\n\
+			 grin_wallet_open {}",
+			info.is_some() as u8
+		);
+		if let Some(i) = info {
+			let _ = writeln!(
+				out,
+				"# HELP grin_wallet_balance_nanogrin Balance of the active account as of the last refresh\n\
+				 # TYPE grin_wallet_balance_nanogrin gauge"
+			);
+			for (kind, amount) in &[
+				("total", i.total),
+				("awaiting_finalization", i.amount_awaiting_finalization),
+				("awaiting_confirmation", i.amount_awaiting_confirmation),
+				("immature", i.amount_immature),
+				("spendable", i.amount_currently_spendable),
+				("locked", i.amount_locked),
+			] {
+				let _ = writeln!(
+					out,
+					"grin_wallet_balance_nanogrin{{kind=\"{}\"}} {}",
+					kind, amount
+				);
+			}
+			let _ = writeln!(
+				out,
+				"# HELP grin_wallet_last_confirmed_height Chain height the wallet was last refreshed at\n\
+				 # TYPE grin_wallet_last_confirmed_height gauge\n\
+				 grin_wallet_last_confirmed_height {}",
+				i.last_confirmed_height
+			);
+			let _ = writeln!(
+				out,
+				"# HELP grin_wallet_node_connected Whether the node is reachable\n\
+				 # TYPE grin_wallet_node_connected gauge\n\
+				 grin_wallet_node_connected {}",
+				node_height.is_some() as u8
+			);
+			if let Some(h) = node_height {
+				let _ = writeln!(
+					out,
+					"# HELP grin_wallet_node_height Chain height reported by the node\n\
+					 # TYPE grin_wallet_node_height gauge\n\
+					 grin_wallet_node_height {}",
+					h
+				);
+			}
+		}
+		out
+	}
+
+	/// The wallet's state as last fetched, fetching it again once it's older
+	/// than `WALLET_STATE_TTL`. A busy wallet isn't waited on, the state last
+	/// fetched is reported instead
+	fn cached_wallet_state<L, C, K>(
+		&self,
+		wallet: &Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+	) -> (Option<WalletInfo>, Option<u64>)
+	where
+		L: WalletLCProvider<'static, C, K> + 'static,
+		C: NodeClient + 'static,
+		K: Keychain + 'static,
+	{
+		let mut cached = self.wallet_state.lock();
+		let fresh = cached
+			.as_ref()
+			.map_or(false, |s| s.fetched.elapsed() < WALLET_STATE_TTL);
+		if !fresh {
+			if let Some(mut w_lock) = wallet.try_lock() {
+				let (info, node_height) = match wallet_state(&mut **w_lock) {
+					Ok((info, node_height)) => (Some(info), node_height),
+					Err(_) => (None, None),
+				};
+				*cached = Some(WalletState {
+					fetched: Instant::now(),
+					info,
+					node_height,
+				});
+			}
+		}
+		cached
+			.as_ref()
+			.map_or((None, None), |s| (s.info.clone(), s.node_height))
+	}
+}
+
+/// Balance of the active account (without refreshing) and the node's chain
+/// height, if the node could be contacted
+fn wallet_state<L, C, K>(
+	wallet: &mut dyn WalletInst<'static, L, C, K>,
+) -> Result<(WalletInfo, Option<u64>), Error>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	let w = wallet.lc_provider()?.wallet_inst()?;
+	let (_, info) = owner::retrieve_summary_info(&mut **w, None, false, 1)?;
+	let node_height = w.w2n_client().get_chain_height().ok();
+	Ok((info, node_height))
+}
+
+/// Handler serving metrics on GET requests
+pub struct MetricsHandler<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	/// Wallet instance
+	pub wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+	/// Metrics to serve
+	pub metrics: Arc<WalletMetrics>,
+}
+
+impl<L, C, K> MetricsHandler<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	/// Create a new metrics handler
+	pub fn new(
+		wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+		metrics: Arc<WalletMetrics>,
+	) -> MetricsHandler<L, C, K> {
+		MetricsHandler { wallet, metrics }
+	}
+}
+
+impl<L, C, K> api::Handler for MetricsHandler<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	fn get(&self, _req: Request<Body>) -> ResponseFuture {
+		let body = self.metrics.render(&self.wallet);
+		Box::new(ok(Response::builder()
+			.status(StatusCode::OK)
+			.header(hyper::header::CONTENT_TYPE, "text/plain; version=0.0.4")
+			.body(body.into())
+			.unwrap()))
+	}
+}
@@ -0,0 +1,482 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Router middleware protecting publicly exposed listeners
+
+use crate::api::{self, HandlerObj, ResponseFuture};
+use crate::config::{CorsConfig, TarpitConfig};
+use crate::server::PeerAddr;
+use crate::util::Mutex;
+use futures::future::ok;
+use futures::{Future, Stream};
+use hyper::header::{
+	HeaderValue, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+	ACCESS_CONTROL_ALLOW_ORIGIN, CONTENT_LENGTH, ORIGIN, VARY,
+};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error as StdError;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::timer::Interval;
+
+/// Address of the client making a request: the one it connected from, or the
+/// one given by the `X-Real-IP` or `X-Forwarded-For` header if it connected
+/// through one of the `trusted_proxies`. `None` if connected other than over
+/// TCP, such as through a unix socket
+pub fn client_address(req: &Request<Body>, trusted_proxies: &[IpAddr]) -> Option<String> {
+	let peer = req.extensions().get::<PeerAddr>()?.0.ip();
+	if !trusted_proxies.contains(&peer) {
+		return Some(peer.to_string());
+	}
+	let headers = req.headers();
+	if let Some(ip) = headers.get("x-real-ip").and_then(|h| h.to_str().ok()) {
+		return Some(ip.trim().to_owned());
+	}
+	// the last address is the one the proxy saw, those before it are
+	// whatever the client claimed
+	if let Some(ips) = headers.get("x-forwarded-for").and_then(|h| h.to_str().ok()) {
+		if let Some(ip) = ips.rsplit(',').next() {
+			return Some(ip.trim().to_owned());
+		}
+	}
+	Some(peer.to_string())
+}
+
+/// Maximum number of clients tracked by the rate limiter, the least
+/// recently seen being forgotten to make room for new ones
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Token bucket state for a single client
+struct Bucket {
+	tokens: f64,
+	last: Instant,
+}
+
+/// Map holding a bounded number of entries, evicting the least recently
+/// used entry to make room for a new one
+struct LruMap<V> {
+	capacity: usize,
+	tick: u64,
+	entries: HashMap<String, (u64, V)>,
+	/// Keys by the tick they were last used at
+	order: BTreeMap<u64, String>,
+}
+
+impl<V> LruMap<V> {
+	fn new(capacity: usize) -> LruMap<V> {
+		LruMap {
+			capacity,
+			tick: 0,
+			entries: HashMap::new(),
+			order: BTreeMap::new(),
+		}
+	}
+
+	/// The entry for `key`, inserted with `default` if there's none, marked
+	/// as the most recently used
+	fn get_or_insert_with<F>(&mut self, key: &str, default: F) -> &mut V
+	where
+		F: FnOnce() -> V,
+	{
+		self.tick += 1;
+		match self.entries.get(key) {
+			Some((last, _)) => {
+				self.order.remove(last);
+			}
+			None if self.entries.len() >= self.capacity => {
+				let oldest = self.order.keys().next().cloned();
+				if let Some(k) = oldest.and_then(|t| self.order.remove(&t)) {
+					self.entries.remove(&k);
+				}
+			}
+			None => {}
+		}
+		self.order.insert(self.tick, key.to_owned());
+		let tick = self.tick;
+		let entry = self
+			.entries
+			.entry(key.to_owned())
+			.or_insert_with(|| (tick, default()));
+		entry.0 = tick;
+		&mut entry.1
+	}
+}
+
+/// Connections held by the tarpit, in all and by client
+#[derive(Default)]
+struct HeldConnections {
+	total: u32,
+	by_client: HashMap<String, u32>,
+}
+
+/// Holds clients over the rate limit with a response dripped out a byte at a
+/// time, up to a number of connections per client and in all
+struct Tarpit {
+	config: TarpitConfig,
+	held: Arc<Mutex<HeldConnections>>,
+}
+
+/// A connection held by the tarpit, released when dropped
+struct HeldConnection {
+	client: String,
+	held: Arc<Mutex<HeldConnections>>,
+}
+
+impl Drop for HeldConnection {
+	fn drop(&mut self) {
+		let mut held = self.held.lock();
+		held.total = held.total.saturating_sub(1);
+		let remaining = match held.by_client.get_mut(&self.client) {
+			Some(n) => {
+				*n = n.saturating_sub(1);
+				*n
+			}
+			None => 0,
+		};
+		if remaining == 0 {
+			held.by_client.remove(&self.client);
+		}
+	}
+}
+
+impl Tarpit {
+	/// Hold a connection for the client, unless the caps are reached
+	fn hold(&self, client: &str) -> Option<HeldConnection> {
+		let mut held = self.held.lock();
+		let for_client = held.by_client.get(client).cloned().unwrap_or(0);
+		if held.total >= self.config.max_connections
+			|| for_client >= self.config.max_connections_per_client
+		{
+			return None;
+		}
+		held.total += 1;
+		held.by_client.insert(client.to_owned(), for_client + 1);
+		Some(HeldConnection {
+			client: client.to_owned(),
+			held: self.held.clone(),
+		})
+	}
+
+	/// A response whose body is dripped out a byte per interval until the
+	/// connection has been held long enough, or the client gives up
+	fn response(&self, connection: HeldConnection) -> ResponseFuture {
+		let interval = Duration::from_secs(self.config.drip_interval_secs.max(1));
+		let drips = self.config.hold_secs / interval.as_secs();
+		let body = Interval::new(Instant::now() + interval, interval)
+			.take(drips)
+			.map(move |_| {
+				// the connection is released once the body is dropped
+				let _ = &connection;
+				" "
+			})
+			.chain(futures::stream::once(Ok("Too many requests")))
+			.map_err(|e| Box::new(e) as Box<dyn StdError + Send + Sync>);
+		Box::new(ok(Response::builder()
+			.status(StatusCode::TOO_MANY_REQUESTS)
+			.body(Body::wrap_stream(body))
+			.unwrap()))
+	}
+}
+
+/// Middleware limiting request body sizes and, optionally, the rate at which
+/// each client may make requests.
+///
+/// Clients are identified by the address they connect from. Only requests
+/// from a trusted reverse proxy are identified by the `X-Real-IP` or
+/// `X-Forwarded-For` header it sets instead. Clients over the rate limit are
+/// turned away, or held in a tarpit if one is set, whose caps per client
+/// rely on the same identification.
+pub struct RequestLimitsMiddleware {
+	max_body_size: u64,
+	rate_limit: Option<u32>,
+	trusted_proxies: Vec<IpAddr>,
+	buckets: Mutex<LruMap<Bucket>>,
+	tarpit: Option<Tarpit>,
+}
+
+impl RequestLimitsMiddleware {
+	/// Create new middleware, rejecting bodies over `max_body_size` bytes and,
+	/// if given, allowing each client `rate_limit` requests per minute
+	pub fn new(max_body_size: u64, rate_limit: Option<u32>) -> RequestLimitsMiddleware {
+		RequestLimitsMiddleware {
+			max_body_size,
+			rate_limit,
+			trusted_proxies: vec![],
+			buckets: Mutex::new(LruMap::new(MAX_TRACKED_CLIENTS)),
+			tarpit: None,
+		}
+	}
+
+	/// Identify the clients of requests from the given reverse proxies by the
+	/// headers the proxies set
+	pub fn with_trusted_proxies(mut self, proxies: Vec<IpAddr>) -> RequestLimitsMiddleware {
+		self.trusted_proxies = proxies;
+		self
+	}
+
+	/// Hold clients over the rate limit in a tarpit rather than turning them
+	/// away
+	pub fn with_tarpit(mut self, config: TarpitConfig) -> RequestLimitsMiddleware {
+		self.tarpit = Some(Tarpit {
+			config,
+			held: Arc::new(Mutex::new(HeldConnections::default())),
+		});
+		self
+	}
+
+	fn client_key(&self, req: &Request<Body>) -> String {
+		client_address(req, &self.trusted_proxies).unwrap_or_else(|| "unknown".to_owned())
+	}
+
+	/// Take a token from the client's bucket, returning false if it's empty
+	fn allow(&self, client: &str, per_minute: u32) -> bool {
+		let capacity = f64::from(per_minute.max(1));
+		let refill_per_sec = capacity / 60.0;
+		let now = Instant::now();
+		let mut buckets = self.buckets.lock();
+		let bucket = buckets.get_or_insert_with(client, || Bucket {
+			tokens: capacity,
+			last: now,
+		});
+		let elapsed = now.duration_since(bucket.last);
+		let elapsed_secs =
+			elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) / 1_000_000_000.0;
+		bucket.tokens = (bucket.tokens + elapsed_secs * refill_per_sec).min(capacity);
+		bucket.last = now;
+		if bucket.tokens < 1.0 {
+			return false;
+		}
+		bucket.tokens -= 1.0;
+		true
+	}
+
+	/// Wrap the request body so reading more than the maximum size fails,
+	/// for requests that don't declare a content length up front
+	fn limit_body(&self, req: Request<Body>) -> Request<Body> {
+		let max_body_size = self.max_body_size;
+		let (parts, body) = req.into_parts();
+		let mut received: u64 = 0;
+		let limited = body
+			.map_err(|e| Box::new(e) as Box<dyn StdError + Send + Sync>)
+			.and_then(move |chunk| {
+				received += chunk.len() as u64;
+				if received > max_body_size {
+					Err(From::from("Request body too large"))
+				} else {
+					Ok(chunk)
+				}
+			});
+		Request::from_parts(parts, Body::wrap_stream(limited))
+	}
+}
+
+fn reject(status: StatusCode, msg: &str) -> ResponseFuture {
+	Box::new(ok(Response::builder()
+		.status(status)
+		.body(msg.to_owned().into())
+		.unwrap()))
+}
+
+impl api::Handler for RequestLimitsMiddleware {
+	fn call(
+		&self,
+		req: Request<Body>,
+		mut handlers: Box<dyn Iterator<Item = HandlerObj>>,
+	) -> ResponseFuture {
+		let next_handler = match handlers.next() {
+			Some(h) => h,
+			None => return reject(StatusCode::INTERNAL_SERVER_ERROR, "no handler found"),
+		};
+
+		if let Some(per_minute) = self.rate_limit {
+			let client = self.client_key(&req);
+			if !self.allow(&client, per_minute) {
+				if let Some(t) = self.tarpit.as_ref() {
+					if let Some(connection) = t.hold(&client) {
+						warn!("Rate limit exceeded for client {}, tarpitting", client);
+						return t.response(connection);
+					}
+				}
+				warn!("Rate limit exceeded for client {}", client);
+				return reject(StatusCode::TOO_MANY_REQUESTS, "Too many requests");
+			}
+		}
+
+		let content_length = req
+			.headers()
+			.get(CONTENT_LENGTH)
+			.and_then(|h| h.to_str().ok())
+			.and_then(|l| l.parse::<u64>().ok());
+		match content_length {
+			Some(l) if l > self.max_body_size => {
+				warn!(
+					"Rejecting request with body of {} bytes (maximum {})",
+					l, self.max_body_size
+				);
+				reject(StatusCode::PAYLOAD_TOO_LARGE, "Request body too large")
+			}
+			Some(_) => next_handler.call(req, handlers),
+			None => next_handler.call(self.limit_body(req), handlers),
+		}
+	}
+}
+
+/// Middleware applying a listener's CORS policy, answering preflight requests
+/// for its routes and setting the allowed origin on all other responses
+pub struct CorsMiddleware {
+	policy: CorsConfig,
+	allowed_methods: String,
+	allowed_headers: String,
+}
+
+impl CorsMiddleware {
+	/// Create new middleware enforcing the given policy
+	pub fn new(policy: CorsConfig) -> CorsMiddleware {
+		let allowed_methods = policy.allowed_methods.join(", ");
+		let allowed_headers = policy.allowed_headers.join(", ");
+		CorsMiddleware {
+			policy,
+			allowed_methods,
+			allowed_headers,
+		}
+	}
+
+	/// Value of the allow-origin header for the request's origin, or None
+	/// if the origin isn't allowed
+	fn allowed_origin(&self, req: &Request<Body>) -> Option<String> {
+		if self.policy.allowed_origins.iter().any(|o| o == "*") {
+			return Some("*".to_owned());
+		}
+		let origin = req.headers().get(ORIGIN).and_then(|h| h.to_str().ok())?;
+		self.policy
+			.allowed_origins
+			.iter()
+			.find(|o| o.as_str() == origin)
+			.cloned()
+	}
+}
+
+impl api::Handler for CorsMiddleware {
+	fn call(
+		&self,
+		req: Request<Body>,
+		mut handlers: Box<dyn Iterator<Item = HandlerObj>>,
+	) -> ResponseFuture {
+		let next_handler = match handlers.next() {
+			Some(h) => h,
+			None => return reject(StatusCode::INTERNAL_SERVER_ERROR, "no handler found"),
+		};
+		let allowed_origin = self
+			.allowed_origin(&req)
+			.and_then(|o| HeaderValue::from_str(&o).ok());
+
+		if *req.method() == Method::OPTIONS {
+			let origin = match allowed_origin {
+				Some(o) => o,
+				None => return reject(StatusCode::FORBIDDEN, "Origin not allowed"),
+			};
+			return Box::new(ok(Response::builder()
+				.status(StatusCode::OK)
+				.header(ACCESS_CONTROL_ALLOW_ORIGIN, origin)
+				.header(ACCESS_CONTROL_ALLOW_METHODS, self.allowed_methods.as_str())
+				.header(ACCESS_CONTROL_ALLOW_HEADERS, self.allowed_headers.as_str())
+				.header(VARY, "Origin")
+				.body(Body::empty())
+				.unwrap()));
+		}
+
+		let allowed_headers = self.allowed_headers.clone();
+		Box::new(next_handler.call(req, handlers).map(move |mut resp| {
+			let headers = resp.headers_mut();
+			headers.insert(VARY, HeaderValue::from_static("Origin"));
+			if let Some(o) = allowed_origin {
+				headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, o);
+				if let Ok(h) = HeaderValue::from_str(&allowed_headers) {
+					headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, h);
+				}
+			}
+			resp
+		}))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn tarpit_caps_connections() {
+		let limits = RequestLimitsMiddleware::new(1024, Some(1)).with_tarpit(TarpitConfig {
+			max_connections_per_client: 2,
+			max_connections: 3,
+			..TarpitConfig::default()
+		});
+		let tarpit = limits.tarpit.as_ref().unwrap();
+		let a1 = tarpit.hold("a").unwrap();
+		let _a2 = tarpit.hold("a").unwrap();
+		assert!(tarpit.hold("a").is_none());
+		let _b1 = tarpit.hold("b").unwrap();
+		assert!(tarpit.hold("c").is_none());
+
+		// released connections free their place
+		drop(a1);
+		assert!(tarpit.hold("c").is_some());
+		assert!(tarpit.hold("a").is_some());
+		assert_eq!(tarpit.held.lock().by_client.get("c"), None);
+	}
+
+	#[test]
+	fn clients_tracked_up_to_cap() {
+		let mut map = LruMap::new(2);
+		*map.get_or_insert_with("a", || 0) += 1;
+		*map.get_or_insert_with("b", || 0) += 1;
+		*map.get_or_insert_with("a", || 0) += 1;
+		// b is the least recently used, so makes way for c
+		*map.get_or_insert_with("c", || 0) += 1;
+		assert_eq!(map.entries.len(), 2);
+		assert_eq!(map.order.len(), 2);
+		assert_eq!(map.entries.get("a").map(|e| e.1), Some(2));
+		assert!(map.entries.get("b").is_none());
+		// a new b doesn't remember the one forgotten
+		assert_eq!(*map.get_or_insert_with("b", || 0), 0);
+		assert!(map.entries.get("a").is_none());
+	}
+
+	#[test]
+	fn forwarded_headers_only_trusted_from_proxies() {
+		let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+		let limits = RequestLimitsMiddleware::new(1024, Some(1)).with_trusted_proxies(vec![proxy]);
+		let request = |peer: &str, forwarded: Option<&str>| {
+			let mut builder = Request::builder();
+			if let Some(f) = forwarded {
+				builder.header("x-forwarded-for", f);
+			}
+			let mut req = builder.body(Body::empty()).unwrap();
+			req.extensions_mut()
+				.insert(PeerAddr(format!("{}:3415", peer).parse().unwrap()));
+			req
+		};
+
+		let spoofed = request("192.168.1.5", Some("1.2.3.4"));
+		assert_eq!(limits.client_key(&spoofed), "192.168.1.5");
+		let proxied = request("10.0.0.1", Some("1.2.3.4, 5.6.7.8"));
+		assert_eq!(limits.client_key(&proxied), "5.6.7.8");
+		let direct = request("10.0.0.1", None);
+		assert_eq!(limits.client_key(&direct), "10.0.0.1");
+		let unknown = Request::builder().body(Body::empty()).unwrap();
+		assert_eq!(limits.client_key(&unknown), "unknown");
+	}
+}
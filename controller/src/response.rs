@@ -0,0 +1,158 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Rendering of JSON-RPC responses, pretty-printed or compact as configured
+//! for the listener or asked for by the request's `pretty` query parameter.
+//! A response over the listener's maximum size is replaced by an error, with
+//! a `truncated` member giving its size and hinting at how to ask for less
+
+use hyper::{Body, Request};
+use serde_json::{json, Value};
+
+use crate::libwallet::ErrorKind;
+
+/// How to ask for less data, given in truncated responses
+const TRUNCATION_HINT: &str = "Page through transactions and outputs with retrieve_txs and \
+                               query_outputs, giving a limit and offset, or ask for fewer items";
+
+/// How a listener renders its responses
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResponseFormat {
+	/// Whether responses are pretty-printed
+	pub pretty: bool,
+	/// Largest response to send, in bytes, if limited
+	pub max_bytes: Option<u64>,
+}
+
+impl Default for ResponseFormat {
+	fn default() -> ResponseFormat {
+		ResponseFormat {
+			pretty: true,
+			max_bytes: None,
+		}
+	}
+}
+
+impl ResponseFormat {
+	/// The format to answer a request in, pretty-printed or not as its
+	/// `pretty` query parameter asks, if given
+	pub fn for_request(&self, req: &Request<Body>) -> ResponseFormat {
+		let pretty = req.uri().query().and_then(|q| {
+			url::form_urlencoded::parse(q.as_bytes())
+				.find(|(k, _)| k == "pretty")
+				.and_then(|(_, v)| match v.as_ref() {
+					"" | "true" | "1" => Some(true),
+					"false" | "0" => Some(false),
+					_ => None,
+				})
+		});
+		ResponseFormat {
+			pretty: pretty.unwrap_or(self.pretty),
+			max_bytes: self.max_bytes,
+		}
+	}
+
+	/// Render a response, replaced by a truncation error if over the maximum
+	/// size
+	pub fn render(&self, resp: &Value) -> Result<String, serde_json::Error> {
+		let body = self.to_string(resp)?;
+		match self.max_bytes {
+			Some(max) if body.len() as u64 > max => {
+				warn!(
+					"Replacing API response of {} bytes, over the maximum of {}",
+					body.len(),
+					max
+				);
+				self.to_string(&truncated(resp, body.len() as u64, max))
+			}
+			_ => Ok(body),
+		}
+	}
+
+	fn to_string(&self, resp: &Value) -> Result<String, serde_json::Error> {
+		match self.pretty {
+			true => serde_json::to_string_pretty(resp),
+			false => serde_json::to_string(resp),
+		}
+	}
+}
+
+/// The error replacing a response, or each response of a batch, over the
+/// maximum size
+fn truncated(resp: &Value, size: u64, max_bytes: u64) -> Value {
+	let replace = |r: &Value| {
+		json!({
+			"id": r.get("id"),
+			"jsonrpc": "2.0",
+			"result": {
+				"Err": ErrorKind::GenericError(format!(
+					"Response of {} bytes is over the maximum of {} bytes",
+					size, max_bytes
+				)),
+			},
+			"truncated": {
+				"size": size,
+				"max_size": max_bytes,
+				"hint": TRUNCATION_HINT,
+			},
+		})
+	};
+	match resp {
+		Value::Array(resps) => Value::Array(resps.iter().map(replace).collect()),
+		r => replace(r),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn renders_and_truncates() {
+		let resp = json!({"jsonrpc": "2.0", "id": 1, "result": {"Ok": [1, 2, 3]}});
+		let format = ResponseFormat {
+			pretty: false,
+			max_bytes: None,
+		};
+		assert_eq!(
+			format.render(&resp).unwrap(),
+			r#"{"id":1,"jsonrpc":"2.0","result":{"Ok":[1,2,3]}}"#
+		);
+		assert!(ResponseFormat::default()
+			.render(&resp)
+			.unwrap()
+			.contains('\n'));
+
+		let req = Request::post("/v3/owner?pretty=true")
+			.body(Body::empty())
+			.unwrap();
+		assert!(format.for_request(&req).pretty);
+		let req = Request::post("/v3/owner").body(Body::empty()).unwrap();
+		assert!(!format.for_request(&req).pretty);
+
+		let format = ResponseFormat {
+			pretty: false,
+			max_bytes: Some(20),
+		};
+		let rendered: Value = serde_json::from_str(&format.render(&resp).unwrap()).unwrap();
+		assert_eq!(rendered["id"], json!(1));
+		assert_eq!(rendered["truncated"]["size"], json!(48));
+		assert_eq!(rendered["truncated"]["max_size"], json!(20));
+		assert!(rendered["result"]["Err"]["GenericError"].is_string());
+
+		let batch = json!([resp.clone(), resp]);
+		let rendered: Value = serde_json::from_str(&format.render(&batch).unwrap()).unwrap();
+		assert_eq!(rendered.as_array().unwrap().len(), 2);
+	}
+}
@@ -0,0 +1,246 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! HTTP(S) server for listeners on a TCP port, telling handlers the address
+//! of the peer each request came from
+
+use crate::api::{ResponseFuture, Router};
+use crate::libwallet::{resource_limits, Error, ErrorKind};
+use futures::future;
+use futures::sync::{mpsc, oneshot};
+use futures::{Future, Poll, Stream};
+use hyper::service::{make_service_fn, Service};
+use hyper::{Body, Request, Server};
+use rustls::ServerConfig;
+use std::io::{self, Read, Write};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio::runtime::Builder;
+use tokio::timer::Timeout;
+use tokio_rustls::TlsAcceptor;
+
+/// How long a client has to complete the TLS handshake
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Address of the peer a request came from, set in the extensions of each
+/// request served by [`start_tcp_server`](fn.start_tcp_server.html)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PeerAddr(pub SocketAddr);
+
+/// A connection, along with the address of its peer
+struct PeerStream<S> {
+	stream: S,
+	peer: SocketAddr,
+}
+
+impl<S: Read> Read for PeerStream<S> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		self.stream.read(buf)
+	}
+}
+
+impl<S: Write> Write for PeerStream<S> {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		self.stream.write(buf)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		self.stream.flush()
+	}
+}
+
+impl<S: AsyncRead> AsyncRead for PeerStream<S> {}
+
+impl<S: AsyncWrite> AsyncWrite for PeerStream<S> {
+	fn shutdown(&mut self) -> Poll<(), io::Error> {
+		self.stream.shutdown()
+	}
+}
+
+/// Router serving the requests of one connection
+struct PeerService {
+	router: Router,
+	peer: SocketAddr,
+}
+
+impl Service for PeerService {
+	type ReqBody = Body;
+	type ResBody = Body;
+	type Error = hyper::Error;
+	type Future = ResponseFuture;
+
+	fn call(&mut self, mut req: Request<Body>) -> ResponseFuture {
+		req.extensions_mut().insert(PeerAddr(self.peer));
+		self.router.call(req)
+	}
+}
+
+/// Run a listener's server to completion on a runtime with as many threads
+/// as the process's resource limits allow
+pub(crate) fn run<F>(server: F)
+where
+	F: Future<Item = (), Error = ()> + Send + 'static,
+{
+	let mut builder = Builder::new();
+	if let Some(threads) = resource_limits().listener_threads {
+		builder.core_threads(threads.max(1));
+	}
+	match builder.build() {
+		Ok(mut rt) => {
+			rt.spawn(server);
+			let _ = rt.shutdown_on_idle().wait();
+		}
+		Err(e) => error!("Unable to start API server runtime: {}", e),
+	}
+}
+
+/// Serve the router to the connections yielded by `incoming` until `stop`
+/// is sent to
+fn serve<I, S>(
+	incoming: I,
+	router: Router,
+	stop: oneshot::Receiver<()>,
+) -> impl Future<Item = (), Error = ()>
+where
+	I: Stream<Item = PeerStream<S>, Error = io::Error> + Send + 'static,
+	S: AsyncRead + AsyncWrite + Send + 'static,
+{
+	let make_service = make_service_fn(move |s: &PeerStream<S>| -> Result<_, hyper::Error> {
+		Ok(PeerService {
+			router: router.clone(),
+			peer: s.peer,
+		})
+	});
+	Server::builder(incoming)
+		.serve(make_service)
+		.with_graceful_shutdown(stop)
+		.map_err(|e| error!("API server error: {}", e))
+}
+
+/// Start an HTTP server for the router on `addr`, or an HTTPS server if a TLS
+/// configuration is given. Returns the server thread and a sender which stops
+/// the server when sent to
+pub fn start_tcp_server(
+	addr: SocketAddr,
+	router: Router,
+	tls: Option<Arc<ServerConfig>>,
+) -> Result<(JoinHandle<()>, oneshot::Sender<()>), Error> {
+	let listener = TcpListener::bind(&addr)
+		.map_err(|e| ErrorKind::GenericError(format!("Failed to bind to {}: {}", addr, e)))?;
+	let (tx, rx) = oneshot::channel::<()>();
+	let api_thread = thread::Builder::new()
+		.name("apis".to_string())
+		.spawn(move || {
+			// a connection failing before it's served doesn't stop the server
+			let incoming = listener
+				.incoming()
+				.then(|r| Ok::<_, io::Error>(r.ok()))
+				.filter_map(|s| s)
+				.filter_map(|s| s.peer_addr().ok().map(|peer| (s, peer)));
+			match tls {
+				None => {
+					let incoming = incoming.map(|(stream, peer)| PeerStream { stream, peer });
+					run(serve(incoming, router, rx));
+				}
+				Some(config) => {
+					let acceptor = TlsAcceptor::from(config);
+					let (conn_tx, conn_rx) = mpsc::unbounded();
+					let (accept_stop_tx, accept_stop_rx) = oneshot::channel::<()>();
+					// each handshake is its own task, so a slow client doesn't
+					// hold up the others
+					let accept = incoming
+						.for_each(move |(s, peer)| {
+							let conn_tx = conn_tx.clone();
+							let handshake = Timeout::new(acceptor.accept(s), TLS_HANDSHAKE_TIMEOUT)
+								.then(move |r| {
+									match r.map_err(|e| e.into_inner()) {
+										Ok(stream) => {
+											let _ =
+												conn_tx.unbounded_send(PeerStream { stream, peer });
+										}
+										// includes clients without an acceptable certificate
+										Err(Some(e)) => {
+											warn!("TLS handshake with {} failed: {}", peer, e)
+										}
+										Err(None) => warn!("TLS handshake with {} timed out", peer),
+									}
+									Ok::<_, ()>(())
+								});
+							tokio::spawn(handshake);
+							Ok(())
+						})
+						.map_err(|e| error!("API server error: {}", e))
+						.select(accept_stop_rx.map_err(|_| ()))
+						.map(|_| ())
+						.map_err(|_| ());
+					let conns = conn_rx.map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe));
+					run(future::lazy(move || {
+						tokio::spawn(accept);
+						serve(conns, router, rx).then(move |r| {
+							let _ = accept_stop_tx.send(());
+							r
+						})
+					}));
+				}
+			}
+		})
+		.map_err(|e| ErrorKind::GenericError(format!("Failed to spawn API thread: {}", e)))?;
+	Ok((api_thread, tx))
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::api::{self, Handler};
+	use futures::future::ok;
+	use hyper::{Response, StatusCode};
+	use std::io::{Read, Write};
+	use std::net::TcpStream;
+
+	struct EchoPeer;
+
+	impl Handler for EchoPeer {
+		fn get(&self, req: Request<Body>) -> api::ResponseFuture {
+			let peer = req.extensions().get::<PeerAddr>().map(|p| p.0.ip());
+			Box::new(ok(Response::builder()
+				.status(StatusCode::OK)
+				.body(peer.map(|p| p.to_string()).unwrap_or_default().into())
+				.unwrap()))
+		}
+	}
+
+	#[test]
+	fn tells_handlers_the_peer() {
+		let mut router = Router::new();
+		router.add_route("/peer", Arc::new(EchoPeer)).unwrap();
+		let addr: SocketAddr = "127.0.0.1:23419".parse().unwrap();
+		let (api_thread, stop) = start_tcp_server(addr, router, None).unwrap();
+
+		let mut stream = TcpStream::connect(addr).unwrap();
+		stream
+			.write_all(b"GET /peer HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+			.unwrap();
+		let mut response = String::new();
+		stream.read_to_string(&mut response).unwrap();
+		assert!(response.starts_with("HTTP/1.1 200"));
+		assert!(response.ends_with("\r\n\r\n127.0.0.1"));
+
+		stop.send(()).unwrap();
+		api_thread.join().unwrap();
+	}
+}
@@ -0,0 +1,115 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! TLS configuration of the listeners, and an HTTPS server requiring clients
+//! to present a certificate signed by a configured CA (mutual TLS)
+
+use crate::api::{Router, TLSConfig};
+use crate::libwallet::{Error, ErrorKind};
+use crate::server::start_tcp_server;
+use futures::sync::oneshot;
+use rustls::internal::pemfile;
+use rustls::{AllowAnyAuthenticatedClient, NoClientAuth, RootCertStore, ServerConfig};
+use std::fs::File;
+use std::io::BufReader;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// TLS configuration requiring client certificates
+#[derive(Clone)]
+pub struct ClientAuthTLSConfig {
+	/// Server certificate and private key
+	pub tls: TLSConfig,
+	/// File containing the PEM encoded certificates of the CAs trusted to
+	/// sign client certificates
+	pub client_ca_file: String,
+}
+
+impl ClientAuthTLSConfig {
+	/// Create a new configuration
+	pub fn new(tls: TLSConfig, client_ca_file: String) -> ClientAuthTLSConfig {
+		ClientAuthTLSConfig {
+			tls,
+			client_ca_file,
+		}
+	}
+
+	/// Build the server configuration, loading the server's certificate and
+	/// key and the client CA certificates
+	pub fn build_server_config(&self) -> Result<Arc<ServerConfig>, Error> {
+		server_config(&self.tls, Some(&self.client_ca_file))
+	}
+}
+
+fn open(path: &str) -> Result<BufReader<File>, Error> {
+	let file = File::open(path)
+		.map_err(|e| ErrorKind::GenericError(format!("Unable to open TLS file {}: {}", path, e)))?;
+	Ok(BufReader::new(file))
+}
+
+/// Build a server configuration with the server's certificate and key,
+/// requiring clients to present a certificate signed by one of the CAs in
+/// `client_ca_file` if given
+pub fn server_config(
+	tls: &TLSConfig,
+	client_ca_file: Option<&str>,
+) -> Result<Arc<ServerConfig>, Error> {
+	let mut config = match client_ca_file {
+		Some(ca_file) => {
+			let mut roots = RootCertStore::empty();
+			let (valid, _) = roots.add_pem_file(&mut open(ca_file)?).map_err(|_| {
+				ErrorKind::GenericError(format!("Unable to parse client CA file {}", ca_file))
+			})?;
+			if valid == 0 {
+				return Err(ErrorKind::GenericError(format!(
+					"No valid certificates found in client CA file {}",
+					ca_file
+				)))?;
+			}
+			ServerConfig::new(AllowAnyAuthenticatedClient::new(roots))
+		}
+		None => ServerConfig::new(NoClientAuth::new()),
+	};
+
+	let certs = pemfile::certs(&mut open(&tls.certificate)?).map_err(|_| {
+		ErrorKind::GenericError(format!(
+			"Unable to parse certificate file {}",
+			tls.certificate
+		))
+	})?;
+	let mut keys = pemfile::pkcs8_private_keys(&mut open(&tls.private_key)?).unwrap_or_default();
+	if keys.is_empty() {
+		keys = pemfile::rsa_private_keys(&mut open(&tls.private_key)?).unwrap_or_default();
+	}
+	let key = keys.pop().ok_or_else(|| {
+		ErrorKind::GenericError(format!("No private key found in {}", tls.private_key))
+	})?;
+
+	config
+		.set_single_cert(certs, key)
+		.map_err(|e| ErrorKind::GenericError(format!("Invalid key or certificate: {}", e)))?;
+	Ok(Arc::new(config))
+}
+
+/// Start an HTTPS server for the router, rejecting connections from clients
+/// that don't present a certificate signed by one of the configured CAs.
+/// Returns the server thread and a sender which stops the server when sent to
+pub fn start_client_auth_server(
+	addr: SocketAddr,
+	router: Router,
+	conf: &ClientAuthTLSConfig,
+) -> Result<(JoinHandle<()>, oneshot::Sender<()>), Error> {
+	start_tcp_server(addr, router, Some(conf.build_server_config()?))
+}
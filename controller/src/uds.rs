@@ -0,0 +1,112 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! HTTP server listening on a unix domain socket rather than a TCP port, for
+//! local processes embedding the wallet. Access is controlled by the socket
+//! file's permissions, which only let the wallet's user connect
+
+use crate::api::Router;
+use crate::libwallet::{Error, ErrorKind};
+use crate::server::run;
+use futures::sync::oneshot;
+use futures::Future;
+use hyper::Server;
+use std::fs;
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::path::PathBuf;
+use std::thread::{self, JoinHandle};
+use tokio::net::UnixListener;
+
+/// Start an HTTP server for the router on the unix socket at `path`, replacing
+/// any socket file left there by a previous run. The socket file is only
+/// accessible to the wallet's user, and is removed when the server stops.
+/// Returns the server thread and a sender which stops the server when sent to
+pub fn start_unix_socket_server(
+	path: &str,
+	router: Router,
+) -> Result<(JoinHandle<()>, oneshot::Sender<()>), Error> {
+	let path = PathBuf::from(path);
+	if let Ok(meta) = fs::symlink_metadata(&path) {
+		if !meta.file_type().is_socket() {
+			return Err(ErrorKind::GenericError(format!(
+				"{} exists and isn't a socket",
+				path.display()
+			)))?;
+		}
+		fs::remove_file(&path).map_err(|e| {
+			ErrorKind::GenericError(format!("Unable to remove {}: {}", path.display(), e))
+		})?;
+	}
+	let listener = UnixListener::bind(&path).map_err(|e| {
+		ErrorKind::GenericError(format!("Failed to bind to {}: {}", path.display(), e))
+	})?;
+	fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).map_err(|e| {
+		ErrorKind::GenericError(format!(
+			"Unable to set permissions of {}: {}",
+			path.display(),
+			e
+		))
+	})?;
+
+	let (tx, rx) = oneshot::channel::<()>();
+	let api_thread = thread::Builder::new()
+		.name("apis".to_string())
+		.spawn(move || {
+			let server = Server::builder(listener.incoming())
+				.serve(router)
+				.with_graceful_shutdown(rx)
+				.map_err(|e| error!("Unix socket API server error: {}", e));
+			run(server);
+			let _ = fs::remove_file(&path);
+		})
+		.map_err(|e| ErrorKind::GenericError(format!("Failed to spawn API thread: {}", e)))?;
+	Ok((api_thread, tx))
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use std::io::{Read, Write};
+	use std::os::unix::net::UnixStream;
+	use std::path::Path;
+
+	#[test]
+	fn serves_on_socket() {
+		let dir = "test_output/uds";
+		let _ = fs::remove_dir_all(dir);
+		fs::create_dir_all(dir).unwrap();
+		let path = format!("{}/owner.sock", dir);
+		// a stale socket is replaced, anything else is left alone
+		fs::write(&path, "").unwrap();
+		assert!(start_unix_socket_server(&path, Router::new()).is_err());
+		fs::remove_file(&path).unwrap();
+		drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+
+		let (api_thread, stop) = start_unix_socket_server(&path, Router::new()).unwrap();
+		let mode = fs::metadata(&path).unwrap().permissions().mode();
+		assert_eq!(mode & 0o777, 0o600);
+
+		let mut stream = UnixStream::connect(&path).unwrap();
+		stream
+			.write_all(b"GET /v3/owner HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+			.unwrap();
+		let mut response = String::new();
+		stream.read_to_string(&mut response).unwrap();
+		assert!(response.starts_with("HTTP/1.1 404"));
+
+		stop.send(()).unwrap();
+		api_thread.join().unwrap();
+		assert!(!Path::new(&path).exists());
+	}
+}
@@ -0,0 +1,146 @@
+// Copyright 2019 The Grin Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! tests moving an account between wallets with the same seed
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_util::grin_keychain as keychain;
+use grin_wallet_util::grin_util as util;
+
+use self::keychain::{ExtKeychain, Keychain};
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use std::thread;
+use std::time::Duration;
+use util::ZeroingString;
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+fn account_transfer_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	let mnemonic = {
+		let mut w_lock = wallet1.lock();
+		let lc = w_lock.lc_provider()?;
+		lc.get_mnemonic(None, ZeroingString::from(""))?
+	};
+
+	// same seed as wallet1
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		Some(mnemonic),
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	create_wallet_and_add!(
+		client3,
+		wallet3,
+		mask3_i,
+		test_dir,
+		"wallet3",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask3 = (&mask3_i).as_ref();
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let path = ExtKeychain::derive_key_id(2, 1, 0, 0, 0);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		api.create_account_path(m, "savings")?;
+		api.set_active_account(m, "savings")?;
+		Ok(())
+	})?;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+
+	let mut export = String::new();
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		export = api.export_account(m, "savings")?;
+		Ok(())
+	})?;
+	let child_index = {
+		wallet_inst!(wallet1, w);
+		w.current_child_index(&path)?
+	};
+
+	// importing twice gives the same result as importing once
+	for _ in 0..2 {
+		wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+			let account = api.import_account(m, &export)?;
+			assert_eq!(account.label, "savings");
+			assert_eq!(account.path, path);
+			api.set_active_account(m, "savings")?;
+			let (_, txs) = api.retrieve_txs(m, false, None, None, None)?;
+			assert_eq!(txs.len(), 3);
+			let (_, outputs) = api.retrieve_outputs(m, true, false, None)?;
+			assert_eq!(outputs.len(), 3);
+			for o in outputs {
+				let tx_id = o.output.tx_log_entry.unwrap();
+				assert!(txs.iter().any(|t| t.id == tx_id));
+			}
+			Ok(())
+		})?;
+	}
+	{
+		wallet_inst!(wallet2, w);
+		assert_eq!(w.current_child_index(&path)?, child_index);
+	}
+
+	// a wallet with another seed can't read the export
+	wallet::controller::owner_single_use(wallet3.clone(), mask3, |api, m| {
+		assert!(api.import_account(m, &export).is_err());
+		assert_eq!(api.accounts(m)?.len(), 1);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn account_transfer() {
+	let test_dir = "test_output/account_transfer";
+	if let Err(e) = account_transfer_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
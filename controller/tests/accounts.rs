@@ -19,9 +19,11 @@ extern crate grin_wallet_impls as impls;
 
 use grin_wallet_util::grin_core as core;
 use grin_wallet_util::grin_keychain as keychain;
+use grin_wallet_util::grin_util as util;
 
 use self::core::global;
 use self::keychain::{ExtKeychain, Keychain};
+use grin_wallet_api::Owner;
 use grin_wallet_libwallet as libwallet;
 use impls::test_framework::{self, LocalWalletClient};
 use libwallet::InitTxArgs;
@@ -135,8 +137,37 @@ fn accounts_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
 		assert_eq!(wallet1_info.total, 5 * reward);
 		assert_eq!(wallet1_info.amount_currently_spendable, (5 - cm) * reward);
 		// check tx log as well
-		let (_, txs) = api.retrieve_txs(m, true, None, None)?;
+		let (_, txs) = api.retrieve_txs(m, true, None, None, None)?;
 		assert_eq!(txs.len(), 5);
+		// and derivations of the account's outputs
+		let (_, outputs) = api.retrieve_outputs(m, false, false, None)?;
+		let commit = util::to_hex(outputs[0].commit.as_ref().to_vec());
+		let derivation = api.get_output_derivation(m, &commit)?;
+		assert_eq!(derivation.account, Some("account2".to_owned()));
+		assert_eq!(derivation.key_id, outputs[0].output.key_id);
+		assert_eq!(derivation.key_index, outputs[0].output.n_child);
+		assert_eq!(
+			derivation.path,
+			format!("m/2/0/{}", outputs[0].output.n_child)
+		);
+		assert_eq!(derivation.switch_commitment_type, "Regular");
+		assert!(api.get_output_derivation(m, "0800").is_err());
+		// keys reserved for use elsewhere are skipped by the wallet
+		let next_index = outputs.iter().map(|o| o.output.n_child).max().unwrap() + 1;
+		let reserved = api.reserve_derivation_indices(m, 3)?;
+		assert_eq!(reserved.account, Some("account2".to_owned()));
+		assert_eq!(reserved.first_index, next_index);
+		assert_eq!(reserved.count, 3);
+		assert_eq!(reserved.key_ids.len(), 3);
+		assert_eq!(reserved.key_ids[0].to_path().last_path_index(), next_index);
+		assert_eq!(
+			reserved.key_ids[2].to_path().last_path_index(),
+			next_index + 2
+		);
+		let reserved = api.reserve_derivation_indices(m, 1)?;
+		assert_eq!(reserved.first_index, next_index + 3);
+		assert!(api.reserve_derivation_indices(m, 0).is_err());
+		assert!(api.reserve_derivation_indices(m, 1001).is_err());
 		Ok(())
 	})?;
 	// now check second account
@@ -156,8 +187,19 @@ fn accounts_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
 		assert_eq!(wallet1_info.total, 7 * reward);
 		assert_eq!(wallet1_info.amount_currently_spendable, 7 * reward);
 		// check tx log as well
-		let (_, txs) = api.retrieve_txs(m, true, None, None)?;
+		let (_, txs) = api.retrieve_txs(m, true, None, None, None)?;
 		assert_eq!(txs.len(), 7);
+		assert_eq!(txs[3].label, None);
+		// label a tx, then clear the label
+		api.set_tx_label(m, txs[3].id, Some("Mining reward".to_owned()))?;
+		let (_, txs) = api.retrieve_txs(m, false, Some(txs[3].id), None, None)?;
+		assert_eq!(txs[0].label, Some("Mining reward".to_owned()));
+		api.set_tx_label(m, txs[0].id, Some("".to_owned()))?;
+		let (_, txs) = api.retrieve_txs(m, false, Some(txs[0].id), None, None)?;
+		assert_eq!(txs[0].label, None);
+		assert!(api
+			.set_tx_label(m, txs[0].id, Some("x".repeat(257)))
+			.is_err());
 		Ok(())
 	})?;
 
@@ -175,8 +217,10 @@ fn accounts_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
 		assert_eq!(wallet1_info.total, 0,);
 		assert_eq!(wallet1_info.amount_currently_spendable, 0,);
 		// check tx log as well
-		let (_, txs) = api.retrieve_txs(m, true, None, None)?;
+		let (_, txs) = api.retrieve_txs(m, true, None, None, None)?;
 		assert_eq!(txs.len(), 0);
+		// txs of other accounts can't be labelled
+		assert!(api.set_tx_label(m, 3, Some("Other".to_owned())).is_err());
 		Ok(())
 	})?;
 
@@ -199,8 +243,8 @@ fn accounts_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
 		let mut slate = api.init_send_tx(m, args)?;
 		slate = client1.send_tx_slate_direct("wallet2", &slate)?;
 		api.tx_lock_outputs(m, &slate, 0)?;
-		slate = api.finalize_tx(m, &slate)?;
-		api.post_tx(m, &slate.tx, false)?;
+		slate = api.finalize_tx(m, &slate, None)?;
+		api.post_tx(m, &slate.tx, Some(false))?;
 		Ok(())
 	})?;
 
@@ -208,7 +252,7 @@ fn accounts_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
 		let (wallet1_refreshed, wallet1_info) = api.retrieve_summary_info(m, true, 1)?;
 		assert!(wallet1_refreshed);
 		assert_eq!(wallet1_info.last_confirmed_height, 13);
-		let (_, txs) = api.retrieve_txs(m, true, None, None)?;
+		let (_, txs) = api.retrieve_txs(m, true, None, None, None)?;
 		assert_eq!(txs.len(), 9);
 		Ok(())
 	})?;
@@ -223,7 +267,7 @@ fn accounts_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
 		assert_eq!(wallet1_info.last_confirmed_height, 12);
 		let (_, wallet1_info) = api.retrieve_summary_info(m, true, 1)?;
 		assert_eq!(wallet1_info.last_confirmed_height, 13);
-		let (_, txs) = api.retrieve_txs(m, true, None, None)?;
+		let (_, txs) = api.retrieve_txs(m, true, None, None, None)?;
 		println!("{:?}", txs);
 		assert_eq!(txs.len(), 5);
 		Ok(())
@@ -234,7 +278,7 @@ fn accounts_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
 		let (wallet2_refreshed, wallet2_info) = api.retrieve_summary_info(m, true, 1)?;
 		assert!(wallet2_refreshed);
 		assert_eq!(wallet2_info.last_confirmed_height, 13);
-		let (_, txs) = api.retrieve_txs(m, true, None, None)?;
+		let (_, txs) = api.retrieve_txs(m, true, None, None, None)?;
 		assert_eq!(txs.len(), 1);
 		Ok(())
 	})?;
@@ -252,11 +296,23 @@ fn accounts_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
 		assert_eq!(wallet2_info.total, 0,);
 		assert_eq!(wallet2_info.amount_currently_spendable, 0,);
 		// check tx log as well
-		let (_, txs) = api.retrieve_txs(m, true, None, None)?;
+		let (_, txs) = api.retrieve_txs(m, true, None, None, None)?;
 		assert_eq!(txs.len(), 0);
 		Ok(())
 	})?;
 
+	// an API scoped to an account acts on it, leaving the active account as it was
+	{
+		let mut api = Owner::new(wallet1.clone());
+		api.scope_to_account(Some("default".to_owned()));
+		let (_, txs) = api.retrieve_txs(mask1, false, None, None, None)?;
+		assert_eq!(txs.len(), 0);
+		api.scope_to_account(Some("unknown".to_owned()));
+		assert!(api.retrieve_txs(mask1, false, None, None, None).is_err());
+		wallet_inst!(wallet1, w);
+		assert_eq!(w.parent_key_id(), ExtKeychain::derive_key_id(2, 2, 0, 0, 0));
+	}
+
 	// let logging finish
 	thread::sleep(Duration::from_millis(200));
 	Ok(())
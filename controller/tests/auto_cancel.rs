@@ -0,0 +1,128 @@
+// Copyright 2019 The Grin Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the updater cancelling transactions left unconfirmed too long
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_util::grin_core as core;
+
+use grin_wallet_api::WalletUpdater;
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, OutputStatus, StatusMessage, TxLogEntryType};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+fn auto_cancel_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+
+	// a send the other party never finalizes
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: reward * 2,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			..Default::default()
+		};
+		let slate = api.init_send_tx(m, args)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		Ok(())
+	})?;
+
+	let updater = WalletUpdater::new(wallet1.clone(), None);
+	updater.set_auto_cancel(Some(5));
+	let refresh = || {
+		updater.start(Duration::from_millis(100), mask1.cloned());
+		thread::sleep(Duration::from_millis(1000));
+		updater.stop();
+		thread::sleep(Duration::from_millis(200));
+	};
+
+	// not yet stale
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 2, false);
+	refresh();
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, false, None, None, None)?;
+		let sent = txs.iter().find(|t| t.tx_slate_id.is_some()).unwrap();
+		assert_eq!(sent.tx_type, TxLogEntryType::TxSent);
+		assert!(sent.creation_height.is_some());
+		assert!(!sent.expired);
+		Ok(())
+	})?;
+
+	// cancelled once it's gone 5 blocks without being confirmed, with its
+	// inputs unlocked
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+	refresh();
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, false, None, None, None)?;
+		let sent = txs.iter().find(|t| t.tx_slate_id.is_some()).unwrap();
+		assert_eq!(sent.tx_type, TxLogEntryType::TxSentCancelled);
+		assert!(sent.expired);
+		let (_, outputs) = api.retrieve_outputs(m, true, false, None)?;
+		assert!(outputs
+			.iter()
+			.all(|o| o.output.status != OutputStatus::Locked
+				&& o.output.status != OutputStatus::Unconfirmed));
+		Ok(())
+	})?;
+	assert!(updater.messages(100).iter().any(|m| match m {
+		StatusMessage::TxExpired(_) => true,
+		_ => false,
+	}));
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn auto_cancel() {
+	let test_dir = "test_output/auto_cancel";
+	if let Err(e) = auto_cancel_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
@@ -0,0 +1,272 @@
+// Copyright 2019 The Grin Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! tests full and incremental backups
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_util::grin_keychain as keychain;
+use grin_wallet_util::grin_util as util;
+
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use impls::{DefaultLCProvider, DefaultWalletImpl};
+use keychain::ExtKeychain;
+use libwallet::{wallet_records, BackupArchive, WalletInst};
+use std::fs;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use util::{Mutex, ZeroingString};
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+fn backup_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+	let mut backups = vec![];
+	let mut states = vec![];
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		// the first incremental backup is a full one
+		let full = api.backup(m, true)?;
+		assert_eq!(full.parent, None);
+		assert_eq!(full.removed, 0);
+		backups.push(full);
+		Ok(())
+	})?;
+	states.push({
+		wallet_inst!(wallet1, w);
+		wallet_records(&mut **w)?
+	});
+
+	// later backups only hold the changes
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 2, false);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let backup = api.backup(m, true)?;
+		assert_eq!(backup.parent, Some(backups[0].id));
+		assert!(backup.changed > 0);
+		backups.push(backup);
+		Ok(())
+	})?;
+	states.push({
+		wallet_inst!(wallet1, w);
+		wallet_records(&mut **w)?
+	});
+
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		api.create_account_path(m, "savings")?;
+		let backup = api.backup(m, true)?;
+		assert_eq!(backup.parent, Some(backups[1].id));
+		// the account and its derivation index
+		assert_eq!(backup.changed, 2);
+		backups.push(backup);
+
+		// with nothing changed, a backup is empty
+		let backup = api.backup(m, true)?;
+		assert_eq!((backup.changed, backup.removed), (0, 0));
+		backups.push(backup);
+		Ok(())
+	})?;
+	let state = {
+		wallet_inst!(wallet1, w);
+		wallet_records(&mut **w)?
+	};
+	states.push(state.clone());
+	states.push(state);
+
+	// the state as of each backup can be rebuilt, whatever order the backups
+	// are given in
+	let mut data: Vec<String> = backups.iter().map(|b| b.data.clone()).collect();
+	data.reverse();
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		for (b, state) in backups.iter().zip(states.iter()) {
+			assert_eq!(&api.reconstruct_backup(m, &data, Some(b.id))?, state);
+		}
+		assert_eq!(&api.reconstruct_backup(m, &data, None)?, &states[3]);
+		// but not without the backups it depends on
+		assert!(api
+			.reconstruct_backup(m, &data[..2], Some(backups[2].id))
+			.is_err());
+		Ok(())
+	})?;
+
+	// a wallet with another seed can't read the backups
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		assert!(api.reconstruct_backup(m, &data, None).is_err());
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+fn restore_from_backup_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	// a wallet with nothing in its data directory yet
+	let client2 = LocalWalletClient::new("wallet2", wallet_proxy.tx.clone());
+	let mut wallet2 =
+		Box::new(DefaultWalletImpl::<LocalWalletClient>::new(client2.clone()).unwrap())
+			as Box<
+				WalletInst<
+					DefaultLCProvider<'static, LocalWalletClient, ExtKeychain>,
+					LocalWalletClient,
+					ExtKeychain,
+				>,
+			>;
+	wallet2
+		.lc_provider()?
+		.set_wallet_directory(&format!("{}/wallet2", test_dir));
+	let wallet2 = Arc::new(Mutex::new(wallet2));
+	wallet_proxy.add_wallet(
+		"wallet2",
+		client2.get_send_instance(),
+		wallet2.clone(),
+		None,
+	);
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+	let mut backups = vec![];
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		backups.push(api.backup(m, false)?.data);
+		api.create_account_path(m, "savings")?;
+		backups.push(api.backup(m, true)?.data);
+		Ok(())
+	})?;
+	let archive = BackupArchive {
+		seed: fs::read_to_string(format!("{}/wallet1/wallet_data/wallet.seed", test_dir))?,
+		backups,
+	};
+	let archive = serde_json::to_string(&archive).unwrap();
+
+	wallet::controller::owner_single_use(wallet2.clone(), None, |api, _| {
+		// a wrong password leaves nothing behind
+		assert!(api
+			.restore_from_backup(&archive, ZeroingString::from("wrong"))
+			.is_err());
+		assert!(api
+			.restore_from_backup("not an archive", ZeroingString::from(""))
+			.is_err());
+		api.restore_from_backup(&archive, ZeroingString::from(""))?;
+		// but a restored wallet isn't overwritten
+		assert!(api
+			.restore_from_backup(&archive, ZeroingString::from(""))
+			.is_err());
+		Ok(())
+	})?;
+
+	let mask2 =
+		wallet2
+			.lock()
+			.lc_provider()?
+			.open_wallet(None, ZeroingString::from(""), false, false)?;
+	let mask2 = mask2.as_ref();
+	let labels = |accounts: Vec<libwallet::AcctPathMapping>| -> Vec<String> {
+		accounts.into_iter().map(|a| a.label).collect()
+	};
+	let mut info1 = None;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		info1 = Some((api.accounts(m)?, api.retrieve_summary_info(m, true, 1)?.1));
+		Ok(())
+	})?;
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (accounts, info) = info1.take().unwrap();
+		assert_eq!(labels(api.accounts(m)?), labels(accounts));
+		let (refreshed, restored) = api.retrieve_summary_info(m, true, 1)?;
+		assert!(refreshed);
+		assert_eq!(restored.total, info.total);
+		assert_eq!(
+			restored.amount_currently_spendable,
+			info.amount_currently_spendable
+		);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn backup() {
+	let test_dir = "test_output/backup";
+	if let Err(e) = backup_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
+
+#[test]
+fn restore_from_backup() {
+	let test_dir = "test_output/restore_from_backup";
+	if let Err(e) = restore_from_backup_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
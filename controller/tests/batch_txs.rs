@@ -0,0 +1,115 @@
+// Copyright 2019 The Grin Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test cancelling and reposting transactions in batches
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, RetrieveTxQueryArgs, TxLogEntryType, TxQueryStatus};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+fn batch_txs_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let mut tx_ids = vec![];
+		for _ in 0..4 {
+			let args = InitTxArgs {
+				src_acct_name: None,
+				amount: 1_000_000_000,
+				minimum_confirmations: 2,
+				max_outputs: 500,
+				num_change_outputs: 1,
+				selection_strategy_is_use_all: false,
+				..Default::default()
+			};
+			let slate = api.init_send_tx(m, args)?;
+			api.tx_lock_outputs(m, &slate, 0)?;
+			let (_, txs) = api.retrieve_txs(m, false, None, Some(slate.id), None)?;
+			tx_ids.push(txs[0].id);
+		}
+
+		// a missing transaction doesn't stop the others being cancelled
+		let results = api.cancel_txs(m, Some(vec![tx_ids[0], 999, tx_ids[1]]), None)?;
+		assert_eq!(results.len(), 3);
+		assert!(results[0].error.is_none());
+		assert_eq!(results[1].tx_id, 999);
+		assert!(results[1].error.is_some());
+		assert!(results[2].error.is_none());
+
+		let filter = RetrieveTxQueryArgs {
+			statuses: Some(vec![TxQueryStatus::Unconfirmed]),
+			..Default::default()
+		};
+		let results = api.cancel_txs(m, None, Some(filter.clone()))?;
+		let mut cancelled: Vec<u32> = results.iter().map(|r| r.tx_id).collect();
+		cancelled.sort();
+		assert_eq!(cancelled, vec![tx_ids[2], tx_ids[3]]);
+		assert!(results.iter().all(|r| r.error.is_none()));
+		let (_, txs) = api.retrieve_txs(m, false, None, None, None)?;
+		let sent_cancelled = txs
+			.iter()
+			.filter(|t| t.tx_type == TxLogEntryType::TxSentCancelled)
+			.count();
+		assert_eq!(sent_cancelled, 4);
+		assert!(api.cancel_txs(m, Some(vec![]), Some(filter)).is_err());
+
+		// neither confirmed nor unknown transactions can be reposted
+		let results = api.post_txs(m, vec![0, 999], None)?;
+		assert_eq!(results.len(), 2);
+		assert!(results.iter().all(|r| r.error.is_some()));
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn batch_txs() {
+	let test_dir = "test_output/batch_txs";
+	if let Err(e) = batch_txs_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
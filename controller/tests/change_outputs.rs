@@ -0,0 +1,112 @@
+// Copyright 2019 The Grin Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test splitting the change of a send between several outputs
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, OutputStatus};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+fn change_outputs_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: 10_000_000_000,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 0,
+			selection_strategy_is_use_all: false,
+			..Default::default()
+		};
+		assert!(api.init_send_tx(m, args.clone()).is_err());
+
+		let args = InitTxArgs {
+			num_change_outputs: 3,
+			..args
+		};
+		let slate = api.init_send_tx(m, args)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		let slate = client1.send_tx_slate_direct("wallet2", &slate)?;
+		let slate = api.finalize_tx(m, &slate, None)?;
+
+		// the change is split about equally
+		let (_, txs) = api.retrieve_txs(m, false, None, Some(slate.id), None)?;
+		let (_, outputs) = api.retrieve_outputs(m, false, false, Some(txs[0].id))?;
+		let change: Vec<u64> = outputs
+			.iter()
+			.filter(|o| o.output.status == OutputStatus::Unconfirmed)
+			.map(|o| o.output.value)
+			.collect();
+		assert_eq!(change.len(), 3);
+		assert_eq!(txs[0].num_outputs, 3);
+		assert_eq!(change.iter().sum::<u64>(), txs[0].amount_credited);
+		assert!(change.iter().max().unwrap() - change.iter().min().unwrap() < 3);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn change_outputs() {
+	let test_dir = "test_output/change_outputs";
+	if let Err(e) = change_outputs_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
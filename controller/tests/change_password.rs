@@ -0,0 +1,123 @@
+// Copyright 2019 The Grin Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test changing the wallet's password
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_util::grin_util as util;
+
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::LocalWalletClient;
+use std::thread;
+use std::time::Duration;
+use util::ZeroingString;
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+fn change_password_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+	assert!(mask1.is_some());
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	// a wrong password leaves the mask as it was
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let res = api.change_password(
+			m,
+			ZeroingString::from("wrong"),
+			ZeroingString::from("new password"),
+		);
+		assert!(res.is_err());
+		api.accounts(m)?;
+		Ok(())
+	})?;
+
+	// nor does a caller without the mask
+	wallet::controller::owner_single_use(wallet1.clone(), None, |api, m| {
+		let res = api.change_password(
+			m,
+			ZeroingString::from(""),
+			ZeroingString::from("new password"),
+		);
+		assert!(res.is_err());
+		Ok(())
+	})?;
+
+	let mut new_mask = None;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		new_mask = api.change_password(
+			m,
+			ZeroingString::from(""),
+			ZeroingString::from("new password"),
+		)?;
+		Ok(())
+	})?;
+	assert!(new_mask.is_some());
+	assert_ne!(new_mask.as_ref(), mask1);
+
+	// the old mask is rejected from now on, the new one accepted
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		assert!(api.accounts(m).is_err());
+		assert!(api.retrieve_summary_info(m, false, 1).is_err());
+		Ok(())
+	})?;
+	wallet::controller::owner_single_use(wallet1.clone(), new_mask.as_ref(), |api, m| {
+		api.accounts(m)?;
+		Ok(())
+	})?;
+
+	// and the wallet opens with the new password only
+	{
+		let mut w_lock = wallet1.lock();
+		let lc = w_lock.lc_provider()?;
+		lc.close_wallet(None)?;
+		assert!(lc
+			.open_wallet(None, ZeroingString::from(""), true, false)
+			.is_err());
+		lc.open_wallet(None, ZeroingString::from("new password"), true, false)?;
+	}
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn change_password() {
+	let test_dir = "test_output/change_password";
+	if let Err(e) = change_password_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
@@ -119,7 +119,7 @@ fn check_repair_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
 		assert_eq!(wallet1_info.total, bh * reward);
 		assert_eq!(wallet1_info.amount_currently_spendable, (bh - cm) * reward);
 		// check tx log as well
-		let (_, txs) = api.retrieve_txs(m, true, None, None)?;
+		let (_, txs) = api.retrieve_txs(m, true, None, None, None)?;
 		let (c, _) = libwallet::TxLogEntry::sum_confirmed(&txs);
 		assert_eq!(wallet1_info.total, c);
 		assert_eq!(txs.len(), bh as usize);
@@ -150,15 +150,36 @@ fn check_repair_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
 	// check we have a problem now
 	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
 		let (_, wallet1_info) = api.retrieve_summary_info(m, true, 1)?;
-		let (_, txs) = api.retrieve_txs(m, true, None, None)?;
+		let (_, txs) = api.retrieve_txs(m, true, None, None, None)?;
 		let (c, _) = libwallet::TxLogEntry::sum_confirmed(&txs);
 		assert!(wallet1_info.total != c);
 		Ok(())
 	})?;
 
+	// an audit reports the problem without fixing anything
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let report = api.audit_chain(m)?;
+		assert_eq!(report.height, bh);
+		let mut unknown: Vec<_> = report.unknown_outputs.iter().map(|o| &o.key_id).collect();
+		unknown.sort();
+		assert_eq!(unknown, vec![&w1_outputs[4].key_id, &w1_outputs[10].key_id]);
+		assert!(report.unknown_outputs.iter().all(|o| o.status.is_none()));
+		assert_eq!(report.unspent_marked_spent_outputs.len(), 1);
+		assert_eq!(
+			report.unspent_marked_spent_outputs[0].key_id,
+			w1_outputs[13].key_id
+		);
+		assert!(report.missing_outputs.is_empty());
+		assert!(report.spent_unmarked_outputs.is_empty());
+		assert_eq!(api.audit_chain(m)?, report);
+		let (_, outputs) = api.retrieve_outputs(m, true, false, None)?;
+		assert_eq!(outputs.len(), w1_outputs.len() - 2);
+		Ok(())
+	})?;
+
 	// this should restore our missing outputs
 	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
-		api.check_repair(m, true)?;
+		api.check_repair(m, true, true)?;
 		Ok(())
 	})?;
 
@@ -167,6 +188,29 @@ fn check_repair_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
 		let (wallet1_refreshed, wallet1_info) = api.retrieve_summary_info(m, true, 1)?;
 		assert!(wallet1_refreshed);
 		assert_eq!(wallet1_info.total, bh * reward);
+		assert!(api.audit_chain(m)?.is_clean());
+		Ok(())
+	})?;
+
+	// an incremental check only scans outputs added since the last check,
+	// so won't restore an older output that goes missing
+	{
+		wallet_inst!(wallet1, w);
+		let restored = w
+			.iter()?
+			.find(|o| o.key_id == w1_outputs[4].key_id)
+			.unwrap();
+		let mut batch = w.batch(mask1)?;
+		batch.delete(&restored.key_id, &restored.mmr_index)?;
+		batch.commit()?;
+	}
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		api.check_repair(m, false, false)?;
+		let (_, wallet1_info) = api.retrieve_summary_info(m, true, 1)?;
+		assert_eq!(wallet1_info.total, (bh - 1) * reward);
+		api.check_repair(m, false, true)?;
+		let (_, wallet1_info) = api.retrieve_summary_info(m, true, 1)?;
+		assert_eq!(wallet1_info.total, bh * reward);
 		Ok(())
 	})?;
 
@@ -199,7 +243,7 @@ fn check_repair_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
 
 	// unlock/restore
 	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
-		api.check_repair(m, true)?;
+		api.check_repair(m, true, true)?;
 		Ok(())
 	})?;
 
@@ -405,7 +449,7 @@ fn two_wallets_one_seed_impl(test_dir: &'static str) -> Result<(), libwallet::Er
 
 	// 0) Check repair when all is okay should leave wallet contents alone
 	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
-		api.check_repair(m, true)?;
+		api.check_repair(m, true, true)?;
 		let info = wallet_info!(wallet1.clone(), m)?;
 		assert_eq!(info.amount_currently_spendable, base_amount * 6);
 		assert_eq!(info.total, base_amount * 6);
@@ -468,7 +512,7 @@ fn two_wallets_one_seed_impl(test_dir: &'static str) -> Result<(), libwallet::Er
 
 	// 2) check_repair should recover them into a single wallet
 	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
-		api.check_repair(m, true)?;
+		api.check_repair(m, true, true)?;
 		Ok(())
 	})?;
 
@@ -572,7 +616,7 @@ fn two_wallets_one_seed_impl(test_dir: &'static str) -> Result<(), libwallet::Er
 	})?;
 
 	wallet::controller::owner_single_use(wallet6.clone(), mask6, |api, m| {
-		api.check_repair(m, true)?;
+		api.check_repair(m, true, true)?;
 		Ok(())
 	})?;
 
@@ -710,7 +754,7 @@ fn two_wallets_one_seed_impl(test_dir: &'static str) -> Result<(), libwallet::Er
 		let outputs = api.retrieve_outputs(m, true, false, None)?.1;
 		assert_eq!(outputs.len(), 3);
 		assert_eq!(info.amount_currently_spendable, base_amount * 15);
-		api.check_repair(m, true)?;
+		api.check_repair(m, true, true)?;
 		let info = wallet_info!(wallet9.clone(), m)?;
 		let outputs = api.retrieve_outputs(m, true, false, None)?.1;
 		assert_eq!(outputs.len(), 6);
@@ -728,7 +772,7 @@ fn two_wallets_one_seed_impl(test_dir: &'static str) -> Result<(), libwallet::Er
 
 	// 7) Ensure check_repair creates missing accounts
 	wallet::controller::owner_single_use(wallet10.clone(), mask10, |api, m| {
-		api.check_repair(m, true)?;
+		api.check_repair(m, true, true)?;
 		api.set_active_account(m, "account_1")?;
 		let info = wallet_info!(wallet10.clone(), m)?;
 		let outputs = api.retrieve_outputs(m, true, false, None)?.1;
@@ -0,0 +1,125 @@
+// Copyright 2019 The Grin Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test selecting inputs with a custom coin selection strategy
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{CoinSelection, InitTxArgs, OutputData, OutputStatus};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+/// Spends every eligible output, counting how often it's asked to
+struct SpendEverything {
+	calls: AtomicUsize,
+}
+
+impl CoinSelection for SpendEverything {
+	fn select(
+		&self,
+		amount: u64,
+		_max_outputs: usize,
+		eligible: &[OutputData],
+	) -> Option<Vec<OutputData>> {
+		self.calls.fetch_add(1, Ordering::SeqCst);
+		match eligible.iter().map(|o| o.value).sum::<u64>() >= amount {
+			true => Some(eligible.to_vec()),
+			false => None,
+		}
+	}
+}
+
+fn coin_selection_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+	let strategy = Arc::new(SpendEverything {
+		calls: AtomicUsize::new(0),
+	});
+	libwallet::register_coin_selection("everything", strategy.clone());
+	assert!(libwallet::coin_selection_names().contains(&"everything".to_owned()));
+
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: 1_000_000_000,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			selection_strategy: Some("no_such_strategy".to_owned()),
+			..Default::default()
+		};
+		assert!(api.init_send_tx(m, args.clone()).is_err());
+
+		// the named strategy overrides the smallest first selection the flags ask for
+		let args = InitTxArgs {
+			selection_strategy: Some("everything".to_owned()),
+			..args
+		};
+		let slate = api.init_send_tx(m, args)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		let (_, txs) = api.retrieve_txs(m, false, None, Some(slate.id), None)?;
+		let (_, outputs) = api.retrieve_outputs(m, false, false, None)?;
+		let locked = outputs
+			.iter()
+			.filter(|o| o.output.status == OutputStatus::Locked)
+			.count();
+		assert!(locked > 1);
+		assert_eq!(txs[0].num_inputs, locked);
+		Ok(())
+	})?;
+	assert!(strategy.calls.load(Ordering::SeqCst) > 0);
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn coin_selection() {
+	let test_dir = "test_output/coin_selection";
+	if let Err(e) = coin_selection_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
@@ -0,0 +1,133 @@
+// Copyright 2019 The Grin Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test receiving several transactions at once through the Foreign API
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, TxLogEntryType};
+use std::collections::HashSet;
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+fn concurrent_receive_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+	let mut slates = vec![];
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		for _ in 0..4 {
+			let args = InitTxArgs {
+				src_acct_name: None,
+				amount: 1_000_000_000,
+				minimum_confirmations: 2,
+				max_outputs: 500,
+				num_change_outputs: 1,
+				selection_strategy_is_use_all: false,
+				..Default::default()
+			};
+			let slate = api.init_send_tx(m, args)?;
+			api.tx_lock_outputs(m, &slate, 0)?;
+			slates.push(slate);
+		}
+		Ok(())
+	})?;
+
+	// the first slate is received twice at once, which only one receive may do
+	slates.push(slates[0].clone());
+	let receives: Vec<_> = slates
+		.iter()
+		.cloned()
+		.map(|slate| {
+			let wallet2 = wallet2.clone();
+			let mask2 = mask2_i.clone();
+			thread::spawn(move || {
+				wallet::controller::foreign_single_use(wallet2, mask2, |api| {
+					api.receive_tx(&slate, None, None)?;
+					Ok(())
+				})
+			})
+		})
+		.collect();
+	let received = receives
+		.into_iter()
+		.filter(|r| r.join().unwrap().is_ok())
+		.count();
+	assert_eq!(received, 4);
+
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, false, None, None, None)?;
+		let received: HashSet<_> = txs
+			.iter()
+			.filter(|t| t.tx_type == TxLogEntryType::TxReceived)
+			.map(|t| t.tx_slate_id.unwrap())
+			.collect();
+		assert_eq!(received.len(), 4);
+		assert_eq!(txs.len(), 4);
+		let (_, outputs) = api.retrieve_outputs(m, false, false, None)?;
+		let keys: HashSet<_> = outputs.iter().map(|o| o.output.key_id.clone()).collect();
+		assert_eq!(keys.len(), 4);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn concurrent_receive() {
+	let test_dir = "test_output/concurrent_receive";
+	if let Err(e) = concurrent_receive_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
@@ -0,0 +1,133 @@
+// Copyright 2019 The Grin Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test consolidating a wallet's outputs
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_util::grin_core as core;
+
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{ConsolidateArgs, OutputStatus};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+fn consolidate_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		let (_, outputs_before) = api.retrieve_outputs(m, false, true, None)?;
+
+		// room for 3 inputs, an output and a kernel per transaction, and 2
+		// transactions
+		let args = ConsolidateArgs {
+			minimum_confirmations: 1,
+			num_outputs: 1,
+			max_weight: 3 + 21 + 3,
+			max_txs: 2,
+			..Default::default()
+		};
+		let slates = api.consolidate(m, args)?;
+		assert_eq!(slates.len(), 2);
+		for s in slates.iter() {
+			assert_eq!(s.tx.inputs().len(), 3);
+			assert_eq!(s.tx.outputs().len(), 1);
+		}
+
+		// posting mines each transaction in a block rewarding the wallet, so
+		// only the rewards are gained, with the fees paid back
+		let (_, info_after) = api.retrieve_summary_info(m, true, 1)?;
+		assert_eq!(info_after.total, info.total + 2 * reward);
+		let (_, outputs) = api.retrieve_outputs(m, false, true, None)?;
+		assert_eq!(outputs.len(), outputs_before.len() - 6 + 2 + 2);
+		let (_, txs) = api.retrieve_txs(m, true, None, None, None)?;
+		let consolidations: Vec<_> = txs
+			.iter()
+			.filter(|t| t.tx_slate_id == Some(slates[0].id) || t.tx_slate_id == Some(slates[1].id))
+			.collect();
+		assert_eq!(consolidations.len(), 2);
+		assert!(consolidations.iter().all(|t| t.confirmed));
+
+		// the value is split between the outputs
+		let args = ConsolidateArgs {
+			minimum_confirmations: 1,
+			num_outputs: 2,
+			..Default::default()
+		};
+		let slates = api.consolidate(m, args)?;
+		assert_eq!(slates.len(), 1);
+		let (_, outputs) = api.retrieve_outputs(m, false, true, None)?;
+		let created: Vec<u64> = outputs
+			.iter()
+			.filter(|o| o.output.tx_log_entry.is_some() && o.output.status == OutputStatus::Unspent)
+			.filter(|o| !o.output.is_coinbase)
+			.map(|o| o.output.value)
+			.collect();
+		assert_eq!(created.len(), 2);
+		assert!(created[0].max(created[1]) - created[0].min(created[1]) <= 1);
+		assert!(created.iter().sum::<u64>() > reward * 2);
+
+		// with fewer spendable outputs than it would create, there's nothing
+		// to do
+		let args = ConsolidateArgs {
+			minimum_confirmations: 1,
+			num_outputs: 50,
+			..Default::default()
+		};
+		assert!(api.consolidate(m, args)?.is_empty());
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn consolidate() {
+	let test_dir = "test_output/consolidate";
+	if let Err(e) = consolidate_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
@@ -0,0 +1,188 @@
+// Copyright 2019 The Grin Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test wallets laying out their keys along another derivation scheme's paths
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_util::grin_keychain as keychain;
+use grin_wallet_util::grin_util as util;
+
+use self::keychain::{ExtKeychain, Identifier, Keychain};
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{DerivationScheme, InitTxArgs};
+use std::fs;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use util::ZeroingString;
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+/// Accounts at `m/44/k`, their outputs at `m/44/k/i`
+struct Purpose44;
+
+impl DerivationScheme for Purpose44 {
+	fn keychain_seed(&self, entropy: &[u8], _: Option<&str>) -> Result<Vec<u8>, libwallet::Error> {
+		Ok(entropy.to_vec())
+	}
+
+	fn account_path(&self, accounts: &[Identifier]) -> Identifier {
+		let k = accounts
+			.iter()
+			.map(|a| <u32>::from(a.to_path().path[1]) + 1)
+			.max()
+			.unwrap_or(0);
+		ExtKeychain::derive_key_id(2, 44, k, 0, 0)
+	}
+}
+
+fn derivation_scheme_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+	create_wallet_and_add!(
+		client3,
+		wallet3,
+		mask3_i,
+		test_dir,
+		"wallet3",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask3 = (&mask3_i).as_ref();
+
+	// wallet 2 is reopened with the other scheme, and wallet 3 on wallet 2's
+	// seed to restore it later
+	{
+		let mut w_lock = wallet2.lock();
+		let lc = w_lock.lc_provider()?;
+		lc.close_wallet(None)?;
+		lc.set_derivation_scheme(Arc::new(Purpose44));
+		lc.open_wallet(None, ZeroingString::from(""), false, false)?;
+	}
+	{
+		let mut w_lock = wallet3.lock();
+		let lc = w_lock.lc_provider()?;
+		lc.close_wallet(None)?;
+		fs::copy(
+			format!("{}/wallet2/wallet_data/wallet.seed", test_dir),
+			format!("{}/wallet3/wallet_data/wallet.seed", test_dir),
+		)?;
+		lc.set_derivation_scheme(Arc::new(Purpose44));
+		lc.open_wallet(None, ZeroingString::from(""), false, false)?;
+	}
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	// accounts are laid out along the scheme's paths
+	let default_path = ExtKeychain::derive_key_id(2, 44, 0, 0, 0);
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let accounts = api.accounts(m)?;
+		assert_eq!(accounts.len(), 1);
+		assert_eq!(accounts[0].path, default_path);
+		let path = api.create_account_path(m, "savings")?;
+		assert_eq!(path, ExtKeychain::derive_key_id(2, 44, 1, 0, 0));
+		Ok(())
+	})?;
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: 1_000_000_000,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			..Default::default()
+		};
+		let mut slate = api.init_send_tx(m, args)?;
+		slate = client1.send_tx_slate_direct("wallet2", &slate)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		slate = api.finalize_tx(m, &slate, None)?;
+		api.post_tx(m, &slate.tx, Some(false))?;
+		Ok(())
+	})?;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+
+	// and so are the keys of outputs received into them
+	let received_path = ExtKeychain::derive_key_id(3, 44, 0, 0, 0);
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, outputs) = api.retrieve_outputs(m, false, true, None)?;
+		assert_eq!(outputs.len(), 1);
+		assert_eq!(outputs[0].output.key_id, received_path);
+		assert_eq!(outputs[0].output.root_key_id, default_path);
+		Ok(())
+	})?;
+
+	// restoring the seed with the same scheme files the output under the
+	// scheme's default account
+	wallet::controller::owner_single_use(wallet3.clone(), mask3, |api, m| {
+		api.restore(m)?;
+		let accounts = api.accounts(m)?;
+		assert_eq!(accounts.len(), 1);
+		assert_eq!(accounts[0].path, default_path);
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		assert_eq!(info.total, 1_000_000_000);
+		let (_, outputs) = api.retrieve_outputs(m, false, false, None)?;
+		assert_eq!(outputs.len(), 1);
+		assert_eq!(outputs[0].output.key_id, received_path);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn derivation_scheme() {
+	let test_dir = "test_output/derivation_scheme";
+	if let Err(e) = derivation_scheme_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
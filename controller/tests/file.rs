@@ -159,8 +159,8 @@ fn file_exchange_test_impl(test_dir: &'static str) -> Result<(), libwallet::Erro
 	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
 		let mut slate = PathToSlate(receive_file.into()).get_tx()?;
 		api.verify_slate_messages(m, &slate)?;
-		slate = api.finalize_tx(m, &slate)?;
-		api.post_tx(m, &slate.tx, false)?;
+		slate = api.finalize_tx(m, &slate, None)?;
+		api.post_tx(m, &slate.tx, Some(false))?;
 		bh += 1;
 		Ok(())
 	})?;
@@ -188,7 +188,7 @@ fn file_exchange_test_impl(test_dir: &'static str) -> Result<(), libwallet::Erro
 
 	// Check messages, all participants should have both
 	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
-		let (_, tx) = api.retrieve_txs(m, true, None, Some(slate.id))?;
+		let (_, tx) = api.retrieve_txs(m, true, None, Some(slate.id), None)?;
 		assert_eq!(
 			tx[0].clone().messages.unwrap().messages[0].message,
 			Some(message.to_owned())
@@ -204,7 +204,7 @@ fn file_exchange_test_impl(test_dir: &'static str) -> Result<(), libwallet::Erro
 	})?;
 
 	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
-		let (_, tx) = api.retrieve_txs(m, true, None, Some(slate.id))?;
+		let (_, tx) = api.retrieve_txs(m, true, None, Some(slate.id), None)?;
 		assert_eq!(
 			tx[0].clone().messages.unwrap().messages[0].message,
 			Some(message.to_owned())
@@ -0,0 +1,238 @@
+// Copyright 2019 The Grin Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test sending over http to wallets hosted by another wallet's listener, or
+//! forwarded by it to their own
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_api::ShutdownHandle;
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use impls::{HttpSlateSender, SlateSender};
+use libwallet::{InitTxArgs, TxLogEntryType};
+use std::thread;
+use std::time::Duration;
+use url::Url;
+use wallet::controller::ForeignListenerConfig;
+use wallet::hosted::{ForwardedWallet, HostedWallet};
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+fn hosted_wallets_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+	create_wallet_and_add!(
+		client3,
+		wallet3,
+		mask3_i,
+		test_dir,
+		"wallet3",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask3 = (&mask3_i).as_ref();
+	create_wallet_and_add!(
+		client4,
+		wallet4,
+		mask4_i,
+		test_dir,
+		"wallet4",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask4 = (&mask4_i).as_ref();
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	// wallet 4 has its own listener, which wallet 2's listener forwards
+	// calls for "vault" to
+	let upstream_addr = "127.0.0.1:23442";
+	let upstream_shutdown = ShutdownHandle::new();
+	let upstream_listener = {
+		let wallet4 = wallet4.clone();
+		let mask4_i = mask4_i.clone();
+		let shutdown = upstream_shutdown.clone();
+		thread::spawn(move || {
+			wallet::controller::foreign_listener(
+				wallet4,
+				mask4_i,
+				vec![],
+				vec![],
+				upstream_addr,
+				ForeignListenerConfig::default(),
+				Some(shutdown),
+			)
+		})
+	};
+
+	// wallet 2's listener hosts wallet 3 as "shop"
+	let addr = "127.0.0.1:23441";
+	let shutdown = ShutdownHandle::new();
+	let listener = {
+		let hosted = vec![HostedWallet {
+			id: "shop".to_owned(),
+			wallet: wallet3.clone(),
+			keychain_mask: mask3_i.clone(),
+		}];
+		let forwarded = vec![ForwardedWallet {
+			id: "vault".to_owned(),
+			upstream: format!("http://{}", upstream_addr),
+			api_secret: None,
+		}];
+		let wallet2 = wallet2.clone();
+		let mask2_i = mask2_i.clone();
+		let shutdown = shutdown.clone();
+		thread::spawn(move || {
+			wallet::controller::foreign_listener(
+				wallet2,
+				mask2_i,
+				hosted,
+				forwarded,
+				addr,
+				ForeignListenerConfig::default(),
+				Some(shutdown),
+			)
+		})
+	};
+	thread::sleep(Duration::from_millis(500));
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+	let send = |dest: &str| {
+		let sender = HttpSlateSender::new(Url::parse(dest).unwrap()).unwrap();
+		wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+			let args = InitTxArgs {
+				src_acct_name: None,
+				amount: 1_000_000_000,
+				minimum_confirmations: 2,
+				max_outputs: 500,
+				num_change_outputs: 1,
+				selection_strategy_is_use_all: false,
+				..Default::default()
+			};
+			let slate = api.init_send_tx(m, args)?;
+			api.tx_lock_outputs(m, &slate, 0)?;
+			let slate = match sender.send_tx(&slate) {
+				Ok(s) => s,
+				Err(e) => {
+					api.cancel_tx(m, None, Some(slate.id))?;
+					return Err(e);
+				}
+			};
+			let slate = api.finalize_tx(m, &slate, None)?;
+			api.post_tx(m, &slate.tx, Some(false))?;
+			Ok(())
+		})
+	};
+
+	// a sender's usual positional receive_tx reaches the hosted wallet by its path
+	send(&format!("http://{}/v2/foreign/shop", addr))?;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+	wallet::controller::owner_single_use(wallet3.clone(), mask3, |api, m| {
+		let (refreshed, txs) = api.retrieve_txs(m, true, None, None, None)?;
+		assert!(refreshed);
+		assert_eq!(txs.len(), 1);
+		assert_eq!(txs[0].tx_type, TxLogEntryType::TxReceived);
+		assert!(txs[0].confirmed);
+		let (_, info) = api.retrieve_summary_info(m, false, 1)?;
+		assert_eq!(info.total, 1_000_000_000);
+		Ok(())
+	})?;
+
+	// the listener's own wallet still receives at the bare route
+	send(&format!("http://{}/v2/foreign", addr))?;
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, false, None, None, None)?;
+		assert_eq!(txs.len(), 1);
+		assert_eq!(txs[0].tx_type, TxLogEntryType::TxReceived);
+		Ok(())
+	})?;
+
+	// a positional receive_tx for the forwarded wallet reaches its listener
+	send(&format!("http://{}/v2/foreign/vault", addr))?;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+	wallet::controller::owner_single_use(wallet4.clone(), mask4, |api, m| {
+		let (refreshed, txs) = api.retrieve_txs(m, true, None, None, None)?;
+		assert!(refreshed);
+		assert_eq!(txs.len(), 1);
+		assert_eq!(txs[0].tx_type, TxLogEntryType::TxReceived);
+		assert!(txs[0].confirmed);
+		let (_, info) = api.retrieve_summary_info(m, false, 1)?;
+		assert_eq!(info.total, 1_000_000_000);
+		Ok(())
+	})?;
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, false, None, None, None)?;
+		assert_eq!(txs.len(), 1);
+		Ok(())
+	})?;
+
+	// nothing is received for a wallet the listener doesn't host or forward
+	assert!(send(&format!("http://{}/v2/foreign/other", addr)).is_err());
+	wallet::controller::owner_single_use(wallet3.clone(), mask3, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, false, None, None, None)?;
+		assert_eq!(txs.len(), 1);
+		Ok(())
+	})?;
+
+	shutdown.shutdown();
+	assert!(listener.join().unwrap().is_ok());
+	upstream_shutdown.shutdown();
+	assert!(upstream_listener.join().unwrap().is_ok());
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn hosted_wallets() {
+	let test_dir = "test_output/hosted_wallets";
+	if let Err(e) = hosted_wallets_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
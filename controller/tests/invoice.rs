@@ -21,7 +21,7 @@ use grin_wallet_libwallet as libwallet;
 use grin_wallet_util::grin_core as core;
 
 use impls::test_framework::{self, LocalWalletClient};
-use libwallet::{InitTxArgs, IssueInvoiceTxArgs, Slate};
+use libwallet::{FeatureFlags, InitTxArgs, IssueInvoiceTxArgs, Slate};
 use std::thread;
 use std::time::Duration;
 
@@ -103,6 +103,26 @@ fn invoice_tx_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
 
 		let mut slate = Slate::blank(2);
 
+		// Invoices can't be issued while the invoice flow is turned off
+		wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+			let flags = FeatureFlags {
+				invoice_flow: false,
+				..FeatureFlags::default()
+			};
+			api.set_feature_flags(m, flags)?;
+			let args = IssueInvoiceTxArgs {
+				amount: reward * 2,
+				..Default::default()
+			};
+			let res = api.issue_invoice_tx(m, args);
+			assert_eq!(
+				res.unwrap_err().kind(),
+				libwallet::ErrorKind::FeatureDisabled("invoice_flow".to_owned())
+			);
+			api.set_feature_flags(m, FeatureFlags::default())?;
+			Ok(())
+		})?;
+
 		wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
 			// Wallet 2 inititates an invoice transaction, requesting payment
 			let args = IssueInvoiceTxArgs {
@@ -138,7 +158,7 @@ fn invoice_tx_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
 
 		// wallet 1 posts so wallet 2 doesn't get the mined amount
 		wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
-			api.post_tx(m, &slate.tx, false)?;
+			api.post_tx(m, &slate.tx, Some(false))?;
 			Ok(())
 		})?;
 		bh += 1;
@@ -149,7 +169,7 @@ fn invoice_tx_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
 		// Check transaction log for wallet 2
 		wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
 			let (_, wallet2_info) = api.retrieve_summary_info(m, true, 1)?;
-			let (refreshed, txs) = api.retrieve_txs(m, true, None, None)?;
+			let (refreshed, txs) = api.retrieve_txs(m, true, None, None, None)?;
 			assert!(refreshed);
 			assert!(txs.len() == 1);
 			println!(
@@ -165,7 +185,7 @@ fn invoice_tx_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
 		// exists
 		wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
 			let (_, wallet1_info) = api.retrieve_summary_info(m, true, 1)?;
-			let (refreshed, txs) = api.retrieve_txs(m, true, None, None)?;
+			let (refreshed, txs) = api.retrieve_txs(m, true, None, None, None)?;
 			assert!(refreshed);
 			assert_eq!(txs.len() as u64, bh + 1);
 			println!(
@@ -0,0 +1,132 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test confirming transactions by looking up their kernel on chain
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_util::grin_core as core;
+use grin_wallet_util::grin_util as util;
+
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, NodeClient, TxLogEntryType};
+use std::thread;
+use std::time::Duration;
+use util::secp::pedersen::Commitment;
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+fn kernel_confirmation_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	// the kernel excess is known as soon as the transaction is finalized
+	let mut excess = None;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: reward / 2,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			..Default::default()
+		};
+		let slate = api.init_send_tx(m, args)?;
+		let slate = client1.send_tx_slate_direct("wallet2", &slate)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		let slate = api.finalize_tx(m, &slate, None)?;
+		let (_, txs) = api.retrieve_txs(m, false, None, Some(slate.id), None)?;
+		let sent = txs
+			.iter()
+			.find(|t| t.tx_type == TxLogEntryType::TxSent)
+			.unwrap();
+		assert!(!sent.confirmed);
+		assert!(sent.kernel_excess.is_some());
+		excess = sent.kernel_excess.clone();
+		api.post_tx(m, &slate.tx, false)?;
+		Ok(())
+	})?;
+
+	// the node finds the kernel in the block the transaction was mined in
+	let height = chain.head().unwrap().height;
+	let excess = Commitment::from_vec(util::from_hex(excess.unwrap()).unwrap());
+	let kernel = client1.get_kernel(&excess, None, None)?;
+	assert_eq!(kernel.map(|k| k.1), Some(height));
+	assert!(client1
+		.get_kernel(&excess, Some(height + 1), None)?
+		.is_none());
+
+	// and refreshing confirms the transaction at that height
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (refreshed, txs) = api.retrieve_txs(m, true, None, None, None)?;
+		assert!(refreshed);
+		let sent = txs
+			.iter()
+			.find(|t| t.tx_type == TxLogEntryType::TxSent)
+			.unwrap();
+		assert!(sent.confirmed);
+		assert_eq!(sent.confirmation_height, Some(height));
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn kernel_confirmation() {
+	let test_dir = "test_output/kernel_confirmation";
+	if let Err(e) = kernel_confirmation_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
@@ -0,0 +1,137 @@
+// Copyright 2019 The Grin Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test sends whose inputs are only selected and locked when finalizing
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, OutputStatus, Slate};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+fn late_lock_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+	let amount = 60_000_000_000;
+	let mut slate = Slate::blank(1);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			late_lock: Some(true),
+			..Default::default()
+		};
+		slate = api.init_send_tx(m, args)?;
+		assert!(slate.tx.inputs().is_empty());
+		assert!(slate.fee > 0);
+		api.tx_lock_outputs(m, &slate, 0)?;
+
+		// nothing is locked while waiting for the recipient
+		slate = client1.send_tx_slate_direct("wallet2", &slate)?;
+		let (_, outputs) = api.retrieve_outputs(m, true, false, None)?;
+		assert!(outputs
+			.iter()
+			.all(|o| o.output.status != OutputStatus::Locked));
+		let (_, waiting) = api.retrieve_summary_info(m, false, 1)?;
+		assert_eq!(
+			waiting.amount_currently_spendable,
+			info.amount_currently_spendable
+		);
+		let (_, txs) = api.retrieve_txs(m, false, None, Some(slate.id), None)?;
+		assert!(txs.is_empty());
+
+		// inputs are selected and locked on finalizing
+		slate = api.finalize_tx(m, &slate, None)?;
+		assert!(!slate.tx.inputs().is_empty());
+		let (_, outputs) = api.retrieve_outputs(m, true, false, None)?;
+		assert!(outputs
+			.iter()
+			.any(|o| o.output.status == OutputStatus::Locked));
+		let (_, txs) = api.retrieve_txs(m, false, None, Some(slate.id), None)?;
+		assert_eq!(txs.len(), 1);
+		assert_eq!(
+			txs[0].amount_debited - txs[0].amount_credited,
+			amount + slate.fee
+		);
+		api.post_tx(m, &slate.tx, Some(false))?;
+		Ok(())
+	})?;
+
+	// the transaction is valid and confirms
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		assert_eq!(info.amount_currently_spendable, amount);
+		let (_, txs) = api.retrieve_txs(m, true, None, Some(slate.id), None)?;
+		assert!(txs[0].confirmed);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn late_lock() {
+	let test_dir = "test_output/late_lock";
+	if let Err(e) = late_lock_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
@@ -0,0 +1,119 @@
+// Copyright 2019 The Grin Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test transaction memos sealed to an escrow key in history exports
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, TxExportEntry, TxExportFormat};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+fn memo_escrow_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+	let (secret_key, public_key) = libwallet::new_escrow_key()?;
+	let escrow_key = libwallet::parse_escrow_key(&public_key)?;
+
+	let mut exports = vec![];
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: 60_000_000_000,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: true,
+			message: Some("Order #1234".to_owned()),
+			..Default::default()
+		};
+		let slate = api.init_send_tx(m, args)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		let slate = client1.send_tx_slate_direct("wallet2", &slate)?;
+		api.finalize_tx(m, &slate, None)?;
+
+		// memos are only sealed once a key is set, and only in JSON exports
+		exports.push(api.export_txs(m, true, TxExportFormat::Json, Default::default())?);
+		api.set_memo_escrow_key(Some(escrow_key.clone()));
+		exports.push(api.export_txs(m, true, TxExportFormat::Json, Default::default())?);
+		let csv = api.export_txs(m, true, TxExportFormat::Csv, Default::default())?;
+		assert!(!csv.contains("Order #1234"));
+		Ok(())
+	})?;
+
+	let plain: Vec<TxExportEntry> = serde_json::from_str(&exports[0]).unwrap();
+	assert!(plain.iter().all(|e| e.escrowed_memo.is_none()));
+
+	let sealed: Vec<TxExportEntry> = serde_json::from_str(&exports[1]).unwrap();
+	assert!(!exports[1].contains("Order #1234"));
+	let memos: Vec<_> = sealed
+		.iter()
+		.filter_map(|e| e.escrowed_memo.clone())
+		.collect();
+	// only the sent transaction has messages, the coinbase outputs have none
+	assert_eq!(memos.len(), 1);
+	let messages = libwallet::open_memo(&secret_key, &memos[0])?;
+	assert_eq!(messages.messages[0].message, Some("Order #1234".to_owned()));
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn memo_escrow() {
+	let test_dir = "test_output/memo_escrow";
+	if let Err(e) = memo_escrow_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
@@ -0,0 +1,236 @@
+// Copyright 2019 The Grin Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test paying several recipients in a single transaction
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_api::ShutdownHandle;
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, InitTxRecipient, InitTxSendArgs, TxLogEntryType};
+use std::thread;
+use std::time::Duration;
+use wallet::controller::ForeignListenerConfig;
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+fn multi_recipient_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	// wallet 2 receives over http
+	let addr = "127.0.0.1:23431";
+	let dest = format!("http://{}", addr);
+	let shutdown = ShutdownHandle::new();
+	let listener = {
+		let wallet2 = wallet2.clone();
+		let mask2_i = mask2_i.clone();
+		let shutdown = shutdown.clone();
+		thread::spawn(move || {
+			wallet::controller::foreign_listener(
+				wallet2,
+				mask2_i,
+				vec![],
+				vec![],
+				addr,
+				ForeignListenerConfig::default(),
+				Some(shutdown),
+			)
+		})
+	};
+	thread::sleep(Duration::from_millis(500));
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+	let send_args = |recipients: Vec<InitTxRecipient>| InitTxArgs {
+		src_acct_name: None,
+		amount: 1_000_000_000,
+		minimum_confirmations: 2,
+		max_outputs: 500,
+		num_change_outputs: 1,
+		selection_strategy_is_use_all: false,
+		send_args: Some(InitTxSendArgs {
+			method: "http".to_owned(),
+			dest: dest.clone(),
+			finalize: true,
+			post_tx: true,
+			fluff: false,
+		}),
+		additional_recipients: Some(recipients),
+		..Default::default()
+	};
+
+	// both rounds are received, and posted as one transaction
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let recipients = vec![InitTxRecipient {
+			amount: 2_000_000_000,
+			dest: dest.clone(),
+		}];
+		// a single send doesn't pay them
+		assert!(api.init_send_tx(m, send_args(recipients.clone())).is_err());
+		let slates = api.init_send_tx_multi(m, send_args(recipients))?;
+		assert_eq!(slates.len(), 2);
+		assert_eq!(slates[0].amount, 1_000_000_000);
+		assert_eq!(slates[1].amount, 2_000_000_000);
+		assert!(slates.iter().all(|s| s.fee > 0));
+		assert_ne!(slates[0].id, slates[1].id);
+		Ok(())
+	})?;
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (refreshed, txs) = api.retrieve_txs(m, true, None, None, None)?;
+		assert!(refreshed);
+		assert_eq!(txs.len(), 2);
+		assert!(txs.iter().all(|t| t.confirmed));
+		let (_, info) = api.retrieve_summary_info(m, false, 1)?;
+		assert_eq!(info.total, 3_000_000_000);
+		Ok(())
+	})?;
+
+	// recipients short of the funds for all of them aren't sent anything
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		let recipients = vec![InitTxRecipient {
+			amount: info.amount_currently_spendable,
+			dest: dest.clone(),
+		}];
+		assert!(api.init_send_tx_multi(m, send_args(recipients)).is_err());
+		Ok(())
+	})?;
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, false, None, None, None)?;
+		assert_eq!(txs.len(), 2);
+		Ok(())
+	})?;
+
+	// nor are those a single transaction could pay, but not one round each
+	// with a fee of its own
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, info) = api.retrieve_summary_info(m, true, 2)?;
+		let spendable = info.amount_currently_spendable;
+		let estimate = api.init_send_tx(
+			m,
+			InitTxArgs {
+				amount: spendable - 1_000_000_000,
+				send_args: None,
+				additional_recipients: None,
+				estimate_only: Some(true),
+				..send_args(vec![])
+			},
+		)?;
+		let total = spendable - estimate.fee;
+		let single = api.init_send_tx(
+			m,
+			InitTxArgs {
+				amount: total,
+				send_args: None,
+				additional_recipients: None,
+				estimate_only: Some(true),
+				..send_args(vec![])
+			},
+		)?;
+		assert!(single.amount <= spendable);
+		let recipients = vec![InitTxRecipient {
+			amount: total - 1_000_000_000,
+			dest: dest.clone(),
+		}];
+		assert!(api.init_send_tx_multi(m, send_args(recipients)).is_err());
+		let (_, txs) = api.retrieve_txs(m, false, None, None, None)?;
+		assert_eq!(
+			txs.iter()
+				.filter(|t| t.tx_type == TxLogEntryType::TxSent)
+				.count(),
+			2
+		);
+		Ok(())
+	})?;
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, false, None, None, None)?;
+		assert_eq!(txs.len(), 2);
+		Ok(())
+	})?;
+
+	// a later round failing cancels the earlier ones, unlocking their inputs
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let recipients = vec![InitTxRecipient {
+			amount: 2_000_000_000,
+			dest: "http://127.0.0.1:1".to_owned(),
+		}];
+		assert!(api.init_send_tx_multi(m, send_args(recipients)).is_err());
+		let (_, txs) = api.retrieve_txs(m, false, None, None, None)?;
+		let sent: Vec<_> = txs
+			.iter()
+			.filter(|t| {
+				t.tx_type == TxLogEntryType::TxSent || t.tx_type == TxLogEntryType::TxSentCancelled
+			})
+			.collect();
+		assert_eq!(sent.len(), 3);
+		assert_eq!(
+			sent.iter()
+				.filter(|t| t.tx_type == TxLogEntryType::TxSentCancelled)
+				.count(),
+			1
+		);
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		assert_eq!(info.amount_locked, 0);
+		Ok(())
+	})?;
+
+	shutdown.shutdown();
+	assert!(listener.join().unwrap().is_ok());
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn multi_recipient() {
+	let test_dir = "test_output/multi_recipient";
+	if let Err(e) = multi_recipient_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
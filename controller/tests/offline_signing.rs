@@ -0,0 +1,189 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test sending from a watch-only wallet with its seed kept offline
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_util::grin_core as core;
+use grin_wallet_util::grin_util::ZeroingString;
+
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, OutputStatus, SignedTx, TxLogEntryType, UnsignedTx};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+fn offline_signing_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	let mnemonic = {
+		let mut w_lock = wallet1.lock();
+		let lc = w_lock.lc_provider()?;
+		lc.get_mnemonic(None, ZeroingString::from(""))?
+	};
+
+	// holds the same seed as wallet1, and never talks to the node
+	create_wallet_and_add!(
+		client_cold,
+		cold,
+		mask_cold_i,
+		test_dir,
+		"cold",
+		Some(mnemonic),
+		&mut wallet_proxy,
+		false
+	);
+	let mask_cold = (&mask_cold_i).as_ref();
+
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		api.retrieve_summary_info(m, true, 1)?;
+		Ok(())
+	})?;
+
+	// from here on wallet1 is only a watch-only copy, without its seed
+	{
+		let mut w_lock = wallet1.lock();
+		let lc = w_lock.lc_provider()?;
+		lc.close_wallet(None)?;
+		lc.open_watch_only(None)?;
+	}
+
+	let amount = reward / 2;
+	let mut unsigned = None;
+	wallet::controller::owner_single_use(wallet1.clone(), None, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			message: Some("from cold storage".to_owned()),
+			..Default::default()
+		};
+		let u = api.prepare_send(m, args)?;
+		assert_eq!(u.inputs.len(), 1);
+		assert_eq!(u.change.len(), 1);
+		assert_eq!(u.change[0].value, reward - amount - u.slate.fee);
+		// the watch-only wallet can't sign
+		assert!(api.sign_offline(m, &u).is_err());
+		unsigned = Some(serde_json::to_string(&u).unwrap());
+		Ok(())
+	})?;
+	let unsigned: UnsignedTx = serde_json::from_str(&unsigned.unwrap()).unwrap();
+
+	let mut signed = None;
+	wallet::controller::owner_single_use(cold.clone(), mask_cold, |api, m| {
+		let slate = api.sign_offline(m, &unsigned)?;
+		let slate = client_cold.send_tx_slate_direct("wallet2", &slate)?;
+		let s = api.finalize_offline(m, &slate)?;
+		assert!(s.change[0].commit.is_some());
+		signed = Some(serde_json::to_string(&s).unwrap());
+		Ok(())
+	})?;
+	let signed: SignedTx = serde_json::from_str(&signed.unwrap()).unwrap();
+	// nothing was recorded in the offline wallet
+	wallet::controller::owner_single_use(cold.clone(), mask_cold, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, false, None, None, None)?;
+		assert!(txs.iter().all(|t| t.tx_type != TxLogEntryType::TxSent));
+		Ok(())
+	})?;
+
+	wallet::controller::owner_single_use(wallet1.clone(), None, |api, m| {
+		api.broadcast_signed(m, &signed, false)?;
+		// a second broadcast changes nothing
+		api.broadcast_signed(m, &signed, false)?;
+		Ok(())
+	})?;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet2.clone(), mask2, 3, false);
+
+	// the watch-only wallet follows the transaction and its change
+	wallet::controller::owner_single_use(wallet1.clone(), None, |api, m| {
+		let (validated, txs) = api.retrieve_txs(m, true, None, Some(signed.slate.id), None)?;
+		assert!(validated);
+		assert_eq!(txs.len(), 1);
+		assert!(txs[0].confirmed);
+		assert_eq!(
+			txs[0].amount_debited - txs[0].amount_credited,
+			amount + signed.slate.fee
+		);
+		let (_, outputs) = api.retrieve_outputs(m, true, false, Some(txs[0].id))?;
+		let change: Vec<_> = outputs
+			.iter()
+			.filter(|o| o.output.status == OutputStatus::Unspent)
+			.collect();
+		assert_eq!(change.len(), 1);
+		assert_eq!(change[0].output.key_id, signed.change[0].key_id);
+		Ok(())
+	})?;
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, true, None, Some(signed.slate.id), None)?;
+		assert_eq!(txs.len(), 1);
+		assert!(txs[0].confirmed);
+		assert_eq!(txs[0].amount_credited, amount);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn offline_signing() {
+	let test_dir = "test_output/offline_signing";
+	if let Err(e) = offline_signing_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
@@ -0,0 +1,234 @@
+// Copyright 2019 The Grin Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! tests running, following and cancelling operations in the background
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_util::grin_util as util;
+
+use grin_wallet_api::{CallPriority, CallQueue, OperationEvent, OperationRunner};
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{LongOperation, OperationState, OperationStatus, ScanProgress};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use util::secp::key::SecretKey;
+use util::{Mutex, ZeroingString};
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+/// Wait for an operation to finish, returning its final status
+fn wait_for(runner: &OperationRunner, id: u32, mask: Option<&SecretKey>) -> OperationStatus {
+	for _ in 0..600 {
+		let status = runner.status(id, mask).unwrap();
+		match status.state {
+			OperationState::Queued | OperationState::Running => {
+				thread::sleep(Duration::from_millis(100))
+			}
+			_ => return status,
+		}
+	}
+	panic!("Operation {} didn't finish", id);
+}
+
+fn operations_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	let mnemonic = {
+		let mut w_lock = wallet1.lock();
+		let lc = w_lock.lc_provider()?;
+		lc.get_mnemonic(None, ZeroingString::from(""))?
+	};
+
+	// same seed as wallet1, to be restored
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		Some(mnemonic),
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+	let mut expected_outputs = 0;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, outputs) = api.retrieve_outputs(m, false, true, None)?;
+		expected_outputs = outputs.len();
+		Ok(())
+	})?;
+
+	let queue = Arc::new(CallQueue::new());
+	let runner = OperationRunner::new(wallet2.clone(), Some(queue.clone()));
+
+	// an operation cancelled while waiting for the wallet never runs
+	let turn = queue.wait_turn(CallPriority::Interactive);
+	let id = runner.start(LongOperation::Restore, mask2.cloned());
+	assert_eq!(
+		runner.status(id, mask2).unwrap().state,
+		OperationState::Queued
+	);
+	// only callers with the same mask can see or cancel it
+	let other_mask = SecretKey::from_slice(&util::static_secp_instance().lock(), &[1; 32]).unwrap();
+	assert!(runner.status(id, Some(&other_mask)).is_none());
+	assert!(!runner.cancel(id, Some(&other_mask)));
+	assert!(runner.cancel(id, mask2));
+	drop(turn);
+	assert_eq!(
+		wait_for(&runner, id, mask2).state,
+		OperationState::Cancelled
+	);
+	{
+		wallet_inst!(wallet2, w);
+		assert_eq!(w.iter()?.count(), 0);
+	}
+
+	// a restore run to completion restores the outputs, reporting its progress
+	// to those following it
+	let turn = queue.wait_turn(CallPriority::Interactive);
+	let id = runner.start(LongOperation::Restore, mask2.cloned());
+	let events = Arc::new(Mutex::new(vec![]));
+	let followed = {
+		let events = events.clone();
+		runner.follow(id, mask2, move |e| {
+			events.lock().push(e);
+			true
+		})
+	};
+	assert!(followed);
+	assert!(!runner.follow(id, Some(&other_mask), |_| true));
+	drop(turn);
+	let status = wait_for(&runner, id, mask2);
+	assert_eq!(status.state, OperationState::Completed);
+	assert!(status.scan_total > 0);
+	assert_eq!(status.scanned, status.scan_total);
+	assert_eq!(status.percent_complete, 100);
+	{
+		let events = events.lock();
+		match (events.first(), events.last()) {
+			(Some(OperationEvent::Status(first)), Some(OperationEvent::Status(last))) => {
+				assert_eq!(first.state, OperationState::Queued);
+				assert_eq!(last.state, OperationState::Completed);
+			}
+			_ => panic!("Unexpected events {:?}", events),
+		}
+		let progress: Vec<&ScanProgress> = events
+			.iter()
+			.filter_map(|e| match e {
+				OperationEvent::Progress(p) => Some(p),
+				_ => None,
+			})
+			.collect();
+		let last = progress.last().unwrap();
+		assert_eq!(last.scanned, status.scan_total);
+		assert_eq!(last.outputs_found, expected_outputs as u64);
+		assert!(last.height > 0);
+	}
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, outputs) = api.retrieve_outputs(m, false, true, None)?;
+		assert_eq!(outputs.len(), expected_outputs);
+		Ok(())
+	})?;
+
+	// and the Owner API reports operations it doesn't know of
+	let runner = Arc::new(runner);
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		api.set_operation_runner(Some(runner.clone()));
+		assert_eq!(
+			api.operation_status(m, id)?.state,
+			OperationState::Completed
+		);
+		assert!(api.operation_status(m, id + 1).is_err());
+		assert!(api.cancel_operation(m, id + 1).is_err());
+		let id = api.start_operation(
+			m,
+			LongOperation::CheckRepair {
+				delete_unconfirmed: false,
+				full: true,
+			},
+		)?;
+		assert_eq!(wait_for(&runner, id, m).state, OperationState::Completed);
+		assert_eq!(api.list_operations(m)?.len(), 3);
+		Ok(())
+	})?;
+
+	// operations are recorded, so one left running by a previous listener is
+	// resumed along with the history
+	// the final state is recorded just after it's reported
+	thread::sleep(Duration::from_millis(500));
+	let last_id = {
+		wallet_inst!(wallet2, w);
+		let recorded: Vec<OperationStatus> = w.operation_iter()?.collect();
+		assert_eq!(recorded.len(), 3);
+		assert!(recorded.iter().all(|s| s.finished.is_some()));
+		let mut interrupted = recorded[2].clone();
+		interrupted.id += 1;
+		interrupted.state = OperationState::Running;
+		interrupted.finished = None;
+		let mut batch = w.batch(mask2)?;
+		batch.save_operation(&interrupted)?;
+		batch.commit()?;
+		interrupted.id
+	};
+	let runner = OperationRunner::new(wallet2.clone(), Some(queue.clone()));
+	assert_eq!(runner.resume(mask2.cloned())?, 1);
+	let status = wait_for(&runner, last_id, mask2);
+	assert_eq!(status.state, OperationState::Completed);
+	assert_eq!(status.resumed, 1);
+	assert_eq!(runner.list(mask2).len(), 4);
+	assert_eq!(
+		runner.start(LongOperation::Restore, mask2.cloned()),
+		last_id + 1
+	);
+	wait_for(&runner, last_id + 1, mask2);
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn operations() {
+	let test_dir = "test_output/operations";
+	if let Err(e) = operations_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
@@ -0,0 +1,164 @@
+// Copyright 2019 The Grin Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test refusing slates outside the limits on what the Foreign API receives
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_api::Foreign;
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{ErrorKind, InitTxArgs, IssueInvoiceTxArgs, ReceiveLimits};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+fn receive_limits_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+	let mut slates = vec![];
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		for amount in &[1_000_000, 1_000_000_000, 2_000_000_000, 50_000_000_000] {
+			let args = InitTxArgs {
+				src_acct_name: None,
+				amount: *amount,
+				minimum_confirmations: 2,
+				max_outputs: 500,
+				num_change_outputs: 1,
+				selection_strategy_is_use_all: false,
+				..Default::default()
+			};
+			let slate = api.init_send_tx(m, args)?;
+			api.tx_lock_outputs(m, &slate, 0)?;
+			slates.push(slate);
+		}
+		Ok(())
+	})?;
+
+	let limits = ReceiveLimits {
+		min_amount: Some(100_000_000),
+		max_amount: Some(10_000_000_000),
+		max_per_hour: Some(1),
+		finalize_invoices: false,
+	};
+	let mut foreign = Foreign::new(wallet2.clone(), mask2_i.clone(), None);
+	foreign.set_receive_limits(Some(limits.clone()));
+	let refused = |res: Result<_, libwallet::Error>| match res {
+		Err(e) => match e.kind() {
+			ErrorKind::ReceiveLimit(_) => {}
+			k => panic!("Unexpected error: {}", k),
+		},
+		Ok(_) => panic!("Receive outside the limits wasn't refused"),
+	};
+
+	// dust and amounts over the maximum are refused
+	refused(foreign.receive_tx(&slates[0], None, None));
+	refused(foreign.receive_tx(&slates[3], None, None));
+
+	// within the limits, received, but only once an hour
+	foreign.receive_tx(&slates[1], None, None)?;
+	refused(foreign.receive_tx(&slates[2], None, None));
+
+	// the count is kept with the wallet, so holds for each API instance
+	let mut other = Foreign::new(wallet2.clone(), mask2_i.clone(), None);
+	other.set_receive_limits(Some(limits));
+	refused(other.receive_tx(&slates[2], None, None));
+
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, false, None, None, None)?;
+		assert_eq!(txs.len(), 1);
+		assert_eq!(txs[0].tx_slate_id, Some(slates[1].id));
+		Ok(())
+	})?;
+
+	// paid invoices aren't finalized
+	let mut invoice = None;
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let args = IssueInvoiceTxArgs {
+			amount: 1_000_000_000,
+			..Default::default()
+		};
+		invoice = Some(api.issue_invoice_tx(m, args)?);
+		Ok(())
+	})?;
+	let mut paid = None;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: 1_000_000_000,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			..Default::default()
+		};
+		let slate = api.process_invoice_tx(m, invoice.as_ref().unwrap(), args)?;
+		api.tx_lock_outputs(m, &slate, 1)?;
+		paid = Some(slate);
+		Ok(())
+	})?;
+	refused(foreign.finalize_invoice_tx(paid.as_ref().unwrap()));
+
+	// without limits, the invoice is finalized
+	foreign.set_receive_limits(None);
+	foreign.finalize_invoice_tx(paid.as_ref().unwrap())?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn receive_limits() {
+	let test_dir = "test_output/receive_limits";
+	if let Err(e) = receive_limits_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
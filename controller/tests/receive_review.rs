@@ -0,0 +1,145 @@
+// Copyright 2019 The Grin Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test queueing received transactions above a threshold for review
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_api::Foreign;
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{ErrorKind, InitTxArgs, TxLogEntryType};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+fn receive_review_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+	let mut slates = vec![];
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		for amount in &[1_000_000_000, 5_000_000_000, 6_000_000_000] {
+			let args = InitTxArgs {
+				src_acct_name: None,
+				amount: *amount,
+				minimum_confirmations: 2,
+				max_outputs: 500,
+				num_change_outputs: 1,
+				selection_strategy_is_use_all: false,
+				..Default::default()
+			};
+			let slate = api.init_send_tx(m, args)?;
+			api.tx_lock_outputs(m, &slate, 0)?;
+			slates.push(slate);
+		}
+		Ok(())
+	})?;
+
+	let mut foreign = Foreign::new(wallet2.clone(), mask2_i.clone(), None);
+	foreign.set_review_threshold(Some(2_000_000_000));
+
+	// below the threshold, received straight away
+	foreign.receive_tx(&slates[0], None, None)?;
+
+	// above it, queued for review, and queued only once
+	for slate in &slates[1..] {
+		for _ in 0..2 {
+			match foreign.receive_tx(slate, None, None) {
+				Err(e) => match e.kind() {
+					ErrorKind::TransactionAwaitingReview(_) => {}
+					k => panic!("Unexpected error: {}", k),
+				},
+				Ok(_) => panic!("Receive above the threshold wasn't queued"),
+			}
+		}
+	}
+
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let pending = api.retrieve_pending_receives(m)?;
+		assert_eq!(pending.len(), 2);
+		assert_eq!(pending[0].slate.id, slates[1].id);
+		let (_, txs) = api.retrieve_txs(m, false, None, None, None)?;
+		assert_eq!(txs.len(), 1);
+
+		api.reject_receive(m, slates[1].id)?;
+		assert!(api.reject_receive(m, slates[1].id).is_err());
+		assert!(api.approve_receive(m, slates[1].id).is_err());
+
+		let slate = api.approve_receive(m, slates[2].id)?;
+		assert_eq!(slate.id, slates[2].id);
+		assert_eq!(slate.participant_data.len(), 2);
+		assert!(api.retrieve_pending_receives(m)?.is_empty());
+
+		let (_, txs) = api.retrieve_txs(m, false, None, None, None)?;
+		assert_eq!(txs.len(), 2);
+		assert!(txs.iter().all(|t| t.tx_type == TxLogEntryType::TxReceived));
+		assert!(txs.iter().any(|t| t.tx_slate_id == Some(slates[2].id)));
+		Ok(())
+	})?;
+
+	// an approved slate can't be queued again
+	assert!(foreign.receive_tx(&slates[2], None, None).is_err());
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		assert!(api.retrieve_pending_receives(m)?.is_empty());
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn receive_review() {
+	let test_dir = "test_output/receive_review";
+	if let Err(e) = receive_review_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
@@ -0,0 +1,133 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test height-locked transactions sweeping a wallet to a recovery destination
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_util::grin_core as core;
+
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::api_impl::owner;
+use libwallet::{InitTxArgs, RecoveryTxArgs};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+fn recovery_tx_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+	let height = chain.head().unwrap().height;
+
+	// the recovery destination signs for its output as for any receive
+	let args = RecoveryTxArgs {
+		lock_blocks: 100,
+		minimum_confirmations: 1,
+		..Default::default()
+	};
+	let slate = {
+		wallet_inst!(wallet1, w);
+		owner::init_recovery_tx(&mut **w, mask1, args.clone(), false)?
+	};
+	let slate = client1.send_tx_slate_direct("wallet2", &slate)?;
+	let slate = {
+		wallet_inst!(wallet1, w);
+		owner::finalize_recovery_tx(&mut **w, mask1, &slate)?
+	};
+	assert_eq!(slate.lock_height, height + 100);
+	assert_eq!(
+		slate.amount + slate.fee,
+		slate.tx.inputs().len() as u64 * reward
+	);
+	assert_eq!(slate.tx.outputs().len(), 1);
+	{
+		wallet_inst!(wallet1, w);
+		assert!(owner::recovery_tx_current(&mut **w, mask1, &args, &slate)?);
+	}
+
+	// nothing was locked, and spending leaves the transaction out of date
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		assert_eq!(info.amount_locked, 0);
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: reward / 2,
+			minimum_confirmations: 1,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			..Default::default()
+		};
+		let slate = api.init_send_tx(m, args)?;
+		let slate = client1.send_tx_slate_direct("wallet2", &slate)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		let slate = api.finalize_tx(m, &slate, None)?;
+		api.post_tx(m, &slate.tx, false)?;
+		Ok(())
+	})?;
+	{
+		wallet_inst!(wallet1, w);
+		assert!(!owner::recovery_tx_current(&mut **w, mask1, &args, &slate)?);
+	}
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn recovery_tx() {
+	let test_dir = "test_output/recovery_tx";
+	if let Err(e) = recovery_tx_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
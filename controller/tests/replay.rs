@@ -0,0 +1,123 @@
+// Copyright 2019 The Grin Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test replaying the slates stored at each step of a transaction
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, TxStep};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+fn replay_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	let mut slate_id = None;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: 20_000_000_000,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			message: Some("replayed".to_owned()),
+			..Default::default()
+		};
+		let slate = api.init_send_tx(m, args)?;
+		let slate = client1.send_tx_slate_direct("wallet2", &slate)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		let slate = api.finalize_tx(m, &slate, None)?;
+		slate_id = Some(slate.id);
+
+		// the sender stored its own steps, but not the recipient's
+		let report = api.replay_tx(m, None, slate_id)?;
+		assert!(report.reproduced, "{:?}", report);
+		let stored: Vec<(TxStep, bool)> = report.steps.iter().map(|s| (s.step, s.stored)).collect();
+		assert_eq!(
+			stored,
+			vec![
+				(TxStep::Lock, true),
+				(TxStep::Receive, false),
+				(TxStep::Finalize, true)
+			]
+		);
+
+		// coinbase transactions have no slate
+		assert!(api.replay_tx(m, Some(0), None).is_err());
+		Ok(())
+	})?;
+
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let report = api.replay_tx(m, None, slate_id)?;
+		assert!(report.reproduced, "{:?}", report);
+		assert!(report
+			.steps
+			.iter()
+			.any(|s| s.step == TxStep::Receive && s.stored));
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn replay() {
+	let test_dir = "test_output/replay";
+	if let Err(e) = replay_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
@@ -148,15 +148,15 @@ fn file_repost_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error>
 	// wallet 1 finalize
 	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
 		slate = PathToSlate((&receive_file).into()).get_tx()?;
-		slate = api.finalize_tx(m, &slate)?;
+		slate = api.finalize_tx(m, &slate, None)?;
 		Ok(())
 	})?;
 
 	// Now repost from cached
 	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
-		let (_, txs) = api.retrieve_txs(m, true, None, Some(slate.id))?;
+		let (_, txs) = api.retrieve_txs(m, true, None, Some(slate.id), None)?;
 		let stored_tx = api.get_stored_tx(m, &txs[0])?;
-		api.post_tx(m, &stored_tx.unwrap(), false)?;
+		api.post_tx(m, &stored_tx.unwrap(), Some(false))?;
 		bh += 1;
 		Ok(())
 	})?;
@@ -213,7 +213,7 @@ fn file_repost_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error>
 		let slate_i = sender_api.init_send_tx(m, args)?;
 		slate = client1.send_tx_slate_direct("wallet2", &slate_i)?;
 		sender_api.tx_lock_outputs(m, &slate, 0)?;
-		slate = sender_api.finalize_tx(m, &mut slate)?;
+		slate = sender_api.finalize_tx(m, &mut slate, None)?;
 		Ok(())
 	})?;
 
@@ -222,9 +222,9 @@ fn file_repost_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error>
 
 	// Now repost from cached
 	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
-		let (_, txs) = api.retrieve_txs(m, true, None, Some(slate.id))?;
+		let (_, txs) = api.retrieve_txs(m, true, None, Some(slate.id), None)?;
 		let stored_tx = api.get_stored_tx(m, &txs[0])?;
-		api.post_tx(m, &stored_tx.unwrap(), false)?;
+		api.post_tx(m, &stored_tx.unwrap(), Some(false))?;
 		bh += 1;
 		Ok(())
 	})?;
@@ -149,14 +149,14 @@ fn compare_wallet_restore(
 	// Overall wallet info should be the same
 	wallet::controller::owner_single_use(wallet_source.clone(), source_mask, |api, m| {
 		src_info = Some(api.retrieve_summary_info(m, true, 1)?.1);
-		src_txs = Some(api.retrieve_txs(m, true, None, None)?.1);
+		src_txs = Some(api.retrieve_txs(m, true, None, None, None)?.1);
 		src_accts = Some(api.accounts(m)?);
 		Ok(())
 	})?;
 
 	wallet::controller::owner_single_use(wallet_dest.clone(), dest_mask, |api, m| {
 		dest_info = Some(api.retrieve_summary_info(m, true, 1)?.1);
-		dest_txs = Some(api.retrieve_txs(m, true, None, None)?.1);
+		dest_txs = Some(api.retrieve_txs(m, true, None, None, None)?.1);
 		dest_accts = Some(api.accounts(m)?);
 		Ok(())
 	})?;
@@ -280,8 +280,8 @@ fn setup_restore(test_dir: &'static str) -> Result<(), libwallet::Error> {
 		let slate_i = sender_api.init_send_tx(m, args)?;
 		slate = client1.send_tx_slate_direct("wallet2", &slate_i)?;
 		sender_api.tx_lock_outputs(m, &slate, 0)?;
-		slate = sender_api.finalize_tx(m, &slate)?;
-		sender_api.post_tx(m, &slate.tx, false)?;
+		slate = sender_api.finalize_tx(m, &slate, None)?;
+		sender_api.post_tx(m, &slate.tx, Some(false))?;
 		Ok(())
 	})?;
 
@@ -303,8 +303,8 @@ fn setup_restore(test_dir: &'static str) -> Result<(), libwallet::Error> {
 		let slate_i = sender_api.init_send_tx(m, args)?;
 		slate = client1.send_tx_slate_direct("wallet3", &slate_i)?;
 		sender_api.tx_lock_outputs(m, &slate, 0)?;
-		slate = sender_api.finalize_tx(m, &slate)?;
-		sender_api.post_tx(m, &slate.tx, false)?;
+		slate = sender_api.finalize_tx(m, &slate, None)?;
+		sender_api.post_tx(m, &slate.tx, Some(false))?;
 		Ok(())
 	})?;
 
@@ -326,8 +326,8 @@ fn setup_restore(test_dir: &'static str) -> Result<(), libwallet::Error> {
 		let slate_i = sender_api.init_send_tx(m, args)?;
 		slate = client3.send_tx_slate_direct("wallet2", &slate_i)?;
 		sender_api.tx_lock_outputs(m, &slate, 0)?;
-		slate = sender_api.finalize_tx(m, &slate)?;
-		sender_api.post_tx(m, &slate.tx, false)?;
+		slate = sender_api.finalize_tx(m, &slate, None)?;
+		sender_api.post_tx(m, &slate.tx, Some(false))?;
 		Ok(())
 	})?;
 
@@ -355,8 +355,8 @@ fn setup_restore(test_dir: &'static str) -> Result<(), libwallet::Error> {
 		let slate_i = sender_api.init_send_tx(m, args)?;
 		slate = client3.send_tx_slate_direct("wallet2", &slate_i)?;
 		sender_api.tx_lock_outputs(m, &slate, 0)?;
-		slate = sender_api.finalize_tx(m, &slate)?;
-		sender_api.post_tx(m, &slate.tx, false)?;
+		slate = sender_api.finalize_tx(m, &slate, None)?;
+		sender_api.post_tx(m, &slate.tx, Some(false))?;
 		Ok(())
 	})?;
 
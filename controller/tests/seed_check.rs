@@ -0,0 +1,117 @@
+// Copyright 2019 The Grin Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test verifying the recovery phrase before the first send
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_util::grin_util as util;
+
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{ErrorKind, FeatureFlags, InitTxArgs};
+use std::thread;
+use std::time::Duration;
+use util::ZeroingString;
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+fn seed_check_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	let mnemonic = {
+		let mut w_lock = wallet1.lock();
+		let lc = w_lock.lc_provider()?;
+		lc.get_mnemonic(None, ZeroingString::from(""))?
+	};
+	let words: Vec<String> = mnemonic.split_whitespace().map(|w| w.to_owned()).collect();
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+	let args = InitTxArgs {
+		src_acct_name: None,
+		amount: 60_000_000_000,
+		minimum_confirmations: 2,
+		max_outputs: 500,
+		num_change_outputs: 1,
+		selection_strategy_is_use_all: false,
+		..Default::default()
+	};
+
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		// sends are only held back once the check is turned on
+		api.init_send_tx(m, args.clone())?;
+		let flags = FeatureFlags {
+			seed_backup_check: true,
+			..FeatureFlags::default()
+		};
+		api.set_feature_flags(m, flags)?;
+		let res = api.init_send_tx(m, args.clone());
+		assert_eq!(res.unwrap_err().kind(), ErrorKind::SeedBackupUnverified);
+
+		// too few or wrong words don't verify the phrase
+		let res = api.verify_seed_backup(m, vec![(1, words[0].clone()), (1, words[0].clone())]);
+		assert!(res.is_err());
+		let wrong = vec![
+			(1, words[0].clone()),
+			(5, words[4].clone()),
+			(9, "notaword".to_owned()),
+		];
+		assert!(!api.verify_seed_backup(m, wrong)?);
+		assert!(api.init_send_tx(m, args.clone()).is_err());
+
+		// words are matched regardless of case
+		let right = vec![
+			(2, words[1].to_uppercase()),
+			(7, words[6].clone()),
+			(24, words[23].clone()),
+		];
+		assert!(api.verify_seed_backup(m, right)?);
+		api.init_send_tx(m, args.clone())?;
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn seed_check() {
+	let test_dir = "test_output/seed_check";
+	if let Err(e) = seed_check_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
@@ -99,8 +99,8 @@ fn self_send_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
 			slate = api.receive_tx(&slate, Some("listener"), None)?;
 			Ok(())
 		})?;
-		slate = api.finalize_tx(m, &slate)?;
-		api.post_tx(m, &slate.tx, false)?; // mines a block
+		slate = api.finalize_tx(m, &slate, None)?;
+		api.post_tx(m, &slate.tx, Some(false))?; // mines a block
 		bh += 1;
 		Ok(())
 	})?;
@@ -0,0 +1,134 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the updater spending received outputs back to the wallet
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_util::grin_core as core;
+
+use grin_wallet_api::WalletUpdater;
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, OutputStatus, StatusMessage, TxLogEntryType};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+fn self_spend_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: reward,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			..Default::default()
+		};
+		let slate = api.init_send_tx(m, args)?;
+		let slate = client1.send_tx_slate_direct("wallet2", &slate)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		let slate = api.finalize_tx(m, &slate, None)?;
+		api.post_tx(m, &slate.tx, false)?;
+		Ok(())
+	})?;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 2, false);
+
+	let updater = WalletUpdater::new(wallet2.clone(), None);
+	updater.set_self_spend_on_receive(true);
+	updater.start(Duration::from_millis(100), mask2.cloned());
+	thread::sleep(Duration::from_millis(1500));
+	updater.stop();
+	thread::sleep(Duration::from_millis(200));
+
+	// the received output was spent once, to an output of the wallet's own,
+	// whose transaction's kernel was recorded on confirmation
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, true, None, None, None)?;
+		let sent: Vec<_> = txs
+			.iter()
+			.filter(|t| t.tx_type == TxLogEntryType::TxSent)
+			.collect();
+		assert_eq!(sent.len(), 1);
+		assert!(sent[0].confirmed);
+		assert!(sent[0].kernel_excess.is_some());
+		let (_, outputs) = api.retrieve_outputs(m, true, false, None)?;
+		let unspent: Vec<_> = outputs
+			.iter()
+			.filter(|o| o.output.status == OutputStatus::Unspent)
+			.collect();
+		assert_eq!(unspent.len(), 1);
+		assert_eq!(unspent[0].output.tx_log_entry, Some(sent[0].id));
+		assert!(unspent[0].output.value < reward);
+		Ok(())
+	})?;
+	assert!(updater.messages(100).iter().any(|m| match m {
+		StatusMessage::ReceivedSelfSpent(_) => true,
+		_ => false,
+	}));
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn self_spend() {
+	let test_dir = "test_output/self_spend";
+	if let Err(e) = self_spend_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
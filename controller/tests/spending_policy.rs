@@ -0,0 +1,162 @@
+// Copyright 2019 The Grin Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test sends being held to the spending policy
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{ErrorKind, InitTxArgs, InitTxSendArgs, IssueInvoiceTxArgs, Slate, SpendingPolicy};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+fn spending_policy_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+	let args = |amount: u64| InitTxArgs {
+		src_acct_name: None,
+		amount,
+		minimum_confirmations: 2,
+		max_outputs: 500,
+		num_change_outputs: 1,
+		selection_strategy_is_use_all: false,
+		..Default::default()
+	};
+	// the policy is held by each wallet instance, so only wallet 1 is limited
+	let set_policy = |policy: &SpendingPolicy| -> Result<(), libwallet::Error> {
+		let mut w_lock = wallet1.lock();
+		w_lock.lc_provider()?.set_spending_policy(policy.clone());
+		Ok(())
+	};
+	let mut policy = SpendingPolicy {
+		max_amount_per_tx: Some(30_000_000_000),
+		max_daily_total: Some(50_000_000_000),
+		allowed_destinations: None,
+	};
+	set_policy(&policy)?;
+
+	// paying an invoice is a send like any other
+	let mut invoice = Slate::blank(2);
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let args = IssueInvoiceTxArgs {
+			amount: 40_000_000_000,
+			..Default::default()
+		};
+		invoice = api.issue_invoice_tx(m, args)?;
+		Ok(())
+	})?;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let res = api.process_invoice_tx(m, &invoice, args(invoice.amount));
+		match res.unwrap_err().kind() {
+			ErrorKind::SpendingPolicy(_) => {}
+			e => panic!("unexpected error {}", e),
+		}
+		Ok(())
+	})?;
+
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let slate = api.init_send_tx(m, args(20_000_000_000))?;
+		let slate = client1.send_tx_slate_direct("wallet2", &slate)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		api.finalize_tx(m, &slate, None)?;
+
+		// over the limit per transaction
+		let res = api.init_send_tx(m, args(40_000_000_000));
+		match res.unwrap_err().kind() {
+			ErrorKind::SpendingPolicy(_) => {}
+			e => panic!("unexpected error {}", e),
+		}
+
+		// within the daily limit when started, but not once it's lowered
+		let slate = api.init_send_tx(m, args(25_000_000_000))?;
+		let slate = client1.send_tx_slate_direct("wallet2", &slate)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		policy.max_daily_total = Some(40_000_000_000);
+		set_policy(&policy)?;
+		let res = api.finalize_tx(m, &slate, None);
+		match res.unwrap_err().kind() {
+			ErrorKind::SpendingPolicy(_) => {}
+			e => panic!("unexpected error {}", e),
+		}
+
+		// only sends to allowed destinations are started
+		policy.max_daily_total = None;
+		policy.allowed_destinations = Some(vec!["http://exchange.example.com".to_owned()]);
+		set_policy(&policy)?;
+		assert!(api.init_send_tx(m, args(1_000_000_000)).is_err());
+		let mut to_other = args(1_000_000_000);
+		to_other.send_args = Some(InitTxSendArgs {
+			method: "http".to_owned(),
+			dest: "http://other.example.com".to_owned(),
+			finalize: true,
+			post_tx: true,
+			fluff: false,
+		});
+		match api.init_send_tx(m, to_other).unwrap_err().kind() {
+			ErrorKind::SpendingPolicy(_) => {}
+			e => panic!("unexpected error {}", e),
+		}
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn spending_policy() {
+	let test_dir = "test_output/spending_policy";
+	if let Err(e) = spending_policy_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
@@ -0,0 +1,152 @@
+// Copyright 2019 The Grin Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test TOTP codes asked for on spends made through the V2 Owner API
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use futures::{Future, Stream};
+use grin_wallet_api::CallQueue;
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_api::Handler;
+use hyper::{Body, Request};
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, TotpSecret, TOTP_STEP_SECS};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use wallet::controller::{OwnerAPIHandlerV2, OwnerHandlerConfig, TOTP_CODE_HEADER};
+use wallet::response::ResponseFormat;
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+fn now() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.unwrap()
+		.as_secs()
+}
+
+fn totp_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+
+	// enroll a secret, confirmed with the code an authenticator would show
+	let mut secret = None;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let enrollment = api.enroll_totp(m)?;
+		let totp = TotpSecret {
+			secret: enrollment.secret,
+			enabled: false,
+			last_used_step: None,
+		};
+		api.confirm_totp(m, &totp.code(now()).unwrap())?;
+		secret = Some(totp);
+		Ok(())
+	})?;
+	let secret = secret.unwrap();
+
+	let handler = OwnerAPIHandlerV2::new(
+		wallet1.clone(),
+		OwnerHandlerConfig {
+			webhooks: None,
+			email: None,
+			metrics: None,
+			node_monitor: None,
+			updater: None,
+			operations: None,
+			memo_escrow_key: None,
+			audit_log: None,
+			call_queue: Arc::new(CallQueue::new()),
+			envelope: false,
+			format: ResponseFormat::default(),
+			read_only: false,
+			shutdown: None,
+			auth_tokens: None,
+			wallet_id: None,
+			trusted_proxies: vec![],
+		},
+	);
+	let args = InitTxArgs {
+		src_acct_name: None,
+		amount: 1_000_000_000,
+		minimum_confirmations: 2,
+		max_outputs: 500,
+		num_change_outputs: 1,
+		selection_strategy_is_use_all: false,
+		..Default::default()
+	};
+	// a V2 spend, its arguments positional
+	let init_send_tx = |code: Option<String>| -> Value {
+		let body = json!({
+			"jsonrpc": "2.0",
+			"method": "init_send_tx",
+			"id": 1,
+			"params": [args],
+		});
+		let mut req = Request::post("/v2/owner");
+		if let Some(c) = code {
+			req.header(TOTP_CODE_HEADER, c.as_str());
+		}
+		let req = req.body(Body::from(body.to_string())).unwrap();
+		let res = handler.post(req).wait().unwrap();
+		let body = res.into_body().concat2().wait().unwrap();
+		serde_json::from_slice(&body).unwrap()
+	};
+
+	// refused without a code
+	let res = init_send_tx(None);
+	assert!(!res["result"]["Err"].is_null());
+
+	// the header carries a code not used before
+	let res = init_send_tx(secret.code(now() + TOTP_STEP_SECS));
+	assert!(!res["result"]["Ok"].is_null());
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn totp() {
+	let test_dir = "test_output/totp";
+	if let Err(e) = totp_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
@@ -22,7 +22,10 @@ use grin_wallet_util::grin_core as core;
 
 use self::core::core::transaction;
 use self::core::global;
-use self::libwallet::{InitTxArgs, OutputStatus, Slate};
+use self::libwallet::{
+	InitTxArgs, OutputStatus, RetrieveTxQueryArgs, Slate, TxLogEntryType, TxQueryStatus,
+	TxSortOrder, TxStep,
+};
 use impls::test_framework::{self, LocalWalletClient};
 use std::thread;
 use std::time::Duration;
@@ -116,7 +119,7 @@ fn basic_transaction_api(test_dir: &'static str) -> Result<(), libwallet::Error>
 
 		slate = client1.send_tx_slate_direct("wallet2", &slate_i)?;
 		sender_api.tx_lock_outputs(m, &slate, 0)?;
-		slate = sender_api.finalize_tx(m, &slate)?;
+		slate = sender_api.finalize_tx(m, &slate, None)?;
 
 		// Check we have a single kernel and that it is a Plain kernel (no lock_height).
 		assert_eq!(slate.tx.kernels().len(), 1);
@@ -135,7 +138,7 @@ fn basic_transaction_api(test_dir: &'static str) -> Result<(), libwallet::Error>
 	// Check transaction log for wallet 1
 	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
 		let (_, wallet1_info) = api.retrieve_summary_info(m, true, 1)?;
-		let (refreshed, txs) = api.retrieve_txs(m, true, None, None)?;
+		let (refreshed, txs) = api.retrieve_txs(m, true, None, None, None)?;
 		assert!(refreshed);
 		let fee = core::libtx::tx_fee(
 			wallet1_info.last_confirmed_height as usize - cm as usize,
@@ -152,12 +155,19 @@ fn basic_transaction_api(test_dir: &'static str) -> Result<(), libwallet::Error>
 		assert_eq!(tx.amount_debited - tx.amount_credited, fee + amount);
 		println!("tx: {:?}", tx);
 		assert_eq!(Some(fee), tx.fee);
+		// versions are recorded as the sender locks and finalizes
+		let steps: Vec<TxStep> = tx.step_versions.iter().map(|v| v.step).collect();
+		assert_eq!(steps, vec![TxStep::Lock, TxStep::Finalize]);
+		assert!(tx
+			.step_versions
+			.iter()
+			.all(|v| v.wallet_version == libwallet::WALLET_VERSION && v.slate_version == 2));
 		Ok(())
 	})?;
 
 	// Check transaction log for wallet 2
 	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
-		let (refreshed, txs) = api.retrieve_txs(m, true, None, None)?;
+		let (refreshed, txs) = api.retrieve_txs(m, true, None, None, None)?;
 		assert!(refreshed);
 		// we should have a transaction entry for this slate
 		let tx = txs.iter().find(|t| t.tx_slate_id == Some(slate.id));
@@ -168,12 +178,14 @@ fn basic_transaction_api(test_dir: &'static str) -> Result<(), libwallet::Error>
 		assert_eq!(amount, tx.amount_credited);
 		assert_eq!(0, tx.amount_debited);
 		assert_eq!(None, tx.fee);
+		let steps: Vec<TxStep> = tx.step_versions.iter().map(|v| v.step).collect();
+		assert_eq!(steps, vec![TxStep::Receive]);
 		Ok(())
 	})?;
 
 	// post transaction
 	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
-		api.post_tx(m, &slate.tx, false)?;
+		api.post_tx(m, &slate.tx, Some(false))?;
 		Ok(())
 	})?;
 
@@ -203,7 +215,7 @@ fn basic_transaction_api(test_dir: &'static str) -> Result<(), libwallet::Error>
 		assert_eq!(wallet1_info.amount_immature, cm * reward + fee);
 
 		// check tx log entry is confirmed
-		let (refreshed, txs) = api.retrieve_txs(m, true, None, None)?;
+		let (refreshed, txs) = api.retrieve_txs(m, true, None, None, None)?;
 		assert!(refreshed);
 		let tx = txs.iter().find(|t| t.tx_slate_id == Some(slate.id));
 		assert!(tx.is_some());
@@ -239,7 +251,7 @@ fn basic_transaction_api(test_dir: &'static str) -> Result<(), libwallet::Error>
 		assert_eq!(wallet2_info.amount_currently_spendable, amount);
 
 		// check tx log entry is confirmed
-		let (refreshed, txs) = api.retrieve_txs(m, true, None, None)?;
+		let (refreshed, txs) = api.retrieve_txs(m, true, None, None, None)?;
 		assert!(refreshed);
 		let tx = txs.iter().find(|t| t.tx_slate_id == Some(slate.id));
 		assert!(tx.is_some());
@@ -298,21 +310,21 @@ fn basic_transaction_api(test_dir: &'static str) -> Result<(), libwallet::Error>
 		let slate_i = sender_api.init_send_tx(m, args)?;
 		slate = client1.send_tx_slate_direct("wallet2", &slate_i)?;
 		sender_api.tx_lock_outputs(m, &slate, 0)?;
-		slate = sender_api.finalize_tx(m, &slate)?;
+		slate = sender_api.finalize_tx(m, &slate, None)?;
 		Ok(())
 	})?;
 
 	wallet::controller::owner_single_use(wallet1.clone(), mask1, |sender_api, m| {
 		let (refreshed, _wallet1_info) = sender_api.retrieve_summary_info(m, true, 1)?;
 		assert!(refreshed);
-		let (_, txs) = sender_api.retrieve_txs(m, true, None, None)?;
+		let (_, txs) = sender_api.retrieve_txs(m, true, None, None, None)?;
 		// find the transaction
 		let tx = txs
 			.iter()
 			.find(|t| t.tx_slate_id == Some(slate.id))
 			.unwrap();
 		let stored_tx = sender_api.get_stored_tx(m, &tx)?;
-		sender_api.post_tx(m, &stored_tx.unwrap(), false)?;
+		sender_api.post_tx(m, &stored_tx.unwrap(), Some(false))?;
 		let (_, wallet1_info) = sender_api.retrieve_summary_info(m, true, 1)?;
 		// should be mined now
 		assert_eq!(
@@ -322,6 +334,30 @@ fn basic_transaction_api(test_dir: &'static str) -> Result<(), libwallet::Error>
 		Ok(())
 	})?;
 
+	// pages of the newest entries, sent ones only
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, all) = api.retrieve_txs(m, false, None, None, None)?;
+		let mut newest: Vec<u32> = all.iter().map(|t| t.id).collect();
+		newest.sort();
+		newest.reverse();
+		let query = |offset, statuses| RetrieveTxQueryArgs {
+			offset: Some(offset),
+			limit: Some(2),
+			statuses,
+			sort_order: Some(TxSortOrder::Desc),
+			..Default::default()
+		};
+		let (_, page) = api.retrieve_txs(m, false, None, None, Some(query(1, None)))?;
+		let ids: Vec<u32> = page.iter().map(|t| t.id).collect();
+		assert_eq!(ids, newest[1..3].to_vec());
+		let sent = Some(vec![TxQueryStatus::Sent]);
+		let (_, page) = api.retrieve_txs(m, false, None, None, Some(query(0, sent)))?;
+		assert_eq!(page.len(), 2);
+		assert!(page[0].id > page[1].id);
+		assert!(page.iter().all(|t| t.tx_type == TxLogEntryType::TxSent));
+		Ok(())
+	})?;
+
 	// mine a few more blocks
 	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
 
@@ -332,7 +368,7 @@ fn basic_transaction_api(test_dir: &'static str) -> Result<(), libwallet::Error>
 		assert_eq!(wallet2_info.amount_currently_spendable, amount * 3);
 
 		// check tx log entry is confirmed
-		let (refreshed, txs) = api.retrieve_txs(m, true, None, None)?;
+		let (refreshed, txs) = api.retrieve_txs(m, true, None, None, None)?;
 		assert!(refreshed);
 		let tx = txs.iter().find(|t| t.tx_slate_id == Some(slate.id));
 		assert!(tx.is_some());
@@ -388,7 +424,7 @@ fn tx_rollback(test_dir: &'static str) -> Result<(), libwallet::Error> {
 	// few values to keep things shorter
 	let reward = core::consensus::REWARD;
 	let cm = global::coinbase_maturity(); // assume all testing precedes soft fork height
-									  // mine a few blocks
+									   // mine a few blocks
 	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
 
 	let amount = 30_000_000_000;
@@ -408,7 +444,7 @@ fn tx_rollback(test_dir: &'static str) -> Result<(), libwallet::Error> {
 		let slate_i = sender_api.init_send_tx(m, args)?;
 		slate = client1.send_tx_slate_direct("wallet2", &slate_i)?;
 		sender_api.tx_lock_outputs(m, &slate, 0)?;
-		slate = sender_api.finalize_tx(m, &slate)?;
+		slate = sender_api.finalize_tx(m, &slate, None)?;
 		Ok(())
 	})?;
 
@@ -420,7 +456,7 @@ fn tx_rollback(test_dir: &'static str) -> Result<(), libwallet::Error> {
 			wallet1_info.last_confirmed_height
 		);
 		assert!(refreshed);
-		let (_, txs) = api.retrieve_txs(m, true, None, None)?;
+		let (_, txs) = api.retrieve_txs(m, true, None, None, None)?;
 		// we should have a transaction entry for this slate
 		let tx = txs.iter().find(|t| t.tx_slate_id == Some(slate.id));
 		assert!(tx.is_some());
@@ -445,7 +481,7 @@ fn tx_rollback(test_dir: &'static str) -> Result<(), libwallet::Error> {
 
 	// Check transaction log for wallet 2
 	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
-		let (refreshed, txs) = api.retrieve_txs(m, true, None, None)?;
+		let (refreshed, txs) = api.retrieve_txs(m, true, None, None, None)?;
 		assert!(refreshed);
 		let mut unconfirmed_count = 0;
 		let tx = txs.iter().find(|t| t.tx_slate_id == Some(slate.id));
@@ -475,7 +511,7 @@ fn tx_rollback(test_dir: &'static str) -> Result<(), libwallet::Error> {
 		// can't roll back coinbase
 		let res = api.cancel_tx(m, Some(1), None);
 		assert!(res.is_err());
-		let (_, txs) = api.retrieve_txs(m, true, None, None)?;
+		let (_, txs) = api.retrieve_txs(m, true, None, None, None)?;
 		let tx = txs
 			.iter()
 			.find(|t| t.tx_slate_id == Some(slate.id))
@@ -502,7 +538,7 @@ fn tx_rollback(test_dir: &'static str) -> Result<(), libwallet::Error> {
 
 	// Wallet 2 rolls back
 	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
-		let (_, txs) = api.retrieve_txs(m, true, None, None)?;
+		let (_, txs) = api.retrieve_txs(m, true, None, None, None)?;
 		let tx = txs
 			.iter()
 			.find(|t| t.tx_slate_id == Some(slate.id))
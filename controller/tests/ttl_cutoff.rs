@@ -0,0 +1,129 @@
+// Copyright 2019 The Grin Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test slates refused once past their TTL cutoff height
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{ErrorKind, InitTxArgs, Slate};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+fn expired(res: Result<(), libwallet::Error>) -> bool {
+	match res.map_err(|e| e.kind()) {
+		Err(ErrorKind::TransactionExpired(_, _)) => true,
+		_ => false,
+	}
+}
+
+fn ttl_cutoff_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+	let args = |ttl_blocks| InitTxArgs {
+		src_acct_name: None,
+		amount: 60_000_000_000,
+		minimum_confirmations: 2,
+		max_outputs: 500,
+		num_change_outputs: 1,
+		selection_strategy_is_use_all: true,
+		ttl_blocks,
+		..Default::default()
+	};
+
+	// the cutoff is carried in the slate
+	let mut slate = Slate::blank(1);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		slate = api.init_send_tx(m, args(Some(2)))?;
+		assert_eq!(slate.ttl_cutoff_height, Some(slate.height + 2));
+		Ok(())
+	})?;
+	let json = serde_json::to_string(&slate).unwrap();
+	assert_eq!(
+		Slate::deserialize_upgrade(&json)?.ttl_cutoff_height,
+		slate.ttl_cutoff_height
+	);
+
+	// and the receiver refuses the slate once the chain reaches it
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 2, false);
+	assert!(expired(wallet::controller::foreign_single_use(
+		wallet2.clone(),
+		mask2_i.clone(),
+		|api| {
+			api.receive_tx(&slate, None, None)?;
+			Ok(())
+		}
+	)));
+
+	// as does the sender when finalizing
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let slate = api.init_send_tx(m, args(Some(3)))?;
+		let slate = client1.send_tx_slate_direct("wallet2", &slate)?;
+		assert_eq!(slate.ttl_cutoff_height, Some(slate.height + 3));
+		api.tx_lock_outputs(m, &slate, 0)?;
+		let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+		assert!(expired(api.finalize_tx(m, &slate, None).map(|_| ())));
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn ttl_cutoff() {
+	let test_dir = "test_output/ttl_cutoff";
+	if let Err(e) = ttl_cutoff_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
@@ -0,0 +1,158 @@
+// Copyright 2019 The Grin Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test Dandelion preferences stored with transactions
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::api_impl::owner;
+use libwallet::{InitTxArgs, IssueInvoiceTxArgs, Slate};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{create_wallet_proxy, setup};
+
+fn tx_fluff_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	setup(test_dir);
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+
+	// the preference given when sending is stored, and finalizing can replace it
+	let mut slate = Slate::blank(1);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: 60_000_000_000,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: true,
+			fluff: Some(true),
+			..Default::default()
+		};
+		slate = api.init_send_tx(m, args)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		let (_, txs) = api.retrieve_txs(m, false, None, Some(slate.id), None)?;
+		assert_eq!(txs[0].fluff, Some(true));
+
+		slate = client1.send_tx_slate_direct("wallet2", &slate)?;
+		slate = api.finalize_tx(m, &slate, Some(false))?;
+		let (_, txs) = api.retrieve_txs(m, false, None, Some(slate.id), None)?;
+		assert_eq!(txs[0].fluff, Some(false));
+		Ok(())
+	})?;
+	{
+		wallet_inst!(wallet1, w);
+		assert_eq!(owner::stored_fluff(&mut **w, &slate.tx)?, Some(false));
+	}
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		api.post_tx(m, &slate.tx, None)?;
+		Ok(())
+	})?;
+
+	// a merchant can have paid invoices fluffed, however they're posted
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let args = IssueInvoiceTxArgs {
+			amount: 30_000_000_000,
+			fluff: Some(true),
+			..Default::default()
+		};
+		slate = api.issue_invoice_tx(m, args)?;
+		let (_, txs) = api.retrieve_txs(m, false, None, Some(slate.id), None)?;
+		assert_eq!(txs[0].fluff, Some(true));
+		Ok(())
+	})?;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: slate.amount,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: true,
+			..Default::default()
+		};
+		slate = api.process_invoice_tx(m, &slate, args)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		Ok(())
+	})?;
+	wallet::controller::foreign_single_use(wallet2.clone(), mask2_i.clone(), |api| {
+		slate = api.finalize_invoice_tx(&slate)?;
+		Ok(())
+	})?;
+	{
+		wallet_inst!(wallet2, w);
+		assert_eq!(owner::stored_fluff(&mut **w, &slate.tx)?, Some(true));
+	}
+	{
+		// the payer stored no preference
+		wallet_inst!(wallet1, w);
+		assert_eq!(owner::stored_fluff(&mut **w, &slate.tx)?, None);
+	}
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, false, None, Some(slate.id), None)?;
+		assert!(api.get_stored_tx(m, &txs[0])?.is_some());
+		api.post_tx(m, &slate.tx, None)?;
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn tx_fluff() {
+	let test_dir = "test_output/tx_fluff";
+	if let Err(e) = tx_fluff_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
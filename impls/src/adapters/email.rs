@@ -0,0 +1,435 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Email Wallet 'plugin' implementation. Slates are sent as attachments
+//! through an SMTP server, and picked up from an IMAP folder, both with
+//! the system's `curl`
+
+use crate::adapters::{SlateReceiver, SlateSender, SlateTransport};
+use crate::config::{EmailSlateConfig, WalletConfig};
+use crate::core::core::amount_to_hr_string;
+use crate::email::{curl_options, curl_quote, run_curl, send_raw};
+use crate::keychain::ExtKeychain;
+use crate::libwallet::api_impl::foreign;
+use crate::libwallet::{log_amount, Error, ErrorKind, Slate, WalletInst};
+use crate::util::{to_base64, ZeroingString};
+use crate::{DefaultLCProvider, DefaultWalletImpl, HTTPNodeClient, Socks5Proxy};
+use chrono::Utc;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// How often the folder is checked, in seconds, unless configured
+const DEFAULT_POLL_INTERVAL: u64 = 30;
+/// How long a sender waits for the signed slate, in seconds, unless configured
+const DEFAULT_REPLY_TIMEOUT: u64 = 600;
+
+/// Subject of the emails carrying a slate, followed by the slate's id
+const SUBJECT: &str = "Grin slate";
+/// Ends the subject of the replies carrying the signed slate
+const SIGNED: &str = "signed";
+
+/// The configured mailbox, reached with curl
+#[derive(Clone)]
+struct Mailbox {
+	config: EmailSlateConfig,
+}
+
+impl Mailbox {
+	fn poll_interval(&self) -> Duration {
+		Duration::from_secs(
+			self.config
+				.poll_interval
+				.unwrap_or(DEFAULT_POLL_INTERVAL)
+				.max(1),
+		)
+	}
+
+	fn options(&self, url: &str) -> Vec<String> {
+		curl_options(
+			url,
+			self.config.username.as_ref(),
+			self.config.password.as_ref(),
+			self.config.require_tls.unwrap_or(true),
+		)
+	}
+
+	/// Email the slate to the address
+	fn send(&self, to: &str, subject: &str, slate: &Slate) -> Result<(), Error> {
+		let email = slate_email(&self.config.address, to, subject, slate)?;
+		send_raw(
+			self.options(&self.config.smtp_url),
+			&self.config.address,
+			&[to.to_owned()],
+			&email,
+		)
+		.map_err(|e| ErrorKind::WalletComms(format!("Emailing slate to {}: {}", to, e)).into())
+	}
+
+	/// UIDs of the unread messages in the folder matching the IMAP search
+	/// criteria
+	fn unread(&self, criteria: &str) -> Result<Vec<u32>, Error> {
+		let mut options = self.options(&self.config.imap_url);
+		let search = format!("UID SEARCH UNSEEN {}", criteria);
+		options.push(format!("request = {}", curl_quote(&search)));
+		let res = run_curl(&options)
+			.map_err(|e| ErrorKind::WalletComms(format!("Searching mailbox: {}", e)))?;
+		Ok(search_results(&String::from_utf8_lossy(&res)))
+	}
+
+	/// The message, which is marked as read
+	fn fetch(&self, uid: u32) -> Result<String, Error> {
+		let url = format!("{};UID={}", self.config.imap_url.trim_end_matches('/'), uid);
+		let res = run_curl(&self.options(&url))
+			.map_err(|e| ErrorKind::WalletComms(format!("Fetching message {}: {}", uid, e)))?;
+		Ok(String::from_utf8_lossy(&res).into_owned())
+	}
+}
+
+/// Transport emailing slates to addresses, given as `email://<address>` or
+/// just the address, and listening for slates arriving in the mailbox
+pub struct EmailTransport {
+	mailbox: Mailbox,
+}
+
+impl EmailTransport {
+	/// Create a transport exchanging slates through the configured mailbox
+	pub fn new(config: EmailSlateConfig) -> EmailTransport {
+		EmailTransport {
+			mailbox: Mailbox { config },
+		}
+	}
+}
+
+impl SlateTransport for EmailTransport {
+	fn sender(&self, dest: &str) -> Result<Box<dyn SlateSender>, Error> {
+		let to = dest.trim_start_matches("email://").to_owned();
+		if !to.contains('@') {
+			let msg = format!("Invalid email address: {}", dest);
+			return Err(ErrorKind::WalletComms(msg).into());
+		}
+		Ok(Box::new(EmailSlateSender {
+			mailbox: self.mailbox.clone(),
+			to,
+		}))
+	}
+
+	fn receiver(&self) -> Result<Option<Box<dyn SlateReceiver>>, Error> {
+		Ok(Some(Box::new(EmailSlateReceiver {
+			mailbox: self.mailbox.clone(),
+		})))
+	}
+}
+
+/// Emails slates to an address, waiting for the signed slate to be emailed
+/// back
+pub struct EmailSlateSender {
+	mailbox: Mailbox,
+	to: String,
+}
+
+impl SlateSender for EmailSlateSender {
+	fn send_tx(&self, slate: &Slate) -> Result<Slate, Error> {
+		let subject = format!("{} {}", SUBJECT, slate.id);
+		self.mailbox.send(&self.to, &subject, slate)?;
+		info!("Slate {} has been emailed to {}", slate.id, self.to);
+
+		let reply = format!("{} {}", subject, SIGNED);
+		let timeout = self
+			.mailbox
+			.config
+			.reply_timeout
+			.unwrap_or(DEFAULT_REPLY_TIMEOUT);
+		let start = Instant::now();
+		info!("Waiting for the reply from {}...", self.to);
+		while start.elapsed().as_secs() < timeout {
+			for uid in self
+				.mailbox
+				.unread(&format!("SUBJECT {}", imap_quote(&reply)))?
+			{
+				match parse_email(&self.mailbox.fetch(uid)?) {
+					Some((_, s)) if s.id == slate.id => {
+						info!("Signed slate {} received from {}", s.id, self.to);
+						return Ok(s);
+					}
+					_ => debug!("Message {} doesn't hold the signed slate", uid),
+				}
+			}
+			sleep(self.mailbox.poll_interval());
+		}
+		let msg = format!("No reply from {} in {} seconds", self.to, timeout);
+		Err(ErrorKind::ClientCallback(msg).into())
+	}
+}
+
+/// Receives the slates emailed to the mailbox, replying with the signed slate
+pub struct EmailSlateReceiver {
+	mailbox: Mailbox,
+}
+
+impl SlateReceiver for EmailSlateReceiver {
+	/// Start a listener, passing received slates to the wallet api directly
+	fn listen(
+		&self,
+		config: WalletConfig,
+		passphrase: ZeroingString,
+		account: &str,
+		node_api_secret: Option<String>,
+	) -> Result<(), Error> {
+		let mut node_client =
+			HTTPNodeClient::new(&config.check_node_api_http_addr, node_api_secret);
+		node_client.set_archive_node_url(config.archive_node_api_http_addr.clone());
+		node_client.set_proxy(config.proxy.as_ref().map(Socks5Proxy::new));
+		let mut wallet = Box::new(
+			DefaultWalletImpl::<'static, HTTPNodeClient>::new(node_client.clone()).unwrap(),
+		)
+			as Box<
+				WalletInst<
+					'static,
+					DefaultLCProvider<HTTPNodeClient, ExtKeychain>,
+					HTTPNodeClient,
+					ExtKeychain,
+				>,
+			>;
+		let lc = wallet.lc_provider().unwrap();
+		lc.set_wallet_directory(&config.data_file_dir);
+		let mask = lc.open_wallet(None, passphrase, true, false)?;
+		let wallet_inst = lc.wallet_inst()?;
+		wallet_inst.set_parent_key_id_by_name(account)?;
+
+		info!(
+			"Listening for transactions emailed to {} ...",
+			self.mailbox.config.address
+		);
+		// replies to slates this wallet sent are left to the sender
+		let criteria = format!(
+			"SUBJECT {} NOT SUBJECT {}",
+			imap_quote(SUBJECT),
+			imap_quote(SIGNED)
+		);
+		loop {
+			// the mailbox may be unreachable for a while, so failures are
+			// only logged
+			let unread = match self.mailbox.unread(&criteria) {
+				Ok(u) => u,
+				Err(e) => {
+					error!("{}", e);
+					vec![]
+				}
+			};
+			for uid in unread {
+				let (from, slate) = match self.mailbox.fetch(uid).map(|m| parse_email(&m)) {
+					Ok(Some((from, slate))) => (from, slate),
+					Ok(None) => {
+						debug!("Message {} doesn't hold a slate", uid);
+						continue;
+					}
+					Err(e) => {
+						error!("{}", e);
+						continue;
+					}
+				};
+				info!(
+					"tx emailed by {}, to send you {} grin. tx uuid: {}",
+					from,
+					log_amount(slate.amount),
+					slate.id,
+				);
+				if let Err(e) = slate.verify_messages() {
+					error!("Error validating participant messages: {}", e);
+					continue;
+				}
+				let res = foreign::receive_tx(
+					&mut **wallet_inst,
+					mask.as_ref(),
+					&slate,
+					None,
+					None,
+					false,
+				);
+				match res {
+					Ok(s) => {
+						let subject = format!("{} {} {}", SUBJECT, s.id, SIGNED);
+						match self.mailbox.send(&from, &subject, &s) {
+							Ok(()) => debug!("Returned slate to {} by email", from),
+							Err(e) => error!("{}. Incoming tx failed", e),
+						}
+					}
+					Err(e) => error!("Error on receiving tx by email: {}. Incoming tx failed", e),
+				}
+			}
+			sleep(self.mailbox.poll_interval());
+		}
+	}
+}
+
+/// Quote a string for an IMAP command
+fn imap_quote(value: &str) -> String {
+	format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// UIDs listed in the response to a UID SEARCH command
+fn search_results(response: &str) -> Vec<u32> {
+	response
+		.lines()
+		.filter(|l| l.starts_with("* SEARCH"))
+		.flat_map(|l| l["* SEARCH".len()..].split_whitespace())
+		.filter_map(|uid| uid.parse().ok())
+		.collect()
+}
+
+/// The email carrying the slate as a JSON attachment
+fn slate_email(from: &str, to: &str, subject: &str, slate: &Slate) -> Result<String, Error> {
+	let json = serde_json::to_string(slate).map_err(|_| ErrorKind::SlateSer)?;
+	let attachment: Vec<String> = to_base64(&json)
+		.as_bytes()
+		.chunks(76)
+		.map(|l| String::from_utf8_lossy(l).into_owned())
+		.collect();
+	let boundary = format!("grin-{}", Uuid::new_v4());
+	Ok(format!(
+		"From: {from}\r\nTo: {to}\r\nSubject: {subject}\r\nDate: {date}\r\n\
+		 MIME-Version: 1.0\r\n\
+		 Content-Type: multipart/mixed; boundary=\"{boundary}\"\r\n\r\n\
+		 --{boundary}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n\
+		 The attached slate is for a transaction of {amount} grin, to be \
+		 processed by a Grin wallet.\r\n\
+		 --{boundary}\r\n\
+		 Content-Type: application/json; name=\"{id}.tx\"\r\n\
+		 Content-Disposition: attachment; filename=\"{id}.tx\"\r\n\
+		 Content-Transfer-Encoding: base64\r\n\r\n\
+		 {attachment}\r\n--{boundary}--\r\n",
+		from = from,
+		to = to,
+		subject = subject,
+		date = Utc::now().to_rfc2822(),
+		boundary = boundary,
+		amount = amount_to_hr_string(slate.amount, false),
+		id = slate.id,
+		attachment = attachment.join("\r\n"),
+	))
+}
+
+/// Address to reply to and the slate held by the email, either as an
+/// attachment or as the body itself
+fn parse_email(email: &str) -> Option<(String, Slate)> {
+	let email = email.replace("\r\n", "\n");
+	let (headers, _) = split_part(&email);
+	let from = header(headers, "reply-to").or_else(|| header(headers, "from"))?;
+	let from = match (from.find('<'), from.rfind('>')) {
+		(Some(start), Some(end)) if start < end => from[start + 1..end].to_owned(),
+		_ => from.trim().to_owned(),
+	};
+	// boundary lines of any part start with "--", which neither JSON nor
+	// base64 lines do
+	let slate = email
+		.split("\n--")
+		.filter_map(|part| {
+			let (headers, body) = split_part(part);
+			let body = match header(headers, "content-transfer-encoding") {
+				Some(ref e) if e.eq_ignore_ascii_case("base64") => {
+					String::from_utf8(from_base64(body)?).ok()?
+				}
+				_ => body.to_owned(),
+			};
+			Slate::deserialize_upgrade(body.trim()).ok()
+		})
+		.next()?;
+	Some((from, slate))
+}
+
+/// Headers and body of a message or part of one
+fn split_part(part: &str) -> (&str, &str) {
+	match part.find("\n\n") {
+		Some(i) => (&part[..i], &part[i + 2..]),
+		None => (part, ""),
+	}
+}
+
+/// Value of the header, if it's among the headers
+fn header(headers: &str, name: &str) -> Option<String> {
+	headers.lines().find_map(|l| {
+		let i = l.find(':')?;
+		match l[..i].trim().eq_ignore_ascii_case(name) {
+			true => Some(l[i + 1..].trim().to_owned()),
+			false => None,
+		}
+	})
+}
+
+/// Decode base64, ignoring line breaks
+fn from_base64(encoded: &str) -> Option<Vec<u8>> {
+	let mut decoded = vec![];
+	let mut bits = 0u32;
+	let mut num_bits = 0;
+	for c in encoded.bytes().filter(|c| !c.is_ascii_whitespace()) {
+		let value = match c {
+			b'A'..=b'Z' => c - b'A',
+			b'a'..=b'z' => c - b'a' + 26,
+			b'0'..=b'9' => c - b'0' + 52,
+			b'+' => 62,
+			b'/' => 63,
+			b'=' => break,
+			_ => return None,
+		};
+		bits = (bits << 6 | value as u32) & 0xffff;
+		num_bits += 6;
+		if num_bits >= 8 {
+			num_bits -= 8;
+			decoded.push((bits >> num_bits) as u8);
+		}
+	}
+	Some(decoded)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn slate_email_round_trip() {
+		let mut slate = Slate::blank(2);
+		slate.amount = 2_500_000_000;
+		let email = slate_email(
+			"alice@example.com",
+			"bob@example.com",
+			&format!("{} {}", SUBJECT, slate.id),
+			&slate,
+		)
+		.unwrap();
+		assert!(email.lines().all(|l| l.len() <= 998));
+		let (from, parsed) = parse_email(&email).unwrap();
+		assert_eq!(from, "alice@example.com");
+		assert_eq!(parsed.id, slate.id);
+		assert_eq!(parsed.amount, slate.amount);
+
+		// slates pasted into the body by hand are picked up too
+		let json = serde_json::to_string(&slate).unwrap();
+		let pasted = format!(
+			"From: Carol <carol@example.com>\r\nReply-To: <c@example.com>\r\n\
+			 Subject: slate\r\n\r\n{}\r\n",
+			json
+		);
+		let (from, parsed) = parse_email(&pasted).unwrap();
+		assert_eq!(from, "c@example.com");
+		assert_eq!(parsed.id, slate.id);
+		assert!(parse_email("From: x@example.com\r\n\r\nhello\r\n").is_none());
+
+		assert_eq!(from_base64("aGVsbG8h\r\nIQ==").unwrap(), b"hello!!");
+		assert_eq!(
+			search_results("* SEARCH 3 14 15\r\nA001 OK SEARCH completed\r\n"),
+			vec![3, 14, 15]
+		);
+	}
+}
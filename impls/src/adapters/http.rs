@@ -14,15 +14,40 @@
 
 /// HTTP Wallet 'plugin' implementation
 use crate::api;
-use crate::libwallet::{Error, ErrorKind, Slate};
-use crate::SlateSender;
+use crate::libwallet::{
+	negotiate_slate_version, redact_json, Error, ErrorKind, Slate, SlateVersion,
+};
+use crate::{SlateSender, SlateTransport, Socks5Proxy};
 use serde::Serialize;
 use serde_json::{json, Value};
 use url::Url;
 
+/// Transport sending slates to wallets listening on `http://` and
+/// `https://` URLs
+pub struct HttpTransport {
+	proxy: Option<Socks5Proxy>,
+}
+
+impl HttpTransport {
+	/// Create a transport whose senders post through the given proxy, if any
+	pub fn new(proxy: Option<Socks5Proxy>) -> HttpTransport {
+		HttpTransport { proxy }
+	}
+}
+
+impl SlateTransport for HttpTransport {
+	fn sender(&self, dest: &str) -> Result<Box<dyn SlateSender>, Error> {
+		let invalid = || ErrorKind::WalletComms(format!("Invalid http destination: {}", dest));
+		let url: Url = dest.parse().map_err(|_| invalid())?;
+		let sender = HttpSlateSender::new(url).map_err(|_| invalid())?;
+		Ok(Box::new(sender.with_proxy(self.proxy.clone())))
+	}
+}
+
 #[derive(Clone)]
 pub struct HttpSlateSender {
 	base_url: Url,
+	proxy: Option<Socks5Proxy>,
 }
 
 impl HttpSlateSender {
@@ -31,12 +56,45 @@ impl HttpSlateSender {
 		if base_url.scheme() != "http" && base_url.scheme() != "https" {
 			Err(SchemeNotHttp)
 		} else {
-			Ok(HttpSlateSender { base_url })
+			Ok(HttpSlateSender {
+				base_url,
+				proxy: None,
+			})
+		}
+	}
+
+	/// Post slates through the given SOCKS5 proxy
+	pub fn with_proxy(self, proxy: Option<Socks5Proxy>) -> HttpSlateSender {
+		HttpSlateSender { proxy, ..self }
+	}
+
+	/// Foreign API of the listening wallet: the destination itself if it
+	/// names one, such as the `/v2/foreign/<wallet_id>` of a hosted wallet,
+	/// or the listener's `/v2/foreign`
+	fn foreign_url(&self) -> Url {
+		match self.base_url.path().starts_with("/v2/foreign") {
+			true => self.base_url.clone(),
+			false => self
+				.base_url
+				.join("/v2/foreign")
+				.expect("/v2/foreign is an invalid url path"),
 		}
 	}
 
-	/// Check version of the listening wallet
-	fn check_other_version(&self) -> Result<(), Error> {
+	/// Post the request, through the proxy if there's one
+	fn post<IN>(&self, url: &Url, input: &IN) -> Result<String, api::Error>
+	where
+		IN: Serialize,
+	{
+		match self.proxy.as_ref() {
+			Some(p) => p.post(url.as_str(), None, input),
+			None => post(url, None, input),
+		}
+	}
+
+	/// Check version of the listening wallet, returning the newest slate
+	/// version both wallets read
+	fn check_other_version(&self) -> Result<SlateVersion, Error> {
 		let req = json!({
 			"jsonrpc": "2.0",
 			"method": "check_version",
@@ -44,7 +102,7 @@ impl HttpSlateSender {
 			"params": []
 		});
 
-		let res: String = post(&self.base_url, None, &req).map_err(|e| {
+		let res: String = self.post(&self.foreign_url(), &req).map_err(|e| {
 			let mut report = format!("Performing version check (is recipient listening?): {}", e);
 			let err_string = format!("{}", e);
 			if err_string.contains("404") {
@@ -59,7 +117,7 @@ impl HttpSlateSender {
 		})?;
 
 		let res: Value = serde_json::from_str(&res).unwrap();
-		trace!("Response: {}", res);
+		trace!("Response: {}", redact_json(&res));
 		if res["error"] != json!(null) {
 			let report = format!(
 				"Posting transaction slate: Error: {}, Message: {}",
@@ -70,7 +128,7 @@ impl HttpSlateSender {
 		}
 
 		let resp_value = res["result"]["Ok"].clone();
-		trace!("resp_value: {}", resp_value.clone());
+		trace!("resp_value: {}", redact_json(&resp_value));
 		let foreign_api_version: u16 =
 			serde_json::from_value(resp_value["foreign_api_version"].clone()).unwrap();
 		let supported_slate_versions: Vec<String> =
@@ -83,25 +141,22 @@ impl HttpSlateSender {
 			return Err(ErrorKind::ClientCallback(report).into());
 		}
 
-		if !supported_slate_versions.contains(&"V2".to_owned()) {
-			let report = format!("Unable to negotiate slate format with other wallet.");
-			error!("{}", report);
-			return Err(ErrorKind::ClientCallback(report).into());
-		}
-
-		Ok(())
+		negotiate_slate_version(&supported_slate_versions).map_err(|e| {
+			error!("{}", e);
+			e
+		})
 	}
 }
 
 impl SlateSender for HttpSlateSender {
 	fn send_tx(&self, slate: &Slate) -> Result<Slate, Error> {
-		let url: Url = self
-			.base_url
-			.join("/v2/foreign")
-			.expect("/v2/foreign is an invalid url path");
+		let url = self.foreign_url();
 		debug!("Posting transaction slate to {}", url);
 
-		self.check_other_version()?;
+		let version = self.check_other_version()?;
+		let mut slate = slate.clone();
+		slate.downgrade(version.clone())?;
+		debug!("Sending slate as {:?}", version);
 
 		// Note: not using easy-jsonrpc as don't want the dependencies in this crate
 		let req = json!({
@@ -114,16 +169,16 @@ impl SlateSender for HttpSlateSender {
 						null
 					]
 		});
-		trace!("Sending receive_tx request: {}", req);
+		trace!("Sending receive_tx request: {}", redact_json(&req));
 
-		let res: String = post(&url, None, &req).map_err(|e| {
+		let res: String = self.post(&url, &req).map_err(|e| {
 			let report = format!("Posting transaction slate (is recipient listening?): {}", e);
 			error!("{}", report);
 			ErrorKind::ClientCallback(report)
 		})?;
 
 		let res: Value = serde_json::from_str(&res).unwrap();
-		trace!("Response: {}", res);
+		trace!("Response: {}", redact_json(&res));
 		if res["error"] != json!(null) {
 			let report = format!(
 				"Posting transaction slate: Error: {}, Message: {}",
@@ -133,10 +188,18 @@ impl SlateSender for HttpSlateSender {
 			return Err(ErrorKind::ClientCallback(report).into());
 		}
 
+		if res["result"]["Err"] != json!(null) {
+			let report = format!(
+				"Posting transaction slate: Recipient rejected slate: {}",
+				res["result"]["Err"]
+			);
+			error!("{}", report);
+			return Err(ErrorKind::ClientCallback(report).into());
+		}
+
 		let slate_value = res["result"]["Ok"].clone();
-		trace!("slate_value: {}", slate_value);
-		let slate = Slate::deserialize_upgrade(&serde_json::to_string(&slate_value).unwrap())
-			.map_err(|_| ErrorKind::SlateDeser)?;
+		trace!("slate_value: {}", redact_json(&slate_value));
+		let slate = Slate::deserialize_upgrade(&serde_json::to_string(&slate_value).unwrap())?;
 
 		Ok(slate)
 	}
@@ -14,13 +14,13 @@
 
 // Keybase Wallet Plugin
 
-use crate::adapters::{SlateReceiver, SlateSender};
+use crate::adapters::{SlateReceiver, SlateSender, SlateTransport};
 use crate::config::WalletConfig;
 use crate::keychain::ExtKeychain;
 use crate::libwallet::api_impl::foreign;
-use crate::libwallet::{Error, ErrorKind, Slate, WalletInst};
+use crate::libwallet::{log_amount, redact_json, Error, ErrorKind, Slate, WalletInst};
 use crate::util::ZeroingString;
-use crate::{DefaultLCProvider, DefaultWalletImpl, HTTPNodeClient};
+use crate::{DefaultLCProvider, DefaultWalletImpl, HTTPNodeClient, Socks5Proxy};
 use serde::Serialize;
 use serde_json::{from_str, json, to_string, Value};
 use std::collections::{HashMap, HashSet};
@@ -229,7 +229,8 @@ fn send<T: Serialize>(message: T, channel: &str, topic: &str, ttl: u16) -> bool
 	if let Ok(res) = response {
 		match res["result"]["message"].as_str() {
 			Some("message sent") => {
-				debug!("Message sent to {}: {}", channel, serialized);
+				let message = serde_json::to_value(&message).unwrap_or_default();
+				debug!("Message sent to {}: {}", channel, redact_json(&message));
 				true
 			}
 			_ => false,
@@ -344,6 +345,21 @@ impl KeybaseAllChannels {
 	}
 }
 
+/// Transport sending slates to keybase users, given as `keybase://<username>`
+/// or just their username, and listening for slates on all channels
+pub struct KeybaseTransport;
+
+impl SlateTransport for KeybaseTransport {
+	fn sender(&self, dest: &str) -> Result<Box<dyn SlateSender>, Error> {
+		let channel = dest.trim_start_matches("keybase://").to_owned();
+		Ok(Box::new(KeybaseChannel::new(channel)?))
+	}
+
+	fn receiver(&self) -> Result<Option<Box<dyn SlateReceiver>>, Error> {
+		Ok(Some(Box::new(KeybaseAllChannels::new()?)))
+	}
+}
+
 impl SlateReceiver for KeybaseAllChannels {
 	/// Start a listener, passing received messages to the wallet api directly
 	#[allow(unreachable_code)]
@@ -354,7 +370,10 @@ impl SlateReceiver for KeybaseAllChannels {
 		account: &str,
 		node_api_secret: Option<String>,
 	) -> Result<(), Error> {
-		let node_client = HTTPNodeClient::new(&config.check_node_api_http_addr, node_api_secret);
+		let mut node_client =
+			HTTPNodeClient::new(&config.check_node_api_http_addr, node_api_secret);
+		node_client.set_archive_node_url(config.archive_node_api_http_addr.clone());
+		node_client.set_proxy(config.proxy.as_ref().map(Socks5Proxy::new));
 		let mut wallet = Box::new(
 			DefaultWalletImpl::<'static, HTTPNodeClient>::new(node_client.clone()).unwrap(),
 		)
@@ -403,7 +422,7 @@ impl SlateReceiver for KeybaseAllChannels {
 						info!(
 							"tx initiated on channel \"{}\", to send you {}(g). tx uuid: {}",
 							channel,
-							slate.amount as f64 / 1000000000.0,
+							log_amount(slate.amount),
 							tx_uuid,
 						);
 						if let Err(e) = slate.verify_messages() {
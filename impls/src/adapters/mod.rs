@@ -12,17 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod email;
 mod file;
 mod http;
 mod keybase;
+mod qr;
 
+pub use self::email::{EmailSlateReceiver, EmailSlateSender, EmailTransport};
 pub use self::file::PathToSlate;
-pub use self::http::HttpSlateSender;
-pub use self::keybase::{KeybaseAllChannels, KeybaseChannel};
+pub use self::http::{HttpSlateSender, HttpTransport};
+pub use self::keybase::{KeybaseAllChannels, KeybaseChannel, KeybaseTransport};
+pub use self::qr::{render_qr, slate_to_qr_parts, QrSlateReader};
 
 use crate::config::WalletConfig;
 use crate::libwallet::{Error, ErrorKind, Slate};
-use crate::util::ZeroingString;
+use crate::util::{RwLock, ZeroingString};
+use std::collections::HashMap;
+use std::sync::Arc;
 
 /// Sends transactions to a corresponding SlateReceiver
 pub trait SlateSender {
@@ -56,40 +62,109 @@ pub trait SlateGetter {
 	fn get_tx(&self) -> Result<Slate, Error>;
 }
 
-/// select a SlateSender based on method and dest fields from, e.g., SendArgs
-pub fn create_sender(method: &str, dest: &str) -> Result<Box<dyn SlateSender>, Error> {
-	use url::Url;
+/// A way of exchanging slates with other wallets, registered under the URI
+/// scheme of the destinations it reaches
+pub trait SlateTransport: Send + Sync {
+	/// Sender for the destination, a URI with one of the transport's schemes
+	/// or, when the transport is picked by method, whatever it takes
+	fn sender(&self, dest: &str) -> Result<Box<dyn SlateSender>, Error>;
+
+	/// Listener passing the slates received over the transport to the
+	/// wallet, if the transport has one
+	fn receiver(&self) -> Result<Option<Box<dyn SlateReceiver>>, Error> {
+		Ok(None)
+	}
+}
 
-	let invalid = || {
-		ErrorKind::WalletComms(format!(
-			"Invalid wallet comm type and destination. method: {}, dest: {}",
-			method, dest
-		))
+lazy_static! {
+	/// Slate transports by URI scheme
+	static ref TRANSPORTS: RwLock<HashMap<String, Arc<dyn SlateTransport>>> = {
+		let mut transports: HashMap<String, Arc<dyn SlateTransport>> = HashMap::new();
+		let http = Arc::new(HttpTransport::new(None));
+		transports.insert("http".to_owned(), http.clone());
+		transports.insert("https".to_owned(), http);
+		transports.insert("keybase".to_owned(), Arc::new(KeybaseTransport));
+		RwLock::new(transports)
 	};
-	Ok(match method {
-		"http" => {
-			let url: Url = dest.parse().map_err(|_| invalid())?;
-			Box::new(HttpSlateSender::new(url).map_err(|_| invalid())?)
-		}
-		"keybase" => Box::new(KeybaseChannel::new(dest.to_owned())?),
-		"self" => {
-			return Err(ErrorKind::WalletComms(
-				"No sender implementation for \"self\".".to_string(),
-			)
-			.into());
-		}
-		"file" => {
-			return Err(ErrorKind::WalletComms(
-				"File based transactions must be performed asynchronously.".to_string(),
-			)
-			.into());
+}
+
+/// Register a transport for destinations with the given URI scheme, which
+/// can also be picked as a send or listen method. Replaces any transport
+/// already registered for the scheme, including the built-in `http`, `https`
+/// and `keybase`
+pub fn register_transport(scheme: &str, transport: Arc<dyn SlateTransport>) {
+	TRANSPORTS.write().insert(scheme.to_owned(), transport);
+}
+
+/// Schemes with a registered transport, sorted
+pub fn transport_schemes() -> Vec<String> {
+	let mut schemes: Vec<String> = TRANSPORTS.read().keys().cloned().collect();
+	schemes.sort();
+	schemes
+}
+
+/// The transport reaching the destination. That's the one registered for
+/// its URI scheme if it has one, otherwise the one registered for the method
+pub fn transport_for(method: &str, dest: &str) -> Option<Arc<dyn SlateTransport>> {
+	let transports = TRANSPORTS.read();
+	dest.find("://")
+		.and_then(|i| transports.get(&dest[..i]))
+		.or_else(|| transports.get(method))
+		.cloned()
+}
+
+/// select a SlateSender based on method and dest fields from, e.g., SendArgs.
+/// A destination with the URI scheme of a registered transport is sent to
+/// over that transport, whatever the method
+pub fn create_sender(method: &str, dest: &str) -> Result<Box<dyn SlateSender>, Error> {
+	if let Some(t) = transport_for(method, dest) {
+		return t.sender(dest);
+	}
+	let msg = match method {
+		"self" => "No sender implementation for \"self\".".to_owned(),
+		"file" => "File based transactions must be performed asynchronously.".to_owned(),
+		_ => format!("Wallet comm method \"{}\" does not exist.", method),
+	};
+	Err(ErrorKind::WalletComms(msg).into())
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// Sends slates straight back
+	struct Echo;
+
+	impl SlateSender for Echo {
+		fn send_tx(&self, slate: &Slate) -> Result<Slate, Error> {
+			Ok(slate.clone())
 		}
-		_ => {
-			return Err(ErrorKind::WalletComms(format!(
-				"Wallet comm method \"{}\" does not exist.",
-				method
-			))
-			.into());
+	}
+
+	struct EchoTransport;
+
+	impl SlateTransport for EchoTransport {
+		fn sender(&self, _dest: &str) -> Result<Box<dyn SlateSender>, Error> {
+			Ok(Box::new(Echo))
 		}
-	})
+	}
+
+	#[test]
+	fn transport_by_scheme() {
+		register_transport("echo", Arc::new(EchoTransport));
+		assert!(transport_schemes().contains(&"echo".to_owned()));
+
+		// the destination's scheme wins over the method
+		let slate = Slate::blank(2);
+		let sender = create_sender("http", "echo://anyone").unwrap();
+		assert_eq!(sender.send_tx(&slate).unwrap().id, slate.id);
+		assert!(create_sender("echo", "anyone").is_ok());
+		let transport = transport_for("echo", "").unwrap();
+		assert!(transport.receiver().unwrap().is_none());
+
+		assert!(create_sender("http", "not a url").is_err());
+		assert!(create_sender("file", "slate.tx").is_err());
+		assert!(create_sender("mqtt", "mqtt://broker").is_err());
+		assert!(transport_for("self", "default").is_none());
+	}
 }
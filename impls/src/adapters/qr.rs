@@ -0,0 +1,185 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! QR code slate exchange, for passing slates to and from mobile wallets
+//!
+//! A slate is split into parts of at most `QR_PART_SIZE` bytes of its JSON,
+//! each part carried by one QR code as the text `grinslate:<n>/<total>:<json>`,
+//! numbered from 1. A scanner pastes the text of every code back, in any order.
+
+use crate::libwallet::{Error, ErrorKind, Slate};
+use qrcode::render::unicode;
+use qrcode::{EcLevel, QrCode};
+
+/// Prefix of the text of each QR code
+const QR_PREFIX: &str = "grinslate:";
+
+/// Most bytes of a slate's JSON carried by one QR code, small enough for the
+/// code to fit in a terminal and be scanned from it by a phone's camera
+pub const QR_PART_SIZE: usize = 600;
+
+/// Splits a slate into the texts of the QR codes carrying it
+pub fn slate_to_qr_parts(slate: &Slate) -> Result<Vec<String>, Error> {
+	qr_parts(slate, QR_PART_SIZE)
+}
+
+fn qr_parts(slate: &Slate, part_size: usize) -> Result<Vec<String>, Error> {
+	let json = serde_json::to_string(slate).map_err(|_| ErrorKind::SlateSer)?;
+	// the JSON is ASCII, so can be split anywhere
+	let chunks: Vec<&[u8]> = json.as_bytes().chunks(part_size).collect();
+	let total = chunks.len();
+	Ok(chunks
+		.into_iter()
+		.enumerate()
+		.map(|(i, c)| {
+			format!(
+				"{}{}/{}:{}",
+				QR_PREFIX,
+				i + 1,
+				total,
+				String::from_utf8_lossy(c)
+			)
+		})
+		.collect())
+}
+
+/// Renders the text of a QR code as a code to be displayed in a terminal,
+/// light on dark so the code stands out on terminals with dark backgrounds
+pub fn render_qr(part: &str) -> Result<String, Error> {
+	let code = QrCode::with_error_correction_level(part.as_bytes(), EcLevel::L)
+		.map_err(|e| ErrorKind::GenericError(format!("Unable to create QR code: {}", e)))?;
+	Ok(code
+		.render::<unicode::Dense1x2>()
+		.dark_color(unicode::Dense1x2::Light)
+		.light_color(unicode::Dense1x2::Dark)
+		.quiet_zone(true)
+		.build())
+}
+
+/// Collects the parts of a slate scanned from QR codes, given as pasted text
+#[derive(Default)]
+pub struct QrSlateReader {
+	parts: Vec<Option<String>>,
+}
+
+impl QrSlateReader {
+	/// An empty reader, expecting the first part
+	pub fn new() -> QrSlateReader {
+		QrSlateReader { parts: vec![] }
+	}
+
+	/// Adds a line of pasted text, ignoring blank lines. Returns the number of
+	/// parts still to be pasted, once the total is known.
+	pub fn add_line(&mut self, line: &str) -> Result<Option<usize>, Error> {
+		let line = line.trim();
+		if line.is_empty() {
+			return Ok(self.remaining());
+		}
+		let invalid = || -> Error {
+			ErrorKind::GenericError(format!("Not the text of a slate QR code: {}", line)).into()
+		};
+		if !line.starts_with(QR_PREFIX) {
+			return Err(invalid());
+		}
+		let mut split = line[QR_PREFIX.len()..].splitn(2, ':');
+		let (index, content) = match (split.next(), split.next()) {
+			(Some(i), Some(c)) => (i, c),
+			_ => return Err(invalid()),
+		};
+		let mut index = index.splitn(2, '/');
+		let (n, total) = match (
+			index.next().and_then(|n| n.parse::<usize>().ok()),
+			index.next().and_then(|t| t.parse::<usize>().ok()),
+		) {
+			(Some(n), Some(t)) if n >= 1 && n <= t => (n, t),
+			_ => return Err(invalid()),
+		};
+		if self.parts.is_empty() {
+			self.parts = vec![None; total];
+		} else if self.parts.len() != total {
+			return Err(ErrorKind::GenericError(format!(
+				"QR code {} is from a slate of {} parts rather than {}",
+				n,
+				total,
+				self.parts.len()
+			))
+			.into());
+		}
+		self.parts[n - 1] = Some(content.to_owned());
+		Ok(self.remaining())
+	}
+
+	/// Number of parts still to be pasted, if any have been
+	pub fn remaining(&self) -> Option<usize> {
+		match self.parts.is_empty() {
+			true => None,
+			false => Some(self.parts.iter().filter(|p| p.is_none()).count()),
+		}
+	}
+
+	/// The slate carried by the pasted parts, once all have been
+	pub fn slate(&self) -> Result<Slate, Error> {
+		if self.remaining() != Some(0) {
+			return Err(ErrorKind::GenericError(format!(
+				"{} of the slate's QR codes haven't been pasted",
+				self.remaining().unwrap_or(1)
+			))
+			.into());
+		}
+		let json: String = self
+			.parts
+			.iter()
+			.map(|p| p.as_ref().map(|s| s.as_str()).unwrap_or(""))
+			.collect();
+		Ok(Slate::deserialize_upgrade(&json)?)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn slate_qr_round_trip() {
+		let mut slate = Slate::blank(2);
+		slate.amount = 2_500_000_000;
+		assert_eq!(slate_to_qr_parts(&slate).unwrap().len(), 1);
+		let parts = qr_parts(&slate, 100).unwrap();
+		assert!(parts.len() > 1);
+		for p in &parts {
+			assert!(p.len() <= QR_PREFIX.len() + 8 + 100);
+			assert!(!render_qr(p).unwrap().is_empty());
+		}
+
+		// pasted in any order, with blank lines in between
+		let mut reader = QrSlateReader::new();
+		assert_eq!(reader.remaining(), None);
+		assert!(reader.slate().is_err());
+		for p in parts.iter().rev() {
+			reader.add_line("").unwrap();
+			reader.add_line(&format!("  {}\n", p)).unwrap();
+		}
+		assert_eq!(reader.remaining(), Some(0));
+		let parsed = reader.slate().unwrap();
+		assert_eq!(parsed.id, slate.id);
+		assert_eq!(parsed.amount, slate.amount);
+
+		let mut reader = QrSlateReader::new();
+		assert_eq!(reader.add_line(&parts[0]).unwrap(), Some(parts.len() - 1));
+		assert!(reader.slate().is_err());
+		assert!(reader.add_line("{\"id\": 1}").is_err());
+		assert!(reader.add_line("grinslate:0/2:{}").is_err());
+		assert!(reader.add_line("grinslate:1/99:{}").is_err());
+	}
+}
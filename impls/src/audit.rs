@@ -0,0 +1,297 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Audit log of Owner API calls, recording who called which method with
+//! which arguments and what came of it. Entries are appended to a file, one
+//! JSON object per line, each holding the hash of the one before, so entries
+//! altered or removed from the middle of the log can be detected. Entries are
+//! hashed in their canonical JSON form, so the chain can be checked by tools
+//! serializing them differently. Secrets such as passwords are left out of the
+//! arguments recorded, and slates are summarized
+
+use chrono::prelude::*;
+use serde_json::{Map, Value};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::blake2::blake2b::blake2b;
+use crate::libwallet::{to_canonical_json, Error, ErrorKind};
+use crate::util::{to_hex, Mutex};
+
+/// Arguments never recorded, replaced with a placeholder
+const SECRET_MEMBERS: &[&str] = &["token", "password", "old", "new", "mnemonic", "words"];
+/// Length beyond which string arguments are cut short
+const MAX_STRING_LEN: usize = 256;
+
+/// A call recorded in the audit log
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+	/// Position of the entry in the log, from 0
+	pub seq: u64,
+	/// Time the call was made
+	pub timestamp: DateTime<Utc>,
+	/// API the call was made on, as `owner_v3`
+	pub api: String,
+	/// Method called
+	pub method: String,
+	/// Summary of the arguments, including amounts and destinations
+	pub params: Value,
+	/// Who made the call, as identified by the listener
+	pub caller: String,
+	/// `ok`, or the error the call failed with
+	pub result: String,
+	/// Hash of the previous entry, or zeros for the first
+	pub prev_hash: String,
+	/// Hash of this entry and the previous entry's hash
+	pub hash: String,
+}
+
+impl AuditEntry {
+	/// The hash of the entry's contents and the previous entry's hash
+	fn compute_hash(&self) -> Result<String, Error> {
+		let mut entry = self.clone();
+		entry.hash = String::new();
+		let data = to_canonical_json(&entry)?;
+		Ok(to_hex(
+			blake2b(32, &[], data.as_bytes()).as_bytes().to_vec(),
+		))
+	}
+}
+
+/// An exported audit log
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuditLogExport {
+	/// All entries, oldest first
+	pub entries: Vec<AuditEntry>,
+	/// Position of the first entry that doesn't chain on from those before
+	/// it, if the log has been tampered with
+	pub first_invalid: Option<u64>,
+}
+
+struct ChainHead {
+	seq: u64,
+	hash: String,
+}
+
+/// Append-only, hash-chained log of Owner API calls, kept in a file
+pub struct AuditLog {
+	path: PathBuf,
+	head: Mutex<ChainHead>,
+}
+
+impl AuditLog {
+	/// Open the log kept in the given file, carrying on from its last entry if
+	/// it already exists
+	pub fn open<P: Into<PathBuf>>(path: P) -> Result<AuditLog, Error> {
+		let log = AuditLog {
+			path: path.into(),
+			head: Mutex::new(ChainHead {
+				seq: 0,
+				hash: genesis_hash(),
+			}),
+		};
+		if let Some(last) = log.read()?.pop() {
+			*log.head.lock() = ChainHead {
+				seq: last.seq + 1,
+				hash: last.hash,
+			};
+		}
+		Ok(log)
+	}
+
+	fn error(&self, e: impl ToString) -> Error {
+		ErrorKind::GenericError(format!(
+			"Audit log in {}: {}",
+			self.path.display(),
+			e.to_string()
+		))
+		.into()
+	}
+
+	/// Record a call of `method` on `api`, with the JSON-RPC `params` given
+	pub fn record(
+		&self,
+		api: &str,
+		method: &str,
+		params: &Value,
+		caller: &str,
+		result: &str,
+	) -> Result<(), Error> {
+		let mut head = self.head.lock();
+		let mut entry = AuditEntry {
+			seq: head.seq,
+			timestamp: Utc::now(),
+			api: api.to_owned(),
+			method: method.to_owned(),
+			params: summarize(params),
+			caller: caller.to_owned(),
+			result: result.to_owned(),
+			prev_hash: head.hash.clone(),
+			hash: String::new(),
+		};
+		entry.hash = entry.compute_hash().map_err(|e| self.error(e))?;
+		let line = serde_json::to_string(&entry).map_err(|e| self.error(e))?;
+		let mut file = OpenOptions::new()
+			.create(true)
+			.append(true)
+			.open(&self.path)
+			.map_err(|e| self.error(e))?;
+		writeln!(file, "{}", line).map_err(|e| self.error(e))?;
+		head.seq += 1;
+		head.hash = entry.hash;
+		Ok(())
+	}
+
+	/// All entries, oldest first, checking they chain together
+	pub fn export(&self) -> Result<AuditLogExport, Error> {
+		let _head = self.head.lock();
+		let entries = self.read()?;
+		let first_invalid = verify(&entries);
+		Ok(AuditLogExport {
+			entries,
+			first_invalid,
+		})
+	}
+
+	fn read(&self) -> Result<Vec<AuditEntry>, Error> {
+		if !self.path.exists() {
+			return Ok(vec![]);
+		}
+		let data = fs::read_to_string(&self.path).map_err(|e| self.error(e))?;
+		data.lines()
+			.filter(|l| !l.trim().is_empty())
+			.map(|l| serde_json::from_str(l).map_err(|e| self.error(e)))
+			.collect()
+	}
+}
+
+fn genesis_hash() -> String {
+	to_hex(vec![0; 32])
+}
+
+/// Position of the first entry that isn't where it should be in the chain or
+/// whose contents don't match its hash
+fn verify(entries: &[AuditEntry]) -> Option<u64> {
+	let mut prev_hash = genesis_hash();
+	for (i, e) in entries.iter().enumerate() {
+		if e.seq != i as u64
+			|| e.prev_hash != prev_hash
+			|| e.compute_hash().ok().as_ref() != Some(&e.hash)
+		{
+			return Some(i as u64);
+		}
+		prev_hash = e.hash.clone();
+	}
+	None
+}
+
+/// The arguments of a call as recorded: secrets are left out, slates are
+/// reduced to their id, amount and fee, long strings are cut short and
+/// non-integer numbers, which have no canonical form, are kept as strings
+fn summarize(value: &Value) -> Value {
+	match value {
+		Value::Array(a) => Value::Array(a.iter().map(summarize).collect()),
+		Value::Object(o) if o.contains_key("participant_data") => {
+			let mut slate = Map::new();
+			for k in &["id", "amount", "fee"] {
+				if let Some(v) = o.get(*k) {
+					slate.insert(k.to_string(), v.clone());
+				}
+			}
+			Value::Object(slate)
+		}
+		Value::Object(o) => Value::Object(
+			o.iter()
+				.map(|(k, v)| match SECRET_MEMBERS.contains(&k.as_str()) {
+					true => (k.clone(), Value::String("<redacted>".to_owned())),
+					false => (k.clone(), summarize(v)),
+				})
+				.collect(),
+		),
+		Value::String(s) if s.len() > MAX_STRING_LEN => {
+			let mut end = MAX_STRING_LEN;
+			while !s.is_char_boundary(end) {
+				end -= 1;
+			}
+			Value::String(format!("{}... ({} bytes)", &s[..end], s.len()))
+		}
+		Value::Number(n) if !n.is_u64() && !n.is_i64() => Value::String(n.to_string()),
+		v => v.clone(),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use serde_json::json;
+
+	#[test]
+	fn audit_log_chains_entries() {
+		let dir = "test_output/audit_log";
+		let _ = fs::remove_dir_all(dir);
+		fs::create_dir_all(dir).unwrap();
+		let path = format!("{}/audit.json", dir);
+
+		let log = AuditLog::open(path.as_str()).unwrap();
+		let send = json!({
+			"token": "d202964900000000",
+			"args": {"amount": 60_000_000_000u64, "dest": "http://127.0.0.1:3415", "ratio": 0.5},
+			"slate": {"id": "0436430c", "amount": "600", "fee": "8", "participant_data": []},
+		});
+		log.record("owner_v3", "init_send_tx", &send, "basic", "ok")
+			.unwrap();
+		log.record(
+			"owner_v3",
+			"open_wallet",
+			&json!({"password": "x"}),
+			"basic",
+			"ok",
+		)
+		.unwrap();
+
+		// a reopened log carries on the chain
+		let log = AuditLog::open(path.as_str()).unwrap();
+		log.record(
+			"owner_v2",
+			"cancel_tx",
+			&json!([null, 1]),
+			"token",
+			"error: no tx",
+		)
+		.unwrap();
+		let export = log.export().unwrap();
+		assert_eq!(export.first_invalid, None);
+		let entries = export.entries;
+		assert_eq!(entries.len(), 3);
+		assert_eq!(entries[2].seq, 2);
+		assert_eq!(entries[2].prev_hash, entries[1].hash);
+		assert_eq!(entries[0].params["token"], "<redacted>");
+		assert_eq!(entries[0].params["args"]["amount"], 60_000_000_000u64);
+		assert_eq!(entries[0].params["args"]["ratio"], "0.5");
+		assert_eq!(
+			entries[0].params["slate"],
+			json!({"id": "0436430c", "amount": "600", "fee": "8"})
+		);
+		assert_eq!(entries[1].params["password"], "<redacted>");
+
+		// altering an entry breaks the chain from there
+		let data = fs::read_to_string(&path).unwrap();
+		fs::write(&path, data.replace("no tx", "done")).unwrap();
+		assert_eq!(log.export().unwrap().first_invalid, Some(2));
+		let lines: Vec<&str> = data.lines().collect();
+		fs::write(&path, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+		assert_eq!(log.export().unwrap().first_invalid, Some(1));
+	}
+}
@@ -20,21 +20,24 @@ use std::fs::File;
 use std::io::{Read, Write};
 use std::marker::PhantomData;
 use std::path::Path;
+use std::sync::Arc;
 
 use failure::ResultExt;
 use uuid::Uuid;
 
 use crate::blake2::blake2b::{Blake2b, Blake2bResult};
 
-use crate::keychain::{ChildNumber, ExtKeychain, Identifier, Keychain, SwitchCommitmentType};
+use crate::keychain::{Identifier, Keychain, SwitchCommitmentType};
 use crate::store::{self, option_to_not_found, to_key, to_key_u64};
 
 use crate::core::core::Transaction;
 use crate::core::ser;
-use crate::libwallet::{check_repair, restore};
+use crate::libwallet::{audit_chain, check_repair, restore};
 use crate::libwallet::{
-	AcctPathMapping, Context, Error, ErrorKind, NodeClient, OutputData, TxLogEntry, WalletBackend,
-	WalletOutputBatch,
+	AcctPathMapping, BackupState, CancelToken, ChainAuditReport, Context, DerivationScheme, Error,
+	ErrorKind, FeatureFlags, GrinDerivation, NodeClient, OperationStatus, OutputData,
+	PendingReceive, PushRegistration, ReceiveCount, ScannedBlockInfo, SeedCheck, Slate,
+	SpendingPolicy, TotpSecret, TxLogEntry, TxStep, WalletBackend, WalletOutputBatch,
 };
 use crate::util::secp::constants::SECRET_KEY_SIZE;
 use crate::util::secp::key::SecretKey;
@@ -53,6 +56,21 @@ const PRIVATE_TX_CONTEXT_PREFIX: u8 = 'p' as u8;
 const TX_LOG_ENTRY_PREFIX: u8 = 't' as u8;
 const TX_LOG_ID_PREFIX: u8 = 'i' as u8;
 const ACCOUNT_PATH_MAPPING_PREFIX: u8 = 'a' as u8;
+const PUSH_REGISTRATION_PREFIX: u8 = 'n' as u8;
+const PENDING_RECEIVE_PREFIX: u8 = 'r' as u8;
+const LAST_SCANNED_BLOCK_PREFIX: u8 = 'l' as u8;
+const LAST_SCANNED_KEY: &'static str = "LAST_SCANNED_KEY";
+const FEATURE_FLAGS_PREFIX: u8 = 'f' as u8;
+const FEATURE_FLAGS_KEY: &'static str = "FEATURE_FLAGS_KEY";
+const OPERATION_PREFIX: u8 = 'j' as u8;
+const BACKUP_STATE_PREFIX: u8 = 'b' as u8;
+const BACKUP_STATE_KEY: &'static str = "BACKUP_STATE_KEY";
+const SEED_CHECK_PREFIX: u8 = 's' as u8;
+const SEED_CHECK_KEY: &'static str = "SEED_CHECK_KEY";
+const TOTP_SECRET_PREFIX: u8 = 'q' as u8;
+const TOTP_SECRET_KEY: &'static str = "TOTP_SECRET_KEY";
+const RECEIVE_COUNT_PREFIX: u8 = 'h' as u8;
+const RECEIVE_COUNT_KEY: &'static str = "RECEIVE_COUNT_KEY";
 
 /// test to see if database files exist in the current directory. If so,
 /// use a DB backend for all operations
@@ -61,6 +79,15 @@ pub fn wallet_db_exists(data_file_dir: &str) -> bool {
 	db_path.exists()
 }
 
+/// Name of the file a slate is stored in after a step of its transaction
+fn slate_filename(slate_id: &Uuid, step: TxStep) -> String {
+	format!(
+		"{}.{}.grinslate",
+		slate_id,
+		format!("{:?}", step).to_lowercase()
+	)
+}
+
 /// Helper to derive XOR keys for storing private transaction keys in the DB
 /// (blind_xor_key, nonce_xor_key)
 fn private_ctx_xor_keys<K>(
@@ -99,7 +126,8 @@ where
 	C: NodeClient + 'ck,
 	K: Keychain + 'ck,
 {
-	db: store::Store,
+	/// Store, released while the wallet is suspended
+	db: Option<store::Store>,
 	data_file_dir: String,
 	/// Keychain
 	pub keychain: Option<K>,
@@ -107,6 +135,10 @@ where
 	pub master_checksum: Box<Option<Blake2bResult>>,
 	/// Parent path to use by default for output operations
 	parent_key_id: Identifier,
+	/// Limits on what the wallet sends
+	spending_policy: SpendingPolicy,
+	/// Scheme account and output key paths are derived with
+	derivation_scheme: Arc<dyn DerivationScheme>,
 	/// wallet to node client
 	w2n_client: C,
 	///phantom
@@ -126,43 +158,58 @@ where
 		fs::create_dir_all(&stored_tx_path)
 			.expect("Couldn't create wallet backend tx storage directory!");
 
-		let store = store::Store::new(db_path.to_str().unwrap(), None, Some(DB_DIR), None)?;
+		let store = Self::open_store(data_file_dir)?;
+		let derivation_scheme: Arc<dyn DerivationScheme> = Arc::new(GrinDerivation);
+		let default_path = derivation_scheme.account_path(&[]);
+		Self::save_default_account(&store, &default_path)?;
+
+		let res = LMDBBackend {
+			db: Some(store),
+			data_file_dir: data_file_dir.to_owned(),
+			keychain: None,
+			master_checksum: Box::new(None),
+			parent_key_id: default_path,
+			spending_policy: SpendingPolicy::default(),
+			derivation_scheme,
+			w2n_client: n_client,
+			_phantom: &PhantomData,
+		};
+		Ok(res)
+	}
 
+	fn save_default_account(store: &store::Store, path: &Identifier) -> Result<(), Error> {
 		// Make sure default wallet derivation path always exists
 		// as well as path (so it can be retrieved by batches to know where to store
 		// completed transactions, for reference
 		let default_account = AcctPathMapping {
 			label: "default".to_owned(),
-			path: LMDBBackend::<C, K>::default_path(),
+			path: path.clone(),
 		};
 		let acct_key = to_key(
 			ACCOUNT_PATH_MAPPING_PREFIX,
 			&mut default_account.label.as_bytes().to_vec(),
 		);
+		let batch = store.batch()?;
+		batch.put_ser(&acct_key, &default_account)?;
+		batch.commit()?;
+		Ok(())
+	}
 
-		{
-			let batch = store.batch()?;
-			batch.put_ser(&acct_key, &default_account)?;
-			batch.commit()?;
-		}
-
-		let res = LMDBBackend {
-			db: store,
-			data_file_dir: data_file_dir.to_owned(),
-			keychain: None,
-			master_checksum: Box::new(None),
-			parent_key_id: LMDBBackend::<C, K>::default_path(),
-			w2n_client: n_client,
-			_phantom: &PhantomData,
-		};
-		Ok(res)
+	fn open_store(data_file_dir: &str) -> Result<store::Store, Error> {
+		let db_path = path::Path::new(data_file_dir).join(DB_DIR);
+		Ok(store::Store::new(
+			db_path.to_str().unwrap(),
+			None,
+			Some(DB_DIR),
+			None,
+		)?)
 	}
 
-	fn default_path() -> Identifier {
-		// return the default parent wallet path, corresponding to the default account
-		// in the BIP32 spec. Parent is account 0 at level 2, child output identifiers
-		// are all at level 3
-		ExtKeychain::derive_key_id(2, 0, 0, 0, 0)
+	/// The open store, or an error if the wallet has been suspended
+	fn db(&self) -> Result<&store::Store, Error> {
+		self.db
+			.as_ref()
+			.ok_or_else(|| ErrorKind::Lifecycle("Wallet is suspended".into()).into())
 	}
 
 	/// Just test to see if database files exist in the current directory. If
@@ -220,6 +267,20 @@ where
 		Ok(())
 	}
 
+	/// Release the store, closing the database and its lock file
+	fn suspend(&mut self) -> Result<(), Error> {
+		self.db = None;
+		Ok(())
+	}
+
+	/// Reopen the store released by `suspend`
+	fn resume(&mut self) -> Result<(), Error> {
+		if self.db.is_none() {
+			self.db = Some(Self::open_store(&self.data_file_dir)?);
+		}
+		Ok(())
+	}
+
 	/// Return the keychain being used, cloned with XORed token value
 	/// for temporary use
 	fn keychain(&self, mask: Option<&SecretKey>) -> Result<K, Error> {
@@ -273,7 +334,7 @@ where
 	/// Set parent path by account name
 	fn set_parent_key_id_by_name(&mut self, label: &str) -> Result<(), Error> {
 		let label = label.to_owned();
-		let res = self.acct_path_iter().find(|l| l.label == label);
+		let res = self.acct_path_iter()?.find(|l| l.label == label);
 		if let Some(a) = res {
 			self.set_parent_key_id(a.path);
 			Ok(())
@@ -287,6 +348,29 @@ where
 		self.parent_key_id = id;
 	}
 
+	fn set_spending_policy(&mut self, policy: SpendingPolicy) {
+		self.spending_policy = policy;
+	}
+
+	fn spending_policy(&self) -> SpendingPolicy {
+		self.spending_policy.clone()
+	}
+
+	fn set_derivation_scheme(&mut self, scheme: Arc<dyn DerivationScheme>) -> Result<(), Error> {
+		let old_default = self.derivation_scheme.account_path(&[]);
+		let default_path = scheme.account_path(&[]);
+		Self::save_default_account(self.db()?, &default_path)?;
+		if self.parent_key_id == old_default {
+			self.parent_key_id = default_path;
+		}
+		self.derivation_scheme = scheme;
+		Ok(())
+	}
+
+	fn derivation_scheme(&self) -> Arc<dyn DerivationScheme> {
+		self.derivation_scheme.clone()
+	}
+
 	fn parent_key_id(&mut self) -> Identifier {
 		self.parent_key_id.clone()
 	}
@@ -296,20 +380,38 @@ where
 			Some(i) => to_key_u64(OUTPUT_PREFIX, &mut id.to_bytes().to_vec(), *i),
 			None => to_key(OUTPUT_PREFIX, &mut id.to_bytes().to_vec()),
 		};
-		option_to_not_found(self.db.get_ser(&key), &format!("Key Id: {}", id)).map_err(|e| e.into())
+		option_to_not_found(self.db()?.get_ser(&key), &format!("Key Id: {}", id))
+			.map_err(|e| e.into())
 	}
 
-	fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = OutputData> + 'a> {
-		Box::new(self.db.iter(&[OUTPUT_PREFIX]).unwrap().map(|o| o.1))
+	fn iter<'a>(&'a self) -> Result<Box<dyn Iterator<Item = OutputData> + 'a>, Error> {
+		Ok(Box::new(self.db()?.iter(&[OUTPUT_PREFIX])?.map(|o| o.1)))
 	}
 
 	fn get_tx_log_entry(&self, u: &Uuid) -> Result<Option<TxLogEntry>, Error> {
 		let key = to_key(TX_LOG_ENTRY_PREFIX, &mut u.as_bytes().to_vec());
-		self.db.get_ser(&key).map_err(|e| e.into())
+		self.db()?.get_ser(&key).map_err(|e| e.into())
 	}
 
-	fn tx_log_iter<'a>(&'a self) -> Box<dyn Iterator<Item = TxLogEntry> + 'a> {
-		Box::new(self.db.iter(&[TX_LOG_ENTRY_PREFIX]).unwrap().map(|o| o.1))
+	fn tx_log_iter<'a>(&'a self) -> Result<Box<dyn Iterator<Item = TxLogEntry> + 'a>, Error> {
+		Ok(Box::new(
+			self.db()?.iter(&[TX_LOG_ENTRY_PREFIX])?.map(|o| o.1),
+		))
+	}
+
+	fn tx_log_iter_rev<'a>(
+		&'a self,
+		parent_key_id: &Identifier,
+	) -> Result<Box<dyn Iterator<Item = TxLogEntry> + 'a>, Error> {
+		let db = self.db()?;
+		let parent_key = parent_key_id.to_bytes().to_vec();
+		// entries are keyed by their id, all below the account's next one
+		let tx_id_key = to_key(TX_LOG_ID_PREFIX, &mut parent_key.clone());
+		let next_tx_log_id: u32 = db.get_ser(&tx_id_key)?.unwrap_or(0);
+		Ok(Box::new((0..next_tx_log_id).rev().filter_map(move |id| {
+			let key = to_key_u64(TX_LOG_ENTRY_PREFIX, &mut parent_key.clone(), id as u64);
+			db.get_ser(&key).ok().and_then(|t| t)
+		})))
 	}
 
 	fn get_private_context(
@@ -327,7 +429,7 @@ where
 			private_ctx_xor_keys(&self.keychain(keychain_mask)?, slate_id)?;
 
 		let mut ctx: Context = option_to_not_found(
-			self.db.get_ser(&ctx_key),
+			self.db()?.get_ser(&ctx_key),
 			&format!("Slate id: {:x?}", slate_id.to_vec()),
 		)?;
 
@@ -339,18 +441,41 @@ where
 		Ok(ctx)
 	}
 
-	fn acct_path_iter<'a>(&'a self) -> Box<dyn Iterator<Item = AcctPathMapping> + 'a> {
-		Box::new(
-			self.db
-				.iter(&[ACCOUNT_PATH_MAPPING_PREFIX])
-				.unwrap()
+	fn acct_path_iter<'a>(
+		&'a self,
+	) -> Result<Box<dyn Iterator<Item = AcctPathMapping> + 'a>, Error> {
+		Ok(Box::new(
+			self.db()?
+				.iter(&[ACCOUNT_PATH_MAPPING_PREFIX])?
 				.map(|o| o.1),
-		)
+		))
+	}
+
+	fn push_registration_iter<'a>(
+		&'a self,
+	) -> Result<Box<dyn Iterator<Item = PushRegistration> + 'a>, Error> {
+		Ok(Box::new(
+			self.db()?.iter(&[PUSH_REGISTRATION_PREFIX])?.map(|o| o.1),
+		))
+	}
+
+	fn pending_receive_iter<'a>(
+		&'a self,
+	) -> Result<Box<dyn Iterator<Item = PendingReceive> + 'a>, Error> {
+		Ok(Box::new(
+			self.db()?.iter(&[PENDING_RECEIVE_PREFIX])?.map(|o| o.1),
+		))
+	}
+
+	fn operation_iter<'a>(
+		&'a self,
+	) -> Result<Box<dyn Iterator<Item = OperationStatus> + 'a>, Error> {
+		Ok(Box::new(self.db()?.iter(&[OPERATION_PREFIX])?.map(|o| o.1)))
 	}
 
 	fn get_acct_path(&self, label: String) -> Result<Option<AcctPathMapping>, Error> {
 		let acct_key = to_key(ACCOUNT_PATH_MAPPING_PREFIX, &mut label.as_bytes().to_vec());
-		self.db.get_ser(&acct_key).map_err(|e| e.into())
+		self.db()?.get_ser(&acct_key).map_err(|e| e.into())
 	}
 
 	fn store_tx(&self, uuid: &str, tx: &Transaction) -> Result<(), Error> {
@@ -385,39 +510,67 @@ where
 		))
 	}
 
+	fn store_slate(&self, step: TxStep, slate: &Slate) -> Result<(), Error> {
+		let path = path::Path::new(&self.data_file_dir)
+			.join(TX_SAVE_DIR)
+			.join(slate_filename(&slate.id, step));
+		let mut stored_slate = File::create(path)?;
+		let json = serde_json::to_string(slate).map_err(|_| ErrorKind::SlateSer)?;
+		stored_slate.write_all(json.as_bytes())?;
+		stored_slate.sync_all()?;
+		Ok(())
+	}
+
+	fn get_stored_slate(&self, slate_id: &Uuid, step: TxStep) -> Result<Option<String>, Error> {
+		let path = path::Path::new(&self.data_file_dir)
+			.join(TX_SAVE_DIR)
+			.join(slate_filename(slate_id, step));
+		if !path.exists() {
+			return Ok(None);
+		}
+		let mut content = String::new();
+		File::open(path)?.read_to_string(&mut content)?;
+		Ok(Some(content))
+	}
+
 	fn batch<'a>(
 		&'a mut self,
 		keychain_mask: Option<&SecretKey>,
 	) -> Result<Box<dyn WalletOutputBatch<K> + 'a>, Error> {
+		// a watch-only wallet has no keychain, but can still record what it sees
+		let keychain = match self.keychain {
+			Some(_) => Some(self.keychain(keychain_mask)?),
+			None => None,
+		};
 		Ok(Box::new(Batch {
 			_store: self,
-			db: RefCell::new(Some(self.db.batch()?)),
-			keychain: Some(self.keychain(keychain_mask)?),
+			db: RefCell::new(Some(self.db()?.batch()?)),
+			keychain,
 		}))
 	}
 
 	fn next_child<'a>(&mut self, keychain_mask: Option<&SecretKey>) -> Result<Identifier, Error> {
 		let parent_key_id = self.parent_key_id.clone();
-		let mut deriv_idx = {
-			let batch = self.db.batch()?;
-			let deriv_key = to_key(DERIV_PREFIX, &mut self.parent_key_id.to_bytes().to_vec());
-			match batch.get_ser(&deriv_key)? {
-				Some(idx) => idx,
-				None => 0,
-			}
-		};
-		let mut return_path = self.parent_key_id.to_path();
-		return_path.depth = return_path.depth + 1;
-		return_path.path[return_path.depth as usize - 1] = ChildNumber::from(deriv_idx);
+		let mut deriv_idx = self.current_child_index(&parent_key_id)?;
+		let return_id = self.derivation_scheme.child_path(&parent_key_id, deriv_idx);
 		deriv_idx = deriv_idx + 1;
 		let mut batch = self.batch(keychain_mask)?;
 		batch.save_child_index(&parent_key_id, deriv_idx)?;
 		batch.commit()?;
-		Ok(Identifier::from_path(&return_path))
+		Ok(return_id)
+	}
+
+	fn current_child_index<'a>(&mut self, parent_key_id: &Identifier) -> Result<u32, Error> {
+		let batch = self.db()?.batch()?;
+		let deriv_key = to_key(DERIV_PREFIX, &mut parent_key_id.to_bytes().to_vec());
+		match batch.get_ser(&deriv_key)? {
+			Some(idx) => Ok(idx),
+			None => Ok(0),
+		}
 	}
 
 	fn last_confirmed_height<'a>(&mut self) -> Result<u64, Error> {
-		let batch = self.db.batch()?;
+		let batch = self.db()?.batch()?;
 		let height_key = to_key(
 			CONFIRMED_HEIGHT_PREFIX,
 			&mut self.parent_key_id.to_bytes().to_vec(),
@@ -429,8 +582,55 @@ where
 		Ok(last_confirmed_height)
 	}
 
-	fn restore(&mut self, keychain_mask: Option<&SecretKey>) -> Result<(), Error> {
-		restore(self, keychain_mask).context(ErrorKind::Restore)?;
+	fn last_scanned_block<'a>(&mut self) -> Result<Option<ScannedBlockInfo>, Error> {
+		let scanned_block_key = to_key(
+			LAST_SCANNED_BLOCK_PREFIX,
+			&mut LAST_SCANNED_KEY.as_bytes().to_vec(),
+		);
+		self.db()?.get_ser(&scanned_block_key).map_err(|e| e.into())
+	}
+
+	fn feature_flags(&self) -> Result<FeatureFlags, Error> {
+		let flags_key = to_key(
+			FEATURE_FLAGS_PREFIX,
+			&mut FEATURE_FLAGS_KEY.as_bytes().to_vec(),
+		);
+		let flags = self.db()?.get_ser(&flags_key)?;
+		Ok(flags.unwrap_or_default())
+	}
+
+	fn backup_state(&self) -> Result<Option<BackupState>, Error> {
+		let state_key = to_key(
+			BACKUP_STATE_PREFIX,
+			&mut BACKUP_STATE_KEY.as_bytes().to_vec(),
+		);
+		self.db()?.get_ser(&state_key).map_err(|e| e.into())
+	}
+
+	fn seed_check(&self) -> Result<Option<SeedCheck>, Error> {
+		let check_key = to_key(SEED_CHECK_PREFIX, &mut SEED_CHECK_KEY.as_bytes().to_vec());
+		self.db()?.get_ser(&check_key).map_err(|e| e.into())
+	}
+
+	fn totp_secret(&self) -> Result<Option<TotpSecret>, Error> {
+		let totp_key = to_key(TOTP_SECRET_PREFIX, &mut TOTP_SECRET_KEY.as_bytes().to_vec());
+		self.db()?.get_ser(&totp_key).map_err(|e| e.into())
+	}
+
+	fn receive_count(&self) -> Result<Option<ReceiveCount>, Error> {
+		let count_key = to_key(
+			RECEIVE_COUNT_PREFIX,
+			&mut RECEIVE_COUNT_KEY.as_bytes().to_vec(),
+		);
+		self.db()?.get_ser(&count_key).map_err(|e| e.into())
+	}
+
+	fn restore(
+		&mut self,
+		keychain_mask: Option<&SecretKey>,
+		cancel: Option<&CancelToken>,
+	) -> Result<(), Error> {
+		restore(self, keychain_mask, cancel).context(ErrorKind::Restore)?;
 		Ok(())
 	}
 
@@ -438,10 +638,20 @@ where
 		&mut self,
 		keychain_mask: Option<&SecretKey>,
 		delete_unconfirmed: bool,
+		full: bool,
+		cancel: Option<&CancelToken>,
 	) -> Result<(), Error> {
-		check_repair(self, keychain_mask, delete_unconfirmed).context(ErrorKind::Restore)?;
+		check_repair(self, keychain_mask, delete_unconfirmed, full, cancel)
+			.context(ErrorKind::Restore)?;
 		Ok(())
 	}
+
+	fn audit_chain(
+		&mut self,
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<ChainAuditReport, Error> {
+		Ok(audit_chain(self, keychain_mask).context(ErrorKind::Restore)?)
+	}
 }
 
 /// An atomic batch in which all changes can be committed all at once or
@@ -560,6 +770,84 @@ where
 		Ok(())
 	}
 
+	fn save_last_scanned_block(&mut self, block: ScannedBlockInfo) -> Result<(), Error> {
+		let scanned_block_key = to_key(
+			LAST_SCANNED_BLOCK_PREFIX,
+			&mut LAST_SCANNED_KEY.as_bytes().to_vec(),
+		);
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&scanned_block_key, &block)?;
+		Ok(())
+	}
+
+	fn save_feature_flags(&mut self, flags: FeatureFlags) -> Result<(), Error> {
+		let flags_key = to_key(
+			FEATURE_FLAGS_PREFIX,
+			&mut FEATURE_FLAGS_KEY.as_bytes().to_vec(),
+		);
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&flags_key, &flags)?;
+		Ok(())
+	}
+
+	fn save_backup_state(&mut self, state: &BackupState) -> Result<(), Error> {
+		let state_key = to_key(
+			BACKUP_STATE_PREFIX,
+			&mut BACKUP_STATE_KEY.as_bytes().to_vec(),
+		);
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&state_key, state)?;
+		Ok(())
+	}
+
+	fn save_seed_check(&mut self, check: &SeedCheck) -> Result<(), Error> {
+		let check_key = to_key(SEED_CHECK_PREFIX, &mut SEED_CHECK_KEY.as_bytes().to_vec());
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&check_key, check)?;
+		Ok(())
+	}
+
+	fn save_receive_count(&mut self, count: &ReceiveCount) -> Result<(), Error> {
+		let count_key = to_key(
+			RECEIVE_COUNT_PREFIX,
+			&mut RECEIVE_COUNT_KEY.as_bytes().to_vec(),
+		);
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&count_key, count)?;
+		Ok(())
+	}
+
+	fn save_totp_secret(&mut self, secret: &TotpSecret) -> Result<(), Error> {
+		let totp_key = to_key(TOTP_SECRET_PREFIX, &mut TOTP_SECRET_KEY.as_bytes().to_vec());
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&totp_key, secret)?;
+		Ok(())
+	}
+
+	fn delete_totp_secret(&mut self) -> Result<(), Error> {
+		let totp_key = to_key(TOTP_SECRET_PREFIX, &mut TOTP_SECRET_KEY.as_bytes().to_vec());
+		let _ = self.db.borrow().as_ref().unwrap().delete(&totp_key);
+		Ok(())
+	}
+
 	fn save_child_index(&mut self, parent_id: &Identifier, child_n: u32) -> Result<(), Error> {
 		let deriv_key = to_key(DERIV_PREFIX, &mut parent_id.to_bytes().to_vec());
 		self.db
@@ -588,6 +876,16 @@ where
 		Ok(())
 	}
 
+	fn delete_tx_log_entry(&mut self, id: u32, parent_id: &Identifier) -> Result<(), Error> {
+		let tx_log_key = to_key_u64(
+			TX_LOG_ENTRY_PREFIX,
+			&mut parent_id.to_bytes().to_vec(),
+			id as u64,
+		);
+		self.db.borrow().as_ref().unwrap().delete(&tx_log_key)?;
+		Ok(())
+	}
+
 	fn save_acct_path(&mut self, mapping: AcctPathMapping) -> Result<(), Error> {
 		let acct_key = to_key(
 			ACCOUNT_PATH_MAPPING_PREFIX,
@@ -613,6 +911,53 @@ where
 		)
 	}
 
+	fn save_push_registration(&mut self, registration: PushRegistration) -> Result<(), Error> {
+		let key = to_key(
+			PUSH_REGISTRATION_PREFIX,
+			&mut registration.token.as_bytes().to_vec(),
+		);
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&key, &registration)?;
+		Ok(())
+	}
+
+	fn delete_push_registration(&mut self, token: &str) -> Result<(), Error> {
+		let key = to_key(PUSH_REGISTRATION_PREFIX, &mut token.as_bytes().to_vec());
+		let _ = self.db.borrow().as_ref().unwrap().delete(&key);
+		Ok(())
+	}
+
+	fn save_pending_receive(&mut self, pending: &PendingReceive) -> Result<(), Error> {
+		let key = to_key(
+			PENDING_RECEIVE_PREFIX,
+			&mut pending.slate.id.as_bytes().to_vec(),
+		);
+		self.db.borrow().as_ref().unwrap().put_ser(&key, pending)?;
+		Ok(())
+	}
+
+	fn delete_pending_receive(&mut self, slate_id: &Uuid) -> Result<(), Error> {
+		let key = to_key(PENDING_RECEIVE_PREFIX, &mut slate_id.as_bytes().to_vec());
+		let _ = self.db.borrow().as_ref().unwrap().delete(&key);
+		Ok(())
+	}
+
+	fn save_operation(&mut self, status: &OperationStatus) -> Result<(), Error> {
+		// big-endian, so operations iterate in order of id
+		let key = to_key(OPERATION_PREFIX, &mut status.id.to_be_bytes().to_vec());
+		self.db.borrow().as_ref().unwrap().put_ser(&key, status)?;
+		Ok(())
+	}
+
+	fn delete_operation(&mut self, id: u32) -> Result<(), Error> {
+		let key = to_key(OPERATION_PREFIX, &mut id.to_be_bytes().to_vec());
+		let _ = self.db.borrow().as_ref().unwrap().delete(&key);
+		Ok(())
+	}
+
 	fn lock_output(&mut self, out: &mut OutputData) -> Result<(), Error> {
 		out.lock();
 		self.save(out.clone())
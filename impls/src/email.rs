@@ -0,0 +1,380 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Email notifications of significant events, for operators without a
+//! monitoring stack. Events are only emailed once they pass the configured
+//! thresholds, and are sent through an SMTP server with the system's `curl`
+
+use std::collections::VecDeque;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::prelude::*;
+use uuid::Uuid;
+
+use crate::config::{EmailEventType, EmailNotificationConfig};
+use crate::core::core::amount_to_hr_string;
+use crate::libwallet::NodeConnectivity;
+use crate::util::Mutex;
+
+/// Window in which authentication failures are counted, unless configured
+const DEFAULT_AUTH_FAILURE_WINDOW: u64 = 300;
+
+/// An email ready to be sent
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmailMessage {
+	/// Subject line
+	pub subject: String,
+	/// Plain text body
+	pub body: String,
+}
+
+/// Thresholds already crossed, so each burst or outage is only emailed once
+#[derive(Default)]
+struct EmailState {
+	auth_failures: VecDeque<Instant>,
+	auth_alerted_at: Option<Instant>,
+	node_down_since: Option<Instant>,
+	node_alerted: bool,
+}
+
+/// Sends emails for the events passing the configured thresholds. Emails are
+/// sent on a thread of their own, so callers are never held up
+#[derive(Clone)]
+pub struct EmailNotifier {
+	config: Arc<EmailNotificationConfig>,
+	state: Arc<Mutex<EmailState>>,
+}
+
+impl EmailNotifier {
+	/// Create a notifier sending emails as configured
+	pub fn new(config: EmailNotificationConfig) -> EmailNotifier {
+		EmailNotifier {
+			config: Arc::new(config),
+			state: Arc::new(Mutex::new(EmailState::default())),
+		}
+	}
+
+	/// Record a send of the given amount, emailing if it's above the threshold
+	pub fn tx_sent(&self, amount: u64, tx_slate_id: Option<Uuid>) {
+		if let Some(m) = self.check_send(amount, tx_slate_id) {
+			self.send(m);
+		}
+	}
+
+	/// Record a request failing authentication, emailing if there have been
+	/// too many lately
+	pub fn auth_failed(&self) {
+		if let Some(m) = self.check_auth_failure(Instant::now()) {
+			self.send(m);
+		}
+	}
+
+	/// Record the result of a check of the node, emailing if it has been
+	/// unreachable for too long
+	pub fn node_checked(&self, status: &NodeConnectivity) {
+		if let Some(m) = self.check_node(status, Instant::now()) {
+			self.send(m);
+		}
+	}
+
+	fn check_send(&self, amount: u64, tx_slate_id: Option<Uuid>) -> Option<EmailMessage> {
+		match self.config.min_send_amount {
+			Some(min) if amount > min => Some(self.message(
+				EmailEventType::LargeSend,
+				&[
+					("amount", amount_to_hr_string(amount, false)),
+					(
+						"tx_slate_id",
+						tx_slate_id.map(|id| id.to_string()).unwrap_or_default(),
+					),
+				],
+			)),
+			_ => None,
+		}
+	}
+
+	fn check_auth_failure(&self, now: Instant) -> Option<EmailMessage> {
+		let threshold = self.config.auth_failures? as usize;
+		let window = self
+			.config
+			.auth_failure_window
+			.unwrap_or(DEFAULT_AUTH_FAILURE_WINDOW);
+		let window_duration = Duration::from_secs(window);
+		let mut state = self.state.lock();
+		state.auth_failures.push_back(now);
+		while let Some(t) = state.auth_failures.front() {
+			if now.duration_since(*t) <= window_duration {
+				break;
+			}
+			state.auth_failures.pop_front();
+		}
+		// a burst is only emailed once per window
+		let recently_alerted = state
+			.auth_alerted_at
+			.map(|t| now.duration_since(t) <= window_duration)
+			.unwrap_or(false);
+		if state.auth_failures.len() < threshold.max(1) || recently_alerted {
+			return None;
+		}
+		state.auth_alerted_at = Some(now);
+		Some(self.message(
+			EmailEventType::AuthFailures,
+			&[
+				("failures", state.auth_failures.len().to_string()),
+				("window", window.to_string()),
+			],
+		))
+	}
+
+	fn check_node(&self, status: &NodeConnectivity, now: Instant) -> Option<EmailMessage> {
+		let mins = self.config.node_unreachable_mins?;
+		let mut state = self.state.lock();
+		if status.reachable {
+			state.node_down_since = None;
+			state.node_alerted = false;
+			return None;
+		}
+		let since = *state.node_down_since.get_or_insert(now);
+		if state.node_alerted || now.duration_since(since) < Duration::from_secs(mins * 60) {
+			return None;
+		}
+		state.node_alerted = true;
+		Some(self.message(
+			EmailEventType::NodeUnreachable,
+			&[
+				("node_url", status.node_url.clone()),
+				(
+					"minutes",
+					(now.duration_since(since).as_secs() / 60).to_string(),
+				),
+				("error", status.last_error.clone().unwrap_or_default()),
+			],
+		))
+	}
+
+	/// The email for the event, from its configured template if there's one
+	fn message(&self, event: EmailEventType, values: &[(&str, String)]) -> EmailMessage {
+		let (subject, body) = match self.config.templates.iter().find(|t| t.event == event) {
+			Some(t) => (t.subject.as_str(), t.body.as_str()),
+			None => default_template(event),
+		};
+		let time = Utc::now().to_rfc2822();
+		let render = |template: &str| {
+			values
+				.iter()
+				.fold(template.replace("{time}", &time), |s, (name, value)| {
+					s.replace(&format!("{{{}}}", name), value)
+				})
+		};
+		EmailMessage {
+			subject: render(subject),
+			body: render(body),
+		}
+	}
+
+	fn send(&self, message: EmailMessage) {
+		let config = self.config.clone();
+		let _ = thread::Builder::new()
+			.name("email".to_string())
+			.spawn(move || {
+				debug!("Emailing \"{}\"", message.subject);
+				if let Err(e) = send_email(&config, &message) {
+					error!("Failed to email \"{}\": {}", message.subject, e);
+				}
+			});
+	}
+}
+
+fn default_template(event: EmailEventType) -> (&'static str, &'static str) {
+	match event {
+		EmailEventType::LargeSend => (
+			"Grin wallet sent {amount} grin",
+			"The wallet sent {amount} grin in transaction {tx_slate_id} at {time}.",
+		),
+		EmailEventType::AuthFailures => (
+			"Grin wallet: repeated authentication failures",
+			"{failures} requests to the Owner API failed authentication within {window} \
+			 seconds, as of {time}.",
+		),
+		EmailEventType::NodeUnreachable => (
+			"Grin wallet: node unreachable",
+			"The node at {node_url} has been unreachable for {minutes} minutes, as of \
+			 {time}.\n\nLast error: {error}",
+		),
+	}
+}
+
+/// Quote a value for a curl config file
+pub(crate) fn curl_quote(value: &str) -> String {
+	format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Send the email with curl
+fn send_email(config: &EmailNotificationConfig, message: &EmailMessage) -> Result<(), String> {
+	let mut email = format!(
+		"From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\n\r\n",
+		config.from,
+		config.to.join(", "),
+		message.subject,
+		Utc::now().to_rfc2822()
+	);
+	email.push_str(&message.body.replace('\n', "\r\n"));
+	email.push_str("\r\n");
+	let options = curl_options(
+		&config.smtp_url,
+		config.username.as_ref(),
+		config.password.as_ref(),
+		config.require_tls.unwrap_or(true),
+	);
+	send_raw(options, &config.from, &config.to, &email)
+}
+
+/// Options connecting curl to the mail server at the URL
+pub(crate) fn curl_options(
+	url: &str,
+	username: Option<&String>,
+	password: Option<&String>,
+	require_tls: bool,
+) -> Vec<String> {
+	let mut options = vec![
+		format!("url = {}", curl_quote(url)),
+		"silent".to_owned(),
+		"show-error".to_owned(),
+	];
+	if require_tls {
+		options.push("ssl-reqd".to_owned());
+	}
+	if let Some(u) = username {
+		let user = format!("{}:{}", u, password.cloned().unwrap_or_default());
+		options.push(format!("user = {}", curl_quote(&user)));
+	}
+	options
+}
+
+/// Send the email, headers and all, through the SMTP server curl is
+/// connected to by the options. The message is given in a temporary file
+pub(crate) fn send_raw(
+	mut options: Vec<String>,
+	from: &str,
+	to: &[String],
+	email: &str,
+) -> Result<(), String> {
+	let file = env::temp_dir().join(format!("grin_wallet_email_{}.eml", Uuid::new_v4()));
+	fs::write(&file, email).map_err(|e| e.to_string())?;
+	options.push(format!("mail-from = {}", curl_quote(from)));
+	options.push(format!(
+		"upload-file = {}",
+		curl_quote(&file.to_string_lossy())
+	));
+	for to in to.iter() {
+		options.push(format!("mail-rcpt = {}", curl_quote(to)));
+	}
+	let res = run_curl(&options).map(|_| ());
+	let _ = fs::remove_file(&file);
+	res
+}
+
+/// Run curl, returning its output. The options, holding the password, are
+/// given on its standard input rather than its command line
+pub(crate) fn run_curl(options: &[String]) -> Result<Vec<u8>, String> {
+	let mut proc = Command::new("curl")
+		.arg("--config")
+		.arg("-")
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.spawn()
+		.map_err(|e| format!("running curl: {}", e))?;
+	proc.stdin
+		.take()
+		.ok_or_else(|| "curl has no stdin".to_owned())?
+		.write_all(options.join("\n").as_bytes())
+		.map_err(|e| e.to_string())?;
+	let output = proc.wait_with_output().map_err(|e| e.to_string())?;
+	if !output.status.success() {
+		return Err(String::from_utf8_lossy(&output.stderr).trim().to_owned());
+	}
+	Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::config::EmailTemplate;
+
+	fn notifier() -> EmailNotifier {
+		EmailNotifier::new(EmailNotificationConfig {
+			smtp_url: "smtps://smtp.example.com:465".to_owned(),
+			username: None,
+			password: None,
+			require_tls: None,
+			from: "wallet@example.com".to_owned(),
+			to: vec!["ops@example.com".to_owned()],
+			min_send_amount: Some(1_000_000_000),
+			auth_failures: Some(3),
+			auth_failure_window: Some(60),
+			node_unreachable_mins: Some(10),
+			templates: vec![EmailTemplate {
+				event: EmailEventType::LargeSend,
+				subject: "Sent {amount}".to_owned(),
+				body: "Slate {tx_slate_id}".to_owned(),
+			}],
+		})
+	}
+
+	#[test]
+	fn email_thresholds() {
+		let n = notifier();
+		let id = Uuid::new_v4();
+		assert_eq!(n.check_send(1_000_000_000, Some(id)), None);
+		let m = n.check_send(2_500_000_000, Some(id)).unwrap();
+		assert_eq!(m.subject, "Sent 2.500000000");
+		assert_eq!(m.body, format!("Slate {}", id));
+
+		// the third failure within the window is emailed, once
+		let start = Instant::now();
+		let at = |secs| start + Duration::from_secs(secs);
+		assert!(n.check_auth_failure(at(0)).is_none());
+		assert!(n.check_auth_failure(at(100)).is_none());
+		assert!(n.check_auth_failure(at(110)).is_none());
+		let m = n.check_auth_failure(at(120)).unwrap();
+		assert!(m.body.starts_with("3 requests"));
+		assert!(n.check_auth_failure(at(130)).is_none());
+
+		// an outage is emailed once it has lasted long enough, then not again
+		let mut status = NodeConnectivity {
+			node_url: "http://127.0.0.1:3413".to_owned(),
+			reachable: false,
+			last_error: Some("connection refused".to_owned()),
+			..Default::default()
+		};
+		assert!(n.check_node(&status, at(0)).is_none());
+		assert!(n.check_node(&status, at(540)).is_none());
+		let m = n.check_node(&status, at(600)).unwrap();
+		assert!(m.body.contains("for 10 minutes"));
+		assert!(m.body.ends_with("Last error: connection refused"));
+		assert!(n.check_node(&status, at(1200)).is_none());
+		status.reachable = true;
+		assert!(n.check_node(&status, at(1260)).is_none());
+		status.reachable = false;
+		assert!(n.check_node(&status, at(1320)).is_none());
+		assert!(n.check_node(&status, at(1920)).is_some());
+	}
+}
@@ -22,6 +22,8 @@ use blake2_rfc as blake2;
 extern crate serde_derive;
 #[macro_use]
 extern crate log;
+#[macro_use]
+extern crate lazy_static;
 use grin_wallet_libwallet as libwallet;
 use grin_wallet_util::grin_api as api;
 use grin_wallet_util::grin_chain as chain;
@@ -33,20 +35,50 @@ use grin_wallet_util::grin_util as util;
 use grin_wallet_config as config;
 
 mod adapters;
+mod audit;
 mod backends;
+mod backup_targets;
+mod email;
 mod error;
 mod lifecycle;
 mod node_clients;
+mod price_oracle;
+mod proxy;
+mod push;
+#[cfg(unix)]
+mod ssh;
 pub mod test_framework;
+mod webhooks;
+mod zmq;
 
 pub use crate::adapters::{
-	create_sender, HttpSlateSender, KeybaseAllChannels, KeybaseChannel, PathToSlate, SlateGetter,
-	SlatePutter, SlateReceiver, SlateSender,
+	create_sender, register_transport, render_qr, slate_to_qr_parts, transport_for,
+	transport_schemes, EmailSlateReceiver, EmailSlateSender, EmailTransport, HttpSlateSender,
+	HttpTransport, KeybaseAllChannels, KeybaseChannel, KeybaseTransport, PathToSlate,
+	QrSlateReader, SlateGetter, SlatePutter, SlateReceiver, SlateSender, SlateTransport,
 };
+pub use crate::audit::{AuditEntry, AuditLog, AuditLogExport};
 pub use crate::backends::{wallet_db_exists, LMDBBackend};
+pub use crate::backup_targets::{
+	create_backup_target, S3BackupTarget, SftpBackupTarget, WebDavBackupTarget,
+};
+pub use crate::email::EmailNotifier;
 pub use crate::error::{Error, ErrorKind};
 pub use crate::lifecycle::DefaultLCProvider;
-pub use crate::node_clients::HTTPNodeClient;
+pub use crate::node_clients::{
+	discover_local_nodes, DiscoveredNode, HTTPNodeClient, NodeDirectory, PublicNode,
+	PublicNodeHealth,
+};
+pub use crate::price_oracle::{HttpPriceOracle, PriceResponse};
+pub use crate::proxy::Socks5Proxy;
+pub use crate::push::{GatewayPushNotifier, PushGatewayRequest};
+#[cfg(unix)]
+pub use crate::ssh::{SshRemote, SshTunnel};
+pub use crate::webhooks::{
+	WebhookDeadLetter, WebhookDeadLetters, WebhookDispatcher, WebhookPayload,
+	WEBHOOK_SIGNATURE_HEADER,
+};
+pub use crate::zmq::ZmqPublisher;
 
 use crate::keychain::{ExtKeychain, Keychain};
 
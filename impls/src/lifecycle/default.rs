@@ -17,13 +17,19 @@
 use crate::config::{config, GlobalWalletConfig, GRIN_WALLET_DIR};
 use crate::core::global;
 use crate::keychain::Keychain;
-use crate::libwallet::{Error, ErrorKind, NodeClient, WalletBackend, WalletLCProvider};
-use crate::lifecycle::seed::WalletSeed;
+use crate::libwallet::{
+	check_repair, install_records, reconstruct, slip39, BackupArchive, DerivationScheme, Error,
+	ErrorKind, GrinDerivation, NodeClient, SeedCheck, SpendingPolicy, WalletBackend,
+	WalletLCProvider, WalletRecords,
+};
+use crate::lifecycle::seed::{EncryptedWalletSeed, WalletSeed, SEED_FILE};
 use crate::util::secp::key::SecretKey;
 use crate::util::ZeroingString;
 use crate::LMDBBackend;
 use failure::ResultExt;
+use std::fs;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 pub struct DefaultLCProvider<'a, C, K>
 where
@@ -33,6 +39,9 @@ where
 	data_dir: String,
 	node_client: C,
 	backend: Option<Box<dyn WalletBackend<'a, C, K> + 'a>>,
+	seed_passphrase: Option<ZeroingString>,
+	derivation_scheme: Arc<dyn DerivationScheme>,
+	spending_policy: SpendingPolicy,
 }
 
 impl<'a, C, K> DefaultLCProvider<'a, C, K>
@@ -46,8 +55,31 @@ where
 			node_client,
 			data_dir: "default".to_owned(),
 			backend: None,
+			seed_passphrase: None,
+			derivation_scheme: Arc::new(GrinDerivation),
+			spending_policy: SpendingPolicy::default(),
 		}
 	}
+
+	/// Write the seed and records of a backup into an empty data directory,
+	/// then check the restored outputs against the chain
+	fn install_backup(
+		&self,
+		data_dir_name: &str,
+		seed: &str,
+		keychain: K,
+		records: WalletRecords,
+	) -> Result<(), Error> {
+		fs::write(PathBuf::from(data_dir_name).join(SEED_FILE), seed).context(ErrorKind::IO)?;
+		let mut wallet: LMDBBackend<'a, C, K> =
+			LMDBBackend::new(&data_dir_name, self.node_client.clone())?;
+		wallet.set_derivation_scheme(self.derivation_scheme.clone())?;
+		wallet.set_keychain(Box::new(keychain), false, false)?;
+		install_records(&mut wallet, None, records)?;
+		info!("Backup installed, checking the restored wallet against the chain");
+		check_repair(&mut wallet, None, false, true, None)?;
+		wallet.close()
+	}
 }
 
 impl<'a, C, K> WalletLCProvider<'a, C, K> for DefaultLCProvider<'a, C, K>
@@ -55,10 +87,29 @@ where
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
+	fn set_seed_passphrase(&mut self, passphrase: Option<ZeroingString>) {
+		self.seed_passphrase = passphrase;
+	}
+
+	fn set_derivation_scheme(&mut self, scheme: Arc<dyn DerivationScheme>) {
+		self.derivation_scheme = scheme;
+	}
+
+	fn set_spending_policy(&mut self, policy: SpendingPolicy) {
+		if let Some(b) = self.backend.as_mut() {
+			b.set_spending_policy(policy.clone());
+		}
+		self.spending_policy = policy;
+	}
+
 	fn set_wallet_directory(&mut self, dir: &str) {
 		self.data_dir = dir.to_owned();
 	}
 
+	fn node_client(&self) -> C {
+		self.node_client.clone()
+	}
+
 	fn create_config(&self, chain_type: &global::ChainTypes, file_name: &str) -> Result<(), Error> {
 		let mut default_config = GlobalWalletConfig::for_chain(chain_type);
 		let mut config_file_name = PathBuf::from(self.data_dir.clone());
@@ -118,9 +169,10 @@ where
 		let mut data_dir_name = PathBuf::from(self.data_dir.clone());
 		data_dir_name.push(GRIN_WALLET_DIR);
 		let data_dir_name = data_dir_name.to_str().unwrap();
-		let _ = WalletSeed::init_file(&data_dir_name, mnemonic_length, mnemonic, password);
+		let generated = mnemonic.is_none();
+		let seed = WalletSeed::init_file(&data_dir_name, mnemonic_length, mnemonic, password);
 		info!("Wallet seed file created");
-		let _wallet: LMDBBackend<'a, C, K> =
+		let mut wallet: LMDBBackend<'a, C, K> =
 			match LMDBBackend::new(&data_dir_name, self.node_client.clone()) {
 				Err(e) => {
 					let msg = format!("Error creating wallet: {}, Data Dir: {}", e, &data_dir_name);
@@ -128,7 +180,26 @@ where
 				}
 				Ok(d) => d,
 			};
+		wallet.set_derivation_scheme(self.derivation_scheme.clone())?;
 		info!("Wallet database backend created at {}", data_dir_name);
+		// so the user can later show they've recorded a generated phrase
+		if let (true, Ok(seed)) = (generated, seed) {
+			let keychain: K = seed
+				.derive_keychain(
+					global::is_floonet(),
+					self.seed_passphrase.as_ref().map(|p| &**p),
+					&*self.derivation_scheme,
+				)
+				.context(ErrorKind::Lifecycle("Error deriving keychain".into()))?;
+			let phrase = seed
+				.to_mnemonic()
+				.context(ErrorKind::Lifecycle("Error recovering wallet seed".into()))?;
+			let check = SeedCheck::new(&keychain, &ZeroingString::from(phrase))?;
+			wallet.set_keychain(Box::new(keychain), false, false)?;
+			let mut batch = wallet.batch(None)?;
+			batch.save_seed_check(&check)?;
+			batch.commit()?;
+		}
 		Ok(())
 	}
 
@@ -150,17 +221,38 @@ where
 				}
 				Ok(d) => d,
 			};
+		wallet.set_derivation_scheme(self.derivation_scheme.clone())?;
 		let wallet_seed = WalletSeed::from_file(&data_dir_name, password)
 			.context(ErrorKind::Lifecycle("Error opening wallet".into()))?;
 		let keychain = wallet_seed
-			.derive_keychain(global::is_floonet())
+			.derive_keychain(
+				global::is_floonet(),
+				self.seed_passphrase.as_ref().map(|p| &**p),
+				&*self.derivation_scheme,
+			)
 			.context(ErrorKind::Lifecycle("Error deriving keychain".into()))?;
 
 		let mask = wallet.set_keychain(Box::new(keychain), create_mask, use_test_rng)?;
+		wallet.set_spending_policy(self.spending_policy.clone());
 		self.backend = Some(Box::new(wallet));
 		Ok(mask)
 	}
 
+	fn open_watch_only(&mut self, _name: Option<&str>) -> Result<(), Error> {
+		let mut data_dir_name = PathBuf::from(self.data_dir.clone());
+		data_dir_name.push(GRIN_WALLET_DIR);
+		let data_dir_name = data_dir_name.to_str().unwrap();
+		let mut wallet: LMDBBackend<'a, C, K> =
+			LMDBBackend::new(&data_dir_name, self.node_client.clone()).map_err(|e| {
+				let msg = format!("Error opening wallet: {}, Data Dir: {}", e, &data_dir_name);
+				ErrorKind::Lifecycle(msg)
+			})?;
+		wallet.set_derivation_scheme(self.derivation_scheme.clone())?;
+		wallet.set_spending_policy(self.spending_policy.clone());
+		self.backend = Some(Box::new(wallet));
+		Ok(())
+	}
+
 	fn close_wallet(&mut self, _name: Option<&str>) -> Result<(), Error> {
 		match self.backend.as_mut() {
 			Some(b) => b.close()?,
@@ -170,6 +262,23 @@ where
 		Ok(())
 	}
 
+	fn suspend_wallet(&mut self) -> Result<(), Error> {
+		match self.backend.as_mut() {
+			Some(b) => b.suspend(),
+			None => Ok(()),
+		}
+	}
+
+	fn resume_wallet(&mut self) -> Result<(), Error> {
+		match self.backend.as_mut() {
+			Some(b) => b.resume(),
+			None => {
+				let msg = "Wallet has not been opened".into();
+				Err(ErrorKind::Lifecycle(msg).into())
+			}
+		}
+	}
+
 	fn wallet_exists(&self, _name: Option<&str>) -> Result<bool, Error> {
 		let mut data_dir_name = PathBuf::from(self.data_dir.clone());
 		data_dir_name.push(GRIN_WALLET_DIR);
@@ -218,8 +327,85 @@ where
 		Ok(())
 	}
 
-	fn change_password(&self, _old: String, _new: String) -> Result<(), Error> {
-		unimplemented!()
+	fn get_seed_shares(
+		&self,
+		_name: Option<&str>,
+		password: ZeroingString,
+		threshold: u8,
+		count: u8,
+	) -> Result<Vec<ZeroingString>, Error> {
+		let mut data_dir_name = PathBuf::from(self.data_dir.clone());
+		data_dir_name.push(GRIN_WALLET_DIR);
+		let data_dir_name = data_dir_name.to_str().unwrap();
+		let wallet_seed = WalletSeed::from_file(&data_dir_name, password).context(
+			ErrorKind::Lifecycle("Error opening wallet seed file".into()),
+		)?;
+		slip39::split(wallet_seed.as_bytes(), threshold, count, b"")
+	}
+
+	fn recover_from_seed_shares(
+		&self,
+		shares: Vec<ZeroingString>,
+		password: ZeroingString,
+	) -> Result<(), Error> {
+		let seed = WalletSeed::from_bytes(&slip39::combine(&shares, b"")?);
+		let mnemonic = seed
+			.to_mnemonic()
+			.context(ErrorKind::Lifecycle("Error recovering wallet seed".into()))?;
+		self.recover_from_mnemonic(ZeroingString::from(mnemonic), password)
+	}
+
+	fn restore_from_backup(&mut self, archive: &str, password: ZeroingString) -> Result<(), Error> {
+		let archive: BackupArchive = serde_json::from_str(archive)
+			.map_err(|e| ErrorKind::Lifecycle(format!("Invalid backup archive: {}", e)))?;
+		let mut data_dir_name = PathBuf::from(self.data_dir.clone());
+		data_dir_name.push(GRIN_WALLET_DIR);
+		if data_dir_name.join(SEED_FILE).exists() {
+			let msg = format!(
+				"A wallet already exists at {}, restore into a new directory",
+				data_dir_name.to_str().unwrap()
+			);
+			return Err(ErrorKind::Lifecycle(msg).into());
+		}
+
+		// nothing is written until the archive is known to be good
+		let enc_seed: EncryptedWalletSeed = serde_json::from_str(&archive.seed)
+			.map_err(|e| ErrorKind::Lifecycle(format!("Invalid seed in backup archive: {}", e)))?;
+		let keychain: K = enc_seed
+			.decrypt(&password)
+			.context(ErrorKind::Lifecycle("Error decrypting wallet seed".into()))?
+			.derive_keychain(
+				global::is_floonet(),
+				self.seed_passphrase.as_ref().map(|p| &**p),
+				&*self.derivation_scheme,
+			)
+			.context(ErrorKind::Lifecycle("Error deriving keychain".into()))?;
+		let records = reconstruct(&keychain, &archive.backups, None)?;
+
+		let created = !data_dir_name.exists();
+		let data_dir_name = data_dir_name.to_str().unwrap();
+		fs::create_dir_all(data_dir_name).context(ErrorKind::IO)?;
+		if let Err(e) = self.install_backup(data_dir_name, &archive.seed, keychain, records) {
+			// leave nothing behind that would block another attempt
+			if created {
+				let _ = fs::remove_dir_all(data_dir_name);
+			} else {
+				let _ = fs::remove_file(PathBuf::from(data_dir_name).join(SEED_FILE));
+			}
+			return Err(e);
+		}
+		info!("Wallet restored from backup at {}", data_dir_name);
+		Ok(())
+	}
+
+	fn change_password(&self, old: ZeroingString, new: ZeroingString) -> Result<(), Error> {
+		let mut data_dir_name = PathBuf::from(self.data_dir.clone());
+		data_dir_name.push(GRIN_WALLET_DIR);
+		let data_dir_name = data_dir_name.to_str().unwrap();
+		WalletSeed::change_password(&data_dir_name, old, new).context(ErrorKind::Lifecycle(
+			"Error changing wallet password".into(),
+		))?;
+		Ok(())
 	}
 
 	fn delete_wallet(&self, _name: Option<String>, _password: String) -> Result<(), Error> {
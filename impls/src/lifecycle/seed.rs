@@ -25,6 +25,7 @@ use ring::aead;
 use ring::{digest, pbkdf2};
 
 use crate::keychain::{mnemonic, Keychain};
+use crate::libwallet::DerivationScheme;
 use crate::util;
 use crate::{Error, ErrorKind};
 use failure::ResultExt;
@@ -53,6 +54,10 @@ impl WalletSeed {
 		Ok(WalletSeed::from_bytes(&bytes))
 	}
 
+	pub fn as_bytes(&self) -> &[u8] {
+		&self.0
+	}
+
 	pub fn _to_hex(&self) -> String {
 		util::to_hex(self.0.to_vec())
 	}
@@ -70,9 +75,16 @@ impl WalletSeed {
 		seed.as_bytes().to_vec()
 	}
 
-	pub fn derive_keychain<K: Keychain>(&self, is_floonet: bool) -> Result<K, Error> {
-		let result = K::from_seed(&self.0, is_floonet)?;
-		Ok(result)
+	/// Derive the keychain from the seed with the given scheme, mixing in the
+	/// BIP39 passphrase if one is given
+	pub fn derive_keychain<K: Keychain>(
+		&self,
+		is_floonet: bool,
+		passphrase: Option<&str>,
+		scheme: &dyn DerivationScheme,
+	) -> Result<K, Error> {
+		let seed = scheme.keychain_seed(&self.0, passphrase)?;
+		Ok(K::from_seed(&seed, is_floonet)?)
 	}
 
 	pub fn init_new(seed_length: usize) -> WalletSeed {
@@ -172,6 +184,30 @@ impl WalletSeed {
 		Ok(seed)
 	}
 
+	/// Re-encrypt the seed file with a new password. The new file is written
+	/// alongside and renamed over the old one, so the seed file is never left
+	/// half written
+	pub fn change_password(
+		data_file_dir: &str,
+		old: util::ZeroingString,
+		new: util::ZeroingString,
+	) -> Result<(), Error> {
+		let seed = WalletSeed::from_file(data_file_dir, old)?;
+		let seed_file_path = Path::new(data_file_dir).join(SEED_FILE);
+		let tmp_file_path = Path::new(data_file_dir).join(format!("{}.tmp", SEED_FILE));
+		let enc_seed = EncryptedWalletSeed::from_seed(&seed, new)?;
+		let enc_seed_json = serde_json::to_string_pretty(&enc_seed).context(ErrorKind::Format)?;
+		{
+			let mut file = File::create(&tmp_file_path).context(ErrorKind::IO)?;
+			file.write_all(&enc_seed_json.as_bytes())
+				.context(ErrorKind::IO)?;
+			file.sync_all().context(ErrorKind::IO)?;
+		}
+		fs::rename(&tmp_file_path, &seed_file_path).context(ErrorKind::IO)?;
+		warn!("Wallet seed file re-encrypted with the new password");
+		Ok(())
+	}
+
 	pub fn from_file(
 		data_file_dir: &str,
 		password: util::ZeroingString,
@@ -274,6 +310,8 @@ impl EncryptedWalletSeed {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::keychain::ExtKeychain;
+	use crate::libwallet::{Bip39Derivation, GrinDerivation};
 	use crate::util::ZeroingString;
 	#[test]
 	fn wallet_seed_encrypt() {
@@ -294,4 +332,51 @@ mod tests {
 		let decrypted_wallet_seed = enc_wallet_seed.decrypt(&password);
 		assert!(decrypted_wallet_seed.is_err());
 	}
+
+	#[test]
+	fn wallet_seed_passphrase() {
+		let wallet_seed = WalletSeed::init_new(32);
+		let root_key_id = |passphrase, scheme: &dyn DerivationScheme| {
+			let k: ExtKeychain = wallet_seed
+				.derive_keychain(false, passphrase, scheme)
+				.unwrap();
+			k.root_key_id()
+		};
+		let grin = &GrinDerivation;
+		assert_eq!(root_key_id(None, grin), root_key_id(Some(""), grin));
+		assert_ne!(
+			root_key_id(None, grin),
+			root_key_id(Some("passphrase"), grin)
+		);
+		assert_eq!(
+			root_key_id(Some("passphrase"), grin),
+			root_key_id(Some("passphrase"), grin)
+		);
+		// the schemes only agree when a passphrase is given
+		let bip39 = &Bip39Derivation;
+		assert_ne!(root_key_id(None, grin), root_key_id(None, bip39));
+		assert_eq!(
+			root_key_id(Some("passphrase"), grin),
+			root_key_id(Some("passphrase"), bip39)
+		);
+	}
+
+	#[test]
+	fn wallet_seed_change_password() {
+		let dir = "test_output/wallet_seed_change_password";
+		let _ = fs::remove_dir_all(dir);
+		let old = ZeroingString::from("old");
+		let new = ZeroingString::from("new");
+		let seed = WalletSeed::init_file(dir, 32, None, old.clone()).unwrap();
+
+		// the wrong password leaves the seed file as it was
+		assert!(WalletSeed::change_password(dir, new.clone(), old.clone()).is_err());
+		assert_eq!(WalletSeed::from_file(dir, old.clone()).unwrap(), seed);
+
+		WalletSeed::change_password(dir, old.clone(), new.clone()).unwrap();
+		assert!(WalletSeed::from_file(dir, old).is_err());
+		assert_eq!(WalletSeed::from_file(dir, new).unwrap(), seed);
+		assert!(!Path::new(dir).join(format!("{}.tmp", SEED_FILE)).exists());
+		let _ = fs::remove_dir_all(dir);
+	}
 }
@@ -0,0 +1,186 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Client for a directory of community-run public nodes, for wallets without
+//! a node of their own. The directory is signed by its maintainer, so a
+//! compromised host can't point wallets at nodes of its choosing
+
+use std::thread;
+use std::time::Instant;
+
+use crate::api;
+use crate::libwallet::{canonical_json_message, Error, ErrorKind};
+use crate::util::secp::key::PublicKey;
+use crate::util::secp::Signature;
+use crate::util::{from_hex, static_secp_instance};
+use crate::HTTPNodeClient;
+
+/// Score lost for each block a node is behind the highest node checked
+const SCORE_PER_BLOCK_BEHIND: u64 = 10;
+/// Milliseconds of latency costing one point of score
+const LATENCY_MS_PER_POINT: u64 = 50;
+
+/// A node listed in the directory
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PublicNode {
+	/// API address of the node
+	pub url: String,
+	/// Name of the node or its operator, if given
+	#[serde(default)]
+	pub name: Option<String>,
+}
+
+/// Directory of public nodes, as served at the directory URL
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NodeDirectory {
+	/// The listed nodes
+	pub nodes: Vec<PublicNode>,
+	/// Hex of the compact secp256k1 signature over the canonical JSON of
+	/// `nodes`
+	pub signature: String,
+}
+
+/// Result of checking a public node
+#[derive(Debug, Clone)]
+pub struct PublicNodeHealth {
+	/// The node checked
+	pub node: PublicNode,
+	/// Chain height reported by the node, if it answered
+	pub chain_height: Option<u64>,
+	/// Time taken to answer, in milliseconds
+	pub latency_ms: u64,
+	/// Score from 0 to 100, higher being better. Nodes that didn't answer
+	/// score 0
+	pub score: u64,
+}
+
+impl NodeDirectory {
+	/// Fetch the directory from the given URL, checking it's signed by the
+	/// given public key (hex)
+	pub fn fetch(url: &str, public_key: &str) -> Result<NodeDirectory, Error> {
+		debug!("Fetching public node directory from {}", url);
+		let dir = api::client::get::<NodeDirectory>(url, None).map_err(|e| {
+			ErrorKind::ClientCallback(format!("Fetching node directory from {}: {}", url, e))
+		})?;
+		dir.verify(public_key)?;
+		Ok(dir)
+	}
+
+	/// Check the directory is signed by the given public key (hex)
+	pub fn verify(&self, public_key: &str) -> Result<(), Error> {
+		let invalid = |what: &str| ErrorKind::ClientCallback(format!("Node directory: {}", what));
+		let secp = static_secp_instance();
+		let secp = secp.lock();
+		let key = from_hex(public_key.to_owned()).map_err(|_| invalid("invalid public key"))?;
+		let key = PublicKey::from_slice(&secp, &key).map_err(|_| invalid("invalid public key"))?;
+		let sig = from_hex(self.signature.clone()).map_err(|_| invalid("invalid signature"))?;
+		let sig = Signature::from_compact(&secp, &sig).map_err(|_| invalid("invalid signature"))?;
+		let msg = canonical_json_message(&self.nodes)?;
+		secp.verify(&msg, &sig, &key)
+			.map_err(|_| invalid("signature doesn't match the directory key"))?;
+		Ok(())
+	}
+
+	/// Check each listed node in parallel, returning the results best first
+	pub fn check_health(&self, node_api_secret: Option<String>) -> Vec<PublicNodeHealth> {
+		let handles: Vec<_> = self
+			.nodes
+			.iter()
+			.cloned()
+			.map(|node| {
+				let secret = node_api_secret.clone();
+				thread::spawn(move || {
+					let start = Instant::now();
+					let client = HTTPNodeClient::new(&node.url, secret);
+					let chain_height = match client.chain_height() {
+						Ok(h) => Some(h),
+						Err(e) => {
+							debug!("Public node {} didn't answer: {}", node.url, e);
+							None
+						}
+					};
+					let elapsed = start.elapsed();
+					PublicNodeHealth {
+						node,
+						chain_height,
+						latency_ms: elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_millis()),
+						score: 0,
+					}
+				})
+			})
+			.collect();
+		let mut results: Vec<PublicNodeHealth> =
+			handles.into_iter().filter_map(|h| h.join().ok()).collect();
+		score(&mut results);
+		results
+	}
+}
+
+/// Score the checked nodes and sort them best first. Nodes lose points for
+/// each block they're behind the highest node, and for their latency
+fn score(results: &mut [PublicNodeHealth]) {
+	let highest = results.iter().filter_map(|r| r.chain_height).max();
+	for r in results.iter_mut() {
+		r.score = match (r.chain_height, highest) {
+			(Some(h), Some(highest)) => {
+				let penalty =
+					(highest - h) * SCORE_PER_BLOCK_BEHIND + r.latency_ms / LATENCY_MS_PER_POINT;
+				100u64.saturating_sub(penalty)
+			}
+			_ => 0,
+		};
+	}
+	results.sort_by(|a, b| b.score.cmp(&a.score).then(a.latency_ms.cmp(&b.latency_ms)));
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn health(url: &str, chain_height: Option<u64>, latency_ms: u64) -> PublicNodeHealth {
+		PublicNodeHealth {
+			node: PublicNode {
+				url: url.to_owned(),
+				name: None,
+			},
+			chain_height,
+			latency_ms,
+			score: 0,
+		}
+	}
+
+	#[test]
+	fn public_node_scores() {
+		let mut results = vec![
+			health("http://behind", Some(1000), 100),
+			health("http://down", None, 5000),
+			health("http://slow", Some(1002), 1000),
+			health("http://fast", Some(1002), 100),
+		];
+		score(&mut results);
+		let order: Vec<(&str, u64)> = results
+			.iter()
+			.map(|r| (r.node.url.as_str(), r.score))
+			.collect();
+		assert_eq!(
+			order,
+			vec![
+				("http://fast", 98),
+				("http://slow", 80),
+				("http://behind", 78),
+				("http://down", 0),
+			]
+		);
+	}
+}
@@ -0,0 +1,59 @@
+// Copyright 2018 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Discovery of grin nodes running on the same machine as the wallet, by
+//! probing the ports nodes serve their API on by default
+
+use crate::core::global::ChainTypes;
+use crate::HTTPNodeClient;
+
+/// Default node API port of each chain type
+const DEFAULT_NODE_API_PORTS: [(ChainTypes, u16); 3] = [
+	(ChainTypes::Mainnet, 3413),
+	(ChainTypes::Floonet, 13413),
+	(ChainTypes::UserTesting, 23413),
+];
+
+/// A node found answering on a local port
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiscoveredNode {
+	/// API address of the node
+	pub url: String,
+	/// Chain height reported by the node
+	pub chain_height: u64,
+}
+
+/// Probe the default node API ports on localhost, returning the nodes that
+/// answer, the node on the default port of the given chain type first
+pub fn discover_local_nodes(
+	chain_type: &ChainTypes,
+	node_api_secret: Option<String>,
+) -> Vec<DiscoveredNode> {
+	let mut ports: Vec<&(ChainTypes, u16)> = DEFAULT_NODE_API_PORTS.iter().collect();
+	ports.sort_by_key(|(c, _)| c != chain_type);
+	ports
+		.into_iter()
+		.filter_map(|(_, port)| {
+			let url = format!("http://127.0.0.1:{}", port);
+			let client = HTTPNodeClient::new(&url, node_api_secret.clone());
+			match client.chain_height() {
+				Ok(chain_height) => Some(DiscoveredNode { url, chain_height }),
+				Err(e) => {
+					debug!("No node found at {}: {}", url, e);
+					None
+				}
+			}
+		})
+		.collect()
+}
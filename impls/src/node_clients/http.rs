@@ -16,49 +16,340 @@
 //! specific to the FileWallet
 
 use futures::{stream, Stream};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 
-use crate::libwallet::{NodeClient, NodeVersionInfo, TxWrapper};
+use crate::libwallet::{
+	resource_limits, NodeClient, NodeHealth, NodeStatus, NodeVersionInfo, TxWrapper,
+};
+use chrono::Utc;
+use rand::{thread_rng, Rng};
 use std::collections::HashMap;
-use tokio::runtime::Runtime;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::runtime::Builder;
 
 use crate::api;
+use crate::core::core::TxKernel;
 use crate::libwallet;
+use crate::proxy::Socks5Proxy;
 use crate::util;
 use crate::util::secp::pedersen;
+use crate::util::RwLock;
+
+/// Attempts made of a request the node keeps answering with a throttling or
+/// transient server error before giving up on it
+const MAX_REQUEST_ATTEMPTS: u32 = 4;
+/// Delay before the first retry, doubled for each one after
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+/// How long the chain height is reused for, so a burst of calls each needing
+/// it makes a single request of the node
+const CHAIN_HEIGHT_TTL: Duration = Duration::from_secs(5);
+/// How long the node's version info is reused for before asking again, in
+/// case the node was upgraded meanwhile
+const VERSION_INFO_TTL: Duration = Duration::from_secs(60);
+
+/// A value fetched from the node, shared between clones of the client and
+/// reused until it's older than its time to live
+#[derive(Clone)]
+struct Cached<T> {
+	value: Arc<RwLock<Option<(T, Instant)>>>,
+	ttl: Duration,
+}
+
+impl<T: Clone> Cached<T> {
+	fn new(ttl: Duration) -> Cached<T> {
+		Cached {
+			value: Arc::new(RwLock::new(None)),
+			ttl,
+		}
+	}
+
+	fn get(&self) -> Option<T> {
+		match self.value.read().as_ref() {
+			Some((v, fetched)) if fetched.elapsed() < self.ttl => Some(v.clone()),
+			_ => None,
+		}
+	}
+
+	fn set(&self, value: T) {
+		*self.value.write() = Some((value, Instant::now()));
+	}
+
+	fn clear(&self) {
+		*self.value.write() = None;
+	}
+}
 
 #[derive(Clone)]
 pub struct HTTPNodeClient {
-	node_url: String,
+	node_urls: Vec<String>,
+	/// Index of the node currently in use, shared between clones so that a
+	/// failover is remembered by all of them
+	active: Arc<AtomicUsize>,
+	health: Arc<RwLock<Vec<NodeHealth>>>,
 	node_api_secret: Option<String>,
-	node_version_info: Option<NodeVersionInfo>,
+	node_version_info: Cached<NodeVersionInfo>,
+	chain_height: Cached<u64>,
+	archive_node_url: Option<String>,
+	proxy: Option<Socks5Proxy>,
+}
+
+/// Split a comma-separated list of node addresses
+fn parse_node_urls(node_url: &str) -> Vec<String> {
+	let urls: Vec<String> = node_url
+		.split(',')
+		.map(|u| u.trim())
+		.filter(|u| !u.is_empty())
+		.map(|u| u.to_owned())
+		.collect();
+	if urls.is_empty() {
+		vec![node_url.to_owned()]
+	} else {
+		urls
+	}
+}
+
+/// Status code the node answered a failed request with, if it answered at
+/// all rather than being unreachable. Unfortunately have to parse string due
+/// to error structure
+fn response_status(e: &api::Error) -> Option<u16> {
+	let msg = format!("{}", e);
+	let prefix = "Wrong response code: ";
+	let start = msg.find(prefix)? + prefix.len();
+	msg[start..]
+		.split(|c: char| !c.is_ascii_digit())
+		.next()?
+		.parse()
+		.ok()
+}
+
+/// Whether the node answered the request, albeit with an error status, as
+/// opposed to being unreachable
+fn node_responded(e: &api::Error) -> bool {
+	response_status(e).is_some()
+}
+
+/// Whether the node refused the request for being made too often
+fn is_throttled(e: &api::Error) -> bool {
+	response_status(e) == Some(429)
+}
+
+/// Whether the request is worth making again shortly: the node was throttling
+/// requests, or hit a server error that's likely to clear up
+fn is_transient(e: &api::Error) -> bool {
+	match response_status(e) {
+		Some(429) | Some(502) | Some(503) | Some(504) => true,
+		_ => false,
+	}
+}
+
+/// Delay before the given retry of a request. Between half and all
+/// of the doubled delay is taken at random, so wallets throttled together
+/// don't all come back together
+fn retry_delay(attempt: u32) -> Duration {
+	let max = RETRY_DELAY.as_millis() as u64 * 2u64.pow(attempt);
+	Duration::from_millis(thread_rng().gen_range(max / 2, max + 1))
+}
+
+/// Make the request, retrying it with a jittered delay while the node answers
+/// with a throttling or transient server error
+fn with_retry<T, F>(url: &str, request: F) -> Result<T, api::Error>
+where
+	F: Fn() -> Result<T, api::Error>,
+{
+	let mut attempt = 0;
+	loop {
+		match request() {
+			Err(ref e) if is_transient(e) && attempt + 1 < MAX_REQUEST_ATTEMPTS => {
+				let delay = retry_delay(attempt);
+				debug!(
+					"Request to {} failed, retrying in {}ms: {}",
+					url,
+					delay.as_millis(),
+					e
+				);
+				thread::sleep(delay);
+				attempt += 1;
+			}
+			res => return res,
+		}
+	}
+}
+
+/// The wallet error for a failed request, telling a node throttling requests
+/// apart from other failures
+fn node_error(report: String, e: &api::Error) -> libwallet::Error {
+	match is_throttled(e) {
+		true => libwallet::ErrorKind::NodeThrottled(report).into(),
+		false => libwallet::ErrorKind::ClientCallback(report).into(),
+	}
+}
+
+/// Put the report in place of the failed request's error, keeping its kind
+fn with_report(e: libwallet::Error, report: String) -> libwallet::Error {
+	match e.kind() {
+		libwallet::ErrorKind::NodeThrottled(_) => {
+			libwallet::ErrorKind::NodeThrottled(report).into()
+		}
+		_ => libwallet::ErrorKind::ClientCallback(report).into(),
+	}
 }
 
 impl HTTPNodeClient {
-	/// Create a new client that will communicate with the given grin node
+	/// Create a new client that will communicate with the given grin node.
+	/// `node_url` may be a comma-separated list of nodes, in order of
+	/// preference, in which case requests fail over to the next node in the
+	/// list whenever the node in use can't be reached
 	pub fn new(node_url: &str, node_api_secret: Option<String>) -> HTTPNodeClient {
+		let node_urls = parse_node_urls(node_url);
+		let health = HTTPNodeClient::unchecked(&node_urls);
 		HTTPNodeClient {
-			node_url: node_url.to_owned(),
+			node_urls,
+			active: Arc::new(AtomicUsize::new(0)),
+			health: Arc::new(RwLock::new(health)),
 			node_api_secret: node_api_secret,
-			node_version_info: None,
+			node_version_info: Cached::new(VERSION_INFO_TTL),
+			chain_height: Cached::new(CHAIN_HEIGHT_TTL),
+			archive_node_url: None,
+			proxy: None,
 		}
 	}
 
+	/// Make all requests of nodes through the given SOCKS5 proxy
+	pub fn set_proxy(&mut self, proxy: Option<Socks5Proxy>) {
+		self.proxy = proxy;
+	}
+
+	/// GET the url, through the proxy if there's one
+	fn get<T>(&self, url: &str) -> Result<T, api::Error>
+	where
+		T: DeserializeOwned + Send + 'static,
+	{
+		with_retry(url, || match self.proxy.as_ref() {
+			Some(p) => p.get(url, self.node_api_secret()),
+			None => api::client::get(url, self.node_api_secret()),
+		})
+	}
+
+	/// POST the input to the url, through the proxy if there's one
+	fn post_no_ret<IN>(&self, url: &str, input: &IN) -> Result<(), api::Error>
+	where
+		IN: Serialize,
+	{
+		with_retry(url, || match self.proxy.as_ref() {
+			Some(p) => p.post_no_ret(url, self.node_api_secret(), input),
+			None => api::client::post_no_ret(url, self.node_api_secret(), input),
+		})
+	}
+
+	/// Set an archival node to fall back on for historical queries the
+	/// primary node is unable to answer
+	pub fn set_archive_node_url(&mut self, archive_node_url: Option<String>) {
+		self.archive_node_url = archive_node_url;
+	}
+
 	/// Allow returning the chain height without needing a wallet instantiated
 	pub fn chain_height(&self) -> Result<u64, libwallet::Error> {
 		self.get_chain_height()
 	}
+
+	/// Check the health of every configured node by requesting its chain tip,
+	/// switching to the first healthy node if the one in use isn't
+	pub fn check_node_health(&self) {
+		for (index, url) in self.node_urls.iter().enumerate() {
+			let url = format!("{}/v1/chain", url);
+			let res = self
+				.get::<api::Tip>(url.as_str())
+				.map(|_| ())
+				.map_err(|e| format!("{}", e));
+			self.record_health(index, res);
+		}
+		let health = self.health.read();
+		let active = self.active.load(Ordering::SeqCst);
+		if health[active].healthy != Some(true) {
+			if let Some(index) = health.iter().position(|h| h.healthy == Some(true)) {
+				warn!(
+					"Node {} is unhealthy, switching to {}",
+					self.node_urls[active], self.node_urls[index]
+				);
+				self.active.store(index, Ordering::SeqCst);
+			}
+		}
+	}
+
+	fn unchecked(node_urls: &[String]) -> Vec<NodeHealth> {
+		node_urls
+			.iter()
+			.map(|url| NodeHealth {
+				url: url.clone(),
+				healthy: None,
+				last_error: None,
+				last_checked: None,
+			})
+			.collect()
+	}
+
+	fn record_health(&self, index: usize, res: Result<(), String>) {
+		let mut health = self.health.write();
+		if let Some(h) = health.get_mut(index) {
+			h.healthy = Some(res.is_ok());
+			h.last_error = res.err();
+			h.last_checked = Some(Utc::now());
+		}
+	}
+
+	/// Make a request of the node in use, failing over to each of the other
+	/// nodes in turn should it fail. The first node to answer becomes the
+	/// node in use
+	fn with_failover<T, F>(&self, request: F) -> Result<T, libwallet::Error>
+	where
+		F: Fn(&str) -> Result<T, libwallet::Error>,
+	{
+		let active = self.active.load(Ordering::SeqCst);
+		let count = self.node_urls.len();
+		let mut last_err = None;
+		for i in 0..count {
+			let index = (active + i) % count;
+			let url = &self.node_urls[index];
+			match request(url) {
+				Ok(r) => {
+					self.record_health(index, Ok(()));
+					if index != active {
+						warn!("Failed over to node {}", url);
+						self.active.store(index, Ordering::SeqCst);
+					}
+					return Ok(r);
+				}
+				Err(e) => {
+					if count > 1 {
+						warn!("Request to node {} failed: {}", url, e);
+					}
+					self.record_health(index, Err(format!("{}", e)));
+					last_err = Some(e);
+				}
+			}
+		}
+		Err(last_err.expect("node client has at least one node"))
+	}
 }
 
 impl NodeClient for HTTPNodeClient {
 	fn node_url(&self) -> &str {
-		&self.node_url
+		&self.node_urls[self.active.load(Ordering::SeqCst)]
 	}
 	fn node_api_secret(&self) -> Option<String> {
 		self.node_api_secret.clone()
 	}
 
 	fn set_node_url(&mut self, node_url: &str) {
-		self.node_url = node_url.to_owned();
+		self.node_urls = parse_node_urls(node_url);
+		self.active = Arc::new(AtomicUsize::new(0));
+		self.health = Arc::new(RwLock::new(HTTPNodeClient::unchecked(&self.node_urls)));
+		self.node_version_info = Cached::new(VERSION_INFO_TTL);
+		self.chain_height = Cached::new(CHAIN_HEIGHT_TTL);
 	}
 
 	fn set_node_api_secret(&mut self, node_api_secret: Option<String>) {
@@ -66,104 +357,214 @@ impl NodeClient for HTTPNodeClient {
 	}
 
 	fn get_version_info(&mut self) -> Option<NodeVersionInfo> {
-		if let Some(v) = self.node_version_info.as_ref() {
-			return Some(v.clone());
+		if let Some(v) = self.node_version_info.get() {
+			return Some(v);
 		}
-		let url = format!("{}/v1/version", self.node_url());
-		let mut retval =
-			match api::client::get::<NodeVersionInfo>(url.as_str(), self.node_api_secret()) {
-				Ok(n) => n,
+		let res = self.with_failover(|addr| {
+			let url = format!("{}/v1/version", addr);
+			match self.get::<NodeVersionInfo>(url.as_str()) {
+				Ok(n) => Ok(Some(n)),
 				Err(e) => {
 					// If node isn't available, allow offline functions
 					// unfortunately have to parse string due to error structure
 					let err_string = format!("{}", e);
 					if err_string.contains("404") {
-						return Some(NodeVersionInfo {
-							node_version: "1.0.0".into(),
-							block_header_version: 1,
-							verified: Some(false),
-						});
+						Ok(None)
 					} else {
-						error!("Unable to contact Node to get version info: {}", e);
-						return None;
+						Err(node_error(err_string, &e))
 					}
 				}
-			};
+			}
+		});
+		let mut retval = match res {
+			Ok(Some(n)) => n,
+			Ok(None) => {
+				return Some(NodeVersionInfo {
+					node_version: "1.0.0".into(),
+					block_header_version: 1,
+					verified: Some(false),
+				});
+			}
+			Err(e) => {
+				error!("Unable to contact Node to get version info: {}", e);
+				return None;
+			}
+		};
 		retval.verified = Some(true);
-		self.node_version_info = Some(retval.clone());
+		self.node_version_info.set(retval.clone());
 		Some(retval)
 	}
 
 	/// Posts a transaction to a grin node
 	fn post_tx(&self, tx: &TxWrapper, fluff: bool) -> Result<(), libwallet::Error> {
-		let url;
-		let dest = self.node_url();
-		if fluff {
-			url = format!("{}/v1/pool/push_tx?fluff", dest);
-		} else {
-			url = format!("{}/v1/pool/push_tx", dest);
-		}
-		let res = api::client::post_no_ret(url.as_str(), self.node_api_secret(), tx);
+		// a node rejecting the transaction isn't a reason to fail over, a node
+		// throttling or failing to answer is
+		let res = self.with_failover(|dest| {
+			let url = if fluff {
+				format!("{}/v1/pool/push_tx?fluff", dest)
+			} else {
+				format!("{}/v1/pool/push_tx", dest)
+			};
+			match self.post_no_ret(url.as_str(), tx) {
+				Ok(()) => Ok(Ok(())),
+				Err(e) if node_responded(&e) && !is_transient(&e) => Ok(Err(e)),
+				Err(e) => Err(node_error(format!("{}", e), &e)),
+			}
+		});
+		let res = res.and_then(|r| {
+			r.map_err(|e| libwallet::ErrorKind::ClientCallback(format!("{}", e)).into())
+		});
 		if let Err(e) = res {
 			let report = format!("Posting transaction to node: {}", e);
 			error!("Post TX Error: {}", e);
-			return Err(libwallet::ErrorKind::ClientCallback(report).into());
+			return Err(with_report(e, report));
 		}
 		Ok(())
 	}
 
 	/// Return the chain tip from a given node
 	fn get_chain_height(&self) -> Result<u64, libwallet::Error> {
-		let addr = self.node_url();
-		let url = format!("{}/v1/chain", addr);
-		let res = api::client::get::<api::Tip>(url.as_str(), self.node_api_secret());
-		match res {
-			Err(e) => {
-				let report = format!("Getting chain height from node: {}", e);
-				error!("Get chain height error: {}", e);
-				Err(libwallet::ErrorKind::ClientCallback(report).into())
+		if let Some(h) = self.chain_height.get() {
+			return Ok(h);
+		}
+		let height = self.with_failover(|addr| {
+			let url = format!("{}/v1/chain", addr);
+			let res = self.get::<api::Tip>(url.as_str());
+			match res {
+				Err(e) => {
+					let report = format!("Getting chain height from node: {}", e);
+					error!("Get chain height error: {}", e);
+					Err(node_error(report, &e))
+				}
+				Ok(r) => Ok(r.height),
+			}
+		})?;
+		self.chain_height.set(height);
+		Ok(height)
+	}
+
+	fn reconnect(&mut self) {
+		self.node_version_info.clear();
+		self.chain_height.clear();
+		self.check_node_health();
+		let health = self.health.read();
+		if let Some(index) = health.iter().position(|h| h.healthy == Some(true)) {
+			if index != self.active.load(Ordering::SeqCst) {
+				warn!("Returning to node {}", self.node_urls[index]);
+				self.active.store(index, Ordering::SeqCst);
 			}
-			Ok(r) => Ok(r.height),
 		}
 	}
 
+	fn node_status(&self) -> NodeStatus {
+		self.check_node_health();
+		NodeStatus {
+			active_node: self.node_url().to_owned(),
+			nodes: self.health.read().clone(),
+		}
+	}
+
+	/// Look up a kernel on chain by its excess
+	fn get_kernel(
+		&self,
+		excess: &pedersen::Commitment,
+		min_height: Option<u64>,
+		max_height: Option<u64>,
+	) -> Result<Option<(TxKernel, u64, u64)>, libwallet::Error> {
+		let mut query = vec![];
+		if let Some(h) = min_height {
+			query.push(format!("min_height={}", h));
+		}
+		if let Some(h) = max_height {
+			query.push(format!("max_height={}", h));
+		}
+		self.with_failover(|addr| {
+			let mut url = format!(
+				"{}/v1/chain/kernels/{}",
+				addr,
+				util::to_hex(excess.0.to_vec())
+			);
+			if !query.is_empty() {
+				url = format!("{}?{}", url, query.join("&"));
+			}
+			match self.get::<api::LocatedTxKernel>(url.as_str()) {
+				Ok(k) => Ok(Some((k.tx_kernel, k.height, k.mmr_index))),
+				// the node answers a kernel it doesn't know of with a 404
+				Err(e) if response_status(&e) == Some(404) => Ok(None),
+				Err(e) => {
+					let report = format!("Getting kernel from node: {}", e);
+					error!("Get kernel error: {}", e);
+					Err(node_error(report, &e))
+				}
+			}
+		})
+	}
+
 	/// Retrieve outputs from node
 	fn get_outputs_from_node(
 		&self,
 		wallet_outputs: Vec<pedersen::Commitment>,
 	) -> Result<HashMap<pedersen::Commitment, (String, u64, u64)>, libwallet::Error> {
-		let addr = self.node_url();
 		// build the necessary query params -
 		// ?id=xxx&id=yyy&id=zzz
 		let query_params: Vec<String> = wallet_outputs
 			.iter()
 			.map(|commit| format!("id={}", util::to_hex(commit.as_ref().to_vec())))
 			.collect();
+		let limits = resource_limits();
 
-		// build a map of api outputs by commit so we can look them up efficiently
-		let mut api_outputs: HashMap<pedersen::Commitment, (String, u64, u64)> = HashMap::new();
-		let mut tasks = Vec::new();
-
-		for query_chunk in query_params.chunks(200) {
-			let url = format!("{}/v1/chain/outputs/byids?{}", addr, query_chunk.join("&"),);
-			tasks.push(api::client::get_async::<Vec<api::Output>>(
-				url.as_str(),
-				self.node_api_secret(),
-			));
-		}
+		let results = self.with_failover(|addr| {
+			let urls: Vec<String> = query_params
+				.chunks(limits.node_query_chunk_size.max(1))
+				.map(|chunk| format!("{}/v1/chain/outputs/byids?{}", addr, chunk.join("&")))
+				.collect();
+			// requests through the proxy are made one at a time
+			if self.proxy.is_some() {
+				return urls
+					.iter()
+					.map(|url| self.get::<Vec<api::Output>>(url))
+					.collect::<Result<Vec<_>, _>>()
+					.map_err(|e| {
+						let report = format!("Getting outputs by id: {}", e);
+						error!("Outputs by id failed: {}", e);
+						node_error(report, &e)
+					});
+			}
+			// the batch is retried as a whole should any request in it fail
+			let res = with_retry(addr, || {
+				let tasks: Vec<_> = urls
+					.iter()
+					.map(|url| {
+						api::client::get_async::<Vec<api::Output>>(
+							url.as_str(),
+							self.node_api_secret(),
+						)
+					})
+					.collect();
 
-		let task = stream::futures_unordered(tasks).collect();
+				let task = stream::iter_ok(tasks)
+					.buffer_unordered(limits.node_request_concurrency.max(1))
+					.collect();
 
-		let mut rt = Runtime::new().unwrap();
-		let results = match rt.block_on(task) {
-			Ok(outputs) => outputs,
-			Err(e) => {
-				let report = format!("Getting outputs by id: {}", e);
-				error!("Outputs by id failed: {}", e);
-				return Err(libwallet::ErrorKind::ClientCallback(report).into());
+				let mut builder = Builder::new();
+				if let Some(threads) = limits.node_client_threads {
+					builder.core_threads(threads.max(1));
+				}
+				let mut rt = builder.build().unwrap();
+				rt.block_on(task)
+			});
+			match res {
+				Ok(outputs) => Ok(outputs),
+				Err(e) => {
+					let report = format!("Getting outputs by id: {}", e);
+					error!("Outputs by id failed: {}", e);
+					Err(node_error(report, &e))
+				}
 			}
-		};
+		})?;
 
+		// build a map of api outputs by commit so we can look them up efficiently
+		let mut api_outputs: HashMap<pedersen::Commitment, (String, u64, u64)> = HashMap::new();
 		for res in results {
 			for out in res {
 				api_outputs.insert(
@@ -187,15 +588,30 @@ impl NodeClient for HTTPNodeClient {
 		),
 		libwallet::Error,
 	> {
-		let addr = self.node_url();
 		let query_param = format!("start_index={}&max={}", start_height, max_outputs);
 
-		let url = format!("{}/v1/txhashset/outputs?{}", addr, query_param,);
-
 		let mut api_outputs: Vec<(pedersen::Commitment, pedersen::RangeProof, bool, u64, u64)> =
 			Vec::new();
 
-		match api::client::get::<api::OutputListing>(url.as_str(), self.node_api_secret()) {
+		let mut res = self.with_failover(|addr| {
+			let url = format!("{}/v1/txhashset/outputs?{}", addr, query_param,);
+			self.get::<api::OutputListing>(url.as_str())
+				.map_err(|e| node_error(format!("{}", e), &e))
+		});
+		if let (Err(e), Some(archive_addr)) = (&res, self.archive_node_url.as_ref()) {
+			warn!(
+				"get_outputs_by_pmmr_index: error contacting {} ({}), falling back to archival node {}",
+				self.node_url(),
+				e,
+				archive_addr
+			);
+			let archive_url = format!("{}/v1/txhashset/outputs?{}", archive_addr, query_param);
+			res = self
+				.get::<api::OutputListing>(archive_url.as_str())
+				.map_err(|e| node_error(format!("{}", e), &e));
+		}
+
+		match res {
 			Ok(o) => {
 				for out in o.outputs {
 					let is_coinbase = match out.output_type {
@@ -217,10 +633,11 @@ impl NodeClient for HTTPNodeClient {
 				// if we got anything other than 200 back from server, bye
 				error!(
 					"get_outputs_by_pmmr_index: error contacting {}. Error: {}",
-					addr, e
+					self.node_url(),
+					e
 				);
 				let report = format!("outputs by pmmr index: {}", e);
-				Err(libwallet::ErrorKind::ClientCallback(report))?
+				Err(with_report(e, report))
 			}
 		}
 	}
@@ -252,3 +669,73 @@ fn single_create_coinbase(url: &str, block_fees: &BlockFees) -> Result<CbData, E
 	))?;
 	Ok(res)
 }*/
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use std::cell::Cell;
+
+	fn response(status: &str) -> api::Error {
+		api::ErrorKind::RequestError(format!("Wrong response code: {} with data \"\"", status))
+			.into()
+	}
+
+	#[test]
+	fn retries_transient_failures() {
+		assert_eq!(
+			response_status(&response("429 Too Many Requests")),
+			Some(429)
+		);
+		assert_eq!(response_status(&response("503")), Some(503));
+		let unreachable: api::Error = api::ErrorKind::RequestError("refused".to_owned()).into();
+		assert_eq!(response_status(&unreachable), None);
+		assert!(is_transient(&response("429 Too Many Requests")));
+		assert!(is_transient(&response("502 Bad Gateway")));
+		assert!(!is_transient(&response("500 Internal Server Error")));
+		assert!(!is_transient(&unreachable));
+		for attempt in 0..MAX_REQUEST_ATTEMPTS {
+			let max = RETRY_DELAY * 2u32.pow(attempt);
+			let delay = retry_delay(attempt);
+			assert!(delay >= max / 2 && delay <= max);
+		}
+
+		// a request is made again until the node stops failing
+		let calls = Cell::new(0);
+		let res = with_retry("http://127.0.0.1:3413", || {
+			calls.set(calls.get() + 1);
+			match calls.get() {
+				1 => Err(response("503 Service Unavailable")),
+				_ => Ok(calls.get()),
+			}
+		});
+		assert_eq!(res.unwrap(), 2);
+		// and not at all if retrying won't help
+		calls.set(0);
+		let res: Result<(), _> = with_retry("http://127.0.0.1:3413", || {
+			calls.set(calls.get() + 1);
+			Err(response("404 Not Found"))
+		});
+		assert!(res.is_err());
+		assert_eq!(calls.get(), 1);
+
+		assert_eq!(
+			node_error("throttled".to_owned(), &response("429")).kind(),
+			libwallet::ErrorKind::NodeThrottled("throttled".to_owned())
+		);
+	}
+	#[test]
+	fn caches_until_stale() {
+		let height = Cached::new(Duration::from_secs(60));
+		assert_eq!(height.get(), None);
+		// clones of the client see what any of them fetched
+		let clone = height.clone();
+		clone.set(100u64);
+		assert_eq!(height.get(), Some(100));
+		height.clear();
+		assert_eq!(clone.get(), None);
+
+		let stale = Cached::new(Duration::from_secs(0));
+		stale.set(100u64);
+		assert_eq!(stale.get(), None);
+	}
+}
@@ -12,6 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod directory;
+mod discovery;
 mod http;
 
+pub use self::directory::{NodeDirectory, PublicNode, PublicNodeHealth};
+pub use self::discovery::{discover_local_nodes, DiscoveredNode};
 pub use self::http::HTTPNodeClient;
@@ -0,0 +1,65 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Historical grin prices fetched from an HTTP price service
+
+use chrono::prelude::{DateTime, Utc};
+
+use crate::api;
+use crate::libwallet::{Error, ErrorKind, PriceOracle};
+
+/// JSON response expected from the price service
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PriceResponse {
+	/// Fiat currency the price is given in
+	pub currency: String,
+	/// Price of one grin
+	pub price: f64,
+}
+
+/// Oracle querying a price service with
+/// `GET <url>?currency=<currency>&time=<unix timestamp>`
+#[derive(Clone, Debug)]
+pub struct HttpPriceOracle {
+	url: String,
+}
+
+impl HttpPriceOracle {
+	/// Create an oracle querying the price service at the given URL
+	pub fn new(url: String) -> HttpPriceOracle {
+		HttpPriceOracle { url }
+	}
+}
+
+impl PriceOracle for HttpPriceOracle {
+	fn price_at(&self, currency: &str, time: DateTime<Utc>) -> Result<f64, Error> {
+		let url = format!(
+			"{}?currency={}&time={}",
+			self.url,
+			currency,
+			time.timestamp()
+		);
+		debug!("Fetching {} price at {} from {}", currency, time, self.url);
+		let res = api::client::get::<PriceResponse>(url.as_str(), None).map_err(|e| {
+			ErrorKind::ClientCallback(format!("Fetching price from {}: {}", self.url, e))
+		})?;
+		if !res.currency.eq_ignore_ascii_case(currency) {
+			return Err(ErrorKind::ClientCallback(format!(
+				"Price service returned a {} price, {} requested",
+				res.currency, currency
+			)))?;
+		}
+		Ok(res.price)
+	}
+}
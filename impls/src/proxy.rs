@@ -0,0 +1,365 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Plain HTTP requests made through a SOCKS5 proxy, such as Tor's, for the
+//! node client and the http slate sender. Host names are resolved by the
+//! proxy rather than locally (as with `socks5h://`), so onion addresses work
+//! and lookups don't leak. Requests are JSON, one per connection
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+use url::Url;
+
+use crate::api;
+use crate::config::ProxyConfig;
+use crate::util::to_base64;
+
+const SOCKS_VERSION: u8 = 0x05;
+const AUTH_NONE: u8 = 0x00;
+const AUTH_PASSWORD: u8 = 0x02;
+const CMD_CONNECT: u8 = 0x01;
+const ATYP_IPV4: u8 = 0x01;
+const ATYP_DOMAIN: u8 = 0x03;
+const ATYP_IPV6: u8 = 0x04;
+/// Time allowed for each read or write, generous as circuits can be slow
+const IO_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// A SOCKS5 proxy outbound requests are made through
+#[derive(Clone, Debug)]
+pub struct Socks5Proxy {
+	address: String,
+	credentials: Option<(String, String)>,
+}
+
+fn request_error(msg: impl ToString) -> api::Error {
+	api::ErrorKind::RequestError(msg.to_string()).into()
+}
+
+impl Socks5Proxy {
+	/// Create a proxy as configured
+	pub fn new(config: &ProxyConfig) -> Socks5Proxy {
+		let address = config.socks5_address.trim_start_matches("socks5h://");
+		Socks5Proxy {
+			address: address.trim_start_matches("socks5://").to_owned(),
+			credentials: config
+				.username
+				.as_ref()
+				.map(|u| (u.clone(), config.password.clone().unwrap_or_default())),
+		}
+	}
+
+	/// Open a connection to the host through the proxy
+	fn connect(&self, host: &str, port: u16) -> io::Result<TcpStream> {
+		let fail = |msg: String| io::Error::new(io::ErrorKind::Other, msg);
+		let mut stream = TcpStream::connect(self.address.as_str())?;
+		stream.set_read_timeout(Some(IO_TIMEOUT))?;
+		stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+		let methods: &[u8] = match self.credentials {
+			Some(_) => &[AUTH_NONE, AUTH_PASSWORD],
+			None => &[AUTH_NONE],
+		};
+		let mut greeting = vec![SOCKS_VERSION, methods.len() as u8];
+		greeting.extend_from_slice(methods);
+		stream.write_all(&greeting)?;
+		let mut choice = [0u8; 2];
+		stream.read_exact(&mut choice)?;
+		match (choice[1], self.credentials.as_ref()) {
+			(AUTH_NONE, _) => {}
+			(AUTH_PASSWORD, Some((user, password))) => {
+				if user.len() > 255 || password.len() > 255 {
+					return Err(fail("proxy username or password is too long".to_owned()));
+				}
+				let mut auth = vec![0x01, user.len() as u8];
+				auth.extend_from_slice(user.as_bytes());
+				auth.push(password.len() as u8);
+				auth.extend_from_slice(password.as_bytes());
+				stream.write_all(&auth)?;
+				let mut status = [0u8; 2];
+				stream.read_exact(&mut status)?;
+				if status[1] != 0 {
+					return Err(fail("proxy refused the username and password".to_owned()));
+				}
+			}
+			_ => {
+				return Err(fail(
+					"proxy accepts none of our authentication methods".to_owned(),
+				));
+			}
+		}
+
+		if host.len() > 255 {
+			return Err(fail(format!("host name {} is too long", host)));
+		}
+		let mut connect = vec![SOCKS_VERSION, CMD_CONNECT, 0x00, ATYP_DOMAIN];
+		connect.push(host.len() as u8);
+		connect.extend_from_slice(host.as_bytes());
+		connect.extend_from_slice(&port.to_be_bytes());
+		stream.write_all(&connect)?;
+		let mut reply = [0u8; 4];
+		stream.read_exact(&mut reply)?;
+		if reply[1] != 0 {
+			return Err(fail(format!(
+				"proxy couldn't connect to {}:{} (reply {})",
+				host, port, reply[1]
+			)));
+		}
+		// skip the address the proxy bound
+		let addr_len = match reply[3] {
+			ATYP_IPV4 => 4,
+			ATYP_IPV6 => 16,
+			ATYP_DOMAIN => {
+				let mut len = [0u8; 1];
+				stream.read_exact(&mut len)?;
+				len[0] as usize
+			}
+			t => return Err(fail(format!("proxy replied with address type {}", t))),
+		};
+		let mut bound = vec![0u8; addr_len + 2];
+		stream.read_exact(&mut bound)?;
+		Ok(stream)
+	}
+
+	/// Make the request through the proxy, returning the body of a successful
+	/// response
+	fn request(
+		&self,
+		method: &str,
+		url: &str,
+		api_secret: Option<String>,
+		body: Option<Vec<u8>>,
+	) -> Result<String, api::Error> {
+		let url: Url = url
+			.parse()
+			.map_err(|e| request_error(format!("invalid url {}: {}", url, e)))?;
+		if url.scheme() != "http" {
+			return Err(request_error(format!(
+				"only http urls can be reached through the proxy, not {}",
+				url
+			)));
+		}
+		let host = url
+			.host_str()
+			.ok_or_else(|| request_error(format!("no host in {}", url)))?;
+		let port = url.port().unwrap_or(80);
+		let mut target = url.path().to_owned();
+		if let Some(q) = url.query() {
+			target.push('?');
+			target.push_str(q);
+		}
+
+		let mut req = format!(
+			"{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nAccept: application/json\r\n",
+			method,
+			target,
+			match url.port() {
+				Some(p) => format!("{}:{}", host, p),
+				None => host.to_owned(),
+			}
+		);
+		if let Some(secret) = api_secret {
+			req.push_str(&format!(
+				"Authorization: Basic {}\r\n",
+				to_base64(&format!("grin:{}", secret))
+			));
+		}
+		let body = body.unwrap_or_default();
+		if method != "GET" {
+			req.push_str(&format!(
+				"Content-Type: application/json\r\nContent-Length: {}\r\n",
+				body.len()
+			));
+		}
+		req.push_str("\r\n");
+
+		let mut stream = self
+			.connect(host, port)
+			.map_err(|e| request_error(format!("connecting to {} through proxy: {}", url, e)))?;
+		let mut data = req.into_bytes();
+		data.extend_from_slice(&body);
+		let mut response = vec![];
+		stream
+			.write_all(&data)
+			.and_then(|_| stream.read_to_end(&mut response))
+			.map_err(|e| request_error(format!("requesting {} through proxy: {}", url, e)))?;
+		let (status, body) = parse_response(&response)
+			.ok_or_else(|| request_error(format!("malformed response from {}", url)))?;
+		if !(200..300).contains(&status) {
+			return Err(request_error(format!(
+				"Wrong response code: {} with data {}",
+				status, body
+			)));
+		}
+		Ok(body)
+	}
+
+	/// GET the url, parsing the JSON response
+	pub fn get<T>(&self, url: &str, api_secret: Option<String>) -> Result<T, api::Error>
+	where
+		T: DeserializeOwned,
+	{
+		let res = self.request("GET", url, api_secret, None)?;
+		serde_json::from_str(&res).map_err(|e| {
+			api::ErrorKind::ResponseError(format!("Cannot parse response: {}", e)).into()
+		})
+	}
+
+	/// POST the input as JSON, returning the response's body
+	pub fn post<IN>(
+		&self,
+		url: &str,
+		api_secret: Option<String>,
+		input: &IN,
+	) -> Result<String, api::Error>
+	where
+		IN: Serialize,
+	{
+		let body = serde_json::to_vec(input).map_err(|e| {
+			api::Error::from(api::ErrorKind::Internal(format!(
+				"Could not serialize data to JSON: {}",
+				e
+			)))
+		})?;
+		self.request("POST", url, api_secret, Some(body))
+	}
+
+	/// POST the input as JSON, ignoring the response's body
+	pub fn post_no_ret<IN>(
+		&self,
+		url: &str,
+		api_secret: Option<String>,
+		input: &IN,
+	) -> Result<(), api::Error>
+	where
+		IN: Serialize,
+	{
+		self.post(url, api_secret, input).map(|_| ())
+	}
+}
+
+/// Status code and body of a raw HTTP response
+pub(crate) fn parse_response(response: &[u8]) -> Option<(u16, String)> {
+	let split = response.windows(4).position(|w| w == b"\r\n\r\n")?;
+	let head = String::from_utf8_lossy(&response[..split]);
+	let mut lines = head.split("\r\n");
+	let status = lines.next()?.split_whitespace().nth(1)?.parse().ok()?;
+	let chunked = lines.any(|l| {
+		let l = l.to_ascii_lowercase();
+		l.starts_with("transfer-encoding:") && l.contains("chunked")
+	});
+	let body = &response[split + 4..];
+	let body = match chunked {
+		true => dechunk(body)?,
+		false => body.to_vec(),
+	};
+	Some((status, String::from_utf8_lossy(&body).into_owned()))
+}
+
+/// Join the chunks of a body sent with chunked transfer encoding
+fn dechunk(mut body: &[u8]) -> Option<Vec<u8>> {
+	let mut out = vec![];
+	loop {
+		let line_end = body.windows(2).position(|w| w == b"\r\n")?;
+		let size_line = String::from_utf8_lossy(&body[..line_end]);
+		let size_hex = size_line.split(';').next()?.trim();
+		let size = usize::from_str_radix(size_hex, 16).ok()?;
+		body = &body[line_end + 2..];
+		if size == 0 {
+			return Some(out);
+		}
+		if body.len() < size + 2 {
+			return None;
+		}
+		out.extend_from_slice(&body[..size]);
+		body = &body[size + 2..];
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use std::net::TcpListener;
+	use std::thread;
+
+	/// Accept one connection as a SOCKS5 proxy requiring a password, then
+	/// answer the request as the destination would
+	fn fake_proxy(listener: TcpListener) -> thread::JoinHandle<(String, Vec<u8>)> {
+		thread::spawn(move || {
+			let (mut s, _) = listener.accept().unwrap();
+			let mut buf = [0u8; 4];
+			s.read_exact(&mut buf).unwrap();
+			assert_eq!(buf, [5, 2, AUTH_NONE, AUTH_PASSWORD]);
+			s.write_all(&[5, AUTH_PASSWORD]).unwrap();
+			let mut auth = [0u8; 11];
+			s.read_exact(&mut auth).unwrap();
+			assert_eq!(&auth, b"\x01\x04user\x04pass");
+			s.write_all(&[1, 0]).unwrap();
+
+			let mut head = [0u8; 5];
+			s.read_exact(&mut head).unwrap();
+			assert_eq!(head[..4], [5, CMD_CONNECT, 0, ATYP_DOMAIN]);
+			let mut host = vec![0u8; head[4] as usize + 2];
+			s.read_exact(&mut host).unwrap();
+			s.write_all(&[5, 0, 0, ATYP_IPV4, 0, 0, 0, 0, 0, 0])
+				.unwrap();
+
+			let mut request = vec![];
+			let mut byte = [0u8; 1];
+			while !request.ends_with(b"\r\n\r\n") {
+				s.read_exact(&mut byte).unwrap();
+				request.push(byte[0]);
+			}
+			s.write_all(
+				b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+				  7\r\n{\"heigh\r\n7\r\nt\": 42}\r\n0\r\n\r\n",
+			)
+			.unwrap();
+			(String::from_utf8(request).unwrap(), host)
+		})
+	}
+
+	#[test]
+	fn get_through_proxy() {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let proxy = Socks5Proxy::new(&ProxyConfig {
+			socks5_address: format!("socks5h://{}", listener.local_addr().unwrap()),
+			username: Some("user".to_owned()),
+			password: Some("pass".to_owned()),
+		});
+		let server = fake_proxy(listener);
+		let res: serde_json::Value = proxy
+			.get(
+				"http://example.onion:3413/v1/chain",
+				Some("secret".to_owned()),
+			)
+			.unwrap();
+		assert_eq!(res["height"], 42);
+
+		let (request, host) = server.join().unwrap();
+		assert_eq!(&host[..host.len() - 2], b"example.onion");
+		assert_eq!(&host[host.len() - 2..], &3413u16.to_be_bytes());
+		assert!(request.starts_with("GET /v1/chain HTTP/1.1\r\nHost: example.onion:3413\r\n"));
+		assert!(request.contains(&format!(
+			"Authorization: Basic {}\r\n",
+			to_base64("grin:secret")
+		)));
+
+		assert!(proxy
+			.get::<serde_json::Value>("https://example.com/", None)
+			.is_err());
+	}
+}
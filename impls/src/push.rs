@@ -0,0 +1,69 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Push notifications sent via an HTTP gateway, which holds the credentials
+//! for each push service and forwards notifications to devices
+
+use uuid::Uuid;
+
+use crate::api;
+use crate::libwallet::{Error, ErrorKind, PushNotifier, PushRegistration};
+
+/// JSON payload POSTed to the push gateway
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PushGatewayRequest {
+	/// Token identifying the device to its push service
+	pub token: String,
+	/// Push service the token was issued by
+	pub platform: String,
+	/// The event that occurred
+	pub event: String,
+	/// Id of the slate received
+	pub tx_slate_id: Uuid,
+}
+
+/// Notifier posting each notification to a push gateway
+#[derive(Clone, Debug)]
+pub struct GatewayPushNotifier {
+	gateway_url: String,
+}
+
+impl GatewayPushNotifier {
+	/// Create a notifier posting to the gateway at the given URL
+	pub fn new(gateway_url: String) -> GatewayPushNotifier {
+		GatewayPushNotifier { gateway_url }
+	}
+}
+
+impl PushNotifier for GatewayPushNotifier {
+	fn notify_slate_received(
+		&self,
+		registration: &PushRegistration,
+		slate_id: &Uuid,
+	) -> Result<(), Error> {
+		let req = PushGatewayRequest {
+			token: registration.token.clone(),
+			platform: registration.platform.clone(),
+			event: "slate_received".to_owned(),
+			tx_slate_id: *slate_id,
+		};
+		debug!(
+			"Sending push notification for slate {} to {} device",
+			slate_id, registration.platform
+		);
+		api::client::post_no_ret(self.gateway_url.as_str(), None, &req).map_err(|e| {
+			ErrorKind::ClientCallback(format!("Posting to push gateway: {}", e)).into()
+		})
+	}
+}
@@ -0,0 +1,221 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Owner API calls made of a wallet on another host, listening on a unix
+//! socket there, through an SSH connection forwarding a local socket to it.
+//! Headless wallets can then be operated by whoever can log in to their host,
+//! without the wallet listening on any port. Uses the ssh command, so the
+//! user's SSH configuration, keys and agent apply
+
+use serde_json::Value;
+use std::env;
+use std::fs;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::process::{self, Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use url::Url;
+
+use crate::libwallet::{Error, ErrorKind};
+use crate::proxy::parse_response;
+use crate::util::to_base64;
+
+/// Time allowed for the SSH connection to be made and the socket forwarded
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+fn ssh_error(msg: impl ToString) -> Error {
+	ErrorKind::GenericError(format!("SSH tunnel: {}", msg.to_string())).into()
+}
+
+/// A wallet's Owner API socket on another host, given as
+/// `ssh://[user@]host[:port]/path/to/socket`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SshRemote {
+	/// User to log in as, the SSH configuration's if not given
+	pub user: Option<String>,
+	/// Host to connect to
+	pub host: String,
+	/// Port SSH listens on, the SSH configuration's if not given
+	pub port: Option<u16>,
+	/// Path of the socket the wallet's Owner API listens on
+	pub socket_path: String,
+}
+
+impl SshRemote {
+	/// Parse an `ssh://` URL
+	pub fn parse(remote: &str) -> Result<SshRemote, Error> {
+		let url = Url::parse(remote).map_err(|e| ssh_error(format!("{}: {}", remote, e)))?;
+		if url.scheme() != "ssh" {
+			return Err(ssh_error(format!("{} isn't an ssh:// URL", remote)));
+		}
+		let host = url
+			.host_str()
+			.ok_or_else(|| ssh_error(format!("no host in {}", remote)))?;
+		if url.path().len() <= 1 {
+			return Err(ssh_error(format!(
+				"{} doesn't give the path of the wallet's socket",
+				remote
+			)));
+		}
+		Ok(SshRemote {
+			user: match url.username() {
+				"" => None,
+				u => Some(u.to_owned()),
+			},
+			host: host.to_owned(),
+			port: url.port(),
+			socket_path: url.path().to_owned(),
+		})
+	}
+
+	/// Destination as given to the ssh command
+	fn destination(&self) -> String {
+		match self.user.as_ref() {
+			Some(u) => format!("{}@{}", u, self.host),
+			None => self.host.clone(),
+		}
+	}
+}
+
+/// An SSH connection forwarding a local socket, only accessible to the
+/// wallet's user, to the remote wallet's socket. The connection is closed
+/// when the tunnel is dropped
+pub struct SshTunnel {
+	ssh: Child,
+	local_socket: PathBuf,
+}
+
+impl SshTunnel {
+	/// Connect to the remote host and forward a local socket to the wallet's,
+	/// waiting for the forwarding to be in place
+	pub fn open(remote: &SshRemote) -> Result<SshTunnel, Error> {
+		let local_socket = env::temp_dir().join(format!("grin-wallet-ssh-{}.sock", process::id()));
+		let _ = fs::remove_file(&local_socket);
+		let mut cmd = Command::new("ssh");
+		cmd.arg("-N")
+			.arg("-o")
+			.arg("ExitOnForwardFailure=yes")
+			.arg("-o")
+			.arg("StreamLocalBindMask=0177")
+			.arg("-L")
+			.arg(format!(
+				"{}:{}",
+				local_socket.to_string_lossy(),
+				remote.socket_path
+			));
+		if let Some(p) = remote.port {
+			cmd.arg("-p").arg(p.to_string());
+		}
+		cmd.arg(remote.destination());
+		let ssh = cmd
+			.stdin(Stdio::null())
+			.spawn()
+			.map_err(|e| ssh_error(format!("unable to run ssh: {}", e)))?;
+		let mut tunnel = SshTunnel { ssh, local_socket };
+
+		let start = Instant::now();
+		while UnixStream::connect(&tunnel.local_socket).is_err() {
+			if let Ok(Some(status)) = tunnel.ssh.try_wait() {
+				return Err(ssh_error(format!("ssh exited with {}", status)));
+			}
+			if start.elapsed() > CONNECT_TIMEOUT {
+				return Err(ssh_error(format!(
+					"no connection to {} after {}s",
+					remote.host,
+					CONNECT_TIMEOUT.as_secs()
+				)));
+			}
+			thread::sleep(Duration::from_millis(100));
+		}
+		Ok(tunnel)
+	}
+
+	/// POST the JSON-RPC request to the API at `path`, such as `/v3/owner`,
+	/// returning the response
+	pub fn call(
+		&self,
+		path: &str,
+		api_secret: Option<String>,
+		req: &Value,
+	) -> Result<Value, Error> {
+		let body = serde_json::to_vec(req).map_err(ssh_error)?;
+		let mut head = format!(
+			"POST {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\
+			 Content-Type: application/json\r\nContent-Length: {}\r\n",
+			path,
+			body.len()
+		);
+		if let Some(secret) = api_secret {
+			head.push_str(&format!(
+				"Authorization: Basic {}\r\n",
+				to_base64(&format!("grin:{}", secret))
+			));
+		}
+		head.push_str("\r\n");
+
+		let mut stream = UnixStream::connect(&self.local_socket).map_err(ssh_error)?;
+		let mut response = vec![];
+		stream
+			.write_all(head.as_bytes())
+			.and_then(|_| stream.write_all(&body))
+			.and_then(|_| stream.read_to_end(&mut response))
+			.map_err(ssh_error)?;
+		let (status, body) =
+			parse_response(&response).ok_or_else(|| ssh_error("malformed response"))?;
+		if !(200..300).contains(&status) {
+			return Err(ssh_error(format!(
+				"wallet answered with status {}: {}",
+				status, body
+			)));
+		}
+		serde_json::from_str(&body).map_err(|e| ssh_error(format!("invalid response: {}", e)))
+	}
+}
+
+impl Drop for SshTunnel {
+	fn drop(&mut self) {
+		let _ = self.ssh.kill();
+		let _ = self.ssh.wait();
+		let _ = fs::remove_file(&self.local_socket);
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn parses_remote() {
+		let remote =
+			SshRemote::parse("ssh://admin@wallets.example.com:2222/srv/grin/owner.sock").unwrap();
+		assert_eq!(
+			remote,
+			SshRemote {
+				user: Some("admin".to_owned()),
+				host: "wallets.example.com".to_owned(),
+				port: Some(2222),
+				socket_path: "/srv/grin/owner.sock".to_owned(),
+			}
+		);
+		assert_eq!(remote.destination(), "admin@wallets.example.com");
+		let remote = SshRemote::parse("ssh://wallets/owner.sock").unwrap();
+		assert_eq!(remote.destination(), "wallets");
+		assert_eq!(remote.port, None);
+
+		assert!(SshRemote::parse("http://wallets/owner.sock").is_err());
+		assert!(SshRemote::parse("ssh://wallets").is_err());
+	}
+}
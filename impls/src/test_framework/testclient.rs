@@ -20,7 +20,7 @@ use crate::api;
 use crate::chain::types::NoopAdapter;
 use crate::chain::Chain;
 use crate::core::core::verifier_cache::LruVerifierCache;
-use crate::core::core::Transaction;
+use crate::core::core::{Transaction, TxKernel};
 use crate::core::global::{set_mining_mode, ChainTypes};
 use crate::core::{pow, ser};
 use crate::keychain::Keychain;
@@ -148,6 +148,7 @@ where
 				"get_chain_height" => self.get_chain_height(m)?,
 				"get_outputs_from_node" => self.get_outputs_from_node(m)?,
 				"get_outputs_by_pmmr_index" => self.get_outputs_by_pmmr_index(m)?,
+				"get_kernel" => self.get_kernel(m)?,
 				"send_tx_slate" => self.send_tx_slate(m)?,
 				"post_tx" => self.post_tx(m)?,
 				_ => panic!("Unknown Wallet Proxy Message"),
@@ -217,7 +218,6 @@ where
 		let mut slate = serde_json::from_str(&m.body).context(
 			libwallet::ErrorKind::ClientCallback("Error parsing TxWrapper".to_owned()),
 		)?;
-;
 		{
 			let mut w_lock = wallet.1.lock();
 			let w = w_lock.lc_provider()?.wallet_inst()?;
@@ -291,6 +291,32 @@ where
 			body: serde_json::to_string(&ol).unwrap(),
 		})
 	}
+
+	/// look up a kernel by excess, between optional heights
+	fn get_kernel(
+		&mut self,
+		m: WalletProxyMessage,
+	) -> Result<WalletProxyMessage, libwallet::Error> {
+		let split = m.body.split(",").collect::<Vec<&str>>();
+		let excess = Commitment::from_vec(util::from_hex(split[0].to_owned()).unwrap());
+		let min_height = split[1].parse::<u64>().ok();
+		let max_height = split[2].parse::<u64>().ok();
+		let kernel = self
+			.chain
+			.get_kernel_height(&excess, min_height, max_height)
+			.unwrap()
+			.map(|(tx_kernel, height, mmr_index)| api::LocatedTxKernel {
+				tx_kernel,
+				height,
+				mmr_index,
+			});
+		Ok(WalletProxyMessage {
+			sender_id: "node".to_owned(),
+			dest: m.sender_id,
+			method: m.method,
+			body: serde_json::to_string(&kernel).unwrap(),
+		})
+	}
 }
 
 #[derive(Clone)]
@@ -408,6 +434,40 @@ impl NodeClient for LocalWalletClient {
 			))?)
 	}
 
+	/// Look up a kernel on chain by its excess
+	fn get_kernel(
+		&self,
+		excess: &pedersen::Commitment,
+		min_height: Option<u64>,
+		max_height: Option<u64>,
+	) -> Result<Option<(TxKernel, u64, u64)>, libwallet::Error> {
+		let height_str = |h: Option<u64>| h.map(|h| h.to_string()).unwrap_or_default();
+		let query_str = format!(
+			"{},{},{}",
+			util::to_hex(excess.0.to_vec()),
+			height_str(min_height),
+			height_str(max_height)
+		);
+		let m = WalletProxyMessage {
+			sender_id: self.id.clone(),
+			dest: self.node_url().to_owned(),
+			method: "get_kernel".to_owned(),
+			body: query_str,
+		};
+		{
+			let p = self.proxy_tx.lock();
+			p.send(m).context(libwallet::ErrorKind::ClientCallback(
+				"Get kernel send".to_owned(),
+			))?;
+		}
+		let r = self.rx.lock();
+		let m = r.recv().unwrap();
+		let kernel: Option<api::LocatedTxKernel> = serde_json::from_str(&m.body).context(
+			libwallet::ErrorKind::ClientCallback("Parsing get_kernel response".to_owned()),
+		)?;
+		Ok(kernel.map(|k| (k.tx_kernel, k.height, k.mmr_index)))
+	}
+
 	/// Retrieve outputs from node
 	fn get_outputs_from_node(
 		&self,
@@ -463,6 +523,9 @@ impl NodeClient for LocalWalletClient {
 			method: "get_outputs_by_pmmr_index".to_owned(),
 			body: query_str,
 		};
+		// restore scans the UTXO set from several threads, so hold our queue
+		// until the response to this request has been received
+		let r = self.rx.lock();
 		{
 			let p = self.proxy_tx.lock();
 			p.send(m).context(libwallet::ErrorKind::ClientCallback(
@@ -470,7 +533,6 @@ impl NodeClient for LocalWalletClient {
 			))?;
 		}
 
-		let r = self.rx.lock();
 		let m = r.recv().unwrap();
 		let o: api::OutputListing = serde_json::from_str(&m.body).unwrap();
 
@@ -0,0 +1,548 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Dispatches notifications of transaction lifecycle events to
+//! configured webhook endpoints. Payloads are sent as canonical JSON, signed
+//! for endpoints with a secret, and retried with backoff when delivery fails.
+//! Events that still can't be delivered are kept in a dead-letter file, so
+//! none are lost without a trace. The same events can also be published on a ZeroMQ socket.
+//! Each consumer can filter the events it's sent by type, account and amount
+
+use chrono::prelude::*;
+use hyper::{Body, Request};
+use ring::{digest, hmac};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::api;
+use crate::config::{EventFilter, WebhookConfig, WebhookEventType};
+use crate::libwallet::{to_canonical_json, AcctPathMapping, Error, ErrorKind, TxLogEntry};
+use crate::util::{to_hex, Mutex};
+use crate::zmq::ZmqPublisher;
+
+/// Header giving the HMAC-SHA256 of the request body, keyed with the
+/// endpoint's secret, as `sha256=<hex>`
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X-Grin-Signature";
+/// Attempts made to deliver an event before it's dead-lettered, unless the
+/// endpoint sets its own number
+const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+/// Delay before the first retry, doubled for each one after
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+/// Longest delay between retries
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+/// JSON payload POSTed to webhook endpoints
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WebhookPayload {
+	/// The event that occurred
+	pub event: WebhookEventType,
+	/// Time at which the event was dispatched
+	pub timestamp: DateTime<Utc>,
+	/// Slate id of the transaction concerned, if known
+	pub tx_slate_id: Option<Uuid>,
+	/// The wallet's log entry for the transaction concerned, if found
+	pub tx: Option<TxLogEntry>,
+	/// The wallet's new height, for height updates
+	#[serde(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub height: Option<u64>,
+	/// Label of the account the transaction concerned is in, if known
+	#[serde(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub account: Option<String>,
+	/// Id of the hosted wallet the event occurred in, if not the listener's
+	/// own wallet
+	#[serde(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub wallet_id: Option<String>,
+}
+
+impl WebhookPayload {
+	/// Create a payload for the given event and transaction
+	pub fn new(
+		event: WebhookEventType,
+		tx_slate_id: Option<Uuid>,
+		tx: Option<TxLogEntry>,
+	) -> WebhookPayload {
+		WebhookPayload {
+			event,
+			timestamp: Utc::now(),
+			tx_slate_id: tx_slate_id.or(tx.as_ref().and_then(|t| t.tx_slate_id)),
+			tx,
+			height: None,
+			account: None,
+			wallet_id: None,
+		}
+	}
+
+	/// Label the payload with the account its transaction is in, found among
+	/// the given accounts
+	pub fn with_account<I>(mut self, accounts: I) -> WebhookPayload
+	where
+		I: IntoIterator<Item = AcctPathMapping>,
+	{
+		if let Some(t) = self.tx.as_ref() {
+			self.account = accounts
+				.into_iter()
+				.find(|a| a.path == t.parent_key_id)
+				.map(|a| a.label);
+		}
+		self
+	}
+
+	/// Amount moved by the transaction concerned, if known
+	fn amount(&self) -> Option<u64> {
+		self.tx.as_ref().map(|t| {
+			if t.amount_credited > t.amount_debited {
+				t.amount_credited - t.amount_debited
+			} else {
+				t.amount_debited - t.amount_credited
+			}
+		})
+	}
+
+	/// Create a payload for the wallet reaching the given height
+	pub fn height_updated(height: u64) -> WebhookPayload {
+		WebhookPayload {
+			height: Some(height),
+			..WebhookPayload::new(WebhookEventType::HeightUpdated, None, None)
+		}
+	}
+}
+
+/// An event that couldn't be delivered to a webhook
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WebhookDeadLetter {
+	/// URL of the webhook
+	pub url: String,
+	/// The undelivered payload
+	pub payload: WebhookPayload,
+	/// Number of delivery attempts made
+	pub attempts: u32,
+	/// Error from the last attempt
+	pub error: String,
+	/// Time of the last attempt
+	pub failed_at: DateTime<Utc>,
+}
+
+/// Store of undelivered events, kept as one JSON object per line in a file
+#[derive(Debug)]
+pub struct WebhookDeadLetters {
+	path: PathBuf,
+	lock: Mutex<()>,
+}
+
+impl WebhookDeadLetters {
+	/// Create a store keeping its events in the given file
+	pub fn new<P: Into<PathBuf>>(path: P) -> WebhookDeadLetters {
+		WebhookDeadLetters {
+			path: path.into(),
+			lock: Mutex::new(()),
+		}
+	}
+
+	fn error(&self, e: impl ToString) -> Error {
+		ErrorKind::GenericError(format!(
+			"Webhook dead letters in {}: {}",
+			self.path.display(),
+			e.to_string()
+		))
+		.into()
+	}
+
+	/// Add an undelivered event
+	pub fn push(&self, letter: &WebhookDeadLetter) -> Result<(), Error> {
+		let line = serde_json::to_string(letter).map_err(|e| self.error(e))?;
+		let _l = self.lock.lock();
+		let mut file = OpenOptions::new()
+			.create(true)
+			.append(true)
+			.open(&self.path)
+			.map_err(|e| self.error(e))?;
+		writeln!(file, "{}", line).map_err(|e| self.error(e))
+	}
+
+	/// All undelivered events, oldest first
+	pub fn list(&self) -> Result<Vec<WebhookDeadLetter>, Error> {
+		let _l = self.lock.lock();
+		if !self.path.exists() {
+			return Ok(vec![]);
+		}
+		let data = fs::read_to_string(&self.path).map_err(|e| self.error(e))?;
+		data.lines()
+			.filter(|l| !l.trim().is_empty())
+			.map(|l| serde_json::from_str(l).map_err(|e| self.error(e)))
+			.collect()
+	}
+}
+
+/// Signature of the body for the signature header
+fn signature(secret: &str, body: &[u8]) -> String {
+	let key = hmac::SigningKey::new(&digest::SHA256, secret.as_bytes());
+	format!(
+		"sha256={}",
+		to_hex(hmac::sign(&key, body).as_ref().to_vec())
+	)
+}
+
+/// The body sent for the payload: its canonical JSON, so consumers can check
+/// the signature over the payload however they serialize it again
+fn payload_body(payload: &WebhookPayload) -> Result<Vec<u8>, Error> {
+	Ok(to_canonical_json(payload)?.into_bytes())
+}
+
+/// POST the JSON body to the webhook, signed if it has a secret
+fn post(hook: &WebhookConfig, body: &[u8]) -> Result<(), String> {
+	let mut req = Request::builder();
+	req.method("POST")
+		.uri(hook.url.as_str())
+		.header("content-type", "application/json");
+	if let Some(secret) = hook.secret.as_ref() {
+		req.header(WEBHOOK_SIGNATURE_HEADER, signature(secret, body).as_str());
+	}
+	let req = req
+		.body(Body::from(body.to_vec()))
+		.map_err(|e| e.to_string())?;
+	api::client::send_request(req)
+		.map(|_| ())
+		.map_err(|e| e.to_string())
+}
+
+/// Deliver the payload to the webhook, retrying with a doubling delay until
+/// its attempts run out, then dead-lettering it
+fn deliver(
+	hook: &WebhookConfig,
+	payload: WebhookPayload,
+	retry_delay: Duration,
+	dead_letters: Option<&WebhookDeadLetters>,
+) {
+	let body = match payload_body(&payload) {
+		Ok(b) => b,
+		Err(e) => {
+			error!("Failed to serialize {:?} event: {}", payload.event, e);
+			return;
+		}
+	};
+	let max_attempts = hook.max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS).max(1);
+	let mut delay = retry_delay;
+	let mut attempts = 0;
+	loop {
+		attempts += 1;
+		debug!(
+			"Posting {:?} event to webhook {} (attempt {})",
+			payload.event, hook.url, attempts
+		);
+		let e = match post(hook, &body) {
+			Ok(()) => return,
+			Err(e) => e,
+		};
+		if attempts < max_attempts {
+			warn!(
+				"Failed to post {:?} event to webhook {}, retrying in {}s: {}",
+				payload.event,
+				hook.url,
+				delay.as_secs(),
+				e
+			);
+			thread::sleep(delay);
+			delay = (delay * 2).min(MAX_RETRY_DELAY);
+			continue;
+		}
+		error!(
+			"Failed to post {:?} event to webhook {} after {} attempts: {}",
+			payload.event, hook.url, attempts, e
+		);
+		if let Some(d) = dead_letters {
+			let letter = WebhookDeadLetter {
+				url: hook.url.clone(),
+				payload,
+				attempts,
+				error: e,
+				failed_at: Utc::now(),
+			};
+			if let Err(e) = d.push(&letter) {
+				error!("Failed to dead-letter webhook event: {}", e);
+			}
+		}
+		return;
+	}
+}
+
+/// Whether the payload passes the filter. Events that aren't about a
+/// transaction pass the account and amount filters
+fn matches(filter: &EventFilter, payload: &WebhookPayload) -> bool {
+	if !filter.events.is_empty() && !filter.events.contains(&payload.event) {
+		return false;
+	}
+	if payload.event == WebhookEventType::HeightUpdated {
+		return true;
+	}
+	let account_matches = filter.accounts.is_empty()
+		|| payload
+			.account
+			.as_ref()
+			.map(|a| filter.accounts.contains(a))
+			.unwrap_or(false);
+	let amount_matches = match filter.min_amount {
+		Some(min) => payload.amount().map(|a| a >= min).unwrap_or(false),
+		None => true,
+	};
+	account_matches && amount_matches
+}
+
+/// Posts event payloads to each webhook whose filter they pass, and
+/// publishes them on the ZeroMQ socket if there's one
+#[derive(Clone, Debug)]
+pub struct WebhookDispatcher {
+	hooks: Vec<(WebhookConfig, EventFilter)>,
+	dead_letters: Option<Arc<WebhookDeadLetters>>,
+	retry_delay: Duration,
+	publisher: Option<(Arc<ZmqPublisher>, EventFilter)>,
+	last_height: Arc<AtomicU64>,
+	wallet_id: Option<String>,
+}
+
+impl WebhookDispatcher {
+	/// Create a dispatcher for the given webhooks
+	pub fn new(hooks: Vec<WebhookConfig>) -> WebhookDispatcher {
+		WebhookDispatcher {
+			hooks: hooks
+				.into_iter()
+				.map(|h| {
+					let filter = h.filter();
+					(h, filter)
+				})
+				.collect(),
+			dead_letters: None,
+			retry_delay: RETRY_DELAY,
+			publisher: None,
+			last_height: Arc::new(AtomicU64::new(0)),
+			wallet_id: None,
+		}
+	}
+
+	/// Also publish the events passing the filter on the given ZeroMQ socket,
+	/// with the event's name as topic
+	pub fn with_publisher(
+		mut self,
+		publisher: ZmqPublisher,
+		filter: EventFilter,
+	) -> WebhookDispatcher {
+		self.publisher = Some((Arc::new(publisher), filter));
+		self
+	}
+
+	/// Keep events that can't be delivered in the given file, rather than
+	/// only logging them
+	pub fn with_dead_letters<P: Into<PathBuf>>(mut self, path: P) -> WebhookDispatcher {
+		self.dead_letters = Some(Arc::new(WebhookDeadLetters::new(path)));
+		self
+	}
+
+	/// Dispatch the events of the hosted wallet with the given id, each
+	/// payload carrying the id. Its height is followed apart from the other
+	/// wallets'
+	pub fn with_wallet_id(mut self, wallet_id: String) -> WebhookDispatcher {
+		self.wallet_id = Some(wallet_id);
+		self.last_height = Arc::new(AtomicU64::new(0));
+		self
+	}
+
+	/// Events that couldn't be delivered, oldest first. Empty if they aren't
+	/// being kept
+	pub fn dead_letters(&self) -> Result<Vec<WebhookDeadLetter>, Error> {
+		match self.dead_letters.as_ref() {
+			Some(d) => d.list(),
+			None => Ok(vec![]),
+		}
+	}
+
+	/// Whether any webhook is subscribed to the given event, or the event is
+	/// to be published
+	pub fn wants(&self, event: WebhookEventType) -> bool {
+		self.publisher
+			.iter()
+			.map(|(_, f)| f)
+			.chain(self.hooks.iter().map(|(_, f)| f))
+			.any(|f| f.events.is_empty() || f.events.contains(&event))
+	}
+
+	/// Dispatch a height update if the height is above the last one seen
+	pub fn notify_height(&self, height: u64) {
+		if self.last_height.fetch_max(height, Ordering::Relaxed) < height {
+			self.dispatch(WebhookPayload::height_updated(height));
+		}
+	}
+
+	/// Post the payload to all webhooks whose filter it passes. Each post,
+	/// along with its retries, is made on its own thread, so callers are never
+	/// held up by a slow or failing endpoint
+	pub fn dispatch(&self, mut payload: WebhookPayload) {
+		if payload.wallet_id.is_none() {
+			payload.wallet_id = self.wallet_id.clone();
+		}
+		if let Some((p, filter)) = self.publisher.as_ref() {
+			if matches(filter, &payload) {
+				match payload_body(&payload) {
+					Ok(body) => p.publish(&format!("{:?}", payload.event), body),
+					Err(e) => error!("Failed to serialize {:?} event: {}", payload.event, e),
+				}
+			}
+		}
+		for (hook, filter) in self.hooks.iter() {
+			if !matches(filter, &payload) {
+				continue;
+			}
+			let hook = hook.clone();
+			let payload = payload.clone();
+			let retry_delay = self.retry_delay;
+			let dead_letters = self.dead_letters.clone();
+			let _ = thread::Builder::new()
+				.name("webhook".to_string())
+				.spawn(move || {
+					deliver(
+						&hook,
+						payload,
+						retry_delay,
+						dead_letters.as_ref().map(|d| &**d),
+					)
+				});
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::keychain::{ExtKeychain, Keychain};
+	use crate::libwallet::TxLogEntryType;
+
+	#[test]
+	fn webhook_signature() {
+		// example from RFC 4231, test case 2
+		assert_eq!(
+			signature("Jefe", b"what do ya want for nothing?"),
+			"sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+		);
+	}
+
+	#[test]
+	fn webhook_body_is_canonical() {
+		let body = payload_body(&WebhookPayload::height_updated(5)).unwrap();
+		let body = String::from_utf8(body).unwrap();
+		assert!(body.starts_with("{\"event\":"));
+		assert!(body.contains(",\"height\":5,\"timestamp\":"));
+		assert!(!body.contains(' '));
+		assert!(!body.contains("wallet_id"));
+
+		let payload = WebhookPayload {
+			wallet_id: Some("shop".to_owned()),
+			..WebhookPayload::height_updated(5)
+		};
+		let body = String::from_utf8(payload_body(&payload).unwrap()).unwrap();
+		assert!(body.ends_with(",\"wallet_id\":\"shop\"}"));
+	}
+
+	#[test]
+	fn dead_letter_undelivered_event() {
+		let path = std::env::temp_dir().join(format!("dead_letters_{}.json", Uuid::new_v4()));
+		let dead_letters = WebhookDeadLetters::new(&path);
+		assert!(dead_letters.list().unwrap().is_empty());
+
+		// nothing listens on port 1
+		let hook = WebhookConfig {
+			url: "http://127.0.0.1:1/hook".to_owned(),
+			events: vec![],
+			secret: Some("secret".to_owned()),
+			max_attempts: Some(2),
+			accounts: vec![],
+			min_amount: None,
+		};
+		for event in &[WebhookEventType::TxCancelled, WebhookEventType::TxConfirmed] {
+			let payload = WebhookPayload::new(*event, None, None);
+			deliver(
+				&hook,
+				payload,
+				Duration::from_millis(1),
+				Some(&dead_letters),
+			);
+		}
+
+		let letters = dead_letters.list().unwrap();
+		assert_eq!(letters.len(), 2);
+		assert_eq!(letters[0].url, hook.url);
+		assert_eq!(letters[0].attempts, 2);
+		assert_eq!(letters[0].payload.event, WebhookEventType::TxCancelled);
+		assert_eq!(letters[1].payload.event, WebhookEventType::TxConfirmed);
+		let _ = fs::remove_file(&path);
+	}
+
+	#[test]
+	fn filter_events() {
+		let accounts = vec![
+			AcctPathMapping {
+				label: "default".to_owned(),
+				path: ExtKeychain::derive_key_id(2, 0, 0, 0, 0),
+			},
+			AcctPathMapping {
+				label: "savings".to_owned(),
+				path: ExtKeychain::derive_key_id(2, 1, 0, 0, 0),
+			},
+		];
+		let mut tx = TxLogEntry::new(accounts[1].path.clone(), TxLogEntryType::TxSent, 1);
+		tx.amount_credited = 1_000;
+		tx.amount_debited = 5_000;
+		let payload = WebhookPayload::new(WebhookEventType::TxConfirmed, None, Some(tx))
+			.with_account(accounts);
+		assert_eq!(payload.account, Some("savings".to_owned()));
+
+		let filter = EventFilter::default();
+		assert!(matches(&filter, &payload));
+		let filter = EventFilter {
+			events: vec![WebhookEventType::TxReceived],
+			..Default::default()
+		};
+		assert!(!matches(&filter, &payload));
+		let filter = EventFilter {
+			accounts: vec!["default".to_owned()],
+			..Default::default()
+		};
+		assert!(!matches(&filter, &payload));
+		let filter = EventFilter {
+			events: vec![
+				WebhookEventType::TxConfirmed,
+				WebhookEventType::HeightUpdated,
+			],
+			accounts: vec!["savings".to_owned()],
+			min_amount: Some(4_000),
+		};
+		assert!(matches(&filter, &payload));
+		let filter = EventFilter {
+			min_amount: Some(4_001),
+			..filter
+		};
+		assert!(!matches(&filter, &payload));
+
+		// height updates aren't about any account or amount
+		assert!(matches(&filter, &WebhookPayload::height_updated(10)));
+		let payload = WebhookPayload::new(WebhookEventType::TxCancelled, None, None);
+		assert!(!matches(&filter, &payload));
+	}
+}
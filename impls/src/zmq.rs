@@ -0,0 +1,380 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Publishes wallet events on a ZeroMQ PUB socket, the way bitcoind does with
+//! its `zmqpub` options. Only the parts of ZMTP 3.0 a publisher needs are
+//! implemented (the NULL security mechanism, and subscriptions sent as
+//! messages), so any ZeroMQ SUB socket can connect without the wallet linking
+//! libzmq. Each event is a message of three frames: the topic, the JSON
+//! payload and a 4 byte little-endian sequence number, counted per topic
+
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::libwallet::{Error, ErrorKind};
+use crate::util::Mutex;
+
+/// More frames of the message follow
+const FLAG_MORE: u8 = 0x01;
+/// The frame size takes 8 bytes rather than 1
+const FLAG_LONG: u8 = 0x02;
+/// The frame is a command rather than part of a message
+const FLAG_COMMAND: u8 = 0x04;
+/// Time a subscriber has to take a message before it's disconnected
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Time a subscriber has to complete the handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Largest frame accepted from a subscriber, which only sends subscriptions
+const MAX_FRAME_LEN: u64 = 4096;
+
+/// A connected subscriber
+struct Subscriber {
+	stream: TcpStream,
+	/// Topic prefixes subscribed to
+	topics: Arc<Mutex<Vec<Vec<u8>>>>,
+	/// Cleared once the subscriber disconnects
+	open: Arc<AtomicBool>,
+}
+
+impl Subscriber {
+	fn wants(&self, topic: &[u8]) -> bool {
+		self.topics.lock().iter().any(|t| topic.starts_with(t))
+	}
+}
+
+/// Publisher of events to ZeroMQ subscribers. Messages are written by a
+/// thread of their own, so publishing never waits on a subscriber
+#[derive(Debug)]
+pub struct ZmqPublisher {
+	local_addr: SocketAddr,
+	sender: Mutex<Sender<(String, Vec<u8>)>>,
+}
+
+impl ZmqPublisher {
+	/// Listen for subscribers on the given address, either as
+	/// `tcp://host:port` or `host:port`
+	pub fn bind(address: &str) -> Result<ZmqPublisher, Error> {
+		let error = |e: io::Error| -> Error {
+			ErrorKind::GenericError(format!("ZeroMQ publisher on {}: {}", address, e)).into()
+		};
+		let listener = TcpListener::bind(address.trim_start_matches("tcp://")).map_err(error)?;
+		let local_addr = listener.local_addr().map_err(error)?;
+		let subscribers = Arc::new(Mutex::new(vec![]));
+
+		let s = subscribers.clone();
+		thread::Builder::new()
+			.name("zmq_accept".to_string())
+			.spawn(move || accept(listener, s))
+			.map_err(error)?;
+		let (sender, receiver) = channel();
+		thread::Builder::new()
+			.name("zmq_publish".to_string())
+			.spawn(move || publish(receiver, subscribers))
+			.map_err(error)?;
+		info!("Publishing wallet events over ZeroMQ on {}", local_addr);
+		Ok(ZmqPublisher {
+			local_addr,
+			sender: Mutex::new(sender),
+		})
+	}
+
+	/// The address subscribers connect to
+	pub fn local_addr(&self) -> SocketAddr {
+		self.local_addr
+	}
+
+	/// Publish a message on the given topic to the subscribers of it
+	pub fn publish(&self, topic: &str, body: Vec<u8>) {
+		let _ = self.sender.lock().send((topic.to_owned(), body));
+	}
+}
+
+fn invalid(msg: &str) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Append a frame with the given flags to the buffer
+fn write_frame(buf: &mut Vec<u8>, flags: u8, body: &[u8]) {
+	if body.len() > 255 {
+		buf.push(flags | FLAG_LONG);
+		buf.extend_from_slice(&(body.len() as u64).to_be_bytes());
+	} else {
+		buf.push(flags);
+		buf.push(body.len() as u8);
+	}
+	buf.extend_from_slice(body);
+}
+
+/// Read a frame, returning its flags and body
+fn read_frame<R: Read>(stream: &mut R) -> io::Result<(u8, Vec<u8>)> {
+	let mut flags = [0u8; 1];
+	stream.read_exact(&mut flags)?;
+	let len = match flags[0] & FLAG_LONG {
+		0 => {
+			let mut len = [0u8; 1];
+			stream.read_exact(&mut len)?;
+			len[0] as u64
+		}
+		_ => {
+			let mut len = [0u8; 8];
+			stream.read_exact(&mut len)?;
+			u64::from_be_bytes(len)
+		}
+	};
+	if len > MAX_FRAME_LEN {
+		return Err(invalid("frame too long"));
+	}
+	let mut body = vec![0u8; len as usize];
+	stream.read_exact(&mut body)?;
+	Ok((flags[0], body))
+}
+
+/// ZMTP 3.0 greeting, offering the NULL security mechanism
+fn greeting() -> [u8; 64] {
+	let mut greeting = [0u8; 64];
+	greeting[0] = 0xff;
+	greeting[9] = 0x7f;
+	greeting[10] = 3;
+	greeting[12..16].copy_from_slice(b"NULL");
+	greeting
+}
+
+/// READY command, completing the NULL handshake as a PUB socket
+fn ready() -> Vec<u8> {
+	let mut body = vec![5];
+	body.extend_from_slice(b"READY");
+	body.push(11);
+	body.extend_from_slice(b"Socket-Type");
+	body.extend_from_slice(&3u32.to_be_bytes());
+	body.extend_from_slice(b"PUB");
+	let mut frame = vec![];
+	write_frame(&mut frame, FLAG_COMMAND, &body);
+	frame
+}
+
+/// The socket type given in the properties of a READY command
+fn socket_type(ready: &[u8]) -> Option<&[u8]> {
+	let mut props = &ready[6..];
+	while !props.is_empty() {
+		let name_len = props[0] as usize;
+		if props.len() < 1 + name_len + 4 {
+			return None;
+		}
+		let name = &props[1..1 + name_len];
+		let mut len = [0u8; 4];
+		len.copy_from_slice(&props[1 + name_len..5 + name_len]);
+		let value_len = u32::from_be_bytes(len) as usize;
+		let rest = &props[5 + name_len..];
+		if rest.len() < value_len {
+			return None;
+		}
+		if name.eq_ignore_ascii_case(b"Socket-Type") {
+			return Some(&rest[..value_len]);
+		}
+		props = &rest[value_len..];
+	}
+	None
+}
+
+/// Exchange greetings and READY commands with a new subscriber
+fn handshake(stream: &mut TcpStream) -> io::Result<()> {
+	stream.write_all(&greeting())?;
+	let mut peer = [0u8; 64];
+	stream.read_exact(&mut peer)?;
+	if peer[0] != 0xff || peer[9] & 0x01 == 0 || peer[10] < 3 || &peer[12..16] != b"NULL" {
+		return Err(invalid(
+			"unsupported greeting, ZMTP 3 with NULL security is needed",
+		));
+	}
+	stream.write_all(&ready())?;
+	let (flags, body) = read_frame(stream)?;
+	if flags & FLAG_COMMAND == 0 || !body.starts_with(b"\x05READY") {
+		return Err(invalid("expected a READY command"));
+	}
+	match socket_type(&body) {
+		Some(b"SUB") | Some(b"XSUB") => Ok(()),
+		_ => Err(invalid("only SUB and XSUB sockets can connect")),
+	}
+}
+
+/// Accept subscribers, each handshaking on a thread of its own
+fn accept(listener: TcpListener, subscribers: Arc<Mutex<Vec<Subscriber>>>) {
+	for stream in listener.incoming() {
+		let mut stream = match stream {
+			Ok(s) => s,
+			Err(e) => {
+				warn!("ZeroMQ publisher failed to accept a subscriber: {}", e);
+				continue;
+			}
+		};
+		let subscribers = subscribers.clone();
+		let _ = thread::Builder::new()
+			.name("zmq_subscriber".to_string())
+			.spawn(move || {
+				let _ = stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT));
+				if let Err(e) = handshake(&mut stream) {
+					debug!("ZeroMQ subscriber handshake failed: {}", e);
+					let _ = stream.shutdown(Shutdown::Both);
+					return;
+				}
+				let _ = stream.set_read_timeout(None);
+				let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+				let writer = match stream.try_clone() {
+					Ok(s) => s,
+					Err(_) => return,
+				};
+				let topics = Arc::new(Mutex::new(vec![]));
+				let open = Arc::new(AtomicBool::new(true));
+				subscribers.lock().push(Subscriber {
+					stream: writer,
+					topics: topics.clone(),
+					open: open.clone(),
+				});
+				read_subscriptions(stream, &topics);
+				open.store(false, Ordering::Relaxed);
+			});
+	}
+}
+
+/// Keep track of the topics a subscriber subscribes to, until it disconnects
+fn read_subscriptions(mut stream: TcpStream, topics: &Mutex<Vec<Vec<u8>>>) {
+	while let Ok((flags, body)) = read_frame(&mut stream) {
+		let (subscribe, topic) = if flags & FLAG_COMMAND != 0 {
+			// ZMTP 3.1 peers may send commands rather than messages
+			if body.starts_with(b"\x09SUBSCRIBE") {
+				(true, &body[10..])
+			} else if body.starts_with(b"\x06CANCEL") {
+				(false, &body[7..])
+			} else {
+				continue;
+			}
+		} else {
+			match body.split_first() {
+				Some((&1, topic)) => (true, topic),
+				Some((&0, topic)) => (false, topic),
+				_ => continue,
+			}
+		};
+		let mut topics = topics.lock();
+		if subscribe {
+			topics.push(topic.to_vec());
+		} else if let Some(i) = topics.iter().position(|t| t.as_slice() == topic) {
+			topics.remove(i);
+		}
+	}
+	let _ = stream.shutdown(Shutdown::Both);
+}
+
+/// Write each message to the subscribers of its topic, dropping any that
+/// have gone away or can't keep up
+fn publish(receiver: Receiver<(String, Vec<u8>)>, subscribers: Arc<Mutex<Vec<Subscriber>>>) {
+	let mut sequences: HashMap<String, u32> = HashMap::new();
+	for (topic, body) in receiver.iter() {
+		let sequence = sequences.entry(topic.clone()).or_insert(0);
+		let mut message = vec![];
+		write_frame(&mut message, FLAG_MORE, topic.as_bytes());
+		write_frame(&mut message, FLAG_MORE, &body);
+		write_frame(&mut message, 0, &sequence.to_le_bytes());
+		*sequence = sequence.wrapping_add(1);
+
+		subscribers.lock().retain(|s| {
+			if !s.open.load(Ordering::Relaxed) {
+				return false;
+			}
+			if !s.wants(topic.as_bytes()) {
+				return true;
+			}
+			match (&s.stream).write_all(&message) {
+				Ok(_) => true,
+				Err(e) => {
+					debug!("Dropping ZeroMQ subscriber: {}", e);
+					let _ = s.stream.shutdown(Shutdown::Both);
+					false
+				}
+			}
+		});
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// Connect to the publisher as a SUB socket would
+	fn subscribe(addr: SocketAddr, topic: &[u8]) -> TcpStream {
+		let mut stream = TcpStream::connect(addr).unwrap();
+		stream.write_all(&greeting()).unwrap();
+		let mut peer = [0u8; 64];
+		stream.read_exact(&mut peer).unwrap();
+		assert_eq!(&peer[12..16], b"NULL");
+
+		let (flags, body) = read_frame(&mut stream).unwrap();
+		assert_eq!(flags, FLAG_COMMAND);
+		assert_eq!(socket_type(&body), Some(&b"PUB"[..]));
+		let mut body = vec![5];
+		body.extend_from_slice(b"READY");
+		body.push(11);
+		body.extend_from_slice(b"Socket-Type");
+		body.extend_from_slice(&3u32.to_be_bytes());
+		body.extend_from_slice(b"SUB");
+		let mut frames = vec![];
+		write_frame(&mut frames, FLAG_COMMAND, &body);
+		let mut subscription = vec![1];
+		subscription.extend_from_slice(topic);
+		write_frame(&mut frames, 0, &subscription);
+		stream.write_all(&frames).unwrap();
+		stream
+	}
+
+	fn read_message(stream: &mut TcpStream) -> Vec<Vec<u8>> {
+		let mut frames = vec![];
+		loop {
+			let (flags, body) = read_frame(stream).unwrap();
+			frames.push(body);
+			if flags & FLAG_MORE == 0 {
+				return frames;
+			}
+		}
+	}
+
+	#[test]
+	fn publish_to_subscribers() {
+		let publisher = ZmqPublisher::bind("tcp://127.0.0.1:0").unwrap();
+		let mut stream = subscribe(publisher.local_addr(), b"Tx");
+		stream
+			.set_read_timeout(Some(Duration::from_secs(5)))
+			.unwrap();
+		// let the subscription arrive
+		thread::sleep(Duration::from_millis(200));
+
+		publisher.publish("HeightUpdated", b"{}".to_vec());
+		publisher.publish("TxReceived", b"{\"a\":1}".to_vec());
+		publisher.publish("TxReceived", vec![b'x'; 300]);
+
+		// only subscribed topics are published, each counted from 0
+		let message = read_message(&mut stream);
+		assert_eq!(message[0], b"TxReceived".to_vec());
+		assert_eq!(message[1], b"{\"a\":1}".to_vec());
+		assert_eq!(message[2], 0u32.to_le_bytes().to_vec());
+		let message = read_message(&mut stream);
+		assert_eq!(message[1].len(), 300);
+		assert_eq!(message[2], 1u32.to_le_bytes().to_vec());
+	}
+}
@@ -0,0 +1,314 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Accounting period settlement reports, giving the wallet's balance at the
+//! start and end of a period and its movements during it, valued in a fiat
+//! currency at the time of each transaction by a pluggable price oracle
+
+use crate::grin_core::core::amount_to_hr_string;
+use crate::types::{TxLogEntry, TxLogEntryType};
+use crate::Error;
+use chrono::prelude::{DateTime, Utc};
+use std::fmt::Write;
+use uuid::Uuid;
+
+/// Nanogrins per grin
+const GRIN_BASE: f64 = 1_000_000_000.0;
+
+/// Source of historical grin prices
+pub trait PriceOracle {
+	/// Price of one grin in the given fiat currency (e.g. `usd`) at the
+	/// given time
+	fn price_at(&self, currency: &str, time: DateTime<Utc>) -> Result<f64, Error>;
+}
+
+/// Oracle quoting the same price at all times, for testing or for
+/// currencies pegged by the user
+#[derive(Clone, Debug)]
+pub struct FixedPriceOracle {
+	price: f64,
+}
+
+impl FixedPriceOracle {
+	/// Create an oracle always quoting the given price
+	pub fn new(price: f64) -> FixedPriceOracle {
+		FixedPriceOracle { price }
+	}
+}
+
+impl PriceOracle for FixedPriceOracle {
+	fn price_at(&self, _currency: &str, _time: DateTime<Utc>) -> Result<f64, Error> {
+		Ok(self.price)
+	}
+}
+
+/// A confirmed transaction within the period
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SettlementEntry {
+	/// Local id of the transaction
+	pub tx_id: u32,
+	/// Slate id of the transaction, if any
+	pub tx_slate_id: Option<Uuid>,
+	/// Type of the transaction
+	pub tx_type: TxLogEntryType,
+	/// Time the transaction was confirmed, or created if unknown
+	pub time: DateTime<Utc>,
+	/// Amount received, in nanogrins
+	pub receipt: u64,
+	/// Amount paid out, excluding the fee, in nanogrins
+	pub disbursement: u64,
+	/// Fee paid, in nanogrins
+	pub fee: u64,
+	/// Price of one grin at the time of the transaction
+	pub price: f64,
+	/// Fiat value of the receipt
+	pub receipt_fiat: f64,
+	/// Fiat value of the disbursement
+	pub disbursement_fiat: f64,
+	/// Fiat value of the fee
+	pub fee_fiat: f64,
+}
+
+/// Settlement report for an accounting period
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SettlementReport {
+	/// Start of the period (inclusive)
+	pub start: DateTime<Utc>,
+	/// End of the period (exclusive)
+	pub end: DateTime<Utc>,
+	/// Fiat currency values are given in
+	pub currency: String,
+	/// Balance at the start of the period, in nanogrins
+	pub opening_balance: u64,
+	/// Total received during the period, in nanogrins
+	pub receipts: u64,
+	/// Total paid out during the period, excluding fees, in nanogrins
+	pub disbursements: u64,
+	/// Total fees paid during the period, in nanogrins
+	pub fees: u64,
+	/// Balance at the end of the period, in nanogrins
+	pub closing_balance: u64,
+	/// Opening balance valued at the price at the start of the period
+	pub opening_balance_fiat: f64,
+	/// Receipts, each valued at the price at the time it was received
+	pub receipts_fiat: f64,
+	/// Disbursements, each valued at the price at the time it was paid
+	pub disbursements_fiat: f64,
+	/// Fees, each valued at the price at the time it was paid
+	pub fees_fiat: f64,
+	/// Closing balance valued at the price at the end of the period
+	pub closing_balance_fiat: f64,
+	/// The transactions making up the period's movements
+	pub entries: Vec<SettlementEntry>,
+}
+
+/// Movements of a confirmed, uncancelled transaction, as (receipt,
+/// disbursement, fee)
+fn movements(tx: &TxLogEntry) -> Option<(u64, u64, u64)> {
+	if !tx.confirmed {
+		return None;
+	}
+	match tx.tx_type {
+		TxLogEntryType::TxReceivedCancelled | TxLogEntryType::TxSentCancelled => None,
+		_ => {
+			let fee = tx.fee.unwrap_or(0);
+			if tx.amount_credited >= tx.amount_debited {
+				Some((tx.amount_credited - tx.amount_debited, 0, 0))
+			} else {
+				let paid = tx.amount_debited - tx.amount_credited;
+				let fee = fee.min(paid);
+				Some((0, paid - fee, fee))
+			}
+		}
+	}
+}
+
+fn to_fiat(amount: u64, price: f64) -> f64 {
+	amount as f64 / GRIN_BASE * price
+}
+
+/// Build the settlement report for the period from `start` (inclusive) to
+/// `end` (exclusive) from the wallet's transaction log
+pub fn settlement_report(
+	txs: &[TxLogEntry],
+	start: DateTime<Utc>,
+	end: DateTime<Utc>,
+	currency: &str,
+	oracle: &dyn PriceOracle,
+) -> Result<SettlementReport, Error> {
+	let mut opening_balance: i128 = 0;
+	let mut entries = vec![];
+	let mut txs: Vec<&TxLogEntry> = txs.iter().collect();
+	txs.sort_by_key(|tx| tx.confirmation_ts.unwrap_or(tx.creation_ts));
+	for tx in txs {
+		let (receipt, disbursement, fee) = match movements(tx) {
+			Some(m) => m,
+			None => continue,
+		};
+		let time = tx.confirmation_ts.unwrap_or(tx.creation_ts);
+		if time < start {
+			opening_balance += receipt as i128 - disbursement as i128 - fee as i128;
+		} else if time < end {
+			let price = oracle.price_at(currency, time)?;
+			entries.push(SettlementEntry {
+				tx_id: tx.id,
+				tx_slate_id: tx.tx_slate_id,
+				tx_type: tx.tx_type.clone(),
+				time,
+				receipt,
+				disbursement,
+				fee,
+				price,
+				receipt_fiat: to_fiat(receipt, price),
+				disbursement_fiat: to_fiat(disbursement, price),
+				fee_fiat: to_fiat(fee, price),
+			});
+		}
+	}
+	let opening_balance = opening_balance.max(0) as u64;
+	let receipts: u64 = entries.iter().map(|e| e.receipt).sum();
+	let disbursements: u64 = entries.iter().map(|e| e.disbursement).sum();
+	let fees: u64 = entries.iter().map(|e| e.fee).sum();
+	let closing_balance = (opening_balance + receipts).saturating_sub(disbursements + fees);
+	Ok(SettlementReport {
+		start,
+		end,
+		currency: currency.to_owned(),
+		opening_balance,
+		receipts,
+		disbursements,
+		fees,
+		closing_balance,
+		opening_balance_fiat: to_fiat(opening_balance, oracle.price_at(currency, start)?),
+		receipts_fiat: entries.iter().map(|e| e.receipt_fiat).sum(),
+		disbursements_fiat: entries.iter().map(|e| e.disbursement_fiat).sum(),
+		fees_fiat: entries.iter().map(|e| e.fee_fiat).sum(),
+		closing_balance_fiat: to_fiat(closing_balance, oracle.price_at(currency, end)?),
+		entries,
+	})
+}
+
+impl SettlementReport {
+	/// Render the report as CSV, one row per transaction followed by the
+	/// period's summary rows. Grin amounts are given in grins
+	pub fn to_csv(&self) -> String {
+		let mut out = String::new();
+		let _ = writeln!(
+			out,
+			"time,tx_id,tx_slate_id,type,receipt,disbursement,fee,price_{c},receipt_{c},disbursement_{c},fee_{c}",
+			c = self.currency
+		);
+		for e in &self.entries {
+			let _ = writeln!(
+				out,
+				"{},{},{},{:?},{},{},{},{:.2},{:.2},{:.2},{:.2}",
+				e.time.to_rfc3339(),
+				e.tx_id,
+				e.tx_slate_id.map(|id| id.to_string()).unwrap_or_default(),
+				e.tx_type,
+				amount_to_hr_string(e.receipt, false),
+				amount_to_hr_string(e.disbursement, false),
+				amount_to_hr_string(e.fee, false),
+				e.price,
+				e.receipt_fiat,
+				e.disbursement_fiat,
+				e.fee_fiat,
+			);
+		}
+		let summary = [
+			(
+				"opening_balance",
+				self.opening_balance,
+				self.opening_balance_fiat,
+			),
+			("receipts", self.receipts, self.receipts_fiat),
+			("disbursements", self.disbursements, self.disbursements_fiat),
+			("fees", self.fees, self.fees_fiat),
+			(
+				"closing_balance",
+				self.closing_balance,
+				self.closing_balance_fiat,
+			),
+		];
+		let _ = writeln!(out);
+		let _ = writeln!(out, "summary,amount,{}", self.currency);
+		for (name, amount, fiat) in summary.iter() {
+			let _ = writeln!(
+				out,
+				"{},{},{:.2}",
+				name,
+				amount_to_hr_string(*amount, false),
+				fiat
+			);
+		}
+		out
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::grin_keychain::ExtKeychain;
+	use chrono::{Duration, TimeZone};
+
+	fn tx(
+		id: u32,
+		t: TxLogEntryType,
+		days: i64,
+		credited: u64,
+		debited: u64,
+		fee: u64,
+	) -> TxLogEntry {
+		let mut tx = TxLogEntry::new(ExtKeychain::derive_key_id(2, 0, 0, 0, 0), t, id);
+		tx.confirmed = true;
+		tx.confirmation_ts = Some(Utc.ymd(2019, 1, 1).and_hms(0, 0, 0) + Duration::days(days));
+		tx.amount_credited = credited;
+		tx.amount_debited = debited;
+		tx.fee = Some(fee);
+		tx
+	}
+
+	#[test]
+	fn settlement_report_balances() {
+		let grin = 1_000_000_000;
+		let txs = vec![
+			tx(0, TxLogEntryType::ConfirmedCoinbase, 0, 60 * grin, 0, 0),
+			tx(1, TxLogEntryType::TxReceived, 40, 10 * grin, 0, 0),
+			tx(
+				2,
+				TxLogEntryType::TxSent,
+				45,
+				3 * grin,
+				20 * grin,
+				grin / 10,
+			),
+			tx(3, TxLogEntryType::TxSentCancelled, 46, 0, 5 * grin, 0),
+			tx(4, TxLogEntryType::TxReceived, 70, 7 * grin, 0, 0),
+		];
+		let start = Utc.ymd(2019, 2, 1).and_hms(0, 0, 0);
+		let end = Utc.ymd(2019, 3, 1).and_hms(0, 0, 0);
+		let report =
+			settlement_report(&txs, start, end, "usd", &FixedPriceOracle::new(2.0)).unwrap();
+		assert_eq!(report.opening_balance, 60 * grin);
+		assert_eq!(report.receipts, 10 * grin);
+		assert_eq!(report.disbursements, 17 * grin - grin / 10);
+		assert_eq!(report.fees, grin / 10);
+		assert_eq!(report.closing_balance, 53 * grin);
+		assert_eq!(report.entries.len(), 2);
+		assert_eq!(report.closing_balance_fiat, 106.0);
+		assert!(report
+			.to_csv()
+			.contains("closing_balance,53.000000000,106.00"));
+	}
+}
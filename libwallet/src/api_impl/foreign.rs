@@ -15,14 +15,16 @@
 //! Generic implementation of owner API functions
 use strum::IntoEnumIterator;
 
-use crate::grin_keychain::Keychain;
+use crate::grin_keychain::{Identifier, Keychain};
 use crate::grin_util::secp::key::SecretKey;
-use crate::internal::{tx, updater};
+use crate::internal::{keys, selection, tx, updater};
 use crate::slate_versions::SlateVersion;
+use crate::statistics;
 use crate::{
-	BlockFees, CbData, Error, ErrorKind, NodeClient, Slate, TxLogEntryType, VersionInfo,
-	WalletBackend,
+	BlockFees, CbData, Error, ErrorKind, NodeClient, PendingReceive, ReceiveCount, ReceiveLimits,
+	ReceiveReservation, Slate, TxLogEntryType, VersionInfo, WalletBackend,
 };
+use chrono::Utc;
 
 const FOREIGN_API_VERSION: u16 = 2;
 const USER_MESSAGE_MAX_LEN: usize = 256;
@@ -50,6 +52,54 @@ where
 	updater::build_coinbase(&mut *w, keychain_mask, block_fees, test_mode)
 }
 
+/// Check the slate hasn't expired, asking the client for the chain height
+pub fn check_ttl<C>(client: &C, slate: &Slate) -> Result<(), Error>
+where
+	C: NodeClient,
+{
+	tx::check_ttl_height(client, slate)
+}
+
+/// Check a slate received through the Foreign API is within the receive
+/// limits, given the slates received or queued for review this hour.
+/// Returns the count including the slate, to be saved with
+/// [`save_receive_count`](fn.save_receive_count.html) once it's received
+/// or queued, without the wallet being released in between
+pub fn check_receive_limits<'a, T: ?Sized, C, K>(
+	w: &T,
+	limits: &ReceiveLimits,
+	slate: &Slate,
+) -> Result<ReceiveCount, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let count = ReceiveCount::this_hour(w.receive_count()?);
+	limits.check_receive(slate.amount, count.count as usize)?;
+	Ok(ReceiveCount {
+		count: count.count + 1,
+		..count
+	})
+}
+
+/// Save the count of slates received this hour
+pub fn save_receive_count<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	count: &ReceiveCount,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let mut batch = w.batch(keychain_mask)?;
+	batch.save_receive_count(count)?;
+	batch.commit()?;
+	Ok(())
+}
+
 /// verify slate messages
 pub fn verify_slate_messages(slate: &Slate) -> Result<(), Error> {
 	slate.verify_messages()
@@ -68,8 +118,158 @@ where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
+{
+	tx::check_ttl(&mut *w, slate)?;
+	let reservation = reserve_receive(&mut *w, keychain_mask, slate, dest_acct_name, use_test_rng)?;
+	let keychain = w.keychain(keychain_mask)?;
+	let ret_slate = build_receive(&keychain, slate, &reservation, message, use_test_rng)?;
+	commit_receive(
+		&mut *w,
+		keychain_mask,
+		&ret_slate,
+		&reservation,
+		use_test_rng,
+	)?;
+	Ok(ret_slate)
+}
+
+/// First step of receiving a tx in three, so that the wallet only needs to
+/// be held for the first and last: checks the tx hasn't been received
+/// already and reserves the key of its output. The slate's TTL is left to
+/// the caller to check. A key reserved for a receive that doesn't complete
+/// is skipped
+pub fn reserve_receive<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	slate: &Slate,
+	dest_acct_name: Option<&str>,
+	use_test_rng: bool,
+) -> Result<ReceiveReservation, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = receive_account(&mut *w, dest_acct_name)?;
+	check_not_received(&mut *w, slate, &parent_key_id, use_test_rng)?;
+	let key_id = keys::next_available_key(&mut *w, keychain_mask)?;
+	Ok(ReceiveReservation {
+		parent_key_id,
+		key_id,
+	})
+}
+
+/// Second step of receiving a tx: adds the output, built from the reserved
+/// key, and the recipient's signature to the slate. This is where the time
+/// goes, in creating the output's range proof, and only needs the keychain
+pub fn build_receive<K>(
+	keychain: &K,
+	slate: &Slate,
+	reservation: &ReceiveReservation,
+	message: Option<String>,
+	use_test_rng: bool,
+) -> Result<Slate, Error>
+where
+	K: Keychain,
 {
 	let mut ret_slate = slate.clone();
+	let message = match message {
+		Some(mut m) => {
+			m.truncate(USER_MESSAGE_MAX_LEN);
+			Some(m)
+		}
+		None => None,
+	};
+
+	let mut context = selection::add_recipient_output(
+		keychain,
+		&mut ret_slate,
+		&reservation.key_id,
+		&reservation.parent_key_id,
+		use_test_rng,
+	)?;
+	let _ = ret_slate.fill_round_1(
+		keychain,
+		&mut context.sec_key,
+		&context.sec_nonce,
+		1,
+		message,
+		use_test_rng,
+	)?;
+	let _ = ret_slate.fill_round_2(keychain, &context.sec_key, &context.sec_nonce, 1)?;
+	Ok(ret_slate)
+}
+
+/// Last step of receiving a tx: saves the output added to the slate and the
+/// tx containing it, unless the same tx was received in the meantime
+pub fn commit_receive<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	slate: &Slate,
+	reservation: &ReceiveReservation,
+	use_test_rng: bool,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	check_not_received(&mut *w, slate, &reservation.parent_key_id, use_test_rng)?;
+	selection::save_recipient_output(
+		&mut *w,
+		keychain_mask,
+		slate,
+		&reservation.key_id,
+		&reservation.parent_key_id,
+		Some(slate.participant_messages()),
+	)?;
+	tx::update_message(&mut *w, keychain_mask, slate)?;
+	statistics::record_received(slate.amount);
+	Ok(())
+}
+
+/// Queue a received tx for review by the wallet's owner instead of signing
+/// it, unless it's queued already
+pub fn queue_receive<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	slate: &Slate,
+	dest_acct_name: Option<&str>,
+	message: Option<String>,
+	use_test_rng: bool,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = receive_account(&mut *w, dest_acct_name)?;
+	check_not_received(&mut *w, slate, &parent_key_id, use_test_rng)?;
+	if w.pending_receive_iter()?.any(|p| p.slate.id == slate.id) {
+		return Ok(());
+	}
+	let mut batch = w.batch(keychain_mask)?;
+	batch.save_pending_receive(&PendingReceive {
+		slate: slate.clone(),
+		dest_acct_name: dest_acct_name.map(|a| a.to_owned()),
+		message,
+		received: Utc::now(),
+	})?;
+	batch.commit()?;
+	Ok(())
+}
+
+/// Account a tx is received into, the default one if the named one
+/// doesn't exist
+fn receive_account<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	dest_acct_name: Option<&str>,
+) -> Result<Identifier, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
 	let parent_key_id = match dest_acct_name {
 		Some(d) => {
 			let pm = w.get_acct_path(d.to_owned())?;
@@ -80,40 +280,35 @@ where
 		}
 		None => w.parent_key_id(),
 	};
-	// Don't do this multiple times
+	Ok(parent_key_id)
+}
+
+/// Don't receive the same tx multiple times
+fn check_not_received<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	slate: &Slate,
+	parent_key_id: &Identifier,
+	use_test_rng: bool,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
 	let tx = updater::retrieve_txs(
 		&mut *w,
 		None,
-		Some(ret_slate.id),
-		Some(&parent_key_id),
+		Some(slate.id),
+		Some(parent_key_id),
 		use_test_rng,
+		None,
 	)?;
 	for t in &tx {
 		if t.tx_type == TxLogEntryType::TxReceived {
-			return Err(ErrorKind::TransactionAlreadyReceived(ret_slate.id.to_string()).into());
+			return Err(ErrorKind::TransactionAlreadyReceived(slate.id.to_string()).into());
 		}
 	}
-
-	let message = match message {
-		Some(mut m) => {
-			m.truncate(USER_MESSAGE_MAX_LEN);
-			Some(m)
-		}
-		None => None,
-	};
-
-	tx::add_output_to_slate(
-		&mut *w,
-		keychain_mask,
-		&mut ret_slate,
-		&parent_key_id,
-		1,
-		message,
-		false,
-		use_test_rng,
-	)?;
-	tx::update_message(&mut *w, keychain_mask, &mut ret_slate)?;
-	Ok(ret_slate)
+	Ok(())
 }
 
 /// Receive an tx that this wallet has issued
@@ -127,15 +322,18 @@ where
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
+	w.feature_flags()?.check_invoice_flow()?;
+	tx::check_ttl(&mut *w, slate)?;
 	let mut sl = slate.clone();
 	let context = w.get_private_context(keychain_mask, sl.id.as_bytes(), 1)?;
 	tx::complete_tx(&mut *w, keychain_mask, &mut sl, 1, &context)?;
-	tx::update_stored_tx(&mut *w, &mut sl, true)?;
+	tx::update_stored_tx(&mut *w, keychain_mask, &mut sl, true)?;
 	tx::update_message(&mut *w, keychain_mask, &mut sl)?;
 	{
 		let mut batch = w.batch(keychain_mask)?;
 		batch.delete_private_context(sl.id.as_bytes(), 1)?;
 		batch.commit()?;
 	}
+	statistics::record_received(sl.amount);
 	Ok(sl)
 }
@@ -14,24 +14,56 @@
 
 //! Generic implementation of owner API functions
 
+use chrono::{Duration, Utc};
 use uuid::Uuid;
 
 use crate::grin_core::core::hash::Hashed;
+use crate::grin_core::core::transaction::{self, Weighting};
+use crate::grin_core::core::verifier_cache::LruVerifierCache;
 use crate::grin_core::core::Transaction;
+use crate::grin_core::libtx::tx_fee;
 use crate::grin_core::ser;
 use crate::grin_util;
 use crate::grin_util::secp::key::SecretKey;
+use crate::grin_util::secp::pedersen;
+use crate::grin_util::RwLock;
+use std::collections::HashSet;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
 
-use crate::grin_keychain::{Identifier, Keychain};
-use crate::internal::{keys, selection, tx, updater};
+use crate::api_impl::foreign;
+use crate::confirmations::{spend_confirmations, OutputSource};
+use crate::grin_keychain::{Identifier, Keychain, SwitchCommitmentType};
+use crate::internal::selection::CoinSelection;
+use crate::internal::{backup, keys, offline, selection, transfer, tx, updater};
+use crate::policy;
+use crate::privacy::log_commit;
+use crate::replay::{self, TxReplayReport};
 use crate::slate::Slate;
-use crate::types::{AcctPathMapping, NodeClient, TxLogEntry, TxWrapper, WalletBackend, WalletInfo};
-use crate::{Error, ErrorKind};
+use crate::statistics;
+use crate::totp::TotpSecret;
+use crate::types::{
+	AcctPathMapping, FeatureFlags, NodeClient, OutputData, OutputStatus, PendingReceive,
+	PushRegistration, SelectionIntent, TxLogEntry, TxStep, TxWrapper, WalletBackend, WalletInfo,
+};
 use crate::{
-	InitTxArgs, IssueInvoiceTxArgs, NodeHeightResult, OutputCommitMapping, TxLogEntryType,
+	AnomalyKind, CancelToken, ChainAuditReport, ConsolidateArgs, InitTxArgs, IssueInvoiceTxArgs,
+	NodeHeightResult, OutputCommitMapping, OutputDerivation, RecoveryTxArgs, RemediationAction,
+	ReservedKeys, RetrieveOutputsQueryArgs, RetrieveTxQueryArgs, SignedTx, StatusMessage,
+	TotpEnrollment, TxAnomaly, TxBatchResult, TxConflict, TxLogEntryType, TxRejectionReason,
+	TxValidationResult, UnsignedTx, WalletBackup, WalletRecords,
 };
+use crate::{Error, ErrorKind};
 
 const USER_MESSAGE_MAX_LEN: usize = 256;
+/// Most received outputs spent back to the wallet in a single transaction
+const MAX_SELF_SPEND_INPUTS: usize = 100;
+
+/// Fewest words of a generated recovery phrase that verify it
+const SEED_CHECK_MIN_WORDS: usize = 3;
+
+/// Most keys reserved by a single call to `reserve_derivation_indices`
+const MAX_RESERVED_KEYS: u32 = 1000;
 
 /// List of accounts
 pub fn accounts<'a, T: ?Sized, C, K>(w: &mut T) -> Result<Vec<AcctPathMapping>, Error>
@@ -67,6 +99,193 @@ where
 	w.set_parent_key_id_by_name(label)
 }
 
+/// Export an account as an encrypted blob, for import into another wallet
+/// with the same seed
+pub fn export_account<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	label: &str,
+) -> Result<String, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	transfer::export_account(&mut *w, keychain_mask, label)
+}
+
+/// Import an account exported by another wallet with the same seed
+pub fn import_account<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	blob: &str,
+) -> Result<AcctPathMapping, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	transfer::import_account(&mut *w, keychain_mask, blob)
+}
+
+/// Back up the wallet, fully or with the changes since the last backup
+pub fn backup<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	incremental: bool,
+) -> Result<WalletBackup, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	backup::backup(&mut *w, keychain_mask, incremental)
+}
+
+/// Rebuild the wallet's records as of a backup
+pub fn reconstruct_backup<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	backups: &[String],
+	until: Option<Uuid>,
+) -> Result<WalletRecords, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	backup::reconstruct(&w.keychain(keychain_mask)?, backups, until)
+}
+
+/// Register a device token to be notified when slates are received
+pub fn register_push_token<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	token: &str,
+	platform: &str,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	if token.is_empty() {
+		return Err(ErrorKind::GenericError("Push token must not be empty".to_owned()).into());
+	}
+	let mut batch = w.batch(keychain_mask)?;
+	batch.save_push_registration(PushRegistration {
+		token: token.to_owned(),
+		platform: platform.to_owned(),
+		registered: Utc::now(),
+	})?;
+	batch.commit()?;
+	Ok(())
+}
+
+/// Remove a device token's registration, if present
+pub fn unregister_push_token<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	token: &str,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let mut batch = w.batch(keychain_mask)?;
+	batch.delete_push_registration(token)?;
+	batch.commit()?;
+	Ok(())
+}
+
+/// Devices registered for push notifications
+pub fn retrieve_push_registrations<'a, T: ?Sized, C, K>(
+	w: &mut T,
+) -> Result<Vec<PushRegistration>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	Ok(w.push_registration_iter()?.collect())
+}
+
+/// Received transactions waiting for review, oldest first
+pub fn retrieve_pending_receives<'a, T: ?Sized, C, K>(
+	w: &mut T,
+) -> Result<Vec<PendingReceive>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let mut pending: Vec<PendingReceive> = w.pending_receive_iter()?.collect();
+	pending.sort_by_key(|p| p.received);
+	Ok(pending)
+}
+
+/// Receive a transaction waiting for review, returning the slate to be
+/// returned to the sender. The transaction stays queued if it can't be
+/// received, e.g. because it has expired
+pub fn approve_receive<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	tx_slate_id: Uuid,
+	use_test_rng: bool,
+) -> Result<Slate, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let pending = pending_receive(w, tx_slate_id)?;
+	let slate = foreign::receive_tx(
+		&mut *w,
+		keychain_mask,
+		&pending.slate,
+		pending.dest_acct_name.as_ref().map(|a| a.as_str()),
+		pending.message,
+		use_test_rng,
+	)?;
+	let mut batch = w.batch(keychain_mask)?;
+	batch.delete_pending_receive(&tx_slate_id)?;
+	batch.commit()?;
+	Ok(slate)
+}
+
+/// Drop a transaction waiting for review without receiving it
+pub fn reject_receive<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	tx_slate_id: Uuid,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	pending_receive(w, tx_slate_id)?;
+	let mut batch = w.batch(keychain_mask)?;
+	batch.delete_pending_receive(&tx_slate_id)?;
+	batch.commit()?;
+	Ok(())
+}
+
+fn pending_receive<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	tx_slate_id: Uuid,
+) -> Result<PendingReceive, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	w.pending_receive_iter()?
+		.find(|p| p.slate.id == tx_slate_id)
+		.ok_or_else(|| ErrorKind::TransactionDoesntExist(tx_slate_id.to_string()).into())
+}
+
 /// retrieve outputs
 pub fn retrieve_outputs<'a, T: ?Sized, C, K>(
 	w: &mut T,
@@ -99,29 +318,133 @@ where
 	))
 }
 
-/// Retrieve txs
+/// How the key of the wallet output with the given hex encoded commitment is
+/// derived, whichever account it belongs to
+pub fn get_output_derivation<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	commit: &str,
+) -> Result<OutputDerivation, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let commit = commit.trim().to_lowercase();
+	let output = updater::retrieve_outputs(&mut *w, keychain_mask, true, None, None)?
+		.into_iter()
+		.find(|o| grin_util::to_hex(o.commit.as_ref().to_vec()) == commit)
+		.ok_or_else(|| ErrorKind::OutputDoesntExist(commit.clone()))?;
+	let account = w
+		.acct_path_iter()?
+		.find(|a| a.path == output.output.root_key_id)
+		.map(|a| a.label);
+	Ok(OutputDerivation::new(&output, account))
+}
+
+/// Reserve the next `count` keys of the active account for use outside the
+/// wallet, which won't derive them for its own outputs
+pub fn reserve_derivation_indices<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	count: u32,
+) -> Result<ReservedKeys, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	if count == 0 || count > MAX_RESERVED_KEYS {
+		return Err(ErrorKind::KeyReservation(format!(
+			"between 1 and {} keys can be reserved at once",
+			MAX_RESERVED_KEYS
+		)))?;
+	}
+	let parent_key_id = w.parent_key_id();
+	let first = keys::reserve_keys(&mut *w, keychain_mask, &parent_key_id, count)?;
+	let account = w
+		.acct_path_iter()?
+		.find(|a| a.path == parent_key_id)
+		.map(|a| a.label);
+	Ok(ReservedKeys::new(account, parent_key_id, first, count))
+}
+
+/// Retrieve outputs matching the given query
+pub fn query_outputs<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	refresh_from_node: bool,
+	query: &RetrieveOutputsQueryArgs,
+) -> Result<(bool, Vec<OutputCommitMapping>), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = account_parent_key_id(&mut *w, query.account.as_ref())?;
+
+	let mut validated = false;
+	if refresh_from_node {
+		validated = update_account_outputs(w, keychain_mask, &parent_key_id, false)?;
+	}
+
+	Ok((
+		validated,
+		updater::query_outputs(&mut *w, keychain_mask, &parent_key_id, query)?,
+	))
+}
+
+/// Parent key of the named account, or of the active account if none is named
+fn account_parent_key_id<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	account: Option<&String>,
+) -> Result<Identifier, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	match account {
+		Some(a) => match w.get_acct_path(a.to_owned())? {
+			Some(p) => Ok(p.path),
+			None => Err(ErrorKind::UnknownAccountLabel(a.to_owned()).into()),
+		},
+		None => Ok(w.parent_key_id()),
+	}
+}
+
+/// Retrieve txs, or the page of them matching the query, from the
+/// query's account if it names one
 pub fn retrieve_txs<'a, T: ?Sized, C, K>(
 	w: &mut T,
 	keychain_mask: Option<&SecretKey>,
 	refresh_from_node: bool,
 	tx_id: Option<u32>,
 	tx_slate_id: Option<Uuid>,
+	query: Option<&RetrieveTxQueryArgs>,
 ) -> Result<(bool, Vec<TxLogEntry>), Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	let parent_key_id = w.parent_key_id();
+	let parent_key_id = account_parent_key_id(&mut *w, query.and_then(|q| q.account.as_ref()))?;
 
 	let mut validated = false;
 	if refresh_from_node {
-		validated = update_outputs(w, keychain_mask, false)?;
+		validated = update_account_outputs(w, keychain_mask, &parent_key_id, false)?;
 	}
 
 	Ok((
 		validated,
-		updater::retrieve_txs(&mut *w, tx_id, tx_slate_id, Some(&parent_key_id), false)?,
+		updater::retrieve_txs(
+			&mut *w,
+			tx_id,
+			tx_slate_id,
+			Some(&parent_key_id),
+			false,
+			query,
+		)?,
 	))
 }
 
@@ -148,6 +471,63 @@ where
 	Ok((validated, wallet_info))
 }
 
+/// The fee of a send is computed for the number of change outputs asked for,
+/// so at least one is needed to leave room for any change
+fn check_num_change_outputs(args: &InitTxArgs) -> Result<(), Error> {
+	if args.num_change_outputs == 0 {
+		return Err(ErrorKind::GenericError(
+			"At least one change output is needed".to_owned(),
+		))?;
+	}
+	Ok(())
+}
+
+/// The coin selection strategy named in the arguments, or described by their
+/// older flags
+fn coin_selection(args: &InitTxArgs) -> Result<Arc<dyn CoinSelection>, Error> {
+	selection::coin_selection(
+		args.selection_strategy.as_ref().map(|s| s.as_str()),
+		args.selection_strategy_is_use_all,
+		args.randomize_selection.unwrap_or(false),
+	)
+}
+
+/// Estimate the amount locked and fee of each of the sends of a
+/// multi-recipient send, made one after another with the given arguments, so
+/// each selects from the outputs the earlier ones leave. Fails as a single
+/// send would if the funds don't cover all of them
+pub fn estimate_send_rounds<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	args: &InitTxArgs,
+	amounts: &[u64],
+) -> Result<Vec<(u64, u64)>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = match args.src_acct_name {
+		Some(ref d) => match w.get_acct_path(d.clone())? {
+			Some(p) => p.path,
+			None => w.parent_key_id(),
+		},
+		None => w.parent_key_id(),
+	};
+	check_num_change_outputs(args)?;
+	let strategy = coin_selection(args)?;
+	tx::estimate_send_rounds(
+		&mut *w,
+		keychain_mask,
+		amounts,
+		args.minimum_confirmations,
+		args.max_outputs as usize,
+		args.num_change_outputs as usize,
+		&*strategy,
+		&parent_key_id,
+	)
+}
+
 /// Initiate tx as sender
 pub fn init_send_tx<'a, T: ?Sized, C, K>(
 	w: &mut T,
@@ -170,6 +550,13 @@ where
 		}
 		None => w.parent_key_id(),
 	};
+	check_num_change_outputs(&args)?;
+	let strategy = coin_selection(&args)?;
+	if args.estimate_only != Some(true) {
+		let dest = args.send_args.as_ref().map(|a| a.dest.as_str());
+		w.spending_policy().check_destination(dest)?;
+		policy::check_send_amount(&*w, args.amount, None)?;
+	}
 
 	let message = match args.message {
 		Some(mut m) => {
@@ -180,6 +567,9 @@ where
 	};
 
 	let mut slate = tx::new_tx_slate(&mut *w, args.amount, 2, use_test_rng)?;
+	if let Some(b) = args.ttl_blocks {
+		slate.ttl_cutoff_height = Some(slate.height + b);
+	}
 
 	// if we just want to estimate, don't save a context, just send the results
 	// back
@@ -191,7 +581,7 @@ where
 			args.minimum_confirmations,
 			args.max_outputs as usize,
 			args.num_change_outputs as usize,
-			args.selection_strategy_is_use_all,
+			&*strategy,
 			&parent_key_id,
 		)?;
 		slate.amount = total;
@@ -199,20 +589,52 @@ where
 		return Ok(slate);
 	}
 
-	let context = tx::add_inputs_to_slate(
+	w.feature_flags()?
+		.check_seed_backup(w.seed_check()?.as_ref())?;
+
+	if let Some(true) = args.late_lock {
+		let intent = SelectionIntent {
+			minimum_confirmations: args.minimum_confirmations,
+			max_outputs: args.max_outputs as usize,
+			num_change_outputs: args.num_change_outputs as usize,
+			selection_strategy_is_use_all: args.selection_strategy_is_use_all,
+			randomize_selection: args.randomize_selection.unwrap_or(false),
+			selection_strategy: args.selection_strategy.clone(),
+		};
+		let mut context = tx::add_late_lock_to_slate(
+			&mut *w,
+			keychain_mask,
+			&mut slate,
+			intent,
+			&parent_key_id,
+			message,
+			use_test_rng,
+		)?;
+		context.fluff = args.fluff;
+		let mut batch = w.batch(keychain_mask)?;
+		batch.save_private_context(slate.id.as_bytes(), 0, &context)?;
+		batch.commit()?;
+		if let Some(v) = args.target_slate_version {
+			slate.version_info.orig_version = v;
+		}
+		return Ok(slate);
+	}
+
+	let mut context = tx::add_inputs_to_slate(
 		&mut *w,
 		keychain_mask,
 		&mut slate,
 		args.minimum_confirmations,
 		args.max_outputs as usize,
 		args.num_change_outputs as usize,
-		args.selection_strategy_is_use_all,
+		&*strategy,
 		&parent_key_id,
 		0,
 		message,
 		true,
 		use_test_rng,
 	)?;
+	context.fluff = args.fluff;
 
 	// Save the aggsig context in our DB for when we
 	// recieve the transaction back
@@ -227,59 +649,227 @@ where
 	Ok(slate)
 }
 
-/// Initiate a transaction as the recipient (invoicing)
-pub fn issue_invoice_tx<'a, T: ?Sized, C, K>(
+/// Consolidate the spendable outputs of an account into fewer outputs,
+/// returning the finalized transactions, which are not posted
+pub fn consolidate<'a, T: ?Sized, C, K>(
 	w: &mut T,
 	keychain_mask: Option<&SecretKey>,
-	args: IssueInvoiceTxArgs,
+	args: ConsolidateArgs,
 	use_test_rng: bool,
-) -> Result<Slate, Error>
+) -> Result<Vec<Slate>, Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	let parent_key_id = match args.dest_acct_name {
-		Some(d) => {
-			let pm = w.get_acct_path(d)?;
-			match pm {
-				Some(p) => p.path,
-				None => w.parent_key_id(),
-			}
-		}
+	let parent_key_id = match args.src_acct_name {
+		Some(d) => match w.get_acct_path(d.clone())? {
+			Some(p) => p.path,
+			None => return Err(ErrorKind::UnknownAccountLabel(d))?,
+		},
 		None => w.parent_key_id(),
 	};
+	if args.num_outputs == 0 {
+		return Err(ErrorKind::GenericError(
+			"Consolidation needs at least one output".to_owned(),
+		))?;
+	}
 
-	let message = match args.message {
-		Some(mut m) => {
-			m.truncate(USER_MESSAGE_MAX_LEN);
-			Some(m)
-		}
-		None => None,
-	};
-
-	let mut slate = tx::new_tx_slate(&mut *w, args.amount, 2, use_test_rng)?;
-	let context = tx::add_output_to_slate(
+	updater::refresh_outputs(&mut *w, keychain_mask, &parent_key_id, false)?;
+	let current_height = w.w2n_client().get_chain_height()?;
+	let batches = selection::select_consolidation_coins(
 		&mut *w,
-		keychain_mask,
-		&mut slate,
+		current_height,
+		args.minimum_confirmations,
+		args.num_outputs as usize,
+		args.max_weight,
+		args.max_txs as usize,
 		&parent_key_id,
-		1,
-		message,
-		true,
-		use_test_rng,
 	)?;
-
-	// Save the aggsig context in our DB for when we
-	// recieve the transaction back
-	{
-		let mut batch = w.batch(keychain_mask)?;
-		batch.save_private_context(slate.id.as_bytes(), 1, &context)?;
-		batch.commit()?;
-	}
-
-	if let Some(v) = args.target_slate_version {
-		slate.version_info.orig_version = v;
+	let mut slates = vec![];
+	for coins in batches {
+		slates.push(tx::consolidation_tx(
+			&mut *w,
+			keychain_mask,
+			&coins,
+			args.num_outputs as usize,
+			&parent_key_id,
+			use_test_rng,
+		)?);
+	}
+	Ok(slates)
+}
+
+/// The outputs of an account a recovery transaction sweeps
+fn recovery_coins<'a, T: ?Sized, C, K>(
+	w: &T,
+	parent_key_id: &Identifier,
+	height: u64,
+	minimum_confirmations: u64,
+) -> Result<Vec<OutputData>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	Ok(w.iter()?
+		.filter(|o| {
+			o.root_key_id == *parent_key_id && o.eligible_to_spend(height, minimum_confirmations)
+		})
+		.collect())
+}
+
+/// Build the sender's side of a transaction sweeping the spendable outputs of
+/// an account to a recovery destination, which can't be posted before
+/// `lock_blocks` blocks from now. The outputs aren't locked
+pub fn init_recovery_tx<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	args: RecoveryTxArgs,
+	use_test_rng: bool,
+) -> Result<Slate, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = match args.src_acct_name {
+		Some(d) => match w.get_acct_path(d.clone())? {
+			Some(p) => p.path,
+			None => return Err(ErrorKind::UnknownAccountLabel(d))?,
+		},
+		None => w.parent_key_id(),
+	};
+
+	updater::refresh_outputs(&mut *w, keychain_mask, &parent_key_id, false)?;
+	let height = w.w2n_client().get_chain_height()?;
+	let coins = recovery_coins(&*w, &parent_key_id, height, args.minimum_confirmations)?;
+	let (slate, context) = tx::recovery_tx(
+		&mut *w,
+		keychain_mask,
+		&coins,
+		height + args.lock_blocks,
+		&parent_key_id,
+		use_test_rng,
+	)?;
+	let mut batch = w.batch(keychain_mask)?;
+	batch.save_private_context(slate.id.as_bytes(), 0, &context)?;
+	batch.commit()?;
+	Ok(slate)
+}
+
+/// Finalize a recovery transaction once the recovery destination has added
+/// its output and signature. The transaction is neither recorded nor posted
+pub fn finalize_recovery_tx<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	slate: &Slate,
+) -> Result<Slate, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let mut sl = slate.clone();
+	let context = w.get_private_context(keychain_mask, sl.id.as_bytes(), 0)?;
+	tx::complete_tx(&mut *w, keychain_mask, &mut sl, 0, &context)?;
+	let mut batch = w.batch(keychain_mask)?;
+	batch.delete_private_context(sl.id.as_bytes(), 0)?;
+	batch.commit()?;
+	Ok(sl)
+}
+
+/// Whether a recovery transaction still sweeps exactly the spendable outputs
+/// of the account, refreshed from the node. It needs rebuilding once any of
+/// them is spent or new ones become spendable
+pub fn recovery_tx_current<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	args: &RecoveryTxArgs,
+	slate: &Slate,
+) -> Result<bool, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = match args.src_acct_name.as_ref() {
+		Some(d) => match w.get_acct_path(d.clone())? {
+			Some(p) => p.path,
+			None => return Err(ErrorKind::UnknownAccountLabel(d.clone()))?,
+		},
+		None => w.parent_key_id(),
+	};
+	updater::refresh_outputs(&mut *w, keychain_mask, &parent_key_id, false)?;
+	let height = w.w2n_client().get_chain_height()?;
+	let coins = recovery_coins(&*w, &parent_key_id, height, args.minimum_confirmations)?;
+	let keychain = w.keychain(keychain_mask)?;
+	let mut commits = HashSet::new();
+	for c in coins {
+		commits.insert(keychain.commit(c.value, &c.key_id, &SwitchCommitmentType::Regular)?);
+	}
+	let inputs: HashSet<pedersen::Commitment> =
+		slate.tx.inputs().iter().map(|i| i.commitment()).collect();
+	Ok(commits == inputs)
+}
+
+/// Initiate a transaction as the recipient (invoicing)
+pub fn issue_invoice_tx<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	args: IssueInvoiceTxArgs,
+	use_test_rng: bool,
+) -> Result<Slate, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	w.feature_flags()?.check_invoice_flow()?;
+	let parent_key_id = match args.dest_acct_name {
+		Some(d) => {
+			let pm = w.get_acct_path(d)?;
+			match pm {
+				Some(p) => p.path,
+				None => w.parent_key_id(),
+			}
+		}
+		None => w.parent_key_id(),
+	};
+
+	let message = match args.message {
+		Some(mut m) => {
+			m.truncate(USER_MESSAGE_MAX_LEN);
+			Some(m)
+		}
+		None => None,
+	};
+
+	let mut slate = tx::new_tx_slate(&mut *w, args.amount, 2, use_test_rng)?;
+	let context = tx::add_output_to_slate(
+		&mut *w,
+		keychain_mask,
+		&mut slate,
+		&parent_key_id,
+		1,
+		message,
+		true,
+		use_test_rng,
+	)?;
+	if let Some(f) = args.fluff {
+		tx::update_fluff(&mut *w, keychain_mask, &slate, f)?;
+	}
+
+	// Save the aggsig context in our DB for when we
+	// recieve the transaction back
+	{
+		let mut batch = w.batch(keychain_mask)?;
+		batch.save_private_context(slate.id.as_bytes(), 1, &context)?;
+		batch.commit()?;
+	}
+
+	if let Some(v) = args.target_slate_version {
+		slate.version_info.orig_version = v;
 	}
 
 	Ok(slate)
@@ -299,6 +889,10 @@ where
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
+	let flags = w.feature_flags()?;
+	flags.check_invoice_flow()?;
+	flags.check_seed_backup(w.seed_check()?.as_ref())?;
+	tx::check_ttl(&mut *w, slate)?;
 	let mut ret_slate = slate.clone();
 	let parent_key_id = match args.src_acct_name {
 		Some(d) => {
@@ -317,12 +911,18 @@ where
 		Some(ret_slate.id),
 		Some(&parent_key_id),
 		use_test_rng,
+		None,
 	)?;
 	for t in &tx {
 		if t.tx_type == TxLogEntryType::TxSent {
 			return Err(ErrorKind::TransactionAlreadyReceived(ret_slate.id.to_string()).into());
 		}
 	}
+	check_num_change_outputs(&args)?;
+	let strategy = coin_selection(&args)?;
+	let dest = args.send_args.as_ref().map(|a| a.dest.as_str());
+	w.spending_policy().check_destination(dest)?;
+	policy::check_send_amount(&*w, ret_slate.amount, None)?;
 
 	let message = match args.message {
 		Some(mut m) => {
@@ -342,7 +942,7 @@ where
 		args.minimum_confirmations,
 		args.max_outputs as usize,
 		args.num_change_outputs as usize,
-		args.selection_strategy_is_use_all,
+		&*strategy,
 		&parent_key_id,
 		0,
 		message,
@@ -361,6 +961,7 @@ where
 	if let Some(v) = args.target_slate_version {
 		ret_slate.version_info.orig_version = v;
 	}
+	statistics::record_sent(ret_slate.amount, ret_slate.fee);
 
 	Ok(ret_slate)
 }
@@ -378,170 +979,1274 @@ where
 	K: Keychain + 'a,
 {
 	let context = w.get_private_context(keychain_mask, slate.id.as_bytes(), participant_id)?;
+	if context.late_lock.is_some() {
+		debug!(
+			"Outputs of late locked tx {} are locked when it's finalized",
+			slate.id
+		);
+		return Ok(());
+	}
 	selection::lock_tx_context(&mut *w, keychain_mask, slate, &context)
 }
 
-/// Finalize slate
+/// Finalize slate, optionally overriding the Dandelion preference stored
+/// with the transaction
 pub fn finalize_tx<'a, T: ?Sized, C, K>(
 	w: &mut T,
 	keychain_mask: Option<&SecretKey>,
 	slate: &Slate,
+	fluff: Option<bool>,
 ) -> Result<Slate, Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
+	tx::check_ttl(&mut *w, slate)?;
+	policy::check_send_amount(&*w, slate.amount, Some(slate.id))?;
 	let mut sl = slate.clone();
-	let context = w.get_private_context(keychain_mask, sl.id.as_bytes(), 0)?;
+	let mut context = w.get_private_context(keychain_mask, sl.id.as_bytes(), 0)?;
+	let late_lock = context.late_lock.is_some();
+	tx::late_lock(&mut *w, keychain_mask, &mut sl, &mut context)?;
 	tx::complete_tx(&mut *w, keychain_mask, &mut sl, 0, &context)?;
-	tx::update_stored_tx(&mut *w, &mut sl, false)?;
+	if late_lock {
+		selection::lock_tx_context(&mut *w, keychain_mask, &sl, &context)?;
+	}
+	tx::update_stored_tx(&mut *w, keychain_mask, &mut sl, false)?;
 	tx::update_message(&mut *w, keychain_mask, &mut sl)?;
+	if let Some(f) = fluff {
+		tx::update_fluff(&mut *w, keychain_mask, &sl, f)?;
+	}
 	{
 		let mut batch = w.batch(keychain_mask)?;
 		batch.delete_private_context(sl.id.as_bytes(), 0)?;
 		batch.commit()?;
 	}
+	statistics::record_sent(sl.amount, sl.fee);
 	Ok(sl)
 }
 
-/// cancel tx
-pub fn cancel_tx<'a, T: ?Sized, C, K>(
+/// Prepare a send from a watch-only wallet, for the wallet holding the seed
+/// to sign offline
+pub fn prepare_send<'a, T: ?Sized, C, K>(
 	w: &mut T,
 	keychain_mask: Option<&SecretKey>,
-	tx_id: Option<u32>,
-	tx_slate_id: Option<Uuid>,
-) -> Result<(), Error>
+	args: InitTxArgs,
+	use_test_rng: bool,
+) -> Result<UnsignedTx, Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	let parent_key_id = w.parent_key_id();
-	if !update_outputs(w, keychain_mask, false)? {
-		return Err(ErrorKind::TransactionCancellationError(
-			"Can't contact running Grin node. Not Cancelling.",
-		))?;
+	let parent_key_id = match args.src_acct_name {
+		Some(d) => match w.get_acct_path(d.clone())? {
+			Some(p) => p.path,
+			None => return Err(ErrorKind::UnknownAccountLabel(d))?,
+		},
+		None => w.parent_key_id(),
+	};
+	check_num_change_outputs(&args)?;
+	let strategy = coin_selection(&args)?;
+	let dest = args.send_args.as_ref().map(|a| a.dest.as_str());
+	w.spending_policy().check_destination(dest)?;
+	policy::check_send_amount(&*w, args.amount, None)?;
+	let message = args.message.map(|mut m| {
+		m.truncate(USER_MESSAGE_MAX_LEN);
+		m
+	});
+
+	let mut slate = tx::new_tx_slate(&mut *w, args.amount, 2, use_test_rng)?;
+	if let Some(b) = args.ttl_blocks {
+		slate.ttl_cutoff_height = Some(slate.height + b);
 	}
-	tx::cancel_tx(&mut *w, keychain_mask, &parent_key_id, tx_id, tx_slate_id)
+	if let Some(v) = args.target_slate_version {
+		slate.version_info.orig_version = v;
+	}
+	offline::prepare_send(
+		&mut *w,
+		keychain_mask,
+		slate,
+		args.minimum_confirmations,
+		args.max_outputs as usize,
+		args.num_change_outputs as usize,
+		&*strategy,
+		&parent_key_id,
+		message,
+	)
 }
 
-/// get stored tx
-pub fn get_stored_tx<'a, T: ?Sized, C, K>(
-	w: &T,
-	entry: &TxLogEntry,
-) -> Result<Option<Transaction>, Error>
+/// Sign a prepared send with the wallet holding the seed, without a node
+pub fn sign_offline<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	unsigned: &UnsignedTx,
+	use_test_rng: bool,
+) -> Result<Slate, Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	w.get_stored_tx(entry)
+	offline::sign_offline(&mut *w, keychain_mask, unsigned, use_test_rng)
 }
 
-/// Posts a transaction to the chain
-/// take a client impl instead of wallet so as not to have to lock the wallet
-pub fn post_tx<'a, C>(client: &C, tx: &Transaction, fluff: bool) -> Result<(), Error>
+/// Finalize a send signed offline, once the recipient has returned it
+pub fn finalize_offline<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	slate: &Slate,
+) -> Result<SignedTx, Error>
 where
+	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
+	K: Keychain + 'a,
 {
-	let tx_hex = grin_util::to_hex(ser::ser_vec(tx, ser::ProtocolVersion::local()).unwrap());
-	let res = client.post_tx(&TxWrapper { tx_hex: tx_hex }, fluff);
-	if let Err(e) = res {
-		error!("api: post_tx: failed with error: {}", e);
-		Err(e)
-	} else {
-		debug!(
-			"api: post_tx: successfully posted tx: {}, fluff? {}",
-			tx.hash(),
-			fluff
-		);
-		Ok(())
-	}
-}
-
-/// verify slate messages
-pub fn verify_slate_messages(slate: &Slate) -> Result<(), Error> {
-	slate.verify_messages()
+	offline::finalize_offline(&mut *w, keychain_mask, slate)
 }
 
-/// Attempt to restore contents of wallet
-pub fn restore<'a, T: ?Sized, C, K>(
+/// Record the change of a send finalized offline in the watch-only wallet,
+/// and post the transaction
+pub fn broadcast_signed<'a, T: ?Sized, C, K>(
 	w: &mut T,
 	keychain_mask: Option<&SecretKey>,
+	signed: &SignedTx,
+	fluff: bool,
 ) -> Result<(), Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	w.restore(keychain_mask)
+	offline::record_signed(&mut *w, keychain_mask, signed)?;
+	let client = w.w2n_client().clone();
+	post_tx(&client, &signed.slate.tx, fluff)
 }
 
-/// check repair
-pub fn check_repair<'a, T: ?Sized, C, K>(
+/// cancel tx
+pub fn cancel_tx<'a, T: ?Sized, C, K>(
 	w: &mut T,
 	keychain_mask: Option<&SecretKey>,
-	delete_unconfirmed: bool,
+	tx_id: Option<u32>,
+	tx_slate_id: Option<Uuid>,
 ) -> Result<(), Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	update_outputs(w, keychain_mask, true)?;
-	w.check_repair(keychain_mask, delete_unconfirmed)
+	let parent_key_id = w.parent_key_id();
+	if !update_outputs(w, keychain_mask, false)? {
+		return Err(ErrorKind::TransactionCancellationError(
+			"Can't contact running Grin node. Not Cancelling.",
+		))?;
+	}
+	tx::cancel_tx(&mut *w, keychain_mask, &parent_key_id, tx_id, tx_slate_id)
 }
 
-/// node height
-pub fn node_height<'a, T: ?Sized, C, K>(
+/// Cancel each of the given transactions in the active account, or each
+/// matching the filter, in the filter's account if it names one, after a
+/// single update from the node. A transaction failing to cancel doesn't stop
+/// the others being cancelled
+pub fn cancel_txs<'a, T: ?Sized, C, K>(
 	w: &mut T,
 	keychain_mask: Option<&SecretKey>,
-) -> Result<NodeHeightResult, Error>
+	tx_ids: Option<Vec<u32>>,
+	filter: Option<&RetrieveTxQueryArgs>,
+) -> Result<Vec<TxBatchResult>, Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	let res = w.w2n_client().get_chain_height();
-	match res {
-		Ok(height) => Ok(NodeHeightResult {
-			height,
-			updated_from_node: true,
-		}),
-		Err(_) => {
-			let outputs = retrieve_outputs(w, keychain_mask, true, false, None)?;
-			let height = match outputs.1.iter().map(|m| m.output.height).max() {
-				Some(height) => height,
-				None => 0,
-			};
-			Ok(NodeHeightResult {
-				height,
-				updated_from_node: false,
-			})
-		}
+	let parent_key_id = account_parent_key_id(&mut *w, filter.and_then(|f| f.account.as_ref()))?;
+	if !update_outputs(w, keychain_mask, false)? {
+		return Err(ErrorKind::TransactionCancellationError(
+			"Can't contact running Grin node. Not Cancelling.",
+		))?;
 	}
+	let tx_ids = match (tx_ids, filter) {
+		(Some(ids), None) => ids,
+		(None, Some(f)) => {
+			updater::retrieve_txs(&mut *w, None, None, Some(&parent_key_id), false, Some(f))?
+				.iter()
+				.map(|t| t.id)
+				.collect()
+		}
+		_ => {
+			return Err(ErrorKind::GenericError(
+				"Either transaction ids or a filter must be given".to_owned(),
+			))?;
+		}
+	};
+	Ok(tx_ids
+		.into_iter()
+		.map(|id| {
+			let res = tx::cancel_tx(&mut *w, keychain_mask, &parent_key_id, Some(id), None);
+			TxBatchResult::new(id, res)
+		})
+		.collect())
 }
 
-/// Attempt to update outputs in wallet, return whether it was successful
-fn update_outputs<'a, T: ?Sized, C, K>(
+/// Set or clear the label of a transaction in the active account
+pub fn set_tx_label<'a, T: ?Sized, C, K>(
 	w: &mut T,
 	keychain_mask: Option<&SecretKey>,
-	update_all: bool,
-) -> Result<bool, Error>
+	tx_id: u32,
+	label: Option<String>,
+) -> Result<(), Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	let parent_key_id = w.parent_key_id();
-	match updater::refresh_outputs(&mut *w, keychain_mask, &parent_key_id, update_all) {
-		Ok(_) => Ok(true),
-		Err(e) => {
-			if let ErrorKind::InvalidKeychainMask = e.kind() {
-				return Err(e);
-			}
-			Ok(false)
+	if let Some(l) = label.as_ref() {
+		if l.chars().count() > USER_MESSAGE_MAX_LEN {
+			return Err(ErrorKind::GenericError(format!(
+				"Transaction label is longer than {} characters",
+				USER_MESSAGE_MAX_LEN
+			)))?;
 		}
 	}
+	let parent_key_id = w.parent_key_id();
+	let txs = updater::retrieve_txs(
+		&mut *w,
+		Some(tx_id),
+		None,
+		Some(&parent_key_id),
+		false,
+		None,
+	)?;
+	let mut tx = match txs.into_iter().next() {
+		Some(t) => t,
+		None => return Err(ErrorKind::TransactionDoesntExist(tx_id.to_string()))?,
+	};
+	// an empty label clears it
+	tx.label = label.filter(|l| !l.is_empty());
+	let mut batch = w.batch(keychain_mask)?;
+	batch.save_tx_log_entry(tx, &parent_key_id)?;
+	batch.commit()?;
+	Ok(())
+}
+
+/// get stored tx
+pub fn get_stored_tx<'a, T: ?Sized, C, K>(
+	w: &T,
+	entry: &TxLogEntry,
+) -> Result<Option<Transaction>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	w.get_stored_tx(entry)
+}
+
+/// Replay the slates stored at each step of a transaction in the active
+/// account against the running code, without changing the wallet
+pub fn replay_tx<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	tx_id: Option<u32>,
+	tx_slate_id: Option<Uuid>,
+) -> Result<TxReplayReport, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	// Test keychain mask, to keep API consistent
+	let _ = w.keychain(keychain_mask)?;
+	let tx_id_string = match (tx_id, tx_slate_id) {
+		(Some(id), _) => id.to_string(),
+		(None, Some(id)) => id.to_string(),
+		(None, None) => String::new(),
+	};
+	let parent_key_id = w.parent_key_id();
+	let txs = updater::retrieve_txs(
+		&mut *w,
+		tx_id,
+		tx_slate_id,
+		Some(&parent_key_id),
+		false,
+		None,
+	)?;
+	let tx = match txs.into_iter().next() {
+		Some(t) => t,
+		None => return Err(ErrorKind::TransactionDoesntExist(tx_id_string))?,
+	};
+	let slate_id = match tx.tx_slate_id {
+		Some(id) => id,
+		None => {
+			return Err(ErrorKind::GenericError(format!(
+				"Transaction {} has no slates to replay",
+				tx_id_string
+			)))?
+		}
+	};
+	let mut stored = vec![];
+	for step in &[TxStep::Lock, TxStep::Receive, TxStep::Finalize] {
+		stored.push((*step, w.get_stored_slate(&slate_id, *step)?));
+	}
+	// the transaction file of a send is only written once it's finalized
+	let stored_tx = w.get_stored_tx(&tx).unwrap_or(None);
+	Ok(replay::replay_slates(
+		slate_id,
+		&stored,
+		&tx.step_versions,
+		stored_tx.as_ref(),
+	))
+}
+
+/// Find transactions in the active account needing the user's attention: transactions
+/// unconfirmed for longer than `stuck_after_mins` minutes, cancelled transactions whose
+/// outputs weren't released, confirmed transactions whose outputs have since vanished
+/// from the chain, and, if refreshed, transactions whose spent outputs have reappeared
+pub fn list_anomalies<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	refresh_from_node: bool,
+	stuck_after_mins: u64,
+) -> Result<(bool, Vec<TxAnomaly>), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = w.parent_key_id();
+
+	let mut validated = false;
+	if refresh_from_node {
+		validated = update_outputs(w, keychain_mask, false)?;
+	}
+
+	let txs = updater::retrieve_txs(&mut *w, None, None, Some(&parent_key_id), false, None)?;
+	let outputs: Vec<OutputData> = w
+		.iter()?
+		.filter(|o| o.root_key_id == parent_key_id && o.tx_log_entry.is_some())
+		.collect();
+	let cutoff = Utc::now() - Duration::minutes(stuck_after_mins as i64);
+
+	let mut anomalies = vec![];
+	for tx in txs {
+		let mut tx_outputs = outputs.iter().filter(|o| o.tx_log_entry == Some(tx.id));
+		let anomaly = |kind, description: String, suggested_action| TxAnomaly {
+			kind,
+			tx_id: tx.id,
+			tx_slate_id: tx.tx_slate_id,
+			description,
+			suggested_action,
+		};
+		match tx.tx_type {
+			TxLogEntryType::TxSent if !tx.confirmed && tx.creation_ts < cutoff => {
+				// a stored transaction with a kernel excess has been finalized
+				let finalized = match w.get_stored_tx(&tx)? {
+					Some(t) => t
+						.kernels()
+						.iter()
+						.all(|k| k.excess.0.iter().any(|b| *b != 0)),
+					None => false,
+				};
+				anomalies.push(if finalized {
+					anomaly(
+						AnomalyKind::StuckUnconfirmed,
+						"Transaction was finalized but hasn't confirmed; it may have been \
+						 dropped from the node's mempool"
+							.to_owned(),
+						RemediationAction::Repost,
+					)
+				} else {
+					anomaly(
+						AnomalyKind::StuckUnconfirmed,
+						"Transaction was never finalized, and its inputs remain locked".to_owned(),
+						RemediationAction::Cancel,
+					)
+				});
+			}
+			TxLogEntryType::TxReceived if !tx.confirmed && tx.creation_ts < cutoff => {
+				anomalies.push(anomaly(
+					AnomalyKind::ReceivedNotFinalized,
+					"Sender hasn't finalized and posted the transaction".to_owned(),
+					RemediationAction::Cancel,
+				));
+			}
+			TxLogEntryType::TxSentCancelled | TxLogEntryType::TxReceivedCancelled => {
+				if tx_outputs.any(|o| {
+					o.status == OutputStatus::Locked || o.status == OutputStatus::Unconfirmed
+				}) {
+					anomalies.push(anomaly(
+						AnomalyKind::CancelledWithLockedOutputs,
+						"Transaction was cancelled but some of its outputs are still locked or \
+						 unconfirmed"
+							.to_owned(),
+						RemediationAction::CheckRepair,
+					));
+				}
+			}
+			// outputs created by a receive are only ever marked spent once locked by
+			// another transaction, which takes them over, unless they leave the chain
+			TxLogEntryType::TxReceived | TxLogEntryType::ConfirmedCoinbase if tx.confirmed => {
+				if tx_outputs.any(|o| o.status == OutputStatus::Spent) {
+					anomalies.push(anomaly(
+						AnomalyKind::Reorged,
+						"Transaction was confirmed but its outputs are no longer on chain"
+							.to_owned(),
+						RemediationAction::CheckRepair,
+					));
+				}
+			}
+			_ => {}
+		}
+	}
+
+	// a spent output back in the UTXO set can be spent again by anyone replaying
+	// the transaction which spent it
+	if validated {
+		let reappeared = updater::find_reappeared_outputs(&mut *w, keychain_mask, &parent_key_id)?;
+		for (commit, out) in reappeared {
+			let tx = match out.tx_log_entry {
+				Some(id) => w
+					.tx_log_iter()?
+					.find(|t| t.id == id && t.parent_key_id == parent_key_id),
+				None => None,
+			};
+			let tx = match tx {
+				Some(t) => t,
+				None => continue,
+			};
+			let commit = grin_util::to_hex(commit.0.to_vec());
+			warn!(
+				"Spent output {} has reappeared on chain, the transaction creating it may \
+				 have been replayed",
+				commit
+			);
+			anomalies.push(TxAnomaly {
+				kind: AnomalyKind::Replayed,
+				tx_id: tx.id,
+				tx_slate_id: tx.tx_slate_id,
+				description: format!(
+					"Output {} spent by this transaction (kernel {}) is unspent on chain again",
+					commit,
+					tx.kernel_excess.as_ref().map_or("unknown", |k| k.as_str())
+				),
+				suggested_action: RemediationAction::CheckRepair,
+			});
+		}
+	}
+	Ok((validated, anomalies))
+}
+
+/// Checks whether any inputs of the given transaction are already spent, either
+/// on chain or by another transaction created by this wallet
+pub fn check_tx_conflicts<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	tx: &Transaction,
+) -> Result<Vec<TxConflict>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let mut conflicts: Vec<TxConflict> = spent_inputs(w, tx)?
+		.into_iter()
+		.map(|commit| TxConflict::InputNotUnspent { commit })
+		.collect();
+	for (entry, shared) in wallet_tx_conflicts(w, tx)? {
+		for commit in shared {
+			conflicts.push(TxConflict::WalletTx {
+				commit,
+				tx_id: entry.id,
+				tx_slate_id: entry.tx_slate_id,
+				cancelled: entry.tx_type == TxLogEntryType::TxSentCancelled,
+			});
+		}
+	}
+	if !conflicts.is_empty() {
+		warn!(
+			"api: check_tx_conflicts: tx {} has {} conflicting input(s)",
+			tx.hash(),
+			conflicts.len()
+		);
+	}
+	Ok(conflicts)
+}
+
+/// Checks a transaction for conflicting spends before it's posted. Inputs
+/// already spent on chain always fail the check, as do inputs also spent by
+/// another unconfirmed transaction of this wallet, unless `replace` is set.
+/// Those transactions are then marked as replaced by this one
+pub fn check_post_tx<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	tx: &Transaction,
+	replace: bool,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let spent = spent_inputs(w, tx)?;
+	if let Some(commit) = spent.first() {
+		return Err(ErrorKind::GenericError(format!(
+			"Transaction input {} already spent on chain",
+			log_commit(commit)
+		)))?;
+	}
+	let conflicts: Vec<_> = wallet_tx_conflicts(w, tx)?
+		.into_iter()
+		.filter(|(t, _)| t.tx_type == TxLogEntryType::TxSent)
+		.collect();
+	if conflicts.is_empty() {
+		return Ok(());
+	}
+	if !replace {
+		let ids: Vec<String> = conflicts.iter().map(|(t, _)| t.id.to_string()).collect();
+		return Err(ErrorKind::GenericError(format!(
+			"Transaction conflicts with earlier wallet transactions {}",
+			ids.join(", ")
+		)))?;
+	}
+	for (entry, shared) in conflicts {
+		info!(
+			"api: check_post_tx: tx {} is replaced by tx {}",
+			entry.id,
+			tx.hash()
+		);
+		let outputs = updater::retrieve_outputs(
+			&mut *w,
+			keychain_mask,
+			false,
+			Some(entry.id),
+			Some(&entry.parent_key_id),
+		)?;
+		updater::replace_tx(&mut *w, keychain_mask, entry, outputs, &shared)?;
+	}
+	Ok(())
+}
+
+/// Inputs of the transaction no longer in the node's UTXO set, which can't be
+/// spent again
+fn spent_inputs<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	tx: &Transaction,
+) -> Result<Vec<pedersen::Commitment>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let commits: Vec<pedersen::Commitment> = tx.inputs().iter().map(|i| i.commitment()).collect();
+	let unspent = w.w2n_client().get_outputs_from_node(commits.clone())?;
+	Ok(commits
+		.into_iter()
+		.filter(|c| !unspent.contains_key(c))
+		.collect())
+}
+
+/// Other unconfirmed sent transactions of ours, which may be sitting in the
+/// mempool, spending any of the same inputs, along with the inputs shared
+fn wallet_tx_conflicts<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	tx: &Transaction,
+) -> Result<Vec<(TxLogEntry, Vec<pedersen::Commitment>)>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let commits: Vec<pedersen::Commitment> = tx.inputs().iter().map(|i| i.commitment()).collect();
+	let tx_hash = tx.hash();
+	let entries: Vec<TxLogEntry> = w
+		.tx_log_iter()?
+		.filter(|t| {
+			!t.confirmed
+				&& t.stored_tx.is_some()
+				&& (t.tx_type == TxLogEntryType::TxSent
+					|| t.tx_type == TxLogEntryType::TxSentCancelled)
+		})
+		.collect();
+	let mut conflicts = vec![];
+	for entry in entries {
+		let other = match w.get_stored_tx(&entry) {
+			Ok(Some(t)) => t,
+			_ => continue,
+		};
+		if other.hash() == tx_hash {
+			continue;
+		}
+		let shared: Vec<pedersen::Commitment> = other
+			.inputs()
+			.iter()
+			.map(|i| i.commitment())
+			.filter(|c| commits.contains(c))
+			.collect();
+		if !shared.is_empty() {
+			conflicts.push((entry, shared));
+		}
+	}
+	Ok(conflicts)
+}
+
+/// Returns the Dandelion preference stored with the unconfirmed wallet
+/// transaction sharing a kernel with the given transaction, if any
+pub fn stored_fluff<'a, T: ?Sized, C, K>(w: &mut T, tx: &Transaction) -> Result<Option<bool>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let excesses: Vec<pedersen::Commitment> = tx.kernels().iter().map(|k| k.excess).collect();
+	let entries: Vec<TxLogEntry> = w
+		.tx_log_iter()?
+		.filter(|t| !t.confirmed && t.fluff.is_some() && t.stored_tx.is_some())
+		.collect();
+	for entry in entries {
+		let stored = match w.get_stored_tx(&entry) {
+			Ok(Some(t)) => t,
+			_ => continue,
+		};
+		if stored
+			.kernels()
+			.iter()
+			.any(|k| excesses.contains(&k.excess))
+		{
+			return Ok(entry.fluff);
+		}
+	}
+	Ok(None)
+}
+
+/// Posts a transaction to the chain
+/// take a client impl instead of wallet so as not to have to lock the wallet
+pub fn post_tx<'a, C>(client: &C, tx: &Transaction, fluff: bool) -> Result<(), Error>
+where
+	C: NodeClient + 'a,
+{
+	let tx_hex = grin_util::to_hex(ser::ser_vec(tx, ser::ProtocolVersion::local()).unwrap());
+	let res = client.post_tx(&TxWrapper { tx_hex: tx_hex }, fluff);
+	if let Err(e) = res {
+		error!("api: post_tx: failed with error: {}", e);
+		Err(e)
+	} else {
+		debug!(
+			"api: post_tx: successfully posted tx: {}, fluff? {}",
+			tx.hash(),
+			fluff
+		);
+		Ok(())
+	}
+}
+
+/// verify slate messages
+pub fn verify_slate_messages(slate: &Slate) -> Result<(), Error> {
+	slate.verify_messages()
+}
+
+/// Runs full local validation on a finalized transaction, collecting the
+/// reasons it would be rejected by a node
+/// take a client impl instead of wallet so as not to have to lock the wallet
+pub fn validate_tx<'a, C>(client: &C, tx: &Transaction) -> Result<TxValidationResult, Error>
+where
+	C: NodeClient + 'a,
+{
+	let mut reasons = vec![];
+
+	let required = tx_fee(
+		tx.inputs().len(),
+		tx.outputs().len(),
+		tx.kernels().len(),
+		None,
+	);
+	if tx.fee() < required {
+		reasons.push(TxRejectionReason::InsufficientFee {
+			fee: tx.fee(),
+			required,
+		});
+	}
+
+	let verifier_cache = Arc::new(RwLock::new(LruVerifierCache::new()));
+	if let Err(e) = tx.validate(Weighting::AsTransaction, verifier_cache) {
+		debug!(
+			"api: validate_tx: tx {} failed validation: {:?}",
+			tx.hash(),
+			e
+		);
+		let reason = match e {
+			transaction::Error::TooHeavy => TxRejectionReason::TooHeavy,
+			transaction::Error::IncorrectSignature => TxRejectionReason::InvalidSignature,
+			transaction::Error::InvalidRangeProof => TxRejectionReason::InvalidRangeProof,
+			transaction::Error::KernelSumMismatch => TxRejectionReason::KernelSumMismatch,
+			transaction::Error::CutThrough => TxRejectionReason::CutThrough,
+			e => TxRejectionReason::Other(format!("{:?}", e)),
+		};
+		reasons.push(reason);
+	}
+
+	let commits: Vec<pedersen::Commitment> = tx.inputs().iter().map(|i| i.commitment()).collect();
+	let unspent = client.get_outputs_from_node(commits.clone())?;
+	let unknown: Vec<String> = commits
+		.iter()
+		.filter(|c| !unspent.contains_key(c))
+		.map(|c| grin_util::to_hex(c.as_ref().to_vec()))
+		.collect();
+	if !unknown.is_empty() {
+		reasons.push(TxRejectionReason::UnknownInputs(unknown));
+	}
+
+	Ok(TxValidationResult {
+		is_valid: reasons.is_empty(),
+		reasons,
+	})
+}
+
+/// Attempt to restore contents of wallet
+pub fn restore<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	cancel: Option<&CancelToken>,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	w.restore(keychain_mask, cancel)
+}
+
+/// check repair
+pub fn check_repair<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	delete_unconfirmed: bool,
+	full: bool,
+	cancel: Option<&CancelToken>,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	update_outputs(w, keychain_mask, true)?;
+	w.check_repair(keychain_mask, delete_unconfirmed, full, cancel)
+}
+
+/// Compare the wallet's outputs and transaction log against the UTXO set,
+/// reporting where they disagree without changing the wallet
+pub fn audit_chain<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+) -> Result<ChainAuditReport, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	w.audit_chain(keychain_mask)
+}
+
+/// The wallet's feature flags
+pub fn get_feature_flags<'a, T: ?Sized, C, K>(w: &mut T) -> Result<FeatureFlags, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	w.feature_flags()
+}
+
+/// Replace the wallet's feature flags
+pub fn set_feature_flags<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	flags: FeatureFlags,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	info!("Setting wallet feature flags to {:?}", flags);
+	let mut batch = w.batch(keychain_mask)?;
+	batch.save_feature_flags(flags)?;
+	batch.commit()?;
+	Ok(())
+}
+
+/// Check the given words against the recovery phrase the wallet generated,
+/// each with its position in the phrase counting from 1. If all are right,
+/// and there are at least `SEED_CHECK_MIN_WORDS` of them, the phrase is
+/// recorded as verified. Returns whether the words were all right
+pub fn verify_seed_backup<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	words: &[(u32, String)],
+) -> Result<bool, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let mut check = match w.seed_check()? {
+		Some(c) => c,
+		None => {
+			return Err(ErrorKind::GenericError(
+				"The wallet has no generated recovery phrase to verify".to_owned(),
+			))?
+		}
+	};
+	let mut positions: Vec<u32> = words.iter().map(|(p, _)| *p).collect();
+	positions.sort();
+	positions.dedup();
+	if positions.len() < SEED_CHECK_MIN_WORDS {
+		return Err(ErrorKind::GenericError(format!(
+			"At least {} words of the recovery phrase are needed",
+			SEED_CHECK_MIN_WORDS
+		)))?;
+	}
+	let keychain = w.keychain(keychain_mask)?;
+	for (position, word) in words {
+		if !check.check_word(&keychain, *position, word)? {
+			return Ok(false);
+		}
+	}
+	if !check.verified {
+		info!("Recovery phrase verified");
+		check.verified = true;
+		let mut batch = w.batch(keychain_mask)?;
+		batch.save_seed_check(&check)?;
+		batch.commit()?;
+	}
+	Ok(true)
+}
+
+fn second_factor_error(msg: &str) -> Error {
+	ErrorKind::SecondFactor(msg.to_owned()).into()
+}
+
+/// Enroll a new TOTP secret for spends, replacing any not yet confirmed.
+/// Codes are only asked for once one from the secret has been confirmed
+pub fn enroll_totp<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	use_test_rng: bool,
+) -> Result<TotpEnrollment, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	if w.totp_secret()?.map_or(false, |s| s.enabled) {
+		return Err(second_factor_error(
+			"a TOTP secret is already enrolled, disable it first",
+		));
+	}
+	let secret = TotpSecret::new(use_test_rng);
+	let mut batch = w.batch(keychain_mask)?;
+	batch.save_totp_secret(&secret)?;
+	batch.commit()?;
+	Ok(TotpEnrollment {
+		uri: secret.provisioning_uri("wallet"),
+		secret: secret.secret,
+	})
+}
+
+/// Confirm the TOTP secret being enrolled with a code from it, after which
+/// codes are asked for on spends
+pub fn confirm_totp<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	code: &str,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let mut secret = match w.totp_secret()? {
+		Some(s) if !s.enabled => s,
+		Some(_) => return Err(second_factor_error("the TOTP secret is already confirmed")),
+		None => return Err(second_factor_error("no TOTP secret is being enrolled")),
+	};
+	if !secret.verify(code, Utc::now().timestamp() as u64) {
+		return Err(second_factor_error("invalid code"));
+	}
+	secret.enabled = true;
+	let mut batch = w.batch(keychain_mask)?;
+	batch.save_totp_secret(&secret)?;
+	batch.commit()?;
+	info!(target: "audit", "TOTP second factor enabled");
+	Ok(())
+}
+
+/// Remove the TOTP secret, given a valid code from it if it's confirmed
+pub fn disable_totp<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	code: &str,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let _ = w.keychain(keychain_mask)?;
+	match w.totp_secret()? {
+		Some(mut s) => {
+			if s.enabled && !s.verify(code, Utc::now().timestamp() as u64) {
+				return Err(second_factor_error("invalid code"));
+			}
+		}
+		None => return Err(second_factor_error("no TOTP secret is enrolled")),
+	}
+	let mut batch = w.batch(keychain_mask)?;
+	batch.delete_totp_secret()?;
+	batch.commit()?;
+	info!(target: "audit", "TOTP second factor disabled");
+	Ok(())
+}
+
+/// Check the TOTP code given for a spend, if the wallet has a confirmed
+/// secret. A code is only accepted once
+pub fn check_totp<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	code: Option<&str>,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let mut secret = match w.totp_secret()? {
+		Some(s) if s.enabled => s,
+		_ => return Ok(()),
+	};
+	let code = match code {
+		Some(c) => c,
+		None => return Err(second_factor_error("a TOTP code is required")),
+	};
+	if !secret.verify(code, Utc::now().timestamp() as u64) {
+		warn!(target: "audit", "Spend refused with an invalid TOTP code");
+		return Err(second_factor_error("invalid code"));
+	}
+	let mut batch = w.batch(keychain_mask)?;
+	batch.save_totp_secret(&secret)?;
+	batch.commit()?;
+	Ok(())
+}
+
+/// node height
+pub fn node_height<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+) -> Result<NodeHeightResult, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let res = w.w2n_client().get_chain_height();
+	match res {
+		Ok(height) => Ok(NodeHeightResult {
+			height,
+			updated_from_node: true,
+		}),
+		Err(_) => {
+			let outputs = retrieve_outputs(w, keychain_mask, true, false, None)?;
+			let height = match outputs.1.iter().map(|m| m.output.height).max() {
+				Some(height) => height,
+				None => 0,
+			};
+			Ok(NodeHeightResult {
+				height,
+				updated_from_node: false,
+			})
+		}
+	}
+}
+
+/// Refresh the active account's outputs and transactions against the node,
+/// reporting progress on the given channel, if any. Returns whether the
+/// refresh was successful, or fails if the node is throttling the wallet's
+/// requests so the caller can back off
+pub fn update_wallet_state<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	status_send_channel: &Option<Sender<StatusMessage>>,
+) -> Result<bool, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let send = |m: StatusMessage| {
+		if let Some(s) = status_send_channel {
+			let _ = s.send(m);
+		}
+	};
+	let parent_key_id = w.parent_key_id();
+	send(StatusMessage::UpdatingOutputs(
+		"Updating outputs and transactions from node".to_owned(),
+	));
+	match updater::refresh_outputs(&mut *w, keychain_mask, &parent_key_id, false) {
+		Ok(_) => {
+			send(StatusMessage::UpdateComplete(
+				"Outputs and transactions are up to date".to_owned(),
+			));
+			Ok(true)
+		}
+		Err(e) => {
+			if let ErrorKind::InvalidKeychainMask = e.kind() {
+				return Err(e);
+			}
+			send(StatusMessage::UpdateWarning(format!(
+				"Could not update outputs from node: {}",
+				e
+			)));
+			match e.kind() {
+				ErrorKind::NodeThrottled(_) => Err(e),
+				_ => Ok(false),
+			}
+		}
+	}
+}
+
+/// Cancel the active account's transactions left unconfirmed for `blocks`
+/// blocks or more since they were created, as of the chain height the wallet
+/// was last refreshed at, unlocking their inputs. Meant to follow a
+/// successful refresh, so transactions confirmed meanwhile aren't cancelled
+pub fn cancel_stale_txs<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	blocks: u64,
+	status_send_channel: &Option<Sender<StatusMessage>>,
+) -> Result<Vec<TxLogEntry>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = w.parent_key_id();
+	let height = w.last_confirmed_height()?;
+	let cancelled = tx::cancel_stale_txs(&mut *w, keychain_mask, &parent_key_id, height, blocks)?;
+	if let Some(s) = status_send_channel {
+		for t in cancelled.iter() {
+			let _ = s.send(StatusMessage::TxExpired(format!(
+				"Cancelled transaction {} after {} blocks without confirmation",
+				t.id, blocks
+			)));
+		}
+	}
+	Ok(cancelled)
+}
+
+/// Spend the active account's confirmed received outputs back to the wallet
+/// and post the transaction, anchoring received funds in outputs created by
+/// the wallet's own transaction rather than by a sender able to replay theirs.
+/// Outputs are spent once they have the confirmations needed for received
+/// outputs, or one if none are set, and only if together they cover the fee.
+/// Returns the transaction posted, if any
+pub fn self_spend_received<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	status_send_channel: &Option<Sender<StatusMessage>>,
+) -> Result<Option<Slate>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = w.parent_key_id();
+	let height = w.last_confirmed_height()?;
+	let minimum_confirmations = spend_confirmations().required(OutputSource::Received, 1);
+	let received: HashSet<u32> = w
+		.tx_log_iter()?
+		.filter(|t| {
+			t.parent_key_id == parent_key_id
+				&& t.tx_type == TxLogEntryType::TxReceived
+				&& t.confirmed
+		})
+		.map(|t| t.id)
+		.collect();
+	let coins: Vec<OutputData> = w
+		.iter()?
+		.filter(|o| {
+			o.root_key_id == parent_key_id
+				&& !o.is_coinbase
+				&& o.tx_log_entry.map_or(false, |id| received.contains(&id))
+				&& o.eligible_to_spend(height, minimum_confirmations)
+		})
+		.take(MAX_SELF_SPEND_INPUTS)
+		.collect();
+	let total: u64 = coins.iter().map(|c| c.value).sum();
+	if coins.is_empty() || total <= tx_fee(coins.len(), 1, 1, None) {
+		return Ok(None);
+	}
+
+	let slate = tx::consolidation_tx(&mut *w, keychain_mask, &coins, 1, &parent_key_id, false)?;
+	if let Err(e) = post_tx(w.w2n_client(), &slate.tx, false) {
+		// unlock the outputs to be spent next time
+		tx::cancel_tx(&mut *w, keychain_mask, &parent_key_id, None, Some(slate.id))?;
+		return Err(e);
+	}
+	if let Some(s) = status_send_channel {
+		let _ = s.send(StatusMessage::ReceivedSelfSpent(format!(
+			"Spent {} received output(s) back to the wallet in transaction {}",
+			coins.len(),
+			slate.id
+		)));
+	}
+	Ok(Some(slate))
+}
+
+/// Attempt to update outputs in wallet, return whether it was successful
+fn update_outputs<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	update_all: bool,
+) -> Result<bool, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = w.parent_key_id();
+	update_account_outputs(w, keychain_mask, &parent_key_id, update_all)
+}
+
+/// As [`update_outputs`](fn.update_outputs.html), for the outputs of the
+/// account with the given parent key
+fn update_account_outputs<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	parent_key_id: &Identifier,
+	update_all: bool,
+) -> Result<bool, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	match updater::refresh_outputs(&mut *w, keychain_mask, parent_key_id, update_all) {
+		Ok(_) => Ok(true),
+		Err(e) => {
+			if let ErrorKind::InvalidKeychainMask = e.kind() {
+				return Err(e);
+			}
+			Ok(false)
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::grin_core::core::TxKernel;
+	use crate::grin_core::libtx::{build, ProofBuilder};
+	use crate::grin_keychain::{ExtKeychain, ExtKeychainPath};
+	use crate::NodeVersionInfo;
+	use std::collections::HashMap;
+
+	/// Node knowing only the given unspent outputs
+	#[derive(Clone)]
+	struct UtxoClient(Vec<pedersen::Commitment>);
+
+	impl NodeClient for UtxoClient {
+		fn node_url(&self) -> &str {
+			"http://127.0.0.1:3413"
+		}
+		fn set_node_url(&mut self, _: &str) {}
+		fn node_api_secret(&self) -> Option<String> {
+			None
+		}
+		fn set_node_api_secret(&mut self, _: Option<String>) {}
+		fn post_tx(&self, _: &TxWrapper, _: bool) -> Result<(), Error> {
+			unimplemented!()
+		}
+		fn get_version_info(&mut self) -> Option<NodeVersionInfo> {
+			None
+		}
+		fn get_chain_height(&self) -> Result<u64, Error> {
+			Ok(10)
+		}
+		fn get_outputs_from_node(
+			&self,
+			wallet_outputs: Vec<pedersen::Commitment>,
+		) -> Result<HashMap<pedersen::Commitment, (String, u64, u64)>, Error> {
+			Ok(wallet_outputs
+				.into_iter()
+				.filter(|c| self.0.contains(c))
+				.map(|c| (c, (grin_util::to_hex(c.as_ref().to_vec()), 1, 1)))
+				.collect())
+		}
+		fn get_kernel(
+			&self,
+			_: &pedersen::Commitment,
+			_: Option<u64>,
+			_: Option<u64>,
+		) -> Result<Option<(TxKernel, u64, u64)>, Error> {
+			unimplemented!()
+		}
+		fn get_outputs_by_pmmr_index(
+			&self,
+			_: u64,
+			_: u64,
+		) -> Result<
+			(
+				u64,
+				u64,
+				Vec<(pedersen::Commitment, pedersen::RangeProof, bool, u64, u64)>,
+			),
+			Error,
+		> {
+			unimplemented!()
+		}
+	}
+
+	fn spend(keychain: &ExtKeychain, n: u32) -> Transaction {
+		let builder = ProofBuilder::new(keychain);
+		let key_id1 = ExtKeychainPath::new(1, n, 0, 0, 0).to_identifier();
+		let key_id2 = ExtKeychainPath::new(1, n + 1, 0, 0, 0).to_identifier();
+		let fee = tx_fee(1, 1, 1, None);
+		build::transaction(
+			vec![
+				build::input(60_000_000, key_id1),
+				build::output(60_000_000 - fee, key_id2),
+				build::with_fee(fee),
+			],
+			keychain,
+			&builder,
+		)
+		.unwrap()
+	}
+
+	#[test]
+	fn validate_tx_reasons() {
+		let keychain = ExtKeychain::from_random_seed(false).unwrap();
+		let tx = spend(&keychain, 1);
+		let client = UtxoClient(vec![tx.inputs()[0].commitment()]);
+
+		let res = validate_tx(&client, &tx).unwrap();
+		assert!(res.is_valid);
+		assert!(res.reasons.is_empty());
+
+		// signed over another transaction's excess
+		let mut bad_sig = tx.clone();
+		bad_sig.body.kernels[0].excess_sig = spend(&keychain, 3).kernels()[0].excess_sig;
+		let res = validate_tx(&client, &bad_sig).unwrap();
+		assert!(!res.is_valid);
+		assert_eq!(res.reasons, vec![TxRejectionReason::InvalidSignature]);
+
+		// spending outputs the node doesn't have
+		let commit = tx.inputs()[0].commitment();
+		let res = validate_tx(&UtxoClient(vec![]), &tx).unwrap();
+		assert!(!res.is_valid);
+		assert_eq!(
+			res.reasons,
+			vec![TxRejectionReason::UnknownInputs(vec![grin_util::to_hex(
+				commit.as_ref().to_vec()
+			)])]
+		);
+	}
 }
@@ -14,12 +14,17 @@
 
 //! Types specific to the wallet api, mostly argument serialization
 
+use crate::error::Error;
 use crate::grin_core::core::{Output, TxKernel};
 use crate::grin_core::libtx::secp_ser;
-use crate::grin_keychain::Identifier;
+use crate::grin_keychain::{ChildNumber, Identifier, SwitchCommitmentType};
 use crate::grin_util::secp::pedersen;
+use crate::slate::Slate;
+use crate::slate_versions::ser::opt_string_or_u64;
 use crate::slate_versions::SlateVersion;
-use crate::types::OutputData;
+use crate::types::{OutputData, OutputStatus};
+use chrono::prelude::*;
+use uuid::Uuid;
 
 /// Send TX API Args
 // TODO: This is here to ensure the legacy V1 API remains intact
@@ -66,8 +71,9 @@ pub struct InitTxArgs {
 	/// the whole amount, the wallet will include more outputs. This parameter should be considered
 	/// a soft limit.
 	pub max_outputs: u32,
-	/// The target number of change outputs to create in the transaction.
-	/// The actual number created will be `num_change_outputs` + whatever remainder is needed.
+	/// The number of change outputs to split the change between, about equally. Several change
+	/// outputs make later spends harder to link, and lock less of the balance while a send is
+	/// pending. Must be at least one, and fewer are created if there's too little change.
 	pub num_change_outputs: u32,
 	/// If `true`, attempt to use up as many outputs as
 	/// possible to create the transaction, up the 'soft limit' of `max_outputs`. This helps
@@ -77,6 +83,18 @@ pub struct InitTxArgs {
 	/// as many outputs as are needed to meet the amount, (and no more) starting with the smallest
 	/// value outputs.
 	pub selection_strategy_is_use_all: bool,
+	/// If `true`, eligible outputs are considered in a random order weighted by value
+	/// rather than smallest first, so that repeated sends from the same wallet don't
+	/// deterministically select the same inputs. Combined with `selection_strategy_is_use_all`,
+	/// a random subset of up to `max_outputs` outputs is used.
+	#[serde(default)]
+	pub randomize_selection: Option<bool>,
+	/// Name of the coin selection strategy choosing the inputs, overriding
+	/// `selection_strategy_is_use_all` and `randomize_selection`. One of the built-in `smallest`,
+	/// `all`, `random` and `random_all`, or a strategy registered by the application with
+	/// [`register_coin_selection`](../fn.register_coin_selection.html).
+	#[serde(default)]
+	pub selection_strategy: Option<String>,
 	/// An optional participant message to include alongside the sender's public
 	/// ParticipantData within the slate. This message will include a signature created with the
 	/// sender's private excess value, and will be publically verifiable. Note this message is for
@@ -95,13 +113,49 @@ pub struct InitTxArgs {
 	/// Sender arguments. If present, the underlying function will also attempt to send the
 	/// transaction to a destination and optionally finalize the result
 	pub send_args: Option<InitTxSendArgs>,
+	/// Optional list of further recipients to pay alongside the one given by `amount` and
+	/// `send_args`, in a send made with `init_send_tx_multi`. Each recipient completes its own
+	/// receive round via the method given in `send_args`, and the finalized transactions are
+	/// aggregated into a single transaction before posting. Requires `send_args` with
+	/// `finalize` set.
+	#[serde(default)]
+	pub additional_recipients: Option<Vec<InitTxRecipient>>,
+	/// If set, the number of blocks from the current height after which the transaction
+	/// expires. The cutoff height is carried in the slate, and neither party will receive or
+	/// finalize the transaction once the chain reaches it.
+	#[serde(with = "opt_string_or_u64")]
+	#[serde(default)]
+	pub ttl_blocks: Option<u64>,
+	/// If `true`, inputs are only selected and locked when the transaction is finalized, so
+	/// the wallet's outputs stay spendable while waiting for the recipient. The fee is fixed
+	/// when the transaction is created, and finalizing fails if the outputs available by then
+	/// need a higher one. Calling `tx_lock_outputs` does nothing for such a transaction.
+	#[serde(default)]
+	pub late_lock: Option<bool>,
+	/// Whether to skip the Dandelion stem phase when the transaction is posted, kept with the
+	/// stored transaction so later posts and reposts follow it. If `true`, the transaction is
+	/// broadcast to all peers immediately, if `false` it is stemmed. If `None`, posting
+	/// defaults to stemming.
+	#[serde(default)]
+	pub fluff: Option<bool>,
+}
+
+/// A single (amount, destination) pair for a multi-recipient send
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InitTxRecipient {
+	/// The amount to send to this recipient, in nanogrins
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub amount: u64,
+	/// The destination, contents will depend on the sending method
+	pub dest: String,
 }
 
 /// Send TX API Args, for convenience functionality that inits the transaction and sends
 /// in one go
 #[derive(Clone, Serialize, Deserialize)]
 pub struct InitTxSendArgs {
-	/// The transaction method. Can currently be 'http' or 'keybase'.
+	/// The transaction method, 'http', 'keybase' or that of another registered transport.
+	/// Ignored if the destination is a URI with a registered scheme
 	pub method: String,
 	/// The destination, contents will depend on the particular method
 	pub dest: String,
@@ -109,7 +163,8 @@ pub struct InitTxSendArgs {
 	pub finalize: bool,
 	/// Whether to post the transasction if the send and finalize were successful
 	pub post_tx: bool,
-	/// Whether to use dandelion when posting. If false, skip the dandelion relay
+	/// Whether to skip the dandelion relay when posting. If false, the preference given by
+	/// `fluff` in [`InitTxArgs`](struct.InitTxArgs.html) is followed
 	pub fluff: bool,
 }
 
@@ -122,10 +177,86 @@ impl Default for InitTxArgs {
 			max_outputs: 500,
 			num_change_outputs: 1,
 			selection_strategy_is_use_all: true,
+			randomize_selection: None,
+			selection_strategy: None,
 			message: None,
 			target_slate_version: None,
 			estimate_only: Some(false),
 			send_args: None,
+			additional_recipients: None,
+			ttl_blocks: None,
+			late_lock: None,
+			fluff: None,
+		}
+	}
+}
+
+/// Output consolidation args
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConsolidateArgs {
+	/// The human readable account name whose outputs should be consolidated, overriding
+	/// whatever the active account is as set via the
+	/// [`set_active_account`](../grin_wallet_api/owner/struct.Owner.html#method.set_active_account) method.
+	pub src_acct_name: Option<String>,
+	/// The minimum number of confirmations an output should have in order to be consolidated.
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub minimum_confirmations: u64,
+	/// The number of outputs each consolidation transaction creates, splitting the combined
+	/// value between them about equally.
+	pub num_outputs: u32,
+	/// The maximum weight of each transaction, as counted towards the block weight limit. This
+	/// bounds the number of outputs a single transaction can combine.
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub max_weight: u64,
+	/// The maximum number of transactions to create. Outputs left over once a transaction is
+	/// full are combined in further transactions, up to this number.
+	pub max_txs: u32,
+	/// Whether to post the transactions once created.
+	pub post_tx: bool,
+	/// Whether to use dandelion when posting. If false, skip the dandelion relay
+	pub fluff: bool,
+}
+
+impl Default for ConsolidateArgs {
+	fn default() -> ConsolidateArgs {
+		ConsolidateArgs {
+			src_acct_name: None,
+			minimum_confirmations: 10,
+			num_outputs: 1,
+			max_weight: 4_000,
+			max_txs: 1,
+			post_tx: true,
+			fluff: false,
+		}
+	}
+}
+
+/// Recovery transaction args
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecoveryTxArgs {
+	/// The human readable account name whose outputs should be swept, overriding whatever the
+	/// active account is as set via the
+	/// [`set_active_account`](../grin_wallet_api/owner/struct.Owner.html#method.set_active_account) method.
+	pub src_acct_name: Option<String>,
+	/// The number of blocks from the current height before the transaction can be included in
+	/// a block. The wallet has to be used within this window to keep its funds from being
+	/// recovered.
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub lock_blocks: u64,
+	/// The minimum number of confirmations an output should have in order to be swept.
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub minimum_confirmations: u64,
+}
+
+impl Default for RecoveryTxArgs {
+	fn default() -> RecoveryTxArgs {
+		RecoveryTxArgs {
+			src_acct_name: None,
+			// about a year
+			lock_blocks: 525_600,
+			minimum_confirmations: 10,
 		}
 	}
 }
@@ -146,6 +277,10 @@ pub struct IssueInvoiceTxArgs {
 	/// down to the minimum slate version compatible with the current. If `None` the slate
 	/// is generated with the latest version.
 	pub target_slate_version: Option<u16>,
+	/// Whether to skip the Dandelion stem phase when the paid invoice is posted, kept with the
+	/// stored transaction. See the `fluff` field of [`InitTxArgs`](struct.InitTxArgs.html).
+	#[serde(default)]
+	pub fluff: Option<bool>,
 }
 
 impl Default for IssueInvoiceTxArgs {
@@ -155,6 +290,7 @@ impl Default for IssueInvoiceTxArgs {
 			amount: 0,
 			message: None,
 			target_slate_version: None,
+			fluff: None,
 		}
 	}
 }
@@ -221,3 +357,424 @@ pub struct VersionInfo {
 	/// Slate version
 	pub supported_slate_versions: Vec<SlateVersion>,
 }
+
+/// Reason a transaction was rejected by local validation
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum TxRejectionReason {
+	/// The transaction exceeds the maximum transaction weight
+	TooHeavy,
+	/// The fee is lower than the minimum fee for the transaction's weight
+	InsufficientFee {
+		/// Fee included in the transaction
+		#[serde(with = "secp_ser::string_or_u64")]
+		fee: u64,
+		/// Minimum fee required
+		#[serde(with = "secp_ser::string_or_u64")]
+		required: u64,
+	},
+	/// One or more kernel signatures failed to verify
+	InvalidSignature,
+	/// One or more rangeproofs failed to verify
+	InvalidRangeProof,
+	/// Sum of input/output commitments doesn't match the kernel excesses and offset
+	KernelSumMismatch,
+	/// The transaction spends one of its own outputs
+	CutThrough,
+	/// Inputs, given as hex commitments, that aren't unspent outputs known to the node
+	UnknownInputs(Vec<String>),
+	/// Any other consensus rule violation
+	Other(String),
+}
+
+/// Result of local transaction validation
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TxValidationResult {
+	/// Whether the transaction passed all checks
+	pub is_valid: bool,
+	/// Reasons for rejection, empty if the transaction is valid
+	pub reasons: Vec<TxRejectionReason>,
+}
+
+/// Transaction status filter used by [`RetrieveTxQueryArgs`](struct.RetrieveTxQueryArgs.html)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum TxQueryStatus {
+	/// Sent or received transactions that are not yet confirmed
+	Unconfirmed,
+	/// Confirmed transactions, including coinbase
+	Confirmed,
+	/// Sent or received transactions that were cancelled
+	Cancelled,
+	/// Sent transactions, including cancelled ones
+	Sent,
+	/// Received transactions, including cancelled ones
+	Received,
+	/// Confirmed coinbase transactions
+	Coinbase,
+}
+
+/// Sort order of transaction query results
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum TxSortOrder {
+	/// Ascending transaction id (oldest first)
+	Asc,
+	/// Descending transaction id (newest first)
+	Desc,
+}
+
+/// Paging, filtering and sorting arguments for transaction queries
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RetrieveTxQueryArgs {
+	/// Number of matching entries to skip
+	pub offset: Option<u32>,
+	/// Maximum number of entries to return
+	pub limit: Option<u32>,
+	/// If present, only return entries matching any of the given statuses
+	pub statuses: Option<Vec<TxQueryStatus>>,
+	/// If present, only return entries created at or after this time
+	pub min_creation_ts: Option<DateTime<Utc>>,
+	/// If present, only return entries created at or before this time
+	pub max_creation_ts: Option<DateTime<Utc>>,
+	/// Order of results, defaults to ascending
+	pub sort_order: Option<TxSortOrder>,
+	/// The human readable account name whose transactions should be returned,
+	/// overriding whatever the active account is
+	pub account: Option<String>,
+}
+
+/// A conflict between a transaction about to be posted and spends
+/// already known to the node or to this wallet
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum TxConflict {
+	/// The input is not in the node's UTXO set, most likely because it has
+	/// already been spent by a confirmed transaction
+	InputNotUnspent {
+		/// The input commitment
+		#[serde(
+			serialize_with = "secp_ser::as_hex",
+			deserialize_with = "secp_ser::commitment_from_hex"
+		)]
+		commit: pedersen::Commitment,
+	},
+	/// The input is also spent by another transaction created by this wallet,
+	/// which may already have been posted to the node's mempool
+	WalletTx {
+		/// The input commitment
+		#[serde(
+			serialize_with = "secp_ser::as_hex",
+			deserialize_with = "secp_ser::commitment_from_hex"
+		)]
+		commit: pedersen::Commitment,
+		/// Id of the conflicting transaction log entry
+		tx_id: u32,
+		/// Slate id of the conflicting transaction
+		tx_slate_id: Option<Uuid>,
+		/// Whether the conflicting transaction has been cancelled in this wallet
+		cancelled: bool,
+	},
+}
+
+/// Filtering arguments for output queries
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RetrieveOutputsQueryArgs {
+	/// If present, only return outputs with any of the given statuses
+	pub statuses: Option<Vec<OutputStatus>>,
+	/// If present, only return outputs with a block height at or above this height
+	pub min_height: Option<u64>,
+	/// If present, only return outputs with a block height at or below this height
+	pub max_height: Option<u64>,
+	/// The human readable account name whose outputs should be returned, overriding
+	/// whatever the active account is
+	pub account: Option<String>,
+}
+
+/// Progress and warnings reported by the background updater
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum StatusMessage {
+	/// Outputs are being refreshed against the node
+	UpdatingOutputs(String),
+	/// The refresh completed
+	UpdateComplete(String),
+	/// The refresh failed, leaving the wallet's state as it was
+	UpdateWarning(String),
+	/// A transaction left unconfirmed too long was cancelled
+	TxExpired(String),
+	/// Received outputs were spent back to the wallet
+	ReceivedSelfSpent(String),
+}
+
+/// Kinds of transaction needing the user's attention
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum AnomalyKind {
+	/// A sent transaction still unconfirmed after the threshold
+	StuckUnconfirmed,
+	/// A cancelled transaction whose outputs are still locked or unconfirmed
+	CancelledWithLockedOutputs,
+	/// A received transaction the sender hasn't finalized or posted within the threshold
+	ReceivedNotFinalized,
+	/// A confirmed transaction whose outputs are no longer on chain, most likely
+	/// because its block was reorganised away
+	Reorged,
+	/// An output the transaction spent is unspent on chain again, most likely
+	/// because the transaction creating it was replayed
+	Replayed,
+}
+
+/// Action suggested to resolve an anomaly
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum RemediationAction {
+	/// Repost the stored, finalized transaction
+	Repost,
+	/// Cancel the transaction, unlocking its inputs
+	Cancel,
+	/// Check the wallet's outputs against the chain with check_repair
+	CheckRepair,
+}
+
+/// A transaction needing the user's attention
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TxAnomaly {
+	/// What is wrong with the transaction
+	pub kind: AnomalyKind,
+	/// Id of the transaction log entry
+	pub tx_id: u32,
+	/// Slate id of the transaction, if any
+	pub tx_slate_id: Option<Uuid>,
+	/// Human readable description of the problem
+	pub description: String,
+	/// Suggested way to resolve it
+	pub suggested_action: RemediationAction,
+}
+
+/// Outcome for one transaction of a batch operation, such as
+/// [`cancel_txs`](../grin_wallet_api/struct.Owner.html#method.cancel_txs). Each
+/// transaction succeeds or fails on its own
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TxBatchResult {
+	/// Id of the transaction log entry
+	pub tx_id: u32,
+	/// Why the operation failed for the transaction, if it did
+	pub error: Option<String>,
+}
+
+impl TxBatchResult {
+	/// The outcome of the operation on the given transaction
+	pub fn new(tx_id: u32, res: Result<(), Error>) -> TxBatchResult {
+		TxBatchResult {
+			tx_id,
+			error: res.err().map(|e| e.kind().to_string()),
+		}
+	}
+}
+
+/// An output whose record in the wallet disagrees with the chain, as listed in
+/// a [`ChainAuditReport`](struct.ChainAuditReport.html)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditedOutput {
+	/// The output's commitment
+	#[serde(
+		serialize_with = "secp_ser::as_hex",
+		deserialize_with = "secp_ser::commitment_from_hex"
+	)]
+	pub commit: pedersen::Commitment,
+	/// The output's key
+	pub key_id: Identifier,
+	/// Value of the output
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub value: u64,
+	/// Status of the output in the wallet, none if the wallet has no record of it
+	pub status: Option<OutputStatus>,
+	/// Transaction log entry the wallet ties the output to, if any
+	pub tx_log_entry: Option<u32>,
+	/// Height of the block the output was included in, if it's in the UTXO set
+	#[serde(with = "opt_string_or_u64")]
+	pub chain_height: Option<u64>,
+}
+
+/// Where the wallet's outputs and transaction log disagree with the UTXO set,
+/// returned by [`audit_chain`](../grin_wallet_api/struct.Owner.html#method.audit_chain).
+/// Nothing in the wallet is changed by the audit
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ChainAuditReport {
+	/// Chain height the audit was made at
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub height: u64,
+	/// Outputs of the wallet's keys in the UTXO set the wallet has no record of
+	pub unknown_outputs: Vec<AuditedOutput>,
+	/// Outputs the wallet holds as unspent that aren't in the UTXO set, spent
+	/// elsewhere or reorged out
+	pub missing_outputs: Vec<AuditedOutput>,
+	/// Outputs locked by a transaction of the wallet that have left the UTXO
+	/// set, spent but not yet marked as such
+	pub spent_unmarked_outputs: Vec<AuditedOutput>,
+	/// Outputs the wallet holds as spent that are still in the UTXO set
+	pub unspent_marked_spent_outputs: Vec<AuditedOutput>,
+	/// Outputs the wallet holds as unconfirmed that are in the UTXO set
+	pub confirmed_unmarked_outputs: Vec<AuditedOutput>,
+	/// Ids of the transaction log entries not marked confirmed although outputs
+	/// they created are in the UTXO set
+	pub unconfirmed_txs_on_chain: Vec<u32>,
+}
+
+impl ChainAuditReport {
+	/// Whether the wallet agrees with the chain
+	pub fn is_clean(&self) -> bool {
+		self.unknown_outputs.is_empty()
+			&& self.missing_outputs.is_empty()
+			&& self.spent_unmarked_outputs.is_empty()
+			&& self.unspent_marked_spent_outputs.is_empty()
+			&& self.confirmed_unmarked_outputs.is_empty()
+			&& self.unconfirmed_txs_on_chain.is_empty()
+	}
+}
+
+/// How the key of a wallet output is derived from the wallet's seed, returned
+/// by [`get_output_derivation`](../grin_wallet_api/struct.Owner.html#method.get_output_derivation)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OutputDerivation {
+	/// The output's commitment
+	#[serde(
+		serialize_with = "secp_ser::as_hex",
+		deserialize_with = "secp_ser::commitment_from_hex"
+	)]
+	pub commit: pedersen::Commitment,
+	/// Label of the account the output belongs to, if it still has one
+	pub account: Option<String>,
+	/// Key of the account the output's key is derived from
+	pub parent_key_id: Identifier,
+	/// The output's key
+	pub key_id: Identifier,
+	/// Derivation path of the output's key from the seed's root key, as `m/0/0/5`
+	pub path: String,
+	/// Index of the output's key among its account's keys, the last element
+	/// of its path
+	pub key_index: u32,
+	/// Switch commitment scheme of the output's blinding factor
+	pub switch_commitment_type: String,
+}
+
+impl OutputDerivation {
+	/// The derivation of the given output, belonging to the given account
+	pub fn new(output: &OutputCommitMapping, account: Option<String>) -> OutputDerivation {
+		let path = output.output.key_id.to_path();
+		let elements: Vec<String> = path.path[..path.depth as usize]
+			.iter()
+			.map(|c| <u32>::from(*c).to_string())
+			.collect();
+		OutputDerivation {
+			commit: output.commit,
+			account,
+			parent_key_id: output.output.root_key_id.clone(),
+			key_id: output.output.key_id.clone(),
+			path: format!("m/{}", elements.join("/")),
+			key_index: path.last_path_index(),
+			// the wallet builds all its outputs with regular switch commitments
+			switch_commitment_type: format!("{:?}", SwitchCommitmentType::Regular),
+		}
+	}
+}
+
+/// Keys of an account set aside for use outside the wallet, which never
+/// derives them itself, returned by
+/// [`reserve_derivation_indices`](../grin_wallet_api/struct.Owner.html#method.reserve_derivation_indices)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReservedKeys {
+	/// Label of the account the keys belong to
+	pub account: Option<String>,
+	/// Key of the account the keys are derived from
+	pub parent_key_id: Identifier,
+	/// Index of the first key reserved among its account's keys
+	pub first_index: u32,
+	/// Number of keys reserved, at consecutive indices from `first_index`
+	pub count: u32,
+	/// The keys reserved, in order of index
+	pub key_ids: Vec<Identifier>,
+}
+
+impl ReservedKeys {
+	/// The `count` keys of the given account starting at `first_index`
+	pub fn new(
+		account: Option<String>,
+		parent_key_id: Identifier,
+		first_index: u32,
+		count: u32,
+	) -> ReservedKeys {
+		let key_ids = (first_index..first_index + count)
+			.map(|i| {
+				let mut path = parent_key_id.to_path();
+				path.depth += 1;
+				path.path[path.depth as usize - 1] = ChildNumber::from(i);
+				Identifier::from_path(&path)
+			})
+			.collect();
+		ReservedKeys {
+			account,
+			parent_key_id,
+			first_index,
+			count,
+			key_ids,
+		}
+	}
+}
+
+/// The account and key reserved for the output of a slate being received,
+/// by [`reserve_receive`](../grin_wallet_libwallet/api_impl/foreign/fn.reserve_receive.html)
+#[derive(Debug, Clone)]
+pub struct ReceiveReservation {
+	/// Account the output is received into
+	pub parent_key_id: Identifier,
+	/// Key the output is built from
+	pub key_id: Identifier,
+}
+
+/// A change output of a transaction prepared for offline signing
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChangeOutput {
+	/// The output's key
+	pub key_id: Identifier,
+	/// The output's value
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub value: u64,
+	/// The output's commitment, once the wallet holding the seed has built it
+	pub commit: Option<String>,
+}
+
+/// A transaction prepared by a watch-only wallet, holding all the wallet
+/// holding the seed needs to build and sign it without a node, returned by
+/// [`prepare_send`](../grin_wallet_api/struct.Owner.html#method.prepare_send)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UnsignedTx {
+	/// The slate, with its amount, fee and heights set but no inputs, outputs
+	/// or signatures
+	pub slate: Slate,
+	/// Account the transaction is sent from
+	pub parent_key_id: Identifier,
+	/// Outputs spent, locked in the watch-only wallet
+	pub inputs: Vec<OutputData>,
+	/// Change outputs to build, with keys set aside by the watch-only wallet
+	pub change: Vec<ChangeOutput>,
+	/// Optional message to sign and include in the slate
+	pub message: Option<String>,
+}
+
+/// A transaction finalized by the wallet holding the seed, along with the
+/// commitments of its change outputs, ready to be handed back to the
+/// watch-only wallet by
+/// [`broadcast_signed`](../grin_wallet_api/struct.Owner.html#method.broadcast_signed)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SignedTx {
+	/// The finalized slate
+	pub slate: Slate,
+	/// Change outputs of the transaction
+	pub change: Vec<ChangeOutput>,
+}
+
+/// A TOTP secret being enrolled, returned by
+/// [`enroll_totp`](../grin_wallet_api/struct.Owner.html#method.enroll_totp) to be
+/// entered in an authenticator app
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TotpEnrollment {
+	/// Base32 encoded secret
+	pub secret: String,
+	/// `otpauth://` URI holding the secret, typically shown as a QR code
+	pub uri: String,
+}
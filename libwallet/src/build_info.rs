@@ -0,0 +1,60 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Version and build details of the running wallet, reported through the
+//! Owner API. The git commit and features are only known to the binary,
+//! which sets them at startup
+
+use strum::IntoEnumIterator;
+
+use crate::grin_util::RwLock;
+use crate::slate_versions::SlateVersion;
+
+/// Version of the wallet software
+pub const WALLET_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Version and build details of the running wallet, returned by
+/// [`get_build_info`](../grin_wallet_api/struct.Owner.html#method.get_build_info)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BuildInfo {
+	/// Version of the wallet software
+	pub version: String,
+	/// Git commit the wallet was built from, if known
+	pub git_hash: Option<String>,
+	/// Cargo features the wallet was built with
+	pub features: Vec<String>,
+	/// Slate versions the wallet reads and writes
+	pub supported_slate_versions: Vec<SlateVersion>,
+}
+
+lazy_static! {
+	/// Git commit and features of the running binary
+	static ref BUILD_DETAILS: RwLock<(Option<String>, Vec<String>)> = RwLock::new((None, vec![]));
+}
+
+/// Set the git commit and features the running binary was built with
+pub fn set_build_details(git_hash: Option<String>, features: Vec<String>) {
+	*BUILD_DETAILS.write() = (git_hash, features);
+}
+
+/// Version and build details of the running wallet
+pub fn build_info() -> BuildInfo {
+	let details = BUILD_DETAILS.read();
+	BuildInfo {
+		version: WALLET_VERSION.to_owned(),
+		git_hash: details.0.clone(),
+		features: details.1.clone(),
+		supported_slate_versions: SlateVersion::iter().collect(),
+	}
+}
@@ -0,0 +1,116 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Canonical JSON serialization, producing the same bytes for the same data
+//! regardless of field order or formatting. Any signature made over serialized
+//! data should be made over this form, so that implementations serializing
+//! differently still agree on what was signed.
+//!
+//! The canonical form has object keys sorted by their UTF-8 bytes, no
+//! insignificant whitespace, strings escaped as by serde_json and integers
+//! written in plain decimal. Non-integer numbers aren't permitted, as there's
+//! no portable way to format them.
+
+use crate::blake2::blake2b::blake2b;
+use crate::grin_util::secp;
+use crate::{Error, ErrorKind};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Serialize a value to canonical JSON
+pub fn to_canonical_json<T: Serialize>(value: &T) -> Result<String, Error> {
+	let value = serde_json::to_value(value)
+		.map_err(|e| ErrorKind::Format(format!("Serializing to JSON: {}", e)))?;
+	let mut out = String::new();
+	write_value(&value, &mut out)?;
+	Ok(out)
+}
+
+/// Hash of a value's canonical JSON, suitable for signing
+pub fn canonical_json_message<T: Serialize>(value: &T) -> Result<secp::Message, Error> {
+	let json = to_canonical_json(value)?;
+	let hashed = blake2b(secp::constants::MESSAGE_SIZE, &[], json.as_bytes());
+	Ok(secp::Message::from_slice(hashed.as_bytes())?)
+}
+
+fn write_value(value: &Value, out: &mut String) -> Result<(), Error> {
+	match value {
+		Value::Null => out.push_str("null"),
+		Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+		Value::Number(n) => {
+			if let Some(i) = n.as_u64() {
+				out.push_str(&i.to_string());
+			} else if let Some(i) = n.as_i64() {
+				out.push_str(&i.to_string());
+			} else {
+				return Err(ErrorKind::Format(format!(
+					"Non-integer number {} can't be serialized canonically",
+					n
+				)))?;
+			}
+		}
+		Value::String(s) => write_string(s, out)?,
+		Value::Array(a) => {
+			out.push('[');
+			for (i, v) in a.iter().enumerate() {
+				if i > 0 {
+					out.push(',');
+				}
+				write_value(v, out)?;
+			}
+			out.push(']');
+		}
+		Value::Object(o) => {
+			let mut entries: Vec<(&String, &Value)> = o.iter().collect();
+			entries.sort_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+			out.push('{');
+			for (i, (k, v)) in entries.into_iter().enumerate() {
+				if i > 0 {
+					out.push(',');
+				}
+				write_string(k, out)?;
+				out.push(':');
+				write_value(v, out)?;
+			}
+			out.push('}');
+		}
+	}
+	Ok(())
+}
+
+fn write_string(s: &str, out: &mut String) -> Result<(), Error> {
+	let escaped = serde_json::to_string(s)
+		.map_err(|e| ErrorKind::Format(format!("Serializing string: {}", e)))?;
+	out.push_str(&escaped);
+	Ok(())
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use serde_json::json;
+
+	#[test]
+	fn canonical_json_sorts_keys_and_strips_whitespace() {
+		let a = json!({"b": 1, "a": {"d": [1, 2, {"z": null, "y": true}], "c": "x\"y"}});
+		let b: Value = serde_json::from_str(
+			r#"{ "a" : { "c" : "x\"y", "d" : [ 1, 2, { "y": true, "z": null } ] }, "b" : 1 }"#,
+		)
+		.unwrap();
+		let expected = r#"{"a":{"c":"x\"y","d":[1,2,{"y":true,"z":null}]},"b":1}"#;
+		assert_eq!(to_canonical_json(&a).unwrap(), expected);
+		assert_eq!(to_canonical_json(&b).unwrap(), expected);
+		assert!(to_canonical_json(&json!({"f": 1.5})).is_err());
+	}
+}
@@ -0,0 +1,160 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Process-wide minimum confirmations before outputs are spent, by where the
+//! outputs came from, set once at startup by the binary or an embedding
+//! application. Coinbase outputs disappear with any reorg of their block and
+//! received outputs with a double spend by their sender, while change only
+//! depends on the wallet's own transaction, so each can be held to a
+//! different number of confirmations
+
+use crate::error::Error;
+use crate::grin_keychain::{Identifier, Keychain};
+use crate::grin_util::RwLock;
+use crate::types::{NodeClient, OutputData, TxLogEntryType, WalletBackend};
+use std::collections::HashSet;
+
+/// Where an output came from
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum OutputSource {
+	/// Block reward mined by the wallet
+	Coinbase,
+	/// Received from another party, or found on restore
+	Received,
+	/// Change from a transaction the wallet sent
+	Change,
+}
+
+/// Minimum confirmations before outputs from each source are spent. Where one
+/// is set it takes the place of the minimum asked for when sending or getting
+/// the wallet's summary, for outputs from that source
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct SpendConfirmations {
+	/// Confirmations for coinbase outputs, once mature
+	pub coinbase: Option<u64>,
+	/// Confirmations for outputs received from others
+	pub received: Option<u64>,
+	/// Confirmations for change outputs
+	pub change: Option<u64>,
+}
+
+impl SpendConfirmations {
+	/// Confirmations an output from `source` needs, given the minimum asked for
+	pub fn required(&self, source: OutputSource, minimum_confirmations: u64) -> u64 {
+		let configured = match source {
+			OutputSource::Coinbase => self.coinbase,
+			OutputSource::Received => self.received,
+			OutputSource::Change => self.change,
+		};
+		configured.unwrap_or(minimum_confirmations)
+	}
+}
+
+lazy_static! {
+	/// Confirmations in effect for this process
+	static ref SPEND_CONFIRMATIONS: RwLock<SpendConfirmations> =
+		RwLock::new(SpendConfirmations::default());
+}
+
+/// Set the minimum confirmations by output source for this process
+pub fn set_spend_confirmations(confirmations: SpendConfirmations) {
+	*SPEND_CONFIRMATIONS.write() = confirmations;
+}
+
+/// The minimum confirmations by output source currently in effect
+pub fn spend_confirmations() -> SpendConfirmations {
+	*SPEND_CONFIRMATIONS.read()
+}
+
+/// Confirmations each of an account's outputs needs before it's spent
+pub(crate) struct ConfirmationRequirements {
+	confirmations: SpendConfirmations,
+	minimum_confirmations: u64,
+	/// Log entries of the account's sends, which its change outputs refer to
+	send_tx_ids: HashSet<u32>,
+}
+
+impl ConfirmationRequirements {
+	/// Requirements for the outputs of the given account, given the minimum
+	/// asked for
+	pub fn new<'a, T: ?Sized, C, K>(
+		wallet: &T,
+		parent_key_id: &Identifier,
+		minimum_confirmations: u64,
+	) -> Result<ConfirmationRequirements, Error>
+	where
+		T: WalletBackend<'a, C, K>,
+		C: NodeClient + 'a,
+		K: Keychain + 'a,
+	{
+		let confirmations = spend_confirmations();
+		// change is only told apart from received outputs if it needs to be
+		let send_tx_ids = match confirmations.change == confirmations.received {
+			true => HashSet::new(),
+			false => wallet
+				.tx_log_iter()?
+				.filter(|t| {
+					t.parent_key_id == *parent_key_id && t.tx_type == TxLogEntryType::TxSent
+				})
+				.map(|t| t.id)
+				.collect(),
+		};
+		Ok(ConfirmationRequirements {
+			confirmations,
+			minimum_confirmations,
+			send_tx_ids,
+		})
+	}
+
+	/// Where the output came from
+	pub fn source(&self, out: &OutputData) -> OutputSource {
+		if out.is_coinbase {
+			OutputSource::Coinbase
+		} else if out
+			.tx_log_entry
+			.map_or(false, |id| self.send_tx_ids.contains(&id))
+		{
+			OutputSource::Change
+		} else {
+			OutputSource::Received
+		}
+	}
+
+	/// Confirmations the output needs before it's spent
+	pub fn required(&self, out: &OutputData) -> u64 {
+		self.confirmations
+			.required(self.source(out), self.minimum_confirmations)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn required_confirmations() {
+		let confirmations = SpendConfirmations {
+			coinbase: Some(100),
+			received: None,
+			change: Some(1),
+		};
+		assert_eq!(confirmations.required(OutputSource::Coinbase, 10), 100);
+		assert_eq!(confirmations.required(OutputSource::Received, 10), 10);
+		assert_eq!(confirmations.required(OutputSource::Change, 10), 1);
+		assert_eq!(
+			SpendConfirmations::default().required(OutputSource::Change, 10),
+			10
+		);
+	}
+}
@@ -0,0 +1,201 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Schemes deriving a wallet's keys from its seed. The lifecycle provider
+//! derives keychains with the [`DerivationScheme`](trait.DerivationScheme.html)
+//! it's given, chosen by name from a process-wide registry holding the
+//! built-in schemes and any registered by the application, and the wallet
+//! backend lays out its accounts and their output keys along the scheme's
+//! paths, so a wallet can be opened on a seed another implementation derives
+//! its keys from differently. Outputs found on restore carry their key's path
+//! in their range proof, and are filed under the account the scheme takes
+//! that path to belong to.
+
+use crate::error::{Error, ErrorKind};
+use crate::grin_keychain::{mnemonic, ChildNumber, ExtKeychain, Identifier, Keychain};
+use crate::grin_util::RwLock;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Name of the scheme used when none is configured
+pub const DEFAULT_DERIVATION_SCHEME: &str = "grin";
+
+/// A way of deriving a wallet's keys from its seed: the seed of the keychain's
+/// master key, and the paths below it of the wallet's accounts and their
+/// output keys. The provided path methods lay keys out as this wallet always
+/// has, accounts at `m/k/0` and their outputs at `m/k/0/i`
+pub trait DerivationScheme: Send + Sync {
+	/// The seed the keychain's master key is derived from, given the entropy
+	/// of the wallet seed and the BIP39 passphrase, if one's set
+	fn keychain_seed(&self, entropy: &[u8], passphrase: Option<&str>) -> Result<Vec<u8>, Error>;
+
+	/// Path of a new account, given those of the accounts the wallet already
+	/// has. With none given, the path of the default account
+	fn account_path(&self, accounts: &[Identifier]) -> Identifier {
+		match accounts
+			.iter()
+			.map(|a| <u32>::from(a.to_path().path[0]))
+			.max()
+		{
+			Some(k) => ExtKeychain::derive_key_id(2, k + 1, 0, 0, 0),
+			None => ExtKeychain::derive_key_id(2, 0, 0, 0, 0),
+		}
+	}
+
+	/// Path of the output key at `index` in the given account
+	fn child_path(&self, account: &Identifier, index: u32) -> Identifier {
+		let mut path = account.to_path();
+		path.depth = path.depth + 1;
+		path.path[path.depth as usize - 1] = ChildNumber::from(index);
+		Identifier::from_path(&path)
+	}
+
+	/// Path of the account an output key belongs to
+	fn parent_path(&self, key_id: &Identifier) -> Identifier {
+		key_id.parent_path()
+	}
+}
+
+/// This wallet's scheme, using the entropy directly unless a passphrase is
+/// set, in which case the BIP39 seed of the recovery phrase and passphrase
+pub struct GrinDerivation;
+
+impl DerivationScheme for GrinDerivation {
+	fn keychain_seed(&self, entropy: &[u8], passphrase: Option<&str>) -> Result<Vec<u8>, Error> {
+		match passphrase {
+			Some(p) if !p.is_empty() => Bip39Derivation.keychain_seed(entropy, Some(p)),
+			_ => Ok(entropy.to_vec()),
+		}
+	}
+}
+
+/// The BIP39 seed of the recovery phrase and passphrase, if any, as used by
+/// wallets following BIP39 throughout
+pub struct Bip39Derivation;
+
+impl DerivationScheme for Bip39Derivation {
+	fn keychain_seed(&self, entropy: &[u8], passphrase: Option<&str>) -> Result<Vec<u8>, Error> {
+		let err = |e: mnemonic::Error| ErrorKind::Derivation(format!("{:?}", e));
+		let phrase = mnemonic::from_entropy(entropy).map_err(err)?;
+		let seed = mnemonic::to_seed(&phrase, passphrase.unwrap_or("")).map_err(err)?;
+		Ok(seed.to_vec())
+	}
+}
+
+lazy_static! {
+	/// Derivation schemes by name
+	static ref DERIVATION_SCHEMES: RwLock<HashMap<String, Arc<dyn DerivationScheme>>> = {
+		let mut schemes: HashMap<String, Arc<dyn DerivationScheme>> = HashMap::new();
+		schemes.insert(DEFAULT_DERIVATION_SCHEME.to_owned(), Arc::new(GrinDerivation));
+		schemes.insert("bip39".to_owned(), Arc::new(Bip39Derivation));
+		RwLock::new(schemes)
+	};
+}
+
+/// Register a derivation scheme under the given name, so wallets can be
+/// configured to use it. Replaces any scheme already registered under the
+/// name, including the built-in `grin` and `bip39`
+pub fn register_derivation_scheme(name: &str, scheme: Arc<dyn DerivationScheme>) {
+	DERIVATION_SCHEMES.write().insert(name.to_owned(), scheme);
+}
+
+/// The scheme registered under `name` if one's given, otherwise the default
+pub fn derivation_scheme(name: Option<&str>) -> Result<Arc<dyn DerivationScheme>, Error> {
+	let name = name.unwrap_or(DEFAULT_DERIVATION_SCHEME);
+	match DERIVATION_SCHEMES.read().get(name) {
+		Some(s) => Ok(s.clone()),
+		None => Err(ErrorKind::UnknownDerivationScheme(name.to_owned()).into()),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	struct Reversed;
+
+	impl DerivationScheme for Reversed {
+		fn keychain_seed(&self, entropy: &[u8], _: Option<&str>) -> Result<Vec<u8>, Error> {
+			Ok(entropy.iter().rev().cloned().collect())
+		}
+	}
+
+	/// Accounts at `m/44/k`, their outputs at `m/44/k/i`
+	struct Purpose44;
+
+	impl DerivationScheme for Purpose44 {
+		fn keychain_seed(&self, entropy: &[u8], _: Option<&str>) -> Result<Vec<u8>, Error> {
+			Ok(entropy.to_vec())
+		}
+
+		fn account_path(&self, accounts: &[Identifier]) -> Identifier {
+			let k = accounts
+				.iter()
+				.map(|a| <u32>::from(a.to_path().path[1]) + 1)
+				.max()
+				.unwrap_or(0);
+			ExtKeychain::derive_key_id(2, 44, k, 0, 0)
+		}
+	}
+
+	#[test]
+	fn derivation_schemes() {
+		let entropy = [7u8; 32];
+		let grin = derivation_scheme(None).unwrap();
+		let bip39 = derivation_scheme(Some("bip39")).unwrap();
+		assert_eq!(
+			grin.keychain_seed(&entropy, None).unwrap(),
+			entropy.to_vec()
+		);
+		assert_eq!(
+			grin.keychain_seed(&entropy, Some("")).unwrap(),
+			entropy.to_vec()
+		);
+		assert_eq!(
+			grin.keychain_seed(&entropy, Some("pass")).unwrap(),
+			bip39.keychain_seed(&entropy, Some("pass")).unwrap()
+		);
+		let seed = bip39.keychain_seed(&entropy, None).unwrap();
+		assert_eq!(seed.len(), 64);
+		assert_ne!(seed, bip39.keychain_seed(&entropy, Some("pass")).unwrap());
+
+		assert!(derivation_scheme(Some("reversed")).is_err());
+		register_derivation_scheme("reversed", Arc::new(Reversed));
+		let reversed = derivation_scheme(Some("reversed")).unwrap();
+		assert_eq!(reversed.keychain_seed(&[1, 2], None).unwrap(), vec![2, 1]);
+	}
+
+	#[test]
+	fn derivation_paths() {
+		let grin = GrinDerivation;
+		let default = grin.account_path(&[]);
+		assert_eq!(default, ExtKeychain::derive_key_id(2, 0, 0, 0, 0));
+		assert_eq!(
+			grin.account_path(&[default.clone()]),
+			ExtKeychain::derive_key_id(2, 1, 0, 0, 0)
+		);
+		let child = grin.child_path(&default, 5);
+		assert_eq!(child, ExtKeychain::derive_key_id(3, 0, 0, 5, 0));
+		assert_eq!(grin.parent_path(&child), default);
+
+		let other = Purpose44;
+		let default = other.account_path(&[]);
+		assert_eq!(default, ExtKeychain::derive_key_id(2, 44, 0, 0, 0));
+		let account = other.account_path(&[default.clone()]);
+		assert_eq!(account, ExtKeychain::derive_key_id(2, 44, 1, 0, 0));
+		let child = other.child_path(&account, 3);
+		assert_eq!(child, ExtKeychain::derive_key_id(3, 44, 1, 3, 0));
+		assert_eq!(other.parent_path(&child), account);
+	}
+}
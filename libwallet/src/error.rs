@@ -69,6 +69,10 @@ pub enum ErrorKind {
 	#[fail(display = "Client Callback Error: {}", _0)]
 	ClientCallback(String),
 
+	/// The node kept refusing requests for being made too often
+	#[fail(display = "Node is throttling requests: {}", _0)]
+	NodeThrottled(String),
+
 	/// Secp Error
 	#[fail(display = "Secp error")]
 	Secp(secp::Error),
@@ -137,6 +141,14 @@ pub enum ErrorKind {
 	#[fail(display = "Transaction {} doesn't exist", _0)]
 	TransactionDoesntExist(String),
 
+	/// Output doesn't exist
+	#[fail(display = "Output {} doesn't exist", _0)]
+	OutputDoesntExist(String),
+
+	/// Keys can't be reserved
+	#[fail(display = "Unable to reserve keys: {}", _0)]
+	KeyReservation(String),
+
 	/// Transaction already rolled back
 	#[fail(display = "Transaction {} cannot be cancelled", _0)]
 	TransactionNotCancellable(String),
@@ -157,6 +169,14 @@ pub enum ErrorKind {
 	#[fail(display = "Transaction {} has already been received", _0)]
 	TransactionAlreadyReceived(String),
 
+	/// Transaction is above the review threshold and waiting for the owner
+	#[fail(display = "Transaction {} is awaiting review by the wallet owner", _0)]
+	TransactionAwaitingReview(String),
+
+	/// Transaction is past the TTL cutoff height in its slate
+	#[fail(display = "Transaction {} expired at height {}", _0, _1)]
+	TransactionExpired(String, u64),
+
 	/// Attempt to repost a transaction that's not completed and stored
 	#[fail(display = "Transaction building not completed: {}", _0)]
 	TransactionBuildingNotCompleted(u32),
@@ -193,6 +213,17 @@ pub enum ErrorKind {
 	#[fail(display = "Unknown Slate Version: {}", _0)]
 	SlateVersion(u16),
 
+	/// Slate is missing a field or has an invalid value for one
+	#[fail(display = "Invalid slate (version {}): {} {}", version, field, reason)]
+	SlateValidation {
+		/// Version the slate appears to be
+		version: u16,
+		/// Path of the field at fault, e.g. `tx.body.kernels[0].excess`
+		field: String,
+		/// What's wrong with it
+		reason: String,
+	},
+
 	/// Compatibility error between incoming slate versions and what's expected
 	#[fail(display = "Compatibility Error: {}", _0)]
 	Compatibility(String),
@@ -209,6 +240,63 @@ pub enum ErrorKind {
 	#[fail(display = "Supplied Keychain Mask Token is incorrect")]
 	InvalidKeychainMask,
 
+	/// Feature turned off by the wallet's feature flags
+	#[fail(display = "Feature '{}' is disabled for this wallet", _0)]
+	FeatureDisabled(String),
+
+	/// Invalid or insufficient SLIP-39 seed shares
+	#[fail(display = "Seed share error: {}", _0)]
+	Slip39(String),
+
+	/// An account export couldn't be read or imported
+	#[fail(display = "Account transfer error: {}", _0)]
+	AccountTransfer(String),
+
+	/// A long-running operation was cancelled
+	#[fail(display = "Operation cancelled")]
+	Cancelled,
+
+	/// No background operation has the given id
+	#[fail(display = "Unknown operation: {}", _0)]
+	UnknownOperation(u32),
+
+	/// Backup error
+	#[fail(display = "Backup error: {}", _0)]
+	Backup(String),
+
+	/// A memo couldn't be sealed to or opened with an escrow key
+	#[fail(display = "Memo escrow error: {}", _0)]
+	MemoEscrow(String),
+
+	/// No coin selection strategy is registered under the given name
+	#[fail(display = "Unknown coin selection strategy '{}'", _0)]
+	UnknownCoinSelection(String),
+
+	/// No derivation scheme is registered under the given name
+	#[fail(display = "Unknown derivation scheme '{}'", _0)]
+	UnknownDerivationScheme(String),
+
+	/// A derivation scheme couldn't derive a keychain's seed
+	#[fail(display = "Key derivation error: {}", _0)]
+	Derivation(String),
+
+	/// The wallet generated its recovery phrase, and the user hasn't yet shown
+	/// they've recorded it
+	#[fail(display = "The wallet's recovery phrase must be verified before sending")]
+	SeedBackupUnverified,
+
+	/// A send is refused by the wallet's spending policy
+	#[fail(display = "Spending policy violation: {}", _0)]
+	SpendingPolicy(String),
+
+	/// A slate received through the Foreign API is outside the receive limits
+	#[fail(display = "Receive refused: {}", _0)]
+	ReceiveLimit(String),
+
+	/// A TOTP code is missing or wrong, or can't be enrolled or confirmed
+	#[fail(display = "Second factor: {}", _0)]
+	SecondFactor(String),
+
 	/// Other
 	#[fail(display = "Generic error: {}", _0)]
 	GenericError(String),
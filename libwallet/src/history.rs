@@ -0,0 +1,205 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Export of the transaction log in formats other tools can read, for
+//! accounting and tax records
+
+use crate::grin_core::core::{amount_to_hr_string, Transaction};
+use crate::grin_core::libtx::secp_ser;
+use crate::grin_util::secp::key::PublicKey;
+use crate::grin_util::to_hex;
+use crate::memo_escrow::seal_memo;
+use crate::types::{TxLogEntry, TxLogEntryType, TxStepVersions};
+use crate::{Error, ErrorKind};
+use chrono::prelude::{DateTime, Utc};
+use std::fmt::Write;
+use uuid::Uuid;
+
+/// Format to export transactions in
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum TxExportFormat {
+	/// One row per transaction, with amounts in grins
+	Csv,
+	/// An array of [`TxExportEntry`](struct.TxExportEntry.html), with amounts
+	/// in nanogrins
+	Json,
+}
+
+/// A transaction as exported
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TxExportEntry {
+	/// Local id of the transaction
+	pub id: u32,
+	/// Slate id of the transaction, if any, as known to the other party
+	pub tx_slate_id: Option<Uuid>,
+	/// Type of the transaction
+	pub tx_type: TxLogEntryType,
+	/// Time the transaction was created
+	pub creation_ts: DateTime<Utc>,
+	/// Time the transaction was confirmed, if it has been
+	pub confirmation_ts: Option<DateTime<Utc>>,
+	/// Whether the transaction is confirmed
+	pub confirmed: bool,
+	/// Amount credited, in nanogrins
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub amount_credited: u64,
+	/// Amount debited, in nanogrins
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub amount_debited: u64,
+	/// Fee, in nanogrins
+	#[serde(with = "secp_ser::opt_string_or_u64")]
+	pub fee: Option<u64>,
+	/// Excess (hex) of the transaction's kernel, by which it can be found on
+	/// chain, if the wallet stored the transaction
+	pub kernel_excess: Option<String>,
+	/// Location of the stored transaction, if any
+	pub stored_tx: Option<String>,
+	/// Label set by the user, if any
+	#[serde(default)]
+	pub label: Option<String>,
+	/// Participant messages of the transaction sealed to an escrow key, if the
+	/// export was made with one, to be read with
+	/// [`open_memo`](fn.open_memo.html). Only included in JSON exports
+	#[serde(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub escrowed_memo: Option<String>,
+	/// Versions of the wallet and of the slate at each step of the transaction,
+	/// to help diagnose problems between wallet versions. Only included in JSON
+	/// exports
+	#[serde(default)]
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub step_versions: Vec<TxStepVersions>,
+}
+
+impl TxExportEntry {
+	/// Export a transaction log entry, along with its stored transaction if
+	/// the wallet has one
+	pub fn new(entry: &TxLogEntry, tx: Option<&Transaction>) -> TxExportEntry {
+		TxExportEntry {
+			id: entry.id,
+			tx_slate_id: entry.tx_slate_id,
+			tx_type: entry.tx_type.clone(),
+			creation_ts: entry.creation_ts,
+			confirmation_ts: entry.confirmation_ts,
+			confirmed: entry.confirmed,
+			amount_credited: entry.amount_credited,
+			amount_debited: entry.amount_debited,
+			fee: entry.fee,
+			kernel_excess: tx
+				.and_then(|t| t.kernels().first().cloned())
+				.map(|k| to_hex(k.excess.0.to_vec()))
+				.or_else(|| entry.kernel_excess.clone()),
+			stored_tx: entry.stored_tx.clone(),
+			label: entry.label.clone(),
+			escrowed_memo: None,
+			step_versions: entry.step_versions.clone(),
+		}
+	}
+
+	/// Seal the transaction's participant messages to the escrow key, if it
+	/// has any messages
+	pub fn escrow_memo(&mut self, entry: &TxLogEntry, escrow_key: &PublicKey) -> Result<(), Error> {
+		if let Some(m) = entry.messages.as_ref() {
+			if m.messages.iter().any(|d| d.message.is_some()) {
+				self.escrowed_memo = Some(seal_memo(escrow_key, m)?);
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break
+fn csv_field(s: &str) -> String {
+	if s.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+		format!("\"{}\"", s.replace('"', "\"\""))
+	} else {
+		s.to_owned()
+	}
+}
+
+/// Render exported transactions in the given format
+pub fn export_txs(entries: &[TxExportEntry], format: TxExportFormat) -> Result<String, Error> {
+	if format == TxExportFormat::Json {
+		return serde_json::to_string_pretty(entries)
+			.map_err(|e| ErrorKind::Format(format!("Serializing to JSON: {}", e)).into());
+	}
+	let mut out = String::new();
+	let _ = writeln!(
+		out,
+		"id,tx_slate_id,type,creation_ts,confirmation_ts,confirmed,amount_credited,amount_debited,fee,kernel_excess,stored_tx,label"
+	);
+	for e in entries {
+		let _ = writeln!(
+			out,
+			"{},{},{:?},{},{},{},{},{},{},{},{},{}",
+			e.id,
+			e.tx_slate_id.map(|id| id.to_string()).unwrap_or_default(),
+			e.tx_type,
+			e.creation_ts.to_rfc3339(),
+			e.confirmation_ts
+				.map(|t| t.to_rfc3339())
+				.unwrap_or_default(),
+			e.confirmed,
+			amount_to_hr_string(e.amount_credited, false),
+			amount_to_hr_string(e.amount_debited, false),
+			e.fee
+				.map(|f| amount_to_hr_string(f, false))
+				.unwrap_or_default(),
+			e.kernel_excess.clone().unwrap_or_default(),
+			csv_field(&e.stored_tx.clone().unwrap_or_default()),
+			csv_field(&e.label.clone().unwrap_or_default()),
+		);
+	}
+	Ok(out)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::grin_keychain::ExtKeychain;
+	use crate::types::TxStep;
+	use chrono::TimeZone;
+
+	#[test]
+	fn export_txs_csv() {
+		let mut tx = TxLogEntry::new(
+			ExtKeychain::derive_key_id(2, 0, 0, 0, 0),
+			TxLogEntryType::TxSent,
+			3,
+		);
+		tx.creation_ts = Utc.ymd(2019, 1, 15).and_hms(16, 1, 26);
+		tx.amount_credited = 2_000_000_000;
+		tx.amount_debited = 10_000_000_000;
+		tx.fee = Some(8_000_000);
+		tx.stored_tx = Some("saved,txs/3.grintx".to_owned());
+		tx.label = Some("Rent, \"March\"".to_owned());
+		tx.step_versions.push(TxStepVersions {
+			step: TxStep::Lock,
+			wallet_version: "2.1.0".to_owned(),
+			slate_version: 2,
+		});
+		let entries = vec![TxExportEntry::new(&tx, None)];
+
+		let csv = export_txs(&entries, TxExportFormat::Csv).unwrap();
+		let row = csv.lines().nth(1).unwrap();
+		assert_eq!(
+			row,
+			"3,,TxSent,2019-01-15T16:01:26+00:00,,false,2.000000000,10.000000000,0.008000000,,\"saved,txs/3.grintx\",\"Rent, \"\"March\"\"\""
+		);
+
+		let json = export_txs(&entries, TxExportFormat::Json).unwrap();
+		let parsed: Vec<TxExportEntry> = serde_json::from_str(&json).unwrap();
+		assert_eq!(parsed, entries);
+	}
+}
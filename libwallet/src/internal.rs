@@ -21,8 +21,11 @@
 #![deny(unused_mut)]
 #![warn(missing_docs)]
 
+pub mod backup;
 pub mod keys;
+pub mod offline;
 pub mod restore;
 pub mod selection;
+pub mod transfer;
 pub mod tx;
 pub mod updater;
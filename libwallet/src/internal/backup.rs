@@ -0,0 +1,402 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Full and incremental backups of the wallet's records. A full backup holds
+//! every record, and an incremental one only the records added, changed or
+//! removed since the previous backup, so the wallet's state at any backup can
+//! be rebuilt from a full backup plus the incremental ones taken after it.
+//! Backups are encrypted with a key derived from the seed, so restoring a
+//! wallet from them also takes its seed, as in a [`BackupArchive`]
+
+use std::collections::BTreeMap;
+
+use crate::blake2::blake2b::blake2b;
+use crate::error::{Error, ErrorKind};
+use crate::grin_core::core::Transaction;
+use crate::grin_core::ser;
+use crate::grin_keychain::{Identifier, Keychain, SwitchCommitmentType};
+use crate::grin_util::secp::key::SecretKey;
+use crate::grin_util::{from_hex, to_hex};
+use crate::types::{
+	AcctPathMapping, FeatureFlags, NodeClient, OutputData, PendingReceive, PushRegistration,
+	ScannedBlockInfo, SeedCheck, TxLogEntry, WalletBackend,
+};
+use chrono::prelude::*;
+use rand::{thread_rng, Rng};
+use ring::aead;
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Version of the backup format, written as its first byte
+const BACKUP_VERSION: u8 = 1;
+/// Personalisation of the hash deriving the encryption key from the seed
+const BACKUP_KEY_DOMAIN: &[u8] = b"grin_wallet_backup";
+
+/// The wallet's records, by a key naming the kind of record and which one it
+/// is, e.g. `tx/<account path>/<id>`
+pub type WalletRecords = BTreeMap<String, Value>;
+
+/// A backup, as returned to be stored by the caller
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WalletBackup {
+	/// Id of the backup
+	pub id: Uuid,
+	/// Id of the backup this one holds the changes since, or `None` for a
+	/// full backup
+	pub parent: Option<Uuid>,
+	/// When the backup was taken
+	pub created: DateTime<Utc>,
+	/// Number of records added or changed
+	pub changed: usize,
+	/// Number of records removed
+	pub removed: usize,
+	/// The encrypted backup, hex-encoded
+	pub data: String,
+}
+
+impl WalletBackup {
+	/// Name to store the backup under, sorting in the order backups were
+	/// taken
+	pub fn file_name(&self) -> String {
+		format!(
+			"grin-wallet-backup-{}-{}",
+			self.created.format("%Y%m%dT%H%M%SZ"),
+			self.id
+		)
+	}
+}
+
+/// Everything needed to restore a wallet from its backups on another host
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BackupArchive {
+	/// Contents of the wallet's seed file, encrypted with its password
+	pub seed: String,
+	/// The `data` of a full backup and of the incremental backups taken
+	/// since, the latest of which is restored
+	pub backups: Vec<String>,
+}
+
+/// What the wallet last backed up, so the next incremental backup only
+/// holds what has changed since
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BackupState {
+	/// Id of the last backup
+	pub id: Uuid,
+	/// When it was taken
+	pub created: DateTime<Utc>,
+	/// Digest of each record as backed up
+	pub digests: BTreeMap<String, String>,
+}
+
+impl ser::Writeable for BackupState {
+	fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+	}
+}
+
+impl ser::Readable for BackupState {
+	fn read(reader: &mut dyn ser::Reader) -> Result<BackupState, ser::Error> {
+		let data = reader.read_bytes_len_prefix()?;
+		serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+	}
+}
+
+/// Contents of a backup, once decrypted
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct BackupContents {
+	id: Uuid,
+	parent: Option<Uuid>,
+	created: DateTime<Utc>,
+	/// Records added or changed
+	set: WalletRecords,
+	/// Keys of records removed
+	removed: Vec<String>,
+}
+
+fn backup_error(msg: &str) -> Error {
+	ErrorKind::Backup(msg.to_owned()).into()
+}
+
+fn record<T: serde::Serialize>(value: &T) -> Result<Value, Error> {
+	serde_json::to_value(value)
+		.map_err(|e| ErrorKind::Format(format!("Serializing record: {}", e)).into())
+}
+
+fn from_record<T: serde::de::DeserializeOwned>(key: &str, value: Value) -> Result<T, Error> {
+	serde_json::from_value(value).map_err(|e| backup_error(&format!("invalid {}: {}", key, e)))
+}
+
+fn digest(value: &Value) -> String {
+	to_hex(
+		blake2b(32, &[], value.to_string().as_bytes())
+			.as_bytes()
+			.to_vec(),
+	)
+}
+
+/// Key encrypting backups, only derivable from the wallet's seed
+fn backup_key<K>(keychain: &K) -> Result<Vec<u8>, Error>
+where
+	K: Keychain,
+{
+	let root = keychain.derive_key(0, &K::root_key_id(), &SwitchCommitmentType::None)?;
+	Ok(blake2b(32, &root.0, BACKUP_KEY_DOMAIN).as_bytes().to_vec())
+}
+
+/// Collect all of the wallet's records
+pub fn wallet_records<'a, T: ?Sized, C, K>(w: &mut T) -> Result<WalletRecords, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let mut records = WalletRecords::new();
+	let accounts: Vec<_> = w.acct_path_iter()?.collect();
+	for a in accounts {
+		let index = w.current_child_index(&a.path)?;
+		records.insert(format!("child_index/{}", a.path.to_hex()), record(&index)?);
+		records.insert(format!("account/{}", a.label), record(&a)?);
+	}
+	for o in w.iter()? {
+		let mmr_index = o.mmr_index.map(|i| i.to_string()).unwrap_or_default();
+		let key = format!("output/{}/{}", o.key_id.to_hex(), mmr_index);
+		records.insert(key, record(&o)?);
+	}
+	for t in w.tx_log_iter()? {
+		if let (Some(name), Ok(Some(tx))) = (t.stored_tx.clone(), w.get_stored_tx(&t)) {
+			let tx_hex = to_hex(ser::ser_vec(&tx, ser::ProtocolVersion::local()).unwrap());
+			records.insert(format!("stored_tx/{}", name), record(&tx_hex)?);
+		}
+		let key = format!("tx/{}/{}", t.parent_key_id.to_hex(), t.id);
+		records.insert(key, record(&t)?);
+	}
+	for p in w.push_registration_iter()? {
+		records.insert(format!("push_registration/{}", p.token), record(&p)?);
+	}
+	for p in w.pending_receive_iter()? {
+		records.insert(format!("pending_receive/{}", p.slate.id), record(&p)?);
+	}
+	records.insert("feature_flags".to_owned(), record(&w.feature_flags()?)?);
+	if let Some(c) = w.seed_check()? {
+		records.insert("seed_check".to_owned(), record(&c)?);
+	}
+	if let Some(b) = w.last_scanned_block()? {
+		records.insert("last_scanned_block".to_owned(), record(&b)?);
+	}
+	Ok(records)
+}
+
+/// Write records collected by [`wallet_records`] into the wallet, typically
+/// a newly created one being restored from backups
+pub fn install_records<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	records: WalletRecords,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	// stored transactions are files rather than database records
+	let mut txs = vec![];
+	let mut batch = w.batch(keychain_mask)?;
+	for (key, value) in records {
+		let mut parts = key.splitn(2, '/');
+		match (parts.next().unwrap_or(""), parts.next()) {
+			("child_index", Some(path)) => {
+				let path = Identifier::from_hex(path)?;
+				batch.save_child_index(&path, from_record(&key, value)?)?;
+			}
+			("account", Some(_)) => {
+				batch.save_acct_path(from_record::<AcctPathMapping>(&key, value)?)?
+			}
+			("output", Some(_)) => batch.save(from_record::<OutputData>(&key, value)?)?,
+			("stored_tx", Some(name)) => {
+				let tx_hex: String = from_record(&key, value)?;
+				let tx_bin = from_hex(tx_hex).map_err(|_| backup_error("invalid stored_tx"))?;
+				let tx: Transaction =
+					ser::deserialize(&mut &tx_bin[..], ser::ProtocolVersion::local())?;
+				txs.push((name.trim_end_matches(".grintx").to_owned(), tx));
+			}
+			("tx", Some(_)) => {
+				let t: TxLogEntry = from_record(&key, value)?;
+				let parent_key_id = t.parent_key_id.clone();
+				batch.save_tx_log_entry(t, &parent_key_id)?;
+			}
+			("push_registration", Some(_)) => {
+				batch.save_push_registration(from_record::<PushRegistration>(&key, value)?)?
+			}
+			("pending_receive", Some(_)) => {
+				batch.save_pending_receive(&from_record::<PendingReceive>(&key, value)?)?
+			}
+			("feature_flags", None) => {
+				batch.save_feature_flags(from_record::<FeatureFlags>(&key, value)?)?
+			}
+			("seed_check", None) => {
+				batch.save_seed_check(&from_record::<SeedCheck>(&key, value)?)?
+			}
+			("last_scanned_block", None) => {
+				batch.save_last_scanned_block(from_record::<ScannedBlockInfo>(&key, value)?)?
+			}
+			_ => return Err(backup_error(&format!("unknown record {}", key))),
+		}
+	}
+	batch.commit()?;
+	for (name, tx) in txs {
+		w.store_tx(&name, &tx)?;
+	}
+	Ok(())
+}
+
+/// Back up the wallet. An incremental backup holds the changes since the
+/// last backup taken, and is full if no backup has been taken yet
+pub fn backup<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	incremental: bool,
+) -> Result<WalletBackup, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let records = wallet_records(&mut *w)?;
+	let digests: BTreeMap<String, String> = records
+		.iter()
+		.map(|(k, v)| (k.clone(), digest(v)))
+		.collect();
+	let last = match incremental {
+		true => w.backup_state()?,
+		false => None,
+	};
+	let (parent, set, removed) = match last {
+		Some(last) => {
+			let set = records
+				.into_iter()
+				.filter(|(k, _)| last.digests.get(k) != digests.get(k))
+				.collect();
+			let removed = last
+				.digests
+				.keys()
+				.filter(|k| !digests.contains_key(*k))
+				.cloned()
+				.collect();
+			(Some(last.id), set, removed)
+		}
+		None => (None, records, vec![]),
+	};
+	let contents = BackupContents {
+		id: Uuid::new_v4(),
+		parent,
+		created: Utc::now(),
+		set,
+		removed,
+	};
+
+	let key = backup_key(&w.keychain(keychain_mask)?)?;
+	let mut data = serde_json::to_vec(&contents)
+		.map_err(|e| ErrorKind::Format(format!("Serializing backup: {}", e)))?;
+	let tag_len = aead::CHACHA20_POLY1305.tag_len();
+	data.extend(vec![0; tag_len]);
+	let nonce: [u8; 12] = thread_rng().gen();
+	let sealing_key = aead::SealingKey::new(&aead::CHACHA20_POLY1305, &key)
+		.map_err(|_| backup_error("invalid key"))?;
+	aead::seal_in_place(&sealing_key, &nonce, &[], &mut data, tag_len)
+		.map_err(|_| backup_error("encryption failed"))?;
+	let mut blob = vec![BACKUP_VERSION];
+	blob.extend_from_slice(&nonce);
+	blob.extend(data);
+
+	// only recorded once the backup has been made, so a failed backup
+	// doesn't leave the next incremental one missing changes
+	let mut batch = w.batch(keychain_mask)?;
+	batch.save_backup_state(&BackupState {
+		id: contents.id,
+		created: contents.created,
+		digests,
+	})?;
+	batch.commit()?;
+
+	Ok(WalletBackup {
+		id: contents.id,
+		parent: contents.parent,
+		created: contents.created,
+		changed: contents.set.len(),
+		removed: contents.removed.len(),
+		data: to_hex(blob),
+	})
+}
+
+fn open_backup<K>(keychain: &K, data: &str) -> Result<BackupContents, Error>
+where
+	K: Keychain,
+{
+	let blob = from_hex(data.trim().to_owned()).map_err(|_| backup_error("invalid hex"))?;
+	if blob.len() < 13 || blob[0] != BACKUP_VERSION {
+		return Err(backup_error("unsupported or truncated backup"));
+	}
+	let key = backup_key(keychain)?;
+	let opening_key = aead::OpeningKey::new(&aead::CHACHA20_POLY1305, &key)
+		.map_err(|_| backup_error("invalid key"))?;
+	let mut data = blob[13..].to_vec();
+	let data =
+		aead::open_in_place(&opening_key, &blob[1..13], &[], 0, &mut data).map_err(|_| {
+			backup_error("can't decrypt, the backup is from a wallet with another seed")
+		})?;
+	serde_json::from_slice(data).map_err(|e| backup_error(&format!("invalid backup: {}", e)))
+}
+
+/// Rebuild the wallet's records as of the backup with the given id, or the
+/// latest backup if none is given, from the full backup it follows on from
+/// and the incremental backups taken in between, given in any order
+pub fn reconstruct<K>(
+	keychain: &K,
+	backups: &[String],
+	until: Option<Uuid>,
+) -> Result<WalletRecords, Error>
+where
+	K: Keychain,
+{
+	let mut by_id = BTreeMap::new();
+	for b in backups {
+		let contents = open_backup(keychain, b)?;
+		by_id.insert(contents.id, contents);
+	}
+	let target = match until {
+		Some(id) => id,
+		None => match by_id.values().max_by_key(|c| c.created) {
+			Some(c) => c.id,
+			None => return Err(backup_error("no backups given")),
+		},
+	};
+	// walk back to the full backup, then apply the changes going forward
+	let mut chain = vec![];
+	let mut next = Some(target);
+	while let Some(id) = next {
+		let contents = by_id
+			.remove(&id)
+			.ok_or_else(|| backup_error(&format!("backup {} is missing", id)))?;
+		next = contents.parent;
+		chain.push(contents);
+	}
+	let mut records = WalletRecords::new();
+	for contents in chain.into_iter().rev() {
+		records.extend(contents.set);
+		for k in contents.removed {
+			records.remove(&k);
+		}
+	}
+	Ok(records)
+}
@@ -14,7 +14,7 @@
 
 //! Wallet key management functions
 use crate::error::{Error, ErrorKind};
-use crate::grin_keychain::{ChildNumber, ExtKeychain, Identifier, Keychain};
+use crate::grin_keychain::{Identifier, Keychain};
 use crate::grin_util::secp::key::SecretKey;
 use crate::types::{AcctPathMapping, NodeClient, WalletBackend};
 
@@ -32,6 +32,30 @@ where
 	Ok(child)
 }
 
+/// Reserve the next `count` keys of the given parent, moving its child index
+/// past them so the wallet never derives them itself. Returns the index of
+/// the first key reserved
+pub fn reserve_keys<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	parent_key_id: &Identifier,
+	count: u32,
+) -> Result<u32, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let first = wallet.current_child_index(parent_key_id)?;
+	let next = first
+		.checked_add(count)
+		.ok_or_else(|| ErrorKind::KeyReservation("no more keys to reserve".to_owned()))?;
+	let mut batch = wallet.batch(keychain_mask)?;
+	batch.save_child_index(parent_key_id, next)?;
+	batch.commit()?;
+	Ok(first)
+}
+
 /// Retrieve an existing key from a wallet
 pub fn retrieve_existing_key<'a, T: ?Sized, C, K>(
 	wallet: &T,
@@ -56,7 +80,7 @@ where
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	Ok(wallet.acct_path_iter().collect())
+	Ok(wallet.acct_path_iter()?.collect())
 }
 
 /// Adds an new parent account path with a given label
@@ -71,27 +95,13 @@ where
 	K: Keychain + 'a,
 {
 	let label = label.to_owned();
-	if let Some(_) = wallet.acct_path_iter().find(|l| l.label == label) {
+	if let Some(_) = wallet.acct_path_iter()?.find(|l| l.label == label) {
 		return Err(ErrorKind::AccountLabelAlreadyExists(label.clone()).into());
 	}
 
-	// We're always using paths at m/k/0 for parent keys for output derivations
-	// so find the highest of those, then increment (to conform with external/internal
-	// derivation chains in BIP32 spec)
-
-	let highest_entry = wallet.acct_path_iter().max_by(|a, b| {
-		<u32>::from(a.path.to_path().path[0]).cmp(&<u32>::from(b.path.to_path().path[0]))
-	});
-
-	let return_id = {
-		if let Some(e) = highest_entry {
-			let mut p = e.path.to_path();
-			p.path[0] = ChildNumber::from(<u32>::from(p.path[0]) + 1);
-			p.to_identifier()
-		} else {
-			ExtKeychain::derive_key_id(2, 0, 0, 0, 0)
-		}
-	};
+	// the wallet's derivation scheme places the new account past those it has
+	let paths: Vec<Identifier> = wallet.acct_path_iter()?.map(|a| a.path).collect();
+	let return_id = wallet.derivation_scheme().account_path(&paths);
 
 	let save_path = AcctPathMapping {
 		label: label.to_owned(),
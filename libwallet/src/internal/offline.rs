@@ -0,0 +1,306 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sending from a wallet whose seed is kept offline. A watch-only copy of the
+//! wallet, opened without its seed, selects and locks the inputs and sets
+//! aside keys for the change. The wallet holding the seed builds and signs
+//! the transaction from that without contacting a node, and once it's
+//! finalized the watch-only copy records the change and posts it
+
+use crate::error::{Error, ErrorKind};
+use crate::grin_core::libtx::{build, proof::ProofBuilder};
+use crate::grin_keychain::{Identifier, Keychain, SwitchCommitmentType};
+use crate::grin_util::secp::key::SecretKey;
+use crate::grin_util::to_hex;
+use crate::internal::selection::{self, CoinSelection};
+use crate::internal::{tx, updater};
+use crate::slate::Slate;
+use crate::types::{
+	Context, NodeClient, OutputData, OutputStatus, TxLogEntry, TxLogEntryType, TxStep,
+	WalletBackend,
+};
+use crate::{ChangeOutput, SignedTx, UnsignedTx};
+
+/// Select the inputs and change of a send from the given account, locking the
+/// inputs and logging the transaction, for the wallet holding the seed to
+/// sign. Only needs the wallet's keychain for outputs whose commitments
+/// weren't cached
+pub fn prepare_send<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	mut slate: Slate,
+	minimum_confirmations: u64,
+	max_outputs: usize,
+	num_change_outputs: usize,
+	strategy: &dyn CoinSelection,
+	parent_key_id: &Identifier,
+	message: Option<String>,
+) -> Result<UnsignedTx, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	updater::refresh_outputs(wallet, keychain_mask, parent_key_id, false)?;
+	let (coins, total, amount, fee) = selection::select_coins_and_fee(
+		wallet,
+		slate.amount,
+		slate.height,
+		minimum_confirmations,
+		max_outputs,
+		num_change_outputs,
+		strategy,
+		parent_key_id,
+	)?;
+	let change = selection::build_change_outputs(
+		wallet,
+		keychain_mask,
+		total - amount - fee,
+		num_change_outputs,
+	)?;
+	slate.fee = fee;
+
+	let mut batch = wallet.batch(keychain_mask)?;
+	let log_id = batch.next_tx_log_id(parent_key_id)?;
+	let mut t = TxLogEntry::new(parent_key_id.clone(), TxLogEntryType::TxSent, log_id);
+	t.tx_slate_id = Some(slate.id);
+	t.creation_height = Some(slate.height);
+	t.fee = Some(fee);
+	t.num_inputs = coins.len();
+	t.record_step(TxStep::Lock, &slate);
+	for coin in &coins {
+		let mut coin = coin.clone();
+		coin.tx_log_entry = Some(log_id);
+		t.amount_debited += coin.value;
+		batch.lock_output(&mut coin)?;
+	}
+	batch.save_tx_log_entry(t, parent_key_id)?;
+	batch.commit()?;
+
+	Ok(UnsignedTx {
+		slate,
+		parent_key_id: parent_key_id.clone(),
+		inputs: coins,
+		change: change
+			.into_iter()
+			.map(|(value, key_id, _)| ChangeOutput {
+				key_id,
+				value,
+				commit: None,
+			})
+			.collect(),
+		message,
+	})
+}
+
+/// Build the inputs and change of a prepared transaction and sign for them,
+/// returning the slate to send to the recipient and keeping the transaction's
+/// context for when it comes back. Doesn't contact a node or touch the
+/// wallet's outputs, which the watch-only copy keeps track of
+pub fn sign_offline<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	unsigned: &UnsignedTx,
+	use_test_rng: bool,
+) -> Result<Slate, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	if unsigned.inputs.is_empty() {
+		return Err(ErrorKind::GenericError(
+			"Transaction has no inputs to sign".to_owned(),
+		))?;
+	}
+	let keychain = wallet.keychain(keychain_mask)?;
+	// only sign for outputs this wallet's seed can have built
+	for input in &unsigned.inputs {
+		let commit = keychain.commit(input.value, &input.key_id, &SwitchCommitmentType::Regular)?;
+		if input.commit != Some(to_hex(commit.0.to_vec())) {
+			return Err(ErrorKind::GenericError(format!(
+				"Input {} isn't an output of this wallet",
+				input.key_id
+			)))?;
+		}
+	}
+	let mut slate = unsigned.slate.clone();
+	let total: u64 = unsigned.inputs.iter().map(|i| i.value).sum();
+	let change: u64 = unsigned.change.iter().map(|c| c.value).sum();
+	if total != slate.amount + slate.fee + change {
+		return Err(ErrorKind::GenericError(
+			"Inputs don't add up to the amount, fee and change".to_owned(),
+		))?;
+	}
+
+	let mut parts = vec![build::with_fee(slate.fee)];
+	for input in &unsigned.inputs {
+		if input.is_coinbase {
+			parts.push(build::coinbase_input(input.value, input.key_id.clone()));
+		} else {
+			parts.push(build::input(input.value, input.key_id.clone()));
+		}
+	}
+	for output in &unsigned.change {
+		parts.push(build::output(output.value, output.key_id.clone()));
+	}
+	parts.push(build::with_lock_height(slate.lock_height));
+	let blinding =
+		slate.add_transaction_elements(&keychain, &ProofBuilder::new(&keychain), parts)?;
+
+	let mut context = Context::new(
+		keychain.secp(),
+		blinding.secret_key(&keychain.secp())?,
+		&unsigned.parent_key_id,
+		use_test_rng,
+		0,
+	);
+	context.fee = slate.fee;
+	for input in &unsigned.inputs {
+		context.add_input(&input.key_id, &input.mmr_index, input.value);
+	}
+	for output in &unsigned.change {
+		context.add_output(&output.key_id, &None, output.value);
+	}
+	slate.fill_round_1(
+		&keychain,
+		&mut context.sec_key,
+		&context.sec_nonce,
+		0,
+		unsigned.message.clone(),
+		use_test_rng,
+	)?;
+
+	let mut batch = wallet.batch(keychain_mask)?;
+	batch.save_private_context(slate.id.as_bytes(), 0, &context)?;
+	batch.commit()?;
+	Ok(slate)
+}
+
+/// Finalize a transaction signed offline once the recipient has returned it,
+/// along with the commitments of its change for the watch-only copy
+pub fn finalize_offline<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	slate: &Slate,
+) -> Result<SignedTx, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	if !slate
+		.participant_data
+		.iter()
+		.any(|p| p.id != 0 && p.part_sig.is_some())
+	{
+		return Err(ErrorKind::GenericError(
+			"Slate hasn't been signed by the recipient".to_owned(),
+		))?;
+	}
+	let mut slate = slate.clone();
+	let context = wallet.get_private_context(keychain_mask, slate.id.as_bytes(), 0)?;
+	tx::complete_tx(wallet, keychain_mask, &mut slate, 0, &context)?;
+	let keychain = wallet.keychain(keychain_mask)?;
+	let change = context
+		.get_outputs()
+		.into_iter()
+		.map(|(key_id, _, value)| {
+			let commit = keychain.commit(value, &key_id, &SwitchCommitmentType::Regular)?;
+			Ok(ChangeOutput {
+				key_id,
+				value,
+				commit: Some(to_hex(commit.0.to_vec())),
+			})
+		})
+		.collect::<Result<Vec<_>, Error>>()?;
+
+	let mut batch = wallet.batch(keychain_mask)?;
+	batch.delete_private_context(slate.id.as_bytes(), 0)?;
+	batch.commit()?;
+	Ok(SignedTx { slate, change })
+}
+
+/// Record the change of a transaction finalized offline against the log
+/// entry made when it was prepared, and store the transaction to be posted.
+/// Recording a transaction again leaves the wallet as it is
+pub fn record_signed<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	signed: &SignedTx,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let slate = &signed.slate;
+	let mut t = match updater::retrieve_txs(wallet, None, Some(slate.id), None, false, None)?
+		.into_iter()
+		.find(|t| t.tx_type == TxLogEntryType::TxSent)
+	{
+		Some(t) => t,
+		None => return Err(ErrorKind::TransactionDoesntExist(slate.id.to_string()))?,
+	};
+	if t.stored_tx.is_some() {
+		return Ok(());
+	}
+	let tx_outputs: Vec<String> = slate
+		.tx
+		.outputs()
+		.iter()
+		.map(|o| to_hex(o.commit.0.to_vec()))
+		.collect();
+	for output in &signed.change {
+		if !output
+			.commit
+			.as_ref()
+			.map_or(false, |c| tx_outputs.contains(c))
+		{
+			return Err(ErrorKind::GenericError(format!(
+				"Change output {} isn't in the transaction",
+				output.key_id
+			)))?;
+		}
+	}
+
+	let filename = format!("{}.grintx", slate.id);
+	{
+		let mut batch = wallet.batch(keychain_mask)?;
+		for output in &signed.change {
+			t.num_outputs += 1;
+			t.amount_credited += output.value;
+			batch.save(OutputData {
+				root_key_id: t.parent_key_id.clone(),
+				key_id: output.key_id.clone(),
+				n_child: output.key_id.to_path().last_path_index(),
+				commit: output.commit.clone(),
+				mmr_index: None,
+				value: output.value,
+				status: OutputStatus::Unconfirmed,
+				height: slate.height,
+				lock_height: 0,
+				is_coinbase: false,
+				tx_log_entry: Some(t.id),
+			})?;
+		}
+		t.messages = Some(slate.participant_messages());
+		t.stored_tx = Some(filename);
+		let parent_key_id = t.parent_key_id.clone();
+		batch.save_tx_log_entry(t, &parent_key_id)?;
+		batch.commit()?;
+	}
+	wallet.store_tx(&format!("{}", slate.id), &slate.tx)?;
+	Ok(())
+}
@@ -17,13 +17,19 @@ use crate::grin_core::consensus::{valid_header_version, WEEK_HEIGHT};
 use crate::grin_core::core::HeaderVersion;
 use crate::grin_core::global;
 use crate::grin_core::libtx::proof;
-use crate::grin_keychain::{ExtKeychain, Identifier, Keychain, SwitchCommitmentType};
+use crate::grin_keychain::{Identifier, Keychain, SwitchCommitmentType};
 use crate::grin_util::secp::key::SecretKey;
 use crate::grin_util::secp::pedersen;
 use crate::internal::{keys, updater};
+use crate::operation::{CancelToken, ScanProgress};
+use crate::privacy::{log_amount, log_commit};
+use crate::resources::resource_limits;
 use crate::types::*;
-use crate::{Error, OutputCommitMapping};
-use std::collections::HashMap;
+use crate::{AuditedOutput, ChainAuditReport, Error, ErrorKind, OutputCommitMapping};
+use crossbeam_utils::thread;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc;
 use std::time::Instant;
 
 /// Utility struct for return values from below
@@ -59,15 +65,19 @@ struct RestoredTxStats {
 	pub num_outputs: usize,
 }
 
-fn identify_utxo_outputs<'a, T, C, K>(
-	wallet: &mut T,
-	keychain_mask: Option<&SecretKey>,
-	outputs: Vec<(pedersen::Commitment, pedersen::RangeProof, bool, u64, u64)>,
+/// Outputs as returned by the node: (commit, proof, is_coinbase, height, mmr_index)
+type ChainOutput = (pedersen::Commitment, pedersen::RangeProof, bool, u64, u64);
+
+/// Number of restore batches making up the range of the UTXO set scanned
+/// by a single worker thread at a time
+const SCAN_WINDOW_BATCHES: u64 = 10;
+
+fn identify_utxo_outputs<K>(
+	keychain: &K,
+	outputs: Vec<ChainOutput>,
 ) -> Result<Vec<OutputResult>, Error>
 where
-	T: WalletBackend<'a, C, K>,
-	C: NodeClient + 'a,
-	K: Keychain + 'a,
+	K: Keychain,
 {
 	let mut wallet_outputs: Vec<OutputResult> = Vec::new();
 
@@ -76,9 +86,8 @@ where
 		outputs.len(),
 	);
 
-	let keychain = wallet.keychain(keychain_mask)?;
-	let legacy_builder = proof::LegacyProofBuilder::new(&keychain);
-	let builder = proof::ProofBuilder::new(&keychain);
+	let legacy_builder = proof::LegacyProofBuilder::new(keychain);
+	let builder = proof::ProofBuilder::new(keychain);
 	let legacy_version = HeaderVersion(1);
 
 	for output in outputs.iter() {
@@ -116,8 +125,11 @@ where
 		};
 
 		info!(
-			"Output found: {:?}, amount: {:?}, key_id: {:?}, mmr_index: {},",
-			commit, amount, key_id, mmr_index,
+			"Output found: {}, amount: {}, key_id: {:?}, mmr_index: {},",
+			log_commit(commit),
+			log_amount(amount),
+			key_id,
+			mmr_index,
 		);
 
 		if switch != SwitchCommitmentType::Regular {
@@ -138,41 +150,230 @@ where
 	Ok(wallet_outputs)
 }
 
-fn collect_chain_outputs<'a, T, C, K>(
+/// Fetch and rewind the outputs with PMMR indices from `start_index` up to
+/// (but excluding) `end_index`, a batch at a time. Also returns the height
+/// of the last output in the range, if there are any
+fn scan_output_range<C, K>(
+	client: &C,
+	keychain: &K,
+	start_index: u64,
+	end_index: u64,
+	batch_size: u64,
+) -> Result<(Vec<OutputResult>, Option<u64>), Error>
+where
+	C: NodeClient,
+	K: Keychain,
+{
+	let mut result_vec = vec![];
+	let mut last_height = None;
+	let mut start_index = start_index;
+	loop {
+		let (highest_index, last_retrieved_index, outputs) =
+			client.get_outputs_by_pmmr_index(start_index, batch_size)?;
+		let done = outputs.is_empty()
+			|| last_retrieved_index + 1 >= end_index
+			|| last_retrieved_index >= highest_index;
+		// the last batch may run into the following range
+		let outputs: Vec<ChainOutput> = outputs.into_iter().filter(|o| o.4 < end_index).collect();
+		last_height = outputs.last().map(|o| o.3).or(last_height);
+		result_vec.extend(identify_utxo_outputs(keychain, outputs)?);
+		if done {
+			break;
+		}
+		start_index = last_retrieved_index + 1;
+	}
+	Ok((result_vec, last_height))
+}
+
+/// Stream the node's UTXO set range by range from `start_index`, handing each
+/// output identified as belonging to this wallet to `f`, in PMMR index order.
+/// Returns the highest PMMR index scanned.
+///
+/// With a single restore thread, only a single range (of the configured
+/// restore batch size) is held in memory at once. Otherwise the UTXO set is
+/// split into ranges of several batches, which a pool of worker threads
+/// fetch from the node and rewind concurrently. The outputs found in a range
+/// are held until those in all earlier ranges have been handed to `f`.
+///
+/// Progress, including the height reached and the number of outputs found,
+/// is reported to `cancel`, and the scan fails with
+/// `ErrorKind::Cancelled` between ranges once it's cancelled
+fn scan_chain_outputs<'a, T, C, K, F>(
 	wallet: &mut T,
 	keychain_mask: Option<&SecretKey>,
-) -> Result<Vec<OutputResult>, Error>
+	start_index: u64,
+	cancel: Option<&CancelToken>,
+	mut f: F,
+) -> Result<u64, Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
+	F: FnMut(&mut T, OutputResult) -> Result<(), Error>,
 {
-	let batch_size = 1000;
-	let mut start_index = 1;
-	let mut result_vec: Vec<OutputResult> = vec![];
-	loop {
-		let (highest_index, last_retrieved_index, outputs) = wallet
-			.w2n_client()
-			.get_outputs_by_pmmr_index(start_index, batch_size)?;
-		warn!(
-			"Checking {} outputs, up to index {}. (Highest index: {})",
-			outputs.len(),
-			highest_index,
-			last_retrieved_index,
-		);
+	let limits = resource_limits();
+	let batch_size = limits.restore_batch_size;
+	let keychain = wallet.keychain(keychain_mask)?;
+	let client = wallet.w2n_client().clone();
+	let mut progress = ScanProgress::default();
+
+	if limits.restore_threads <= 1 {
+		let mut start_index = start_index;
+		loop {
+			if let Some(c) = cancel {
+				c.check()?;
+			}
+			let (highest_index, last_retrieved_index, outputs) =
+				client.get_outputs_by_pmmr_index(start_index, batch_size)?;
+			let outputs_len = outputs.len();
+			warn!(
+				"Checking {} outputs, up to index {}. (Highest index: {})",
+				outputs_len, highest_index, last_retrieved_index,
+			);
 
-		result_vec.append(&mut identify_utxo_outputs(
-			wallet,
-			keychain_mask,
-			outputs.clone(),
-		)?);
+			let last_height = outputs.last().map(|o| o.3);
+			for output in identify_utxo_outputs(&keychain, outputs)? {
+				f(wallet, output)?;
+				progress.outputs_found += 1;
+			}
+			if let Some(c) = cancel {
+				progress.scanned = last_retrieved_index;
+				progress.scan_total = highest_index;
+				progress.height = last_height.unwrap_or(progress.height);
+				c.set_progress(progress.clone());
+			}
 
-		if highest_index == last_retrieved_index {
-			break;
+			if highest_index <= last_retrieved_index || outputs_len == 0 {
+				return Ok(highest_index);
+			}
+			start_index = last_retrieved_index + 1;
 		}
-		start_index = last_retrieved_index + 1;
 	}
-	Ok(result_vec)
+
+	let (highest_index, _, _) = client.get_outputs_by_pmmr_index(1, 1)?;
+	if highest_index < start_index {
+		return Ok(highest_index);
+	}
+	let window_size = batch_size.max(1) * SCAN_WINDOW_BATCHES;
+	let num_windows = (highest_index - start_index) / window_size + 1;
+	warn!(
+		"Checking outputs up to index {} in {} ranges on {} threads",
+		highest_index, num_windows, limits.restore_threads,
+	);
+
+	let next_window = AtomicU64::new(0);
+	let stop = AtomicBool::new(false);
+	let (tx, rx) = mpsc::channel::<(u64, Result<(Vec<OutputResult>, Option<u64>), Error>)>();
+	let res = thread::scope(|s| -> Result<(), Error> {
+		for _ in 0..limits.restore_threads.min(num_windows as usize) {
+			let tx = tx.clone();
+			let (client, keychain) = (&client, &keychain);
+			let (next_window, stop) = (&next_window, &stop);
+			s.spawn(move |_| {
+				let cancelled = || cancel.map_or(false, |c| c.is_cancelled());
+				while !stop.load(Ordering::Relaxed) && !cancelled() {
+					let window = next_window.fetch_add(1, Ordering::Relaxed);
+					if window >= num_windows {
+						break;
+					}
+					let window_start = start_index + window * window_size;
+					let res = scan_output_range(
+						client,
+						keychain,
+						window_start,
+						window_start + window_size,
+						batch_size,
+					);
+					if tx.send((window, res)).is_err() {
+						break;
+					}
+				}
+			});
+		}
+		drop(tx);
+
+		// hand the outputs found to f in order, as each range completes
+		let mut pending = BTreeMap::new();
+		let mut next_to_process = 0;
+		for (window, res) in rx.iter() {
+			if let Some(Err(e)) = cancel.map(|c| c.check()) {
+				stop.store(true, Ordering::Relaxed);
+				return Err(e);
+			}
+			let range = match res {
+				Ok(r) => r,
+				Err(e) => {
+					stop.store(true, Ordering::Relaxed);
+					return Err(e);
+				}
+			};
+			pending.insert(window, range);
+			while let Some((outputs, last_height)) = pending.remove(&next_to_process) {
+				for output in outputs {
+					if let Err(e) = f(wallet, output) {
+						stop.store(true, Ordering::Relaxed);
+						return Err(e);
+					}
+					progress.outputs_found += 1;
+				}
+				progress.height = last_height.unwrap_or(progress.height);
+				next_to_process += 1;
+			}
+			if let Some(c) = cancel {
+				progress.scanned = start_index - 1 + next_to_process * window_size;
+				progress.scan_total = highest_index;
+				c.set_progress(progress.clone());
+			}
+		}
+		Ok(highest_index)
+	});
+	match res {
+		Ok(r) => r,
+		Err(_) => Err(ErrorKind::GenericError("Restore worker thread panicked".to_owned()).into()),
+	}
+}
+
+/// Collect the wallet's outputs in the UTXO set from `start_index`, along
+/// with the highest PMMR index scanned
+fn collect_chain_outputs<'a, T, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	start_index: u64,
+	cancel: Option<&CancelToken>,
+) -> Result<(Vec<OutputResult>, u64), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let mut result_vec: Vec<OutputResult> = vec![];
+	let highest_index =
+		scan_chain_outputs(wallet, keychain_mask, start_index, cancel, |_, output| {
+			result_vec.push(output);
+			Ok(())
+		})?;
+	Ok((result_vec, highest_index))
+}
+
+/// Record how far the UTXO set has been scanned
+fn save_last_scanned_block<'a, T, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	block: ScannedBlockInfo,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	debug!(
+		"Scanned UTXO set up to index {} at height {}",
+		block.mmr_index, block.height
+	);
+	let mut batch = wallet.batch(keychain_mask)?;
+	batch.save_last_scanned_block(block)?;
+	batch.commit()?;
+	Ok(())
 }
 
 ///
@@ -189,9 +390,9 @@ where
 	K: Keychain + 'a,
 {
 	let commit = wallet.calc_commit_for_cache(keychain_mask, output.value, &output.key_id)?;
+	let parent_key_id = wallet.derivation_scheme().parent_path(&output.key_id);
 	let mut batch = wallet.batch(keychain_mask)?;
 
-	let parent_key_id = output.key_id.parent_path();
 	if !found_parents.contains_key(&parent_key_id) {
 		found_parents.insert(parent_key_id.clone(), 0);
 		if let Some(ref mut s) = tx_stats {
@@ -270,7 +471,7 @@ where
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	let parent_key_id = output.key_id.parent_path();
+	let parent_key_id = wallet.derivation_scheme().parent_path(&output.key_id);
 	let updated_tx_entry = if output.tx_log_entry.is_some() {
 		let entries = updater::retrieve_txs(
 			wallet,
@@ -278,6 +479,7 @@ where
 			None,
 			Some(&parent_key_id),
 			false,
+			None,
 		)?;
 		if entries.len() > 0 {
 			let mut entry = entries[0].clone();
@@ -303,20 +505,38 @@ where
 
 /// Check / repair wallet contents
 /// assume wallet contents have been freshly updated with contents
-/// of latest block
+/// of latest block.
+/// Unless `full` is set, only the part of the UTXO set added since the last
+/// restore or check is scanned. The whole set is scanned if there was no
+/// earlier scan or the chain is now shorter than it was then.
+/// If `cancel` is cancelled while the UTXO set is being scanned, stops
+/// before making any change
 pub fn check_repair<'a, T, C, K>(
 	wallet: &mut T,
 	keychain_mask: Option<&SecretKey>,
 	delete_unconfirmed: bool,
+	full: bool,
+	cancel: Option<&CancelToken>,
 ) -> Result<(), Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
+	let height = wallet.w2n_client().get_chain_height()?;
+	let start_index = match wallet.last_scanned_block()? {
+		Some(b) if !full && b.height <= height => b.mmr_index + 1,
+		_ => 1,
+	};
+
 	// First, get a definitive list of outputs we own from the chain
-	warn!("Starting wallet check.");
-	let chain_outs = collect_chain_outputs(wallet, keychain_mask)?;
+	if start_index > 1 {
+		warn!("Starting wallet check from output index {}.", start_index);
+	} else {
+		warn!("Starting wallet check.");
+	}
+	let (chain_outs, highest_index) =
+		collect_chain_outputs(wallet, keychain_mask, start_index, cancel)?;
 	warn!(
 		"Identified {} wallet_outputs as belonging to this wallet",
 		chain_outs.len(),
@@ -352,9 +572,11 @@ where
 	for m in accidental_spend_outs.into_iter() {
 		let mut o = m.0;
 		warn!(
-			"Output for {} with ID {} ({:?}) marked as spent but exists in UTXO set. \
+			"Output for {} grin with ID {} ({}) marked as spent but exists in UTXO set. \
 			 Marking unspent and cancelling any associated transaction log entries.",
-			o.value, o.key_id, m.1.commit,
+			log_amount(o.value),
+			o.key_id,
+			log_commit(&m.1.commit),
 		);
 		o.status = OutputStatus::Unspent;
 		// any transactions associated with this should be cancelled
@@ -369,9 +591,11 @@ where
 	// Restore missing outputs, adding transaction for it back to the log
 	for m in missing_outs.into_iter() {
 		warn!(
-			"Confirmed output for {} with ID {} ({:?}) exists in UTXO set but not in wallet. \
+			"Confirmed output for {} grin with ID {} ({}) exists in UTXO set but not in wallet. \
 			 Restoring.",
-			m.value, m.key_id, m.commit,
+			log_amount(m.value),
+			m.key_id,
+			log_commit(&m.commit),
 		);
 		restore_missing_output(wallet, keychain_mask, m, &mut found_parents, &mut None)?;
 	}
@@ -381,9 +605,11 @@ where
 		for m in locked_outs.into_iter() {
 			let mut o = m.0;
 			warn!(
-				"Confirmed output for {} with ID {} ({:?}) exists in UTXO set and is locked. \
+				"Confirmed output for {} grin with ID {} ({}) exists in UTXO set and is locked. \
 				 Unlocking and cancelling associated transaction log entries.",
-				o.value, o.key_id, m.1.commit,
+				log_amount(o.value),
+				o.key_id,
+				log_commit(&m.1.commit),
 			);
 			o.status = OutputStatus::Unspent;
 			cancel_tx_log_entry(wallet, keychain_mask, &o)?;
@@ -400,9 +626,11 @@ where
 		for m in unconfirmed_outs.into_iter() {
 			let o = m.output.clone();
 			warn!(
-				"Unconfirmed output for {} with ID {} ({:?}) not in UTXO set. \
+				"Unconfirmed output for {} grin with ID {} ({}) not in UTXO set. \
 				 Deleting and cancelling associated transaction log entries.",
-				o.value, o.key_id, m.commit,
+				log_amount(o.value),
+				o.key_id,
+				log_commit(&m.commit),
 			);
 			cancel_tx_log_entry(wallet, keychain_mask, &o)?;
 			let mut batch = wallet.batch(keychain_mask)?;
@@ -414,30 +642,163 @@ where
 	// restore labels, account paths and child derivation indices
 	let label_base = "account";
 	let mut acct_index = 1;
+	let default_path = wallet.derivation_scheme().account_path(&[]);
 	for (path, max_child_index) in found_parents.iter() {
 		// default path already exists
-		if *path != ExtKeychain::derive_key_id(2, 0, 0, 0, 0) {
+		if *path != default_path {
 			let label = format!("{}_{}", label_base, acct_index);
 			keys::set_acct_path(wallet, keychain_mask, &label, path)?;
 			acct_index += 1;
 		}
+		// keys past those found may be in use, or reserved for use elsewhere
+		let next_child_index = wallet.current_child_index(path)?.max(max_child_index + 1);
 		let mut batch = wallet.batch(keychain_mask)?;
-		debug!("Next child for account {} is {}", path, max_child_index + 1);
-		batch.save_child_index(path, max_child_index + 1)?;
+		debug!("Next child for account {} is {}", path, next_child_index);
+		batch.save_child_index(path, next_child_index)?;
 		batch.commit()?;
 	}
+
+	save_last_scanned_block(
+		wallet,
+		keychain_mask,
+		ScannedBlockInfo {
+			height,
+			mmr_index: highest_index,
+		},
+	)?;
 	Ok(())
 }
 
-/// Restore a wallet
-pub fn restore<'a, T, C, K>(wallet: &mut T, keychain_mask: Option<&SecretKey>) -> Result<(), Error>
+/// Compare the wallet's outputs and transaction log against the whole UTXO
+/// set, reporting where they disagree without changing the wallet.
+/// [`check_repair`](fn.check_repair.html) fixes most of what is reported
+pub fn audit_chain<'a, T, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+) -> Result<ChainAuditReport, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let height = wallet.w2n_client().get_chain_height()?;
+	warn!(
+		"Starting wallet audit against the chain at height {}.",
+		height
+	);
+	let (chain_outs, _) = collect_chain_outputs(wallet, keychain_mask, 1, None)?;
+	let wallet_outputs = updater::retrieve_outputs(&mut *wallet, keychain_mask, true, None, None)?;
+	let chain_heights: HashMap<pedersen::Commitment, u64> =
+		chain_outs.iter().map(|o| (o.commit, o.height)).collect();
+
+	let mut report = ChainAuditReport {
+		height,
+		..Default::default()
+	};
+	for o in chain_outs.iter() {
+		if !wallet_outputs.iter().any(|wo| wo.commit == o.commit) {
+			report.unknown_outputs.push(AuditedOutput {
+				commit: o.commit,
+				key_id: o.key_id.clone(),
+				value: o.value,
+				status: None,
+				tx_log_entry: None,
+				chain_height: Some(o.height),
+			});
+		}
+	}
+
+	let mut unconfirmed_txs_on_chain = HashSet::new();
+	for wo in wallet_outputs.iter() {
+		let chain_height = chain_heights.get(&wo.commit).cloned();
+		let list = match (&wo.output.status, chain_height) {
+			(OutputStatus::Unspent, None) => &mut report.missing_outputs,
+			(OutputStatus::Locked, None) => &mut report.spent_unmarked_outputs,
+			(OutputStatus::Spent, Some(_)) => &mut report.unspent_marked_spent_outputs,
+			(OutputStatus::Unconfirmed, Some(_)) => &mut report.confirmed_unmarked_outputs,
+			_ => continue,
+		};
+		list.push(AuditedOutput {
+			commit: wo.commit,
+			key_id: wo.output.key_id.clone(),
+			value: wo.output.value,
+			status: Some(wo.output.status.clone()),
+			tx_log_entry: wo.output.tx_log_entry,
+			chain_height,
+		});
+		// outputs still unconfirmed are tied to the transactions creating
+		// them, confirmed by the outputs being on chain
+		if let (OutputStatus::Unconfirmed, Some(id)) = (&wo.output.status, wo.output.tx_log_entry) {
+			unconfirmed_txs_on_chain.insert((wo.output.root_key_id.clone(), id));
+		}
+	}
+	report.unconfirmed_txs_on_chain = wallet
+		.tx_log_iter()?
+		.filter(|t| {
+			!t.confirmed && unconfirmed_txs_on_chain.contains(&(t.parent_key_id.clone(), t.id))
+		})
+		.map(|t| t.id)
+		.collect();
+
+	warn!(
+		"Wallet audit found {} unknown, {} missing, {} spent but unmarked, {} marked spent \
+		 and {} unmarked confirmed outputs.",
+		report.unknown_outputs.len(),
+		report.missing_outputs.len(),
+		report.spent_unmarked_outputs.len(),
+		report.unspent_marked_spent_outputs.len(),
+		report.confirmed_unmarked_outputs.len(),
+	);
+	Ok(report)
+}
+
+/// Undo a cancelled restore, deleting the outputs it saved and the
+/// transaction log entries it created
+fn rollback_restore<'a, T, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	known_txs: &HashSet<(Identifier, u32)>,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	// restore only runs on a wallet without outputs
+	let outputs: Vec<OutputData> = wallet.iter()?.collect();
+	let txs: Vec<TxLogEntry> = wallet
+		.tx_log_iter()?
+		.filter(|t| !known_txs.contains(&(t.parent_key_id.clone(), t.id)))
+		.collect();
+	warn!(
+		"Restore cancelled, removing {} restored outputs",
+		outputs.len()
+	);
+	let mut batch = wallet.batch(keychain_mask)?;
+	for o in outputs {
+		batch.delete(&o.key_id, &o.mmr_index)?;
+	}
+	for t in txs {
+		batch.delete_tx_log_entry(t.id, &t.parent_key_id)?;
+	}
+	batch.commit()?;
+	Ok(())
+}
+
+/// Restore a wallet. If `cancel` is cancelled while the UTXO set is being
+/// scanned, the outputs restored so far are removed again
+pub fn restore<'a, T, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	cancel: Option<&CancelToken>,
+) -> Result<(), Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
 	// Don't proceed if wallet_data has anything in it
-	let is_empty = wallet.iter().next().is_none();
+	let is_empty = wallet.iter()?.next().is_none();
 	if !is_empty {
 		error!("Not restoring. Please back up and remove existing db directory first.");
 		return Ok(());
@@ -445,34 +806,49 @@ where
 
 	let now = Instant::now();
 	warn!("Starting restore.");
-
-	let result_vec = collect_chain_outputs(wallet, keychain_mask)?;
-
-	warn!(
-		"Identified {} wallet_outputs as belonging to this wallet",
-		result_vec.len(),
-	);
+	let height = wallet.w2n_client().get_chain_height()?;
 
 	let mut found_parents: HashMap<Identifier, u32> = HashMap::new();
 	let mut restore_stats = HashMap::new();
-
-	// Now save what we have
-	for output in result_vec {
+	let mut num_restored = 0;
+	let known_txs: HashSet<(Identifier, u32)> = wallet
+		.tx_log_iter()?
+		.map(|t| (t.parent_key_id, t.id))
+		.collect();
+
+	// Save outputs as they're found, rather than holding the whole set in memory
+	let res = scan_chain_outputs(wallet, keychain_mask, 1, cancel, |wallet, output| {
+		num_restored += 1;
 		restore_missing_output(
 			wallet,
 			keychain_mask,
 			output,
 			&mut found_parents,
 			&mut Some(&mut restore_stats),
-		)?;
-	}
+		)
+	});
+	let highest_index = match res {
+		Ok(i) => i,
+		Err(e) => {
+			if let ErrorKind::Cancelled = e.kind() {
+				rollback_restore(wallet, keychain_mask, &known_txs)?;
+			}
+			return Err(e);
+		}
+	};
+
+	warn!(
+		"Identified {} wallet_outputs as belonging to this wallet",
+		num_restored,
+	);
 
 	// restore labels, account paths and child derivation indices
 	let label_base = "account";
 	let mut acct_index = 1;
+	let default_path = wallet.derivation_scheme().account_path(&[]);
 	for (path, max_child_index) in found_parents.iter() {
 		// default path already exists
-		if *path != ExtKeychain::derive_key_id(2, 0, 0, 0, 0) {
+		if *path != default_path {
 			let label = format!("{}_{}", label_base, acct_index);
 			keys::set_acct_path(wallet, keychain_mask, &label, path)?;
 			acct_index += 1;
@@ -494,6 +870,15 @@ where
 		batch.commit()?;
 	}
 
+	save_last_scanned_block(
+		wallet,
+		keychain_mask,
+		ScannedBlockInfo {
+			height,
+			mmr_index: highest_index,
+		},
+	)?;
+
 	let mut sec = now.elapsed().as_secs();
 	let min = sec / 60;
 	sec %= 60;
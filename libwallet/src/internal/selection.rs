@@ -12,9 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-//! Selection of inputs for building transactions
+//! Selection of inputs for building transactions. Which of the eligible
+//! outputs are spent is decided by a [`CoinSelection`](trait.CoinSelection.html)
+//! strategy, chosen by name from a process-wide registry holding the built-in
+//! strategies and any registered by the application
 
+use crate::confirmations::ConfirmationRequirements;
 use crate::error::{Error, ErrorKind};
+use crate::grin_core::consensus::{BLOCK_INPUT_WEIGHT, BLOCK_KERNEL_WEIGHT, BLOCK_OUTPUT_WEIGHT};
 use crate::grin_core::core::amount_to_hr_string;
 use crate::grin_core::libtx::{
 	build,
@@ -23,10 +28,139 @@ use crate::grin_core::libtx::{
 };
 use crate::grin_keychain::{Identifier, Keychain};
 use crate::grin_util::secp::key::SecretKey;
+use crate::grin_util::RwLock;
 use crate::internal::keys;
-use crate::slate::Slate;
+use crate::slate::{ParticipantMessages, Slate};
 use crate::types::*;
+use rand::{thread_rng, Rng};
+use std::cmp::{self, Ordering};
 use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A strategy choosing which of a wallet's outputs to spend
+pub trait CoinSelection: Send + Sync {
+	/// Choose outputs worth at least `amount` from the eligible ones, which are
+	/// given in order of increasing value. Up to `max_outputs` outputs should
+	/// be chosen where that's enough, but it's a soft limit. Returns `None` if
+	/// the outputs aren't worth enough
+	fn select(
+		&self,
+		amount: u64,
+		max_outputs: usize,
+		eligible: &[OutputData],
+	) -> Option<Vec<OutputData>>;
+}
+
+/// Spends as few outputs as needed, smallest first
+pub struct SmallestFirst;
+
+impl CoinSelection for SmallestFirst {
+	fn select(
+		&self,
+		amount: u64,
+		max_outputs: usize,
+		eligible: &[OutputData],
+	) -> Option<Vec<OutputData>> {
+		select_window(amount, max_outputs, false, eligible)
+	}
+}
+
+/// Spends as many outputs as possible, up to `max_outputs`, to shrink the
+/// wallet's output set
+pub struct UseAll;
+
+impl CoinSelection for UseAll {
+	fn select(
+		&self,
+		amount: u64,
+		max_outputs: usize,
+		eligible: &[OutputData],
+	) -> Option<Vec<OutputData>> {
+		select_window(amount, max_outputs, true, eligible)
+	}
+}
+
+/// Considers outputs in a random order weighted by value, so repeated sends
+/// don't deterministically pick the same inputs, falling back to
+/// [`SmallestFirst`](struct.SmallestFirst.html) or [`UseAll`](struct.UseAll.html)
+pub struct WeightedRandom {
+	/// Whether to spend all of a random subset of up to `max_outputs` outputs
+	pub use_all: bool,
+}
+
+impl WeightedRandom {
+	fn select_with<R: Rng>(
+		&self,
+		amount: u64,
+		max_outputs: usize,
+		eligible: &[OutputData],
+		rng: &mut R,
+	) -> Option<Vec<OutputData>> {
+		let candidates = weighted_shuffle(eligible.to_vec(), rng)
+			.into_iter()
+			.take(max_outputs)
+			.collect::<Vec<_>>();
+		select_from(amount, self.use_all, candidates)
+			.or_else(|| select_window(amount, max_outputs, self.use_all, eligible))
+	}
+}
+
+impl CoinSelection for WeightedRandom {
+	fn select(
+		&self,
+		amount: u64,
+		max_outputs: usize,
+		eligible: &[OutputData],
+	) -> Option<Vec<OutputData>> {
+		self.select_with(amount, max_outputs, eligible, &mut thread_rng())
+	}
+}
+
+lazy_static! {
+	/// Coin selection strategies by name
+	static ref COIN_SELECTIONS: RwLock<HashMap<String, Arc<dyn CoinSelection>>> = {
+		let mut strategies: HashMap<String, Arc<dyn CoinSelection>> = HashMap::new();
+		strategies.insert("smallest".to_owned(), Arc::new(SmallestFirst));
+		strategies.insert("all".to_owned(), Arc::new(UseAll));
+		strategies.insert("random".to_owned(), Arc::new(WeightedRandom { use_all: false }));
+		strategies.insert("random_all".to_owned(), Arc::new(WeightedRandom { use_all: true }));
+		RwLock::new(strategies)
+	};
+}
+
+/// Register a coin selection strategy under the given name, so it can be
+/// chosen per transaction. Replaces any strategy already registered under the
+/// name, including the built-in `smallest`, `all`, `random` and `random_all`
+pub fn register_coin_selection(name: &str, strategy: Arc<dyn CoinSelection>) {
+	COIN_SELECTIONS.write().insert(name.to_owned(), strategy);
+}
+
+/// Names of the registered coin selection strategies, sorted
+pub fn coin_selection_names() -> Vec<String> {
+	let mut names: Vec<String> = COIN_SELECTIONS.read().keys().cloned().collect();
+	names.sort();
+	names
+}
+
+/// The strategy registered under `name` if one's given, otherwise the built-in
+/// strategy described by the older `use_all` and `randomize` flags
+pub fn coin_selection(
+	name: Option<&str>,
+	use_all: bool,
+	randomize: bool,
+) -> Result<Arc<dyn CoinSelection>, Error> {
+	let name = match (name, use_all, randomize) {
+		(Some(n), _, _) => n,
+		(None, false, false) => "smallest",
+		(None, true, false) => "all",
+		(None, false, true) => "random",
+		(None, true, true) => "random_all",
+	};
+	match COIN_SELECTIONS.read().get(name) {
+		Some(s) => Ok(s.clone()),
+		None => Err(ErrorKind::UnknownCoinSelection(name.to_owned()).into()),
+	}
+}
 
 /// Initialize a transaction on the sender side, returns a corresponding
 /// libwallet transaction slate with the appropriate inputs selected,
@@ -41,7 +175,7 @@ pub fn build_send_tx<'a, T: ?Sized, C, K>(
 	minimum_confirmations: u64,
 	max_outputs: usize,
 	change_outputs: usize,
-	selection_strategy_is_use_all: bool,
+	strategy: &dyn CoinSelection,
 	parent_key_id: Identifier,
 	use_test_nonce: bool,
 ) -> Result<Context, Error>
@@ -59,7 +193,7 @@ where
 		slate.lock_height,
 		max_outputs,
 		change_outputs,
-		selection_strategy_is_use_all,
+		strategy,
 		&parent_key_id,
 	)?;
 	let blinding = slate.add_transaction_elements(keychain, &ProofBuilder::new(keychain), elems)?;
@@ -120,6 +254,7 @@ where
 			),
 		);
 	}
+	wallet.store_slate(TxStep::Lock, slate)?;
 
 	let tx_entry = {
 		let lock_inputs = context.get_inputs().clone();
@@ -131,9 +266,12 @@ where
 		let log_id = batch.next_tx_log_id(&parent_key_id)?;
 		let mut t = TxLogEntry::new(parent_key_id.clone(), TxLogEntryType::TxSent, log_id);
 		t.tx_slate_id = Some(slate_id.clone());
+		t.creation_height = Some(height);
 		let filename = format!("{}.grintx", slate_id);
 		t.stored_tx = Some(filename);
 		t.fee = Some(slate.fee);
+		t.fluff = context.fluff;
+		t.record_step(TxStep::Lock, slate);
 		let mut amount_debited = 0;
 		t.num_inputs = lock_inputs.len();
 		for id in lock_inputs {
@@ -191,43 +329,86 @@ where
 	// Create a potential output for this transaction
 	let key_id = keys::next_available_key(wallet, keychain_mask).unwrap();
 	let keychain = wallet.keychain(keychain_mask)?;
-	let key_id_inner = key_id.clone();
-	let amount = slate.amount;
-	let height = slate.height;
+	let context = add_recipient_output(&keychain, slate, &key_id, &parent_key_id, use_test_rng)?;
+	let messages = Some(slate.participant_messages());
+	save_recipient_output(
+		wallet,
+		keychain_mask,
+		slate,
+		&key_id,
+		&parent_key_id,
+		messages,
+	)?;
+
+	Ok((key_id, context))
+}
 
-	let slate_id = slate.id.clone();
+/// Adds an output of the slate's amount to it, built from the given key,
+/// returning the context holding the blinding sum. Only needs the keychain,
+/// so can be done without holding the wallet
+pub fn add_recipient_output<K>(
+	keychain: &K,
+	slate: &mut Slate,
+	key_id: &Identifier,
+	parent_key_id: &Identifier,
+	use_test_rng: bool,
+) -> Result<Context, Error>
+where
+	K: Keychain,
+{
+	let amount = slate.amount;
 	let blinding = slate.add_transaction_elements(
-		&keychain,
-		&ProofBuilder::new(&keychain),
+		keychain,
+		&ProofBuilder::new(keychain),
 		vec![build::output(amount, key_id.clone())],
 	)?;
 
 	// Add blinding sum to our context
 	let mut context = Context::new(
 		keychain.secp(),
-		blinding
-			.secret_key(wallet.keychain(keychain_mask)?.secp())
-			.unwrap(),
-		&parent_key_id,
+		blinding.secret_key(keychain.secp()).unwrap(),
+		parent_key_id,
 		use_test_rng,
 		1,
 	);
+	context.add_output(key_id, &None, amount);
+	Ok(context)
+}
 
-	context.add_output(&key_id, &None, amount);
-	let messages = Some(slate.participant_messages());
-	let commit = wallet.calc_commit_for_cache(keychain_mask, amount, &key_id_inner)?;
+/// Saves the output added to the slate by
+/// [`add_recipient_output`](fn.add_recipient_output.html), along with a new
+/// transaction containing it
+pub fn save_recipient_output<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	slate: &Slate,
+	key_id: &Identifier,
+	parent_key_id: &Identifier,
+	messages: Option<ParticipantMessages>,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let amount = slate.amount;
+	let height = slate.height;
+	let commit = wallet.calc_commit_for_cache(keychain_mask, amount, key_id)?;
+	wallet.store_slate(TxStep::Receive, slate)?;
 	let mut batch = wallet.batch(keychain_mask)?;
-	let log_id = batch.next_tx_log_id(&parent_key_id)?;
+	let log_id = batch.next_tx_log_id(parent_key_id)?;
 	let mut t = TxLogEntry::new(parent_key_id.clone(), TxLogEntryType::TxReceived, log_id);
-	t.tx_slate_id = Some(slate_id);
+	t.tx_slate_id = Some(slate.id);
+	t.creation_height = Some(height);
 	t.amount_credited = amount;
 	t.num_outputs = 1;
 	t.messages = messages;
+	t.record_step(TxStep::Receive, slate);
 	batch.save(OutputData {
 		root_key_id: parent_key_id.clone(),
-		key_id: key_id_inner.clone(),
+		key_id: key_id.clone(),
 		mmr_index: None,
-		n_child: key_id_inner.to_path().last_path_index(),
+		n_child: key_id.to_path().last_path_index(),
 		commit: commit,
 		value: amount,
 		status: OutputStatus::Unconfirmed,
@@ -236,10 +417,9 @@ where
 		is_coinbase: false,
 		tx_log_entry: Some(log_id),
 	})?;
-	batch.save_tx_log_entry(t, &parent_key_id)?;
+	batch.save_tx_log_entry(t, parent_key_id)?;
 	batch.commit()?;
-
-	Ok((key_id, context))
+	Ok(())
 }
 
 /// Builds a transaction to send to someone from the HD seed associated with the
@@ -254,7 +434,7 @@ pub fn select_send_tx<'a, T: ?Sized, C, K, B>(
 	lock_height: u64,
 	max_outputs: usize,
 	change_outputs: usize,
-	selection_strategy_is_use_all: bool,
+	strategy: &dyn CoinSelection,
 	parent_key_id: &Identifier,
 ) -> Result<
 	(
@@ -278,7 +458,7 @@ where
 		minimum_confirmations,
 		max_outputs,
 		change_outputs,
-		selection_strategy_is_use_all,
+		strategy,
 		&parent_key_id,
 	)?;
 
@@ -301,7 +481,7 @@ pub fn select_coins_and_fee<'a, T: ?Sized, C, K>(
 	minimum_confirmations: u64,
 	max_outputs: usize,
 	change_outputs: usize,
-	selection_strategy_is_use_all: bool,
+	strategy: &dyn CoinSelection,
 	parent_key_id: &Identifier,
 ) -> Result<
 	(
@@ -317,16 +497,75 @@ where
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	// select some spendable coins from the wallet
-	let (max_outputs, mut coins) = select_coins(
+	select_coins_and_fee_excluding(
 		wallet,
 		amount,
 		current_height,
 		minimum_confirmations,
 		max_outputs,
-		selection_strategy_is_use_all,
+		change_outputs,
+		strategy,
 		parent_key_id,
-	);
+		&[],
+	)
+}
+
+/// Select outputs and calculate the fee as
+/// [`select_coins_and_fee`](fn.select_coins_and_fee.html) does, leaving out
+/// the outputs given, as if they'd already been spent
+pub fn select_coins_and_fee_excluding<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	amount: u64,
+	current_height: u64,
+	minimum_confirmations: u64,
+	max_outputs: usize,
+	change_outputs: usize,
+	strategy: &dyn CoinSelection,
+	parent_key_id: &Identifier,
+	excluded: &[OutputData],
+) -> Result<
+	(
+		Vec<OutputData>,
+		u64, // total
+		u64, // amount
+		u64, // fee
+	),
+	Error,
+>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let eligible = eligible_coins(
+		wallet,
+		current_height,
+		minimum_confirmations,
+		parent_key_id,
+		excluded,
+	)?;
+	coins_and_fee(amount, max_outputs, change_outputs, strategy, &eligible)
+}
+
+/// Select outputs from the eligible ones, given in order of increasing value,
+/// and calculate the fee of spending them
+fn coins_and_fee(
+	amount: u64,
+	max_outputs: usize,
+	change_outputs: usize,
+	strategy: &dyn CoinSelection,
+	eligible: &[OutputData],
+) -> Result<
+	(
+		Vec<OutputData>,
+		u64, // total
+		u64, // amount
+		u64, // fee
+	),
+	Error,
+> {
+	// select some spendable coins from the wallet
+	let (max_outputs, mut coins) = select_eligible(amount, max_outputs, strategy, eligible);
 
 	// sender is responsible for setting the fee on the partial tx
 	// recipient should double check the fee calculation and not blindly trust the
@@ -380,16 +619,7 @@ where
 			}
 
 			// select some spendable coins from the wallet
-			coins = select_coins(
-				wallet,
-				amount_with_fee,
-				current_height,
-				minimum_confirmations,
-				max_outputs,
-				selection_strategy_is_use_all,
-				parent_key_id,
-			)
-			.1;
+			coins = select_eligible(amount_with_fee, max_outputs, strategy, eligible).1;
 			fee = tx_fee(coins.len(), num_outputs, 1, None);
 			total = coins.iter().map(|c| c.value).sum();
 			amount_with_fee = amount + fee;
@@ -398,6 +628,54 @@ where
 	Ok((coins, total, amount, fee))
 }
 
+/// Weight of a transaction with a single kernel and the given number of
+/// inputs and outputs, as counted towards the block weight limit
+pub fn tx_weight(num_inputs: usize, num_outputs: usize) -> u64 {
+	(num_inputs * BLOCK_INPUT_WEIGHT + num_outputs * BLOCK_OUTPUT_WEIGHT + BLOCK_KERNEL_WEIGHT)
+		as u64
+}
+
+/// Select outputs to consolidate, smallest first, grouped into batches that
+/// each fit in a transaction of at most `max_weight` creating `num_outputs`
+/// outputs. Batches that wouldn't reduce the number of outputs are dropped
+pub fn select_consolidation_coins<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	current_height: u64,
+	minimum_confirmations: u64,
+	num_outputs: usize,
+	max_weight: u64,
+	max_txs: usize,
+	parent_key_id: &Identifier,
+) -> Result<Vec<Vec<OutputData>>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let requirements =
+		ConfirmationRequirements::new(&*wallet, parent_key_id, minimum_confirmations)?;
+	let mut eligible = wallet
+		.iter()?
+		.filter(|out| {
+			out.root_key_id == *parent_key_id
+				&& out.eligible_to_spend(current_height, requirements.required(out))
+		})
+		.collect::<Vec<OutputData>>();
+	eligible.sort_by_key(|out| out.value);
+
+	let max_inputs =
+		(max_weight.saturating_sub(tx_weight(0, num_outputs)) / BLOCK_INPUT_WEIGHT as u64) as usize;
+	if max_inputs == 0 {
+		return Ok(vec![]);
+	}
+	Ok(eligible
+		.chunks(max_inputs)
+		.take(max_txs)
+		.filter(|batch| batch.len() > num_outputs)
+		.map(|batch| batch.to_vec())
+		.collect())
+}
+
 /// Selects inputs and change for a transaction
 pub fn inputs_and_change<'a, T: ?Sized, C, K, B>(
 	coins: &Vec<OutputData>,
@@ -440,18 +718,41 @@ where
 		}
 	}
 
+	let change_amounts_derivations =
+		build_change_outputs(wallet, keychain_mask, change, num_change_outputs)?;
+	for (change_amount, change_key, _) in &change_amounts_derivations {
+		parts.push(build::output(*change_amount, change_key.clone()));
+	}
+
+	Ok((parts, change_amounts_derivations))
+}
+
+/// Splits the change into outputs, deriving a key for each
+pub fn build_change_outputs<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	change: u64,
+	num_change_outputs: usize,
+) -> Result<Vec<(u64, Identifier, Option<u64>)>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
 	let mut change_amounts_derivations = vec![];
 
 	if change == 0 {
 		debug!("No change (sending exactly amount + fee), no change outputs to build");
 	} else {
+		// don't create outputs of no value when there's less change than outputs
+		let num_change_outputs = cmp::min(num_change_outputs as u64, change) as usize;
 		debug!(
 			"Building change outputs: total change: {} ({} outputs)",
 			change, num_change_outputs
 		);
 
 		let part_change = change / num_change_outputs as u64;
-		let remainder_change = change % part_change;
+		let remainder_change = change % num_change_outputs as u64;
 
 		for x in 0..num_change_outputs {
 			// n-1 equal change_outputs and a final one accounting for any remainder
@@ -461,52 +762,105 @@ where
 				part_change
 			};
 
-			let change_key = wallet.next_child(keychain_mask).unwrap();
-
-			change_amounts_derivations.push((change_amount, change_key.clone(), None));
-			parts.push(build::output(change_amount, change_key));
+			let change_key = wallet.next_child(keychain_mask)?;
+			change_amounts_derivations.push((change_amount, change_key, None));
 		}
 	}
 
-	Ok((parts, change_amounts_derivations))
+	Ok(change_amounts_derivations)
 }
 
-/// Select spendable coins from a wallet.
-/// Default strategy is to spend the maximum number of outputs (up to
-/// max_outputs). Alternative strategy is to spend smallest outputs first
-/// but only as many as necessary. When we introduce additional strategies
-/// we should pass something other than a bool in.
-/// TODO: Possibly move this into another trait to be owned by a wallet?
-
+/// Select spendable coins from a wallet with the given strategy. If the
+/// eligible outputs aren't worth enough, the largest of them are returned, so
+/// the caller can report what's available
 pub fn select_coins<'a, T: ?Sized, C, K>(
 	wallet: &mut T,
 	amount: u64,
 	current_height: u64,
 	minimum_confirmations: u64,
 	max_outputs: usize,
-	select_all: bool,
+	strategy: &dyn CoinSelection,
 	parent_key_id: &Identifier,
-) -> (usize, Vec<OutputData>)
+) -> Result<(usize, Vec<OutputData>), Error>
 //    max_outputs_available, Outputs
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	// first find all eligible outputs based on number of confirmations
+	let eligible = eligible_coins(
+		wallet,
+		current_height,
+		minimum_confirmations,
+		parent_key_id,
+		&[],
+	)?;
+	Ok(select_eligible(amount, max_outputs, strategy, &eligible))
+}
+
+/// The wallet's outputs eligible to be spent from the given account, other
+/// than those excluded, in order of increasing value
+fn eligible_coins<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	current_height: u64,
+	minimum_confirmations: u64,
+	parent_key_id: &Identifier,
+	excluded: &[OutputData],
+) -> Result<Vec<OutputData>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	// first find all eligible outputs based on number of confirmations, which
+	// may differ by where the outputs came from
+	let requirements =
+		ConfirmationRequirements::new(&*wallet, parent_key_id, minimum_confirmations)?;
 	let mut eligible = wallet
-		.iter()
+		.iter()?
 		.filter(|out| {
 			out.root_key_id == *parent_key_id
-				&& out.eligible_to_spend(current_height, minimum_confirmations)
+				&& out.eligible_to_spend(current_height, requirements.required(out))
+				&& !excluded
+					.iter()
+					.any(|e| e.key_id == out.key_id && e.mmr_index == out.mmr_index)
 		})
 		.collect::<Vec<OutputData>>();
 
-	let max_available = eligible.len();
-
 	// sort eligible outputs by increasing value
 	eligible.sort_by_key(|out| out.value);
+	Ok(eligible)
+}
+
+/// Select outputs from the eligible ones with the given strategy, returning
+/// the number eligible alongside them
+fn select_eligible(
+	amount: u64,
+	max_outputs: usize,
+	strategy: &dyn CoinSelection,
+	eligible: &[OutputData],
+) -> (usize, Vec<OutputData>) {
+	if let Some(outputs) = strategy.select(amount, max_outputs, eligible) {
+		return (eligible.len(), outputs);
+	}
 
+	// we failed to find a suitable set of outputs to spend,
+	// so return the largest amount we can so we can provide guidance on what is
+	// possible
+	(
+		eligible.len(),
+		eligible.iter().rev().take(max_outputs).cloned().collect(),
+	)
+}
+
+/// Select outputs from those given, sorted by increasing value, considering
+/// `max_outputs` consecutive outputs at a time
+fn select_window(
+	amount: u64,
+	max_outputs: usize,
+	select_all: bool,
+	eligible: &[OutputData],
+) -> Option<Vec<OutputData>> {
 	// use a sliding window to identify potential sets of possible outputs to spend
 	// Case of amount > total amount of max_outputs(500):
 	// The limit exists because by default, we always select as many inputs as
@@ -519,33 +873,38 @@ where
 		for window in eligible.windows(max_outputs) {
 			let windowed_eligibles = window.iter().cloned().collect::<Vec<_>>();
 			if let Some(outputs) = select_from(amount, select_all, windowed_eligibles) {
-				return (max_available, outputs);
+				return Some(outputs);
 			}
 		}
 		// Not exist in any window of which total amount >= amount.
 		// Then take coins from the smallest one up to the total amount of selected
 		// coins = the amount.
-		if let Some(outputs) = select_from(amount, false, eligible.clone()) {
+		let outputs = select_from(amount, false, eligible.to_vec());
+		if let Some(o) = outputs.as_ref() {
 			debug!(
 				"Extending maximum number of outputs. {} outputs selected.",
-				outputs.len()
+				o.len()
 			);
-			return (max_available, outputs);
 		}
+		outputs
 	} else {
-		if let Some(outputs) = select_from(amount, select_all, eligible.clone()) {
-			return (max_available, outputs);
-		}
+		select_from(amount, select_all, eligible.to_vec())
 	}
+}
 
-	// we failed to find a suitable set of outputs to spend,
-	// so return the largest amount we can so we can provide guidance on what is
-	// possible
-	eligible.reverse();
-	(
-		max_available,
-		eligible.iter().take(max_outputs).cloned().collect(),
-	)
+/// Randomly orders the given outputs, with the probability of an output appearing
+/// early in the list weighted by its value
+fn weighted_shuffle<R: Rng>(outputs: Vec<OutputData>, rng: &mut R) -> Vec<OutputData> {
+	let mut keyed = outputs
+		.into_iter()
+		.map(|out| {
+			// Efraimidis-Spirakis: key = ln(u) / w, larger keys come first
+			let u: f64 = rng.gen_range(std::f64::MIN_POSITIVE, 1.0);
+			(u.ln() / (out.value.max(1) as f64), out)
+		})
+		.collect::<Vec<_>>();
+	keyed.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+	keyed.into_iter().map(|(_, out)| out).collect()
 }
 
 fn select_from(amount: u64, select_all: bool, outputs: Vec<OutputData>) -> Option<Vec<OutputData>> {
@@ -571,3 +930,69 @@ fn select_from(amount: u64, select_all: bool, outputs: Vec<OutputData>) -> Optio
 		None
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::grin_keychain::ExtKeychain;
+	use rand::rngs::StdRng;
+	use rand::SeedableRng;
+
+	/// Weighted random selection with a seeded RNG, choosing the same outputs
+	/// on every run
+	struct Seeded(WeightedRandom, [u8; 32]);
+
+	impl CoinSelection for Seeded {
+		fn select(
+			&self,
+			amount: u64,
+			max_outputs: usize,
+			eligible: &[OutputData],
+		) -> Option<Vec<OutputData>> {
+			let mut rng = StdRng::from_seed(self.1);
+			self.0.select_with(amount, max_outputs, eligible, &mut rng)
+		}
+	}
+
+	fn output(i: u32, value: u64) -> OutputData {
+		OutputData {
+			root_key_id: ExtKeychain::derive_key_id(2, 0, 0, 0, 0),
+			key_id: ExtKeychain::derive_key_id(3, 0, 0, i, 0),
+			n_child: i,
+			commit: None,
+			mmr_index: Some(i as u64),
+			value,
+			status: OutputStatus::Unspent,
+			height: 1,
+			lock_height: 0,
+			is_coinbase: false,
+			tx_log_entry: None,
+		}
+	}
+
+	#[test]
+	fn randomize_selection() {
+		// outputs of 1 to 20 grins, in order of increasing value
+		let eligible: Vec<OutputData> = (1..=20)
+			.map(|i| output(i, i as u64 * 1_000_000_000))
+			.collect();
+		let amount = 25_000_000_000;
+		let key_ids =
+			|coins: &[OutputData]| coins.iter().map(|o| o.key_id.clone()).collect::<Vec<_>>();
+
+		let (default, _, _, _) = coins_and_fee(amount, 500, 1, &SmallestFirst, &eligible).unwrap();
+		let seeded = Seeded(WeightedRandom { use_all: false }, [7; 32]);
+		let (coins, total, _, fee) = coins_and_fee(amount, 500, 1, &seeded, &eligible).unwrap();
+
+		// the seed decides the outputs chosen, which aren't those chosen
+		// smallest first
+		let (again, _, _, _) = coins_and_fee(amount, 500, 1, &seeded, &eligible).unwrap();
+		assert_eq!(key_ids(&again), key_ids(&coins));
+		assert_ne!(key_ids(&coins), key_ids(&default));
+
+		// but still cover the amount and the fee of spending them with change
+		assert_eq!(total, coins.iter().map(|o| o.value).sum::<u64>());
+		assert_eq!(fee, tx_fee(coins.len(), 2, 1, None));
+		assert!(total >= amount + fee);
+	}
+}
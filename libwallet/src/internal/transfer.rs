@@ -0,0 +1,210 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Transfer of an account's label, derivation index, outputs and transaction
+//! log between wallet instances sharing the same seed. The exported blob is
+//! encrypted with a key derived from the seed, so only a wallet with the same
+//! seed can read it
+
+use std::collections::HashMap;
+
+use crate::blake2::blake2b::blake2b;
+use crate::error::{Error, ErrorKind};
+use crate::grin_core::core::Transaction;
+use crate::grin_keychain::{Keychain, SwitchCommitmentType};
+use crate::grin_util::secp::key::SecretKey;
+use crate::grin_util::{from_hex, to_hex};
+use crate::types::{AcctPathMapping, NodeClient, OutputData, TxLogEntry, WalletBackend};
+use rand::{thread_rng, Rng};
+use ring::aead;
+
+/// Version of the blob format, written as its first byte
+const TRANSFER_VERSION: u8 = 1;
+/// Personalisation of the hash deriving the encryption key from the seed
+const TRANSFER_KEY_DOMAIN: &[u8] = b"grin_wallet_account_transfer";
+/// Extension of stored transaction files
+const STORED_TX_EXT: &str = ".grintx";
+
+/// Everything exported about an account
+#[derive(Serialize, Deserialize)]
+struct AccountTransfer {
+	account: AcctPathMapping,
+	child_index: u32,
+	outputs: Vec<OutputData>,
+	txs: Vec<TxLogEntry>,
+	/// Stored transactions, by the name they're stored under
+	stored_txs: HashMap<String, Transaction>,
+}
+
+/// Key encrypting transfers, only derivable from the wallet's seed
+fn transfer_key<K>(keychain: &K) -> Result<Vec<u8>, Error>
+where
+	K: Keychain,
+{
+	let root = keychain.derive_key(0, &K::root_key_id(), &SwitchCommitmentType::None)?;
+	Ok(blake2b(32, &root.0, TRANSFER_KEY_DOMAIN)
+		.as_bytes()
+		.to_vec())
+}
+
+fn transfer_error(msg: &str) -> Error {
+	ErrorKind::AccountTransfer(msg.to_owned()).into()
+}
+
+/// Export the account with the given label as an encrypted, hex-encoded blob
+pub fn export_account<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	label: &str,
+) -> Result<String, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let account = match w.get_acct_path(label.to_owned())? {
+		Some(a) => a,
+		None => return Err(ErrorKind::UnknownAccountLabel(label.to_owned()).into()),
+	};
+	let outputs: Vec<OutputData> = w
+		.iter()?
+		.filter(|o| o.root_key_id == account.path)
+		.collect();
+	let txs: Vec<TxLogEntry> = w
+		.tx_log_iter()?
+		.filter(|t| t.parent_key_id == account.path)
+		.collect();
+	let mut stored_txs = HashMap::new();
+	for t in &txs {
+		if let (Some(name), Ok(Some(tx))) = (t.stored_tx.clone(), w.get_stored_tx(t)) {
+			stored_txs.insert(name, tx);
+		}
+	}
+	let transfer = AccountTransfer {
+		child_index: w.current_child_index(&account.path)?,
+		account,
+		outputs,
+		txs,
+		stored_txs,
+	};
+
+	let key = transfer_key(&w.keychain(keychain_mask)?)?;
+	let mut data = serde_json::to_vec(&transfer)
+		.map_err(|e| ErrorKind::Format(format!("Serializing account: {}", e)))?;
+	let tag_len = aead::CHACHA20_POLY1305.tag_len();
+	data.extend(vec![0; tag_len]);
+	let nonce: [u8; 12] = thread_rng().gen();
+	let sealing_key = aead::SealingKey::new(&aead::CHACHA20_POLY1305, &key)
+		.map_err(|_| transfer_error("invalid key"))?;
+	aead::seal_in_place(&sealing_key, &nonce, &[], &mut data, tag_len)
+		.map_err(|_| transfer_error("encryption failed"))?;
+
+	let mut blob = vec![TRANSFER_VERSION];
+	blob.extend_from_slice(&nonce);
+	blob.extend(data);
+	Ok(to_hex(blob))
+}
+
+/// Import an account exported by a wallet with the same seed, returning its
+/// label and path. If the wallet already knows the account's path under
+/// another label, that label is kept. Outputs and transactions the wallet
+/// already has are skipped, so importing the same account twice changes
+/// nothing
+pub fn import_account<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	blob: &str,
+) -> Result<AcctPathMapping, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let blob = from_hex(blob.trim().to_owned()).map_err(|_| transfer_error("invalid hex"))?;
+	if blob.len() < 13 || blob[0] != TRANSFER_VERSION {
+		return Err(transfer_error("unsupported or truncated export"));
+	}
+	let key = transfer_key(&w.keychain(keychain_mask)?)?;
+	let opening_key = aead::OpeningKey::new(&aead::CHACHA20_POLY1305, &key)
+		.map_err(|_| transfer_error("invalid key"))?;
+	let mut data = blob[13..].to_vec();
+	let data =
+		aead::open_in_place(&opening_key, &blob[1..13], &[], 0, &mut data).map_err(|_| {
+			transfer_error("can't decrypt, the export is from a wallet with another seed")
+		})?;
+	let transfer: AccountTransfer = serde_json::from_slice(data)
+		.map_err(|e| transfer_error(&format!("invalid account data: {}", e)))?;
+	let path = transfer.account.path.clone();
+	let label = transfer.account.label.clone();
+
+	let known_account = w.acct_path_iter()?.find(|a| a.path == path);
+	if known_account.is_none() && w.get_acct_path(label.clone())?.is_some() {
+		return Err(ErrorKind::AccountLabelAlreadyExists(label).into());
+	}
+	let next_index = w.current_child_index(&path)?.max(transfer.child_index);
+	let existing_outputs: Vec<(_, _)> = w.iter()?.map(|o| (o.key_id, o.mmr_index)).collect();
+	let existing_txs: Vec<TxLogEntry> = w
+		.tx_log_iter()?
+		.filter(|t| t.parent_key_id == path)
+		.collect();
+
+	for (name, tx) in transfer.stored_txs.iter() {
+		w.store_tx(name.trim_end_matches(STORED_TX_EXT), tx)?;
+	}
+
+	let mut batch = w.batch(keychain_mask)?;
+	let account = match known_account {
+		Some(a) => a,
+		None => {
+			batch.save_acct_path(transfer.account.clone())?;
+			transfer.account
+		}
+	};
+	batch.save_child_index(&path, next_index)?;
+
+	// transactions are given new ids here, or matched to ones already known
+	let mut tx_ids = HashMap::new();
+	for mut t in transfer.txs {
+		let known = existing_txs.iter().find(|e| match t.tx_slate_id {
+			Some(id) => e.tx_slate_id == Some(id),
+			None => {
+				e.tx_slate_id.is_none()
+					&& e.tx_type == t.tx_type
+					&& e.creation_ts == t.creation_ts
+					&& e.amount_credited == t.amount_credited
+					&& e.amount_debited == t.amount_debited
+			}
+		});
+		match known {
+			Some(e) => {
+				tx_ids.insert(t.id, e.id);
+			}
+			None => {
+				let id = batch.next_tx_log_id(&path)?;
+				tx_ids.insert(t.id, id);
+				t.id = id;
+				batch.save_tx_log_entry(t, &path)?;
+			}
+		}
+	}
+	for mut o in transfer.outputs {
+		if existing_outputs.contains(&(o.key_id.clone(), o.mmr_index)) {
+			continue;
+		}
+		o.tx_log_entry = o.tx_log_entry.and_then(|id| tx_ids.get(&id).cloned());
+		batch.save(o)?;
+	}
+	batch.commit()?;
+	Ok(account)
+}
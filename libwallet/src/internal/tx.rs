@@ -17,14 +17,20 @@
 use uuid::Uuid;
 
 use crate::grin_core::consensus::valid_header_version;
-use crate::grin_core::core::HeaderVersion;
-use crate::grin_keychain::{Identifier, Keychain};
+use crate::grin_core::core::{amount_to_hr_string, HeaderVersion, Transaction, TxKernel};
+use crate::grin_core::libtx::{build, proof::ProofBuilder, tx_fee};
+use crate::grin_keychain::{BlindSum, BlindingFactor, Identifier, Keychain};
 use crate::grin_util::secp::key::SecretKey;
-use crate::grin_util::Mutex;
+use crate::grin_util::{self as util, Mutex};
+use crate::internal::selection::CoinSelection;
 use crate::internal::{selection, updater};
 use crate::slate::Slate;
-use crate::types::{Context, NodeClient, TxLogEntryType, WalletBackend};
+use crate::types::{
+	Context, NodeClient, OutputData, SelectionIntent, TxLogEntry, TxLogEntryType, TxStep,
+	WalletBackend,
+};
 use crate::{Error, ErrorKind};
+use rand::thread_rng;
 
 // static for incrementing test UUIDs
 lazy_static! {
@@ -76,7 +82,7 @@ pub fn estimate_send_tx<'a, T: ?Sized, C, K>(
 	minimum_confirmations: u64,
 	max_outputs: usize,
 	num_change_outputs: usize,
-	selection_strategy_is_use_all: bool,
+	strategy: &dyn CoinSelection,
 	parent_key_id: &Identifier,
 ) -> Result<
 	(
@@ -109,12 +115,58 @@ where
 		minimum_confirmations,
 		max_outputs,
 		num_change_outputs,
-		selection_strategy_is_use_all,
+		strategy,
 		parent_key_id,
 	)?;
 	Ok((total, fee))
 }
 
+/// Estimates the locked amount and fee of each of a series of sends made one
+/// after another, each selecting from the outputs the earlier ones leave
+pub fn estimate_send_rounds<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	amounts: &[u64],
+	minimum_confirmations: u64,
+	max_outputs: usize,
+	num_change_outputs: usize,
+	strategy: &dyn CoinSelection,
+	parent_key_id: &Identifier,
+) -> Result<
+	Vec<(
+		u64, // total
+		u64, // fee
+	)>,
+	Error,
+>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let current_height = wallet.w2n_client().get_chain_height()?;
+	updater::refresh_outputs(wallet, keychain_mask, parent_key_id, false)?;
+
+	let mut spent = vec![];
+	let mut estimates = vec![];
+	for amount in amounts {
+		let (coins, total, _amount, fee) = selection::select_coins_and_fee_excluding(
+			wallet,
+			*amount,
+			current_height,
+			minimum_confirmations,
+			max_outputs,
+			num_change_outputs,
+			strategy,
+			parent_key_id,
+			&spent,
+		)?;
+		spent.extend(coins);
+		estimates.push((total, fee));
+	}
+	Ok(estimates)
+}
+
 /// Add inputs to the slate (effectively becoming the sender)
 pub fn add_inputs_to_slate<'a, T: ?Sized, C, K>(
 	wallet: &mut T,
@@ -123,7 +175,7 @@ pub fn add_inputs_to_slate<'a, T: ?Sized, C, K>(
 	minimum_confirmations: u64,
 	max_outputs: usize,
 	num_change_outputs: usize,
-	selection_strategy_is_use_all: bool,
+	strategy: &dyn CoinSelection,
 	parent_key_id: &Identifier,
 	participant_id: usize,
 	message: Option<String>,
@@ -153,7 +205,7 @@ where
 		minimum_confirmations,
 		max_outputs,
 		num_change_outputs,
-		selection_strategy_is_use_all,
+		strategy,
 		parent_key_id.clone(),
 		use_test_rng,
 	)?;
@@ -183,6 +235,268 @@ where
 	Ok(context)
 }
 
+/// Start a send without selecting its inputs, which are only chosen and locked
+/// by [`late_lock`](fn.late_lock.html) when the transaction is finalized. The
+/// fee is signed by the recipient, so it's fixed now from a trial selection,
+/// and the excess committed to is random rather than the inputs' and change's
+pub fn add_late_lock_to_slate<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	slate: &mut Slate,
+	intent: SelectionIntent,
+	parent_key_id: &Identifier,
+	message: Option<String>,
+	use_test_rng: bool,
+) -> Result<Context, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	updater::refresh_outputs(wallet, keychain_mask, parent_key_id, false)?;
+	let (_, _, _, fee) = selection::select_coins_and_fee(
+		wallet,
+		slate.amount,
+		slate.height,
+		intent.minimum_confirmations,
+		intent.max_outputs,
+		intent.num_change_outputs,
+		&*intent.coin_selection()?,
+		parent_key_id,
+	)?;
+	slate.fee = fee;
+	slate.tx = Transaction::empty().with_kernel(
+		TxKernel::empty()
+			.with_fee(fee)
+			.with_lock_height(slate.lock_height),
+	);
+
+	let keychain = wallet.keychain(keychain_mask)?;
+	let sec_key = match use_test_rng {
+		false => SecretKey::new(keychain.secp(), &mut thread_rng()),
+		true => SecretKey::from_slice(keychain.secp(), &[2; 32])?,
+	};
+	let mut context = Context::new(keychain.secp(), sec_key, parent_key_id, use_test_rng, 0);
+	context.fee = fee;
+	context.late_lock = Some(intent);
+
+	slate.fill_round_1(
+		&keychain,
+		&mut context.sec_key,
+		&context.sec_nonce,
+		0,
+		message,
+		use_test_rng,
+	)?;
+	Ok(context)
+}
+
+/// Select the inputs of a late locked transaction and add them and the change
+/// to the slate, recording them in the context. The offset is adjusted so the
+/// excess committed to when the transaction was started still balances it
+pub fn late_lock<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	slate: &mut Slate,
+	context: &mut Context,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let intent = match context.late_lock.take() {
+		Some(i) => i,
+		None => return Ok(()),
+	};
+	let parent_key_id = context.parent_key_id.clone();
+	updater::refresh_outputs(wallet, keychain_mask, &parent_key_id, false)?;
+	let current_height = wallet.w2n_client().get_chain_height()?;
+	let (coins, total, amount, fee) = selection::select_coins_and_fee(
+		wallet,
+		slate.amount,
+		current_height,
+		intent.minimum_confirmations,
+		intent.max_outputs,
+		intent.num_change_outputs,
+		&*intent.coin_selection()?,
+		&parent_key_id,
+	)?;
+	if fee > context.fee {
+		return Err(ErrorKind::Fee(format!(
+			"The outputs now available need a fee of {}, above the {} agreed with the recipient",
+			amount_to_hr_string(fee, false),
+			amount_to_hr_string(context.fee, false)
+		)))?;
+	}
+	let needed = amount + context.fee;
+	if total < needed {
+		return Err(ErrorKind::NotEnoughFunds {
+			available: total,
+			available_disp: amount_to_hr_string(total, false),
+			needed,
+			needed_disp: amount_to_hr_string(needed, false),
+		})?;
+	}
+
+	let keychain = wallet.keychain(keychain_mask)?;
+	let (parts, change) = selection::inputs_and_change(
+		&coins,
+		wallet,
+		keychain_mask,
+		amount,
+		context.fee,
+		intent.num_change_outputs,
+	)?;
+	let blinding =
+		slate.add_transaction_elements(&keychain, &ProofBuilder::new(&keychain), parts)?;
+	slate.tx.offset = keychain.blind_sum(
+		&BlindSum::new()
+			.add_blinding_factor(blinding)
+			.sub_blinding_factor(BlindingFactor::from_secret_key(context.sec_key.clone())),
+	)?;
+
+	for coin in coins {
+		context.add_input(&coin.key_id, &coin.mmr_index, coin.value);
+	}
+	for (amount, id, mmr_index) in &change {
+		context.add_output(&id, &mmr_index, *amount);
+	}
+	Ok(())
+}
+
+/// Build a transaction spending the given outputs into `num_outputs` new
+/// outputs of about equal value, less the fee. The wallet is the only party
+/// to the transaction, so it's signed and finalized straight away, and the
+/// outputs are locked as for a send
+pub fn consolidation_tx<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	coins: &Vec<OutputData>,
+	num_outputs: usize,
+	parent_key_id: &Identifier,
+	use_test_rng: bool,
+) -> Result<Slate, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let total: u64 = coins.iter().map(|c| c.value).sum();
+	let fee = tx_fee(coins.len(), num_outputs, 1, None);
+	// every new output needs some value
+	let needed = fee + num_outputs as u64;
+	if total < needed {
+		return Err(ErrorKind::NotEnoughFunds {
+			available: total,
+			available_disp: amount_to_hr_string(total, false),
+			needed,
+			needed_disp: amount_to_hr_string(needed, false),
+		})?;
+	}
+
+	let mut slate = new_tx_slate(&mut *wallet, 0, 1, use_test_rng)?;
+	let keychain = wallet.keychain(keychain_mask)?;
+	let (mut parts, outputs) =
+		selection::inputs_and_change(coins, wallet, keychain_mask, 0, fee, num_outputs)?;
+	parts.push(build::with_lock_height(slate.lock_height));
+	let blinding =
+		slate.add_transaction_elements(&keychain, &ProofBuilder::new(&keychain), parts)?;
+	slate.fee = fee;
+
+	let mut context = Context::new(
+		keychain.secp(),
+		blinding.secret_key(&keychain.secp())?,
+		parent_key_id,
+		use_test_rng,
+		0,
+	);
+	context.fee = fee;
+	for coin in coins {
+		context.add_input(&coin.key_id, &coin.mmr_index, coin.value);
+	}
+	for (amount, id, mmr_index) in &outputs {
+		context.add_output(&id, &mmr_index, *amount);
+	}
+
+	slate.fill_round_1(
+		&keychain,
+		&mut context.sec_key,
+		&context.sec_nonce,
+		0,
+		None,
+		use_test_rng,
+	)?;
+	slate.fill_round_2(&keychain, &context.sec_key, &context.sec_nonce, 0)?;
+	slate.finalize(&keychain)?;
+
+	selection::lock_tx_context(wallet, keychain_mask, &slate, &context)?;
+	Ok(slate)
+}
+
+/// Build the sender's side of a transaction spending all of the given outputs
+/// to a single output of the recipient's, less the fee, which can't be
+/// included in a block before `lock_height`. Nothing is changed in the wallet:
+/// the outputs stay spendable as usual, which leaves the transaction invalid
+/// once any of them is spent
+pub fn recovery_tx<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	coins: &Vec<OutputData>,
+	lock_height: u64,
+	parent_key_id: &Identifier,
+	use_test_rng: bool,
+) -> Result<(Slate, Context), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let total: u64 = coins.iter().map(|c| c.value).sum();
+	let fee = tx_fee(coins.len(), 1, 1, None);
+	if total <= fee {
+		return Err(ErrorKind::NotEnoughFunds {
+			available: total,
+			available_disp: amount_to_hr_string(total, false),
+			needed: fee + 1,
+			needed_disp: amount_to_hr_string(fee + 1, false),
+		})?;
+	}
+	let amount = total - fee;
+
+	let mut slate = new_tx_slate(&mut *wallet, amount, 2, use_test_rng)?;
+	slate.lock_height = lock_height;
+	let keychain = wallet.keychain(keychain_mask)?;
+	let (mut parts, _) =
+		selection::inputs_and_change(coins, wallet, keychain_mask, amount, fee, 0)?;
+	parts.push(build::with_lock_height(lock_height));
+	let blinding =
+		slate.add_transaction_elements(&keychain, &ProofBuilder::new(&keychain), parts)?;
+	slate.fee = fee;
+
+	let mut context = Context::new(
+		keychain.secp(),
+		blinding.secret_key(&keychain.secp())?,
+		parent_key_id,
+		use_test_rng,
+		0,
+	);
+	context.fee = fee;
+	for coin in coins {
+		context.add_input(&coin.key_id, &coin.mmr_index, coin.value);
+	}
+
+	slate.fill_round_1(
+		&keychain,
+		&mut context.sec_key,
+		&context.sec_nonce,
+		0,
+		None,
+		use_test_rng,
+	)?;
+	Ok((slate, context))
+}
+
 /// Add receiver output to the slate
 pub fn add_output_to_slate<'a, T: ?Sized, C, K>(
 	wallet: &mut T,
@@ -275,7 +589,14 @@ where
 	} else if let Some(tx_slate_id) = tx_slate_id {
 		tx_id_string = tx_slate_id.to_string();
 	}
-	let tx_vec = updater::retrieve_txs(wallet, tx_id, tx_slate_id, Some(&parent_key_id), false)?;
+	let tx_vec = updater::retrieve_txs(
+		wallet,
+		tx_id,
+		tx_slate_id,
+		Some(&parent_key_id),
+		false,
+		None,
+	)?;
 	if tx_vec.len() != 1 {
 		return Err(ErrorKind::TransactionDoesntExist(tx_id_string))?;
 	}
@@ -299,9 +620,51 @@ where
 	Ok(())
 }
 
+/// Cancel the unconfirmed transactions created at least `blocks` blocks
+/// before `current_height`, marking them as expired. Returns the cancelled
+/// transactions
+pub fn cancel_stale_txs<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	parent_key_id: &Identifier,
+	current_height: u64,
+	blocks: u64,
+) -> Result<Vec<TxLogEntry>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let stale: Vec<TxLogEntry> =
+		updater::retrieve_txs(wallet, None, None, Some(&parent_key_id), true, None)?
+			.into_iter()
+			.filter(|t| match t.creation_height {
+				Some(h) => current_height >= h.saturating_add(blocks),
+				// created before heights were recorded
+				None => false,
+			})
+			.collect();
+	let mut cancelled = vec![];
+	for mut tx in stale {
+		let res = updater::retrieve_outputs(
+			wallet,
+			keychain_mask,
+			false,
+			Some(tx.id),
+			Some(&parent_key_id),
+		)?;
+		let outputs = res.iter().map(|m| m.output.clone()).collect();
+		tx.expired = true;
+		updater::cancel_tx_and_outputs(wallet, keychain_mask, tx.clone(), outputs, parent_key_id)?;
+		cancelled.push(tx);
+	}
+	Ok(cancelled)
+}
+
 /// Update the stored transaction (this update needs to happen when the TX is finalised)
 pub fn update_stored_tx<'a, T: ?Sized, C, K>(
 	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
 	slate: &Slate,
 	is_invoiced: bool,
 ) -> Result<(), Error>
@@ -311,7 +674,7 @@ where
 	K: Keychain + 'a,
 {
 	// finalize command
-	let tx_vec = updater::retrieve_txs(wallet, None, Some(slate.id), None, false)?;
+	let tx_vec = updater::retrieve_txs(wallet, None, Some(slate.id), None, false, None)?;
 	let mut tx = None;
 	// don't want to assume this is the right tx, in case of self-sending
 	for t in tx_vec {
@@ -324,11 +687,25 @@ where
 			break;
 		}
 	}
-	let tx = match tx {
+	let mut tx = match tx {
 		Some(t) => t,
 		None => return Err(ErrorKind::TransactionDoesntExist(slate.id.to_string()))?,
 	};
 	wallet.store_tx(&format!("{}", tx.tx_slate_id.unwrap()), &slate.tx)?;
+	wallet.store_slate(TxStep::Finalize, slate)?;
+	// the recipient of an invoice only has the transaction once it's finalized
+	if tx.stored_tx.is_none() {
+		tx.stored_tx = Some(format!("{}.grintx", slate.id));
+	}
+	// the kernel is how the transaction is found on chain once posted
+	if let Some(k) = slate.tx.kernels().first() {
+		tx.kernel_excess = Some(util::to_hex(k.excess.0.to_vec()));
+	}
+	tx.record_step(TxStep::Finalize, slate);
+	let parent_key = tx.parent_key_id.clone();
+	let mut batch = wallet.batch(keychain_mask)?;
+	batch.save_tx_log_entry(tx, &parent_key)?;
+	batch.commit()?;
 	Ok(())
 }
 
@@ -343,7 +720,7 @@ where
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	let tx_vec = updater::retrieve_txs(wallet, None, Some(slate.id), None, false)?;
+	let tx_vec = updater::retrieve_txs(wallet, None, Some(slate.id), None, false, None)?;
 	if tx_vec.is_empty() {
 		return Err(ErrorKind::TransactionDoesntExist(slate.id.to_string()))?;
 	}
@@ -357,6 +734,57 @@ where
 	Ok(())
 }
 
+/// Update the Dandelion preference stored with the transaction
+pub fn update_fluff<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	slate: &Slate,
+	fluff: bool,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let tx_vec = updater::retrieve_txs(wallet, None, Some(slate.id), None, false, None)?;
+	if tx_vec.is_empty() {
+		return Err(ErrorKind::TransactionDoesntExist(slate.id.to_string()))?;
+	}
+	let mut batch = wallet.batch(keychain_mask)?;
+	for mut tx in tx_vec.into_iter() {
+		tx.fluff = Some(fluff);
+		let parent_key = tx.parent_key_id.clone();
+		batch.save_tx_log_entry(tx, &parent_key)?;
+	}
+	batch.commit()?;
+	Ok(())
+}
+
+/// Refuse to build a transaction any further once the chain has reached the
+/// TTL cutoff height in its slate
+pub fn check_ttl<'a, T: ?Sized, C, K>(wallet: &mut T, slate: &Slate) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	check_ttl_height(wallet.w2n_client(), slate)
+}
+
+/// As [`check_ttl`](fn.check_ttl.html), asking the given client for the
+/// chain height
+pub fn check_ttl_height<C>(client: &C, slate: &Slate) -> Result<(), Error>
+where
+	C: NodeClient,
+{
+	if let Some(cutoff) = slate.ttl_cutoff_height {
+		if client.get_chain_height()? >= cutoff {
+			return Err(ErrorKind::TransactionExpired(slate.id.to_string(), cutoff))?;
+		}
+	}
+	Ok(())
+}
+
 #[cfg(test)]
 mod test {
 	use crate::grin_core::libtx::{build, ProofBuilder};
@@ -18,7 +18,8 @@
 use std::collections::HashMap;
 use uuid::Uuid;
 
-use crate::error::Error;
+use crate::confirmations::ConfirmationRequirements;
+use crate::error::{Error, ErrorKind};
 use crate::grin_core::consensus::reward;
 use crate::grin_core::core::{Output, TxKernel};
 use crate::grin_core::global;
@@ -29,10 +30,34 @@ use crate::grin_util as util;
 use crate::grin_util::secp::key::SecretKey;
 use crate::grin_util::secp::pedersen;
 use crate::internal::keys;
+use crate::privacy::log_amount;
+use crate::statistics;
 use crate::types::{
 	NodeClient, OutputData, OutputStatus, TxLogEntry, TxLogEntryType, WalletBackend, WalletInfo,
 };
-use crate::{BlockFees, CbData, OutputCommitMapping};
+use crate::{
+	BlockFees, CbData, OutputCommitMapping, RetrieveOutputsQueryArgs, RetrieveTxQueryArgs,
+	TxQueryStatus, TxSortOrder,
+};
+
+/// The keychain, if it's needed to compute the commitment of any of the
+/// outputs. Commitments are cached when outputs are saved, so a watch-only
+/// wallet, which has no keychain, can still follow its outputs
+fn keychain_for_commits<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	outputs: &[OutputData],
+) -> Result<Option<K>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	match outputs.iter().any(|o| o.commit.is_none()) {
+		true => Ok(Some(wallet.keychain(keychain_mask)?)),
+		false => Ok(None),
+	}
+}
 
 /// Retrieve all of the outputs (doesn't attempt to update from node)
 pub fn retrieve_outputs<'a, T: ?Sized, C, K>(
@@ -49,7 +74,7 @@ where
 {
 	// just read the wallet here, no need for a write lock
 	let mut outputs = wallet
-		.iter()
+		.iter()?
 		.filter(|out| show_spent || out.status != OutputStatus::Spent)
 		.collect::<Vec<_>>();
 
@@ -70,7 +95,7 @@ where
 	}
 
 	outputs.sort_by_key(|out| out.n_child);
-	let keychain = wallet.keychain(keychain_mask)?;
+	let keychain = keychain_for_commits(wallet, keychain_mask, &outputs)?;
 
 	let res = outputs
 		.into_iter()
@@ -78,6 +103,8 @@ where
 			let commit = match output.commit.clone() {
 				Some(c) => pedersen::Commitment::from_vec(util::from_hex(c).unwrap()),
 				None => keychain
+					.as_ref()
+					.unwrap()
 					.commit(output.value, &output.key_id, &SwitchCommitmentType::Regular)
 					.unwrap(), // TODO: proper support for different switch commitment schemes
 			};
@@ -87,48 +114,171 @@ where
 	Ok(res)
 }
 
-/// Retrieve all of the transaction entries, or a particular entry
-/// if `parent_key_id` is set, only return entries from that key
-pub fn retrieve_txs<'a, T: ?Sized, C, K>(
+/// Retrieve the outputs matching the given query (doesn't attempt to update from node).
+/// Filters are applied while iterating over the store, so only matching outputs
+/// are ever collected
+pub fn query_outputs<'a, T: ?Sized, C, K>(
 	wallet: &mut T,
-	tx_id: Option<u32>,
-	tx_slate_id: Option<Uuid>,
-	parent_key_id: Option<&Identifier>,
-	outstanding_only: bool,
-) -> Result<Vec<TxLogEntry>, Error>
+	keychain_mask: Option<&SecretKey>,
+	parent_key_id: &Identifier,
+	query: &RetrieveOutputsQueryArgs,
+) -> Result<Vec<OutputCommitMapping>, Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	let mut txs: Vec<TxLogEntry> = wallet
-		.tx_log_iter()
-		.filter(|tx_entry| {
-			let f_pk = match parent_key_id {
-				Some(k) => tx_entry.parent_key_id == *k,
-				None => true,
+	let mut outputs = wallet
+		.iter()?
+		.filter(|out| {
+			let f_pk = out.root_key_id == *parent_key_id;
+			let f_status = match &query.statuses {
+				Some(s) if !s.is_empty() => s.contains(&out.status),
+				_ => true,
 			};
-			let f_tx_id = match tx_id {
-				Some(i) => tx_entry.id == i,
+			let f_min = match query.min_height {
+				Some(h) => out.height >= h,
 				None => true,
 			};
-			let f_txs = match tx_slate_id {
-				Some(t) => tx_entry.tx_slate_id == Some(t),
+			let f_max = match query.max_height {
+				Some(h) => out.height <= h,
 				None => true,
 			};
-			let f_outstanding = match outstanding_only {
-				true => {
-					!tx_entry.confirmed
-						&& (tx_entry.tx_type == TxLogEntryType::TxReceived
-							|| tx_entry.tx_type == TxLogEntryType::TxSent)
-				}
-				false => true,
+			f_pk && f_status && f_min && f_max
+		})
+		.collect::<Vec<_>>();
+
+	outputs.sort_by_key(|out| out.n_child);
+	let keychain = keychain_for_commits(wallet, keychain_mask, &outputs)?;
+
+	let res = outputs
+		.into_iter()
+		.map(|output| {
+			let commit = match output.commit.clone() {
+				Some(c) => pedersen::Commitment::from_vec(util::from_hex(c).unwrap()),
+				None => keychain
+					.as_ref()
+					.unwrap()
+					.commit(output.value, &output.key_id, &SwitchCommitmentType::Regular)
+					.unwrap(), // TODO: proper support for different switch commitment schemes
 			};
-			f_pk && f_tx_id && f_txs && f_outstanding
+			OutputCommitMapping { output, commit }
 		})
 		.collect();
-	txs.sort_by_key(|tx| tx.creation_ts);
-	Ok(txs)
+	Ok(res)
+}
+
+/// Retrieve all of the transaction entries, or a particular entry
+/// if `parent_key_id` is set, only return entries from that key.
+/// If a query is given, only the page of entries matching it is returned,
+/// in the order it asks for. Entries are then filtered as they're read from
+/// the store, newest first if sorted in descending order, so only the
+/// requested page is ever collected
+pub fn retrieve_txs<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	tx_id: Option<u32>,
+	tx_slate_id: Option<Uuid>,
+	parent_key_id: Option<&Identifier>,
+	outstanding_only: bool,
+	query: Option<&RetrieveTxQueryArgs>,
+) -> Result<Vec<TxLogEntry>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let matches = |tx_entry: &TxLogEntry| {
+		let f_pk = match parent_key_id {
+			Some(k) => tx_entry.parent_key_id == *k,
+			None => true,
+		};
+		let f_tx_id = match tx_id {
+			Some(i) => tx_entry.id == i,
+			None => true,
+		};
+		let f_txs = match tx_slate_id {
+			Some(t) => tx_entry.tx_slate_id == Some(t),
+			None => true,
+		};
+		let f_outstanding = match outstanding_only {
+			true => {
+				!tx_entry.confirmed
+					&& (tx_entry.tx_type == TxLogEntryType::TxReceived
+						|| tx_entry.tx_type == TxLogEntryType::TxSent)
+			}
+			false => true,
+		};
+		let f_query = match query {
+			Some(q) => tx_matches_query(tx_entry, q),
+			None => true,
+		};
+		f_pk && f_tx_id && f_txs && f_outstanding && f_query
+	};
+	let query = match query {
+		Some(q) => q,
+		None => {
+			let mut txs: Vec<TxLogEntry> = wallet.tx_log_iter()?.filter(|t| matches(t)).collect();
+			txs.sort_by_key(|tx| tx.creation_ts);
+			return Ok(txs);
+		}
+	};
+	let offset = query.offset.unwrap_or(0) as usize;
+	let limit = match query.limit {
+		Some(l) => l as usize,
+		None => std::usize::MAX,
+	};
+	let entries = match (&query.sort_order, parent_key_id) {
+		(Some(TxSortOrder::Desc), Some(k)) => wallet.tx_log_iter_rev(k)?,
+		(Some(TxSortOrder::Desc), None) => {
+			let mut txs: Vec<TxLogEntry> = wallet.tx_log_iter()?.collect();
+			txs.sort_by(|a, b| b.id.cmp(&a.id));
+			Box::new(txs.into_iter())
+		}
+		_ => wallet.tx_log_iter()?,
+	};
+	Ok(entries
+		.filter(|t| matches(t))
+		.skip(offset)
+		.take(limit)
+		.collect())
+}
+
+fn tx_matches_query(tx_entry: &TxLogEntry, query: &RetrieveTxQueryArgs) -> bool {
+	if let Some(min) = query.min_creation_ts {
+		if tx_entry.creation_ts < min {
+			return false;
+		}
+	}
+	if let Some(max) = query.max_creation_ts {
+		if tx_entry.creation_ts > max {
+			return false;
+		}
+	}
+	let statuses = match &query.statuses {
+		Some(s) if !s.is_empty() => s,
+		_ => return true,
+	};
+	statuses.iter().any(|status| match status {
+		TxQueryStatus::Unconfirmed => {
+			!tx_entry.confirmed
+				&& (tx_entry.tx_type == TxLogEntryType::TxReceived
+					|| tx_entry.tx_type == TxLogEntryType::TxSent)
+		}
+		TxQueryStatus::Confirmed => tx_entry.confirmed,
+		TxQueryStatus::Cancelled => {
+			tx_entry.tx_type == TxLogEntryType::TxReceivedCancelled
+				|| tx_entry.tx_type == TxLogEntryType::TxSentCancelled
+		}
+		TxQueryStatus::Sent => {
+			tx_entry.tx_type == TxLogEntryType::TxSent
+				|| tx_entry.tx_type == TxLogEntryType::TxSentCancelled
+		}
+		TxQueryStatus::Received => {
+			tx_entry.tx_type == TxLogEntryType::TxReceived
+				|| tx_entry.tx_type == TxLogEntryType::TxReceivedCancelled
+		}
+		TxQueryStatus::Coinbase => tx_entry.tx_type == TxLogEntryType::ConfirmedCoinbase,
+	})
 }
 
 /// Refreshes the outputs in a wallet with the latest information
@@ -164,13 +314,12 @@ where
 {
 	let mut wallet_outputs: HashMap<pedersen::Commitment, (Identifier, Option<u64>)> =
 		HashMap::new();
-	let keychain = wallet.keychain(keychain_mask)?;
 	let unspents: Vec<OutputData> = wallet
-		.iter()
+		.iter()?
 		.filter(|x| x.root_key_id == *parent_key_id && x.status != OutputStatus::Spent)
 		.collect();
 
-	let tx_entries = retrieve_txs(wallet, None, None, Some(&parent_key_id), true)?;
+	let tx_entries = retrieve_txs(wallet, None, None, Some(&parent_key_id), true, None)?;
 
 	// Only select outputs that are actually involved in an outstanding transaction
 	let unspents: Vec<OutputData> = match update_all {
@@ -190,10 +339,13 @@ where
 		true => unspents,
 	};
 
+	let keychain = keychain_for_commits(wallet, keychain_mask, &unspents)?;
 	for out in unspents {
 		let commit = match out.commit.clone() {
 			Some(c) => pedersen::Commitment::from_vec(util::from_hex(c).unwrap()),
 			None => keychain
+				.as_ref()
+				.unwrap()
 				.commit(out.value, &out.key_id, &SwitchCommitmentType::Regular)
 				.unwrap(), // TODO: proper support for different switch commitment schemes
 		};
@@ -239,6 +391,39 @@ where
 	Ok(())
 }
 
+/// Mark a sent transaction as cancelled in favour of a replacement spending
+/// some of the same inputs. Unlike [`cancel_tx_and_outputs`], inputs shared
+/// with the replacement stay locked, and change outputs are kept, so either
+/// transaction is picked up if it ends up confirmed
+pub fn replace_tx<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	tx: TxLogEntry,
+	outputs: Vec<OutputCommitMapping>,
+	shared_inputs: &[pedersen::Commitment],
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let mut batch = wallet.batch(keychain_mask)?;
+	for m in outputs {
+		let mut o = m.output;
+		// only unlock inputs the replacement doesn't spend
+		if o.status == OutputStatus::Locked && !shared_inputs.contains(&m.commit) {
+			o.status = OutputStatus::Unspent;
+			batch.save(o)?;
+		}
+	}
+	let parent_key_id = tx.parent_key_id.clone();
+	let mut tx = tx;
+	tx.tx_type = TxLogEntryType::TxSentCancelled;
+	batch.save_tx_log_entry(tx, &parent_key_id)?;
+	batch.commit()?;
+	Ok(())
+}
+
 /// Apply refreshed API output data to the wallet
 pub fn apply_api_outputs<'a, T: ?Sized, C, K>(
 	wallet: &mut T,
@@ -268,6 +453,7 @@ where
 			warn!("Please wait for sync on node to complete or fork to resolve and try again.");
 			return Ok(());
 		}
+		let excesses = unconfirmed_kernel_excesses(wallet, parent_key_id)?;
 		let mut batch = wallet.batch(keychain_mask)?;
 		for (commit, (id, mmr_index)) in wallet_outputs.iter() {
 			if let Ok(mut output) = batch.get(id, mmr_index) {
@@ -299,7 +485,14 @@ where
 							});
 							if let Some(mut t) = tx {
 								t.update_confirmation_ts();
+								if let (false, Some(ts)) = (t.confirmed, t.confirmation_ts) {
+									statistics::record_confirmed(t.creation_ts, ts);
+								}
 								t.confirmed = true;
+								t.confirmation_height = Some(o.1);
+								if let Some(e) = excesses.get(&t.id) {
+									t.kernel_excess = Some(e.clone());
+								}
 								batch.save_tx_log_entry(t, &parent_key_id)?;
 							}
 						}
@@ -319,6 +512,80 @@ where
 	Ok(())
 }
 
+/// Kernel excesses of the account's unconfirmed transactions, as hex, taken
+/// from the finalized transactions the wallet holds
+fn unconfirmed_kernel_excesses<'a, T: ?Sized, C, K>(
+	wallet: &T,
+	parent_key_id: &Identifier,
+) -> Result<HashMap<u32, String>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let mut excesses = HashMap::new();
+	let txs = wallet.tx_log_iter()?.filter(|t| {
+		t.parent_key_id == *parent_key_id
+			&& !t.confirmed
+			&& t.stored_tx.is_some()
+			&& (t.tx_type == TxLogEntryType::TxSent || t.tx_type == TxLogEntryType::TxReceived)
+	});
+	for t in txs {
+		if let Ok(Some(tx)) = wallet.get_stored_tx(&t) {
+			// the excess is only set once the transaction is finalized
+			if let Some(k) = tx
+				.kernels()
+				.first()
+				.filter(|k| k.excess.0.iter().any(|b| *b != 0))
+			{
+				excesses.insert(t.id, util::to_hex(k.excess.0.to_vec()));
+			}
+		}
+	}
+	Ok(excesses)
+}
+
+/// Outputs of the account marked spent that the node reports as unspent
+/// again, as happens when a transaction creating them is replayed, along with
+/// their commitments. Outputs of cancelled receives aren't kept, so only
+/// check_repair finds those should the transaction be played after all
+pub fn find_reappeared_outputs<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	parent_key_id: &Identifier,
+) -> Result<Vec<(pedersen::Commitment, OutputData)>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let keychain = wallet.keychain(keychain_mask)?;
+	let mut spent = HashMap::new();
+	for out in wallet
+		.iter()?
+		.filter(|o| o.root_key_id == *parent_key_id && o.status == OutputStatus::Spent)
+	{
+		let commit = match out.commit.as_ref() {
+			Some(c) => pedersen::Commitment::from_vec(
+				util::from_hex(c.clone())
+					.map_err(|_| ErrorKind::GenericError(format!("Invalid commitment {}", c)))?,
+			),
+			None => keychain.commit(out.value, &out.key_id, &SwitchCommitmentType::Regular)?,
+		};
+		spent.insert(commit, out);
+	}
+	if spent.is_empty() {
+		return Ok(vec![]);
+	}
+	let unspent = wallet
+		.w2n_client()
+		.get_outputs_from_node(spent.keys().cloned().collect())?;
+	Ok(spent
+		.into_iter()
+		.filter(|(commit, _)| unspent.contains_key(commit))
+		.collect())
+}
+
 /// Builds a single api query to retrieve the latest output data from the node.
 /// So we can refresh the local wallet outputs.
 fn refresh_output_state<'a, T: ?Sized, C, K>(
@@ -352,10 +619,85 @@ where
 		height,
 		parent_key_id,
 	)?;
+	confirm_txs_by_kernel(wallet, keychain_mask, parent_key_id)?;
 	clean_old_unconfirmed(wallet, keychain_mask, height)?;
 	Ok(())
 }
 
+/// Confirm the account's unconfirmed transactions whose kernel the node has on
+/// chain, along with the outputs they spend and create. Looking at outputs
+/// alone misses transactions whose outputs were spent again before the wallet
+/// saw them, as can happen with self-spends and payjoins
+fn confirm_txs_by_kernel<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	parent_key_id: &Identifier,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let txs: Vec<TxLogEntry> = wallet
+		.tx_log_iter()?
+		.filter(|t| {
+			t.parent_key_id == *parent_key_id
+				&& !t.confirmed
+				&& t.kernel_excess.is_some()
+				&& (t.tx_type == TxLogEntryType::TxSent || t.tx_type == TxLogEntryType::TxReceived)
+		})
+		.collect();
+	let mut confirmed = vec![];
+	for t in txs {
+		let excess = match t
+			.kernel_excess
+			.as_ref()
+			.and_then(|e| util::from_hex(e.clone()).ok())
+		{
+			Some(e) => pedersen::Commitment::from_vec(e),
+			None => continue,
+		};
+		// the kernel can't be in a block older than the transaction
+		let kernel = wallet
+			.w2n_client()
+			.get_kernel(&excess, t.creation_height, None)?;
+		if let Some((_, height, _)) = kernel {
+			confirmed.push((t, height));
+		}
+	}
+	if confirmed.is_empty() {
+		return Ok(());
+	}
+
+	let mut batch = wallet.batch(keychain_mask)?;
+	for (mut t, height) in confirmed {
+		let outputs: Vec<OutputData> = batch
+			.iter()
+			.filter(|o| o.root_key_id == *parent_key_id && o.tx_log_entry == Some(t.id))
+			.collect();
+		for mut o in outputs {
+			match o.status {
+				OutputStatus::Locked => o.mark_spent(),
+				OutputStatus::Unconfirmed => {
+					o.height = height;
+					o.mark_unspent();
+				}
+				_ => continue,
+			}
+			batch.save(o)?;
+		}
+		t.update_confirmation_ts();
+		if let Some(ts) = t.confirmation_ts {
+			statistics::record_confirmed(t.creation_ts, ts);
+		}
+		t.confirmed = true;
+		t.confirmation_height = Some(height);
+		batch.save_tx_log_entry(t, parent_key_id)?;
+	}
+	batch.commit()?;
+	Ok(())
+}
+
 fn clean_old_unconfirmed<'a, T: ?Sized, C, K>(
 	wallet: &mut T,
 	keychain_mask: Option<&SecretKey>,
@@ -370,7 +712,7 @@ where
 		return Ok(());
 	}
 	let mut ids_to_del = vec![];
-	for out in wallet.iter() {
+	for out in wallet.iter()? {
 		if out.status == OutputStatus::Unconfirmed
 			&& out.height > 0
 			&& out.height < height - 50
@@ -400,8 +742,10 @@ where
 	K: Keychain + 'a,
 {
 	let current_height = wallet.last_confirmed_height()?;
+	let requirements =
+		ConfirmationRequirements::new(&*wallet, parent_key_id, minimum_confirmations)?;
 	let outputs = wallet
-		.iter()
+		.iter()?
 		.filter(|out| out.root_key_id == *parent_key_id);
 
 	let mut unspent_total = 0;
@@ -415,8 +759,9 @@ where
 			OutputStatus::Unspent => {
 				if out.is_coinbase && out.lock_height > current_height {
 					immature_total += out.value;
-				} else if out.num_confirmations(current_height) < minimum_confirmations {
-					// Treat anything less than minimum confirmations as "unconfirmed".
+				} else if out.num_confirmations(current_height) < requirements.required(&out) {
+					// Treat anything less than minimum confirmations for the output's
+					// source as "unconfirmed".
 					unconfirmed_total += out.value;
 				} else {
 					unspent_total += out.value;
@@ -528,7 +873,12 @@ where
 	let mut block_fees = block_fees.clone();
 	block_fees.key_id = Some(key_id.clone());
 
-	debug!("receive_coinbase: {:?}", block_fees);
+	debug!(
+		"receive_coinbase: fees {}, height {}, key_id {:?}",
+		log_amount(block_fees.fees),
+		block_fees.height,
+		block_fees.key_id,
+	);
 
 	let keychain = wallet.keychain(keychain_mask)?;
 	let (out, kern) = reward::output(
@@ -43,25 +43,78 @@ extern crate strum;
 #[macro_use]
 extern crate strum_macros;
 
+mod accounting;
 pub mod api_impl;
+mod build_info;
+mod canonical_json;
+mod confirmations;
+mod derivation;
 mod error;
+mod history;
 mod internal;
+mod memo_escrow;
+mod operation;
+mod policy;
+mod privacy;
+mod replay;
+mod resources;
 mod slate;
 pub mod slate_versions;
+pub mod slip39;
+mod statistics;
+mod totp;
 mod types;
 
+pub use crate::accounting::{
+	settlement_report, FixedPriceOracle, PriceOracle, SettlementEntry, SettlementReport,
+};
+pub use crate::build_info::{build_info, set_build_details, BuildInfo, WALLET_VERSION};
+pub use crate::canonical_json::{canonical_json_message, to_canonical_json};
+pub use crate::confirmations::{
+	set_spend_confirmations, spend_confirmations, OutputSource, SpendConfirmations,
+};
+pub use crate::derivation::{
+	derivation_scheme, register_derivation_scheme, Bip39Derivation, DerivationScheme,
+	GrinDerivation, DEFAULT_DERIVATION_SCHEME,
+};
 pub use crate::error::{Error, ErrorKind};
-pub use crate::slate::{ParticipantData, ParticipantMessageData, Slate};
+pub use crate::history::{export_txs, TxExportEntry, TxExportFormat};
+pub use crate::memo_escrow::{new_escrow_key, open_memo, parse_escrow_key, seal_memo};
+pub use crate::operation::{
+	CancelToken, LongOperation, OperationState, OperationStatus, ScanProgress,
+};
+pub use crate::policy::{ReceiveCount, ReceiveLimits, SpendingPolicy};
+pub use crate::privacy::{log_amount, log_commit, privacy_mode, redact_json, set_privacy_mode};
+pub use crate::replay::{replay_slates, TxReplayReport, TxReplayStep};
+pub use crate::resources::{resource_limits, set_resource_limits, ResourceLimits};
+pub use crate::slate::{ParticipantData, ParticipantMessageData, ParticipantMessages, Slate};
 pub use crate::slate_versions::{
-	SlateVersion, VersionedSlate, CURRENT_SLATE_VERSION, GRIN_BLOCK_HEADER_VERSION,
+	negotiate_slate_version, SlateVersion, VersionedSlate, CURRENT_SLATE_VERSION,
+	GRIN_BLOCK_HEADER_VERSION,
 };
+pub use crate::statistics::{record_error, statistics, WalletStatistics};
+pub use crate::totp::{TotpSecret, TOTP_STEP_SECS};
 pub use api_impl::types::{
-	BlockFees, CbData, InitTxArgs, InitTxSendArgs, IssueInvoiceTxArgs, NodeHeightResult,
-	OutputCommitMapping, SendTXArgs, VersionInfo,
+	AnomalyKind, AuditedOutput, BlockFees, CbData, ChainAuditReport, ChangeOutput, ConsolidateArgs,
+	InitTxArgs, InitTxRecipient, InitTxSendArgs, IssueInvoiceTxArgs, NodeHeightResult,
+	OutputCommitMapping, OutputDerivation, ReceiveReservation, RecoveryTxArgs, RemediationAction,
+	ReservedKeys, RetrieveOutputsQueryArgs, RetrieveTxQueryArgs, SendTXArgs, SignedTx,
+	StatusMessage, TotpEnrollment, TxAnomaly, TxBatchResult, TxConflict, TxQueryStatus,
+	TxRejectionReason, TxSortOrder, TxValidationResult, UnsignedTx, VersionInfo,
+};
+pub use internal::backup::{
+	install_records, reconstruct, wallet_records, BackupArchive, BackupState, WalletBackup,
+	WalletRecords,
+};
+pub use internal::restore::{audit_chain, check_repair, restore};
+pub use internal::selection::{
+	coin_selection_names, register_coin_selection, CoinSelection, SmallestFirst, UseAll,
+	WeightedRandom,
 };
-pub use internal::restore::{check_repair, restore};
 pub use types::{
-	AcctPathMapping, BlockIdentifier, Context, NodeClient, NodeVersionInfo, OutputData,
-	OutputStatus, TxLogEntry, TxLogEntryType, TxWrapper, WalletBackend, WalletInfo, WalletInst,
-	WalletLCProvider, WalletOutputBatch,
+	AcctPathMapping, BackupTarget, BlockIdentifier, Context, FeatureFlags, NodeClient,
+	NodeConnectivity, NodeHealth, NodeStatus, NodeVersionInfo, OutputData, OutputStatus,
+	PendingReceive, PushNotifier, PushRegistration, ScannedBlockInfo, SeedCheck, SelectionIntent,
+	TxLogEntry, TxLogEntryType, TxStep, TxStepVersions, TxWrapper, WalletBackend, WalletInfo,
+	WalletInst, WalletLCProvider, WalletOutputBatch,
 };
@@ -0,0 +1,155 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Escrow of transaction memos. The participant messages of a transaction
+//! are sealed to a public key set up by the wallet's owner, so whoever holds
+//! the matching secret key, such as a merchant's support staff, can read the
+//! order references in them without any access to the wallet or its keys
+
+use crate::blake2::blake2b::blake2b;
+use crate::error::{Error, ErrorKind};
+use crate::grin_util::secp::key::{PublicKey, SecretKey};
+use crate::grin_util::{from_hex, static_secp_instance, to_hex};
+use crate::slate::ParticipantMessages;
+use rand::{thread_rng, Rng};
+use ring::aead;
+
+/// Version of the sealed memo format, written as its first byte
+const ESCROW_VERSION: u8 = 1;
+/// Personalisation of the hash deriving the encryption key from the shared
+/// secret
+const ESCROW_KEY_DOMAIN: &[u8] = b"grin_wallet_memo_escrow";
+/// Length of a compressed public key
+const PUBLIC_KEY_LEN: usize = 33;
+/// Length of the header before the encrypted messages
+const HEADER_LEN: usize = 1 + PUBLIC_KEY_LEN + 12;
+
+fn escrow_error(msg: &str) -> Error {
+	ErrorKind::MemoEscrow(msg.to_owned()).into()
+}
+
+/// Key encrypting a memo, from the Diffie-Hellman secret shared by the
+/// given public and secret keys
+fn memo_key(public_key: &PublicKey, secret_key: &SecretKey) -> Result<Vec<u8>, Error> {
+	let secp = static_secp_instance();
+	let secp = secp.lock();
+	let mut shared = public_key.clone();
+	shared.mul_assign(&secp, secret_key)?;
+	let key = blake2b(32, &shared.serialize_vec(&secp, true), ESCROW_KEY_DOMAIN);
+	Ok(key.as_bytes().to_vec())
+}
+
+/// Parse a hex-encoded public key to escrow memos to
+pub fn parse_escrow_key(hex: &str) -> Result<PublicKey, Error> {
+	let secp = static_secp_instance();
+	let secp = secp.lock();
+	let bytes = from_hex(hex.trim().to_owned()).map_err(|_| escrow_error("invalid hex"))?;
+	PublicKey::from_slice(&secp, &bytes).map_err(|_| escrow_error("invalid public key"))
+}
+
+/// Generate a new escrow key pair, returning the secret key and the
+/// hex-encoded public key to configure wallets with
+pub fn new_escrow_key() -> Result<(SecretKey, String), Error> {
+	let secp = static_secp_instance();
+	let secp = secp.lock();
+	let secret_key = SecretKey::new(&secp, &mut thread_rng());
+	let public_key = PublicKey::from_secret_key(&secp, &secret_key)?;
+	Ok((
+		secret_key,
+		to_hex(public_key.serialize_vec(&secp, true).to_vec()),
+	))
+}
+
+/// Seal the messages to the escrow key, as the hex encoding of the format
+/// version, a one-off public key, a nonce and the encrypted messages
+pub fn seal_memo(escrow_key: &PublicKey, messages: &ParticipantMessages) -> Result<String, Error> {
+	let (ephemeral_secret, ephemeral_public) = {
+		let secp = static_secp_instance();
+		let secp = secp.lock();
+		let secret = SecretKey::new(&secp, &mut thread_rng());
+		let public = PublicKey::from_secret_key(&secp, &secret)?;
+		(secret, public.serialize_vec(&secp, true).to_vec())
+	};
+	let key = memo_key(escrow_key, &ephemeral_secret)?;
+	let mut data = serde_json::to_vec(messages)
+		.map_err(|e| ErrorKind::Format(format!("Serializing messages: {}", e)))?;
+	let tag_len = aead::CHACHA20_POLY1305.tag_len();
+	data.extend(vec![0; tag_len]);
+	let nonce: [u8; 12] = thread_rng().gen();
+	let sealing_key = aead::SealingKey::new(&aead::CHACHA20_POLY1305, &key)
+		.map_err(|_| escrow_error("invalid key"))?;
+	aead::seal_in_place(&sealing_key, &nonce, &ephemeral_public, &mut data, tag_len)
+		.map_err(|_| escrow_error("encryption failed"))?;
+
+	let mut sealed = vec![ESCROW_VERSION];
+	sealed.extend(ephemeral_public);
+	sealed.extend_from_slice(&nonce);
+	sealed.extend(data);
+	Ok(to_hex(sealed))
+}
+
+/// Open a memo sealed to the public key of the given secret key
+pub fn open_memo(secret_key: &SecretKey, sealed: &str) -> Result<ParticipantMessages, Error> {
+	let sealed = from_hex(sealed.trim().to_owned()).map_err(|_| escrow_error("invalid hex"))?;
+	if sealed.len() < HEADER_LEN || sealed[0] != ESCROW_VERSION {
+		return Err(escrow_error("unsupported or truncated memo"));
+	}
+	let ephemeral_public = &sealed[1..1 + PUBLIC_KEY_LEN];
+	let ephemeral_key = {
+		let secp = static_secp_instance();
+		let secp = secp.lock();
+		PublicKey::from_slice(&secp, ephemeral_public)
+			.map_err(|_| escrow_error("invalid public key"))?
+	};
+	let key = memo_key(&ephemeral_key, secret_key)?;
+	let opening_key = aead::OpeningKey::new(&aead::CHACHA20_POLY1305, &key)
+		.map_err(|_| escrow_error("invalid key"))?;
+	let mut data = sealed[HEADER_LEN..].to_vec();
+	let data = aead::open_in_place(
+		&opening_key,
+		&sealed[1 + PUBLIC_KEY_LEN..HEADER_LEN],
+		ephemeral_public,
+		0,
+		&mut data,
+	)
+	.map_err(|_| escrow_error("can't decrypt, the memo is sealed to another key"))?;
+	serde_json::from_slice(data).map_err(|e| escrow_error(&format!("invalid messages: {}", e)))
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::slate::ParticipantMessageData;
+
+	#[test]
+	fn seal_and_open_memo() {
+		let (secret_key, public_key) = new_escrow_key().unwrap();
+		let escrow_key = parse_escrow_key(&public_key).unwrap();
+		let messages = ParticipantMessages {
+			messages: vec![ParticipantMessageData {
+				id: 0,
+				public_key: escrow_key.clone(),
+				message: Some("Order #1234".to_owned()),
+				message_sig: None,
+			}],
+		};
+		let sealed = seal_memo(&escrow_key, &messages).unwrap();
+		let opened = open_memo(&secret_key, &sealed).unwrap();
+		assert_eq!(opened.messages[0].message, Some("Order #1234".to_owned()));
+
+		let (other_key, _) = new_escrow_key().unwrap();
+		assert!(open_memo(&other_key, &sealed).is_err());
+		assert!(open_memo(&secret_key, &sealed[..40]).is_err());
+	}
+}
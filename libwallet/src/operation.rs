@@ -0,0 +1,177 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Long-running wallet operations, which report their progress and can be
+//! cancelled while they run
+
+use crate::error::{Error, ErrorKind};
+use crate::grin_core::ser;
+use crate::grin_util::Mutex;
+use chrono::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A long-running operation that can be run in the background
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum LongOperation {
+	/// Restore the wallet's outputs from the chain, as
+	/// [`restore`](../grin_wallet_api/struct.Owner.html#method.restore)
+	Restore,
+	/// Check the wallet's outputs against the chain, as
+	/// [`check_repair`](../grin_wallet_api/struct.Owner.html#method.check_repair)
+	CheckRepair {
+		/// Whether to unlock locked outputs and delete unconfirmed ones
+		delete_unconfirmed: bool,
+		/// Whether to scan the whole UTXO set
+		full: bool,
+	},
+}
+
+/// State of a background operation
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum OperationState {
+	/// Waiting for its turn to use the wallet
+	Queued,
+	/// Running
+	Running,
+	/// Finished successfully
+	Completed,
+	/// Cancelled, with any changes it made undone
+	Cancelled,
+	/// Failed with the given error
+	Failed(String),
+}
+
+impl OperationState {
+	/// Whether the operation has stopped, one way or another
+	pub fn is_finished(&self) -> bool {
+		match self {
+			OperationState::Queued | OperationState::Running => false,
+			_ => true,
+		}
+	}
+}
+
+/// Status of a background operation. Also kept in the wallet database, so
+/// unfinished operations are resumed when the wallet's listener restarts
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OperationStatus {
+	/// Id of the operation
+	pub id: u32,
+	/// The operation
+	pub operation: LongOperation,
+	/// Its state
+	pub state: OperationState,
+	/// Output MMR index the UTXO set has been scanned up to
+	pub scanned: u64,
+	/// Highest output MMR index to scan up to, once known
+	pub scan_total: u64,
+	/// Percentage of the operation done, from how much of the UTXO set has
+	/// been scanned. Only reaches 100 once the operation has completed
+	#[serde(default)]
+	pub percent_complete: u8,
+	/// When the operation was started
+	pub created: DateTime<Utc>,
+	/// When the operation finished, if it has
+	pub finished: Option<DateTime<Utc>>,
+	/// Number of times the operation has been resumed after a restart
+	pub resumed: u32,
+}
+
+impl ser::Writeable for OperationStatus {
+	fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+	}
+}
+
+impl ser::Readable for OperationStatus {
+	fn read(reader: &mut dyn ser::Reader) -> Result<OperationStatus, ser::Error> {
+		let data = reader.read_bytes_len_prefix()?;
+		serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+	}
+}
+
+/// Progress of an operation's scan of the UTXO set
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ScanProgress {
+	/// Output MMR index the UTXO set has been scanned up to
+	pub scanned: u64,
+	/// Highest output MMR index to scan up to, once known
+	pub scan_total: u64,
+	/// Height of the block holding the last output scanned
+	pub height: u64,
+	/// Number of the wallet's outputs found so far
+	pub outputs_found: u64,
+}
+
+/// Called with an operation's progress each time it's reported, returning
+/// whether to keep being called
+type ProgressCallback = Box<dyn Fn(&ScanProgress) -> bool + Send + Sync>;
+
+/// Token shared by an operation and whoever started it, through which the
+/// operation reports its progress and is asked to stop
+#[derive(Clone, Default)]
+pub struct CancelToken {
+	cancelled: Arc<AtomicBool>,
+	progress: Arc<Mutex<ScanProgress>>,
+	callbacks: Arc<Mutex<Vec<ProgressCallback>>>,
+}
+
+impl CancelToken {
+	/// Create a token for a new operation
+	pub fn new() -> CancelToken {
+		CancelToken::default()
+	}
+
+	/// Ask the operation to stop
+	pub fn cancel(&self) {
+		self.cancelled.store(true, Ordering::Relaxed);
+	}
+
+	/// Whether the operation has been asked to stop
+	pub fn is_cancelled(&self) -> bool {
+		self.cancelled.load(Ordering::Relaxed)
+	}
+
+	/// Fail with [`ErrorKind::Cancelled`](enum.ErrorKind.html) if the
+	/// operation has been asked to stop
+	pub fn check(&self) -> Result<(), Error> {
+		if self.is_cancelled() {
+			return Err(ErrorKind::Cancelled.into());
+		}
+		Ok(())
+	}
+
+	/// Record how much of the UTXO set has been scanned, passing it on to
+	/// the callbacks registered with [`on_progress`](#method.on_progress)
+	pub fn set_progress(&self, mut progress: ScanProgress) {
+		progress.scanned = progress.scanned.min(progress.scan_total);
+		*self.progress.lock() = progress.clone();
+		self.callbacks.lock().retain(|f| f(&progress));
+	}
+
+	/// How much of the UTXO set has been scanned
+	pub fn progress(&self) -> ScanProgress {
+		self.progress.lock().clone()
+	}
+
+	/// Call `f` with the operation's progress each time it's reported, until
+	/// `f` returns false
+	pub fn on_progress<F>(&self, f: F)
+	where
+		F: Fn(&ScanProgress) -> bool + Send + Sync + 'static,
+	{
+		self.callbacks.lock().push(Box::new(f));
+	}
+}
@@ -0,0 +1,291 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Spending policy, set for each wallet instance by the binary or an
+//! embedding application through its lifecycle provider. Sends are checked
+//! against it when started and again when finalized, so whoever can call the
+//! Owner API, such as semi-trusted automation, is held to the operator's
+//! limits. Also holds the limits on what the Foreign API receives
+
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use crate::error::{Error, ErrorKind};
+use crate::grin_core::core::amount_to_hr_string;
+use crate::grin_core::ser;
+use crate::grin_keychain::Keychain;
+use crate::types::{NodeClient, TxLogEntryType, WalletBackend};
+
+/// Limits on what the wallet sends. Nothing is limited by default
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct SpendingPolicy {
+	/// Largest amount sent in one transaction, in nanogrin
+	pub max_amount_per_tx: Option<u64>,
+	/// Largest amount sent over the last 24 hours, in nanogrin
+	pub max_daily_total: Option<u64>,
+	/// Destinations slates may be sent to. Where set, sends without a
+	/// destination, returning the slate to the caller, are refused
+	pub allowed_destinations: Option<Vec<String>>,
+}
+
+impl SpendingPolicy {
+	/// Check a send of `amount`, given the amount already sent over the last
+	/// 24 hours
+	pub fn check_amount(&self, amount: u64, sent_today: u64) -> Result<(), Error> {
+		if let Some(max) = self.max_amount_per_tx {
+			if amount > max {
+				return Err(violation(format!(
+					"sending {} is over the limit of {} per transaction",
+					amount_to_hr_string(amount, false),
+					amount_to_hr_string(max, false)
+				)));
+			}
+		}
+		if let Some(max) = self.max_daily_total {
+			if sent_today.saturating_add(amount) > max {
+				return Err(violation(format!(
+					"sending {} after {} in the last 24 hours is over the daily limit of {}",
+					amount_to_hr_string(amount, false),
+					amount_to_hr_string(sent_today, false),
+					amount_to_hr_string(max, false)
+				)));
+			}
+		}
+		Ok(())
+	}
+
+	/// Check a send to `dest`, or returning the slate to the caller if none
+	pub fn check_destination(&self, dest: Option<&str>) -> Result<(), Error> {
+		if let Some(allowed) = self.allowed_destinations.as_ref() {
+			let permitted = dest.map_or(false, |d| {
+				allowed
+					.iter()
+					.any(|a| a.trim_end_matches('/') == d.trim_end_matches('/'))
+			});
+			if !permitted {
+				return Err(violation(match dest {
+					Some(d) => format!("{} isn't an allowed destination", d),
+					None => "sends must be made to an allowed destination".to_owned(),
+				}));
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Limits on the slates received through the Foreign API, so public
+/// listeners can't be spammed with dust receives bloating the wallet
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReceiveLimits {
+	/// Smallest amount received, in nanogrin
+	pub min_amount: Option<u64>,
+	/// Largest amount received, in nanogrin
+	pub max_amount: Option<u64>,
+	/// Most slates received, or queued for review, in an hour
+	pub max_per_hour: Option<u32>,
+	/// Whether invoices this wallet issued are finalized when the payer
+	/// returns them
+	pub finalize_invoices: bool,
+}
+
+impl Default for ReceiveLimits {
+	fn default() -> ReceiveLimits {
+		ReceiveLimits {
+			min_amount: None,
+			max_amount: None,
+			max_per_hour: None,
+			finalize_invoices: true,
+		}
+	}
+}
+
+impl ReceiveLimits {
+	/// Check a receive of `amount`, given the number of slates received in
+	/// the current hour
+	pub fn check_receive(&self, amount: u64, received_this_hour: usize) -> Result<(), Error> {
+		if let Some(min) = self.min_amount {
+			if amount < min {
+				return Err(refusal(format!(
+					"{} is below the minimum of {}",
+					amount_to_hr_string(amount, false),
+					amount_to_hr_string(min, false)
+				)));
+			}
+		}
+		if let Some(max) = self.max_amount {
+			if amount > max {
+				return Err(refusal(format!(
+					"{} is over the maximum of {}",
+					amount_to_hr_string(amount, false),
+					amount_to_hr_string(max, false)
+				)));
+			}
+		}
+		if let Some(max) = self.max_per_hour {
+			if received_this_hour >= max as usize {
+				return Err(refusal(format!(
+					"no more than {} slates are received per hour, try again later",
+					max
+				)));
+			}
+		}
+		Ok(())
+	}
+
+	/// Check invoices may be finalized
+	pub fn check_invoice(&self) -> Result<(), Error> {
+		match self.finalize_invoices {
+			true => Ok(()),
+			false => Err(refusal("invoices aren't paid to this wallet".to_owned())),
+		}
+	}
+}
+
+/// Number of slates received through the Foreign API, or queued for review,
+/// in an hour, kept with the wallet to check the receive limits against
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ReceiveCount {
+	/// Start of the hour, in seconds since the epoch
+	pub hour: i64,
+	/// Slates received in it
+	pub count: u32,
+}
+
+impl ReceiveCount {
+	/// The count for the current hour, given the one last saved
+	pub fn this_hour(last: Option<ReceiveCount>) -> ReceiveCount {
+		let hour = Utc::now().timestamp() / 3600 * 3600;
+		match last {
+			Some(c) if c.hour == hour => c,
+			_ => ReceiveCount { hour, count: 0 },
+		}
+	}
+}
+
+impl ser::Writeable for ReceiveCount {
+	fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+	}
+}
+
+impl ser::Readable for ReceiveCount {
+	fn read(reader: &mut dyn ser::Reader) -> Result<ReceiveCount, ser::Error> {
+		let data = reader.read_bytes_len_prefix()?;
+		serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+	}
+}
+
+fn refusal(msg: String) -> Error {
+	debug!("Receive refused: {}", msg);
+	ErrorKind::ReceiveLimit(msg).into()
+}
+
+fn violation(msg: String) -> Error {
+	warn!(target: "audit", "Send refused by spending policy: {}", msg);
+	ErrorKind::SpendingPolicy(msg).into()
+}
+
+/// Check the amount of a send against the wallet's policy, counting what
+/// the wallet sent over the last 24 hours in all accounts, other than the
+/// transaction of the slate `exclude` if given
+pub(crate) fn check_send_amount<'a, T: ?Sized, C, K>(
+	wallet: &T,
+	amount: u64,
+	exclude: Option<Uuid>,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let policy = wallet.spending_policy();
+	if policy.max_amount_per_tx.is_none() && policy.max_daily_total.is_none() {
+		return Ok(());
+	}
+	let since = Utc::now() - Duration::hours(24);
+	let sent_today = wallet
+		.tx_log_iter()?
+		.filter(|t| t.tx_type == TxLogEntryType::TxSent && t.creation_ts > since)
+		.filter(|t| exclude.map_or(true, |id| t.tx_slate_id != Some(id)))
+		.map(|t| {
+			t.amount_debited
+				.saturating_sub(t.amount_credited)
+				.saturating_sub(t.fee.unwrap_or(0))
+		})
+		.fold(0u64, |acc, a| acc.saturating_add(a));
+	policy.check_amount(amount, sent_today)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn policy_limits() {
+		assert!(SpendingPolicy::default().check_amount(u64::MAX, 0).is_ok());
+		assert!(SpendingPolicy::default().check_destination(None).is_ok());
+
+		let policy = SpendingPolicy {
+			max_amount_per_tx: Some(10),
+			max_daily_total: Some(25),
+			allowed_destinations: Some(vec!["http://exchange.example.com/".to_owned()]),
+		};
+		let dest = Some("http://exchange.example.com");
+		assert!(policy.check_amount(10, 15).is_ok());
+		assert!(policy.check_amount(11, 0).is_err());
+		assert!(policy.check_amount(10, 16).is_err());
+		assert!(policy.check_destination(dest).is_ok());
+		assert!(policy
+			.check_destination(Some("http://other.example.com"))
+			.is_err());
+		assert!(policy.check_destination(None).is_err());
+	}
+
+	#[test]
+	fn receive_limits() {
+		assert!(ReceiveLimits::default().check_receive(1, 1000).is_ok());
+		assert!(ReceiveLimits::default().check_invoice().is_ok());
+
+		let limits = ReceiveLimits {
+			min_amount: Some(10),
+			max_amount: Some(20),
+			max_per_hour: Some(3),
+			finalize_invoices: false,
+		};
+		assert!(limits.check_receive(10, 2).is_ok());
+		assert!(limits.check_receive(20, 0).is_ok());
+		assert!(limits.check_receive(9, 0).is_err());
+		assert!(limits.check_receive(21, 0).is_err());
+		assert!(limits.check_receive(15, 3).is_err());
+		assert!(limits.check_invoice().is_err());
+	}
+
+	#[test]
+	fn receive_count() {
+		let now = ReceiveCount::this_hour(None);
+		assert_eq!(now.count, 0);
+		assert_eq!(now.hour % 3600, 0);
+
+		let last = ReceiveCount {
+			hour: now.hour,
+			count: 3,
+		};
+		assert_eq!(ReceiveCount::this_hour(Some(last.clone())), last);
+		let earlier = ReceiveCount {
+			hour: now.hour - 3600,
+			count: 3,
+		};
+		assert_eq!(ReceiveCount::this_hour(Some(earlier)), now);
+	}
+}
@@ -0,0 +1,168 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Privacy mode, redacting amounts and commitments from logs and debug output
+//! so logs can be shared without disclosing balances. Amounts are shown as the
+//! power of ten range they fall in, and commitments as a hash keyed for this
+//! process, so one can be followed through a log without being found on chain.
+
+use crate::blake2::blake2b::blake2b;
+use crate::grin_core::core::amount_to_hr_string;
+use crate::grin_util::secp::pedersen::Commitment;
+use crate::grin_util::to_hex;
+use rand::{thread_rng, Rng};
+use serde_json::Value;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Members of JSON objects holding commitments, excesses or proofs
+const COMMIT_MEMBERS: [&str; 5] = [
+	"commit",
+	"excess",
+	"excess_sig",
+	"proof",
+	"public_blind_excess",
+];
+
+static PRIVACY_MODE: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+	/// Key commitments are hashed with, different for each process
+	static ref LOG_KEY: [u8; 32] = thread_rng().gen();
+}
+
+/// Turn privacy mode on or off for this process
+pub fn set_privacy_mode(enabled: bool) {
+	PRIVACY_MODE.store(enabled, Ordering::Relaxed);
+	info!("Privacy mode {}", if enabled { "on" } else { "off" });
+}
+
+/// Whether privacy mode is on
+pub fn privacy_mode() -> bool {
+	PRIVACY_MODE.load(Ordering::Relaxed)
+}
+
+/// An amount to log, in grin, or the range it falls in in privacy mode
+pub fn log_amount(amount: u64) -> String {
+	if !privacy_mode() {
+		return amount_to_hr_string(amount, false);
+	}
+	if amount == 0 {
+		return "0".to_owned();
+	}
+	let mut lower = 1u64;
+	while amount / lower >= 10 {
+		lower *= 10;
+	}
+	let grin = |n: u64| format!("{}", n as f64 / 1_000_000_000.0);
+	match lower.checked_mul(10) {
+		Some(upper) => format!("{}-{}", grin(lower), grin(upper)),
+		None => format!(">{}", grin(lower)),
+	}
+}
+
+/// A commitment to log, hex encoded, or hashed in privacy mode
+pub fn log_commit(commit: &Commitment) -> String {
+	match privacy_mode() {
+		true => log_hash(commit.as_ref()),
+		false => to_hex(commit.as_ref().to_vec()),
+	}
+}
+
+fn log_hash(bytes: &[u8]) -> String {
+	let hash = blake2b(8, &LOG_KEY[..], bytes);
+	format!("#{}", to_hex(hash.as_bytes().to_vec()))
+}
+
+/// A JSON value, such as a slate or an API request or response, to log. In
+/// privacy mode amounts and fees are replaced with their ranges, and
+/// commitments, excesses and proofs with their hashes
+pub fn redact_json(value: &Value) -> Value {
+	if !privacy_mode() {
+		return value.clone();
+	}
+	redact_value(value)
+}
+
+fn redact_value(value: &Value) -> Value {
+	match value {
+		Value::Array(a) => Value::Array(a.iter().map(redact_value).collect()),
+		Value::Object(o) => Value::Object(
+			o.iter()
+				.map(|(k, v)| (k.clone(), redact_member(k, v)))
+				.collect(),
+		),
+		v => v.clone(),
+	}
+}
+
+fn redact_member(key: &str, value: &Value) -> Value {
+	let is_amount = key.starts_with("amount") || ["fee", "fees", "value", "total"].contains(&key);
+	if is_amount {
+		let amount = match value {
+			Value::Number(n) => n.as_u64(),
+			Value::String(s) => s.parse().ok(),
+			_ => None,
+		};
+		if let Some(a) = amount {
+			return Value::String(log_amount(a));
+		}
+	}
+	if COMMIT_MEMBERS.contains(&key) {
+		if let Value::String(s) = value {
+			return Value::String(log_hash(s.as_bytes()));
+		}
+	}
+	redact_value(value)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use serde_json::json;
+
+	#[test]
+	fn redacts_in_privacy_mode() {
+		let commit = Commitment::from_vec(vec![8; 33]);
+		let slate = json!({
+			"amount": "60000000000",
+			"fee": 8000000,
+			"height": "10",
+			"tx": {"body": {"outputs": [{"commit": "0808", "features": "Plain"}]}},
+		});
+
+		set_privacy_mode(false);
+		assert_eq!(log_amount(60_000_000_000), "60.000000000");
+		assert_eq!(log_commit(&commit), to_hex(vec![8; 33]));
+		assert_eq!(redact_json(&slate), slate);
+
+		set_privacy_mode(true);
+		assert_eq!(log_amount(0), "0");
+		assert_eq!(log_amount(60_000_000_000), "10-100");
+		assert_eq!(log_amount(8_000_000), "0.001-0.01");
+		assert_eq!(log_amount(1_000_000_000), "1-10");
+		assert_eq!(log_amount(u64::max_value()), ">10000000000");
+		let hashed = log_commit(&commit);
+		assert_eq!(hashed.len(), 17);
+		assert_eq!(hashed, log_commit(&commit));
+
+		let redacted = redact_json(&slate);
+		assert_eq!(redacted["amount"], "10-100");
+		assert_eq!(redacted["fee"], "0.001-0.01");
+		assert_eq!(redacted["height"], "10");
+		let output = &redacted["tx"]["body"]["outputs"][0];
+		assert!(output["commit"].as_str().unwrap().starts_with('#'));
+		assert_eq!(output["features"], "Plain");
+		set_privacy_mode(false);
+	}
+}
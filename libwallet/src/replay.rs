@@ -0,0 +1,199 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Dry-run replay of the slates stored at each step of a transaction against
+//! the running code, to investigate transactions that worked, or failed, with
+//! another version of the wallet. Replays only work on copies of the stored
+//! slates, with a throwaway keychain, and never touch the wallet's records
+
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::grin_core::core::Transaction;
+use crate::grin_keychain::{ExtKeychain, Keychain};
+use crate::slate::Slate;
+use crate::types::{TxStep, TxStepVersions};
+
+/// Outcome of replaying one step of a transaction
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TxReplayStep {
+	/// Step of the transaction
+	pub step: TxStep,
+	/// Version of the wallet which took the step, if recorded
+	pub wallet_version: Option<String>,
+	/// Version of the slate stored at the step, if stored
+	pub slate_version: Option<u16>,
+	/// Whether a slate was stored at the step. Steps taken by the other party,
+	/// or by a wallet predating slate storage, have none
+	pub stored: bool,
+	/// What the running code didn't reproduce
+	pub failures: Vec<String>,
+}
+
+/// Outcome of replaying a transaction's stored slates
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TxReplayReport {
+	/// Id of the transaction's slate
+	pub slate_id: Uuid,
+	/// Each step of the transaction, in order
+	pub steps: Vec<TxReplayStep>,
+	/// Whether at least one slate was stored, and every stored step was
+	/// reproduced
+	pub reproduced: bool,
+}
+
+/// Replay the slates stored at each step of a transaction, as JSON, checking
+/// with the running code that each still deserializes and serializes back to
+/// what was stored, that its signatures verify and that it follows from the
+/// step before it. The finalized slate is finalized again, which must give
+/// the same kernel, and the same transaction as the one stored if given
+pub fn replay_slates(
+	slate_id: Uuid,
+	stored: &[(TxStep, Option<String>)],
+	versions: &[TxStepVersions],
+	stored_tx: Option<&Transaction>,
+) -> TxReplayReport {
+	let mut steps = vec![];
+	let mut previous: Option<(TxStep, Slate)> = None;
+	for (step, json) in stored {
+		let wallet_version = versions
+			.iter()
+			.rev()
+			.find(|v| v.step == *step)
+			.map(|v| v.wallet_version.clone());
+		let json = match json {
+			Some(j) => j,
+			None => {
+				steps.push(TxReplayStep {
+					step: *step,
+					wallet_version,
+					slate_version: None,
+					stored: false,
+					failures: vec![],
+				});
+				continue;
+			}
+		};
+		let mut failures = vec![];
+		let slate = replay_step(json, &mut failures);
+		if let Some(s) = slate.as_ref() {
+			if s.id != slate_id {
+				failures.push(format!("slate is for transaction {}", s.id));
+			}
+			if let Some((prev_step, prev)) = previous.as_ref() {
+				check_follows(prev, *prev_step, s, &mut failures);
+			}
+			if *step == TxStep::Finalize {
+				replay_finalize(s, stored_tx, &mut failures);
+			}
+		}
+		steps.push(TxReplayStep {
+			step: *step,
+			wallet_version,
+			slate_version: Slate::parse_slate_version(json).ok(),
+			stored: true,
+			failures,
+		});
+		if let Some(s) = slate {
+			previous = Some((*step, s));
+		}
+	}
+	let reproduced = steps.iter().any(|s| s.stored) && steps.iter().all(|s| s.failures.is_empty());
+	TxReplayReport {
+		slate_id,
+		steps,
+		reproduced,
+	}
+}
+
+/// Deserialize a stored slate, checking it serializes back to the same JSON
+/// and that its signatures verify
+fn replay_step(json: &str, failures: &mut Vec<String>) -> Option<Slate> {
+	let slate = match Slate::deserialize_upgrade(json) {
+		Ok(s) => s,
+		Err(e) => {
+			failures.push(format!("slate no longer deserializes: {}", e));
+			return None;
+		}
+	};
+	let recorded: Option<Value> = serde_json::from_str(json).ok();
+	if serde_json::to_value(&slate).ok() != recorded {
+		failures.push("slate doesn't serialize back to the stored JSON".to_owned());
+	}
+	let keychain = match ExtKeychain::from_random_seed(false) {
+		Ok(k) => k,
+		Err(e) => {
+			failures.push(format!("sandbox keychain: {}", e));
+			return Some(slate);
+		}
+	};
+	if let Err(e) = slate.verify_part_sigs(keychain.secp()) {
+		failures.push(format!("partial signatures don't verify: {}", e));
+	}
+	if let Err(e) = slate.verify_messages() {
+		failures.push(format!("participant messages don't verify: {}", e));
+	}
+	Some(slate)
+}
+
+/// Check a slate keeps what the slate of the previous step settled
+fn check_follows(prev: &Slate, prev_step: TxStep, slate: &Slate, failures: &mut Vec<String>) {
+	let mut changed = |what: &str, same: bool| {
+		if !same {
+			failures.push(format!("{} changed since the {:?} step", what, prev_step));
+		}
+	};
+	changed("amount", prev.amount == slate.amount);
+	changed("fee", prev.fee == slate.fee);
+	changed("lock height", prev.lock_height == slate.lock_height);
+	changed("offset", prev.tx.offset == slate.tx.offset);
+	for p in prev.participant_data.iter() {
+		let same = slate.participant_with_id(p.id as usize).map_or(false, |q| {
+			q.public_blind_excess == p.public_blind_excess && q.public_nonce == p.public_nonce
+		});
+		changed(format!("participant {}", p.id).as_str(), same);
+	}
+}
+
+/// Finalize a copy of the finalized slate again, checking it gives the kernel
+/// and transaction that were recorded
+fn replay_finalize(slate: &Slate, stored_tx: Option<&Transaction>, failures: &mut Vec<String>) {
+	let keychain = match ExtKeychain::from_random_seed(false) {
+		Ok(k) => k,
+		Err(_) => return,
+	};
+	let mut replayed = slate.clone();
+	if let Err(e) = replayed.finalize(&keychain) {
+		failures.push(format!("finalizing again failed: {}", e));
+		return;
+	}
+	let (recorded, kernel) = match (slate.tx.kernels().first(), replayed.tx.kernels().first()) {
+		(Some(r), Some(k)) => (r, k),
+		_ => {
+			failures.push("slate has no kernel".to_owned());
+			return;
+		}
+	};
+	if kernel.excess != recorded.excess {
+		failures.push("finalizing again gave a different kernel excess".to_owned());
+	}
+	if kernel.excess_sig != recorded.excess_sig {
+		failures.push("finalizing again gave a different kernel signature".to_owned());
+	}
+	if let Some(tx) = stored_tx {
+		if *tx != replayed.tx {
+			failures.push("finalizing again gave a different transaction than stored".to_owned());
+		}
+	}
+}
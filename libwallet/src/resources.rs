@@ -0,0 +1,108 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Process-wide limits on the resources (threads, memory) the wallet uses,
+//! set once at startup by the binary or an embedding application. The limits
+//! cover restore and check, requests to the node, the background updater and
+//! the listeners' runtimes. The wallet keeps no caches sized by these limits
+//! and has no FFI layer, so neither is tuned here
+
+use crate::grin_util::RwLock;
+
+/// Caps on batch sizes and concurrency used throughout the wallet
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ResourceLimits {
+	/// Number of outputs requested from the node at a time while scanning
+	/// the UTXO set during restore and check
+	pub restore_batch_size: u64,
+	/// Number of threads fetching and rewinding ranges of the UTXO set
+	/// concurrently during restore and check. With a single thread the
+	/// UTXO set is scanned sequentially
+	pub restore_threads: usize,
+	/// Maximum number of outputs queried from the node in a single request
+	pub node_query_chunk_size: usize,
+	/// Maximum number of requests made to the node concurrently
+	pub node_request_concurrency: usize,
+	/// Number of threads used to run requests to the node, defaulting to the
+	/// number of CPUs if not set
+	pub node_client_threads: Option<usize>,
+	/// Shortest interval, in seconds, the background updater refreshes the
+	/// wallet at, whatever frequency clients ask for
+	pub min_updater_interval_secs: Option<u64>,
+	/// Number of threads serving each API listener, defaulting to the number
+	/// of CPUs if not set
+	pub listener_threads: Option<usize>,
+}
+
+impl ResourceLimits {
+	/// Limits for constrained (mobile or embedded) devices
+	pub fn low() -> ResourceLimits {
+		ResourceLimits {
+			restore_batch_size: 100,
+			restore_threads: 1,
+			node_query_chunk_size: 50,
+			node_request_concurrency: 1,
+			node_client_threads: Some(1),
+			min_updater_interval_secs: Some(60),
+			listener_threads: Some(1),
+		}
+	}
+
+	/// Limits for a typical desktop wallet
+	pub fn standard() -> ResourceLimits {
+		ResourceLimits {
+			restore_batch_size: 1000,
+			restore_threads: 4,
+			node_query_chunk_size: 200,
+			node_request_concurrency: 8,
+			node_client_threads: None,
+			min_updater_interval_secs: None,
+			listener_threads: None,
+		}
+	}
+
+	/// Limits for a wallet running on a dedicated server
+	pub fn server() -> ResourceLimits {
+		ResourceLimits {
+			restore_batch_size: 1000,
+			restore_threads: 8,
+			node_query_chunk_size: 200,
+			node_request_concurrency: 32,
+			node_client_threads: None,
+			min_updater_interval_secs: None,
+			listener_threads: None,
+		}
+	}
+}
+
+impl Default for ResourceLimits {
+	fn default() -> ResourceLimits {
+		ResourceLimits::standard()
+	}
+}
+
+lazy_static! {
+	/// Limits in effect for this process
+	static ref RESOURCE_LIMITS: RwLock<ResourceLimits> = RwLock::new(ResourceLimits::default());
+}
+
+/// Set the resource limits for this process
+pub fn set_resource_limits(limits: ResourceLimits) {
+	*RESOURCE_LIMITS.write() = limits;
+}
+
+/// The resource limits currently in effect
+pub fn resource_limits() -> ResourceLimits {
+	*RESOURCE_LIMITS.read()
+}
@@ -39,11 +39,14 @@ use std::fmt;
 use std::sync::Arc;
 use uuid::Uuid;
 
+use crate::slate_versions::ser::opt_string_or_u64;
 use crate::slate_versions::v2::{
 	InputV2, OutputV2, ParticipantDataV2, SlateV2, TransactionBodyV2, TransactionV2, TxKernelV2,
 	VersionCompatInfoV2,
 };
-use crate::slate_versions::{CURRENT_SLATE_VERSION, GRIN_BLOCK_HEADER_VERSION};
+use crate::slate_versions::{
+	SlateVersion, VersionedSlate, CURRENT_SLATE_VERSION, GRIN_BLOCK_HEADER_VERSION,
+};
 
 /// Public data for each participant in the slate
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -170,6 +173,11 @@ pub struct Slate {
 	/// Lock height
 	#[serde(with = "secp_ser::string_or_u64")]
 	pub lock_height: u64,
+	/// Height from which neither party will build this transaction any
+	/// further, if any
+	#[serde(with = "opt_string_or_u64")]
+	#[serde(default)]
+	pub ttl_cutoff_height: Option<u64>,
 	/// Participant data, each participant in the transaction will
 	/// insert their public data here. For now, 0 is sender and 1
 	/// is receiver, though this will change for multi-party
@@ -204,19 +212,26 @@ impl Slate {
 
 	/// Recieve a slate, upgrade it to the latest version internally
 	pub fn deserialize_upgrade(slate_json: &str) -> Result<Slate, Error> {
-		let version = Slate::parse_slate_version(slate_json)?;
-		let v2: SlateV2 = match version {
-			2 => serde_json::from_str(slate_json).context(ErrorKind::SlateDeser)?,
-			// left as a reminder
-			/*0 => {
-				let v0: SlateV0 =
-					serde_json::from_str(slate_json).context(ErrorKind::SlateDeser)?;
-				let v1 = SlateV1::from(v0);
-				SlateV2::from(v1)
-			}*/
-			_ => return Err(ErrorKind::SlateVersion(version).into()),
-		};
-		Ok(v2.into())
+		let value: serde_json::Value =
+			serde_json::from_str(slate_json).context(ErrorKind::SlateDeser)?;
+		Ok(VersionedSlate::from_json(value)?.into())
+	}
+
+	/// Serialize the slate as an older version, for a counterpart which doesn't
+	/// read the current one. Fails rather than dropping anything the older
+	/// version can't carry
+	pub fn downgrade(&mut self, version: SlateVersion) -> Result<(), Error> {
+		if version.number() > self.version_info.version {
+			return Err(ErrorKind::Compatibility(format!(
+				"Slate {} is version {}, and can't be sent as the newer {:?}",
+				self.id, self.version_info.version, version
+			))
+			.into());
+		}
+		// Nothing in the current slate is lost in older versions yet, checks
+		// go here as fields are added
+		self.version_info.orig_version = version.number();
+		Ok(())
 	}
 
 	/// Create a new slate
@@ -229,6 +244,7 @@ impl Slate {
 			fee: 0,
 			height: 0,
 			lock_height: 0,
+			ttl_cutoff_height: None,
 			participant_data: vec![],
 			version_info: VersionCompatInfo {
 				version: CURRENT_SLATE_VERSION,
@@ -515,7 +531,7 @@ impl Slate {
 	}
 
 	/// Verifies all of the partial signatures in the Slate are valid
-	fn verify_part_sigs(&self, secp: &secp::Secp256k1) -> Result<(), Error> {
+	pub(crate) fn verify_part_sigs(&self, secp: &secp::Secp256k1) -> Result<(), Error> {
 		// collect public nonces
 		for p in self.participant_data.iter() {
 			if p.is_complete() {
@@ -720,6 +736,7 @@ impl From<Slate> for SlateV2 {
 			fee,
 			height,
 			lock_height,
+			ttl_cutoff_height,
 			participant_data,
 			version_info,
 		} = slate;
@@ -734,6 +751,7 @@ impl From<Slate> for SlateV2 {
 			fee,
 			height,
 			lock_height,
+			ttl_cutoff_height,
 			participant_data,
 			version_info,
 		}
@@ -750,6 +768,7 @@ impl From<&Slate> for SlateV2 {
 			fee,
 			height,
 			lock_height,
+			ttl_cutoff_height,
 			participant_data,
 			version_info,
 		} = slate;
@@ -760,6 +779,7 @@ impl From<&Slate> for SlateV2 {
 		let fee = *fee;
 		let height = *height;
 		let lock_height = *lock_height;
+		let ttl_cutoff_height = *ttl_cutoff_height;
 		let participant_data = map_vec!(participant_data, |data| ParticipantDataV2::from(data));
 		let version_info = VersionCompatInfoV2::from(version_info);
 		SlateV2 {
@@ -770,6 +790,7 @@ impl From<&Slate> for SlateV2 {
 			fee,
 			height,
 			lock_height,
+			ttl_cutoff_height,
 			participant_data,
 			version_info,
 		}
@@ -909,6 +930,7 @@ impl From<SlateV2> for Slate {
 			fee,
 			height,
 			lock_height,
+			ttl_cutoff_height,
 			participant_data,
 			version_info,
 		} = slate;
@@ -923,6 +945,7 @@ impl From<SlateV2> for Slate {
 			fee,
 			height,
 			lock_height,
+			ttl_cutoff_height,
 			participant_data,
 			version_info,
 		}
@@ -19,9 +19,14 @@
 
 use crate::slate::Slate;
 use crate::slate_versions::v2::SlateV2;
+use crate::{Error, ErrorKind};
+use serde_json::Value;
+use strum::IntoEnumIterator;
 
+pub mod ser;
 #[allow(missing_docs)]
 pub mod v2;
+mod validation;
 
 /// The most recent version of the slate
 pub const CURRENT_SLATE_VERSION: u16 = 2;
@@ -36,6 +41,39 @@ pub enum SlateVersion {
 	V2,
 }
 
+impl SlateVersion {
+	/// Number of the version, as given in a slate's `version_info`
+	pub fn number(&self) -> u16 {
+		match *self {
+			SlateVersion::V2 => 2,
+		}
+	}
+
+	/// Version with the given number, if this wallet reads it. Slates of any
+	/// such version are upgraded to the current one without loss
+	pub fn from_number(number: u16) -> Option<SlateVersion> {
+		SlateVersion::iter().find(|v| v.number() == number)
+	}
+}
+
+/// Newest slate version read by both this wallet and a counterpart advertising
+/// `supported` from its `check_version`, so slates sent to a wallet lagging a
+/// release can be downgraded to one it understands. Versions this wallet
+/// doesn't know, advertised by newer wallets, are skipped
+pub fn negotiate_slate_version(supported: &[String]) -> Result<SlateVersion, Error> {
+	supported
+		.iter()
+		.filter_map(|v| serde_json::from_value(Value::String(v.clone())).ok())
+		.max()
+		.ok_or_else(|| {
+			ErrorKind::Compatibility(format!(
+				"Unable to negotiate slate format with other wallet, which supports [{}]",
+				supported.join(", ")
+			))
+			.into()
+		})
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(untagged)]
 /// Versions are ordered newest to oldest so serde attempts to
@@ -53,6 +91,18 @@ impl VersionedSlate {
 		}
 	}
 
+	/// Validate and deserialize slate JSON, reporting the field at fault if
+	/// the slate doesn't match the schema of the version it declares
+	pub fn from_json(slate: Value) -> Result<VersionedSlate, Error> {
+		let version = validation::validate_slate_json(&slate)?;
+		match version {
+			2 => Ok(VersionedSlate::V2(
+				serde_json::from_value(slate).map_err(|e| validation::deser_error(version, e))?,
+			)),
+			_ => unreachable!("validation only accepts known slate versions"),
+		}
+	}
+
 	/// convert this slate type to a specified older version
 	pub fn into_version(slate: Slate, version: SlateVersion) -> VersionedSlate {
 		match version {
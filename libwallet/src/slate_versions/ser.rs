@@ -0,0 +1,78 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Serialization of slate fields not covered by `secp_ser`
+
+/// Optional u64, serialized as a string like `secp_ser::string_or_u64` and
+/// read from either a string or a number
+pub mod opt_string_or_u64 {
+	use serde::de::{self, Deserializer, Visitor};
+	use serde::Serializer;
+	use std::fmt;
+
+	/// Serialize the value as a string, or null
+	pub fn serialize<S>(value: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		match value {
+			Some(v) => serializer.serialize_some(&v.to_string()),
+			None => serializer.serialize_none(),
+		}
+	}
+
+	/// Deserialize from a string, a number or null
+	pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		struct OptU64Visitor;
+
+		impl<'de> Visitor<'de> for OptU64Visitor {
+			type Value = Option<u64>;
+
+			fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+				formatter.write_str("an unsigned integer, a string containing one, or null")
+			}
+
+			fn visit_none<E>(self) -> Result<Self::Value, E> {
+				Ok(None)
+			}
+
+			fn visit_unit<E>(self) -> Result<Self::Value, E> {
+				Ok(None)
+			}
+
+			fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+			where
+				D: Deserializer<'de>,
+			{
+				deserializer.deserialize_any(self)
+			}
+
+			fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E> {
+				Ok(Some(value))
+			}
+
+			fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+			where
+				E: de::Error,
+			{
+				value.parse().map(Some).map_err(E::custom)
+			}
+		}
+
+		deserializer.deserialize_option(OptU64Visitor)
+	}
+}
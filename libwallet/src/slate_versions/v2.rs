@@ -34,6 +34,8 @@
 //!    version: u16
 //!    orig_version: u16,
 //!    block_header_version: u16,
+//! * ttl_cutoff_height optional field added, left out when unset so slates
+//!   without one stay readable by older wallets
 
 use crate::grin_core::core::transaction::{KernelFeatures, OutputFeatures};
 use crate::grin_core::libtx::secp_ser;
@@ -42,6 +44,7 @@ use crate::grin_util::secp;
 use crate::grin_util::secp::key::PublicKey;
 use crate::grin_util::secp::pedersen::{Commitment, RangeProof};
 use crate::grin_util::secp::Signature;
+use crate::slate_versions::ser::opt_string_or_u64;
 use uuid::Uuid;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -67,6 +70,10 @@ pub struct SlateV2 {
 	/// Lock height
 	#[serde(with = "secp_ser::string_or_u64")]
 	pub lock_height: u64,
+	/// TTL, the height from which the transaction won't be built further
+	#[serde(with = "opt_string_or_u64")]
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub ttl_cutoff_height: Option<u64>,
 	/// Participant data, each participant in the transaction will
 	/// insert their public data here. For now, 0 is sender and 1
 	/// is receiver, though this will change for multi-party
@@ -0,0 +1,282 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Schema validation of incoming slate JSON, run before deserialization so
+//! that a malformed slate is reported by the field at fault rather than by
+//! serde's generic error
+
+use crate::{Error, ErrorKind};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+const COMMITMENT_SIZE: usize = 33;
+const SIGNATURE_SIZE: usize = 64;
+const BLINDING_FACTOR_SIZE: usize = 32;
+
+const OUTPUT_FEATURES: &[&str] = &["Plain", "Coinbase"];
+const KERNEL_FEATURES: &[&str] = &["Plain", "Coinbase", "HeightLocked"];
+
+/// Check the slate JSON against the schema of the version it declares,
+/// returning that version
+pub fn validate_slate_json(slate: &Value) -> Result<u16, Error> {
+	let obj = match slate.as_object() {
+		Some(o) => o,
+		None => return Err(invalid(0, "", "slate must be a JSON object")),
+	};
+	let version = slate_version(obj)?;
+	let v = Validator { version };
+	match version {
+		2 => v.slate_v2(obj)?,
+		_ => return Err(ErrorKind::SlateVersion(version))?,
+	}
+	Ok(version)
+}
+
+/// Error for a slate which passed validation but still failed to deserialize
+pub fn deser_error(version: u16, e: serde_json::Error) -> Error {
+	invalid(version, "slate", &e.to_string())
+}
+
+fn invalid(version: u16, field: &str, reason: &str) -> Error {
+	ErrorKind::SlateValidation {
+		version,
+		field: field.to_owned(),
+		reason: reason.to_owned(),
+	}
+	.into()
+}
+
+/// Determine the version a slate appears to be, as `SlateVersionProbe` does,
+/// complaining if the version info is present but malformed
+fn slate_version(obj: &Map<String, Value>) -> Result<u16, Error> {
+	match obj.get("version_info") {
+		Some(info) => {
+			let v = Validator { version: 0 };
+			let info = v.object(info, "version_info")?;
+			let version = v.u16(info, "version_info", "version")?;
+			let v = Validator { version };
+			v.u16(info, "version_info", "orig_version")?;
+			v.u16(info, "version_info", "block_header_version")?;
+			Ok(version)
+		}
+		None => match obj.get("version") {
+			Some(_) => Ok(1),
+			None => Ok(0),
+		},
+	}
+}
+
+fn path(parent: &str, name: &str) -> String {
+	if parent.is_empty() {
+		name.to_owned()
+	} else {
+		format!("{}.{}", parent, name)
+	}
+}
+
+struct Validator {
+	version: u16,
+}
+
+impl Validator {
+	fn err(&self, field: &str, reason: &str) -> Error {
+		invalid(self.version, field, reason)
+	}
+
+	fn field<'v>(
+		&self,
+		obj: &'v Map<String, Value>,
+		parent: &str,
+		name: &str,
+	) -> Result<&'v Value, Error> {
+		match obj.get(name) {
+			Some(v) => Ok(v),
+			None => Err(self.err(&path(parent, name), "is missing")),
+		}
+	}
+
+	fn object<'v>(&self, value: &'v Value, field: &str) -> Result<&'v Map<String, Value>, Error> {
+		value
+			.as_object()
+			.ok_or_else(|| self.err(field, "must be an object"))
+	}
+
+	fn array<'v>(
+		&self,
+		obj: &'v Map<String, Value>,
+		parent: &str,
+		name: &str,
+	) -> Result<&'v Vec<Value>, Error> {
+		self.field(obj, parent, name)?
+			.as_array()
+			.ok_or_else(|| self.err(&path(parent, name), "must be an array"))
+	}
+
+	fn u16(&self, obj: &Map<String, Value>, parent: &str, name: &str) -> Result<u16, Error> {
+		match self.field(obj, parent, name)?.as_u64() {
+			Some(n) if n <= u16::max_value() as u64 => Ok(n as u16),
+			_ => Err(self.err(&path(parent, name), "must be an integer from 0 to 65535")),
+		}
+	}
+
+	/// Fields serialized with `secp_ser::string_or_u64`
+	fn string_or_u64(
+		&self,
+		obj: &Map<String, Value>,
+		parent: &str,
+		name: &str,
+	) -> Result<(), Error> {
+		let valid = match self.field(obj, parent, name)? {
+			Value::Number(n) => n.is_u64(),
+			Value::String(s) => s.parse::<u64>().is_ok(),
+			_ => false,
+		};
+		if !valid {
+			return Err(self.err(
+				&path(parent, name),
+				"must be an unsigned integer or a string containing one",
+			));
+		}
+		Ok(())
+	}
+
+	fn optional_string_or_u64(
+		&self,
+		obj: &Map<String, Value>,
+		parent: &str,
+		name: &str,
+	) -> Result<(), Error> {
+		match obj.get(name) {
+			None | Some(Value::Null) => Ok(()),
+			Some(_) => self.string_or_u64(obj, parent, name),
+		}
+	}
+
+	/// Hex encoded bytes, of exactly `size` bytes if given
+	fn hex(
+		&self,
+		obj: &Map<String, Value>,
+		parent: &str,
+		name: &str,
+		size: Option<usize>,
+	) -> Result<(), Error> {
+		let field = path(parent, name);
+		let s = match self.field(obj, parent, name)?.as_str() {
+			Some(s) => s,
+			None => return Err(self.err(&field, "must be a hex string")),
+		};
+		if s.is_empty() || s.len() % 2 != 0 || !s.chars().all(|c| c.is_ascii_hexdigit()) {
+			return Err(self.err(&field, "must be a hex string"));
+		}
+		if let Some(size) = size {
+			if s.len() != size * 2 {
+				return Err(self.err(
+					&field,
+					&format!("must be {} bytes ({} hex characters)", size, size * 2),
+				));
+			}
+		}
+		Ok(())
+	}
+
+	fn optional_hex(
+		&self,
+		obj: &Map<String, Value>,
+		parent: &str,
+		name: &str,
+		size: Option<usize>,
+	) -> Result<(), Error> {
+		match obj.get(name) {
+			None | Some(Value::Null) => Ok(()),
+			Some(_) => self.hex(obj, parent, name, size),
+		}
+	}
+
+	fn one_of(
+		&self,
+		obj: &Map<String, Value>,
+		parent: &str,
+		name: &str,
+		allowed: &[&str],
+	) -> Result<(), Error> {
+		match self.field(obj, parent, name)?.as_str() {
+			Some(s) if allowed.contains(&s) => Ok(()),
+			_ => Err(self.err(
+				&path(parent, name),
+				&format!("must be one of {}", allowed.join(", ")),
+			)),
+		}
+	}
+
+	fn slate_v2(&self, obj: &Map<String, Value>) -> Result<(), Error> {
+		match self.field(obj, "", "num_participants")?.as_u64() {
+			Some(_) => {}
+			None => return Err(self.err("num_participants", "must be an unsigned integer")),
+		}
+		match self.field(obj, "", "id")?.as_str().map(Uuid::parse_str) {
+			Some(Ok(_)) => {}
+			_ => return Err(self.err("id", "must be a UUID string")),
+		}
+		for name in &["amount", "fee", "height", "lock_height"] {
+			self.string_or_u64(obj, "", name)?;
+		}
+		self.optional_string_or_u64(obj, "", "ttl_cutoff_height")?;
+		self.transaction_v2(self.object(self.field(obj, "", "tx")?, "tx")?)?;
+		for (i, p) in self.array(obj, "", "participant_data")?.iter().enumerate() {
+			let parent = format!("participant_data[{}]", i);
+			self.participant_v2(self.object(p, &parent)?, &parent)?;
+		}
+		Ok(())
+	}
+
+	fn transaction_v2(&self, tx: &Map<String, Value>) -> Result<(), Error> {
+		self.hex(tx, "tx", "offset", Some(BLINDING_FACTOR_SIZE))?;
+		let body = self.object(self.field(tx, "tx", "body")?, "tx.body")?;
+		for (i, input) in self.array(body, "tx.body", "inputs")?.iter().enumerate() {
+			let parent = format!("tx.body.inputs[{}]", i);
+			let input = self.object(input, &parent)?;
+			self.one_of(input, &parent, "features", OUTPUT_FEATURES)?;
+			self.hex(input, &parent, "commit", Some(COMMITMENT_SIZE))?;
+		}
+		for (i, output) in self.array(body, "tx.body", "outputs")?.iter().enumerate() {
+			let parent = format!("tx.body.outputs[{}]", i);
+			let output = self.object(output, &parent)?;
+			self.one_of(output, &parent, "features", OUTPUT_FEATURES)?;
+			self.hex(output, &parent, "commit", Some(COMMITMENT_SIZE))?;
+			self.hex(output, &parent, "proof", None)?;
+		}
+		for (i, kernel) in self.array(body, "tx.body", "kernels")?.iter().enumerate() {
+			let parent = format!("tx.body.kernels[{}]", i);
+			let kernel = self.object(kernel, &parent)?;
+			self.one_of(kernel, &parent, "features", KERNEL_FEATURES)?;
+			self.string_or_u64(kernel, &parent, "fee")?;
+			self.string_or_u64(kernel, &parent, "lock_height")?;
+			self.hex(kernel, &parent, "excess", Some(COMMITMENT_SIZE))?;
+			self.hex(kernel, &parent, "excess_sig", Some(SIGNATURE_SIZE))?;
+		}
+		Ok(())
+	}
+
+	fn participant_v2(&self, p: &Map<String, Value>, parent: &str) -> Result<(), Error> {
+		self.string_or_u64(p, parent, "id")?;
+		self.hex(p, parent, "public_blind_excess", None)?;
+		self.hex(p, parent, "public_nonce", None)?;
+		self.optional_hex(p, parent, "part_sig", Some(SIGNATURE_SIZE))?;
+		self.optional_hex(p, parent, "message_sig", Some(SIGNATURE_SIZE))?;
+		match p.get("message") {
+			None | Some(Value::Null) | Some(Value::String(_)) => Ok(()),
+			Some(_) => Err(self.err(&path(parent, "message"), "must be a string or null")),
+		}
+	}
+}
@@ -0,0 +1,520 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! SLIP-39 Shamir secret sharing, splitting a secret such as the wallet seed
+//! into mnemonic shares any threshold of which recover it, as specified in
+//! https://github.com/satoshilabs/slips/blob/master/slip-0039.md
+//!
+//! Shares are created in a single group. Shares in several groups, as
+//! created by other implementations, can be combined
+
+mod wordlist;
+
+use self::wordlist::WORDS;
+use crate::grin_util::ZeroingString;
+use crate::{Error, ErrorKind};
+use hmac::{Hmac, Mac};
+use pbkdf2::pbkdf2;
+use rand::{thread_rng, Rng};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::iter;
+
+/// Maximum number of shares in a group
+pub const MAX_SHARE_COUNT: u8 = 16;
+
+const RADIX_BITS: usize = 10;
+const CUSTOMIZATION_STRING: &[u8] = b"shamir";
+const CUSTOMIZATION_STRING_EXTENDABLE: &[u8] = b"shamir_extendable";
+// identifier, flags and group and member parameters
+const METADATA_WORDS: usize = 4;
+const CHECKSUM_WORDS: usize = 3;
+const MIN_SECRET_BYTES: usize = 16;
+const DIGEST_LENGTH: usize = 4;
+const DIGEST_INDEX: u8 = 254;
+const SECRET_INDEX: u8 = 255;
+const ROUND_COUNT: u8 = 4;
+const BASE_ITERATION_COUNT: usize = 10000;
+// exponent of the PBKDF2 iteration count for new shares
+const ITERATION_EXPONENT: u8 = 0;
+
+const RS1024_GEN: [u32; 10] = [
+	0xe0e040, 0x1c1c080, 0x3838100, 0x7070200, 0xe0e0009, 0x1c0c2412, 0x38086c24, 0x3090fc48,
+	0x21b1f890, 0x3f3f120,
+];
+
+lazy_static! {
+	// exponent and logarithm tables of GF(256) with generator 3
+	static ref GF256: ([u8; 255], [u8; 256]) = {
+		let mut exp = [0u8; 255];
+		let mut log = [0u8; 256];
+		let mut poly: u16 = 1;
+		for (i, e) in exp.iter_mut().enumerate() {
+			*e = poly as u8;
+			log[poly as usize] = i as u8;
+			poly = (poly << 1) ^ poly;
+			if poly & 0x100 != 0 {
+				poly ^= 0x11b;
+			}
+		}
+		(exp, log)
+	};
+}
+
+fn share_error(msg: &str) -> Error {
+	ErrorKind::Slip39(msg.to_owned()).into()
+}
+
+fn gf_mul(a: u8, b: u8) -> u8 {
+	if a == 0 || b == 0 {
+		return 0;
+	}
+	let (exp, log) = &*GF256;
+	exp[(log[a as usize] as usize + log[b as usize] as usize) % 255]
+}
+
+fn gf_div(a: u8, b: u8) -> u8 {
+	if a == 0 {
+		return 0;
+	}
+	let (exp, log) = &*GF256;
+	exp[(log[a as usize] as usize + 255 - log[b as usize] as usize) % 255]
+}
+
+/// Evaluate at `x` the polynomial passing through the given points, each
+/// byte of the values being interpolated separately
+fn interpolate(points: &[(u8, Vec<u8>)], x: u8) -> Vec<u8> {
+	if let Some((_, v)) = points.iter().find(|(px, _)| *px == x) {
+		return v.clone();
+	}
+	let mut result = vec![0u8; points[0].1.len()];
+	for (i, (xi, yi)) in points.iter().enumerate() {
+		let mut basis = 1u8;
+		for (j, (xj, _)) in points.iter().enumerate() {
+			if i != j {
+				basis = gf_mul(basis, gf_div(x ^ xj, xi ^ xj));
+			}
+		}
+		for (r, y) in result.iter_mut().zip(yi) {
+			*r ^= gf_mul(*y, basis);
+		}
+	}
+	result
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+	let mut mac = Hmac::<Sha256>::new_varkey(key).expect("HMAC accepts keys of any length");
+	mac.input(data);
+	mac.result().code().to_vec()
+}
+
+fn split_secret(threshold: u8, count: u8, secret: &[u8]) -> Vec<(u8, Vec<u8>)> {
+	if threshold == 1 {
+		return (0..count).map(|i| (i, secret.to_vec())).collect();
+	}
+	let mut rng = thread_rng();
+	let mut random_part = vec![0u8; secret.len() - DIGEST_LENGTH];
+	rng.fill(&mut random_part[..]);
+	let mut digest = hmac_sha256(&random_part, secret)[..DIGEST_LENGTH].to_vec();
+	digest.extend(random_part);
+
+	let mut shares: Vec<(u8, Vec<u8>)> = (0..threshold - 2)
+		.map(|i| {
+			let mut v = vec![0u8; secret.len()];
+			rng.fill(&mut v[..]);
+			(i, v)
+		})
+		.collect();
+	let mut base = shares.clone();
+	base.push((DIGEST_INDEX, digest));
+	base.push((SECRET_INDEX, secret.to_vec()));
+	for i in threshold - 2..count {
+		shares.push((i, interpolate(&base, i)));
+	}
+	shares
+}
+
+fn recover_secret(threshold: u8, shares: &[(u8, Vec<u8>)]) -> Result<Vec<u8>, Error> {
+	if threshold == 1 {
+		return Ok(shares[0].1.clone());
+	}
+	let secret = interpolate(shares, SECRET_INDEX);
+	let digest = interpolate(shares, DIGEST_INDEX);
+	if hmac_sha256(&digest[DIGEST_LENGTH..], &secret)[..DIGEST_LENGTH] != digest[..DIGEST_LENGTH] {
+		return Err(share_error("Invalid digest of the shared secret"));
+	}
+	Ok(secret)
+}
+
+/// Encrypt or decrypt the master secret with a 4 round Feistel network,
+/// running the rounds in the given order
+fn feistel(
+	input: &[u8],
+	passphrase: &[u8],
+	iteration_exponent: u8,
+	salt: &[u8],
+	rounds: &mut dyn Iterator<Item = u8>,
+) -> Vec<u8> {
+	let half = input.len() / 2;
+	let mut l = input[..half].to_vec();
+	let mut r = input[half..].to_vec();
+	let iterations = (BASE_ITERATION_COUNT << iteration_exponent) / ROUND_COUNT as usize;
+	for i in rounds {
+		let mut password = vec![i];
+		password.extend_from_slice(passphrase);
+		let mut round_salt = salt.to_vec();
+		round_salt.extend_from_slice(&r);
+		let mut f = vec![0u8; r.len()];
+		pbkdf2::<Hmac<Sha256>>(&password, &round_salt, iterations, &mut f);
+		let next: Vec<u8> = l.iter().zip(f).map(|(a, b)| a ^ b).collect();
+		l = r;
+		r = next;
+	}
+	r.extend(l);
+	r
+}
+
+/// A single mnemonic share
+#[derive(Clone, Debug, PartialEq)]
+struct Share {
+	identifier: u16,
+	extendable: bool,
+	iteration_exponent: u8,
+	group_index: u8,
+	group_threshold: u8,
+	group_count: u8,
+	member_index: u8,
+	member_threshold: u8,
+	value: Vec<u8>,
+}
+
+impl Share {
+	fn customization(extendable: bool) -> &'static [u8] {
+		match extendable {
+			true => CUSTOMIZATION_STRING_EXTENDABLE,
+			false => CUSTOMIZATION_STRING,
+		}
+	}
+
+	fn salt(&self) -> Vec<u8> {
+		if self.extendable {
+			return vec![];
+		}
+		let mut salt = CUSTOMIZATION_STRING.to_vec();
+		salt.extend(&[(self.identifier >> 8) as u8, self.identifier as u8]);
+		salt
+	}
+
+	fn polymod(customization: &[u8], data: &[u32]) -> u32 {
+		let mut chk: u32 = 1;
+		let values = customization
+			.iter()
+			.map(|c| *c as u32)
+			.chain(data.iter().cloned());
+		for v in values {
+			let b = chk >> 20;
+			chk = ((chk & 0xfffff) << 10) ^ v;
+			for (i, g) in RS1024_GEN.iter().enumerate() {
+				if (b >> i) & 1 == 1 {
+					chk ^= g;
+				}
+			}
+		}
+		chk
+	}
+
+	fn to_mnemonic(&self) -> ZeroingString {
+		let id_exp = (self.identifier as u32) << 5
+			| (self.extendable as u32) << 4
+			| self.iteration_exponent as u32;
+		let params = (self.group_index as u32) << 16
+			| (self.group_threshold as u32 - 1) << 12
+			| (self.group_count as u32 - 1) << 8
+			| (self.member_index as u32) << 4
+			| (self.member_threshold as u32 - 1);
+		let mut data = vec![id_exp >> 10, id_exp & 1023, params >> 10, params & 1023];
+
+		// the value is left padded with zeros to a whole number of words
+		let value_words = (self.value.len() * 8 + RADIX_BITS - 1) / RADIX_BITS;
+		let padding = value_words * RADIX_BITS - self.value.len() * 8;
+		let bits: Vec<u32> = iter::repeat(0)
+			.take(padding)
+			.chain(
+				self.value
+					.iter()
+					.flat_map(|b| (0..8).rev().map(move |i| (*b as u32 >> i) & 1)),
+			)
+			.collect();
+		data.extend(
+			bits.chunks(RADIX_BITS)
+				.map(|c| c.iter().fold(0, |acc, b| acc << 1 | b)),
+		);
+
+		let mut checksummed = data.clone();
+		checksummed.extend(&[0; CHECKSUM_WORDS]);
+		let chk = Share::polymod(Share::customization(self.extendable), &checksummed) ^ 1;
+		data.extend(
+			(0..CHECKSUM_WORDS)
+				.rev()
+				.map(|i| chk >> (i * RADIX_BITS) & 1023),
+		);
+
+		let words: Vec<&str> = data.iter().map(|d| WORDS[*d as usize]).collect();
+		ZeroingString::from(words.join(" "))
+	}
+
+	fn from_mnemonic(mnemonic: &str) -> Result<Share, Error> {
+		let min_words = METADATA_WORDS + CHECKSUM_WORDS + (MIN_SECRET_BYTES * 8 + 9) / RADIX_BITS;
+		let mut data = vec![];
+		for w in mnemonic.split_whitespace() {
+			match WORDS.binary_search(&w.to_lowercase().as_str()) {
+				Ok(i) => data.push(i as u32),
+				Err(_) => return Err(share_error(&format!("Unknown word '{}'", w))),
+			}
+		}
+		if data.len() < min_words {
+			return Err(share_error(&format!(
+				"Shares must have at least {} words",
+				min_words
+			)));
+		}
+
+		let id_exp = data[0] << 10 | data[1];
+		let extendable = (id_exp >> 4) & 1 == 1;
+		if Share::polymod(Share::customization(extendable), &data) != 1 {
+			return Err(share_error("Invalid checksum, please check the words"));
+		}
+		let params = data[2] << 10 | data[3];
+		let group_threshold = ((params >> 12) & 15) as u8 + 1;
+		let group_count = ((params >> 8) & 15) as u8 + 1;
+		if group_threshold > group_count {
+			return Err(share_error("Group threshold exceeds the number of groups"));
+		}
+
+		let value_data = &data[METADATA_WORDS..data.len() - CHECKSUM_WORDS];
+		let padding = value_data.len() * RADIX_BITS % 16;
+		if padding > 8 {
+			return Err(share_error("Invalid share length"));
+		}
+		let bits: Vec<u8> = value_data
+			.iter()
+			.flat_map(|w| (0..RADIX_BITS).rev().map(move |i| ((w >> i) & 1) as u8))
+			.collect();
+		if bits[..padding].iter().any(|b| *b != 0) {
+			return Err(share_error("Invalid padding"));
+		}
+		let value = bits[padding..]
+			.chunks(8)
+			.map(|c| c.iter().fold(0, |acc, b| acc << 1 | b))
+			.collect();
+
+		Ok(Share {
+			identifier: (id_exp >> 5) as u16,
+			extendable,
+			iteration_exponent: (id_exp & 15) as u8,
+			group_index: (params >> 16) as u8,
+			group_threshold,
+			group_count,
+			member_index: ((params >> 4) & 15) as u8,
+			member_threshold: (params & 15) as u8 + 1,
+			value,
+		})
+	}
+}
+
+/// Split the secret into `count` mnemonic shares, any `threshold` of which
+/// recover it. The secret is encrypted with the passphrase, which must then
+/// be given again to recover it
+pub fn split(
+	secret: &[u8],
+	threshold: u8,
+	count: u8,
+	passphrase: &[u8],
+) -> Result<Vec<ZeroingString>, Error> {
+	if secret.len() < MIN_SECRET_BYTES || secret.len() % 2 != 0 {
+		return Err(share_error(&format!(
+			"The secret must be an even number of bytes, at least {}",
+			MIN_SECRET_BYTES
+		)));
+	}
+	if threshold == 0 || threshold > count || count > MAX_SHARE_COUNT {
+		return Err(share_error(&format!(
+			"The threshold must be between 1 and the number of shares, which may be at most {}",
+			MAX_SHARE_COUNT
+		)));
+	}
+	if threshold == 1 && count > 1 {
+		return Err(share_error(
+			"Several shares with a threshold of 1 would all be copies of the secret",
+		));
+	}
+
+	let identifier = thread_rng().gen::<u16>() & 0x7fff;
+	let mut share = Share {
+		identifier,
+		extendable: false,
+		iteration_exponent: ITERATION_EXPONENT,
+		group_index: 0,
+		group_threshold: 1,
+		group_count: 1,
+		member_index: 0,
+		member_threshold: threshold,
+		value: vec![],
+	};
+	let encrypted = feistel(
+		secret,
+		passphrase,
+		ITERATION_EXPONENT,
+		&share.salt(),
+		&mut (0..ROUND_COUNT),
+	);
+	Ok(split_secret(threshold, count, &encrypted)
+		.into_iter()
+		.map(|(i, value)| {
+			share.member_index = i;
+			share.value = value;
+			share.to_mnemonic()
+		})
+		.collect())
+}
+
+/// Number of shares needed to recover the secret the given share belongs
+/// to, if it was split in a single group
+pub fn threshold(mnemonic: &str) -> Result<u8, Error> {
+	let share = Share::from_mnemonic(mnemonic)?;
+	match share.group_count {
+		1 => Ok(share.member_threshold),
+		_ => Err(share_error(
+			"Shares in several groups aren't supported here",
+		)),
+	}
+}
+
+/// Recover the secret from a quorum of its mnemonic shares, decrypting it
+/// with the passphrase given when it was split
+pub fn combine(mnemonics: &[ZeroingString], passphrase: &[u8]) -> Result<Vec<u8>, Error> {
+	let shares = mnemonics
+		.iter()
+		.map(|m| Share::from_mnemonic(m))
+		.collect::<Result<Vec<_>, _>>()?;
+	let first = match shares.first() {
+		Some(s) => s.clone(),
+		None => return Err(share_error("No shares given")),
+	};
+	let mut groups: BTreeMap<u8, Vec<Share>> = BTreeMap::new();
+	for s in shares {
+		if s.identifier != first.identifier
+			|| s.extendable != first.extendable
+			|| s.iteration_exponent != first.iteration_exponent
+			|| s.group_threshold != first.group_threshold
+			|| s.group_count != first.group_count
+			|| s.value.len() != first.value.len()
+		{
+			return Err(share_error(
+				"The shares don't all belong to the same secret",
+			));
+		}
+		let group = groups.entry(s.group_index).or_insert(vec![]);
+		if let Some(g) = group.first() {
+			if g.member_threshold != s.member_threshold {
+				return Err(share_error(
+					"The shares don't all belong to the same secret",
+				));
+			}
+		}
+		match group.iter().find(|g| g.member_index == s.member_index) {
+			Some(g) if g.value != s.value => {
+				return Err(share_error("Two different shares have the same index"));
+			}
+			Some(_) => {}
+			None => group.push(s),
+		}
+	}
+
+	let mut group_shares = vec![];
+	for (index, members) in groups.iter() {
+		let threshold = members[0].member_threshold;
+		if members.len() < threshold as usize {
+			if first.group_count == 1 {
+				return Err(share_error(&format!(
+					"{} shares are needed, only {} given",
+					threshold,
+					members.len()
+				)));
+			}
+			continue;
+		}
+		let points: Vec<(u8, Vec<u8>)> = members
+			.iter()
+			.take(threshold as usize)
+			.map(|m| (m.member_index, m.value.clone()))
+			.collect();
+		group_shares.push((*index, recover_secret(threshold, &points)?));
+	}
+	if group_shares.len() < first.group_threshold as usize {
+		return Err(share_error(&format!(
+			"Shares from {} complete groups are needed, only {} given",
+			first.group_threshold,
+			group_shares.len()
+		)));
+	}
+	group_shares.truncate(first.group_threshold as usize);
+	let encrypted = recover_secret(first.group_threshold, &group_shares)?;
+	Ok(feistel(
+		&encrypted,
+		passphrase,
+		first.iteration_exponent,
+		&first.salt(),
+		&mut (0..ROUND_COUNT).rev(),
+	))
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::grin_util::{from_hex, to_hex};
+
+	#[test]
+	fn slip39_test_vector() {
+		let share = ZeroingString::from(
+			"duckling enlarge academic academic agency result length solution fridge kidney \
+			 coal piece deal husband erode duke ajar critical decision keyboard",
+		);
+		let secret = combine(std::slice::from_ref(&share), b"TREZOR").unwrap();
+		assert_eq!(to_hex(secret), "bb54aac4b89dc868ba37d9cc21b2cece");
+
+		let altered = share.replace("keyboard", "kernel");
+		assert!(combine(&[ZeroingString::from(altered)], b"TREZOR").is_err());
+	}
+
+	#[test]
+	fn slip39_split_combine() {
+		let secret =
+			from_hex("5ec1cbd7e5dc2f5d3ef3ba2b18fb3e8aee7d7f6e2e1a1e5c3b7f4c3ae2d5c4b1".to_owned())
+				.unwrap();
+		let shares = split(&secret, 3, 5, b"").unwrap();
+		assert_eq!(shares.len(), 5);
+		assert_eq!(shares[0].split_whitespace().count(), 33);
+		assert_eq!(threshold(&shares[2]).unwrap(), 3);
+
+		for subset in &[[0, 1, 2], [4, 2, 0], [1, 3, 4]] {
+			let s: Vec<ZeroingString> = subset.iter().map(|i| shares[*i].clone()).collect();
+			assert_eq!(combine(&s, b"").unwrap(), secret);
+		}
+		assert!(combine(&shares[..2], b"").is_err());
+		assert_ne!(combine(&shares[..3], b"other").unwrap(), secret);
+		assert!(split(&secret, 1, 3, b"").is_err());
+		assert!(split(&secret, 4, 3, b"").is_err());
+	}
+}
@@ -0,0 +1,145 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Process-wide counters of what the wallet did since it started, for
+//! operators of many wallets to aggregate without parsing transaction logs
+
+use chrono::prelude::{DateTime, Utc};
+use std::collections::BTreeMap;
+
+use crate::grin_core::libtx::secp_ser;
+use crate::grin_util::Mutex;
+
+/// What the wallet did since it started
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WalletStatistics {
+	/// Time at which counting started
+	pub started: DateTime<Utc>,
+	/// Sends finalized, including invoices paid
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub txs_sent: u64,
+	/// Slates received, including invoices finalized
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub txs_received: u64,
+	/// Total amount sent, in nanogrin
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub amount_sent: u64,
+	/// Total amount received, in nanogrin
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub amount_received: u64,
+	/// Total fees paid on sends, in nanogrin
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub fees_paid: u64,
+	/// Transactions seen confirmed on chain
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub txs_confirmed: u64,
+	/// Average seconds between a transaction's creation and its confirmation
+	/// being seen, if any was confirmed
+	pub avg_confirmation_secs: Option<f64>,
+	/// Errors returned through the API listeners, by kind
+	pub errors: BTreeMap<String, u64>,
+}
+
+impl WalletStatistics {
+	fn new() -> WalletStatistics {
+		WalletStatistics {
+			started: Utc::now(),
+			txs_sent: 0,
+			txs_received: 0,
+			amount_sent: 0,
+			amount_received: 0,
+			fees_paid: 0,
+			txs_confirmed: 0,
+			avg_confirmation_secs: None,
+			errors: BTreeMap::new(),
+		}
+	}
+}
+
+lazy_static! {
+	/// Counters for this process, along with the total confirmation time the
+	/// average is taken over
+	static ref STATISTICS: Mutex<(WalletStatistics, u64)> =
+		Mutex::new((WalletStatistics::new(), 0));
+}
+
+/// What the wallet did since this process started
+pub fn statistics() -> WalletStatistics {
+	STATISTICS.lock().0.clone()
+}
+
+/// Count an error of the given kind returned through an API listener
+pub fn record_error(kind: &str) {
+	*STATISTICS
+		.lock()
+		.0
+		.errors
+		.entry(kind.to_owned())
+		.or_insert(0) += 1;
+}
+
+pub(crate) fn record_sent(amount: u64, fee: u64) {
+	let stats = &mut STATISTICS.lock().0;
+	stats.txs_sent += 1;
+	stats.amount_sent = stats.amount_sent.saturating_add(amount);
+	stats.fees_paid = stats.fees_paid.saturating_add(fee);
+}
+
+pub(crate) fn record_received(amount: u64) {
+	let stats = &mut STATISTICS.lock().0;
+	stats.txs_received += 1;
+	stats.amount_received = stats.amount_received.saturating_add(amount);
+}
+
+pub(crate) fn record_confirmed(created: DateTime<Utc>, confirmed: DateTime<Utc>) {
+	let (stats, total_secs) = &mut *STATISTICS.lock();
+	let secs = confirmed
+		.signed_duration_since(created)
+		.num_seconds()
+		.max(0) as u64;
+	*total_secs = total_secs.saturating_add(secs);
+	stats.txs_confirmed += 1;
+	stats.avg_confirmation_secs = Some(*total_secs as f64 / stats.txs_confirmed as f64);
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use chrono::Duration;
+
+	#[test]
+	fn statistics_counters() {
+		let before = statistics();
+		record_sent(10, 2);
+		record_received(7);
+		record_error("NotEnoughFunds");
+		let created = Utc::now();
+		record_confirmed(created, created + Duration::seconds(60));
+		record_confirmed(created, created + Duration::seconds(120));
+
+		let after = statistics();
+		assert_eq!(after.started, before.started);
+		assert_eq!(after.txs_sent, before.txs_sent + 1);
+		assert_eq!(after.amount_sent, before.amount_sent + 10);
+		assert_eq!(after.fees_paid, before.fees_paid + 2);
+		assert_eq!(after.txs_received, before.txs_received + 1);
+		assert_eq!(after.amount_received, before.amount_received + 7);
+		assert_eq!(after.txs_confirmed, before.txs_confirmed + 2);
+		assert!(after.avg_confirmation_secs.is_some());
+		assert_eq!(
+			after.errors.get("NotEnoughFunds"),
+			Some(&(before.errors.get("NotEnoughFunds").unwrap_or(&0) + 1))
+		);
+	}
+}
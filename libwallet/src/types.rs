@@ -15,22 +15,31 @@
 //! Types and traits that should be provided by a wallet
 //! implementation
 
+use crate::blake2::blake2b::blake2b;
+use crate::derivation::DerivationScheme;
 use crate::error::{Error, ErrorKind};
 use crate::grin_core::core::hash::Hash;
-use crate::grin_core::core::Transaction;
+use crate::grin_core::core::{Transaction, TxKernel};
 use crate::grin_core::libtx::{aggsig, secp_ser};
 use crate::grin_core::{global, ser};
-use crate::grin_keychain::{Identifier, Keychain};
+use crate::grin_keychain::{Identifier, Keychain, SwitchCommitmentType};
 use crate::grin_util::secp::key::{PublicKey, SecretKey};
 use crate::grin_util::secp::{self, pedersen, Secp256k1};
-use crate::grin_util::ZeroingString;
-use crate::slate::ParticipantMessages;
+use crate::grin_util::{to_hex, ZeroingString};
+use crate::internal::backup::{BackupState, WalletBackup};
+use crate::internal::selection::{self, CoinSelection};
+use crate::operation::{CancelToken, OperationStatus};
+use crate::policy::{ReceiveCount, SpendingPolicy};
+use crate::slate::{ParticipantMessages, Slate};
+use crate::totp::TotpSecret;
+use crate::{ChainAuditReport, WALLET_VERSION};
 use chrono::prelude::*;
 use failure::ResultExt;
 use serde;
 use serde_json;
 use std::collections::HashMap;
 use std::fmt;
+use std::sync::Arc;
 use uuid::Uuid;
 
 /// Combined trait to allow dynamic wallet dispatch
@@ -54,6 +63,26 @@ where
 	/// default is assumed to be ~/.grin/main/wallet_data (or floonet equivalent)
 	fn set_wallet_directory(&mut self, dir: &str);
 
+	/// Set a BIP39 passphrase to mix into the derivation of the keychain
+	/// from the seed when a wallet is next opened. The passphrase isn't
+	/// stored, so must be set each time a wallet using one is opened. With no
+	/// passphrase the keychain is derived as it always has been
+	fn set_seed_passphrase(&mut self, passphrase: Option<ZeroingString>);
+
+	/// Set the scheme keychains, and the paths of accounts and their output
+	/// keys, are derived from wallet seeds with when a wallet is next created,
+	/// opened or recovered. Wallets created with a scheme must be opened with
+	/// the same one
+	fn set_derivation_scheme(&mut self, scheme: Arc<dyn DerivationScheme>);
+
+	/// Set the spending policy sends are held to, for the open wallet and
+	/// those opened later. Each wallet instance holds its own policy
+	fn set_spending_policy(&mut self, policy: SpendingPolicy);
+
+	/// Return a copy of the node client wallets are opened with, usable
+	/// whether or not a wallet is open
+	fn node_client(&self) -> C;
+
 	/// Output a grin-wallet.toml file into the current top-level system wallet directory
 	fn create_config(&self, chain_type: &global::ChainTypes, file_name: &str) -> Result<(), Error>;
 
@@ -75,9 +104,22 @@ where
 		use_test_rng: bool,
 	) -> Result<Option<SecretKey>, Error>;
 
+	/// Open the wallet's store without its seed, as a watch-only wallet. It can
+	/// follow its outputs against the chain through their cached commitments,
+	/// and prepare sends for a wallet holding the seed to sign offline, but
+	/// can't derive keys or sign
+	fn open_watch_only(&mut self, name: Option<&str>) -> Result<(), Error>;
+
 	///
 	fn close_wallet(&mut self, name: Option<&str>) -> Result<(), Error>;
 
+	/// Suspend the open wallet, releasing its store and file locks while
+	/// keeping the keychain in memory. Does nothing if no wallet is open
+	fn suspend_wallet(&mut self) -> Result<(), Error>;
+
+	/// Resume a wallet suspended with `suspend_wallet`
+	fn resume_wallet(&mut self) -> Result<(), Error>;
+
 	/// whether a wallet exists at the given directory
 	fn wallet_exists(&self, name: Option<&str>) -> Result<bool, Error>;
 
@@ -99,8 +141,35 @@ where
 		password: ZeroingString,
 	) -> Result<(), Error>;
 
-	/// changes password
-	fn change_password(&self, old: String, new: String) -> Result<(), Error>;
+	/// Split the seed of the given wallet into `count` SLIP-39 mnemonic
+	/// shares, any `threshold` of which recover it
+	fn get_seed_shares(
+		&self,
+		name: Option<&str>,
+		password: ZeroingString,
+		threshold: u8,
+		count: u8,
+	) -> Result<Vec<ZeroingString>, Error>;
+
+	/// Recover a seed from a quorum of SLIP-39 shares, without destroying
+	/// existing data, as with `recover_from_mnemonic`
+	fn recover_from_seed_shares(
+		&self,
+		shares: Vec<ZeroingString>,
+		password: ZeroingString,
+	) -> Result<(), Error>;
+
+	/// Restore a wallet into the wallet directory, which mustn't hold a
+	/// wallet yet, from a [`BackupArchive`](struct.BackupArchive.html)
+	/// serialized as JSON, whose seed is encrypted with the given password.
+	/// The wallet's records are restored as of the latest backup in the
+	/// archive, then checked against the chain
+	fn restore_from_backup(&mut self, archive: &str, password: ZeroingString) -> Result<(), Error>;
+
+	/// Change the password the wallet's seed is encrypted with, given the
+	/// current one. The seed is the only data encrypted with the password,
+	/// and is replaced in one step so it can't be left unreadable
+	fn change_password(&self, old: ZeroingString, new: ZeroingString) -> Result<(), Error>;
 
 	/// deletes wallet
 	fn delete_wallet(&self, name: Option<String>, password: String) -> Result<(), Error>;
@@ -131,6 +200,13 @@ where
 	/// Close wallet and remove any stored credentials (TBD)
 	fn close(&mut self) -> Result<(), Error>;
 
+	/// Flush and release the underlying store, along with any file locks it
+	/// holds. Calls needing the store fail until `resume` is called
+	fn suspend(&mut self) -> Result<(), Error>;
+
+	/// Reopen the store released by `suspend`
+	fn resume(&mut self) -> Result<(), Error>;
+
 	/// Return the keychain being used. Ensure a cloned copy so it will be dropped
 	/// and zeroized by the caller
 	/// Can optionally take a mask value
@@ -157,8 +233,23 @@ where
 	/// return the parent path
 	fn parent_key_id(&mut self) -> Identifier;
 
-	/// Iterate over all output data stored by the backend
-	fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = OutputData> + 'a>;
+	/// Set the spending policy sends from this wallet are held to
+	fn set_spending_policy(&mut self, policy: SpendingPolicy);
+
+	/// The spending policy sends from this wallet are held to
+	fn spending_policy(&self) -> SpendingPolicy;
+
+	/// Set the scheme the wallet's account and output key paths are derived
+	/// with, moving the default account, and the parent key if it's the
+	/// default account, to the scheme's default account path
+	fn set_derivation_scheme(&mut self, scheme: Arc<dyn DerivationScheme>) -> Result<(), Error>;
+
+	/// The scheme the wallet's account and output key paths are derived with
+	fn derivation_scheme(&self) -> Arc<dyn DerivationScheme>;
+
+	/// Iterate over all output data stored by the backend. Fails while the
+	/// wallet is suspended, as do the other iterators
+	fn iter<'a>(&'a self) -> Result<Box<dyn Iterator<Item = OutputData> + 'a>, Error>;
 
 	/// Get output data by id
 	fn get(&self, id: &Identifier, mmr_index: &Option<u64>) -> Result<OutputData, Error>;
@@ -175,10 +266,34 @@ where
 	) -> Result<Context, Error>;
 
 	/// Iterate over all output data stored by the backend
-	fn tx_log_iter<'a>(&'a self) -> Box<dyn Iterator<Item = TxLogEntry> + 'a>;
+	fn tx_log_iter<'a>(&'a self) -> Result<Box<dyn Iterator<Item = TxLogEntry> + 'a>, Error>;
+
+	/// Iterate over the transaction log entries of the account with the given
+	/// parent key, newest first
+	fn tx_log_iter_rev<'a>(
+		&'a self,
+		parent_key_id: &Identifier,
+	) -> Result<Box<dyn Iterator<Item = TxLogEntry> + 'a>, Error>;
 
 	/// Iterate over all stored account paths
-	fn acct_path_iter<'a>(&'a self) -> Box<dyn Iterator<Item = AcctPathMapping> + 'a>;
+	fn acct_path_iter<'a>(
+		&'a self,
+	) -> Result<Box<dyn Iterator<Item = AcctPathMapping> + 'a>, Error>;
+
+	/// Iterate over devices registered for push notifications
+	fn push_registration_iter<'a>(
+		&'a self,
+	) -> Result<Box<dyn Iterator<Item = PushRegistration> + 'a>, Error>;
+
+	/// Iterate over received slates waiting for review
+	fn pending_receive_iter<'a>(
+		&'a self,
+	) -> Result<Box<dyn Iterator<Item = PendingReceive> + 'a>, Error>;
+
+	/// Iterate over recorded background operations, in order of id
+	fn operation_iter<'a>(
+		&'a self,
+	) -> Result<Box<dyn Iterator<Item = OperationStatus> + 'a>, Error>;
 
 	/// Gets an account path for a given label
 	fn get_acct_path(&self, label: String) -> Result<Option<AcctPathMapping>, Error>;
@@ -189,6 +304,13 @@ where
 	/// Retrieves a stored transaction from a TxLogEntry
 	fn get_stored_tx(&self, entry: &TxLogEntry) -> Result<Option<Transaction>, Error>;
 
+	/// Stores the slate as it stands after a step of its transaction, for
+	/// replays
+	fn store_slate(&self, step: TxStep, slate: &Slate) -> Result<(), Error>;
+
+	/// Retrieves the JSON of the slate stored at a step of a transaction, if any
+	fn get_stored_slate(&self, slate_id: &Uuid, step: TxStep) -> Result<Option<String>, Error>;
+
 	/// Create a new write batch to update or remove output data
 	fn batch<'a>(
 		&'a mut self,
@@ -198,18 +320,55 @@ where
 	/// Next child ID when we want to create a new output, based on current parent
 	fn next_child<'a>(&mut self, keychain_mask: Option<&SecretKey>) -> Result<Identifier, Error>;
 
+	/// Index the next child of the given parent will be derived at
+	fn current_child_index<'a>(&mut self, parent_key_id: &Identifier) -> Result<u32, Error>;
+
 	/// last verified height of outputs directly descending from the given parent key
 	fn last_confirmed_height<'a>(&mut self) -> Result<u64, Error>;
 
-	/// Attempt to restore the contents of a wallet from seed
-	fn restore(&mut self, keychain_mask: Option<&SecretKey>) -> Result<(), Error>;
+	/// How far the UTXO set was scanned by the last restore or check, if any
+	fn last_scanned_block<'a>(&mut self) -> Result<Option<ScannedBlockInfo>, Error>;
+
+	/// The wallet's feature flags, or the defaults if none have been saved
+	fn feature_flags(&self) -> Result<FeatureFlags, Error>;
+
+	/// What was last backed up, if the wallet has been backed up
+	fn backup_state(&self) -> Result<Option<BackupState>, Error>;
 
-	/// Attempt to check and fix wallet state
+	/// Check values for the recovery phrase, if the wallet generated it
+	fn seed_check(&self) -> Result<Option<SeedCheck>, Error>;
+
+	/// The TOTP secret enrolled for spends, if any
+	fn totp_secret(&self) -> Result<Option<TotpSecret>, Error>;
+
+	/// Slates received through the Foreign API in the hour they were last
+	/// counted in, if any have been
+	fn receive_count(&self) -> Result<Option<ReceiveCount>, Error>;
+
+	/// Attempt to restore the contents of a wallet from seed, stopping and
+	/// undoing the restore if `cancel` is cancelled
+	fn restore(
+		&mut self,
+		keychain_mask: Option<&SecretKey>,
+		cancel: Option<&CancelToken>,
+	) -> Result<(), Error>;
+
+	/// Attempt to check and fix wallet state, scanning the whole UTXO set if
+	/// `full` is set or only the part added since the last scan otherwise.
+	/// Stops without changing the wallet if `cancel` is cancelled during the
+	/// scan
 	fn check_repair(
 		&mut self,
 		keychain_mask: Option<&SecretKey>,
 		delete_unconfirmed: bool,
+		full: bool,
+		cancel: Option<&CancelToken>,
 	) -> Result<(), Error>;
+
+	/// Compare the wallet's outputs and transaction log against the whole
+	/// UTXO set without changing the wallet
+	fn audit_chain(&mut self, keychain_mask: Option<&SecretKey>)
+		-> Result<ChainAuditReport, Error>;
 }
 
 /// Batch trait to update the output data backend atomically. Trying to use a
@@ -246,6 +405,27 @@ where
 		height: u64,
 	) -> Result<(), Error>;
 
+	/// Save how far the UTXO set has been scanned
+	fn save_last_scanned_block(&mut self, block: ScannedBlockInfo) -> Result<(), Error>;
+
+	/// Save the wallet's feature flags
+	fn save_feature_flags(&mut self, flags: FeatureFlags) -> Result<(), Error>;
+
+	/// Save what was last backed up
+	fn save_backup_state(&mut self, state: &BackupState) -> Result<(), Error>;
+
+	/// Save check values for the recovery phrase
+	fn save_seed_check(&mut self, check: &SeedCheck) -> Result<(), Error>;
+
+	/// Save the count of slates received through the Foreign API this hour
+	fn save_receive_count(&mut self, count: &ReceiveCount) -> Result<(), Error>;
+
+	/// Save the TOTP secret enrolled for spends
+	fn save_totp_secret(&mut self, secret: &TotpSecret) -> Result<(), Error>;
+
+	/// Delete the TOTP secret, no longer asking for codes
+	fn delete_totp_secret(&mut self) -> Result<(), Error>;
+
 	/// get next tx log entry for the parent
 	fn next_tx_log_id(&mut self, parent_key_id: &Identifier) -> Result<u32, Error>;
 
@@ -255,12 +435,35 @@ where
 	/// save a tx log entry
 	fn save_tx_log_entry(&mut self, t: TxLogEntry, parent_id: &Identifier) -> Result<(), Error>;
 
+	/// Delete a tx log entry
+	fn delete_tx_log_entry(&mut self, id: u32, parent_id: &Identifier) -> Result<(), Error>;
+
 	/// save an account label -> path mapping
 	fn save_acct_path(&mut self, mapping: AcctPathMapping) -> Result<(), Error>;
 
 	/// Iterate over account names stored in backend
 	fn acct_path_iter(&self) -> Box<dyn Iterator<Item = AcctPathMapping>>;
 
+	/// Save a device registration for push notifications, replacing any
+	/// existing registration of the same token
+	fn save_push_registration(&mut self, registration: PushRegistration) -> Result<(), Error>;
+
+	/// Delete the push notification registration for the given token
+	fn delete_push_registration(&mut self, token: &str) -> Result<(), Error>;
+
+	/// Queue a received slate for review
+	fn save_pending_receive(&mut self, pending: &PendingReceive) -> Result<(), Error>;
+
+	/// Remove the received slate with the given id from the review queue
+	fn delete_pending_receive(&mut self, slate_id: &Uuid) -> Result<(), Error>;
+
+	/// Record the status of a background operation, replacing any earlier
+	/// record of it
+	fn save_operation(&mut self, status: &OperationStatus) -> Result<(), Error>;
+
+	/// Delete the record of a background operation
+	fn delete_operation(&mut self, id: u32) -> Result<(), Error>;
+
 	/// Save an output as locked in the backend
 	fn lock_output(&mut self, out: &mut OutputData) -> Result<(), Error>;
 
@@ -308,6 +511,26 @@ pub trait NodeClient: Send + Sync + Clone {
 	/// retrieves the current tip from the specified grin node
 	fn get_chain_height(&self) -> Result<u64, Error>;
 
+	/// Drop anything cached from the node, such as its version info, and
+	/// return to the most preferred reachable node. Called when the node
+	/// becomes reachable again after an outage
+	fn reconnect(&mut self) {}
+
+	/// Report the node currently in use and the health of each configured
+	/// node. Clients with a single node report only that node, with unknown
+	/// health
+	fn node_status(&self) -> NodeStatus {
+		NodeStatus {
+			active_node: self.node_url().to_owned(),
+			nodes: vec![NodeHealth {
+				url: self.node_url().to_owned(),
+				healthy: None,
+				last_error: None,
+				last_checked: None,
+			}],
+		}
+	}
+
 	/// retrieve a list of outputs from the specified grin node
 	/// need "by_height" and "by_id" variants
 	fn get_outputs_from_node(
@@ -315,6 +538,17 @@ pub trait NodeClient: Send + Sync + Clone {
 		wallet_outputs: Vec<pedersen::Commitment>,
 	) -> Result<HashMap<pedersen::Commitment, (String, u64, u64)>, Error>;
 
+	/// Look up a kernel on chain by its excess, searching blocks between
+	/// `min_height` and `max_height` if given. Returns the kernel along with
+	/// the height of the block it's in and its MMR index, or None if it
+	/// isn't on chain
+	fn get_kernel(
+		&self,
+		excess: &pedersen::Commitment,
+		min_height: Option<u64>,
+		max_height: Option<u64>,
+	) -> Result<Option<(TxKernel, u64, u64)>, Error>;
+
 	/// Get a list of outputs from the node by traversing the UTXO
 	/// set in PMMR index order.
 	/// Returns
@@ -345,6 +579,48 @@ pub struct NodeVersionInfo {
 	pub verified: Option<bool>,
 }
 
+/// Health of a node, as last seen by the node client
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NodeHealth {
+	/// The node's API address
+	pub url: String,
+	/// Whether the node answered the last request made to it, or `None`
+	/// if it hasn't been contacted yet
+	pub healthy: Option<bool>,
+	/// The error returned by the node's last failed request
+	pub last_error: Option<String>,
+	/// When the node was last contacted
+	pub last_checked: Option<DateTime<Utc>>,
+}
+
+/// The node a client is currently using, along with the health of every
+/// node it may fail over to
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NodeStatus {
+	/// API address of the node requests are currently sent to
+	pub active_node: String,
+	/// Health of each configured node, in order of preference
+	pub nodes: Vec<NodeHealth>,
+}
+
+/// Connectivity to the node in use, as last checked
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct NodeConnectivity {
+	/// API address of the node checked
+	pub node_url: String,
+	/// Whether the node answered the last check
+	pub reachable: bool,
+	/// Chain height reported by the node, if reachable
+	pub chain_height: Option<u64>,
+	/// Version reported by the node, if known
+	pub node_version: Option<String>,
+	/// Block header version reported by the node, if known
+	pub block_header_version: Option<u16>,
+	/// The error returned by the last failed check, cleared once the node
+	/// is reachable again
+	pub last_error: Option<String>,
+}
+
 /// Information about an output that's being tracked by the wallet. Must be
 /// enough to reconstruct the commitment associated with the ouput when the
 /// root private key is known.
@@ -501,6 +777,43 @@ pub struct Context {
 	pub fee: u64,
 	/// keep track of the participant id
 	pub participant_id: usize,
+	/// If inputs are only selected and locked when the transaction is
+	/// finalized, how to select them
+	#[serde(default)]
+	pub late_lock: Option<SelectionIntent>,
+	/// Dandelion preference to store with the transaction once it's locked
+	#[serde(default)]
+	pub fluff: Option<bool>,
+}
+
+/// Coin selection parameters, kept in the context of a late locked transaction
+/// until it's finalized
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SelectionIntent {
+	/// Minimum confirmations of the outputs to spend
+	pub minimum_confirmations: u64,
+	/// Soft limit on the number of outputs to spend
+	pub max_outputs: usize,
+	/// Number of change outputs to create
+	pub num_change_outputs: usize,
+	/// Whether to spend as many outputs as possible
+	pub selection_strategy_is_use_all: bool,
+	/// Whether to consider outputs in a random order
+	pub randomize_selection: bool,
+	/// Name of the coin selection strategy, overriding the flags above
+	#[serde(default)]
+	pub selection_strategy: Option<String>,
+}
+
+impl SelectionIntent {
+	/// The coin selection strategy to select the inputs with
+	pub fn coin_selection(&self) -> Result<Arc<dyn CoinSelection>, Error> {
+		selection::coin_selection(
+			self.selection_strategy.as_ref().map(|s| s.as_str()),
+			self.selection_strategy_is_use_all,
+			self.randomize_selection,
+		)
+	}
 }
 
 impl Context {
@@ -524,6 +837,8 @@ impl Context {
 			output_ids: vec![],
 			fee: 0,
 			participant_id: participant_id,
+			late_lock: None,
+			fluff: None,
 		}
 	}
 }
@@ -731,6 +1046,30 @@ pub struct TxLogEntry {
 	pub messages: Option<ParticipantMessages>,
 	/// Location of the store transaction, (reference or resending)
 	pub stored_tx: Option<String>,
+	/// Label or memo set by the user, to note what the transaction was for
+	#[serde(default)]
+	pub label: Option<String>,
+	/// Chain height when this tx entry was created, if known
+	#[serde(default)]
+	pub creation_height: Option<u64>,
+	/// Whether the transaction was cancelled for being left unconfirmed too long
+	#[serde(default)]
+	pub expired: bool,
+	/// Whether to skip the Dandelion stem phase when posting the transaction, if a
+	/// preference was given
+	#[serde(default)]
+	pub fluff: Option<bool>,
+	/// Excess of the transaction's kernel, as hex, recorded once the wallet
+	/// holds the finalized transaction
+	#[serde(default)]
+	pub kernel_excess: Option<String>,
+	/// Height of the block the transaction was confirmed in, if known
+	#[serde(default)]
+	pub confirmation_height: Option<u64>,
+	/// Versions of the wallet and of the slate at each step of the transaction
+	/// this wallet took part in
+	#[serde(default)]
+	pub step_versions: Vec<TxStepVersions>,
 }
 
 impl ser::Writeable for TxLogEntry {
@@ -764,9 +1103,26 @@ impl TxLogEntry {
 			fee: None,
 			messages: None,
 			stored_tx: None,
+			label: None,
+			creation_height: None,
+			expired: false,
+			fluff: None,
+			kernel_excess: None,
+			confirmation_height: None,
+			step_versions: vec![],
 		}
 	}
 
+	/// Record the versions of this wallet and of the slate at a step of the
+	/// transaction
+	pub fn record_step(&mut self, step: TxStep, slate: &Slate) {
+		self.step_versions.push(TxStepVersions {
+			step,
+			wallet_version: WALLET_VERSION.to_owned(),
+			slate_version: slate.version_info.orig_version,
+		});
+	}
+
 	/// Given a vec of TX log entries, return credited + debited sums
 	pub fn sum_confirmed(txs: &Vec<TxLogEntry>) -> (u64, u64) {
 		txs.iter().fold((0, 0), |acc, tx| match tx.confirmed {
@@ -781,6 +1137,28 @@ impl TxLogEntry {
 	}
 }
 
+/// Step of a transaction at which versions are recorded in its log entry
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum TxStep {
+	/// The sender's inputs were locked
+	Lock,
+	/// The recipient added its output
+	Receive,
+	/// The transaction was finalized
+	Finalize,
+}
+
+/// Versions of the wallet and of the slate at a step of a transaction
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TxStepVersions {
+	/// Step of the transaction
+	pub step: TxStep,
+	/// Version of the wallet software taking the step
+	pub wallet_version: String,
+	/// Version of the slate as received or created at the step
+	pub slate_version: u16,
+}
+
 /// Map of named accounts to BIP32 paths
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AcctPathMapping {
@@ -803,6 +1181,233 @@ impl ser::Readable for AcctPathMapping {
 	}
 }
 
+/// A device to be woken by a push notification when a slate is received
+/// for this wallet, so it can complete the transaction
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PushRegistration {
+	/// Token identifying the device to its push service
+	pub token: String,
+	/// Push service the token was issued by, e.g. "apns" or "fcm"
+	pub platform: String,
+	/// Time at which the token was registered
+	pub registered: DateTime<Utc>,
+}
+
+impl ser::Writeable for PushRegistration {
+	fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+	}
+}
+
+impl ser::Readable for PushRegistration {
+	fn read(reader: &mut dyn ser::Reader) -> Result<PushRegistration, ser::Error> {
+		let data = reader.read_bytes_len_prefix()?;
+		serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+	}
+}
+
+/// A received slate for more than the review threshold, waiting for the
+/// wallet's owner to approve or reject it rather than being signed
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PendingReceive {
+	/// The slate as received
+	pub slate: Slate,
+	/// Account to receive into, the default account if not given
+	pub dest_acct_name: Option<String>,
+	/// Message to add to the slate once approved
+	pub message: Option<String>,
+	/// Time at which the slate was received
+	pub received: DateTime<Utc>,
+}
+
+impl ser::Writeable for PendingReceive {
+	fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+	}
+}
+
+impl ser::Readable for PendingReceive {
+	fn read(reader: &mut dyn ser::Reader) -> Result<PendingReceive, ser::Error> {
+		let data = reader.read_bytes_len_prefix()?;
+		serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+	}
+}
+
+/// How far the UTXO set has been scanned for the wallet's outputs, so a
+/// later check can carry on from there
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ScannedBlockInfo {
+	/// Chain height when the scan started
+	pub height: u64,
+	/// Highest output PMMR index scanned
+	pub mmr_index: u64,
+}
+
+impl ser::Writeable for ScannedBlockInfo {
+	fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+	}
+}
+
+impl ser::Readable for ScannedBlockInfo {
+	fn read(reader: &mut dyn ser::Reader) -> Result<ScannedBlockInfo, ser::Error> {
+		let data = reader.read_bytes_len_prefix()?;
+		serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+	}
+}
+
+/// Features that can be turned on or off for each wallet, so risky features
+/// can be staged on some instances without a separate build. Flags missing
+/// from what's stored take their default values
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct FeatureFlags {
+	/// Allow invoices to be issued, paid and finalized. On by default
+	pub invoice_flow: bool,
+	/// Require payment proofs for outgoing transactions. Stored for wallets
+	/// supporting payment proofs, and not checked yet by this one
+	pub payment_proofs_required: bool,
+	/// Allow experimental atomic swaps. Stored for wallets supporting swaps,
+	/// and not checked yet by this one
+	pub experimental_swaps: bool,
+	/// Refuse to send from a wallet whose generated recovery phrase hasn't
+	/// been verified with `verify_seed_backup`. Off by default
+	pub seed_backup_check: bool,
+}
+
+impl Default for FeatureFlags {
+	fn default() -> FeatureFlags {
+		FeatureFlags {
+			invoice_flow: true,
+			payment_proofs_required: false,
+			experimental_swaps: false,
+			seed_backup_check: false,
+		}
+	}
+}
+
+impl FeatureFlags {
+	/// Error if the invoice flow is turned off
+	pub fn check_invoice_flow(&self) -> Result<(), Error> {
+		if !self.invoice_flow {
+			return Err(ErrorKind::FeatureDisabled("invoice_flow".to_owned()))?;
+		}
+		Ok(())
+	}
+
+	/// Error if sending needs a generated recovery phrase to have been
+	/// verified, and it hasn't been
+	pub fn check_seed_backup(&self, check: Option<&SeedCheck>) -> Result<(), Error> {
+		match check {
+			Some(c) if self.seed_backup_check && !c.verified => {
+				Err(ErrorKind::SeedBackupUnverified)?
+			}
+			_ => Ok(()),
+		}
+	}
+}
+
+impl ser::Writeable for FeatureFlags {
+	fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+	}
+}
+
+impl ser::Readable for FeatureFlags {
+	fn read(reader: &mut dyn ser::Reader) -> Result<FeatureFlags, ser::Error> {
+		let data = reader.read_bytes_len_prefix()?;
+		serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+	}
+}
+
+/// Check values for the words of a recovery phrase generated by the wallet,
+/// so the user can show they've recorded it. Each is a hash of a word and
+/// its position keyed by the wallet's root key, so they reveal nothing of
+/// the phrase without the seed
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct SeedCheck {
+	/// Hex encoded check value of each word, in order
+	pub words: Vec<String>,
+	/// Whether the user has shown they've recorded the phrase
+	pub verified: bool,
+}
+
+impl SeedCheck {
+	/// Check values for the given recovery phrase of the keychain's seed
+	pub fn new<K: Keychain>(keychain: &K, mnemonic: &str) -> Result<SeedCheck, Error> {
+		let key = SeedCheck::key(keychain)?;
+		let words = mnemonic
+			.split_whitespace()
+			.enumerate()
+			.map(|(i, w)| SeedCheck::word_check(&key, i as u32 + 1, w))
+			.collect();
+		Ok(SeedCheck {
+			words,
+			verified: false,
+		})
+	}
+
+	/// Whether the given word is the phrase's word at the given position,
+	/// counting from 1
+	pub fn check_word<K: Keychain>(
+		&self,
+		keychain: &K,
+		position: u32,
+		word: &str,
+	) -> Result<bool, Error> {
+		let key = SeedCheck::key(keychain)?;
+		Ok(position >= 1
+			&& self.words.get(position as usize - 1)
+				== Some(&SeedCheck::word_check(&key, position, word)))
+	}
+
+	fn key<K: Keychain>(keychain: &K) -> Result<Vec<u8>, Error> {
+		let root_key = keychain.derive_key(0, &K::root_key_id(), &SwitchCommitmentType::Regular)?;
+		Ok(blake2b(32, &root_key.0[..], b"seed_check")
+			.as_bytes()
+			.to_vec())
+	}
+
+	fn word_check(key: &[u8], position: u32, word: &str) -> String {
+		let data = format!("{}:{}", position, word.trim().to_lowercase());
+		to_hex(blake2b(16, key, data.as_bytes()).as_bytes().to_vec())
+	}
+}
+
+impl ser::Writeable for SeedCheck {
+	fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+	}
+}
+
+impl ser::Readable for SeedCheck {
+	fn read(reader: &mut dyn ser::Reader) -> Result<SeedCheck, ser::Error> {
+		let data = reader.read_bytes_len_prefix()?;
+		serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+	}
+}
+
+/// Delivers push notifications to registered devices, typically through a
+/// gateway holding the credentials for each push service
+pub trait PushNotifier: Send + Sync {
+	/// Notify the registered device that a slate with the given id was received
+	fn notify_slate_received(
+		&self,
+		registration: &PushRegistration,
+		slate_id: &Uuid,
+	) -> Result<(), Error>;
+}
+
+/// Somewhere off the host that backups are copied to, such as an object
+/// store or a remote file system
+pub trait BackupTarget: Send + Sync {
+	/// Describes the target in logs
+	fn name(&self) -> String;
+	/// Store the backup. Only its encrypted data is sent, under a name
+	/// giving when it was taken and its id
+	fn store(&self, backup: &WalletBackup) -> Result<(), Error>;
+}
+
 /// Dummy wrapper for the hex-encoded serialized transaction.
 #[derive(Serialize, Deserialize)]
 pub struct TxWrapper {
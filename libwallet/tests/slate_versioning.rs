@@ -12,7 +12,65 @@
 // limitations under the License.
 
 //! core::libtx specific tests
-//use grin_wallet_libwallet::Slate;
+use grin_wallet_libwallet::{negotiate_slate_version, ErrorKind, Slate, SlateVersion};
+
+// invalid slates should be reported by the field at fault
+#[test]
+fn slate_validation() {
+	let v2 = include_str!("slates/v2.slate");
+	assert!(Slate::deserialize_upgrade(&v2).is_ok());
+
+	let expect_invalid = |slate: &serde_json::Value, expected_field: &str| {
+		let res = Slate::deserialize_upgrade(&slate.to_string());
+		match res.unwrap_err().kind() {
+			ErrorKind::SlateValidation { version, field, .. } => {
+				assert_eq!(version, 2);
+				assert_eq!(field, expected_field);
+			}
+			k => panic!("unexpected error {:?}", k),
+		}
+	};
+
+	let mut slate: serde_json::Value = serde_json::from_str(&v2).unwrap();
+	slate["tx"]["body"]["kernels"][0]
+		.as_object_mut()
+		.unwrap()
+		.remove("excess");
+	expect_invalid(&slate, "tx.body.kernels[0].excess");
+
+	let mut slate: serde_json::Value = serde_json::from_str(&v2).unwrap();
+	slate["participant_data"][0]["public_nonce"] = serde_json::json!("not hex");
+	expect_invalid(&slate, "participant_data[0].public_nonce");
+
+	let mut slate: serde_json::Value = serde_json::from_str(&v2).unwrap();
+	slate["amount"] = serde_json::json!(-1);
+	expect_invalid(&slate, "amount");
+}
+
+// slates are sent as the newest version both wallets read
+#[test]
+fn slate_version_negotiation() {
+	let versions = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+	assert_eq!(
+		negotiate_slate_version(&versions(&["V2"])).unwrap(),
+		SlateVersion::V2
+	);
+	// versions of newer wallets this one doesn't know are skipped
+	assert_eq!(
+		negotiate_slate_version(&versions(&["V9", "V2"])).unwrap(),
+		SlateVersion::V2
+	);
+	assert!(negotiate_slate_version(&versions(&["V9"])).is_err());
+	assert!(negotiate_slate_version(&[]).is_err());
+	assert_eq!(SlateVersion::from_number(2), Some(SlateVersion::V2));
+	assert_eq!(SlateVersion::from_number(9), None);
+
+	let v2 = include_str!("slates/v2.slate");
+	let mut slate = Slate::deserialize_upgrade(&v2).unwrap();
+	slate.downgrade(SlateVersion::V2).unwrap();
+	let s = serde_json::to_string(&slate).unwrap();
+	assert_eq!(Slate::parse_slate_version(&s).unwrap(), 2);
+}
 
 // test all slate conversions
 /* TODO: Turn back on upon release of new slate version
@@ -23,6 +23,7 @@ use crate::core::global;
 use crate::util::init_logger;
 use clap::App;
 use grin_wallet_config as config;
+use grin_wallet_libwallet::set_build_details;
 use grin_wallet_util::grin_core as core;
 use grin_wallet_util::grin_util as util;
 use std::env;
@@ -82,7 +83,7 @@ fn real_main() -> i32 {
 
 	// special cases for certain lifecycle commands
 	match args.subcommand() {
-		("init", Some(init_args)) => {
+		("init", Some(init_args)) | ("setup", Some(init_args)) => {
 			if init_args.is_present("here") {
 				current_dir = Some(env::current_dir().unwrap_or_else(|e| {
 					panic!("Error creating config file: {}", e);
@@ -96,7 +97,8 @@ fn real_main() -> i32 {
 	// Load relevant config, try and load a wallet config file
 	// Use defaults for configuration if config file not found anywhere
 	let mut config = config::initial_setup_wallet(&chain_type, current_dir).unwrap_or_else(|e| {
-		panic!("Error loading wallet configuration: {}", e);
+		eprintln!("Error loading wallet configuration: {}", e);
+		std::process::exit(1);
 	});
 
 	config.members.as_mut().unwrap().wallet.chain_type = Some(chain_type);
@@ -110,6 +112,10 @@ fn real_main() -> i32 {
 	);
 
 	log_build_info();
+	set_build_details(
+		built_info::GIT_VERSION.map(|v| v.to_owned()),
+		built_info::FEATURES.iter().map(|f| f.to_string()).collect(),
+	);
 
 	global::set_mining_mode(
 		config
@@ -13,25 +13,118 @@
 // limitations under the License.
 
 use crate::cmd::wallet_args;
-use crate::config::GlobalWalletConfig;
+use crate::config::{GlobalWalletConfig, ResourceProfile};
+use crate::util::file::get_first_line;
 use clap::ArgMatches;
-use grin_wallet_impls::HTTPNodeClient;
-use grin_wallet_libwallet::NodeClient;
+use grin_wallet_impls::{
+	discover_local_nodes, register_transport, EmailTransport, HTTPNodeClient, HttpTransport,
+	Socks5Proxy,
+};
+use grin_wallet_libwallet::{
+	set_privacy_mode, set_resource_limits, set_spend_confirmations, NodeClient, ResourceLimits,
+	SpendConfirmations,
+};
+use grin_wallet_util::grin_core::global::ChainTypes;
 use semver::Version;
+use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
 const MIN_COMPAT_NODE_VERSION: &str = "2.0.0-beta.1";
 
-pub fn wallet_command(wallet_args: &ArgMatches<'_>, config: GlobalWalletConfig) -> i32 {
+/// If the configured node can't be reached, look for one on this machine
+/// and offer to switch to it, saving it to the configuration file
+fn discover_node(config: &mut GlobalWalletConfig, node_api_secret: Option<String>) {
+	let wallet_config = config.members.as_ref().unwrap().wallet.clone();
+	let current = HTTPNodeClient::new(
+		&wallet_config.check_node_api_http_addr,
+		node_api_secret.clone(),
+	);
+	if current.chain_height().is_ok() {
+		return;
+	}
+	let chain_type = wallet_config.chain_type.unwrap_or(ChainTypes::Mainnet);
+	let node = discover_local_nodes(&chain_type, node_api_secret)
+		.into_iter()
+		.find(|n| n.url != wallet_config.check_node_api_http_addr);
+	let node = match node {
+		Some(n) => n,
+		None => return,
+	};
+	println!(
+		"The node at {} can't be reached, but a node was found at {} (chain height {})",
+		wallet_config.check_node_api_http_addr, node.url, node.chain_height
+	);
+	match wallet_args::prompt_confirm("Use this node, saving it to the configuration file?") {
+		Ok(true) => {}
+		_ => return,
+	}
+	config
+		.members
+		.as_mut()
+		.unwrap()
+		.wallet
+		.check_node_api_http_addr = node.url;
+	if let Some(path) = config.config_file_path.clone() {
+		if let Err(e) = config.write_to_file(path.to_str().unwrap()) {
+			println!("Unable to save the configuration file: {}", e);
+		}
+	}
+}
+
+pub fn wallet_command(wallet_args: &ArgMatches<'_>, mut config: GlobalWalletConfig) -> i32 {
+	// lifecycle commands creating or recovering a wallet don't need a node
+	let needs_node = match wallet_args.subcommand().0 {
+		"init" | "setup" | "recover" | "split_seed" | "public_nodes" => false,
+		_ => true,
+	};
+	let node_discovery = config.members.as_ref().unwrap().wallet.node_discovery;
+	if needs_node && node_discovery == Some(true) {
+		let secret = config
+			.members
+			.as_ref()
+			.unwrap()
+			.wallet
+			.node_api_secret_path
+			.clone();
+		discover_node(&mut config, get_first_line(secret));
+	}
+
 	// just get defaults from the global config
 	let wallet_config = config.members.unwrap().wallet;
 
+	set_resource_limits(match wallet_config.resource_profile {
+		Some(ResourceProfile::Low) => ResourceLimits::low(),
+		Some(ResourceProfile::Server) => ResourceLimits::server(),
+		Some(ResourceProfile::Standard) | None => ResourceLimits::standard(),
+	});
+	if wallet_config.log_privacy == Some(true) {
+		set_privacy_mode(true);
+	}
+	if let Some(c) = wallet_config.spend_confirmations.as_ref() {
+		set_spend_confirmations(SpendConfirmations {
+			coinbase: c.coinbase,
+			received: c.received,
+			change: c.change,
+		});
+	}
+
 	// Check the node version info, and exit with report if we're not compatible
+	// global args are parsed later, as doing so may prompt for a passphrase
+	let node_api_secret = get_first_line(wallet_config.node_api_secret_path.clone());
 	let mut node_client = HTTPNodeClient::new(&wallet_config.check_node_api_http_addr, None);
-	let global_wallet_args = wallet_args::parse_global_args(&wallet_config, &wallet_args)
-		.expect("Can't read configuration file");
-	node_client.set_node_api_secret(global_wallet_args.node_api_secret.clone());
+	node_client.set_node_api_secret(node_api_secret);
+	node_client.set_archive_node_url(wallet_config.archive_node_api_http_addr.clone());
+
+	// Route node requests and slates sent over http through the proxy, if any
+	let proxy = wallet_config.proxy.as_ref().map(Socks5Proxy::new);
+	node_client.set_proxy(proxy.clone());
+	let http = Arc::new(HttpTransport::new(proxy));
+	register_transport("http", http.clone());
+	register_transport("https", http);
+	if let Some(c) = wallet_config.email_slates.clone() {
+		register_transport("email", Arc::new(EmailTransport::new(c)));
+	}
 
 	// This will also cache the node version info for calls to foreign API check middleware
 	if let Some(v) = node_client.clone().get_version_info() {
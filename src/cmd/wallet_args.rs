@@ -16,26 +16,45 @@ use crate::api::TLSConfig;
 use crate::config::GRIN_WALLET_DIR;
 use crate::util::file::get_first_line;
 use crate::util::{Mutex, ZeroingString};
+use chrono::prelude::{DateTime, NaiveDate, Utc};
 /// Argument parsing and error handling for wallet commands
 use clap::ArgMatches;
 use failure::Fail;
+use grin_wallet_api::ShutdownHandle;
 use grin_wallet_config::WalletConfig;
 use grin_wallet_controller::command;
+use grin_wallet_controller::hosted::HostedWallet;
 use grin_wallet_controller::{Error, ErrorKind};
-use grin_wallet_impls::{DefaultLCProvider, DefaultWalletImpl};
+use grin_wallet_impls::{
+	discover_local_nodes, DefaultLCProvider, DefaultWalletImpl, HTTPNodeClient,
+};
 use grin_wallet_impls::{PathToSlate, SlateGetter as _};
 use grin_wallet_libwallet::Slate;
-use grin_wallet_libwallet::{IssueInvoiceTxArgs, NodeClient, WalletInst, WalletLCProvider};
+use grin_wallet_libwallet::{
+	derivation_scheme, slip39, InitTxRecipient, IssueInvoiceTxArgs, NodeClient,
+	RetrieveTxQueryArgs, SpendingPolicy, TxExportFormat, WalletInst, WalletLCProvider,
+};
 use grin_wallet_util::grin_core as core;
 use grin_wallet_util::grin_core::core::amount_to_hr_string;
 use grin_wallet_util::grin_core::global;
 use grin_wallet_util::grin_keychain as keychain;
 use linefeed::terminal::Signal;
-use linefeed::{Interface, ReadResult};
+use linefeed::{DefaultTerminal, Interface, ReadResult};
 use rpassword;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+/// Shutdown handle triggered on SIGINT or SIGTERM, so listeners can stop
+/// and close the wallet cleanly
+fn shutdown_on_signal() -> ShutdownHandle {
+	let shutdown = ShutdownHandle::new();
+	let s = shutdown.clone();
+	if let Err(e) = ctrlc::set_handler(move || s.shutdown()) {
+		println!("Unable to handle shutdown signals: {}", e);
+	}
+	shutdown
+}
+
 // define what to do on argument error
 macro_rules! arg_parse {
 	( $r:expr ) => {
@@ -86,6 +105,18 @@ fn prompt_password_confirm() -> ZeroingString {
 	first
 }
 
+// A mistyped passphrase would derive a different, empty wallet, so it's
+// confirmed before creating or recovering one
+fn confirm_seed_passphrase(g_args: &command::GlobalArgs) -> Result<(), ParseError> {
+	if let Some(p) = &g_args.seed_passphrase {
+		if *p != prompt_password_stdout("Confirm BIP39 passphrase: ") {
+			let msg = "BIP39 passphrases don't match".to_owned();
+			return Err(ParseError::ArgumentError(msg));
+		}
+	}
+	Ok(())
+}
+
 fn prompt_replace_seed() -> Result<bool, ParseError> {
 	let interface = Arc::new(Interface::new("replace_seed")?);
 	interface.set_report_signal(Signal::Interrupt, true);
@@ -113,6 +144,68 @@ fn prompt_replace_seed() -> Result<bool, ParseError> {
 	}
 }
 
+// Prompt for a value, returning `default` if none is entered
+fn prompt_value(
+	interface: &Interface<DefaultTerminal>,
+	prompt: &str,
+	default: &str,
+) -> Result<String, ParseError> {
+	interface.set_prompt(&format!("{} [{}]> ", prompt, default))?;
+	loop {
+		match interface.read_line()? {
+			ReadResult::Eof => return Err(ParseError::CancelledError),
+			ReadResult::Signal(sig) => {
+				if sig == Signal::Interrupt {
+					interface.cancel_read_line()?;
+					return Err(ParseError::CancelledError);
+				}
+			}
+			ReadResult::Input(line) => {
+				let line = line.trim();
+				return Ok(match line.is_empty() {
+					true => default.to_owned(),
+					false => line.to_owned(),
+				});
+			}
+		}
+	}
+}
+
+fn prompt_yes_no(
+	interface: &Interface<DefaultTerminal>,
+	prompt: &str,
+	default: bool,
+) -> Result<bool, ParseError> {
+	let default = if default { "y" } else { "n" };
+	loop {
+		match prompt_value(interface, &format!("{} (y/n)", prompt), default)?.as_str() {
+			"Y" | "y" => return Ok(true),
+			"N" | "n" => return Ok(false),
+			_ => println!("Please respond y or n"),
+		}
+	}
+}
+
+/// Ask a yes or no question on the terminal, defaulting to no
+pub fn prompt_confirm(prompt: &str) -> Result<bool, ParseError> {
+	let interface = Interface::new("confirm")?;
+	interface.set_report_signal(Signal::Interrupt, true);
+	prompt_yes_no(&interface, prompt, false)
+}
+
+fn prompt_existing_file(
+	interface: &Interface<DefaultTerminal>,
+	prompt: &str,
+) -> Result<String, ParseError> {
+	loop {
+		let path = prompt_value(interface, prompt, "")?;
+		match Path::new(&path).is_file() {
+			true => return Ok(path),
+			false => println!("File '{}' doesn't exist", path),
+		}
+	}
+}
+
 fn prompt_recovery_phrase<L, C, K>(
 	wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
 ) -> Result<ZeroingString, ParseError>
@@ -157,6 +250,50 @@ where
 	Ok(phrase)
 }
 
+fn prompt_seed_shares() -> Result<Vec<ZeroingString>, ParseError> {
+	let interface = Arc::new(Interface::new("recover")?);
+	let mut shares = vec![];
+	let mut needed = None;
+	interface.set_report_signal(Signal::Interrupt, true);
+	loop {
+		if let Some(n) = needed {
+			if shares.len() >= n as usize {
+				break;
+			}
+		}
+		interface.set_prompt(&format!("share {}> ", shares.len() + 1))?;
+		match needed {
+			Some(n) => println!("Please enter seed share {} of {}:", shares.len() + 1, n),
+			None => println!("Please enter a seed share:"),
+		}
+		let res = interface.read_line()?;
+		match res {
+			ReadResult::Eof => return Err(ParseError::CancelledError),
+			ReadResult::Signal(sig) => {
+				if sig == Signal::Interrupt {
+					interface.cancel_read_line()?;
+					return Err(ParseError::CancelledError);
+				}
+			}
+			ReadResult::Input(line) => match slip39::threshold(&line) {
+				Ok(t) => {
+					if needed.is_none() {
+						needed = Some(t);
+					}
+					shares.push(ZeroingString::from(line));
+				}
+				Err(e) => {
+					println!();
+					println!("Seed share is invalid: {}", e);
+					println!();
+					interface.set_buffer(&line)?;
+				}
+			},
+		}
+	}
+	Ok(shares)
+}
+
 fn prompt_pay_invoice(slate: &Slate, method: &str, dest: &str) -> Result<bool, ParseError> {
 	let interface = Arc::new(Interface::new("pay")?);
 	let amount = amount_to_hr_string(slate.amount, false);
@@ -229,6 +366,13 @@ where
 		as Box<WalletInst<'static, L, C, K>>;
 	let lc = wallet.lc_provider().unwrap();
 	lc.set_wallet_directory(&config.data_file_dir);
+	if let Some(p) = config.spending_policy.as_ref() {
+		lc.set_spending_policy(SpendingPolicy {
+			max_amount_per_tx: p.max_amount_per_tx,
+			max_daily_total: p.max_daily_total,
+			allowed_destinations: p.allowed_destinations.clone(),
+		});
+	}
 	Ok(Arc::new(Mutex::new(wallet)))
 }
 
@@ -256,6 +400,23 @@ fn parse_u64(arg: &str, name: &str) -> Result<u64, ParseError> {
 	}
 }
 
+// parses a date (midnight UTC) or an RFC 3339 time
+fn parse_time(arg: &str, name: &str) -> Result<DateTime<Utc>, ParseError> {
+	if let Ok(d) = NaiveDate::parse_from_str(arg, "%Y-%m-%d") {
+		return Ok(DateTime::from_utc(d.and_hms(0, 0, 0), Utc));
+	}
+	match DateTime::parse_from_rfc3339(arg) {
+		Ok(t) => Ok(t.with_timezone(&Utc)),
+		Err(e) => {
+			let msg = format!(
+				"Could not parse {} as YYYY-MM-DD or an RFC 3339 time. e={}",
+				name, e
+			);
+			Err(ParseError::ArgumentError(msg))
+		}
+	}
+}
+
 pub fn parse_global_args(
 	config: &WalletConfig,
 	args: &ArgMatches,
@@ -270,6 +431,10 @@ pub fn parse_global_args(
 		None => None,
 		Some(p) => Some(ZeroingString::from(p)),
 	};
+	let seed_passphrase = match args.is_present("seed_passphrase") {
+		true => Some(prompt_password_stdout("BIP39 passphrase: ")),
+		false => None,
+	};
 
 	let tls_conf = match config.tls_certificate_file.clone() {
 		None => None,
@@ -299,6 +464,7 @@ pub fn parse_global_args(
 		chain_type: chain_type,
 		node_api_secret: node_api_secret,
 		password: password,
+		seed_passphrase: seed_passphrase,
 		tls_conf: tls_conf,
 	})
 }
@@ -319,6 +485,7 @@ where
 		false => 32,
 		true => 16,
 	};
+	confirm_seed_passphrase(g_args)?;
 	let recovery_phrase = match args.is_present("recover") {
 		true => Some(prompt_recovery_phrase(wallet)?),
 		false => None,
@@ -344,6 +511,113 @@ where
 	})
 }
 
+pub fn parse_setup_args(
+	config: &WalletConfig,
+	g_args: &command::GlobalArgs,
+	args: &ArgMatches,
+) -> Result<command::SetupArgs, ParseError> {
+	let interface = Interface::new("setup")?;
+	interface.set_report_signal(Signal::Interrupt, true);
+	let mut config = config.clone();
+	println!();
+	println!(
+		"This will configure and create a new wallet in {}",
+		config.data_file_dir
+	);
+	println!("Press enter to accept the value given in brackets");
+	println!();
+
+	let chain_type = config
+		.chain_type
+		.clone()
+		.unwrap_or(global::ChainTypes::Mainnet);
+	let nodes = discover_local_nodes(&chain_type, g_args.node_api_secret.clone());
+	for n in nodes.iter() {
+		println!(
+			"Found a node on this machine at {} (chain height {})",
+			n.url, n.chain_height
+		);
+	}
+	if let Some(n) = nodes.first() {
+		config.check_node_api_http_addr = n.url.clone();
+	}
+	println!("Which node should the wallet use? Several may be given, separated by commas");
+	loop {
+		let addr = prompt_value(
+			&interface,
+			"Node API address",
+			&config.check_node_api_http_addr,
+		)?;
+		let client = HTTPNodeClient::new(&addr, g_args.node_api_secret.clone());
+		match client.chain_height() {
+			Ok(height) => {
+				println!("Connected to node at chain height {}", height);
+				config.check_node_api_http_addr = addr;
+				break;
+			}
+			Err(e) => {
+				println!("Unable to connect to the node: {}", e);
+				if prompt_yes_no(&interface, "Use this node anyway?", false)? {
+					config.check_node_api_http_addr = addr;
+					break;
+				}
+			}
+		}
+	}
+	println!();
+
+	println!("The wallet can listen for transactions sent to it over http");
+	config.api_listen_interface = match prompt_yes_no(
+		&interface,
+		"Accept transactions from other machines?",
+		false,
+	)? {
+		true => "0.0.0.0".to_owned(),
+		false => "127.0.0.1".to_owned(),
+	};
+	loop {
+		let port = prompt_value(
+			&interface,
+			"Listener port",
+			&config.api_listen_port.to_string(),
+		)?;
+		match port.parse::<u16>() {
+			Ok(p) if p > 0 => {
+				config.api_listen_port = p;
+				break;
+			}
+			_ => println!("'{}' is not a valid port", port),
+		}
+	}
+	if prompt_yes_no(
+		&interface,
+		"Serve the wallet's APIs over TLS (https)?",
+		false,
+	)? {
+		config.tls_certificate_file =
+			Some(prompt_existing_file(&interface, "TLS certificate file")?);
+		config.tls_certificate_key = Some(prompt_existing_file(&interface, "TLS key file")?);
+	}
+	println!();
+
+	let list_length = match args.is_present("short_wordlist") {
+		false => 32,
+		true => 16,
+	};
+	confirm_seed_passphrase(g_args)?;
+	println!("Please enter a password for your new wallet");
+	let password = match g_args.password.clone() {
+		Some(p) => p,
+		None => prompt_password_confirm(),
+	};
+
+	Ok(command::SetupArgs {
+		list_length,
+		password,
+		config,
+	})
+}
+
 pub fn parse_recover_args<L, C, K>(
 	wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
 	g_args: &command::GlobalArgs,
@@ -355,9 +629,9 @@ where
 	C: NodeClient + 'static,
 	K: keychain::Keychain + 'static,
 {
-	let (passphrase, recovery_phrase) = {
+	let (passphrase, recovery_phrase, seed_shares) = {
 		match args.is_present("display") {
-			true => (prompt_password(&g_args.password), None),
+			true => (prompt_password(&g_args.password), None, None),
 			false => {
 				let cont = {
 					let mut w_lock = wallet.lock();
@@ -371,15 +645,60 @@ where
 				if !cont {
 					return Err(ParseError::CancelledError);
 				}
-				let phrase = prompt_recovery_phrase(wallet.clone())?;
+				confirm_seed_passphrase(g_args)?;
+				let (phrase, shares) = match args.is_present("shares") {
+					true => (None, Some(prompt_seed_shares()?)),
+					false => (Some(prompt_recovery_phrase(wallet.clone())?), None),
+				};
 				println!("Please provide a new password for the recovered wallet");
-				(prompt_password_confirm(), Some(phrase.to_owned()))
+				(prompt_password_confirm(), phrase, shares)
 			}
 		}
 	};
 	Ok(command::RecoverArgs {
 		passphrase: passphrase,
 		recovery_phrase: recovery_phrase,
+		seed_shares: seed_shares,
+	})
+}
+
+pub fn parse_split_seed_args(
+	g_args: &command::GlobalArgs,
+	args: &ArgMatches,
+) -> Result<command::SplitSeedArgs, ParseError> {
+	let threshold = parse_u64(parse_required(args, "threshold")?, "threshold")?;
+	let count = parse_u64(parse_required(args, "count")?, "count")?;
+	let max = slip39::MAX_SHARE_COUNT as u64;
+	if threshold < 2 || threshold > count || count > max {
+		let msg = format!(
+			"The threshold must be at least 2 and at most the number of shares, which may be at most {}",
+			max
+		);
+		return Err(ParseError::ArgumentError(msg));
+	}
+	Ok(command::SplitSeedArgs {
+		password: prompt_password(&g_args.password),
+		threshold: threshold as u8,
+		count: count as u8,
+	})
+}
+
+pub fn parse_change_password_args(
+	g_args: &command::GlobalArgs,
+) -> Result<command::ChangePasswordArgs, ParseError> {
+	let old = prompt_password(&g_args.password);
+	let new = prompt_password_stdout("New password: ");
+	if new != prompt_password_stdout("Confirm new password: ") {
+		let msg = "New passwords don't match".to_owned();
+		return Err(ParseError::ArgumentError(msg));
+	}
+	Ok(command::ChangePasswordArgs { old, new })
+}
+
+pub fn parse_public_nodes_args(args: &ArgMatches) -> Result<command::PublicNodesArgs, ParseError> {
+	Ok(command::PublicNodesArgs {
+		select: args.is_present("select"),
+		pin: args.value_of("pin").map(|p| p.to_owned()),
 	})
 }
 
@@ -404,7 +723,10 @@ pub fn parse_account_args(account_args: &ArgMatches) -> Result<command::AccountA
 	Ok(command::AccountArgs { create: create })
 }
 
-pub fn parse_send_args(args: &ArgMatches) -> Result<command::SendArgs, ParseError> {
+pub fn parse_send_args(
+	config: &WalletConfig,
+	args: &ArgMatches,
+) -> Result<command::SendArgs, ParseError> {
 	// amount
 	let amount = parse_required(args, "amount")?;
 	let amount = core::core::amount_from_hr_string(amount);
@@ -453,11 +775,7 @@ pub fn parse_send_args(args: &ArgMatches) -> Result<command::SendArgs, ParseErro
 			}
 		}
 	};
-	if !estimate_selection_strategies
-		&& method == "http"
-		&& !dest.starts_with("http://")
-		&& !dest.starts_with("https://")
-	{
+	if !estimate_selection_strategies && method == "http" && !dest.contains("://") {
 		let msg = format!(
 			"HTTP Destination should start with http://: or https://: {}",
 			dest,
@@ -465,9 +783,24 @@ pub fn parse_send_args(args: &ArgMatches) -> Result<command::SendArgs, ParseErro
 		return Err(ParseError::ArgumentError(msg));
 	}
 
+	// qr
+	let qr = args.is_present("qr");
+	if qr && method != "file" {
+		return Err(ParseError::ArgumentError(
+			"QR codes can only be displayed with the file method".to_owned(),
+		));
+	}
+
 	// change_outputs
-	let change_outputs = parse_required(args, "change_outputs")?;
-	let change_outputs = parse_u64(change_outputs, "change_outputs")? as usize;
+	let change_outputs = match args.value_of("change_outputs") {
+		Some(c) => parse_u64(c, "change_outputs")? as usize,
+		None => config.num_change_outputs.unwrap_or(1) as usize,
+	};
+	if change_outputs == 0 {
+		return Err(ParseError::ArgumentError(
+			"At least one change output is needed".to_owned(),
+		));
+	}
 
 	// fluff
 	let fluff = args.is_present("fluff");
@@ -486,6 +819,46 @@ pub fn parse_send_args(args: &ArgMatches) -> Result<command::SendArgs, ParseErro
 		}
 	};
 
+	// ttl_blocks
+	let ttl_blocks = match args.value_of("ttl_blocks") {
+		Some(b) => Some(parse_u64(b, "ttl_blocks")?),
+		None => None,
+	};
+
+	// additional recipients, given as amount:destination
+	let mut additional_recipients = vec![];
+	if let Some(recipients) = args.values_of("recipient") {
+		if method == "file" || method == "self" {
+			let msg = "Additional recipients are not supported with the file and self methods"
+				.to_string();
+			return Err(ParseError::ArgumentError(msg));
+		}
+		for r in recipients {
+			let mut split = r.splitn(2, ':');
+			let (r_amount, r_dest) = match (split.next(), split.next()) {
+				(Some(a), Some(d)) => (a, d),
+				_ => {
+					let msg = format!("Recipient should be given as amount:destination: {}", r);
+					return Err(ParseError::ArgumentError(msg));
+				}
+			};
+			let r_amount = match core::core::amount_from_hr_string(r_amount) {
+				Ok(a) => a,
+				Err(e) => {
+					let msg = format!(
+						"Could not parse recipient amount as a number with optional decimal point. e={}",
+						e
+					);
+					return Err(ParseError::ArgumentError(msg));
+				}
+			};
+			additional_recipients.push(InitTxRecipient {
+				amount: r_amount,
+				dest: r_dest.to_owned(),
+			});
+		}
+	}
+
 	Ok(command::SendArgs {
 		amount: amount,
 		message: message,
@@ -498,6 +871,11 @@ pub fn parse_send_args(args: &ArgMatches) -> Result<command::SendArgs, ParseErro
 		fluff: fluff,
 		max_outputs: max_outputs,
 		target_slate_version: target_slate_version,
+		additional_recipients: additional_recipients,
+		replace: args.is_present("replace"),
+		ttl_blocks,
+		late_lock: args.is_present("late_lock"),
+		qr,
 	})
 }
 
@@ -508,18 +886,25 @@ pub fn parse_receive_args(receive_args: &ArgMatches) -> Result<command::ReceiveA
 		false => None,
 	};
 
-	// input
-	let tx_file = parse_required(receive_args, "input")?;
+	// input, or the text of the sender's QR codes pasted into stdin
+	let qr_input = receive_args.is_present("qr_input");
+	let tx_file = match qr_input {
+		true => None,
+		false => Some(parse_required(receive_args, "input")?),
+	};
 
 	// validate input
-	if !Path::new(&tx_file).is_file() {
-		let msg = format!("File {} not found.", &tx_file);
-		return Err(ParseError::ArgumentError(msg));
+	if let Some(f) = tx_file {
+		if !Path::new(f).is_file() {
+			let msg = format!("File {} not found.", f);
+			return Err(ParseError::ArgumentError(msg));
+		}
 	}
 
 	Ok(command::ReceiveArgs {
-		input: tx_file.to_owned(),
+		input: tx_file.map(|f| f.to_owned()),
 		message: message,
+		qr: qr_input || receive_args.is_present("qr"),
 	})
 }
 
@@ -534,6 +919,80 @@ pub fn parse_finalize_args(args: &ArgMatches) -> Result<command::FinalizeArgs, P
 	Ok(command::FinalizeArgs {
 		input: tx_file.to_owned(),
 		fluff: fluff,
+		replace: args.is_present("replace"),
+	})
+}
+
+pub fn parse_prepare_send_args(
+	config: &WalletConfig,
+	args: &ArgMatches,
+) -> Result<command::PrepareSendArgs, ParseError> {
+	let amount = parse_required(args, "amount")?;
+	let amount = match core::core::amount_from_hr_string(amount) {
+		Ok(a) => a,
+		Err(e) => {
+			let msg = format!(
+				"Could not parse amount as a number with optional decimal point. e={}",
+				e
+			);
+			return Err(ParseError::ArgumentError(msg));
+		}
+	};
+	let min_c = parse_required(args, "minimum_confirmations")?;
+	let min_c = parse_u64(min_c, "minimum_confirmations")?;
+	let change_outputs = match args.value_of("change_outputs") {
+		Some(c) => parse_u64(c, "change_outputs")? as usize,
+		None => config.num_change_outputs.unwrap_or(1) as usize,
+	};
+	if change_outputs == 0 {
+		return Err(ParseError::ArgumentError(
+			"At least one change output is needed".to_owned(),
+		));
+	}
+	let target_slate_version = match args.value_of("slate_version") {
+		Some(v) => Some(parse_u64(v, "slate_version")? as u16),
+		None => None,
+	};
+	let ttl_blocks = match args.value_of("ttl_blocks") {
+		Some(b) => Some(parse_u64(b, "ttl_blocks")?),
+		None => None,
+	};
+	Ok(command::PrepareSendArgs {
+		amount,
+		message: args.value_of("message").map(|m| m.to_owned()),
+		minimum_confirmations: min_c,
+		selection_strategy: parse_required(args, "selection_strategy")?.to_owned(),
+		change_outputs,
+		max_outputs: 500,
+		target_slate_version,
+		ttl_blocks,
+		dest: parse_required(args, "dest")?.to_owned(),
+	})
+}
+
+pub fn parse_sign_offline_args(args: &ArgMatches) -> Result<command::SignOfflineArgs, ParseError> {
+	let input = parse_required(args, "input")?;
+	if !Path::new(&input).is_file() {
+		let msg = format!("File {} not found.", input);
+		return Err(ParseError::ArgumentError(msg));
+	}
+	Ok(command::SignOfflineArgs {
+		input: input.to_owned(),
+		dest: args.value_of("dest").map(|d| d.to_owned()),
+	})
+}
+
+pub fn parse_broadcast_signed_args(
+	args: &ArgMatches,
+) -> Result<command::BroadcastSignedArgs, ParseError> {
+	let input = parse_required(args, "input")?;
+	if !Path::new(&input).is_file() {
+		let msg = format!("File {} not found.", input);
+		return Err(ParseError::ArgumentError(msg));
+	}
+	Ok(command::BroadcastSignedArgs {
+		input: input.to_owned(),
+		fluff: args.is_present("fluff"),
 	})
 }
 
@@ -571,16 +1030,22 @@ pub fn parse_issue_invoice_args(
 	let dest = parse_required(args, "dest")?;
 	Ok(command::IssueInvoiceArgs {
 		dest: dest.into(),
+		qr: args.is_present("qr"),
 		issue_args: IssueInvoiceTxArgs {
 			dest_acct_name: None,
 			amount,
 			message,
 			target_slate_version,
+			fluff: match args.is_present("fluff") {
+				true => Some(true),
+				false => None,
+			},
 		},
 	})
 }
 
 pub fn parse_process_invoice_args(
+	config: &WalletConfig,
 	args: &ArgMatches,
 	prompt: bool,
 ) -> Result<command::ProcessInvoiceArgs, ParseError> {
@@ -619,11 +1084,7 @@ pub fn parse_process_invoice_args(
 			}
 		}
 	};
-	if !estimate_selection_strategies
-		&& method == "http"
-		&& !dest.starts_with("http://")
-		&& !dest.starts_with("https://")
-	{
+	if !estimate_selection_strategies && method == "http" && !dest.contains("://") {
 		let msg = format!(
 			"HTTP Destination should start with http://: or https://: {}",
 			dest,
@@ -657,6 +1118,7 @@ pub fn parse_process_invoice_args(
 		method: method.to_owned(),
 		dest: dest.to_owned(),
 		max_outputs: max_outputs,
+		change_outputs: config.num_change_outputs.unwrap_or(1).max(1) as usize,
 		input: tx_file.to_owned(),
 	})
 }
@@ -672,8 +1134,10 @@ pub fn parse_info_args(args: &ArgMatches) -> Result<command::InfoArgs, ParseErro
 
 pub fn parse_check_args(args: &ArgMatches) -> Result<command::CheckArgs, ParseError> {
 	let delete_unconfirmed = args.is_present("delete_unconfirmed");
+	let full = args.is_present("full");
 	Ok(command::CheckArgs {
 		delete_unconfirmed: delete_unconfirmed,
+		full: full,
 	})
 }
 
@@ -702,6 +1166,72 @@ pub fn parse_txs_args(args: &ArgMatches) -> Result<command::TxsArgs, ParseError>
 	})
 }
 
+pub fn parse_settlement_args(args: &ArgMatches) -> Result<command::SettlementArgs, ParseError> {
+	let start = parse_time(parse_required(args, "start")?, "start")?;
+	let end = parse_time(parse_required(args, "end")?, "end")?;
+	if start >= end {
+		let msg = format!("The settlement period must end after it starts.");
+		return Err(ParseError::ArgumentError(msg));
+	}
+	Ok(command::SettlementArgs {
+		start,
+		end,
+		currency: parse_required(args, "currency")?.to_lowercase(),
+		format: parse_required(args, "format")?.to_owned(),
+		dest: args.value_of("dest").map(|d| d.to_owned()),
+	})
+}
+
+pub fn parse_export_history_args(
+	config: &WalletConfig,
+	args: &ArgMatches,
+) -> Result<command::ExportHistoryArgs, ParseError> {
+	let format = match parse_required(args, "format")? {
+		"json" => TxExportFormat::Json,
+		_ => TxExportFormat::Csv,
+	};
+	let min_creation_ts = match args.value_of("start") {
+		Some(s) => Some(parse_time(s, "start")?),
+		None => None,
+	};
+	let max_creation_ts = match args.value_of("end") {
+		Some(e) => Some(parse_time(e, "end")?),
+		None => None,
+	};
+	Ok(command::ExportHistoryArgs {
+		format,
+		filter: RetrieveTxQueryArgs {
+			min_creation_ts,
+			max_creation_ts,
+			..Default::default()
+		},
+		dest: args.value_of("dest").map(|d| d.to_owned()),
+		memo_escrow_key: config.memo_escrow_key.clone(),
+	})
+}
+
+pub fn parse_escrow_key_args(args: &ArgMatches) -> Result<command::EscrowKeyArgs, ParseError> {
+	Ok(command::EscrowKeyArgs {
+		dest: parse_required(args, "dest")?.to_owned(),
+	})
+}
+
+pub fn parse_open_memos_args(args: &ArgMatches) -> Result<command::OpenMemosArgs, ParseError> {
+	Ok(command::OpenMemosArgs {
+		key_file: parse_required(args, "key_file")?.to_owned(),
+		input: parse_required(args, "input")?.to_owned(),
+	})
+}
+
+pub fn parse_client_args(args: &ArgMatches) -> Result<command::ClientArgs, ParseError> {
+	Ok(command::ClientArgs {
+		remote: parse_required(args, "remote")?.to_owned(),
+		api_secret: get_first_line(args.value_of("api_secret_file").map(|f| f.to_owned())),
+		method: parse_required(args, "method")?.to_owned(),
+		params: args.value_of("params").map(|p| p.to_owned()),
+	})
+}
+
 pub fn parse_repost_args(args: &ArgMatches) -> Result<command::RepostArgs, ParseError> {
 	let tx_id = match args.value_of("id") {
 		None => None,
@@ -718,6 +1248,7 @@ pub fn parse_repost_args(args: &ArgMatches) -> Result<command::RepostArgs, Parse
 		id: tx_id.unwrap(),
 		dump_file: dump_file,
 		fluff: fluff,
+		replace: args.is_present("replace"),
 	})
 }
 
@@ -751,6 +1282,73 @@ pub fn parse_cancel_args(args: &ArgMatches) -> Result<command::CancelArgs, Parse
 	})
 }
 
+pub fn parse_consolidate_args(args: &ArgMatches) -> Result<command::ConsolidateArgs, ParseError> {
+	let min_c = parse_required(args, "minimum_confirmations")?;
+	let min_c = parse_u64(min_c, "minimum_confirmations")?;
+	let outputs = parse_required(args, "outputs")?;
+	let outputs = parse_u64(outputs, "outputs")? as u32;
+	let max_weight = parse_required(args, "max_weight")?;
+	let max_weight = parse_u64(max_weight, "max_weight")?;
+	let max_txs = parse_required(args, "max_txs")?;
+	let max_txs = parse_u64(max_txs, "max_txs")? as u32;
+	Ok(command::ConsolidateArgs {
+		minimum_confirmations: min_c,
+		num_outputs: outputs,
+		max_weight,
+		max_txs,
+		fluff: args.is_present("fluff"),
+	})
+}
+
+pub fn parse_label_args(args: &ArgMatches) -> Result<command::LabelArgs, ParseError> {
+	let tx_id = parse_required(args, "id")?;
+	let tx_id = parse_u64(tx_id, "id")? as u32;
+	Ok(command::LabelArgs {
+		tx_id,
+		label: args.value_of("label").map(|l| l.to_owned()),
+	})
+}
+
+/// Open the wallets the listeners serve alongside this one, asking for the
+/// password of each
+fn open_hosted_wallets<C>(
+	wallet_config: &WalletConfig,
+	node_client: C,
+) -> Result<
+	Vec<HostedWallet<DefaultLCProvider<C, keychain::ExtKeychain>, C, keychain::ExtKeychain>>,
+	Error,
+>
+where
+	C: NodeClient + 'static + Clone,
+{
+	let mut hosted = vec![];
+	for h in wallet_config.hosted_wallets.iter().flatten() {
+		let mut config = wallet_config.clone();
+		config.data_file_dir = h.data_dir.clone();
+		if h.spending_policy.is_some() {
+			config.spending_policy = h.spending_policy.clone();
+		}
+		let wallet = inst_wallet::<
+			DefaultLCProvider<C, keychain::ExtKeychain>,
+			C,
+			keychain::ExtKeychain,
+		>(config, node_client.clone())
+		.map_err(|e| ErrorKind::ArgumentError(format!("Wallet {}: {}", h.id, e)))?;
+		let keychain_mask = {
+			let mut wallet_lock = wallet.lock();
+			let lc = wallet_lock.lc_provider()?;
+			let password = prompt_password_stdout(&format!("Password for wallet {}: ", h.id));
+			lc.open_wallet(None, password, false, false)?
+		};
+		hosted.push(HostedWallet {
+			id: h.id.clone(),
+			wallet,
+			keychain_mask,
+		});
+	}
+	Ok(hosted)
+}
+
 pub fn wallet_command<C>(
 	wallet_args: &ArgMatches,
 	mut wallet_config: WalletConfig,
@@ -801,16 +1399,42 @@ where
 			std::process::exit(1);
 		});
 
+	let scheme = derivation_scheme(wallet_config.derivation_scheme.as_ref().map(|s| s.as_str()))
+		.unwrap_or_else(|e| {
+			println!("{}", e);
+			std::process::exit(1);
+		});
+
 	{
 		let mut wallet_lock = wallet.lock();
 		let lc = wallet_lock.lc_provider().unwrap();
 		lc.set_wallet_directory(&wallet_config.data_file_dir);
+		lc.set_seed_passphrase(global_wallet_args.seed_passphrase.clone());
+		lc.set_derivation_scheme(scheme);
 	}
 
 	// don't open wallet for certain lifecycle commands
 	let keychain_mask = match wallet_args.subcommand() {
 		("init", Some(_)) => None,
+		("setup", Some(_)) => None,
 		("recover", _) => None,
+		("split_seed", _) => None,
+		("change_password", _) => None,
+		("public_nodes", _) => None,
+		("escrow_key", _) => None,
+		("open_memos", _) => None,
+		("client", _) => None,
+		// run on a watch-only copy of the wallet, which has no seed to open
+		("prepare_send", _) | ("broadcast_signed", _) => {
+			let mut wallet_lock = wallet.lock();
+			let lc = wallet_lock.lc_provider().unwrap();
+			lc.open_watch_only(None)?;
+			if let Some(account) = wallet_args.value_of("account") {
+				let wallet_inst = lc.wallet_inst()?;
+				wallet_inst.set_parent_key_id_by_name(account)?;
+			}
+			None
+		}
 		_ => {
 			let mut wallet_lock = wallet.lock();
 			let lc = wallet_lock.lc_provider().unwrap();
@@ -840,34 +1464,104 @@ where
 			));
 			command::init(wallet, &global_wallet_args, a)
 		}
+		("setup", Some(args)) => {
+			let a = arg_parse!(parse_setup_args(&wallet_config, &global_wallet_args, &args));
+			command::setup(wallet, &global_wallet_args, a)
+		}
 		("recover", Some(args)) => {
 			let a = arg_parse!(parse_recover_args(
 				wallet.clone(),
 				&global_wallet_args,
 				&args
 			));
-			command::recover(wallet, a)
+			command::recover(wallet, &global_wallet_args, a)
+		}
+		("split_seed", Some(args)) => {
+			let a = arg_parse!(parse_split_seed_args(&global_wallet_args, &args));
+			command::split_seed(wallet, a)
+		}
+		("change_password", Some(_)) => {
+			let a = arg_parse!(parse_change_password_args(&global_wallet_args));
+			command::change_password(wallet, a)
+		}
+		("public_nodes", Some(args)) => {
+			let a = arg_parse!(parse_public_nodes_args(&args));
+			command::public_nodes(
+				&wallet_config,
+				global_wallet_args.node_api_secret.clone(),
+				a,
+			)
+		}
+		("escrow_key", Some(args)) => {
+			let a = arg_parse!(parse_escrow_key_args(&args));
+			command::escrow_key(a)
+		}
+		("open_memos", Some(args)) => {
+			let a = arg_parse!(parse_open_memos_args(&args));
+			command::open_memos(a)
+		}
+		("client", Some(args)) => {
+			let a = arg_parse!(parse_client_args(&args));
+			command::client(a)
 		}
 		("listen", Some(args)) => {
 			let mut c = wallet_config.clone();
 			let a = arg_parse!(parse_listen_args(&mut c, &args));
-			command::listen(wallet, keychain_mask, &c, &a, &global_wallet_args.clone())
+			let node_client = wallet.lock().lc_provider()?.node_client();
+			let hosted = match a.method.as_str() {
+				"http" => open_hosted_wallets(&c, node_client)?,
+				_ => vec![],
+			};
+			command::listen(
+				wallet,
+				keychain_mask,
+				hosted,
+				&c,
+				&a,
+				&global_wallet_args.clone(),
+				Some(shutdown_on_signal()),
+			)
 		}
-		("owner_api", Some(_)) => {
+		("owner_api", Some(args)) => {
+			let mut c = wallet_config.clone();
+			if args.is_present("read_only") {
+				c.owner_api_read_only = Some(true);
+			}
 			let mut g = global_wallet_args.clone();
-			g.tls_conf = None;
+			// TLS is only used by the owner api when verifying client certificates
+			if wallet_config.owner_api_tls_client_ca_file.is_none() {
+				g.tls_conf = None;
+			}
 			print!("mask: {:?}", keychain_mask);
-			command::owner_api(wallet, keychain_mask, &wallet_config, &g)
+			let node_client = wallet.lock().lc_provider()?.node_client();
+			let hosted = open_hosted_wallets(&wallet_config, node_client)?;
+			command::owner_api(
+				wallet,
+				keychain_mask,
+				hosted,
+				&c,
+				&g,
+				Some(shutdown_on_signal()),
+			)
 		}
 		("web", Some(_)) => {
-			command::owner_api(wallet, keychain_mask, &wallet_config, &global_wallet_args)
+			let node_client = wallet.lock().lc_provider()?.node_client();
+			let hosted = open_hosted_wallets(&wallet_config, node_client)?;
+			command::owner_api(
+				wallet,
+				keychain_mask,
+				hosted,
+				&wallet_config,
+				&global_wallet_args,
+				Some(shutdown_on_signal()),
+			)
 		}
 		("account", Some(args)) => {
 			let a = arg_parse!(parse_account_args(&args));
 			command::account(wallet, km, a)
 		}
 		("send", Some(args)) => {
-			let a = arg_parse!(parse_send_args(&args));
+			let a = arg_parse!(parse_send_args(&wallet_config, &args));
 			command::send(
 				wallet,
 				km,
@@ -883,12 +1577,28 @@ where
 			let a = arg_parse!(parse_finalize_args(&args));
 			command::finalize(wallet, km, a)
 		}
+		("prepare_send", Some(args)) => {
+			let a = arg_parse!(parse_prepare_send_args(&wallet_config, &args));
+			command::prepare_send(wallet, km, a)
+		}
+		("sign_offline", Some(args)) => {
+			let a = arg_parse!(parse_sign_offline_args(&args));
+			command::sign_offline(wallet, km, a)
+		}
+		("broadcast_signed", Some(args)) => {
+			let a = arg_parse!(parse_broadcast_signed_args(&args));
+			command::broadcast_signed(wallet, km, a)
+		}
 		("invoice", Some(args)) => {
 			let a = arg_parse!(parse_issue_invoice_args(&args));
 			command::issue_invoice_tx(wallet, km, a)
 		}
 		("pay", Some(args)) => {
-			let a = arg_parse!(parse_process_invoice_args(&args, !test_mode));
+			let a = arg_parse!(parse_process_invoice_args(
+				&wallet_config,
+				&args,
+				!test_mode
+			));
 			command::process_invoice(
 				wallet,
 				km,
@@ -922,6 +1632,14 @@ where
 				wallet_config.dark_background_color_scheme.unwrap_or(true),
 			)
 		}
+		("settlement", Some(args)) => {
+			let a = arg_parse!(parse_settlement_args(&args));
+			command::settlement(wallet, km, a, wallet_config.price_oracle_url.clone())
+		}
+		("export_history", Some(args)) => {
+			let a = arg_parse!(parse_export_history_args(&wallet_config, &args));
+			command::export_history(wallet, km, a)
+		}
 		("repost", Some(args)) => {
 			let a = arg_parse!(parse_repost_args(&args));
 			command::repost(wallet, km, a)
@@ -930,6 +1648,14 @@ where
 			let a = arg_parse!(parse_cancel_args(&args));
 			command::cancel(wallet, km, a)
 		}
+		("consolidate", Some(args)) => {
+			let a = arg_parse!(parse_consolidate_args(&args));
+			command::consolidate(wallet, km, a)
+		}
+		("label", Some(args)) => {
+			let a = arg_parse!(parse_label_args(&args));
+			command::label(wallet, km, a)
+		}
 		("restore", Some(_)) => command::restore(wallet, km),
 		("check", Some(args)) => {
 			let a = arg_parse!(parse_check_args(&args));
@@ -218,7 +218,7 @@ fn command_line_test_impl(test_dir: &str) -> Result<(), grin_wallet_controller::
 	// Check our transaction log, should have 10 entries
 	grin_wallet_controller::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
 		api.set_active_account(m, "mining")?;
-		let (refreshed, txs) = api.retrieve_txs(m, true, None, None)?;
+		let (refreshed, txs) = api.retrieve_txs(m, true, None, None, None)?;
 		assert!(refreshed);
 		assert_eq!(txs.len(), bh as usize);
 		Ok(())
@@ -299,7 +299,7 @@ fn command_line_test_impl(test_dir: &str) -> Result<(), grin_wallet_controller::
 
 	grin_wallet_controller::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
 		api.set_active_account(m, "mining")?;
-		let (refreshed, txs) = api.retrieve_txs(m, true, None, None)?;
+		let (refreshed, txs) = api.retrieve_txs(m, true, None, None, None)?;
 		assert!(refreshed);
 		assert_eq!(txs.len(), bh as usize + 1);
 		Ok(())
@@ -335,7 +335,7 @@ fn command_line_test_impl(test_dir: &str) -> Result<(), grin_wallet_controller::
 
 	grin_wallet_controller::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
 		api.set_active_account(m, "mining")?;
-		let (refreshed, txs) = api.retrieve_txs(m, true, None, None)?;
+		let (refreshed, txs) = api.retrieve_txs(m, true, None, None, None)?;
 		assert!(refreshed);
 		assert_eq!(txs.len(), bh as usize + 2);
 		Ok(())
@@ -471,7 +471,7 @@ fn command_line_test_impl(test_dir: &str) -> Result<(), grin_wallet_controller::
 	let mut tx_id = "".to_string();
 	grin_wallet_controller::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
 		api.set_active_account(m, "default")?;
-		let (_, txs) = api.retrieve_txs(m, true, None, None)?;
+		let (_, txs) = api.retrieve_txs(m, true, None, None, None)?;
 		let some_tx_id = txs[0].tx_slate_id.clone();
 		assert!(some_tx_id.is_some());
 		tx_id = some_tx_id.unwrap().to_hyphenated().to_string().clone();